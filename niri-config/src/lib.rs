@@ -3,6 +3,7 @@ extern crate tracing;
 
 use std::collections::HashSet;
 use std::ffi::OsStr;
+use std::num::NonZeroU8;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::Duration;
@@ -11,7 +12,10 @@ use bitflags::bitflags;
 use knuffel::errors::DecodeError;
 use knuffel::Decode as _;
 use miette::{miette, Context, IntoDiagnostic, NarratableReportHandler};
-use niri_ipc::{ConfiguredMode, LayoutSwitchTarget, SizeChange, Transform, WorkspaceReferenceArg};
+use niri_ipc::{
+    CenterFocusedColumnMode, ConfiguredMode, LayoutStrategyName, LayoutSwitchTarget, OpacityChange,
+    SizeChange, Transform, WorkspaceReferenceArg,
+};
 use regex::Regex;
 use smithay::input::keyboard::keysyms::KEY_NoSymbol;
 use smithay::input::keyboard::xkb::{keysym_from_name, KEYSYM_CASE_INSENSITIVE};
@@ -24,6 +28,14 @@ pub struct Config {
     pub input: Input,
     #[knuffel(children(name = "output"))]
     pub outputs: Vec<Output>,
+    #[knuffel(child, unwrap(argument))]
+    pub primary_output: Option<String>,
+    #[knuffel(child, unwrap(argument), default)]
+    pub output_identity: OutputIdentity,
+    #[knuffel(children(name = "output-profile"))]
+    pub output_profiles: Vec<OutputProfile>,
+    #[knuffel(child, default)]
+    pub startup: Startup,
     #[knuffel(children(name = "spawn-at-startup"))]
     pub spawn_at_startup: Vec<SpawnAtStartup>,
     #[knuffel(child, default)]
@@ -31,6 +43,8 @@ pub struct Config {
     #[knuffel(child, default)]
     pub prefer_no_csd: bool,
     #[knuffel(child, default)]
+    pub appearance: Appearance,
+    #[knuffel(child, default)]
     pub cursor: Cursor,
     #[knuffel(
         child,
@@ -41,16 +55,30 @@ pub struct Config {
     ]
     pub screenshot_path: Option<String>,
     #[knuffel(child, default)]
+    pub clip_recorder: ClipRecorder,
+    #[knuffel(child, default)]
     pub hotkey_overlay: HotkeyOverlay,
     #[knuffel(child, default)]
+    pub resize_mode: ResizeMode,
+    #[knuffel(child, default)]
+    pub notifications: Notifications,
+    #[knuffel(child, default)]
+    pub blur: Blur,
+    #[knuffel(child, default)]
     pub animations: Animations,
     #[knuffel(child, default)]
     pub environment: Environment,
+    #[knuffel(child, default)]
+    pub sandboxed_clients: SandboxedClients,
     #[knuffel(children(name = "window-rule"))]
     pub window_rules: Vec<WindowRule>,
     #[knuffel(child, default)]
     pub binds: Binds,
     #[knuffel(child, default)]
+    pub switch_events: SwitchEvents,
+    #[knuffel(child, default)]
+    pub idle: Idle,
+    #[knuffel(child, default)]
     pub debug: DebugConfig,
     #[knuffel(children(name = "workspace"))]
     pub workspaces: Vec<Workspace>,
@@ -78,6 +106,8 @@ pub struct Input {
     pub focus_follows_mouse: bool,
     #[knuffel(child)]
     pub workspace_auto_back_and_forth: bool,
+    #[knuffel(child, unwrap(argument))]
+    pub output_edge_resistance: Option<FloatOrInt<0, 1000>>,
 }
 
 #[derive(knuffel::Decode, Debug, PartialEq, Eq)]
@@ -142,6 +172,16 @@ pub enum CenterFocusedColumn {
     OnOverflow,
 }
 
+impl From<CenterFocusedColumnMode> for CenterFocusedColumn {
+    fn from(value: CenterFocusedColumnMode) -> Self {
+        match value {
+            CenterFocusedColumnMode::Never => Self::Never,
+            CenterFocusedColumnMode::Always => Self::Always,
+            CenterFocusedColumnMode::OnOverflow => Self::OnOverflow,
+        }
+    }
+}
+
 #[derive(knuffel::DecodeScalar, Debug, Default, PartialEq, Eq)]
 pub enum TrackLayout {
     /// The layout change is global.
@@ -305,6 +345,8 @@ pub struct Output {
     pub mode: Option<ConfiguredMode>,
     #[knuffel(child)]
     pub variable_refresh_rate: bool,
+    #[knuffel(child, default)]
+    pub layout: LayoutOverride,
 }
 
 impl Default for Output {
@@ -317,10 +359,59 @@ impl Default for Output {
             position: None,
             mode: None,
             variable_refresh_rate: false,
+            layout: LayoutOverride::default(),
         }
     }
 }
 
+/// How workspaces recognize the output they belong to when it reconnects.
+#[derive(knuffel::DecodeScalar, Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum OutputIdentity {
+    /// The connector name and the monitor must both match.
+    #[default]
+    Strict,
+    /// The monitor must match, on whatever connector it is plugged into.
+    Fuzzy,
+}
+
+/// Output configuration used only while a specific set of outputs is connected.
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
+pub struct OutputProfile {
+    #[knuffel(argument)]
+    pub name: String,
+    /// Configs for the outputs, overriding the top-level output sections.
+    ///
+    /// The profile matches when exactly these outputs are connected.
+    #[knuffel(children(name = "output"))]
+    pub outputs: Vec<Output>,
+    /// Named workspaces to move to outputs when the profile activates.
+    #[knuffel(children(name = "workspace"))]
+    pub workspaces: Vec<OutputProfileWorkspace>,
+    /// Commands to run when the profile activates.
+    #[knuffel(children(name = "exec"))]
+    pub exec: Vec<SpawnAtStartup>,
+}
+
+#[derive(knuffel::Decode, Debug, Clone, PartialEq, Eq)]
+pub struct OutputProfileWorkspace {
+    #[knuffel(argument)]
+    pub name: String,
+    #[knuffel(property)]
+    pub output: String,
+}
+
+impl OutputProfile {
+    /// Returns whether the connected outputs are exactly the ones in this profile.
+    pub fn matches(&self, connected: &[String]) -> bool {
+        self.outputs.len() == connected.len()
+            && connected.iter().all(|name| {
+                self.outputs
+                    .iter()
+                    .any(|o| o.name.eq_ignore_ascii_case(name))
+            })
+    }
+}
+
 #[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Position {
     #[knuffel(property)]
@@ -333,12 +424,18 @@ pub struct Position {
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub struct FloatOrInt<const MIN: i32, const MAX: i32>(pub f64);
 
+/// Number of columns, which can't be zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnCount(pub NonZeroU8);
+
 #[derive(knuffel::Decode, Debug, Clone, PartialEq)]
 pub struct Layout {
     #[knuffel(child, default)]
     pub focus_ring: FocusRing,
     #[knuffel(child, default)]
     pub border: Border,
+    #[knuffel(child, default)]
+    pub tab_indicator: TabIndicator,
     #[knuffel(child, unwrap(children), default)]
     pub preset_column_widths: Vec<PresetWidth>,
     #[knuffel(child)]
@@ -349,6 +446,22 @@ pub struct Layout {
     pub gaps: FloatOrInt<0, 65535>,
     #[knuffel(child, default)]
     pub struts: Struts,
+    #[knuffel(child)]
+    pub workspace_wrap_around: bool,
+    #[knuffel(child, unwrap(argument), default = Self::default().auto_tile_max_columns)]
+    pub auto_tile_max_columns: ColumnCount,
+    #[knuffel(child, unwrap(argument), default)]
+    pub dim_inactive: FloatOrInt<0, 1>,
+    #[knuffel(child, unwrap(argument), default)]
+    pub dim_behind_modal: FloatOrInt<0, 1>,
+    #[knuffel(child)]
+    pub remember_window_sizes: bool,
+    #[knuffel(child, unwrap(argument), default)]
+    pub dialog_placement: DialogPlacement,
+    #[knuffel(child)]
+    pub transposed: bool,
+    #[knuffel(child)]
+    pub transpose_portrait_outputs: bool,
 }
 
 impl Default for Layout {
@@ -356,11 +469,20 @@ impl Default for Layout {
         Self {
             focus_ring: Default::default(),
             border: Default::default(),
+            tab_indicator: Default::default(),
             preset_column_widths: Default::default(),
             default_column_width: Default::default(),
             center_focused_column: Default::default(),
             gaps: FloatOrInt(16.),
             struts: Default::default(),
+            workspace_wrap_around: false,
+            auto_tile_max_columns: ColumnCount(NonZeroU8::new(3).unwrap()),
+            dim_inactive: FloatOrInt(0.),
+            dim_behind_modal: FloatOrInt(0.),
+            remember_window_sizes: false,
+            dialog_placement: Default::default(),
+            transposed: false,
+            transpose_portrait_outputs: false,
         }
     }
 }
@@ -371,6 +493,39 @@ pub struct SpawnAtStartup {
     pub command: Vec<String>,
 }
 
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq, Eq)]
+pub struct Startup {
+    /// Output to focus, by connector name, or "primary" for the primary output.
+    #[knuffel(child, unwrap(argument))]
+    pub focus_output: Option<String>,
+    #[knuffel(child, unwrap(argument))]
+    pub pointer_position: Option<StartupPointerPosition>,
+}
+
+/// Where the pointer starts out on the focused output.
+#[derive(knuffel::DecodeScalar, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartupPointerPosition {
+    Center,
+    TopLeft,
+}
+
+/// Appearance preferences exposed to apps through the Settings portal.
+#[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq)]
+pub struct Appearance {
+    #[knuffel(child, unwrap(argument), default)]
+    pub color_scheme: ColorScheme,
+    #[knuffel(child, unwrap(argument, str))]
+    pub accent_color: Option<Color>,
+}
+
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ColorScheme {
+    #[default]
+    NoPreference,
+    PreferDark,
+    PreferLight,
+}
+
 #[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
 pub struct FocusRing {
     #[knuffel(child)]
@@ -400,6 +555,53 @@ impl Default for FocusRing {
     }
 }
 
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+pub struct TabIndicator {
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(child, unwrap(argument), default = Self::default().width)]
+    pub width: FloatOrInt<0, 65535>,
+    #[knuffel(child, unwrap(argument), default = Self::default().gap)]
+    pub gap: FloatOrInt<0, 65535>,
+    #[knuffel(child, unwrap(argument), default)]
+    pub position: TabIndicatorPosition,
+    #[knuffel(child, default = Self::default().active_color)]
+    pub active_color: Color,
+    #[knuffel(child, default = Self::default().inactive_color)]
+    pub inactive_color: Color,
+}
+
+impl Default for TabIndicator {
+    fn default() -> Self {
+        Self {
+            off: true,
+            width: FloatOrInt(4.),
+            gap: FloatOrInt(4.),
+            position: TabIndicatorPosition::Left,
+            active_color: Color::new(127, 200, 255, 255),
+            inactive_color: Color::new(80, 80, 80, 255),
+        }
+    }
+}
+
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DialogPlacement {
+    /// Centered over the parent window.
+    #[default]
+    CenterOnParent,
+    /// Down and to the right of the topmost floating window, or of the parent window.
+    Cascade,
+    /// Centered on the pointer.
+    AtPointer,
+}
+
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TabIndicatorPosition {
+    #[default]
+    Left,
+    Right,
+}
+
 #[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
 pub struct Gradient {
     #[knuffel(property, str)]
@@ -533,12 +735,98 @@ pub struct Struts {
     pub bottom: FloatOrInt<0, 65535>,
 }
 
+#[derive(knuffel::Decode, Debug, Clone, PartialEq, Eq)]
+pub struct ClipRecorder {
+    #[knuffel(child, unwrap(argument), default = Self::default().path)]
+    pub path: String,
+    #[knuffel(child, unwrap(argument), default = Self::default().max_duration_ms)]
+    pub max_duration_ms: u32,
+    #[knuffel(child, unwrap(argument), default = Self::default().fps)]
+    pub fps: u8,
+}
+
+impl Default for ClipRecorder {
+    fn default() -> Self {
+        Self {
+            path: String::from("~/Videos/Clips/Clip from %Y-%m-%d %H-%M-%S.webm"),
+            max_duration_ms: 10000,
+            fps: 30,
+        }
+    }
+}
+
 #[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct HotkeyOverlay {
     #[knuffel(child)]
     pub skip_at_startup: bool,
 }
 
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+pub struct ResizeMode {
+    #[knuffel(child, unwrap(argument, str), default = Self::default().width_step)]
+    pub width_step: SizeChange,
+    #[knuffel(child, unwrap(argument, str), default = Self::default().height_step)]
+    pub height_step: SizeChange,
+}
+
+impl Default for ResizeMode {
+    fn default() -> Self {
+        Self {
+            width_step: SizeChange::AdjustProportion(10.),
+            height_step: SizeChange::AdjustProportion(10.),
+        }
+    }
+}
+
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Notifications {
+    #[knuffel(child)]
+    pub on: bool,
+    #[knuffel(child, unwrap(argument), default)]
+    pub position: NotificationPosition,
+    #[knuffel(child, unwrap(argument), default = Self::default().default_timeout_ms)]
+    pub default_timeout_ms: u32,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Self {
+            on: false,
+            position: NotificationPosition::default(),
+            default_timeout_ms: 5000,
+        }
+    }
+}
+
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+pub struct Blur {
+    #[knuffel(child)]
+    pub on: bool,
+    #[knuffel(child, unwrap(argument), default = Self::default().passes)]
+    pub passes: u8,
+    #[knuffel(child, unwrap(argument), default = Self::default().offset)]
+    pub offset: FloatOrInt<0, 100>,
+}
+
+impl Default for Blur {
+    fn default() -> Self {
+        Self {
+            on: false,
+            passes: 3,
+            offset: FloatOrInt(2.),
+        }
+    }
+}
+
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationPosition {
+    TopLeft,
+    #[default]
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
 #[derive(knuffel::Decode, Debug, Clone, PartialEq)]
 pub struct Animations {
     #[knuffel(child)]
@@ -559,6 +847,10 @@ pub struct Animations {
     pub window_resize: WindowResizeAnim,
     #[knuffel(child, default)]
     pub config_notification_open_close: ConfigNotificationOpenCloseAnim,
+    #[knuffel(child, default)]
+    pub window_dim: WindowDimAnim,
+    #[knuffel(child, default)]
+    pub magnifier_zoom: MagnifierZoomAnim,
 }
 
 impl Default for Animations {
@@ -573,26 +865,44 @@ impl Default for Animations {
             window_close: Default::default(),
             window_resize: Default::default(),
             config_notification_open_close: Default::default(),
+            window_dim: Default::default(),
+            magnifier_zoom: Default::default(),
         }
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
-pub struct WorkspaceSwitchAnim(pub Animation);
+pub struct WorkspaceSwitchAnim {
+    pub anim: Animation,
+    pub style: WorkspaceSwitchStyle,
+}
 
 impl Default for WorkspaceSwitchAnim {
     fn default() -> Self {
-        Self(Animation {
-            off: false,
-            kind: AnimationKind::Spring(SpringParams {
-                damping_ratio: 1.,
-                stiffness: 1000,
-                epsilon: 0.0001,
-            }),
-        })
+        Self {
+            anim: Animation {
+                off: false,
+                kind: AnimationKind::Spring(SpringParams {
+                    damping_ratio: 1.,
+                    stiffness: 1000,
+                    epsilon: 0.0001,
+                }),
+            },
+            style: WorkspaceSwitchStyle::default(),
+        }
     }
 }
 
+/// How workspaces move during a switch.
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WorkspaceSwitchStyle {
+    /// Workspaces slide vertically past each other.
+    #[default]
+    Slide,
+    /// The outgoing workspace shrinks and fades out while the incoming one grows in.
+    Depth,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct WindowOpenAnim {
     pub anim: Animation,
@@ -705,6 +1015,37 @@ impl Default for ConfigNotificationOpenCloseAnim {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowDimAnim(pub Animation);
+
+impl Default for WindowDimAnim {
+    fn default() -> Self {
+        Self(Animation {
+            off: false,
+            kind: AnimationKind::Easing(EasingParams {
+                duration_ms: 200,
+                curve: AnimationCurve::EaseOutCubic,
+            }),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MagnifierZoomAnim(pub Animation);
+
+impl Default for MagnifierZoomAnim {
+    fn default() -> Self {
+        Self(Animation {
+            off: false,
+            kind: AnimationKind::Spring(SpringParams {
+                damping_ratio: 1.,
+                stiffness: 800,
+                epsilon: 0.0001,
+            }),
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Animation {
     pub off: bool,
@@ -749,12 +1090,71 @@ pub struct EnvironmentVariable {
     pub value: Option<String>,
 }
 
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq, Eq)]
+pub struct SandboxedClients {
+    #[knuffel(children(name = "allow"))]
+    pub allow: Vec<SandboxAllow>,
+}
+
 #[derive(knuffel::Decode, Debug, Clone, PartialEq, Eq)]
+pub struct SandboxAllow {
+    #[knuffel(argument)]
+    pub protocol: PrivilegedProtocol,
+    #[knuffel(property)]
+    pub app_id: Option<String>,
+}
+
+/// Protocols that sandboxed clients can't use unless allowed.
+#[derive(knuffel::DecodeScalar, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrivilegedProtocol {
+    Screencopy,
+    DataControl,
+    ForeignToplevel,
+    GammaControl,
+    InputInhibit,
+    OutputManagement,
+    LayerShell,
+    SessionLock,
+    InputMethod,
+    VirtualKeyboard,
+    VirtualPointer,
+    SecurityContext,
+}
+
+impl SandboxedClients {
+    /// Returns the privileged protocols allowed for a sandboxed client with this app id.
+    pub fn allowed_protocols(&self, app_id: Option<&str>) -> Vec<PrivilegedProtocol> {
+        self.allow
+            .iter()
+            .filter(|allow| allow.app_id.is_none() || allow.app_id.as_deref() == app_id)
+            .map(|allow| allow.protocol)
+            .collect()
+    }
+}
+
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
 pub struct Workspace {
     #[knuffel(argument)]
     pub name: WorkspaceName,
     #[knuffel(child, unwrap(argument))]
     pub open_on_output: Option<String>,
+    #[knuffel(child, default)]
+    pub layout: LayoutOverride,
+}
+
+/// Subset of layout settings that can be overridden in a more specific context.
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq)]
+pub struct LayoutOverride {
+    #[knuffel(child, unwrap(argument))]
+    pub gaps: Option<FloatOrInt<0, 65535>>,
+    #[knuffel(child)]
+    pub default_column_width: Option<DefaultColumnWidth>,
+    #[knuffel(child, unwrap(argument))]
+    pub center_focused_column: Option<CenterFocusedColumn>,
+    #[knuffel(child, unwrap(argument))]
+    pub dim_inactive: Option<FloatOrInt<0, 1>>,
+    #[knuffel(child, unwrap(argument))]
+    pub zoom: Option<FloatOrInt<0, 1>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -772,12 +1172,14 @@ pub struct WindowRule {
     pub default_column_width: Option<DefaultColumnWidth>,
     #[knuffel(child, unwrap(argument))]
     pub open_on_output: Option<String>,
-    #[knuffel(child, unwrap(argument))]
-    pub open_on_workspace: Option<String>,
+    #[knuffel(child)]
+    pub open_on_workspace: Option<OpenOnWorkspace>,
     #[knuffel(child, unwrap(argument))]
     pub open_maximized: Option<bool>,
     #[knuffel(child, unwrap(argument))]
     pub open_fullscreen: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub prefer_no_csd: Option<bool>,
 
     // Rules applied dynamically.
     #[knuffel(child, unwrap(argument))]
@@ -802,7 +1204,26 @@ pub struct WindowRule {
     #[knuffel(child, unwrap(argument))]
     pub clip_to_geometry: Option<bool>,
     #[knuffel(child, unwrap(argument))]
+    pub oversized_window: Option<OversizedWindow>,
+    #[knuffel(child, unwrap(argument))]
     pub block_out_from: Option<BlockOutFrom>,
+    #[knuffel(child, unwrap(argument))]
+    pub confirm_close: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub on_close: Option<OnClose>,
+    #[knuffel(child, unwrap(argument))]
+    pub always_center: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub keep_aspect_ratio: Option<bool>,
+}
+
+#[derive(knuffel::Decode, Debug, Clone, PartialEq, Eq)]
+pub struct OpenOnWorkspace {
+    #[knuffel(argument)]
+    pub name: String,
+    /// Whether to focus the window and switch to its workspace when it opens.
+    #[knuffel(property, default)]
+    pub focus: bool,
 }
 
 // Remember to update the PartialEq impl when adding fields!
@@ -858,6 +1279,23 @@ pub enum BlockOutFrom {
     ScreenCapture,
 }
 
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OversizedWindow {
+    /// Let the window grow its tile and column.
+    #[default]
+    Expand,
+    /// Keep the tile at the requested size and cut off the rest of the window.
+    Clip,
+    /// Keep the tile at the requested size and draw the window scaled down to fit.
+    ScaleDown,
+}
+
+#[derive(knuffel::DecodeScalar, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnClose {
+    MoveFocusLeft,
+    MoveFocusRight,
+}
+
 #[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq)]
 pub struct BorderRule {
     #[knuffel(child)]
@@ -879,12 +1317,54 @@ pub struct BorderRule {
 #[derive(Debug, Default, PartialEq)]
 pub struct Binds(pub Vec<Bind>);
 
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq)]
+pub struct SwitchEvents {
+    #[knuffel(child)]
+    pub lid_open: Option<SwitchAction>,
+    #[knuffel(child)]
+    pub lid_close: Option<SwitchAction>,
+    #[knuffel(child)]
+    pub tablet_mode_on: Option<SwitchAction>,
+    #[knuffel(child)]
+    pub tablet_mode_off: Option<SwitchAction>,
+}
+
+/// Actions to run automatically when the user is inactive.
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq)]
+pub struct Idle {
+    #[knuffel(child, unwrap(argument))]
+    pub dim_after_sec: Option<u32>,
+    #[knuffel(child, unwrap(argument))]
+    pub power_off_monitors_after_sec: Option<u32>,
+    #[knuffel(child, unwrap(argument))]
+    pub lock_after_sec: Option<u32>,
+    #[knuffel(child)]
+    pub lock_command: Option<LockCommand>,
+    #[knuffel(child)]
+    pub inhibit_while_playing: bool,
+}
+
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
+pub struct LockCommand {
+    #[knuffel(arguments)]
+    pub command: Vec<String>,
+}
+
+/// Actions to run when a switch toggles, in order.
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
+pub struct SwitchAction {
+    #[knuffel(children)]
+    pub actions: Vec<Action>,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Bind {
     pub key: Key,
     pub action: Action,
     pub cooldown: Option<Duration>,
     pub allow_when_locked: bool,
+    /// Whether the bind is turned off while the focused window inhibits compositor shortcuts.
+    pub allow_inhibiting: bool,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
@@ -896,6 +1376,13 @@ pub struct Key {
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum Trigger {
     Keysym(Keysym),
+    /// Raw evdev key code, for keys without a useful keysym.
+    KeyCode(u32),
+    MouseLeft,
+    MouseRight,
+    MouseMiddle,
+    MouseBack,
+    MouseForward,
     WheelScrollDown,
     WheelScrollUp,
     WheelScrollLeft,
@@ -929,7 +1416,22 @@ pub enum Action {
     ToggleDebugTint,
     DebugToggleOpaqueRegions,
     DebugToggleDamage,
+    MagnifierZoomIn,
+    MagnifierZoomOut,
+    MagnifierReset,
+    ToggleReducedMotion,
+    SetWindowGroup(#[knuffel(argument)] String),
+    UnsetWindowGroup,
+    GatherWindowGroup(#[knuffel(argument)] String),
+    StashWindowGroup(#[knuffel(argument)] String),
+    DismissNotification,
+    DismissAllNotifications,
+    ToggleDoNotDisturb,
     Spawn(#[knuffel(arguments)] Vec<String>),
+    FocusOrSpawn(
+        #[knuffel(argument)] String,
+        #[knuffel(arguments)] Vec<String>,
+    ),
     DoScreenTransition(#[knuffel(property(name = "delay-ms"))] Option<u16>),
     #[knuffel(skip)]
     ConfirmScreenshot,
@@ -940,10 +1442,16 @@ pub enum Action {
     ScreenshotWindow,
     CloseWindow,
     FullscreenWindow,
+    MinimizeWindow,
+    RestoreMinimizedWindow,
     FocusColumnLeft,
     FocusColumnRight,
     FocusColumnFirst,
     FocusColumnLast,
+    FocusBack,
+    FocusForward,
+    SwapWithPrevious,
+    FocusColumn(#[knuffel(argument)] usize),
     FocusColumnRightOrFirst,
     FocusColumnLeftOrLast,
     FocusColumnOrMonitorLeft,
@@ -960,6 +1468,14 @@ pub enum Action {
     MoveColumnRight,
     MoveColumnToFirst,
     MoveColumnToLast,
+    MoveColumnToIndex(#[knuffel(argument)] usize),
+    ShowWindowHints,
+    #[knuffel(skip)]
+    FocusWindowHint(char),
+    #[knuffel(skip)]
+    CancelWindowHints,
+    EnterResizeMode,
+    ExitResizeMode,
     MoveWindowDown,
     MoveWindowUp,
     MoveWindowDownOrToWorkspaceDown,
@@ -972,6 +1488,8 @@ pub enum Action {
     FocusWorkspaceDown,
     FocusWorkspaceUp,
     FocusWorkspace(#[knuffel(argument)] WorkspaceReference),
+    #[knuffel(skip)]
+    FocusWorkspaceFuzzy(String),
     FocusWorkspacePrevious,
     MoveWindowToWorkspaceDown,
     MoveWindowToWorkspaceUp,
@@ -985,6 +1503,12 @@ pub enum Action {
     FocusMonitorRight,
     FocusMonitorDown,
     FocusMonitorUp,
+    WarpCursor(
+        #[knuffel(argument)] i32,
+        #[knuffel(argument)] i32,
+        #[knuffel(property(name = "output"))] Option<String>,
+    ),
+    WarpCursorToFocusedWindow,
     MoveWindowToMonitorLeft,
     MoveWindowToMonitorRight,
     MoveWindowToMonitorDown,
@@ -997,13 +1521,33 @@ pub enum Action {
     ResetWindowHeight,
     SwitchPresetColumnWidth,
     MaximizeColumn,
+    ToggleAutoTile,
+    ToggleColumnAlwaysCenter,
+    RecordClip,
+    ToggleKeyboardShortcutsInhibit,
+    BreakInputInhibit,
     SetColumnWidth(#[knuffel(argument, str)] SizeChange),
+    ToggleColumnWidth(
+        #[knuffel(argument, str)] SizeChange,
+        #[knuffel(argument, str)] SizeChange,
+    ),
+    SetWindowOpacity(#[knuffel(argument, str)] OpacityChange),
+    ResetWindowOpacity,
+    SetWorkspaceGaps(#[knuffel(argument, str)] SizeChange),
+    SetWorkspaceDefaultColumnWidth(#[knuffel(argument, str)] SizeChange),
+    SetWorkspaceCenterFocusedColumn(#[knuffel(argument, str)] CenterFocusedColumnMode),
+    SetWorkspaceZoom(#[knuffel(argument)] FloatOrInt<0, 1>),
+    ResetWorkspaceLayout,
+    SetLayoutStrategy(#[knuffel(argument, str)] LayoutStrategyName),
     SwitchLayout(#[knuffel(argument, str)] LayoutSwitchTarget),
     ShowHotkeyOverlay,
     MoveWorkspaceToMonitorLeft,
     MoveWorkspaceToMonitorRight,
     MoveWorkspaceToMonitorDown,
     MoveWorkspaceToMonitorUp,
+    SetPrimaryOutput(#[knuffel(argument)] String),
+    SaveLayoutPreset(#[knuffel(argument)] String),
+    LoadLayoutPreset(#[knuffel(argument)] String),
 }
 
 impl From<niri_ipc::Action> for Action {
@@ -1012,15 +1556,24 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::Quit { skip_confirmation } => Self::Quit(skip_confirmation),
             niri_ipc::Action::PowerOffMonitors => Self::PowerOffMonitors,
             niri_ipc::Action::Spawn { command } => Self::Spawn(command),
+            niri_ipc::Action::FocusOrSpawn { app_id, command } => {
+                Self::FocusOrSpawn(app_id, command)
+            }
             niri_ipc::Action::DoScreenTransition { delay_ms } => Self::DoScreenTransition(delay_ms),
             niri_ipc::Action::Screenshot => Self::Screenshot,
             niri_ipc::Action::ScreenshotScreen => Self::ScreenshotScreen,
             niri_ipc::Action::ScreenshotWindow => Self::ScreenshotWindow,
             niri_ipc::Action::CloseWindow => Self::CloseWindow,
             niri_ipc::Action::FullscreenWindow => Self::FullscreenWindow,
+            niri_ipc::Action::MinimizeWindow => Self::MinimizeWindow,
+            niri_ipc::Action::RestoreMinimizedWindow => Self::RestoreMinimizedWindow,
             niri_ipc::Action::FocusColumnLeft => Self::FocusColumnLeft,
             niri_ipc::Action::FocusColumnRight => Self::FocusColumnRight,
             niri_ipc::Action::FocusColumnFirst => Self::FocusColumnFirst,
+            niri_ipc::Action::FocusColumn { index } => Self::FocusColumn(index),
+            niri_ipc::Action::FocusBack => Self::FocusBack,
+            niri_ipc::Action::FocusForward => Self::FocusForward,
+            niri_ipc::Action::SwapWithPrevious => Self::SwapWithPrevious,
             niri_ipc::Action::FocusColumnLast => Self::FocusColumnLast,
             niri_ipc::Action::FocusColumnRightOrFirst => Self::FocusColumnRightOrFirst,
             niri_ipc::Action::FocusColumnLeftOrLast => Self::FocusColumnLeftOrLast,
@@ -1037,6 +1590,10 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::MoveColumnLeft => Self::MoveColumnLeft,
             niri_ipc::Action::MoveColumnRight => Self::MoveColumnRight,
             niri_ipc::Action::MoveColumnToFirst => Self::MoveColumnToFirst,
+            niri_ipc::Action::MoveColumnToIndex { index } => Self::MoveColumnToIndex(index),
+            niri_ipc::Action::ShowWindowHints => Self::ShowWindowHints,
+            niri_ipc::Action::EnterResizeMode => Self::EnterResizeMode,
+            niri_ipc::Action::ExitResizeMode => Self::ExitResizeMode,
             niri_ipc::Action::MoveColumnToLast => Self::MoveColumnToLast,
             niri_ipc::Action::MoveWindowDown => Self::MoveWindowDown,
             niri_ipc::Action::MoveWindowUp => Self::MoveWindowUp,
@@ -1051,9 +1608,14 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::CenterColumn => Self::CenterColumn,
             niri_ipc::Action::FocusWorkspaceDown => Self::FocusWorkspaceDown,
             niri_ipc::Action::FocusWorkspaceUp => Self::FocusWorkspaceUp,
-            niri_ipc::Action::FocusWorkspace { reference } => {
-                Self::FocusWorkspace(WorkspaceReference::from(reference))
-            }
+            niri_ipc::Action::FocusWorkspace {
+                reference: Some(reference),
+                ..
+            } => Self::FocusWorkspace(WorkspaceReference::from(reference)),
+            niri_ipc::Action::FocusWorkspace {
+                reference: None,
+                name,
+            } => Self::FocusWorkspaceFuzzy(name.unwrap_or_default()),
             niri_ipc::Action::FocusWorkspacePrevious => Self::FocusWorkspacePrevious,
             niri_ipc::Action::MoveWindowToWorkspaceDown => Self::MoveWindowToWorkspaceDown,
             niri_ipc::Action::MoveWindowToWorkspaceUp => Self::MoveWindowToWorkspaceUp,
@@ -1071,6 +1633,8 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::FocusMonitorRight => Self::FocusMonitorRight,
             niri_ipc::Action::FocusMonitorDown => Self::FocusMonitorDown,
             niri_ipc::Action::FocusMonitorUp => Self::FocusMonitorUp,
+            niri_ipc::Action::WarpCursor { x, y, output } => Self::WarpCursor(x, y, output),
+            niri_ipc::Action::WarpCursorToFocusedWindow => Self::WarpCursorToFocusedWindow,
             niri_ipc::Action::MoveWindowToMonitorLeft => Self::MoveWindowToMonitorLeft,
             niri_ipc::Action::MoveWindowToMonitorRight => Self::MoveWindowToMonitorRight,
             niri_ipc::Action::MoveWindowToMonitorDown => Self::MoveWindowToMonitorDown,
@@ -1083,16 +1647,52 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::ResetWindowHeight => Self::ResetWindowHeight,
             niri_ipc::Action::SwitchPresetColumnWidth => Self::SwitchPresetColumnWidth,
             niri_ipc::Action::MaximizeColumn => Self::MaximizeColumn,
+            niri_ipc::Action::ToggleAutoTile => Self::ToggleAutoTile,
+            niri_ipc::Action::ToggleColumnAlwaysCenter => Self::ToggleColumnAlwaysCenter,
+            niri_ipc::Action::RecordClip => Self::RecordClip,
+            niri_ipc::Action::ToggleKeyboardShortcutsInhibit => {
+                Self::ToggleKeyboardShortcutsInhibit
+            }
+            niri_ipc::Action::BreakInputInhibit => Self::BreakInputInhibit,
             niri_ipc::Action::SetColumnWidth { change } => Self::SetColumnWidth(change),
+            niri_ipc::Action::ToggleColumnWidth { normal, toggled } => {
+                Self::ToggleColumnWidth(normal, toggled)
+            }
+            niri_ipc::Action::SetWindowOpacity { change } => Self::SetWindowOpacity(change),
+            niri_ipc::Action::ResetWindowOpacity => Self::ResetWindowOpacity,
+            niri_ipc::Action::SetWorkspaceGaps { change } => Self::SetWorkspaceGaps(change),
+            niri_ipc::Action::SetWorkspaceDefaultColumnWidth { change } => {
+                Self::SetWorkspaceDefaultColumnWidth(change)
+            }
+            niri_ipc::Action::SetWorkspaceCenterFocusedColumn { mode } => {
+                Self::SetWorkspaceCenterFocusedColumn(mode)
+            }
+            niri_ipc::Action::SetWorkspaceZoom { zoom } => Self::SetWorkspaceZoom(FloatOrInt(zoom)),
+            niri_ipc::Action::ResetWorkspaceLayout => Self::ResetWorkspaceLayout,
+            niri_ipc::Action::SetLayoutStrategy { strategy } => Self::SetLayoutStrategy(strategy),
             niri_ipc::Action::SwitchLayout { layout } => Self::SwitchLayout(layout),
             niri_ipc::Action::ShowHotkeyOverlay => Self::ShowHotkeyOverlay,
             niri_ipc::Action::MoveWorkspaceToMonitorLeft => Self::MoveWorkspaceToMonitorLeft,
             niri_ipc::Action::MoveWorkspaceToMonitorRight => Self::MoveWorkspaceToMonitorRight,
             niri_ipc::Action::MoveWorkspaceToMonitorDown => Self::MoveWorkspaceToMonitorDown,
             niri_ipc::Action::MoveWorkspaceToMonitorUp => Self::MoveWorkspaceToMonitorUp,
+            niri_ipc::Action::SetPrimaryOutput { output } => Self::SetPrimaryOutput(output),
+            niri_ipc::Action::SaveLayoutPreset { name } => Self::SaveLayoutPreset(name),
+            niri_ipc::Action::LoadLayoutPreset { name } => Self::LoadLayoutPreset(name),
             niri_ipc::Action::ToggleDebugTint => Self::ToggleDebugTint,
             niri_ipc::Action::DebugToggleOpaqueRegions => Self::DebugToggleOpaqueRegions,
             niri_ipc::Action::DebugToggleDamage => Self::DebugToggleDamage,
+            niri_ipc::Action::MagnifierZoomIn => Self::MagnifierZoomIn,
+            niri_ipc::Action::MagnifierZoomOut => Self::MagnifierZoomOut,
+            niri_ipc::Action::MagnifierReset => Self::MagnifierReset,
+            niri_ipc::Action::ToggleReducedMotion => Self::ToggleReducedMotion,
+            niri_ipc::Action::SetWindowGroup { name } => Self::SetWindowGroup(name),
+            niri_ipc::Action::UnsetWindowGroup => Self::UnsetWindowGroup,
+            niri_ipc::Action::GatherWindowGroup { name } => Self::GatherWindowGroup(name),
+            niri_ipc::Action::StashWindowGroup { name } => Self::StashWindowGroup(name),
+            niri_ipc::Action::DismissNotification => Self::DismissNotification,
+            niri_ipc::Action::DismissAllNotifications => Self::DismissAllNotifications,
+            niri_ipc::Action::ToggleDoNotDisturb => Self::ToggleDoNotDisturb,
         }
     }
 }
@@ -1216,6 +1816,50 @@ impl<S: knuffel::traits::ErrorSpan, const MIN: i32, const MAX: i32> knuffel::Dec
     }
 }
 
+impl<S: knuffel::traits::ErrorSpan> knuffel::DecodeScalar<S> for ColumnCount {
+    fn type_check(
+        type_name: &Option<knuffel::span::Spanned<knuffel::ast::TypeName, S>>,
+        ctx: &mut knuffel::decode::Context<S>,
+    ) {
+        if let Some(type_name) = &type_name {
+            ctx.emit_error(DecodeError::unexpected(
+                type_name,
+                "type name",
+                "no type name expected for this node",
+            ));
+        }
+    }
+
+    fn raw_decode(
+        val: &knuffel::span::Spanned<knuffel::ast::Literal, S>,
+        ctx: &mut knuffel::decode::Context<S>,
+    ) -> Result<ColumnCount, DecodeError<S>> {
+        let one = ColumnCount(NonZeroU8::MIN);
+        match &**val {
+            knuffel::ast::Literal::Int(ref value) => match value.try_into() {
+                Ok(v) => match NonZeroU8::new(v) {
+                    Some(v) => Ok(ColumnCount(v)),
+                    None => {
+                        ctx.emit_error(DecodeError::conversion(val, "value must be at least 1"));
+                        Ok(one)
+                    }
+                },
+                Err(e) => {
+                    ctx.emit_error(DecodeError::conversion(val, e));
+                    Ok(one)
+                }
+            },
+            _ => {
+                ctx.emit_error(DecodeError::unsupported(
+                    val,
+                    "Unsupported value, only numbers are recognized",
+                ));
+                Ok(one)
+            }
+        }
+    }
+}
+
 #[derive(knuffel::Decode, Debug, Default, PartialEq)]
 pub struct DebugConfig {
     #[knuffel(child, unwrap(argument))]
@@ -1236,6 +1880,14 @@ pub struct DebugConfig {
     pub render_drm_device: Option<PathBuf>,
     #[knuffel(child)]
     pub emulate_zero_presentation_time: bool,
+    #[knuffel(child)]
+    pub disable_transactions: bool,
+    #[knuffel(child)]
+    pub disable_frame_callbacks_for_invisible_windows: bool,
+    #[knuffel(child)]
+    pub keep_laptop_panel_on_when_lid_is_closed: bool,
+    #[knuffel(child, unwrap(argument))]
+    pub texture_cache_budget_mib: Option<u32>,
 }
 
 #[derive(knuffel::DecodeScalar, Debug, Clone, Copy, PartialEq, Eq)]
@@ -1270,6 +1922,15 @@ impl Config {
         let _span = tracy_client::span!("Config::parse");
         knuffel::parse(filename, text)
     }
+
+    /// Whether any window may prefer no CSD, either globally or through a window rule.
+    pub fn any_prefer_no_csd(&self) -> bool {
+        self.prefer_no_csd
+            || self
+                .window_rules
+                .iter()
+                .any(|rule| rule.prefer_no_csd == Some(true))
+    }
 }
 
 impl Default for Config {
@@ -1282,6 +1943,26 @@ impl Default for Config {
     }
 }
 
+impl LayoutOverride {
+    pub fn merge_with(&mut self, other: &Self) {
+        if let Some(x) = other.gaps {
+            self.gaps = Some(x);
+        }
+        if let Some(x) = &other.default_column_width {
+            self.default_column_width = Some(x.clone());
+        }
+        if let Some(x) = other.center_focused_column {
+            self.center_focused_column = Some(x);
+        }
+        if let Some(x) = other.dim_inactive {
+            self.dim_inactive = Some(x);
+        }
+        if let Some(x) = other.zoom {
+            self.zoom = Some(x);
+        }
+    }
+}
+
 impl BorderRule {
     pub fn merge_with(&mut self, other: &Self) {
         self.off |= other.off;
@@ -1586,10 +2267,18 @@ where
         node: &knuffel::ast::SpannedNode<S>,
         ctx: &mut knuffel::decode::Context<S>,
     ) -> Result<Self, DecodeError<S>> {
-        let default = Self::default().0;
-        Ok(Self(Animation::decode_node(node, ctx, default, |_, _| {
-            Ok(false)
-        })?))
+        let default = Self::default();
+        let mut style = default.style;
+        let anim = Animation::decode_node(node, ctx, default.anim, |child, ctx| {
+            if &**child.node_name == "style" {
+                style = parse_arg_node("style", child, ctx)?;
+                Ok(true)
+            } else {
+                Ok(false)
+            }
+        })?;
+
+        Ok(Self { anim, style })
     }
 }
 
@@ -1767,6 +2456,36 @@ where
     }
 }
 
+impl<S> knuffel::Decode<S> for WindowDimAnim
+where
+    S: knuffel::traits::ErrorSpan,
+{
+    fn decode_node(
+        node: &knuffel::ast::SpannedNode<S>,
+        ctx: &mut knuffel::decode::Context<S>,
+    ) -> Result<Self, DecodeError<S>> {
+        let default = Self::default().0;
+        Ok(Self(Animation::decode_node(node, ctx, default, |_, _| {
+            Ok(false)
+        })?))
+    }
+}
+
+impl<S> knuffel::Decode<S> for MagnifierZoomAnim
+where
+    S: knuffel::traits::ErrorSpan,
+{
+    fn decode_node(
+        node: &knuffel::ast::SpannedNode<S>,
+        ctx: &mut knuffel::decode::Context<S>,
+    ) -> Result<Self, DecodeError<S>> {
+        let default = Self::default().0;
+        Ok(Self(Animation::decode_node(node, ctx, default, |_, _| {
+            Ok(false)
+        })?))
+    }
+}
+
 impl Animation {
     fn decode_node<S: knuffel::traits::ErrorSpan>(
         node: &knuffel::ast::SpannedNode<S>,
@@ -2194,6 +2913,7 @@ where
 
         let mut cooldown = None;
         let mut allow_when_locked = false;
+        let mut allow_inhibiting = true;
         let mut allow_when_locked_node = None;
         for (name, val) in &node.properties {
             match &***name {
@@ -2206,6 +2926,9 @@ where
                     allow_when_locked = knuffel::traits::DecodeScalar::decode(val, ctx)?;
                     allow_when_locked_node = Some(name);
                 }
+                "allow-inhibiting" => {
+                    allow_inhibiting = knuffel::traits::DecodeScalar::decode(val, ctx)?;
+                }
                 name_str => {
                     ctx.emit_error(DecodeError::unexpected(
                         name,
@@ -2226,6 +2949,7 @@ where
             action: Action::Spawn(vec![]),
             cooldown: None,
             allow_when_locked: false,
+            allow_inhibiting: true,
         };
 
         if let Some(child) = children.next() {
@@ -2248,11 +2972,17 @@ where
                         }
                     }
 
+                    // The toggle must keep working, otherwise there'd be no way to turn the binds
+                    // back on with the keyboard.
+                    let allow_inhibiting = allow_inhibiting
+                        && !matches!(action, Action::ToggleKeyboardShortcutsInhibit);
+
                     Ok(Self {
                         key,
                         action,
                         cooldown,
                         allow_when_locked,
+                        allow_inhibiting,
                     })
                 }
                 Err(e) => {
@@ -2316,6 +3046,25 @@ impl FromStr for Key {
             Trigger::TouchpadScrollLeft
         } else if key.eq_ignore_ascii_case("TouchpadScrollRight") {
             Trigger::TouchpadScrollRight
+        } else if key.eq_ignore_ascii_case("MouseLeft") {
+            Trigger::MouseLeft
+        } else if key.eq_ignore_ascii_case("MouseRight") {
+            Trigger::MouseRight
+        } else if key.eq_ignore_ascii_case("MouseMiddle") {
+            Trigger::MouseMiddle
+        } else if key.eq_ignore_ascii_case("MouseBack") {
+            Trigger::MouseBack
+        } else if key.eq_ignore_ascii_case("MouseForward") {
+            Trigger::MouseForward
+        } else if let Some(code) = key
+            .get(..8)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("KeyCode:"))
+            .map(|_| &key[8..])
+        {
+            let code = code
+                .parse()
+                .map_err(|_| miette!("invalid key code: {code}"))?;
+            Trigger::KeyCode(code)
         } else {
             let keysym = keysym_from_name(key, KEYSYM_CASE_INSENSITIVE);
             if keysym.raw() == KEY_NoSymbol {
@@ -2461,6 +3210,7 @@ mod tests {
                 warp-mouse-to-focus
                 focus-follows-mouse
                 workspace-auto-back-and-forth
+                output-edge-resistance 30
             }
 
             output "eDP-1" {
@@ -2469,6 +3219,29 @@ mod tests {
                 position x=10 y=20
                 mode "1920x1080@144"
                 variable-refresh-rate
+
+                layout {
+                    gaps 4
+                }
+            }
+
+            primary-output "eDP-1"
+            output-identity "fuzzy"
+
+            output-profile "docked" {
+                output "eDP-1" {
+                    off
+                }
+                output "DP-1" {
+                    scale 1.5
+                }
+                workspace "chat" output="DP-1"
+                exec "notify-send" "Docked"
+            }
+
+            startup {
+                focus-output "primary"
+                pointer-position "center"
             }
 
             layout {
@@ -2484,6 +3257,11 @@ mod tests {
                     inactive-color "rgba(255, 200, 100, 0.0)"
                 }
 
+                tab-indicator {
+                    width 2
+                    position "right"
+                }
+
                 preset-column-widths {
                     proportion 0.25
                     proportion 0.5
@@ -2502,12 +3280,28 @@ mod tests {
                 }
 
                 center-focused-column "on-overflow"
+
+                workspace-wrap-around
+
+                auto-tile-max-columns 4
+
+                dim-inactive 0.2
+                dim-behind-modal 0.5
+                remember-window-sizes
+                dialog-placement "cascade"
+                transposed
+                transpose-portrait-outputs
             }
 
             spawn-at-startup "alacritty" "-e" "fish"
 
             prefer-no-csd
 
+            appearance {
+                color-scheme "prefer-dark"
+                accent-color "#3584e4"
+            }
+
             cursor {
                 xcursor-theme "breeze_cursors"
                 xcursor-size 16
@@ -2515,15 +3309,35 @@ mod tests {
 
             screenshot-path "~/Screenshots/screenshot.png"
 
+            clip-recorder {
+                path "~/Clips/clip.gif"
+                fps 15
+            }
+
             hotkey-overlay {
                 skip-at-startup
             }
 
+            resize-mode {
+                width-step "50"
+            }
+
+            notifications {
+                on
+                position "bottom-right"
+            }
+
+            blur {
+                on
+                passes 2
+            }
+
             animations {
                 slowdown 2.0
 
                 workspace-switch {
                     spring damping-ratio=1.0 stiffness=1000 epsilon=0.0001
+                    style "depth"
                 }
 
                 horizontal-view-movement {
@@ -2539,14 +3353,21 @@ mod tests {
                 DISPLAY null
             }
 
+            sandboxed-clients {
+                allow "screencopy" app-id="com.obsproject.Studio"
+                allow "data-control"
+            }
+
             window-rule {
                 match app-id=".*alacritty"
                 exclude title="~"
                 exclude is-active=true is-focused=false
 
                 open-on-output "eDP-1"
+                open-on-workspace "chat" focus=true
                 open-maximized true
                 open-fullscreen false
+                prefer-no-csd false
 
                 focus-ring {
                     off
@@ -2557,26 +3378,55 @@ mod tests {
                     on
                     width 8.5
                 }
+
+                confirm-close true
+                on-close "move-focus-left"
+                always-center true
+                oversized-window "scale-down"
+                keep-aspect-ratio true
             }
 
             binds {
                 Mod+T allow-when-locked=true { spawn "alacritty"; }
                 Mod+Q { close-window; }
+                Mod+B { focus-or-spawn "firefox" "firefox" "--new-window"; }
                 Mod+Shift+H { focus-monitor-left; }
                 Mod+Ctrl+Shift+L { move-window-to-monitor-right; }
                 Mod+Comma { consume-window-into-column; }
                 Mod+1 { focus-workspace 1; }
                 Mod+Shift+1 { focus-workspace "workspace-1"; }
-                Mod+Shift+E { quit skip-confirmation=true; }
+                Mod+Shift+E allow-inhibiting=false { quit skip-confirmation=true; }
+                Mod+Escape { toggle-keyboard-shortcuts-inhibit; }
                 Mod+WheelScrollDown cooldown-ms=150 { focus-workspace-down; }
+                Mod+W { warp-cursor 10 20 output="DP-1"; }
+            }
+
+            switch-events {
+                lid-close { spawn "loginctl" "lock-session"; power-off-monitors; }
+                tablet-mode-on { spawn "bash" "-c" "gsettings set org.gnome.desktop.a11y.applications screen-keyboard-enabled true"; }
+            }
+
+            idle {
+                dim-after-sec 120
+                power-off-monitors-after-sec 300
+                lock-command "swaylock" "-f"
+                inhibit-while-playing
             }
 
             debug {
                 render-drm-device "/dev/dri/renderD129"
+                texture-cache-budget-mib 256
             }
 
             workspace "workspace-1" {
                 open-on-output "eDP-1"
+
+                layout {
+                    gaps 0
+                    center-focused-column "always"
+                    dim-inactive 0
+                    zoom 0.8
+                }
             }
             workspace "workspace-2"
             workspace "workspace-3"
@@ -2634,6 +3484,7 @@ mod tests {
                     warp_mouse_to_focus: true,
                     focus_follows_mouse: true,
                     workspace_auto_back_and_forth: true,
+                    output_edge_resistance: Some(FloatOrInt(30.)),
                 },
                 outputs: vec![Output {
                     off: false,
@@ -2647,7 +3498,39 @@ mod tests {
                         refresh: Some(144.),
                     }),
                     variable_refresh_rate: true,
+                    layout: LayoutOverride {
+                        gaps: Some(FloatOrInt(4.)),
+                        ..Default::default()
+                    },
                 }],
+                primary_output: Some("eDP-1".to_owned()),
+                output_identity: OutputIdentity::Fuzzy,
+                output_profiles: vec![OutputProfile {
+                    name: "docked".to_owned(),
+                    outputs: vec![
+                        Output {
+                            off: true,
+                            name: "eDP-1".to_owned(),
+                            ..Default::default()
+                        },
+                        Output {
+                            name: "DP-1".to_owned(),
+                            scale: Some(FloatOrInt(1.5)),
+                            ..Default::default()
+                        },
+                    ],
+                    workspaces: vec![OutputProfileWorkspace {
+                        name: "chat".to_owned(),
+                        output: "DP-1".to_owned(),
+                    }],
+                    exec: vec![SpawnAtStartup {
+                        command: vec!["notify-send".to_owned(), "Docked".to_owned()],
+                    }],
+                }],
+                startup: Startup {
+                    focus_output: Some("primary".to_owned()),
+                    pointer_position: Some(StartupPointerPosition::Center),
+                },
                 layout: Layout {
                     focus_ring: FocusRing {
                         off: false,
@@ -2690,6 +3573,12 @@ mod tests {
                         active_gradient: None,
                         inactive_gradient: None,
                     },
+                    tab_indicator: TabIndicator {
+                        off: false,
+                        width: FloatOrInt(2.),
+                        position: TabIndicatorPosition::Right,
+                        ..Default::default()
+                    },
                     preset_column_widths: vec![
                         PresetWidth::Proportion(0.25),
                         PresetWidth::Proportion(0.5),
@@ -2707,29 +3596,63 @@ mod tests {
                         bottom: FloatOrInt(0.),
                     },
                     center_focused_column: CenterFocusedColumn::OnOverflow,
+                    workspace_wrap_around: true,
+                    auto_tile_max_columns: ColumnCount(NonZeroU8::new(4).unwrap()),
+                    dim_inactive: FloatOrInt(0.2),
+                    dim_behind_modal: FloatOrInt(0.5),
+                    remember_window_sizes: true,
+                    dialog_placement: DialogPlacement::Cascade,
+                    transposed: true,
+                    transpose_portrait_outputs: true,
                 },
                 spawn_at_startup: vec![SpawnAtStartup {
                     command: vec!["alacritty".to_owned(), "-e".to_owned(), "fish".to_owned()],
                 }],
                 prefer_no_csd: true,
+                appearance: Appearance {
+                    color_scheme: ColorScheme::PreferDark,
+                    accent_color: Some(Color::new(53, 132, 228, 255)),
+                },
                 cursor: Cursor {
                     xcursor_theme: String::from("breeze_cursors"),
                     xcursor_size: 16,
                 },
                 screenshot_path: Some(String::from("~/Screenshots/screenshot.png")),
+                clip_recorder: ClipRecorder {
+                    path: String::from("~/Clips/clip.gif"),
+                    max_duration_ms: 10000,
+                    fps: 15,
+                },
                 hotkey_overlay: HotkeyOverlay {
                     skip_at_startup: true,
                 },
+                resize_mode: ResizeMode {
+                    width_step: SizeChange::SetFixed(50),
+                    ..Default::default()
+                },
+                notifications: Notifications {
+                    on: true,
+                    position: NotificationPosition::BottomRight,
+                    ..Default::default()
+                },
+                blur: Blur {
+                    on: true,
+                    passes: 2,
+                    ..Default::default()
+                },
                 animations: Animations {
                     slowdown: 2.,
-                    workspace_switch: WorkspaceSwitchAnim(Animation {
-                        off: false,
-                        kind: AnimationKind::Spring(SpringParams {
-                            damping_ratio: 1.,
-                            stiffness: 1000,
-                            epsilon: 0.0001,
-                        }),
-                    }),
+                    workspace_switch: WorkspaceSwitchAnim {
+                        anim: Animation {
+                            off: false,
+                            kind: AnimationKind::Spring(SpringParams {
+                                damping_ratio: 1.,
+                                stiffness: 1000,
+                                epsilon: 0.0001,
+                            }),
+                        },
+                        style: WorkspaceSwitchStyle::Depth,
+                    },
                     horizontal_view_movement: HorizontalViewMovementAnim(Animation {
                         off: false,
                         kind: AnimationKind::Easing(EasingParams {
@@ -2756,6 +3679,18 @@ mod tests {
                         value: None,
                     },
                 ]),
+                sandboxed_clients: SandboxedClients {
+                    allow: vec![
+                        SandboxAllow {
+                            protocol: PrivilegedProtocol::Screencopy,
+                            app_id: Some(String::from("com.obsproject.Studio")),
+                        },
+                        SandboxAllow {
+                            protocol: PrivilegedProtocol::DataControl,
+                            app_id: None,
+                        },
+                    ],
+                },
                 window_rules: vec![WindowRule {
                     matches: vec![Match {
                         app_id: Some(Regex::new(".*alacritty").unwrap()),
@@ -2784,8 +3719,13 @@ mod tests {
                         },
                     ],
                     open_on_output: Some("eDP-1".to_owned()),
+                    open_on_workspace: Some(OpenOnWorkspace {
+                        name: "chat".to_owned(),
+                        focus: true,
+                    }),
                     open_maximized: Some(true),
                     open_fullscreen: Some(false),
+                    prefer_no_csd: Some(false),
                     focus_ring: BorderRule {
                         off: true,
                         width: Some(FloatOrInt(3.)),
@@ -2796,20 +3736,34 @@ mod tests {
                         width: Some(FloatOrInt(8.5)),
                         ..Default::default()
                     },
+                    confirm_close: Some(true),
+                    on_close: Some(OnClose::MoveFocusLeft),
+                    always_center: Some(true),
+                    oversized_window: Some(OversizedWindow::ScaleDown),
+                    keep_aspect_ratio: Some(true),
                     ..Default::default()
                 }],
                 workspaces: vec![
                     Workspace {
                         name: WorkspaceName("workspace-1".to_string()),
                         open_on_output: Some("eDP-1".to_string()),
+                        layout: LayoutOverride {
+                            gaps: Some(FloatOrInt(0.)),
+                            center_focused_column: Some(CenterFocusedColumn::Always),
+                            dim_inactive: Some(FloatOrInt(0.)),
+                            zoom: Some(FloatOrInt(0.8)),
+                            ..Default::default()
+                        },
                     },
                     Workspace {
                         name: WorkspaceName("workspace-2".to_string()),
                         open_on_output: None,
+                        layout: LayoutOverride::default(),
                     },
                     Workspace {
                         name: WorkspaceName("workspace-3".to_string()),
                         open_on_output: None,
+                        layout: LayoutOverride::default(),
                     },
                 ],
                 binds: Binds(vec![
@@ -2821,6 +3775,7 @@ mod tests {
                         action: Action::Spawn(vec!["alacritty".to_owned()]),
                         cooldown: None,
                         allow_when_locked: true,
+                        allow_inhibiting: true,
                     },
                     Bind {
                         key: Key {
@@ -2830,6 +3785,20 @@ mod tests {
                         action: Action::CloseWindow,
                         cooldown: None,
                         allow_when_locked: false,
+                        allow_inhibiting: true,
+                    },
+                    Bind {
+                        key: Key {
+                            trigger: Trigger::Keysym(Keysym::b),
+                            modifiers: Modifiers::COMPOSITOR,
+                        },
+                        action: Action::FocusOrSpawn(
+                            "firefox".to_owned(),
+                            vec!["firefox".to_owned(), "--new-window".to_owned()],
+                        ),
+                        cooldown: None,
+                        allow_when_locked: false,
+                        allow_inhibiting: true,
                     },
                     Bind {
                         key: Key {
@@ -2839,6 +3808,7 @@ mod tests {
                         action: Action::FocusMonitorLeft,
                         cooldown: None,
                         allow_when_locked: false,
+                        allow_inhibiting: true,
                     },
                     Bind {
                         key: Key {
@@ -2848,6 +3818,7 @@ mod tests {
                         action: Action::MoveWindowToMonitorRight,
                         cooldown: None,
                         allow_when_locked: false,
+                        allow_inhibiting: true,
                     },
                     Bind {
                         key: Key {
@@ -2857,6 +3828,7 @@ mod tests {
                         action: Action::ConsumeWindowIntoColumn,
                         cooldown: None,
                         allow_when_locked: false,
+                        allow_inhibiting: true,
                     },
                     Bind {
                         key: Key {
@@ -2866,6 +3838,7 @@ mod tests {
                         action: Action::FocusWorkspace(WorkspaceReference::Index(1)),
                         cooldown: None,
                         allow_when_locked: false,
+                        allow_inhibiting: true,
                     },
                     Bind {
                         key: Key {
@@ -2877,6 +3850,7 @@ mod tests {
                         )),
                         cooldown: None,
                         allow_when_locked: false,
+                        allow_inhibiting: true,
                     },
                     Bind {
                         key: Key {
@@ -2886,6 +3860,17 @@ mod tests {
                         action: Action::Quit(true),
                         cooldown: None,
                         allow_when_locked: false,
+                        allow_inhibiting: false,
+                    },
+                    Bind {
+                        key: Key {
+                            trigger: Trigger::Keysym(Keysym::Escape),
+                            modifiers: Modifiers::COMPOSITOR,
+                        },
+                        action: Action::ToggleKeyboardShortcutsInhibit,
+                        cooldown: None,
+                        allow_when_locked: false,
+                        allow_inhibiting: false,
                     },
                     Bind {
                         key: Key {
@@ -2895,10 +3880,48 @@ mod tests {
                         action: Action::FocusWorkspaceDown,
                         cooldown: Some(Duration::from_millis(150)),
                         allow_when_locked: false,
+                        allow_inhibiting: true,
+                    },
+                    Bind {
+                        key: Key {
+                            trigger: Trigger::Keysym(Keysym::w),
+                            modifiers: Modifiers::COMPOSITOR,
+                        },
+                        action: Action::WarpCursor(10, 20, Some("DP-1".to_owned())),
+                        cooldown: None,
+                        allow_when_locked: false,
+                        allow_inhibiting: true,
                     },
                 ]),
+                switch_events: SwitchEvents {
+                    lid_open: None,
+                    lid_close: Some(SwitchAction {
+                        actions: vec![
+                            Action::Spawn(vec!["loginctl".to_owned(), "lock-session".to_owned()]),
+                            Action::PowerOffMonitors,
+                        ],
+                    }),
+                    tablet_mode_on: Some(SwitchAction {
+                        actions: vec![Action::Spawn(vec![
+                            "bash".to_owned(),
+                            "-c".to_owned(),
+                            "gsettings set org.gnome.desktop.a11y.applications screen-keyboard-enabled true".to_owned(),
+                        ])],
+                    }),
+                    tablet_mode_off: None,
+                },
+                idle: Idle {
+                    dim_after_sec: Some(120),
+                    power_off_monitors_after_sec: Some(300),
+                    lock_after_sec: None,
+                    lock_command: Some(LockCommand {
+                        command: vec!["swaylock".to_owned(), "-f".to_owned()],
+                    }),
+                    inhibit_while_playing: true,
+                },
                 debug: DebugConfig {
                     render_drm_device: Some(PathBuf::from("/dev/dri/renderD129")),
+                    texture_cache_budget_mib: Some(256),
                     ..Default::default()
                 },
             },
@@ -2985,10 +4008,58 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_key_code() {
+        assert_eq!(
+            "Mod+KeyCode:656".parse::<Key>().unwrap(),
+            Key {
+                trigger: Trigger::KeyCode(656),
+                modifiers: Modifiers::COMPOSITOR
+            },
+        );
+        assert_eq!(
+            "keycode:191".parse::<Key>().unwrap(),
+            Key {
+                trigger: Trigger::KeyCode(191),
+                modifiers: Modifiers::empty()
+            },
+        );
+        assert!("KeyCode:".parse::<Key>().is_err());
+        assert!("KeyCode:G1".parse::<Key>().is_err());
+    }
+
     #[test]
     fn default_repeat_params() {
         let config = Config::parse("config.kdl", "").unwrap();
         assert_eq!(config.input.keyboard.repeat_delay, 600);
         assert_eq!(config.input.keyboard.repeat_rate, 25);
     }
+
+    #[test]
+    fn auto_tile_max_columns_rejects_zero() {
+        let config = Config::parse("config.kdl", "layout { auto-tile-max-columns 0; }");
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn output_profile_matches_exact_set() {
+        let config = Config::parse(
+            "config.kdl",
+            r#"
+            output-profile "docked" {
+                output "eDP-1"
+                output "DP-1"
+            }
+            "#,
+        )
+        .unwrap();
+        let profile = &config.output_profiles[0];
+
+        let connected = |names: &[&str]| names.iter().map(|&n| n.to_owned()).collect::<Vec<_>>();
+        assert!(profile.matches(&connected(&["DP-1", "eDP-1"])));
+        assert!(profile.matches(&connected(&["edp-1", "dp-1"])));
+        assert!(!profile.matches(&connected(&["eDP-1"])));
+        assert!(!profile.matches(&connected(&["eDP-1", "DP-1", "HDMI-A-1"])));
+        assert!(!profile.matches(&connected(&["eDP-1", "HDMI-A-1"])));
+    }
 }
@@ -20,6 +20,11 @@ pub enum Request {
     FocusedWindow,
     /// Perform an action.
     Action(Action),
+    /// Perform several actions in order, as one batch.
+    ///
+    /// The actions run back to back, so windows are configured and animated only once for the
+    /// whole batch, rather than once for every action.
+    Actions(Vec<Action>),
     /// Change output configuration temporarily.
     ///
     /// The configuration is changed temporarily and not saved into the config file. If the output
@@ -35,6 +40,23 @@ pub enum Request {
     Workspaces,
     /// Request information about the focused output.
     FocusedOutput,
+    /// Request the focus history of every output.
+    FocusHistory,
+    /// Request the list of minimized windows.
+    MinimizedWindows,
+    /// Request the cursor position.
+    CursorPosition,
+    /// Let the user pick a window and request information about it.
+    ///
+    /// The reply comes once the user clicks a window, types its hint, or cancels with Escape.
+    PickWindow,
+    /// Let the user pick an output and request its name.
+    ///
+    /// The reply comes once the user clicks an output, confirms the focused one with Enter, or
+    /// cancels with Escape.
+    PickOutput,
+    /// Request the memory usage of the texture caches (for debugging).
+    TextureCacheStats,
     /// Respond with an error (for testing error handling).
     ReturnError,
 }
@@ -68,6 +90,18 @@ pub enum Response {
     Workspaces(Vec<Workspace>),
     /// Information about the focused output.
     FocusedOutput(Option<Output>),
+    /// Focus history of every output.
+    FocusHistory(Vec<FocusHistory>),
+    /// Minimized windows, oldest first.
+    MinimizedWindows(Vec<Window>),
+    /// Cursor position.
+    CursorPosition(CursorPosition),
+    /// Picked window, or `None` if the pick was cancelled.
+    PickedWindow(Option<PickedWindow>),
+    /// Name of the picked output, or `None` if the pick was cancelled.
+    PickedOutput(Option<String>),
+    /// Memory usage of the texture caches.
+    TextureCacheStats(TextureCacheStats),
 }
 
 /// Actions that niri can perform.
@@ -92,6 +126,15 @@ pub enum Action {
         #[cfg_attr(feature = "clap", arg(last = true, required = true))]
         command: Vec<String>,
     },
+    /// Focus a window with the app ID, or spawn a command if there is no such window.
+    FocusOrSpawn {
+        /// App ID of the window to focus.
+        #[cfg_attr(feature = "clap", arg())]
+        app_id: String,
+        /// Command to spawn when no window has the app ID.
+        #[cfg_attr(feature = "clap", arg(last = true, required = true))]
+        command: Vec<String>,
+    },
     /// Do a screen transition.
     DoScreenTransition {
         /// Delay in milliseconds for the screen to freeze before starting the transition.
@@ -108,6 +151,10 @@ pub enum Action {
     CloseWindow,
     /// Toggle fullscreen on the focused window.
     FullscreenWindow,
+    /// Minimize the focused window.
+    MinimizeWindow,
+    /// Restore the most recently minimized window.
+    RestoreMinimizedWindow,
     /// Focus the column to the left.
     FocusColumnLeft,
     /// Focus the column to the right.
@@ -116,6 +163,18 @@ pub enum Action {
     FocusColumnFirst,
     /// Focus the last column.
     FocusColumnLast,
+    /// Focus the previous window in the focus history of the focused output.
+    FocusBack,
+    /// Focus the next window in the focus history of the focused output.
+    FocusForward,
+    /// Swap the focused window with the previously focused window on the focused output.
+    SwapWithPrevious,
+    /// Focus the column at the index, counting from 1.
+    FocusColumn {
+        /// Index of the column to focus.
+        #[cfg_attr(feature = "clap", arg())]
+        index: usize,
+    },
     /// Focus the next column to the right, looping if at end.
     FocusColumnRightOrFirst,
     /// Focus the next column to the left, looping if at start.
@@ -148,6 +207,18 @@ pub enum Action {
     MoveColumnToFirst,
     /// Move the focused column to the end of the workspace.
     MoveColumnToLast,
+    /// Move the focused column to the index, counting from 1.
+    MoveColumnToIndex {
+        /// New index for the column.
+        #[cfg_attr(feature = "clap", arg())]
+        index: usize,
+    },
+    /// Show letter hints over the visible windows and focus the window whose letter is typed.
+    ShowWindowHints,
+    /// Enter the keyboard resize mode, where the arrow keys resize the active column and window.
+    EnterResizeMode,
+    /// Exit the keyboard resize mode.
+    ExitResizeMode,
     /// Move the focused window down in a column.
     MoveWindowDown,
     /// Move the focused window up in a column.
@@ -170,11 +241,16 @@ pub enum Action {
     FocusWorkspaceDown,
     /// Focus the workspace above.
     FocusWorkspaceUp,
-    /// Focus a workspace by reference (index or name).
+    /// Focus a workspace by reference (index or name), or by fuzzy-matching its name.
     FocusWorkspace {
         /// Reference (index or name) of the workspace to focus.
-        #[cfg_attr(feature = "clap", arg())]
-        reference: WorkspaceReferenceArg,
+        #[cfg_attr(feature = "clap", arg(required_unless_present = "name"))]
+        reference: Option<WorkspaceReferenceArg>,
+        /// Fuzzy query to match against workspace names.
+        ///
+        /// Among equally good matches, the most recently used workspace is focused.
+        #[cfg_attr(feature = "clap", arg(long, conflicts_with = "reference"))]
+        name: Option<String>,
     },
     /// Focus the previous workspace.
     FocusWorkspacePrevious,
@@ -210,6 +286,20 @@ pub enum Action {
     FocusMonitorDown,
     /// Focus the monitor above.
     FocusMonitorUp,
+    /// Move the cursor to a position on an output.
+    WarpCursor {
+        /// Horizontal position relative to the left edge of the output, in logical pixels.
+        #[cfg_attr(feature = "clap", arg())]
+        x: i32,
+        /// Vertical position relative to the top edge of the output, in logical pixels.
+        #[cfg_attr(feature = "clap", arg())]
+        y: i32,
+        /// Name of the output, or the focused output if unset.
+        #[cfg_attr(feature = "clap", arg(long))]
+        output: Option<String>,
+    },
+    /// Move the cursor to the center of the focused window.
+    WarpCursorToFocusedWindow,
     /// Move the focused window to the monitor to the left.
     MoveWindowToMonitorLeft,
     /// Move the focused window to the monitor to the right.
@@ -238,12 +328,79 @@ pub enum Action {
     SwitchPresetColumnWidth,
     /// Toggle the maximized state of the focused column.
     MaximizeColumn,
+    /// Toggle automatic column width balancing on the focused workspace.
+    ToggleAutoTile,
+    /// Toggle keeping the focused column centered whenever it is focused.
+    ToggleColumnAlwaysCenter,
+    /// Select a region to record a short clip of, or stop the current recording.
+    RecordClip,
+    /// Toggle turning off compositor shortcuts for the focused window.
+    ///
+    /// Binds with allow-inhibiting=false keep working.
+    ToggleKeyboardShortcutsInhibit,
+    /// Take back all input from a client that took it through wlr-input-inhibitor.
+    ///
+    /// This works even while input is inhibited.
+    BreakInputInhibit,
     /// Change the width of the focused column.
     SetColumnWidth {
         /// How to change the width.
         #[cfg_attr(feature = "clap", arg())]
         change: SizeChange,
     },
+    /// Toggle the focused column to a width and back to the width it had before.
+    ///
+    /// If the column already has the toggled width, it goes to the normal width instead.
+    ToggleColumnWidth {
+        /// Width to go to if the column has no width to go back to.
+        #[cfg_attr(feature = "clap", arg())]
+        normal: SizeChange,
+        /// Width to toggle to.
+        #[cfg_attr(feature = "clap", arg())]
+        toggled: SizeChange,
+    },
+    /// Change the opacity of the focused window.
+    SetWindowOpacity {
+        /// How to change the opacity.
+        #[cfg_attr(feature = "clap", arg())]
+        change: OpacityChange,
+    },
+    /// Reset the opacity of the focused window back to the one from the window rules.
+    ResetWindowOpacity,
+    /// Change the gaps of the focused workspace.
+    ///
+    /// Only fixed values are supported, percentages are ignored.
+    SetWorkspaceGaps {
+        /// How to change the gaps.
+        #[cfg_attr(feature = "clap", arg())]
+        change: SizeChange,
+    },
+    /// Change the default width of new columns on the focused workspace.
+    SetWorkspaceDefaultColumnWidth {
+        /// How to change the width.
+        #[cfg_attr(feature = "clap", arg())]
+        change: SizeChange,
+    },
+    /// Change when the focused column is centered on the focused workspace.
+    SetWorkspaceCenterFocusedColumn {
+        /// When to center the focused column.
+        #[cfg_attr(feature = "clap", arg())]
+        mode: CenterFocusedColumnMode,
+    },
+    /// Change the zoom of the focused workspace.
+    SetWorkspaceZoom {
+        /// Zoom to render the workspace at, from 0.25 to 1.
+        #[cfg_attr(feature = "clap", arg())]
+        zoom: f64,
+    },
+    /// Reset the layout settings of the focused workspace back to the configured ones.
+    ResetWorkspaceLayout,
+    /// Change how windows are arranged on the focused workspace.
+    SetLayoutStrategy {
+        /// Layout strategy to use.
+        #[cfg_attr(feature = "clap", arg())]
+        strategy: LayoutStrategyName,
+    },
     /// Switch between keyboard layouts.
     SwitchLayout {
         /// Layout to switch to.
@@ -260,6 +417,63 @@ pub enum Action {
     MoveWorkspaceToMonitorDown,
     /// Move the focused workspace to the monitor above.
     MoveWorkspaceToMonitorUp,
+    /// Make a different monitor the primary one.
+    ///
+    /// Workspaces whose original monitor is disconnected move to the new primary monitor.
+    SetPrimaryOutput {
+        /// Name of the output to make primary.
+        #[cfg_attr(feature = "clap", arg())]
+        output: String,
+    },
+    /// Save the column structure of the focused workspace as a named layout preset.
+    SaveLayoutPreset {
+        /// Name of the preset.
+        #[cfg_attr(feature = "clap", arg())]
+        name: String,
+    },
+    /// Load a named layout preset.
+    ///
+    /// Newly opened windows with matching app ids are placed into the columns of the preset.
+    LoadLayoutPreset {
+        /// Name of the preset.
+        #[cfg_attr(feature = "clap", arg())]
+        name: String,
+    },
+    /// Zoom in the magnifier on the output under the cursor.
+    MagnifierZoomIn,
+    /// Zoom out the magnifier on the output under the cursor.
+    MagnifierZoomOut,
+    /// Reset the magnifier zoom on the output under the cursor.
+    MagnifierReset,
+    /// Toggle reduced motion, which makes all animations finish instantly.
+    ToggleReducedMotion,
+    /// Add the focused window to a named window group.
+    SetWindowGroup {
+        /// Name of the group.
+        #[cfg_attr(feature = "clap", arg())]
+        name: String,
+    },
+    /// Remove the focused window from its window group.
+    UnsetWindowGroup,
+    /// Move all windows of a group onto the active workspace, next to the active column, restoring
+    /// the minimized ones.
+    GatherWindowGroup {
+        /// Name of the group.
+        #[cfg_attr(feature = "clap", arg())]
+        name: String,
+    },
+    /// Minimize all windows of a group.
+    StashWindowGroup {
+        /// Name of the group.
+        #[cfg_attr(feature = "clap", arg())]
+        name: String,
+    },
+    /// Dismiss the newest shown notification.
+    DismissNotification,
+    /// Dismiss all notifications.
+    DismissAllNotifications,
+    /// Toggle do-not-disturb, which hides all notifications except critical ones.
+    ToggleDoNotDisturb,
     /// Toggle a debug tint on windows.
     ToggleDebugTint,
     /// Toggle visualization of render element opaque regions.
@@ -281,6 +495,15 @@ pub enum SizeChange {
     AdjustProportion(f64),
 }
 
+/// Change in window opacity.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum OpacityChange {
+    /// Set the opacity, from 0 (transparent) to 1 (opaque).
+    SetOpacity(f64),
+    /// Add or subtract to the current opacity.
+    AdjustOpacity(f64),
+}
+
 /// Workspace reference (index or name) to operate on.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum WorkspaceReferenceArg {
@@ -290,6 +513,27 @@ pub enum WorkspaceReferenceArg {
     Name(String),
 }
 
+/// Way of arranging windows on a workspace.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutStrategyName {
+    /// Scrollable columns arranged manually.
+    Scrolling,
+    /// One master window on the left with the rest stacked on the right.
+    MasterStack,
+}
+
+/// When to center the focused column.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CenterFocusedColumnMode {
+    /// Never center the focused column.
+    Never,
+    /// Always center the focused column.
+    Always,
+    /// Center the focused column if it doesn't fit on the screen together with the previously
+    /// focused column.
+    OnOverflow,
+}
+
 /// Layout to switch to.
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LayoutSwitchTarget {
@@ -491,7 +735,7 @@ pub enum Transform {
 }
 
 /// Toplevel window.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Window {
     /// Title, if set.
     pub title: Option<String>,
@@ -499,6 +743,69 @@ pub struct Window {
     pub app_id: Option<String>,
 }
 
+/// Cursor position.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CursorPosition {
+    /// Horizontal position in the global coordinate space, in logical pixels.
+    pub x: f64,
+    /// Vertical position in the global coordinate space, in logical pixels.
+    pub y: f64,
+    /// Output under the cursor and the position on it, if any.
+    pub output: Option<OutputCursorPosition>,
+}
+
+/// Cursor position on an output.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OutputCursorPosition {
+    /// Name of the output.
+    pub name: String,
+    /// Horizontal position relative to the left edge of the output, in logical pixels.
+    pub x: f64,
+    /// Vertical position relative to the top edge of the output, in logical pixels.
+    pub y: f64,
+}
+
+/// Window picked by the user.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PickedWindow {
+    /// Unique id of the window, stable for as long as the window is open.
+    pub id: u32,
+    /// Title, if set.
+    pub title: Option<String>,
+    /// Application ID, if set.
+    pub app_id: Option<String>,
+}
+
+/// Windows focused on an output, like a browser history.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FocusHistory {
+    /// Name of the output.
+    pub output: String,
+    /// Focused windows, oldest first.
+    pub windows: Vec<Window>,
+    /// Index of the current entry in `windows`, if any.
+    ///
+    /// Entries after it can be returned to with the `focus-forward` action.
+    pub current: Option<usize>,
+}
+
+/// Memory usage of niri's texture caches.
+///
+/// All sizes are estimates in bytes.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureCacheStats {
+    /// Budget above which the caches are evicted.
+    pub budget: u64,
+    /// Cached textures of hidden UI elements, like the hotkey overlay.
+    pub ui: u64,
+    /// Textures of the closing window animations.
+    pub closing_windows: u64,
+    /// Frozen outputs of the open screenshot UI.
+    pub screenshot_ui: u64,
+    /// Number of times some cache was evicted to stay within the budget.
+    pub evictions: u64,
+}
+
 /// Output configuration change result.
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OutputConfigChanged {
@@ -523,6 +830,21 @@ pub struct Workspace {
     pub output: Option<String>,
     /// Whether the workspace is currently active on its output.
     pub is_active: bool,
+    /// How much of the workspace is currently visible on its output, in percent from 0 to 100.
+    ///
+    /// During a workspace switch, this value gradually goes down on the old workspace and up on
+    /// the new one, following the animation or the gesture.
+    pub visibility: u8,
+    /// Number of windows on the workspace.
+    pub window_count: u32,
+    /// Id of the active window on the workspace, if any.
+    ///
+    /// The id is unique for the lifetime of the compositor.
+    pub active_window_id: Option<u32>,
+    /// The active window on the workspace, if any.
+    pub active_window: Option<Window>,
+    /// Whether any window on the workspace requested attention.
+    pub is_urgent: bool,
 }
 
 impl FromStr for WorkspaceReferenceArg {
@@ -583,6 +905,24 @@ impl FromStr for SizeChange {
     }
 }
 
+impl FromStr for OpacityChange {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.bytes().next() {
+            Some(b'-' | b'+') => {
+                let value = s.parse().map_err(|_| "error parsing value")?;
+                Ok(Self::AdjustOpacity(value))
+            }
+            Some(_) => {
+                let value = s.parse().map_err(|_| "error parsing value")?;
+                Ok(Self::SetOpacity(value))
+            }
+            None => Err("value is missing"),
+        }
+    }
+}
+
 impl FromStr for LayoutSwitchTarget {
     type Err = &'static str;
 
@@ -595,6 +935,31 @@ impl FromStr for LayoutSwitchTarget {
     }
 }
 
+impl FromStr for LayoutStrategyName {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "scrolling" => Ok(Self::Scrolling),
+            "master-stack" => Ok(Self::MasterStack),
+            _ => Err(r#"invalid layout strategy, can be "scrolling" or "master-stack""#),
+        }
+    }
+}
+
+impl FromStr for CenterFocusedColumnMode {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "never" => Ok(Self::Never),
+            "always" => Ok(Self::Always),
+            "on-overflow" => Ok(Self::OnOverflow),
+            _ => Err(r#"invalid mode, can be "never", "always" or "on-overflow""#),
+        }
+    }
+}
+
 impl FromStr for Transform {
     type Err = &'static str;
 
@@ -2,7 +2,7 @@ use std::time::Duration;
 
 use keyframe::functions::{EaseOutCubic, EaseOutQuad};
 use keyframe::EasingFunction;
-use portable_atomic::{AtomicF64, Ordering};
+use portable_atomic::{AtomicBool, AtomicF64, Ordering};
 
 use crate::utils::get_monotonic_time;
 
@@ -11,6 +11,12 @@ pub use spring::{Spring, SpringParams};
 
 pub static ANIMATION_SLOWDOWN: AtomicF64 = AtomicF64::new(1.);
 
+/// Whether all animations should finish instantly.
+///
+/// Unlike zero slowdown, this can be toggled at runtime and follows the `enable-animations` portal
+/// setting, and it does not affect the gesture velocities.
+pub static REDUCED_MOTION: AtomicBool = AtomicBool::new(false);
+
 #[derive(Debug)]
 pub struct Animation {
     from: f64,
@@ -156,6 +162,7 @@ impl Animation {
             current_time: now,
             kind,
         }
+        .with_reduced_motion()
     }
 
     pub fn spring(spring: Spring) -> Self {
@@ -180,6 +187,7 @@ impl Animation {
             current_time: now,
             kind,
         }
+        .with_reduced_motion()
     }
 
     pub fn decelerate(
@@ -218,6 +226,16 @@ impl Animation {
             current_time: now,
             kind,
         }
+        .with_reduced_motion()
+    }
+
+    /// Makes the animation end right away when reduced motion is on.
+    fn with_reduced_motion(mut self) -> Self {
+        if REDUCED_MOTION.load(Ordering::Relaxed) {
+            self.duration = Duration::ZERO;
+            self.clamped_duration = Duration::ZERO;
+        }
+        self
     }
 
     pub fn set_current_time(&mut self, time: Duration) {
@@ -232,8 +250,8 @@ impl Animation {
         }
 
         let slowdown = ANIMATION_SLOWDOWN.load(Ordering::Relaxed);
-        if slowdown <= f64::EPSILON {
-            // Zero slowdown will cause the animation to end right away.
+        if slowdown <= f64::EPSILON || REDUCED_MOTION.load(Ordering::Relaxed) {
+            // Zero slowdown or reduced motion will cause the animation to end right away.
             self.current_time = end_time;
             return;
         }
@@ -340,7 +358,7 @@ impl Animation {
         self.to
     }
 
-    #[cfg(test)]
+    #[cfg(any(test, feature = "fuzzing"))]
     pub fn from(&self) -> f64 {
         self.from
     }
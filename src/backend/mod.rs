@@ -10,6 +10,9 @@ use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use crate::input::CompositorMod;
 use crate::niri::Niri;
 
+pub mod headless;
+pub use headless::Headless;
+
 pub mod tty;
 pub use tty::Tty;
 
@@ -19,6 +22,7 @@ pub use winit::Winit;
 pub enum Backend {
     Tty(Tty),
     Winit(Winit),
+    Headless(Headless),
 }
 
 #[derive(PartialEq, Eq)]
@@ -38,6 +42,7 @@ impl Backend {
         match self {
             Backend::Tty(tty) => tty.init(niri),
             Backend::Winit(winit) => winit.init(niri),
+            Backend::Headless(headless) => headless.init(niri),
         }
     }
 
@@ -45,6 +50,7 @@ impl Backend {
         match self {
             Backend::Tty(tty) => tty.seat_name(),
             Backend::Winit(winit) => winit.seat_name(),
+            Backend::Headless(headless) => headless.seat_name(),
         }
     }
 
@@ -55,6 +61,7 @@ impl Backend {
         match self {
             Backend::Tty(tty) => tty.with_primary_renderer(f),
             Backend::Winit(winit) => winit.with_primary_renderer(f),
+            Backend::Headless(headless) => headless.with_primary_renderer(f),
         }
     }
 
@@ -67,6 +74,7 @@ impl Backend {
         match self {
             Backend::Tty(tty) => tty.render(niri, output, target_presentation_time),
             Backend::Winit(winit) => winit.render(niri, output),
+            Backend::Headless(headless) => headless.render(niri, output),
         }
     }
 
@@ -74,20 +82,21 @@ impl Backend {
         match self {
             Backend::Tty(_) => CompositorMod::Super,
             Backend::Winit(_) => CompositorMod::Alt,
+            Backend::Headless(_) => CompositorMod::Super,
         }
     }
 
     pub fn change_vt(&mut self, vt: i32) {
         match self {
             Backend::Tty(tty) => tty.change_vt(vt),
-            Backend::Winit(_) => (),
+            Backend::Winit(_) | Backend::Headless(_) => (),
         }
     }
 
     pub fn suspend(&mut self) {
         match self {
             Backend::Tty(tty) => tty.suspend(),
-            Backend::Winit(_) => (),
+            Backend::Winit(_) | Backend::Headless(_) => (),
         }
     }
 
@@ -95,6 +104,7 @@ impl Backend {
         match self {
             Backend::Tty(tty) => tty.toggle_debug_tint(),
             Backend::Winit(winit) => winit.toggle_debug_tint(),
+            Backend::Headless(_) => (),
         }
     }
 
@@ -102,13 +112,14 @@ impl Backend {
         match self {
             Backend::Tty(tty) => tty.import_dmabuf(dmabuf),
             Backend::Winit(winit) => winit.import_dmabuf(dmabuf),
+            Backend::Headless(headless) => headless.import_dmabuf(dmabuf),
         }
     }
 
     pub fn early_import(&mut self, surface: &WlSurface) {
         match self {
             Backend::Tty(tty) => tty.early_import(surface),
-            Backend::Winit(_) => (),
+            Backend::Winit(_) | Backend::Headless(_) => (),
         }
     }
 
@@ -116,6 +127,7 @@ impl Backend {
         match self {
             Backend::Tty(tty) => tty.ipc_outputs(),
             Backend::Winit(winit) => winit.ipc_outputs(),
+            Backend::Headless(headless) => headless.ipc_outputs(),
         }
     }
 
@@ -126,28 +138,28 @@ impl Backend {
     {
         match self {
             Backend::Tty(tty) => tty.primary_gbm_device(),
-            Backend::Winit(_) => None,
+            Backend::Winit(_) | Backend::Headless(_) => None,
         }
     }
 
     pub fn set_monitors_active(&mut self, active: bool) {
         match self {
             Backend::Tty(tty) => tty.set_monitors_active(active),
-            Backend::Winit(_) => (),
+            Backend::Winit(_) | Backend::Headless(_) => (),
         }
     }
 
     pub fn on_output_config_changed(&mut self, niri: &mut Niri) {
         match self {
             Backend::Tty(tty) => tty.on_output_config_changed(niri),
-            Backend::Winit(_) => (),
+            Backend::Winit(_) | Backend::Headless(_) => (),
         }
     }
 
     pub fn on_debug_config_changed(&mut self) {
         match self {
             Backend::Tty(tty) => tty.on_debug_config_changed(),
-            Backend::Winit(_) => (),
+            Backend::Winit(_) | Backend::Headless(_) => (),
         }
     }
 
@@ -166,4 +178,12 @@ impl Backend {
             panic!("backend is not Winit")
         }
     }
+
+    pub fn headless(&mut self) -> &mut Headless {
+        if let Self::Headless(v) = self {
+            v
+        } else {
+            panic!("backend is not Headless")
+        }
+    }
 }
@@ -62,7 +62,7 @@ use crate::niri::{Niri, RedrawState, State};
 use crate::render_helpers::debug::draw_damage;
 use crate::render_helpers::renderer::AsGlesRenderer;
 use crate::render_helpers::{resources, shaders, RenderTarget};
-use crate::utils::{get_monotonic_time, logical_output};
+use crate::utils::{get_monotonic_time, is_laptop_panel, logical_output, OutputSerial};
 
 const SUPPORTED_COLOR_FORMATS: &[Fourcc] = &[Fourcc::Argb8888, Fourcc::Abgr8888];
 
@@ -616,6 +616,12 @@ impl Tty {
             }
         }
 
+        // Plugging or unplugging a monitor with the lid closed turns the laptop panel off or back
+        // on.
+        if niri.is_lid_closed {
+            self.on_output_config_changed(niri);
+        }
+
         self.refresh_ipc_outputs(niri);
     }
 
@@ -704,6 +710,8 @@ impl Tty {
         );
         debug!("connecting connector: {output_name}");
 
+        let has_external_output = self.has_external_output();
+
         let device = self.devices.get_mut(&node).context("missing device")?;
 
         let non_desktop = find_drm_property(&device.drm, connector.handle(), "non-desktop")
@@ -738,6 +746,11 @@ impl Tty {
             return Ok(());
         }
 
+        if is_disabled_by_lid(niri, has_external_output, &output_name) {
+            debug!("output is disabled because the lid is closed");
+            return Ok(());
+        }
+
         for m in connector.modes() {
             trace!("{m:?}");
         }
@@ -857,6 +870,12 @@ impl Tty {
         output
             .user_data()
             .insert_if_missing(|| TtyOutputState { node, crtc });
+        if let Some(serial) = get_edid_serial(&device.drm, connector.handle()) {
+            debug!("monitor serial number: {serial}");
+            output
+                .user_data()
+                .insert_if_missing(|| OutputSerial(serial));
+        }
 
         let mut planes = surface.planes().clone();
 
@@ -1578,6 +1597,33 @@ impl Tty {
         }
     }
 
+    /// Returns whether a connected output other than the laptop panel is enabled in the config.
+    fn has_external_output(&self) -> bool {
+        let config = self.config.borrow();
+        self.devices.values().any(|device| {
+            device.drm_scanner.crtcs().any(|(connector, _)| {
+                if connector.state() != connector::State::Connected {
+                    return false;
+                }
+
+                let output_name = format!(
+                    "{}-{}",
+                    connector.interface().as_str(),
+                    connector.interface_id(),
+                );
+                if is_laptop_panel(&output_name) {
+                    return false;
+                }
+
+                !config
+                    .outputs
+                    .iter()
+                    .find(|o| o.name.eq_ignore_ascii_case(&output_name))
+                    .is_some_and(|o| o.off)
+            })
+        })
+    }
+
     pub fn on_output_config_changed(&mut self, niri: &mut Niri) {
         let _span = tracy_client::span!("Tty::on_output_config_changed");
 
@@ -1591,6 +1637,8 @@ impl Tty {
         let mut to_disconnect = vec![];
         let mut to_connect = vec![];
 
+        let has_external_output = self.has_external_output();
+
         for (&node, device) in &mut self.devices {
             for surface in device.surfaces.values_mut() {
                 let crtc = surface.compositor.crtc();
@@ -1603,7 +1651,7 @@ impl Tty {
                     .find(|o| o.name.eq_ignore_ascii_case(&surface.name))
                     .cloned()
                     .unwrap_or_default();
-                if config.off {
+                if config.off || is_disabled_by_lid(niri, has_external_output, &surface.name) {
                     to_disconnect.push((node, crtc));
                     continue;
                 }
@@ -1734,7 +1782,7 @@ impl Tty {
                     .cloned()
                     .unwrap_or_default();
 
-                if !config.off {
+                if !config.off && !is_disabled_by_lid(niri, has_external_output, &output_name) {
                     to_connect.push((node, connector.clone(), crtc));
                 }
             }
@@ -2201,6 +2249,18 @@ fn pick_mode(
     mode.map(|m| (*m, fallback))
 }
 
+fn is_disabled_by_lid(niri: &Niri, has_external_output: bool, output_name: &str) -> bool {
+    niri.is_lid_closed
+        // With nothing else connected, turning the panel off would leave no output at all.
+        && has_external_output
+        && is_laptop_panel(output_name)
+        && !niri
+            .config
+            .borrow()
+            .debug
+            .keep_laptop_panel_on_when_lid_is_closed
+}
+
 fn truncate_to_nul(mut s: String) -> String {
     if let Some(index) = s.find('\0') {
         s.truncate(index);
@@ -2220,6 +2280,42 @@ fn get_edid_info(device: &DrmDevice, connector: connector::Handle) -> Option<Edi
     }
 }
 
+/// Reads the serial number of the monitor from the connector EDID.
+fn get_edid_serial(device: &DrmDevice, connector: connector::Handle) -> Option<String> {
+    let (_, _, blob) = find_drm_property(device, connector, "EDID")?;
+    let edid = device.get_property_blob(blob).ok()?;
+    parse_edid_serial(&edid)
+}
+
+/// Extracts the serial number from an EDID base block.
+///
+/// Prefers the serial number string descriptor, since the numeric serial in the header is often
+/// left at zero or shared between units.
+fn parse_edid_serial(edid: &[u8]) -> Option<String> {
+    const HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+    if edid.len() < 128 || edid[..8] != HEADER {
+        return None;
+    }
+
+    for offset in [54, 72, 90, 108] {
+        let descriptor = &edid[offset..offset + 18];
+        // Display descriptors start with three zero bytes, and 0xff is the serial number tag.
+        if descriptor[..3] != [0, 0, 0] || descriptor[3] != 0xff {
+            continue;
+        }
+
+        let text = &descriptor[5..];
+        let end = text.iter().position(|&b| b == b'\n').unwrap_or(text.len());
+        let serial = String::from_utf8_lossy(&text[..end]).trim().to_owned();
+        if !serial.is_empty() {
+            return Some(serial);
+        }
+    }
+
+    let serial = u32::from_le_bytes(edid[12..16].try_into().unwrap());
+    (serial != 0).then(|| serial.to_string())
+}
+
 fn set_max_bpc(device: &DrmDevice, connector: connector::Handle, bpc: u64) -> anyhow::Result<u64> {
     let props = device
         .get_properties(connector)
@@ -2342,4 +2438,37 @@ mod tests {
         check("a\0\0\0b", "a");
         check("bb😁\0cc", "bb😁");
     }
+
+    fn edid_with_serial(numeric: u32, text: Option<&str>) -> Vec<u8> {
+        let mut edid = vec![0; 128];
+        edid[..8].copy_from_slice(&[0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00]);
+        edid[12..16].copy_from_slice(&numeric.to_le_bytes());
+
+        // Put the serial number descriptor in the second slot, after a monitor name one.
+        edid[54 + 3] = 0xfc;
+        if let Some(text) = text {
+            let descriptor = &mut edid[72..90];
+            descriptor[3] = 0xff;
+            descriptor[5..].fill(b' ');
+            descriptor[5..5 + text.len()].copy_from_slice(text.as_bytes());
+            descriptor[5 + text.len()] = b'\n';
+        }
+
+        edid
+    }
+
+    #[test]
+    fn parse_edid_serial_works() {
+        let edid = edid_with_serial(1234, Some("ABC123"));
+        assert_eq!(parse_edid_serial(&edid).as_deref(), Some("ABC123"));
+
+        let edid = edid_with_serial(1234, None);
+        assert_eq!(parse_edid_serial(&edid).as_deref(), Some("1234"));
+
+        let edid = edid_with_serial(0, None);
+        assert_eq!(parse_edid_serial(&edid), None);
+
+        assert_eq!(parse_edid_serial(&edid[..64]), None);
+        assert_eq!(parse_edid_serial(&[0; 128]), None);
+    }
 }
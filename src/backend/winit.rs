@@ -7,6 +7,7 @@ use std::time::Duration;
 
 use niri_config::Config;
 use smithay::backend::allocator::dmabuf::Dmabuf;
+use smithay::backend::egl::context::GlAttributes;
 use smithay::backend::renderer::damage::OutputDamageTracker;
 use smithay::backend::renderer::gles::GlesRenderer;
 use smithay::backend::renderer::{DebugFlags, ImportDma, ImportEgl, Renderer};
@@ -35,12 +36,19 @@ impl Winit {
     pub fn new(
         config: Rc<RefCell<Config>>,
         event_loop: LoopHandle<State>,
+        vsync: bool,
     ) -> Result<Self, winit::Error> {
         let builder = Window::default_attributes()
             .with_inner_size(LogicalSize::new(1280.0, 800.0))
             // .with_resizable(false)
             .with_title("niri");
-        let (backend, winit) = winit::init_from_attributes(builder)?;
+        let gl_attributes = GlAttributes {
+            version: (3, 0),
+            profile: None,
+            debug: cfg!(debug_assertions),
+            vsync,
+        };
+        let (backend, winit) = winit::init_from_attributes_with_gl_attr(builder, gl_attributes)?;
 
         let output = Output::new(
             "winit".to_string(),
@@ -235,7 +243,7 @@ impl Winit {
 
         // FIXME: this should wait until a frame callback from the host compositor, but it redraws
         // right away instead.
-        if output_state.unfinished_animations_remain {
+        if output_state.unfinished_animations_remain || niri.benchmark.is_some() {
             self.backend.window().request_redraw();
         }
 
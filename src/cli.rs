@@ -25,6 +25,17 @@ pub struct Cli {
     /// on a TTY as your non-main compositor instance, to avoid messing up the global environment.
     #[arg(long)]
     pub session: bool,
+    /// Render frames back to back in the nested backend and print the timings, then exit.
+    ///
+    /// Vsync is disabled and the clock advances by exactly one 60 Hz frame between frames, so runs
+    /// are reproducible.
+    #[arg(
+        long,
+        value_name = "FRAMES",
+        num_args = 0..=1,
+        default_missing_value = "600"
+    )]
+    pub benchmark: Option<u32>,
     /// Command to run upon compositor startup.
     #[arg(last = true)]
     pub command: Vec<OsString>,
@@ -66,11 +77,33 @@ pub enum Msg {
     FocusedWindow,
     /// Print information about the focused output.
     FocusedOutput,
+    /// Print the focus history of every output.
+    FocusHistory,
+    /// List minimized windows.
+    MinimizedWindows,
+    /// Print the cursor position.
+    CursorPosition,
+    /// Pick a window with the mouse or the keyboard and print information about it.
+    PickWindow,
+    /// Pick an output with the mouse or the keyboard and print its name.
+    PickOutput,
+    /// Print the memory usage of the texture caches.
+    TextureCacheStats,
     /// Perform an action.
     Action {
         #[command(subcommand)]
         action: Action,
     },
+    /// Perform several actions in order, as one batch.
+    ///
+    /// Each argument is one action, written the same way as for `niri msg action`, for example
+    /// `niri msg actions "focus-workspace 2" "move-column-to-first"`.
+    Actions {
+        /// Actions to perform; the arguments within one action are split like in a shell, so
+        /// arguments with spaces can be quoted, as in `"spawn -- alacritty -e 'my script'"`.
+        #[arg(required = true, value_parser = parse_action)]
+        actions: Vec<Action>,
+    },
     /// Change output configuration temporarily.
     ///
     /// The configuration is changed temporarily and not saved into the config file. If the output
@@ -91,3 +124,17 @@ pub enum Msg {
     /// Request an error from the running niri instance.
     RequestError,
 }
+
+#[derive(Parser)]
+#[command(no_binary_name = true)]
+struct ActionArg {
+    #[command(subcommand)]
+    action: Action,
+}
+
+fn parse_action(s: &str) -> Result<Action, String> {
+    let args = shlex::split(s).ok_or_else(|| format!("unterminated quote in action: {s}"))?;
+    ActionArg::try_parse_from(args)
+        .map(|arg| arg.action)
+        .map_err(|err| err.to_string())
+}
@@ -0,0 +1,162 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, ensure, Context};
+use smithay::output::Output;
+use smithay::utils::{Physical, Rectangle};
+
+/// How many captured frames can wait for the encoder before new frames are dropped.
+const MAX_PENDING_FRAMES: usize = 8;
+
+/// Recording of a short clip of an output region.
+///
+/// Frames are piped as raw RGBA into an `ffmpeg` process, which picks the container and codec from
+/// the file extension, so the same code records both webm and GIF clips.
+pub struct ClipRecorder {
+    output: Output,
+    rect: Rectangle<i32, Physical>,
+    started_at: Duration,
+    max_duration: Duration,
+    frame_interval: Duration,
+    frames: mpsc::SyncSender<Vec<u8>>,
+}
+
+impl ClipRecorder {
+    pub fn start(
+        output: Output,
+        rect: Rectangle<i32, Physical>,
+        path: PathBuf,
+        config: &niri_config::ClipRecorder,
+        now: Duration,
+    ) -> anyhow::Result<Self> {
+        // Most video encoders need even frame sizes.
+        let mut rect = rect;
+        rect.size.w &= !1;
+        rect.size.h &= !1;
+        ensure!(!rect.size.is_empty(), "selection is too small");
+
+        let fps = config.fps.max(1);
+
+        if let Some(parent) = path.parent() {
+            if let Err(err) = std::fs::create_dir(parent) {
+                if err.kind() != std::io::ErrorKind::AlreadyExists {
+                    warn!("error creating clip directory: {err:?}");
+                }
+            }
+        }
+
+        let mut child = spawn_ffmpeg(&path, rect, fps).context("error spawning ffmpeg")?;
+        let stdin = child.stdin.take().unwrap();
+
+        let (tx, rx) = mpsc::sync_channel(MAX_PENDING_FRAMES);
+        thread::spawn(move || encode(child, stdin, rx, path));
+
+        Ok(Self {
+            output,
+            rect,
+            started_at: now,
+            max_duration: Duration::from_millis(u64::from(config.max_duration_ms)),
+            frame_interval: Duration::from_secs(1) / u32::from(fps),
+            frames: tx,
+        })
+    }
+
+    pub fn output(&self) -> &Output {
+        &self.output
+    }
+
+    /// Returns the recorded region in output-local physical coordinates.
+    pub fn rect(&self) -> Rectangle<i32, Physical> {
+        self.rect
+    }
+
+    pub fn frame_interval(&self) -> Duration {
+        self.frame_interval
+    }
+
+    pub fn is_finished(&self, now: Duration) -> bool {
+        now.saturating_sub(self.started_at) >= self.max_duration
+    }
+
+    /// Queues a frame for encoding.
+    ///
+    /// If the encoder can't keep up, the frame is dropped. Returns `false` if the encoder has
+    /// stopped, in which case the recording should be stopped too.
+    pub fn push_frame(&self, pixels: Vec<u8>) -> bool {
+        match self.frames.try_send(pixels) {
+            Ok(()) => true,
+            Err(mpsc::TrySendError::Full(_)) => {
+                trace!("clip encoder is behind, dropping frame");
+                true
+            }
+            Err(mpsc::TrySendError::Disconnected(_)) => false,
+        }
+    }
+}
+
+fn spawn_ffmpeg(path: &Path, rect: Rectangle<i32, Physical>, fps: u8) -> anyhow::Result<Child> {
+    let mut command = Command::new("ffmpeg");
+    command
+        .args(["-loglevel", "error", "-y"])
+        .args(["-f", "rawvideo", "-pix_fmt", "rgba"])
+        .arg("-s")
+        .arg(format!("{}x{}", rect.size.w, rect.size.h))
+        .arg("-r")
+        .arg(fps.to_string())
+        .args(["-i", "-"]);
+
+    // Without a generated palette GIFs come out with heavy dithering.
+    let is_gif = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gif"));
+    if is_gif {
+        command.args(["-vf", "split[a][b];[a]palettegen[p];[b][p]paletteuse"]);
+    }
+
+    let child = command
+        .arg(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|err| {
+            if err.kind() == std::io::ErrorKind::NotFound {
+                anyhow!("ffmpeg is not installed or not in $PATH")
+            } else {
+                err.into()
+            }
+        })?;
+    Ok(child)
+}
+
+/// Returns whether an `ffmpeg` binary is available in `$PATH`.
+pub fn is_ffmpeg_available() -> bool {
+    let Some(paths) = std::env::var_os("PATH") else {
+        return false;
+    };
+
+    std::env::split_paths(&paths).any(|dir| dir.join("ffmpeg").is_file())
+}
+
+fn encode(mut child: Child, mut stdin: ChildStdin, frames: mpsc::Receiver<Vec<u8>>, path: PathBuf) {
+    let _span = tracy_client::span!("clip_recorder::encode");
+
+    for frame in frames {
+        if let Err(err) = stdin.write_all(&frame) {
+            warn!("error writing clip frame: {err:?}");
+            break;
+        }
+    }
+
+    // Closing stdin tells ffmpeg that the clip is over.
+    drop(stdin);
+
+    match child.wait() {
+        Ok(status) if status.success() => debug!("saved clip to {path:?}"),
+        Ok(status) => warn!("error encoding clip: ffmpeg exited with {status}"),
+        Err(err) => warn!("error waiting for ffmpeg: {err:?}"),
+    }
+}
@@ -11,7 +11,7 @@ use smithay::backend::allocator::Fourcc;
 use smithay::backend::renderer::element::memory::MemoryRenderBuffer;
 use smithay::input::pointer::{CursorIcon, CursorImageAttributes, CursorImageStatus};
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
-use smithay::utils::{IsAlive, Logical, Physical, Point, Transform};
+use smithay::utils::{IsAlive, Logical, Physical, Point, Size, Transform};
 use smithay::wayland::compositor::with_states;
 use xcursor::parser::{parse_xcursor, Image};
 use xcursor::CursorTheme;
@@ -19,6 +19,12 @@ use xcursor::CursorTheme;
 /// Some default looking `left_ptr` icon.
 static FALLBACK_CURSOR_DATA: &[u8] = include_bytes!("../resources/cursor.rgba");
 
+/// Cursor images are loaded for the output scale rounded to a multiple of `1 / SCALE_BUCKETS`.
+///
+/// This way, fractional scales get a crisp cursor, without loading a new image for every
+/// slightly different scale.
+const SCALE_BUCKETS: f64 = 4.;
+
 type XCursorCache = HashMap<(CursorIcon, i32), Option<Rc<XCursor>>>;
 
 pub struct CursorManager {
@@ -60,7 +66,7 @@ impl CursorManager {
     }
 
     /// Get the current rendering cursor.
-    pub fn get_render_cursor(&self, scale: i32) -> RenderCursor {
+    pub fn get_render_cursor(&self, scale: f64) -> RenderCursor {
         match self.current_cursor.clone() {
             CursorImageStatus::Hidden => RenderCursor::Hidden,
             CursorImageStatus::Surface(surface) => {
@@ -76,22 +82,24 @@ impl CursorManager {
 
                 RenderCursor::Surface { hotspot, surface }
             }
-            CursorImageStatus::Named(icon) => self
-                .get_cursor_with_name(icon, scale)
-                .map(|cursor| RenderCursor::Named {
-                    icon,
-                    scale,
-                    cursor,
-                })
-                .unwrap_or_else(|| RenderCursor::Named {
-                    icon: Default::default(),
-                    scale,
-                    cursor: self.get_default_cursor(scale),
-                }),
+            CursorImageStatus::Named(icon) => {
+                let scale = bucketed_scale(scale);
+                self.get_cursor_with_name(icon, scale)
+                    .map(|cursor| RenderCursor::Named {
+                        icon,
+                        scale,
+                        cursor,
+                    })
+                    .unwrap_or_else(|| RenderCursor::Named {
+                        icon: Default::default(),
+                        scale,
+                        cursor: self.get_default_cursor(scale),
+                    })
+            }
         }
     }
 
-    pub fn is_current_cursor_animated(&self, scale: i32) -> bool {
+    pub fn is_current_cursor_animated(&self, scale: f64) -> bool {
         match &self.current_cursor {
             CursorImageStatus::Hidden => false,
             CursorImageStatus::Surface(_) => false,
@@ -103,12 +111,15 @@ impl CursorManager {
     }
 
     /// Get named cursor for the given `icon` and `scale`.
-    pub fn get_cursor_with_name(&self, icon: CursorIcon, scale: i32) -> Option<Rc<XCursor>> {
+    ///
+    /// The scale is rounded to the nearest bucket.
+    pub fn get_cursor_with_name(&self, icon: CursorIcon, scale: f64) -> Option<Rc<XCursor>> {
         self.named_cursor_cache
             .borrow_mut()
-            .entry((icon, scale))
-            .or_insert_with_key(|(icon, scale)| {
-                let size = self.size as i32 * scale;
+            .entry((icon, scale_bucket(scale)))
+            .or_insert_with_key(|(icon, bucket)| {
+                let size = (f64::from(self.size) * f64::from(*bucket) / SCALE_BUCKETS).round();
+                let size = size as i32;
                 let mut cursor = Self::load_xcursor(&self.theme, icon.name(), size);
 
                 // Check alternative names to account for non-compliant themes.
@@ -136,7 +147,7 @@ impl CursorManager {
     }
 
     /// Get default cursor.
-    pub fn get_default_cursor(&self, scale: i32) -> Rc<XCursor> {
+    pub fn get_default_cursor(&self, scale: f64) -> Rc<XCursor> {
         // The default cursor always has a fallback.
         self.get_cursor_with_name(CursorIcon::Default, scale)
             .unwrap()
@@ -218,11 +229,21 @@ pub enum RenderCursor {
     },
     Named {
         icon: CursorIcon,
-        scale: i32,
+        /// Scale of the cursor images, rounded to a bucket.
+        scale: f64,
         cursor: Rc<XCursor>,
     },
 }
 
+fn scale_bucket(scale: f64) -> i32 {
+    (scale * SCALE_BUCKETS).round().max(1.) as i32
+}
+
+/// Rounds the scale to the scale that cursor images are loaded at.
+pub fn bucketed_scale(scale: f64) -> f64 {
+    f64::from(scale_bucket(scale)) / SCALE_BUCKETS
+}
+
 type TextureCache = HashMap<(CursorIcon, i32), Vec<MemoryRenderBuffer>>;
 
 #[derive(Default)]
@@ -235,16 +256,20 @@ impl CursorTextureCache {
         self.cache.get_mut().clear();
     }
 
+    /// Returns the texture for the cursor frame.
+    ///
+    /// The texture is at buffer scale 1, so it has to be rendered at its logical size, as
+    /// computed by [`XCursor::logical_size()`].
     pub fn get(
         &self,
         icon: CursorIcon,
-        scale: i32,
+        scale: f64,
         cursor: &XCursor,
         idx: usize,
     ) -> MemoryRenderBuffer {
         self.cache
             .borrow_mut()
-            .entry((icon, scale))
+            .entry((icon, scale_bucket(scale)))
             .or_insert_with(|| {
                 cursor
                     .frames()
@@ -254,7 +279,7 @@ impl CursorTextureCache {
                             &frame.pixels_rgba,
                             Fourcc::Argb8888,
                             (frame.width as i32, frame.height as i32),
-                            scale,
+                            1,
                             Transform::Normal,
                             None,
                         )
@@ -313,4 +338,12 @@ impl XCursor {
     pub fn hotspot(image: &Image) -> Point<i32, Physical> {
         (image.xhot as i32, image.yhot as i32).into()
     }
+
+    /// Get the logical size of the given `image` loaded at the given bucketed `scale`.
+    pub fn logical_size(image: &Image, scale: f64) -> Size<i32, Logical> {
+        Size::<i32, Physical>::from((image.width as i32, image.height as i32))
+            .to_f64()
+            .to_logical(scale)
+            .to_i32_round()
+    }
 }
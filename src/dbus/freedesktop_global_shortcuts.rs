@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+use zbus::{dbus_interface, ObjectServer, SignalContext};
+
+const RESPONSE_SUCCESS: u32 = 0;
+const RESPONSE_CANCELLED: u32 = 1;
+const RESPONSE_OTHER: u32 = 2;
+
+/// Backend for the xdg-desktop-portal GlobalShortcuts interface.
+pub struct GlobalShortcuts {
+    to_niri: calloop::channel::Sender<GlobalShortcutsToNiri>,
+    /// App IDs of the open sessions.
+    sessions: Arc<Mutex<HashMap<OwnedObjectPath, String>>>,
+}
+
+struct Session {
+    handle: OwnedObjectPath,
+    to_niri: calloop::channel::Sender<GlobalShortcutsToNiri>,
+    sessions: Arc<Mutex<HashMap<OwnedObjectPath, String>>>,
+}
+
+/// Shortcut in the D-Bus representation: an ID and a dictionary of properties.
+pub type DBusShortcut = (String, HashMap<String, OwnedValue>);
+
+#[derive(Debug, Clone)]
+pub struct Shortcut {
+    pub id: String,
+    /// Human-readable description from the app.
+    pub description: String,
+    /// Trigger in the portal shortcut format, for example `CTRL+SHIFT+a`.
+    pub trigger: Option<String>,
+}
+
+pub enum GlobalShortcutsToNiri {
+    BindShortcuts {
+        session_handle: OwnedObjectPath,
+        app_id: String,
+        shortcuts: Vec<Shortcut>,
+        /// Receives the bound shortcuts, or `None` if the user denied the request.
+        reply: async_channel::Sender<Option<Vec<Shortcut>>>,
+    },
+    ListShortcuts {
+        session_handle: OwnedObjectPath,
+        reply: async_channel::Sender<Vec<Shortcut>>,
+    },
+    CloseSession {
+        session_handle: OwnedObjectPath,
+    },
+}
+
+#[dbus_interface(name = "org.freedesktop.impl.portal.GlobalShortcuts")]
+impl GlobalShortcuts {
+    async fn create_session(
+        &self,
+        #[zbus(object_server)] server: &ObjectServer,
+        _handle: ObjectPath<'_>,
+        session_handle: ObjectPath<'_>,
+        app_id: &str,
+        _options: HashMap<&str, Value<'_>>,
+    ) -> (u32, HashMap<String, OwnedValue>) {
+        let session_handle = OwnedObjectPath::from(session_handle);
+
+        let session = Session {
+            handle: session_handle.clone(),
+            to_niri: self.to_niri.clone(),
+            sessions: self.sessions.clone(),
+        };
+        match server.at(&session_handle, session).await {
+            Ok(true) => (),
+            Ok(false) => {
+                warn!("global shortcuts session path already exists");
+                return (RESPONSE_OTHER, HashMap::new());
+            }
+            Err(err) => {
+                warn!("error creating global shortcuts session object: {err:?}");
+                return (RESPONSE_OTHER, HashMap::new());
+            }
+        }
+
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(session_handle, app_id.to_owned());
+
+        (RESPONSE_SUCCESS, HashMap::new())
+    }
+
+    async fn bind_shortcuts(
+        &self,
+        _handle: ObjectPath<'_>,
+        session_handle: ObjectPath<'_>,
+        shortcuts: Vec<DBusShortcut>,
+        _parent_window: &str,
+        _options: HashMap<&str, Value<'_>>,
+    ) -> (u32, HashMap<String, OwnedValue>) {
+        let session_handle = OwnedObjectPath::from(session_handle);
+        let Some(app_id) = self.sessions.lock().unwrap().get(&session_handle).cloned() else {
+            warn!("BindShortcuts called for an unknown session");
+            return (RESPONSE_OTHER, HashMap::new());
+        };
+
+        let shortcuts = shortcuts.into_iter().map(Shortcut::from_dbus).collect();
+
+        let (reply, from_niri) = async_channel::bounded(1);
+        let msg = GlobalShortcutsToNiri::BindShortcuts {
+            session_handle,
+            app_id,
+            shortcuts,
+            reply,
+        };
+        if let Err(err) = self.to_niri.send(msg) {
+            warn!("error sending message to niri: {err:?}");
+            return (RESPONSE_OTHER, HashMap::new());
+        }
+
+        match from_niri.recv().await {
+            Ok(Some(bound)) => (RESPONSE_SUCCESS, shortcuts_results(&bound)),
+            Ok(None) => (RESPONSE_CANCELLED, HashMap::new()),
+            Err(err) => {
+                warn!("error receiving message from niri: {err:?}");
+                (RESPONSE_OTHER, HashMap::new())
+            }
+        }
+    }
+
+    async fn list_shortcuts(
+        &self,
+        _handle: ObjectPath<'_>,
+        session_handle: ObjectPath<'_>,
+    ) -> (u32, HashMap<String, OwnedValue>) {
+        let session_handle = OwnedObjectPath::from(session_handle);
+
+        let (reply, from_niri) = async_channel::bounded(1);
+        let msg = GlobalShortcutsToNiri::ListShortcuts {
+            session_handle,
+            reply,
+        };
+        if let Err(err) = self.to_niri.send(msg) {
+            warn!("error sending message to niri: {err:?}");
+            return (RESPONSE_OTHER, HashMap::new());
+        }
+
+        match from_niri.recv().await {
+            Ok(bound) => (RESPONSE_SUCCESS, shortcuts_results(&bound)),
+            Err(err) => {
+                warn!("error receiving message from niri: {err:?}");
+                (RESPONSE_OTHER, HashMap::new())
+            }
+        }
+    }
+
+    #[dbus_interface(signal)]
+    pub async fn activated(
+        ctxt: &SignalContext<'_>,
+        session_handle: ObjectPath<'_>,
+        shortcut_id: &str,
+        timestamp: u64,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub async fn deactivated(
+        ctxt: &SignalContext<'_>,
+        session_handle: ObjectPath<'_>,
+        shortcut_id: &str,
+        timestamp: u64,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<()>;
+
+    #[dbus_interface(property)]
+    async fn version(&self) -> u32 {
+        1
+    }
+}
+
+#[dbus_interface(name = "org.freedesktop.impl.portal.Session")]
+impl Session {
+    async fn close(
+        &self,
+        #[zbus(object_server)] server: &ObjectServer,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) {
+        self.sessions.lock().unwrap().remove(&self.handle);
+
+        let msg = GlobalShortcutsToNiri::CloseSession {
+            session_handle: self.handle.clone(),
+        };
+        if let Err(err) = self.to_niri.send(msg) {
+            warn!("error sending CloseSession to niri: {err:?}");
+        }
+
+        if let Err(err) = server.remove::<Session, _>(ctxt.path()).await {
+            warn!("error removing global shortcuts session object: {err:?}");
+        }
+    }
+
+    #[dbus_interface(property)]
+    async fn version(&self) -> u32 {
+        1
+    }
+}
+
+impl GlobalShortcuts {
+    pub fn new(to_niri: calloop::channel::Sender<GlobalShortcutsToNiri>) -> Self {
+        Self {
+            to_niri,
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl Shortcut {
+    fn from_dbus((id, props): DBusShortcut) -> Self {
+        let string = |name| {
+            props
+                .get(name)
+                .and_then(|value| value.downcast_ref::<str>())
+                .map(str::to_owned)
+        };
+
+        Self {
+            description: string("description").unwrap_or_default(),
+            trigger: string("preferred_trigger"),
+            id,
+        }
+    }
+
+    fn to_dbus(&self) -> DBusShortcut {
+        let mut props = HashMap::new();
+        props.insert(
+            String::from("description"),
+            OwnedValue::from(Value::from(self.description.as_str())),
+        );
+        if let Some(trigger) = &self.trigger {
+            props.insert(
+                String::from("trigger_description"),
+                OwnedValue::from(Value::from(trigger.as_str())),
+            );
+        }
+        (self.id.clone(), props)
+    }
+}
+
+fn shortcuts_results(shortcuts: &[Shortcut]) -> HashMap<String, OwnedValue> {
+    let shortcuts: Vec<DBusShortcut> = shortcuts.iter().map(Shortcut::to_dbus).collect();
+    HashMap::from([(
+        String::from("shortcuts"),
+        OwnedValue::from(Value::from(shortcuts)),
+    )])
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use zbus::fdo::RequestNameFlags;
+use zbus::zvariant::Value;
+use zbus::{dbus_interface, SignalContext};
+
+use super::Start;
+use crate::utils::version;
+
+pub const OBJECT_PATH: &str = "/org/freedesktop/Notifications";
+
+/// Minimal notification server.
+///
+/// Supports only the summary and a plain text body; actions, icons and sounds are ignored.
+pub struct Notifications {
+    to_niri: calloop::channel::Sender<NotificationsToNiri>,
+    /// Id of the last notification, ids start from 1.
+    last_id: u32,
+}
+
+pub enum NotificationsToNiri {
+    Notify(Notification),
+    Close { id: u32 },
+}
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    /// Id of this notification, or of the notification it replaces.
+    pub id: u32,
+    pub app_name: String,
+    pub summary: String,
+    pub body: String,
+    pub urgency: Urgency,
+    /// Timeout as requested by the app: -1 for the default, 0 for never, otherwise milliseconds.
+    pub expire_timeout: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Urgency {
+    Low,
+    Normal,
+    Critical,
+}
+
+/// Reason passed in the `NotificationClosed` signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseReason {
+    Expired = 1,
+    Dismissed = 2,
+    ClosedByCall = 3,
+}
+
+#[dbus_interface(name = "org.freedesktop.Notifications")]
+impl Notifications {
+    async fn get_capabilities(&self) -> Vec<&str> {
+        vec!["body"]
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn notify(
+        &mut self,
+        app_name: String,
+        replaces_id: u32,
+        _app_icon: &str,
+        summary: String,
+        body: String,
+        _actions: Vec<&str>,
+        hints: HashMap<&str, Value<'_>>,
+        expire_timeout: i32,
+    ) -> u32 {
+        let id = if replaces_id != 0 {
+            replaces_id
+        } else {
+            self.last_id = self.last_id.checked_add(1).unwrap_or(1);
+            self.last_id
+        };
+
+        let urgency = match hints.get("urgency") {
+            Some(Value::U8(0)) => Urgency::Low,
+            Some(Value::U8(2)) => Urgency::Critical,
+            _ => Urgency::Normal,
+        };
+
+        let notification = Notification {
+            id,
+            app_name,
+            summary,
+            body,
+            urgency,
+            expire_timeout,
+        };
+        if let Err(err) = self.to_niri.send(NotificationsToNiri::Notify(notification)) {
+            warn!("error sending message to niri: {err:?}");
+        }
+
+        id
+    }
+
+    async fn close_notification(&self, id: u32) {
+        if let Err(err) = self.to_niri.send(NotificationsToNiri::Close { id }) {
+            warn!("error sending message to niri: {err:?}");
+        }
+    }
+
+    async fn get_server_information(&self) -> (String, String, String, String) {
+        (
+            String::from("niri"),
+            String::from("niri"),
+            version(),
+            String::from("1.2"),
+        )
+    }
+
+    #[dbus_interface(signal)]
+    pub async fn notification_closed(
+        ctxt: &SignalContext<'_>,
+        id: u32,
+        reason: u32,
+    ) -> zbus::Result<()>;
+}
+
+impl Notifications {
+    pub fn new(to_niri: calloop::channel::Sender<NotificationsToNiri>) -> Self {
+        Self {
+            to_niri,
+            last_id: 0,
+        }
+    }
+}
+
+impl Start for Notifications {
+    fn start(self) -> anyhow::Result<zbus::blocking::Connection> {
+        let conn = zbus::blocking::Connection::session()?;
+        let flags = RequestNameFlags::AllowReplacement
+            | RequestNameFlags::ReplaceExisting
+            | RequestNameFlags::DoNotQueue;
+
+        conn.object_server().at(OBJECT_PATH, self)?;
+        conn.request_name_with_flags("org.freedesktop.Notifications", flags)?;
+
+        Ok(conn)
+    }
+}
@@ -0,0 +1,30 @@
+use zbus::fdo::RequestNameFlags;
+
+use super::freedesktop_global_shortcuts::GlobalShortcuts;
+use super::freedesktop_screenshot::Screenshot;
+use super::freedesktop_settings::Settings;
+
+pub const OBJECT_PATH: &str = "/org/freedesktop/portal/desktop";
+
+/// Starts the xdg-desktop-portal backend.
+///
+/// All portal interfaces live on the same object under the same bus name, so they are served
+/// from a single connection.
+pub fn start(
+    global_shortcuts: GlobalShortcuts,
+    settings: Settings,
+    screenshot: Screenshot,
+) -> anyhow::Result<zbus::blocking::Connection> {
+    let conn = zbus::blocking::Connection::session()?;
+    let flags = RequestNameFlags::AllowReplacement
+        | RequestNameFlags::ReplaceExisting
+        | RequestNameFlags::DoNotQueue;
+
+    let server = conn.object_server();
+    server.at(OBJECT_PATH, global_shortcuts)?;
+    server.at(OBJECT_PATH, settings)?;
+    server.at(OBJECT_PATH, screenshot)?;
+    conn.request_name_with_flags("org.freedesktop.impl.portal.desktop.niri", flags)?;
+
+    Ok(conn)
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use zbus::dbus_interface;
+use zbus::zvariant::{ObjectPath, OwnedValue, Structure, Value};
+
+const RESPONSE_SUCCESS: u32 = 0;
+const RESPONSE_CANCELLED: u32 = 1;
+const RESPONSE_OTHER: u32 = 2;
+
+/// Backend for the xdg-desktop-portal Screenshot interface.
+pub struct Screenshot {
+    to_niri: calloop::channel::Sender<PortalScreenshotToNiri>,
+}
+
+pub enum PortalScreenshotToNiri {
+    Screenshot {
+        reply: async_channel::Sender<Option<PathBuf>>,
+    },
+    PickColor {
+        reply: async_channel::Sender<Option<[f64; 3]>>,
+    },
+}
+
+/// Portal request waiting for the user to confirm the screenshot UI.
+///
+/// Dropping it makes the request fail.
+pub enum PortalScreenshotReply {
+    Screenshot(async_channel::Sender<Option<PathBuf>>),
+    PickColor(async_channel::Sender<Option<[f64; 3]>>),
+}
+
+#[dbus_interface(name = "org.freedesktop.impl.portal.Screenshot")]
+impl Screenshot {
+    async fn screenshot(
+        &self,
+        _handle: ObjectPath<'_>,
+        _app_id: &str,
+        _parent_window: &str,
+        _options: HashMap<&str, Value<'_>>,
+    ) -> (u32, HashMap<String, OwnedValue>) {
+        // Non-interactive requests also go through the screenshot UI, so that the user consents
+        // to every capture.
+        let (reply, from_niri) = async_channel::bounded(1);
+        let msg = PortalScreenshotToNiri::Screenshot { reply };
+        if let Err(err) = self.to_niri.send(msg) {
+            warn!("error sending message to niri: {err:?}");
+            return (RESPONSE_OTHER, HashMap::new());
+        }
+
+        let path = match from_niri.recv().await {
+            Ok(Some(path)) => path,
+            Ok(None) | Err(_) => return (RESPONSE_CANCELLED, HashMap::new()),
+        };
+
+        let uri = match url::Url::from_file_path(&path) {
+            Ok(uri) => uri,
+            Err(()) => {
+                warn!("error converting screenshot path to URI: {path:?}");
+                return (RESPONSE_OTHER, HashMap::new());
+            }
+        };
+
+        let results = HashMap::from([(
+            String::from("uri"),
+            OwnedValue::from(Value::from(uri.as_str())),
+        )]);
+        (RESPONSE_SUCCESS, results)
+    }
+
+    async fn pick_color(
+        &self,
+        _handle: ObjectPath<'_>,
+        _app_id: &str,
+        _parent_window: &str,
+        _options: HashMap<&str, Value<'_>>,
+    ) -> (u32, HashMap<String, OwnedValue>) {
+        let (reply, from_niri) = async_channel::bounded(1);
+        let msg = PortalScreenshotToNiri::PickColor { reply };
+        if let Err(err) = self.to_niri.send(msg) {
+            warn!("error sending message to niri: {err:?}");
+            return (RESPONSE_OTHER, HashMap::new());
+        }
+
+        let [r, g, b] = match from_niri.recv().await {
+            Ok(Some(color)) => color,
+            Ok(None) | Err(_) => return (RESPONSE_CANCELLED, HashMap::new()),
+        };
+
+        let color = Value::from(Structure::from((r, g, b)));
+        let results = HashMap::from([(String::from("color"), OwnedValue::from(color))]);
+        (RESPONSE_SUCCESS, results)
+    }
+
+    #[dbus_interface(property)]
+    async fn version(&self) -> u32 {
+        2
+    }
+}
+
+impl Screenshot {
+    pub fn new(to_niri: calloop::channel::Sender<PortalScreenshotToNiri>) -> Self {
+        Self { to_niri }
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::thread;
+
+use anyhow::Context;
+use niri_config::{Appearance, ColorScheme};
+use zbus::zvariant::{OwnedValue, Structure, Value};
+use zbus::{dbus_interface, DBusError, SignalContext};
+
+const APPEARANCE_NAMESPACE: &str = "org.freedesktop.appearance";
+const INTERFACE_NAMESPACE: &str = "org.gnome.desktop.interface";
+
+/// Backend for the xdg-desktop-portal Settings interface.
+///
+/// Serves the `org.freedesktop.appearance` namespace from the `appearance` config section, and
+/// `enable-animations` in `org.gnome.desktop.interface` from the reduced motion state, which GTK
+/// and other toolkits read to turn off their own animations.
+pub struct Settings {
+    appearance: Appearance,
+    reduced_motion: bool,
+}
+
+#[derive(Debug, DBusError)]
+#[dbus_error(prefix = "org.freedesktop.portal.Error")]
+enum PortalError {
+    #[dbus_error(zbus_error)]
+    ZBus(zbus::Error),
+    NotFound(String),
+}
+
+#[dbus_interface(name = "org.freedesktop.impl.portal.Settings")]
+impl Settings {
+    async fn read_all(
+        &self,
+        namespaces: Vec<String>,
+    ) -> HashMap<String, HashMap<String, OwnedValue>> {
+        let mut rv = HashMap::new();
+
+        for (namespace, values) in self.namespaces() {
+            if namespaces.is_empty()
+                || namespaces
+                    .iter()
+                    .any(|pattern| matches_namespace(pattern, namespace))
+            {
+                rv.insert(String::from(namespace), values.into_iter().collect());
+            }
+        }
+
+        rv
+    }
+
+    async fn read(&self, namespace: &str, key: &str) -> Result<OwnedValue, PortalError> {
+        let Some((_, values)) = self
+            .namespaces()
+            .into_iter()
+            .find(|(ns, _)| *ns == namespace)
+        else {
+            return Err(PortalError::NotFound(format!(
+                "unknown namespace: {namespace}"
+            )));
+        };
+
+        values
+            .into_iter()
+            .find_map(|(k, value)| (k == key).then_some(value))
+            .ok_or_else(|| PortalError::NotFound(format!("unknown key: {key}")))
+    }
+
+    #[dbus_interface(signal)]
+    async fn setting_changed(
+        ctxt: &SignalContext<'_>,
+        namespace: &str,
+        key: &str,
+        value: OwnedValue,
+    ) -> zbus::Result<()>;
+
+    #[dbus_interface(property)]
+    async fn version(&self) -> u32 {
+        1
+    }
+}
+
+impl Settings {
+    pub fn new(appearance: Appearance, reduced_motion: bool) -> Self {
+        Self {
+            appearance,
+            reduced_motion,
+        }
+    }
+
+    fn namespaces(&self) -> [(&'static str, Vec<(&'static str, OwnedValue)>); 2] {
+        [
+            (APPEARANCE_NAMESPACE, values(&self.appearance)),
+            (
+                INTERFACE_NAMESPACE,
+                vec![("enable-animations", OwnedValue::from(!self.reduced_motion))],
+            ),
+        ]
+    }
+
+    /// Updates the appearance and notifies the apps about the changed values.
+    pub async fn set_appearance(
+        &mut self,
+        ctxt: &SignalContext<'_>,
+        appearance: Appearance,
+    ) -> zbus::Result<()> {
+        let old = values(&self.appearance);
+        self.appearance = appearance;
+
+        for (key, value) in values(&self.appearance) {
+            if old.iter().any(|(k, v)| *k == key && *v == value) {
+                continue;
+            }
+
+            Self::setting_changed(ctxt, APPEARANCE_NAMESPACE, key, value).await?;
+        }
+
+        // An unset accent color is signalled with out-of-range values.
+        if appearance.accent_color.is_none() && old.iter().any(|(k, _)| *k == "accent-color") {
+            let unset = rgb_value(-1., -1., -1.);
+            Self::setting_changed(ctxt, APPEARANCE_NAMESPACE, "accent-color", unset).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Updates the reduced motion state and notifies the apps if it changed.
+    pub async fn set_reduced_motion(
+        &mut self,
+        ctxt: &SignalContext<'_>,
+        reduced_motion: bool,
+    ) -> zbus::Result<()> {
+        if self.reduced_motion == reduced_motion {
+            return Ok(());
+        }
+
+        self.reduced_motion = reduced_motion;
+        let value = OwnedValue::from(!reduced_motion);
+        Self::setting_changed(ctxt, INTERFACE_NAMESPACE, "enable-animations", value).await
+    }
+}
+
+/// Reads `enable-animations` through the xdg-desktop-portal and sends it and its changes to niri.
+///
+/// When niri is the Settings backend this reads back the reduced motion state, so nothing changes.
+/// With another backend, this follows the desktop-wide setting, e.g. from GNOME Settings.
+pub fn watch_enable_animations(to_niri: calloop::channel::Sender<bool>) {
+    let res = thread::Builder::new()
+        .name("enable-animations Watcher".to_owned())
+        .spawn(move || {
+            if let Err(err) = watch_enable_animations_inner(&to_niri) {
+                debug!("error watching enable-animations: {err:?}");
+            }
+        });
+
+    if let Err(err) = res {
+        warn!("error spawning a thread to watch enable-animations: {err:?}");
+    }
+}
+
+fn watch_enable_animations_inner(to_niri: &calloop::channel::Sender<bool>) -> anyhow::Result<()> {
+    let conn = zbus::blocking::Connection::session().context("error connecting to session bus")?;
+    let proxy = zbus::blocking::Proxy::new(
+        &conn,
+        "org.freedesktop.portal.Desktop",
+        "/org/freedesktop/portal/desktop",
+        "org.freedesktop.portal.Settings",
+    )
+    .context("error creating a Proxy")?;
+
+    // Subscribe before reading to not miss a change in between.
+    let signals = proxy
+        .receive_signal("SettingChanged")
+        .context("error creating a signal iterator")?;
+
+    let value: OwnedValue = proxy
+        .call("Read", &(INTERFACE_NAMESPACE, "enable-animations"))
+        .context("error calling Read")?;
+    if let Some(enabled) = to_bool(&value) {
+        if to_niri.send(enabled).is_err() {
+            return Ok(());
+        }
+    }
+
+    for message in signals {
+        let (namespace, key, value): (&str, &str, OwnedValue) =
+            message.body().context("error parsing signal")?;
+        if namespace != INTERFACE_NAMESPACE || key != "enable-animations" {
+            continue;
+        }
+
+        if let Some(enabled) = to_bool(&value) {
+            if to_niri.send(enabled).is_err() {
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Unpacks a boolean, which `Read` returns wrapped in an extra variant.
+fn to_bool(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(value) => Some(*value),
+        Value::Value(value) => to_bool(value),
+        _ => None,
+    }
+}
+
+/// Checks whether a namespace pattern from `ReadAll` matches the namespace.
+///
+/// Patterns may end with `*` to match any namespace with that prefix.
+fn matches_namespace(pattern: &str, namespace: &str) -> bool {
+    if pattern.is_empty() {
+        return true;
+    }
+
+    match pattern.strip_suffix('*') {
+        Some(prefix) => namespace.starts_with(prefix),
+        None => pattern == namespace,
+    }
+}
+
+fn values(appearance: &Appearance) -> Vec<(&'static str, OwnedValue)> {
+    let color_scheme: u32 = match appearance.color_scheme {
+        ColorScheme::NoPreference => 0,
+        ColorScheme::PreferDark => 1,
+        ColorScheme::PreferLight => 2,
+    };
+
+    let mut rv = vec![("color-scheme", OwnedValue::from(color_scheme))];
+
+    if let Some(color) = appearance.accent_color {
+        let [r, g, b] = [color.r, color.g, color.b].map(|x| f64::from(x) / 255.);
+        rv.push(("accent-color", rgb_value(r, g, b)));
+    }
+
+    rv
+}
+
+fn rgb_value(r: f64, g: f64, b: f64) -> OwnedValue {
+    OwnedValue::from(Value::from(Structure::from((r, g, b))))
+}
@@ -1,23 +1,35 @@
+use portable_atomic::Ordering;
 use zbus::blocking::Connection;
 use zbus::Interface;
 
+use crate::animation;
 use crate::niri::State;
 
+pub mod freedesktop_global_shortcuts;
+pub mod freedesktop_notifications;
+pub mod freedesktop_portal;
 pub mod freedesktop_screensaver;
+pub mod freedesktop_screenshot;
+pub mod freedesktop_settings;
 pub mod gnome_shell_introspect;
 pub mod gnome_shell_screenshot;
 pub mod mutter_display_config;
 pub mod mutter_service_channel;
+pub mod niri_compositor;
 
 #[cfg(feature = "xdp-gnome-screencast")]
 pub mod mutter_screen_cast;
 #[cfg(feature = "xdp-gnome-screencast")]
 use mutter_screen_cast::ScreenCast;
 
+use self::freedesktop_global_shortcuts::GlobalShortcuts;
+use self::freedesktop_notifications::Notifications;
 use self::freedesktop_screensaver::ScreenSaver;
+use self::freedesktop_settings::Settings;
 use self::gnome_shell_introspect::Introspect;
 use self::mutter_display_config::DisplayConfig;
 use self::mutter_service_channel::ServiceChannel;
+use self::niri_compositor::Compositor;
 
 trait Start: Interface {
     fn start(self) -> anyhow::Result<zbus::blocking::Connection>;
@@ -30,6 +42,9 @@ pub struct DBusServers {
     pub conn_screen_saver: Option<Connection>,
     pub conn_screen_shot: Option<Connection>,
     pub conn_introspect: Option<Connection>,
+    pub conn_portal: Option<Connection>,
+    pub conn_compositor: Option<Connection>,
+    pub conn_notifications: Option<Connection>,
     #[cfg(feature = "xdp-gnome-screencast")]
     pub conn_screen_cast: Option<Connection>,
 }
@@ -82,6 +97,75 @@ impl DBusServers {
             let introspect = Introspect::new(to_niri, from_niri);
             dbus.conn_introspect = try_start(introspect);
 
+            let (to_niri, from_global_shortcuts) = calloop::channel::channel();
+            niri.event_loop
+                .insert_source(from_global_shortcuts, move |event, _, state| match event {
+                    calloop::channel::Event::Msg(msg) => state.on_global_shortcuts_msg(msg),
+                    calloop::channel::Event::Closed => (),
+                })
+                .unwrap();
+            let global_shortcuts = GlobalShortcuts::new(to_niri);
+
+            let reduced_motion = animation::REDUCED_MOTION.load(Ordering::Relaxed);
+            let settings = Settings::new(config.appearance, reduced_motion);
+
+            let (to_niri, from_enable_animations) = calloop::channel::channel();
+            niri.event_loop
+                .insert_source(from_enable_animations, move |event, _, state| match event {
+                    calloop::channel::Event::Msg(enabled) => {
+                        state.niri.on_enable_animations_changed(enabled)
+                    }
+                    calloop::channel::Event::Closed => (),
+                })
+                .unwrap();
+            freedesktop_settings::watch_enable_animations(to_niri);
+
+            let (to_niri, from_portal_screenshot) = calloop::channel::channel();
+            niri.event_loop
+                .insert_source(from_portal_screenshot, move |event, _, state| match event {
+                    calloop::channel::Event::Msg(msg) => state.on_portal_screenshot_msg(msg),
+                    calloop::channel::Event::Closed => (),
+                })
+                .unwrap();
+            let screenshot = freedesktop_screenshot::Screenshot::new(to_niri);
+
+            match freedesktop_portal::start(global_shortcuts, settings, screenshot) {
+                Ok(conn) => {
+                    niri.global_shortcuts.set_connection(conn.clone());
+                    dbus.conn_portal = Some(conn);
+                }
+                Err(err) => warn!("error starting the portal backend: {err:?}"),
+            }
+
+            let (to_niri, from_compositor) = calloop::channel::channel();
+            niri.event_loop
+                .insert_source(from_compositor, move |event, _, state| match event {
+                    calloop::channel::Event::Msg(msg) => state.on_compositor_msg(msg),
+                    calloop::channel::Event::Closed => (),
+                })
+                .unwrap();
+            let compositor = Compositor::new(
+                to_niri,
+                niri.compositor_state.clone(),
+                backend.ipc_outputs(),
+            );
+            dbus.conn_compositor = try_start(compositor);
+
+            if config.notifications.on {
+                let (to_niri, from_notifications) = calloop::channel::channel();
+                niri.event_loop
+                    .insert_source(from_notifications, move |event, _, state| match event {
+                        calloop::channel::Event::Msg(msg) => state.on_notifications_msg(msg),
+                        calloop::channel::Event::Closed => (),
+                    })
+                    .unwrap();
+                let notifications = Notifications::new(to_niri);
+                dbus.conn_notifications = try_start(notifications);
+                if let Some(conn) = &dbus.conn_notifications {
+                    niri.notifier.set_connection(conn.clone());
+                }
+            }
+
             #[cfg(feature = "xdp-gnome-screencast")]
             if niri.pipewire.is_some() {
                 let (to_niri, from_screen_cast) = calloop::channel::channel();
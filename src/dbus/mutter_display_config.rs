@@ -8,6 +8,7 @@ use zbus::{dbus_interface, fdo, SignalContext};
 
 use super::Start;
 use crate::backend::IpcOutputMap;
+use crate::utils::is_laptop_panel;
 
 pub struct DisplayConfig {
     ipc_outputs: Arc<Mutex<IpcOutputMap>>,
@@ -61,8 +62,7 @@ impl DisplayConfig {
             // Take only enabled outputs.
             .filter(|(_, output)| output.current_mode.is_some() && output.logical.is_some())
             .map(|(c, output)| {
-                // Loosely matches the check in Mutter.
-                let is_laptop_panel = matches!(c.get(..4), Some("eDP-" | "LVDS" | "DSI-"));
+                let is_laptop_panel = is_laptop_panel(c);
 
                 // FIXME: use proper serial when we have libdisplay-info.
                 // A serial is required for correct session restore by xdp-gnome.
@@ -29,7 +29,9 @@ impl ServiceChannel {
             compositor_state: Default::default(),
             // Would be nice to thread config here but for now it's fine.
             can_view_decoration_globals: false,
+            can_view_kde_decoration_global: false,
             restricted: false,
+            allowed_protocols: Vec::new(),
         });
         self.display.insert_client(sock2, data).unwrap();
         Ok(unsafe { zbus::zvariant::OwnedFd::from_raw_fd(sock1.into_raw_fd()) })
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use niri_ipc::Reply;
+use zbus::fdo::{self, RequestNameFlags};
+use zbus::zvariant::{SerializeDict, Type, Value};
+use zbus::{dbus_interface, SignalContext};
+
+use super::Start;
+use crate::backend::IpcOutputMap;
+use crate::utils::version;
+
+pub const OBJECT_PATH: &str = "/io/github/YaLTeR/niri";
+
+/// niri's own D-Bus interface, mirroring the IPC socket.
+pub struct Compositor {
+    to_niri: calloop::channel::Sender<CompositorToNiri>,
+    state: Arc<Mutex<CompositorState>>,
+    ipc_outputs: Arc<Mutex<IpcOutputMap>>,
+}
+
+pub enum CompositorToNiri {
+    Request {
+        request: niri_ipc::Request,
+        reply: async_channel::Sender<Reply>,
+    },
+}
+
+/// Compositor state served by the interface, kept up to date by niri.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CompositorState {
+    pub windows: HashMap<u64, WindowProperties>,
+    pub workspaces: Vec<WorkspaceProperties>,
+    /// Id of the focused window, or 0 if no window is focused.
+    pub focused_window: u64,
+    /// Name of the focused output, or an empty string if there are no outputs.
+    pub focused_output: String,
+}
+
+#[derive(Debug, Clone, PartialEq, SerializeDict, Type, Value)]
+#[zvariant(signature = "dict")]
+pub struct WindowProperties {
+    pub title: String,
+    #[zvariant(rename = "app-id")]
+    pub app_id: String,
+    /// Name of the output that the window is on.
+    pub output: Option<String>,
+    /// Name of the window group that the window belongs to.
+    pub group: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, SerializeDict, Type, Value)]
+#[zvariant(signature = "dict")]
+pub struct WorkspaceProperties {
+    /// Index of the workspace on its output, counting from 1.
+    pub idx: u8,
+    pub name: Option<String>,
+    pub output: Option<String>,
+    #[zvariant(rename = "is-active")]
+    pub is_active: bool,
+    #[zvariant(rename = "window-count")]
+    pub window_count: u32,
+    #[zvariant(rename = "active-window-id")]
+    pub active_window_id: Option<u64>,
+}
+
+#[derive(Debug, SerializeDict, Type, Value)]
+#[zvariant(signature = "dict")]
+pub struct OutputProperties {
+    pub make: String,
+    pub model: String,
+    /// Logical position and size, if the output is enabled.
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub scale: Option<f64>,
+}
+
+#[dbus_interface(name = "io.github.YaLTeR.niri.Compositor")]
+impl Compositor {
+    /// Handles a request in the JSON format of the IPC socket and returns the JSON reply.
+    async fn request(&self, request: &str) -> fdo::Result<String> {
+        let request = match serde_json::from_str(request) {
+            Ok(request) => request,
+            Err(err) => return Err(fdo::Error::InvalidArgs(err.to_string())),
+        };
+
+        let (reply, from_niri) = async_channel::bounded(1);
+        let msg = CompositorToNiri::Request { request, reply };
+        if let Err(err) = self.to_niri.send(msg) {
+            warn!("error sending message to niri: {err:?}");
+            return Err(fdo::Error::Failed("internal error".to_owned()));
+        }
+
+        let reply = match from_niri.recv().await {
+            Ok(reply) => reply,
+            Err(err) => {
+                warn!("error receiving message from niri: {err:?}");
+                return Err(fdo::Error::Failed("internal error".to_owned()));
+            }
+        };
+
+        serde_json::to_string(&reply).map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    async fn get_windows(&self) -> HashMap<u64, WindowProperties> {
+        self.state.lock().unwrap().windows.clone()
+    }
+
+    async fn get_workspaces(&self) -> Vec<WorkspaceProperties> {
+        self.state.lock().unwrap().workspaces.clone()
+    }
+
+    async fn get_outputs(&self) -> HashMap<String, OutputProperties> {
+        let ipc_outputs = self.ipc_outputs.lock().unwrap();
+        ipc_outputs
+            .iter()
+            .map(|(name, output)| {
+                let logical = output.logical.as_ref();
+                let props = OutputProperties {
+                    make: output.make.clone(),
+                    model: output.model.clone(),
+                    x: logical.map(|l| l.x),
+                    y: logical.map(|l| l.y),
+                    width: logical.map(|l| l.width),
+                    height: logical.map(|l| l.height),
+                    scale: logical.map(|l| l.scale),
+                };
+                (name.clone(), props)
+            })
+            .collect()
+    }
+
+    #[dbus_interface(property)]
+    async fn version(&self) -> String {
+        version()
+    }
+
+    #[dbus_interface(property)]
+    async fn focused_window(&self) -> u64 {
+        self.state.lock().unwrap().focused_window
+    }
+
+    #[dbus_interface(property)]
+    async fn focused_output(&self) -> String {
+        self.state.lock().unwrap().focused_output.clone()
+    }
+
+    #[dbus_interface(signal)]
+    pub async fn windows_changed(ctxt: &SignalContext<'_>) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub async fn workspaces_changed(ctxt: &SignalContext<'_>) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    pub async fn outputs_changed(ctxt: &SignalContext<'_>) -> zbus::Result<()>;
+
+    /// Text for screen readers to speak, like the title of the newly focused window.
+    #[dbus_interface(signal)]
+    pub async fn announcement(ctxt: &SignalContext<'_>, text: &str) -> zbus::Result<()>;
+}
+
+impl CompositorState {
+    fn focused_workspace(&self) -> Option<&WorkspaceProperties> {
+        self.workspaces
+            .iter()
+            .find(|ws| ws.is_active && ws.output.as_deref() == Some(self.focused_output.as_str()))
+    }
+
+    /// Returns the announcements for the focus changes from `self` to `new`.
+    ///
+    /// Switching to another workspace announces the workspace, and focusing a different window
+    /// or renaming the focused one announces the window title.
+    pub fn announcements(&self, new: &CompositorState) -> Vec<String> {
+        let mut rv = Vec::new();
+
+        // Don't announce the initial workspace.
+        if let (Some(old_ws), Some(new_ws)) = (self.focused_workspace(), new.focused_workspace()) {
+            if (&old_ws.output, old_ws.idx) != (&new_ws.output, new_ws.idx) {
+                let text = match &new_ws.name {
+                    Some(name) => name.clone(),
+                    None => format!("Workspace {}", new_ws.idx),
+                };
+                rv.push(text);
+            }
+        }
+
+        if let Some(window) = new.windows.get(&new.focused_window) {
+            let old_window = self.windows.get(&self.focused_window);
+            let changed = self.focused_window != new.focused_window
+                || old_window.map(|w| &w.title) != Some(&window.title);
+
+            let text = if window.title.is_empty() {
+                &window.app_id
+            } else {
+                &window.title
+            };
+
+            if changed && !text.is_empty() {
+                rv.push(text.clone());
+            }
+        }
+
+        rv
+    }
+}
+
+impl Compositor {
+    pub fn new(
+        to_niri: calloop::channel::Sender<CompositorToNiri>,
+        state: Arc<Mutex<CompositorState>>,
+        ipc_outputs: Arc<Mutex<IpcOutputMap>>,
+    ) -> Self {
+        Self {
+            to_niri,
+            state,
+            ipc_outputs,
+        }
+    }
+}
+
+impl Start for Compositor {
+    fn start(self) -> anyhow::Result<zbus::blocking::Connection> {
+        let conn = zbus::blocking::Connection::session()?;
+        let flags = RequestNameFlags::AllowReplacement
+            | RequestNameFlags::ReplaceExisting
+            | RequestNameFlags::DoNotQueue;
+
+        conn.object_server().at(OBJECT_PATH, self)?;
+        conn.request_name_with_flags("io.github.YaLTeR.niri", flags)?;
+
+        Ok(conn)
+    }
+}
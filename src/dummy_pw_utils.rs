@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use anyhow::bail;
 use smithay::reexports::calloop::LoopHandle;
 
@@ -10,4 +12,8 @@ impl PipeWire {
     pub fn new(_event_loop: &LoopHandle<'static, State>) -> anyhow::Result<Self> {
         bail!("PipeWire support is disabled (see \"xdp-gnome-screencast\" feature)");
     }
+
+    pub fn playing_pids(&self) -> HashSet<i32> {
+        HashSet::new()
+    }
 }
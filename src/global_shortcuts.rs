@@ -0,0 +1,332 @@
+use std::collections::HashMap;
+
+use niri_config::{Key, Modifiers, Trigger};
+use pangocairo::pango::glib::markup_escape_text;
+use smithay::input::keyboard::Keysym;
+use smithay::output::Output;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+
+use crate::dbus::freedesktop_global_shortcuts::{self, Shortcut};
+use crate::dbus::freedesktop_portal::OBJECT_PATH;
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::ui::exit_confirm_dialog::ExitConfirmDialog;
+
+/// Shortcuts registered by apps through the GlobalShortcuts portal.
+///
+/// Apps only get their shortcuts after the user confirms them in a dialog. Bound shortcuts fire
+/// regardless of which window is focused, but niri's own binds take priority.
+pub struct GlobalShortcuts {
+    bound: Vec<BoundShortcut>,
+    /// Bind request waiting for the user's consent.
+    pending: Option<PendingRequest>,
+    /// Shortcuts currently held down, by key code.
+    pressed: HashMap<u32, (OwnedObjectPath, String)>,
+    dialog: Option<ExitConfirmDialog>,
+    /// Connection that serves the portal backend, used to emit the signals.
+    conn: Option<zbus::blocking::Connection>,
+}
+
+struct BoundShortcut {
+    session_handle: OwnedObjectPath,
+    shortcut: Shortcut,
+    key: Option<Key>,
+}
+
+struct PendingRequest {
+    session_handle: OwnedObjectPath,
+    shortcuts: Vec<Shortcut>,
+    reply: async_channel::Sender<Option<Vec<Shortcut>>>,
+}
+
+impl GlobalShortcuts {
+    pub fn new() -> Self {
+        let dialog = match ExitConfirmDialog::with_text("") {
+            Ok(x) => Some(x),
+            Err(err) => {
+                warn!("error creating the global shortcuts dialog: {err:?}");
+                None
+            }
+        };
+
+        Self {
+            bound: Vec::new(),
+            pending: None,
+            pressed: HashMap::new(),
+            dialog,
+            conn: None,
+        }
+    }
+
+    pub fn set_connection(&mut self, conn: zbus::blocking::Connection) {
+        self.conn = Some(conn);
+    }
+
+    /// Asks the user to allow the shortcuts.
+    ///
+    /// Returns `true` if the dialog was shown.
+    pub fn request(
+        &mut self,
+        session_handle: OwnedObjectPath,
+        app_id: &str,
+        shortcuts: Vec<Shortcut>,
+        reply: async_channel::Sender<Option<Vec<Shortcut>>>,
+    ) -> bool {
+        // Only one request can wait for consent at a time.
+        self.deny();
+
+        let Some(dialog) = &mut self.dialog else {
+            send_reply(&reply, None);
+            return false;
+        };
+
+        if let Err(err) = dialog.set_text(consent_text(app_id, &shortcuts)) {
+            warn!("error rendering the global shortcuts dialog: {err:?}");
+            send_reply(&reply, None);
+            return false;
+        }
+
+        dialog.show();
+        self.pending = Some(PendingRequest {
+            session_handle,
+            shortcuts,
+            reply,
+        });
+        true
+    }
+
+    /// Binds the shortcuts of the pending request.
+    ///
+    /// Returns `true` if the dialog was hidden.
+    pub fn accept(&mut self) -> bool {
+        let Some(request) = self.pending.take() else {
+            return false;
+        };
+
+        // Binding replaces all earlier shortcuts of the session.
+        self.bound
+            .retain(|bound| bound.session_handle != request.session_handle);
+        for shortcut in &request.shortcuts {
+            let key = shortcut.trigger.as_deref().and_then(parse_trigger);
+            if key.is_none() {
+                debug!(
+                    "global shortcut {:?} has no usable trigger: {:?}",
+                    shortcut.id, shortcut.trigger
+                );
+            }
+
+            self.bound.push(BoundShortcut {
+                session_handle: request.session_handle.clone(),
+                shortcut: shortcut.clone(),
+                key,
+            });
+        }
+
+        send_reply(&request.reply, Some(request.shortcuts));
+        self.dialog.as_mut().map_or(false, |d| d.hide())
+    }
+
+    /// Denies the pending request, if any.
+    ///
+    /// Returns `true` if the dialog was hidden.
+    pub fn deny(&mut self) -> bool {
+        let Some(request) = self.pending.take() else {
+            return false;
+        };
+
+        send_reply(&request.reply, None);
+        self.dialog.as_mut().map_or(false, |d| d.hide())
+    }
+
+    pub fn is_dialog_open(&self) -> bool {
+        self.dialog.as_ref().map_or(false, |d| d.is_open())
+    }
+
+    pub fn list(&self, session_handle: &OwnedObjectPath) -> Vec<Shortcut> {
+        self.bound
+            .iter()
+            .filter(|bound| &bound.session_handle == session_handle)
+            .map(|bound| bound.shortcut.clone())
+            .collect()
+    }
+
+    pub fn close_session(&mut self, session_handle: &OwnedObjectPath) {
+        self.bound
+            .retain(|bound| &bound.session_handle != session_handle);
+        self.pressed
+            .retain(|_, (handle, _)| handle != session_handle);
+
+        if self
+            .pending
+            .as_ref()
+            .is_some_and(|request| &request.session_handle == session_handle)
+        {
+            self.deny();
+        }
+    }
+
+    /// Handles a key that none of niri's binds used.
+    ///
+    /// Returns `true` if the key triggered a global shortcut and should not reach the client.
+    pub fn on_key(
+        &mut self,
+        key_code: u32,
+        raw: Option<Keysym>,
+        modifiers: Modifiers,
+        pressed: bool,
+        time: u32,
+    ) -> bool {
+        if !pressed {
+            let Some((session_handle, id)) = self.pressed.remove(&key_code) else {
+                return false;
+            };
+            self.emit(&session_handle, &id, time, false);
+            return true;
+        }
+
+        let Some(raw) = raw else {
+            return false;
+        };
+
+        let bound = self.bound.iter().find(|bound| {
+            bound.key.is_some_and(|key| {
+                key.trigger == Trigger::Keysym(raw) && key.modifiers == modifiers
+            })
+        });
+        let Some(bound) = bound else {
+            return false;
+        };
+
+        let session_handle = bound.session_handle.clone();
+        let id = bound.shortcut.id.clone();
+        self.emit(&session_handle, &id, time, true);
+        self.pressed.insert(key_code, (session_handle, id));
+        true
+    }
+
+    pub fn render<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        self.dialog.as_ref()?.render(renderer, output)
+    }
+
+    fn emit(&self, session_handle: &OwnedObjectPath, id: &str, time: u32, activated: bool) {
+        use freedesktop_global_shortcuts::GlobalShortcuts as Iface;
+
+        let Some(conn) = &self.conn else {
+            return;
+        };
+
+        let iface = match conn.object_server().interface::<_, Iface>(OBJECT_PATH) {
+            Ok(iface) => iface,
+            Err(err) => {
+                warn!("error getting GlobalShortcuts interface: {err:?}");
+                return;
+            }
+        };
+
+        let ctxt = iface.signal_context();
+        let path = ObjectPath::from(session_handle.clone());
+        let timestamp = u64::from(time);
+        let res = async_io::block_on(async {
+            if activated {
+                Iface::activated(ctxt, path, id, timestamp, HashMap::new()).await
+            } else {
+                Iface::deactivated(ctxt, path, id, timestamp, HashMap::new()).await
+            }
+        });
+
+        if let Err(err) = res {
+            warn!("error emitting global shortcut signal: {err:?}");
+        }
+    }
+}
+
+impl Default for GlobalShortcuts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn send_reply(
+    reply: &async_channel::Sender<Option<Vec<Shortcut>>>,
+    shortcuts: Option<Vec<Shortcut>>,
+) {
+    if let Err(err) = reply.send_blocking(shortcuts) {
+        warn!("error sending reply to GlobalShortcuts: {err:?}");
+    }
+}
+
+/// Parses a trigger in the portal shortcut format, like `CTRL+SHIFT+a` or `LOGO+Return`.
+fn parse_trigger(trigger: &str) -> Option<Key> {
+    let parts = trigger
+        .split('+')
+        .map(|part| {
+            if part.eq_ignore_ascii_case("logo") {
+                "Super"
+            } else {
+                part
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let key = parts.join("+").parse::<Key>().ok()?;
+    matches!(key.trigger, Trigger::Keysym(_)).then_some(key)
+}
+
+fn consent_text(app_id: &str, shortcuts: &[Shortcut]) -> String {
+    let app_id = if app_id.is_empty() {
+        "An application"
+    } else {
+        app_id
+    };
+
+    let mut text = format!(
+        "<b>{}</b> wants to register global shortcuts:\n",
+        markup_escape_text(app_id)
+    );
+
+    for shortcut in shortcuts {
+        let description = if shortcut.description.is_empty() {
+            &shortcut.id
+        } else {
+            &shortcut.description
+        };
+        text.push('\n');
+        text.push_str(&markup_escape_text(description));
+
+        if let Some(trigger) = &shortcut.trigger {
+            text.push_str(&format!(
+                "  <span face='mono' bgcolor='#2C2C2C'> {} </span>",
+                markup_escape_text(trigger)
+            ));
+        }
+    }
+
+    text.push_str(
+        "\n\nPress <span face='mono' bgcolor='#2C2C2C'> Enter </span> to allow, \
+         any other key to deny.",
+    );
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_portal_triggers() {
+        let key = parse_trigger("CTRL+SHIFT+a").unwrap();
+        assert_eq!(key.trigger, Trigger::Keysym(Keysym::a));
+        assert_eq!(key.modifiers, Modifiers::CTRL | Modifiers::SHIFT);
+
+        let key = parse_trigger("LOGO+Return").unwrap();
+        assert_eq!(key.trigger, Trigger::Keysym(Keysym::Return));
+        assert_eq!(key.modifiers, Modifiers::SUPER);
+
+        assert!(parse_trigger("CTRL+").is_none());
+        assert!(parse_trigger("CTRL+MouseLeft").is_none());
+    }
+}
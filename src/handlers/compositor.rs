@@ -1,7 +1,9 @@
 use std::collections::hash_map::Entry;
+use std::time::Duration;
 
 use smithay::backend::renderer::utils::{on_commit_buffer_handler, with_renderer_surface_state};
 use smithay::input::pointer::CursorImageStatus;
+use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
 use smithay::reexports::calloop::Interest;
 use smithay::reexports::wayland_server::protocol::wl_buffer;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
@@ -17,9 +19,15 @@ use smithay::wayland::shm::{ShmHandler, ShmState};
 use smithay::{delegate_compositor, delegate_shm};
 
 use super::xdg_shell::add_mapped_toplevel_pre_commit_hook;
+use crate::layout::preset::PresetPlacement;
+use crate::layout::workspace::ColumnWidth;
+use crate::layout::LayoutElement as _;
 use crate::niri::{ClientState, State};
-use crate::utils::send_scale_transform;
-use crate::window::{InitialConfigureState, Mapped, ResolvedWindowRules, Unmapped};
+use crate::utils::{get_monotonic_time, send_scale_transform};
+use crate::window::{app_id, InitialConfigureState, Mapped, ResolvedWindowRules, Unmapped};
+
+/// How long to hold off mapping a new window that committed a buffer of the wrong size.
+pub const MAP_WAIT_TIMEOUT: Duration = Duration::from_millis(150);
 
 impl CompositorHandler for State {
     fn compositor_state(&mut self) -> &mut CompositorState {
@@ -112,91 +120,84 @@ impl CompositorHandler for State {
                         });
 
                 if is_mapped {
-                    // The toplevel got mapped.
-                    let Unmapped { window, state } = entry.remove();
-
-                    window.on_commit();
-
-                    let toplevel = window.toplevel().expect("no X11 support");
-
-                    let (rules, width, is_full_width, output, workspace_name) =
-                        if let InitialConfigureState::Configured {
-                            rules,
-                            width,
-                            is_full_width,
-                            output,
-                            workspace_name,
-                        } = state
-                        {
-                            // Check that the output is still connected.
-                            let output =
-                                output.filter(|o| self.niri.layout.monitor_for_output(o).is_some());
-
-                            // Check that the workspace still exists.
-                            let workspace_name = workspace_name
-                                .filter(|n| self.niri.layout.find_workspace_by_name(n).is_some());
-
-                            (rules, width, is_full_width, output, workspace_name)
-                        } else {
-                            error!("window map must happen after initial configure");
-                            (ResolvedWindowRules::empty(), None, false, None, None)
-                        };
-
-                    let parent = toplevel
-                        .parent()
-                        .and_then(|parent| self.niri.layout.find_window_and_output(&parent))
-                        // Only consider the parent if we configured the window for the same
-                        // output.
-                        //
-                        // Normally when we're following the parent, the configured output will be
-                        // None. If the configured output is set, that means it was set explicitly
-                        // by a window rule or a fullscreen request.
-                        .filter(|(_, parent_output)| {
-                            output.is_none() || output.as_ref() == Some(*parent_output)
-                        })
-                        .map(|(mapped, _)| mapped.window.clone());
-
-                    let hook = add_mapped_toplevel_pre_commit_hook(toplevel);
-                    let mapped = Mapped::new(window, rules, hook);
-                    let window = mapped.window.clone();
-
-                    let output = if let Some(p) = parent {
-                        // Open dialogs immediately to the right of their parent window.
-                        self.niri
-                            .layout
-                            .add_window_right_of(&p, mapped, width, is_full_width)
-                    } else if let Some(workspace_name) = &workspace_name {
-                        self.niri.layout.add_window_to_named_workspace(
-                            workspace_name,
-                            mapped,
-                            width,
-                            is_full_width,
-                        )
-                    } else if let Some(output) = &output {
-                        self.niri
-                            .layout
-                            .add_window_on_output(output, mapped, width, is_full_width);
-                        Some(output)
-                    } else {
-                        self.niri.layout.add_window(mapped, width, is_full_width)
-                    };
-
-                    if let Some(output) = output.cloned() {
-                        self.niri.layout.start_open_animation_for_window(&window);
-
-                        let new_active_window =
-                            self.niri.layout.active_window().map(|(m, _)| &m.window);
-                        if new_active_window == Some(&window) {
-                            self.maybe_warp_cursor_to_focus();
+                    let unmapped = entry.into_mut();
+                    unmapped.window.on_commit();
+
+                    // Hold off mapping until the window commits a buffer of the size that we
+                    // configured, so that the new tile doesn't flash at the wrong size. Some
+                    // windows never pick that size, so give up after a timeout.
+                    if unmapped.is_waiting_for_configured_size() {
+                        let now = get_monotonic_time();
+                        match unmapped.map_deadline {
+                            None => {
+                                let deadline = now + MAP_WAIT_TIMEOUT;
+                                unmapped.map_deadline = Some(deadline);
+
+                                // Show where the window will appear in the meantime.
+                                if let InitialConfigureState::Configured {
+                                    width,
+                                    is_full_width,
+                                    output,
+                                    workspace_name,
+                                    ..
+                                } = &unmapped.state
+                                {
+                                    // Without a configured width, the window picked its own.
+                                    let width = if *is_full_width {
+                                        ColumnWidth::Proportion(1.)
+                                    } else {
+                                        width.unwrap_or_else(|| {
+                                            let width = unmapped.window.geometry().size.w;
+                                            ColumnWidth::Fixed(f64::from(width))
+                                        })
+                                    };
+                                    self.niri.layout.show_insert_hint(
+                                        unmapped.window.clone(),
+                                        output.as_ref(),
+                                        workspace_name.as_deref(),
+                                        width,
+                                    );
+                                }
+
+                                // Wake up once the deadline passes. Refreshing the state also
+                                // checks the deadline, which covers a synthetic clock that
+                                // doesn't move along with the timer.
+                                self.niri
+                                    .event_loop
+                                    .insert_source(
+                                        Timer::from_duration(MAP_WAIT_TIMEOUT),
+                                        move |_, _, state| {
+                                            state.map_windows_past_deadline();
+                                            TimeoutAction::Drop
+                                        },
+                                    )
+                                    .unwrap();
+                                self.niri.queue_redraw_all();
+                                return;
+                            }
+                            Some(deadline) if now < deadline => return,
+                            Some(_) => (),
                         }
-
-                        self.niri.queue_redraw(&output);
                     }
+
+                    let unmapped = self.niri.unmapped_windows.remove(surface).unwrap();
+                    self.map_toplevel(unmapped);
                     return;
                 }
 
                 // The toplevel remains unmapped.
-                let unmapped = entry.get();
+                let unmapped = entry.into_mut();
+                if unmapped.map_deadline.is_some() {
+                    // The toplevel unmapped while waiting for the configured size. Newly-unmapped
+                    // toplevels must perform the initial commit-configure sequence afresh.
+                    let window = unmapped.window.clone();
+                    self.niri.layout.hide_insert_hint(&window);
+                    *unmapped = Unmapped::new(window);
+
+                    self.niri.queue_redraw_all();
+                    return;
+                }
+
                 if unmapped.needs_initial_configure() {
                     let toplevel = unmapped.window.toplevel().expect("no x11 support").clone();
                     self.queue_initial_configure(toplevel);
@@ -204,6 +205,26 @@ impl CompositorHandler for State {
                 return;
             }
 
+            // This is a commit of a minimized toplevel.
+            if let Some(idx) = self.niri.find_minimized_window(surface) {
+                let window = self.niri.minimized_windows[idx].mapped.window.clone();
+                window.on_commit();
+
+                let is_mapped =
+                    with_renderer_surface_state(surface, |state| state.buffer().is_some())
+                        .unwrap_or_else(|| {
+                            error!("no renderer surface state even though we use commit handler");
+                            false
+                        });
+                if !is_mapped {
+                    // The minimized toplevel got unmapped.
+                    self.niri.minimized_windows.remove(idx);
+                    let unmapped = Unmapped::new(window);
+                    self.niri.unmapped_windows.insert(surface.clone(), unmapped);
+                }
+                return;
+            }
+
             // This is a commit of a previously-mapped root or a non-toplevel root.
             if let Some((mapped, output)) = self.niri.layout.find_window_and_output(surface) {
                 let window = mapped.window.clone();
@@ -244,7 +265,10 @@ impl CompositorHandler for State {
                             id: u64::from(id.get()),
                         });
 
-                    self.niri.layout.remove_window(&window);
+                    self.niri.remember_window_size(&window);
+                    self.niri.layout.close_window(&window);
+                    self.niri.forget_focused_window(&window);
+                    self.niri.dbus_compositor_changed = true;
 
                     if was_active {
                         self.maybe_warp_cursor_to_focus();
@@ -273,6 +297,10 @@ impl CompositorHandler for State {
                 }
 
                 // The toplevel remains mapped.
+                if let Some((mapped, _)) = self.niri.layout.find_window_and_output_mut(surface) {
+                    mapped.update_is_dialog();
+                }
+
                 self.niri.layout.update_window(&window, serial);
 
                 // Popup placement depends on window size which might have changed.
@@ -358,6 +386,203 @@ impl CompositorHandler for State {
     }
 }
 
+impl State {
+    /// Adds a toplevel that committed its first buffer to the layout.
+    fn map_toplevel(&mut self, unmapped: Unmapped) {
+        if unmapped.map_deadline.is_some() {
+            self.niri.layout.hide_insert_hint(&unmapped.window);
+        }
+
+        let Unmapped {
+            window,
+            state,
+            activation_origin,
+            ..
+        } = unmapped;
+
+        let toplevel = window.toplevel().expect("no X11 support");
+
+        let (rules, width, is_full_width, output, workspace_name) =
+            if let InitialConfigureState::Configured {
+                rules,
+                width,
+                is_full_width,
+                output,
+                workspace_name,
+            } = state
+            {
+                // Check that the output is still connected.
+                let output = output.filter(|o| self.niri.layout.monitor_for_output(o).is_some());
+
+                // Check that the workspace still exists.
+                let workspace_name =
+                    workspace_name.filter(|n| self.niri.layout.find_workspace_by_name(n).is_some());
+
+                (rules, width, is_full_width, output, workspace_name)
+            } else {
+                error!("window map must happen after initial configure");
+                (ResolvedWindowRules::empty(), None, false, None, None)
+            };
+
+        let parent = toplevel
+            .parent()
+            .and_then(|parent| self.niri.layout.find_window_and_output(&parent))
+            // Only consider the parent if we configured the window for the same output.
+            //
+            // Normally when we're following the parent, the configured output will be None. If
+            // the configured output is set, that means it was set explicitly by a window rule or
+            // a fullscreen request.
+            .filter(|(_, parent_output)| {
+                output.is_none() || output.as_ref() == Some(*parent_output)
+            })
+            .map(|(mapped, _)| mapped.window.clone());
+
+        let focus_on_workspace = rules
+            .open_on_workspace
+            .as_ref()
+            .map_or(false, |ws| ws.focus);
+
+        let hook = add_mapped_toplevel_pre_commit_hook(toplevel);
+        let mut mapped = Mapped::new(window, rules, hook);
+        let window = mapped.window.clone();
+
+        // Windows join the group last set for their app.
+        if let Some(app_id) = app_id(mapped.toplevel()) {
+            let group = self.niri.window_groups.group(&app_id);
+            mapped.set_group(group.map(String::from));
+        }
+
+        // Dialogs float at the size they picked, unless they open fullscreen.
+        let is_floating = mapped.is_dialog() && !mapped.is_pending_fullscreen();
+
+        // Windows placed by rules, next to their parent or floating don't fill preset slots.
+        let preset_placement =
+            if parent.is_none() && workspace_name.is_none() && output.is_none() && !is_floating {
+                self.niri.take_preset_placement(&window)
+            } else {
+                None
+            };
+
+        // Dialogs pick their own height.
+        let restore_height = parent.is_none() && !is_floating;
+
+        // Open-on rules of a parentless dialog still put it into a column where they say.
+        let follows_rules = parent.is_none() && (workspace_name.is_some() || output.is_some());
+
+        let output = if is_floating && !follows_rules {
+            // Dialogs open where the dialog placement option says.
+            let pointer = self.niri.seat.get_pointer().unwrap().current_location();
+            let pointer = self
+                .niri
+                .output_under(pointer)
+                .map(|(output, pos)| (output.clone(), pos));
+            let pointer = pointer.as_ref().map(|(output, pos)| (output, *pos));
+            self.niri
+                .layout
+                .add_floating_window(mapped, parent.as_ref(), pointer)
+        } else if let Some(p) = parent {
+            // Fullscreen dialogs open immediately to the right of their parent window.
+            self.niri
+                .layout
+                .add_window_right_of(&p, mapped, width, is_full_width)
+        } else if let Some(placement) = preset_placement {
+            match placement {
+                PresetPlacement::IntoColumn(column_of) => {
+                    self.niri.layout.add_window_to_column(&column_of, mapped)
+                }
+                PresetPlacement::RightOf {
+                    window,
+                    width,
+                    is_full_width,
+                } => self.niri.layout.add_window_right_of(
+                    &window,
+                    mapped,
+                    Some(width),
+                    is_full_width,
+                ),
+                PresetPlacement::LeftOf {
+                    window,
+                    width,
+                    is_full_width,
+                } => self.niri.layout.add_window_left_of(
+                    &window,
+                    mapped,
+                    Some(width),
+                    is_full_width,
+                ),
+                PresetPlacement::NewColumn {
+                    width,
+                    is_full_width,
+                } => self
+                    .niri
+                    .layout
+                    .add_window(mapped, Some(width), is_full_width),
+            }
+        } else if let Some(workspace_name) = &workspace_name {
+            self.niri.layout.add_window_to_named_workspace(
+                workspace_name,
+                mapped,
+                width,
+                is_full_width,
+                focus_on_workspace,
+            )
+        } else if let Some(output) = &output {
+            self.niri
+                .layout
+                .add_window_on_output(output, mapped, width, is_full_width);
+            Some(output)
+        } else {
+            self.niri.layout.add_window(mapped, width, is_full_width)
+        };
+        self.niri.dbus_compositor_changed = true;
+
+        if restore_height {
+            self.niri.restore_window_height(&window);
+        }
+
+        if let Some(output) = output.cloned() {
+            let origin = activation_origin.and_then(|origin| {
+                let output_geo = self.niri.global_space.output_geometry(&output)?;
+                Some(origin - output_geo.loc.to_f64())
+            });
+            self.niri
+                .layout
+                .start_open_animation_for_window(&window, origin);
+
+            let new_active_window = self.niri.layout.active_window().map(|(m, _)| &m.window);
+            if new_active_window == Some(&window) {
+                // The new window takes the focus from an on-demand layer surface.
+                self.niri.clear_layer_shell_on_demand_focus(false);
+                self.maybe_warp_cursor_to_focus();
+            }
+
+            self.niri.queue_redraw(&output);
+        }
+    }
+
+    /// Maps the toplevels that didn't commit a buffer of the configured size before their
+    /// deadline.
+    pub fn map_windows_past_deadline(&mut self) {
+        let now = get_monotonic_time();
+        let expired: Vec<_> = self
+            .niri
+            .unmapped_windows
+            .iter()
+            .filter(|(_, unmapped)| {
+                unmapped
+                    .map_deadline
+                    .is_some_and(|deadline| deadline <= now)
+            })
+            .map(|(surface, _)| surface.clone())
+            .collect();
+
+        for surface in expired {
+            let unmapped = self.niri.unmapped_windows.remove(&surface).unwrap();
+            self.map_toplevel(unmapped);
+        }
+    }
+}
+
 impl BufferHandler for State {
     fn buffer_destroyed(&mut self, _buffer: &wl_buffer::WlBuffer) {}
 }
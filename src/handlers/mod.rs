@@ -10,7 +10,7 @@ use std::thread;
 
 use smithay::backend::allocator::dmabuf::Dmabuf;
 use smithay::backend::drm::DrmNode;
-use smithay::backend::input::TabletToolDescriptor;
+use smithay::backend::input::{InputEvent, TabletToolDescriptor};
 use smithay::desktop::{PopupKind, PopupManager};
 use smithay::input::pointer::{CursorIcon, CursorImageStatus, PointerHandle};
 use smithay::input::{keyboard, Seat, SeatHandler, SeatState};
@@ -21,7 +21,7 @@ use smithay::reexports::wayland_server::protocol::wl_data_source::WlDataSource;
 use smithay::reexports::wayland_server::protocol::wl_output::WlOutput;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::reexports::wayland_server::Resource;
-use smithay::utils::{Logical, Rectangle, Size};
+use smithay::utils::{Logical, Point, Rectangle, Size};
 use smithay::wayland::compositor::with_states;
 use smithay::wayland::dmabuf::{DmabufGlobal, DmabufHandler, DmabufState, ImportNotifier};
 use smithay::wayland::drm_lease::{
@@ -53,24 +53,32 @@ use smithay::wayland::xdg_activation::{
     XdgActivationHandler, XdgActivationState, XdgActivationToken, XdgActivationTokenData,
 };
 use smithay::{
-    delegate_cursor_shape, delegate_data_control, delegate_data_device, delegate_dmabuf,
-    delegate_drm_lease, delegate_fractional_scale, delegate_idle_inhibit, delegate_idle_notify,
-    delegate_input_method_manager, delegate_output, delegate_pointer_constraints,
-    delegate_pointer_gestures, delegate_presentation, delegate_primary_selection,
-    delegate_relative_pointer, delegate_seat, delegate_security_context, delegate_session_lock,
+    delegate_content_type, delegate_cursor_shape, delegate_data_control, delegate_data_device,
+    delegate_dmabuf, delegate_drm_lease, delegate_fractional_scale, delegate_idle_inhibit,
+    delegate_idle_notify, delegate_input_method_manager, delegate_output,
+    delegate_pointer_constraints, delegate_pointer_gestures, delegate_presentation,
+    delegate_primary_selection, delegate_relative_pointer, delegate_seat,
+    delegate_security_context, delegate_session_lock, delegate_single_pixel_buffer,
     delegate_tablet_manager, delegate_text_input_manager, delegate_viewporter,
     delegate_virtual_keyboard_manager, delegate_xdg_activation,
 };
 
+pub use crate::handlers::compositor::MAP_WAIT_TIMEOUT;
 pub use crate::handlers::xdg_shell::KdeDecorationsModeState;
 use crate::niri::{ClientState, State};
 use crate::protocols::foreign_toplevel::{
     self, ForeignToplevelHandler, ForeignToplevelManagerState,
 };
 use crate::protocols::gamma_control::{GammaControlHandler, GammaControlManagerState};
+use crate::protocols::input_inhibit::{InputInhibitHandler, InputInhibitManagerState};
+use crate::protocols::output_management::{OutputManagementHandler, OutputManagementManagerState};
 use crate::protocols::screencopy::{Screencopy, ScreencopyHandler};
+use crate::protocols::virtual_pointer::{VirtualPointerHandler, VirtualPointerInputBackend};
 use crate::utils::{output_size, send_scale_transform};
-use crate::{delegate_foreign_toplevel, delegate_gamma_control, delegate_screencopy};
+use crate::{
+    delegate_foreign_toplevel, delegate_gamma_control, delegate_input_inhibit,
+    delegate_output_management, delegate_screencopy, delegate_virtual_pointer,
+};
 
 impl SeatHandler for State {
     type KeyboardFocus = WlSurface;
@@ -180,6 +188,13 @@ impl InputMethodHandler for State {
 delegate_input_method_manager!(State);
 delegate_virtual_keyboard_manager!(State);
 
+impl VirtualPointerHandler for State {
+    fn process_virtual_pointer_event(&mut self, event: InputEvent<VirtualPointerInputBackend>) {
+        self.process_input_event(event);
+    }
+}
+delegate_virtual_pointer!(State);
+
 impl SelectionHandler for State {
     type SelectionUserData = Arc<[u8]>;
 
@@ -324,10 +339,15 @@ impl SecurityContextHandler for State {
             .event_loop
             .insert_source(source, move |client, _, state| {
                 let config = state.niri.config.borrow();
+                let allowed_protocols = config
+                    .sandboxed_clients
+                    .allowed_protocols(context.app_id.as_deref());
                 let data = Arc::new(ClientState {
                     compositor_state: Default::default(),
-                    can_view_decoration_globals: config.prefer_no_csd,
+                    can_view_decoration_globals: config.any_prefer_no_csd(),
+                    can_view_kde_decoration_global: config.prefer_no_csd,
                     restricted: true,
+                    allowed_protocols,
                 });
 
                 if let Err(err) = state.niri.display_handle.insert_client(client, data) {
@@ -365,7 +385,9 @@ impl ForeignToplevelHandler for State {
     }
 
     fn activate(&mut self, wl_surface: WlSurface) {
-        if let Some((mapped, _)) = self.niri.layout.find_window_and_output(&wl_surface) {
+        if let Some(idx) = self.niri.find_minimized_window(&wl_surface) {
+            self.restore_minimized_window(idx);
+        } else if let Some((mapped, _)) = self.niri.layout.find_window_and_output(&wl_surface) {
             let window = mapped.window.clone();
             self.niri.layout.activate_window(&window);
             self.niri.queue_redraw_all();
@@ -375,6 +397,11 @@ impl ForeignToplevelHandler for State {
     fn close(&mut self, wl_surface: WlSurface) {
         if let Some((mapped, _)) = self.niri.layout.find_window_and_output(&wl_surface) {
             mapped.toplevel().send_close();
+        } else if let Some(idx) = self.niri.find_minimized_window(&wl_surface) {
+            self.niri.minimized_windows[idx]
+                .mapped
+                .toplevel()
+                .send_close();
         }
     }
 
@@ -410,6 +437,19 @@ impl ForeignToplevelHandler for State {
             self.niri.layout.set_fullscreen(&window, false);
         }
     }
+
+    fn set_minimized(&mut self, wl_surface: WlSurface) {
+        if let Some((mapped, _)) = self.niri.layout.find_window_and_output(&wl_surface) {
+            let window = mapped.window.clone();
+            self.minimize_window(&window);
+        }
+    }
+
+    fn unset_minimized(&mut self, wl_surface: WlSurface) {
+        if let Some(idx) = self.niri.find_minimized_window(&wl_surface) {
+            self.restore_minimized_window(idx);
+        }
+    }
 }
 delegate_foreign_toplevel!(State);
 
@@ -474,6 +514,8 @@ delegate_drm_lease!(State);
 
 delegate_viewporter!(State);
 
+delegate_single_pixel_buffer!(State);
+
 impl GammaControlHandler for State {
     fn gamma_control_manager_state(&mut self) -> &mut GammaControlManagerState {
         &mut self.niri.gamma_control_manager_state
@@ -505,6 +547,29 @@ impl GammaControlHandler for State {
 }
 delegate_gamma_control!(State);
 
+impl InputInhibitHandler for State {
+    fn input_inhibit_manager_state(&mut self) -> &mut InputInhibitManagerState {
+        &mut self.niri.input_inhibit_manager_state
+    }
+}
+delegate_input_inhibit!(State);
+
+impl OutputManagementHandler for State {
+    fn output_management_state(&mut self) -> &mut OutputManagementManagerState {
+        &mut self.niri.output_management_state
+    }
+
+    fn apply_output_config(&mut self, config: Vec<niri_config::Output>) {
+        self.apply_output_management_config(config);
+    }
+}
+delegate_output_management!(State);
+
+/// Where an activation token was requested from, in global coordinates.
+///
+/// Stored in the token user data, so that the window using the token can open from there.
+pub struct ActivationOrigin(pub Point<f64, Logical>);
+
 impl XdgActivationHandler for State {
     fn activation_state(&mut self) -> &mut XdgActivationState {
         &mut self.niri.activation_state
@@ -520,10 +585,25 @@ impl XdgActivationHandler for State {
         };
 
         let keyboard = seat.get_keyboard().unwrap();
-        keyboard
+        let valid = keyboard
             .last_enter()
             .map(|last_enter| serial.is_no_older_than(&last_enter))
-            .unwrap_or(false)
+            .unwrap_or(false);
+
+        // Launchers and docks are usually layer-shell surfaces, so open the window from the
+        // center of the one that requested the token.
+        if valid {
+            if let Some(origin) = data
+                .surface
+                .as_ref()
+                .and_then(|surface| self.niri.layer_surface_center(surface))
+            {
+                data.user_data
+                    .insert_if_missing(|| ActivationOrigin(origin));
+            }
+        }
+
+        valid
     }
 
     fn request_activation(
@@ -533,11 +613,23 @@ impl XdgActivationHandler for State {
         surface: WlSurface,
     ) {
         if token_data.timestamp.elapsed().as_secs() < 10 {
-            if let Some((mapped, _)) = self.niri.layout.find_window_and_output(&surface) {
+            if let Some(unmapped) = self.niri.unmapped_windows.get_mut(&surface) {
+                // The window will open from where it was launched.
+                unmapped.activation_origin = token_data
+                    .user_data
+                    .get::<ActivationOrigin>()
+                    .map(|origin| origin.0);
+            } else if let Some((mapped, _)) = self.niri.layout.find_window_and_output(&surface) {
                 let window = mapped.window.clone();
                 self.niri.layout.activate_window(&window);
                 self.niri.queue_redraw_all();
             }
+        } else if let Some((mapped, _)) = self.niri.layout.find_window_and_output_mut(&surface) {
+            // The token is too old to steal focus, so mark the window as requesting attention
+            // instead.
+            if !mapped.is_focused() {
+                mapped.set_urgent(true);
+            }
         }
     }
 }
@@ -545,3 +637,5 @@ delegate_xdg_activation!(State);
 
 impl FractionalScaleHandler for State {}
 delegate_fractional_scale!(State);
+
+delegate_content_type!(State);
@@ -1,5 +1,6 @@
 use std::cell::Cell;
 
+use niri_config::{Workspace as WorkspaceConfig, WorkspaceName};
 use smithay::desktop::{
     find_popup_root_surface, get_popup_toplevel_coords, layer_map_for_output, utils, LayerSurface,
     PopupKeyboardGrab, PopupKind, PopupManager, PopupPointerGrab, PopupUngrabStrategy, Window,
@@ -15,9 +16,9 @@ use smithay::reexports::wayland_server::protocol::wl_output;
 use smithay::reexports::wayland_server::protocol::wl_seat::WlSeat;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::reexports::wayland_server::{self, Resource, WEnum};
-use smithay::utils::{Logical, Rectangle, Serial};
+use smithay::utils::{Logical, Rectangle, Serial, Size};
 use smithay::wayland::compositor::{
-    add_pre_commit_hook, with_states, BufferAssignment, HookId, SurfaceAttributes,
+    add_blocker, add_pre_commit_hook, with_states, BufferAssignment, HookId, SurfaceAttributes,
 };
 use smithay::wayland::input_method::InputMethodSeat;
 use smithay::wayland::shell::kde::decoration::{KdeDecorationHandler, KdeDecorationState};
@@ -27,17 +28,19 @@ use smithay::wayland::shell::xdg::{
     PopupSurface, PositionerState, ToplevelSurface, XdgPopupSurfaceData, XdgShellHandler,
     XdgShellState, XdgToplevelSurfaceData,
 };
-use smithay::wayland::xdg_foreign::{XdgForeignHandler, XdgForeignState};
-use smithay::{
-    delegate_kde_decoration, delegate_xdg_decoration, delegate_xdg_foreign, delegate_xdg_shell,
-};
+use smithay::{delegate_kde_decoration, delegate_xdg_decoration, delegate_xdg_shell};
 
+use crate::delegate_xdg_foreign;
 use crate::input::resize_grab::ResizeGrab;
 use crate::input::DOUBLE_CLICK_TIME;
 use crate::layout::workspace::ColumnWidth;
+use crate::layout::LayoutElement;
 use crate::niri::{PopupGrabState, State};
+use crate::protocols::xdg_foreign::{XdgForeignHandler, XdgForeignState};
 use crate::utils::{get_monotonic_time, send_scale_transform, ResizeEdge};
-use crate::window::{InitialConfigureState, ResolvedWindowRules, Unmapped, WindowRef};
+use crate::window::{
+    app_id, is_dialog, InitialConfigureState, ResolvedWindowRules, Unmapped, WindowRef,
+};
 
 impl XdgShellHandler for State {
     fn xdg_shell_state(&mut self) -> &mut XdgShellState {
@@ -193,8 +196,7 @@ impl XdgShellHandler for State {
                     return;
                 }
 
-                let mon = self.niri.layout.monitor_for_output(output).unwrap();
-                if !mon.render_above_top_layer()
+                if !self.niri.render_above_top_layer(output)
                     && layers.layers_on(Layer::Top).any(|l| {
                         l.cached_state().keyboard_interactivity
                             == wlr_layer::KeyboardInteractivity::Exclusive
@@ -253,7 +255,7 @@ impl XdgShellHandler for State {
         // A configure is required in response to this event. However, if an initial configure
         // wasn't sent, then we will send this as part of the initial configure later.
         if initial_configure_sent(&surface) {
-            surface.send_configure();
+            self.send_toplevel_configure(&surface);
         }
     }
 
@@ -261,6 +263,18 @@ impl XdgShellHandler for State {
         // FIXME
     }
 
+    fn minimize_request(&mut self, surface: ToplevelSurface) {
+        let Some((mapped, _)) = self
+            .niri
+            .layout
+            .find_window_and_output(surface.wl_surface())
+        else {
+            return;
+        };
+        let window = mapped.window.clone();
+        self.minimize_window(&window);
+    }
+
     fn fullscreen_request(
         &mut self,
         toplevel: ToplevelSurface,
@@ -287,7 +301,7 @@ impl XdgShellHandler for State {
 
             // A configure is required in response to this event regardless if there are pending
             // changes.
-            toplevel.send_configure();
+            self.send_toplevel_configure(&toplevel);
         } else if let Some(unmapped) = self.niri.unmapped_windows.get_mut(toplevel.wl_surface()) {
             match &mut unmapped.state {
                 InitialConfigureState::NotConfigured { wants_fullscreen } => {
@@ -358,7 +372,7 @@ impl XdgShellHandler for State {
 
             // A configure is required in response to this event regardless if there are pending
             // changes.
-            toplevel.send_configure();
+            self.send_toplevel_configure(&toplevel);
         } else if let Some(unmapped) = self.niri.unmapped_windows.get_mut(toplevel.wl_surface()) {
             match &mut unmapped.state {
                 InitialConfigureState::NotConfigured { wants_fullscreen } => {
@@ -442,13 +456,18 @@ impl XdgShellHandler for State {
     }
 
     fn toplevel_destroyed(&mut self, surface: ToplevelSurface) {
-        if self
-            .niri
-            .unmapped_windows
-            .remove(surface.wl_surface())
-            .is_some()
-        {
+        if let Some(unmapped) = self.niri.unmapped_windows.remove(surface.wl_surface()) {
             // An unmapped toplevel got destroyed.
+            if unmapped.map_deadline.is_some() {
+                self.niri.layout.hide_insert_hint(&unmapped.window);
+                self.niri.queue_redraw_all();
+            }
+            return;
+        }
+
+        if let Some(idx) = self.niri.find_minimized_window(surface.wl_surface()) {
+            // A minimized toplevel got destroyed.
+            self.niri.minimized_windows.remove(idx);
             return;
         }
 
@@ -484,7 +503,10 @@ impl XdgShellHandler for State {
         let active_window = self.niri.layout.active_window().map(|(m, _)| &m.window);
         let was_active = active_window == Some(&window);
 
-        self.niri.layout.remove_window(&window);
+        self.niri.remember_window_size(&window);
+        self.niri.layout.close_window(&window);
+        self.niri.forget_focused_window(&window);
+        self.niri.dbus_compositor_changed = true;
 
         if was_active {
             self.maybe_warp_cursor_to_focus();
@@ -501,10 +523,12 @@ impl XdgShellHandler for State {
 
     fn app_id_changed(&mut self, toplevel: ToplevelSurface) {
         self.update_window_rules(&toplevel);
+        self.niri.dbus_compositor_changed = true;
     }
 
     fn title_changed(&mut self, toplevel: ToplevelSurface) {
         self.update_window_rules(&toplevel);
+        self.niri.dbus_compositor_changed = true;
     }
 }
 
@@ -512,9 +536,11 @@ delegate_xdg_shell!(State);
 
 impl XdgDecorationHandler for State {
     fn new_decoration(&mut self, toplevel: ToplevelSurface) {
-        // If we want CSD, we hide this global altogether.
+        // Window rules are not known yet at this point, so start with the global preference. The
+        // initial configure will apply the window rules.
+        let mode = decoration_mode(self.niri.config.borrow().prefer_no_csd);
         toplevel.with_pending_state(|state| {
-            state.decoration_mode = Some(zxdg_toplevel_decoration_v1::Mode::ServerSide);
+            state.decoration_mode = Some(mode);
         });
     }
 
@@ -530,29 +556,62 @@ impl XdgDecorationHandler for State {
         toplevel.with_pending_state(|state| {
             state.decoration_mode = Some(mode);
         });
+        set_decoration_mode_requested(&toplevel, true);
 
         // A configure is required in response to this event. However, if an initial configure
         // wasn't sent, then we will send this as part of the initial configure later.
         if initial_configure_sent(&toplevel) {
-            toplevel.send_configure();
+            self.send_toplevel_configure(&toplevel);
         }
     }
 
     fn unset_mode(&mut self, toplevel: ToplevelSurface) {
-        // If we want CSD, we hide this global altogether.
+        let mode = decoration_mode(self.prefer_no_csd_for(&toplevel));
         toplevel.with_pending_state(|state| {
-            state.decoration_mode = Some(zxdg_toplevel_decoration_v1::Mode::ServerSide);
+            state.decoration_mode = Some(mode);
         });
+        set_decoration_mode_requested(&toplevel, false);
 
         // A configure is required in response to this event. However, if an initial configure
         // wasn't sent, then we will send this as part of the initial configure later.
         if initial_configure_sent(&toplevel) {
-            toplevel.send_configure();
+            self.send_toplevel_configure(&toplevel);
         }
     }
 }
 delegate_xdg_decoration!(State);
 
+/// Whether the client explicitly requested an xdg-decoration mode.
+#[derive(Default)]
+struct DecorationModeRequested(Cell<bool>);
+
+fn set_decoration_mode_requested(toplevel: &ToplevelSurface, requested: bool) {
+    with_states(toplevel.wl_surface(), |states| {
+        states
+            .data_map
+            .get_or_insert(DecorationModeRequested::default)
+            .0
+            .set(requested);
+    });
+}
+
+fn decoration_mode_requested(toplevel: &ToplevelSurface) -> bool {
+    with_states(toplevel.wl_surface(), |states| {
+        states
+            .data_map
+            .get::<DecorationModeRequested>()
+            .map_or(false, |requested| requested.0.get())
+    })
+}
+
+fn decoration_mode(prefer_no_csd: bool) -> zxdg_toplevel_decoration_v1::Mode {
+    if prefer_no_csd {
+        zxdg_toplevel_decoration_v1::Mode::ServerSide
+    } else {
+        zxdg_toplevel_decoration_v1::Mode::ClientSide
+    }
+}
+
 /// Whether KDE server decorations are in use.
 #[derive(Default)]
 pub struct KdeDecorationsModeState {
@@ -614,6 +673,36 @@ fn initial_configure_sent(toplevel: &ToplevelSurface) -> bool {
 }
 
 impl State {
+    /// Sends a configure to the toplevel right away, tracking it if the window is mapped.
+    fn send_toplevel_configure(&mut self, toplevel: &ToplevelSurface) {
+        let surface = toplevel.wl_surface();
+        if let Some((mapped, _)) = self.niri.layout.find_window_and_output_mut(surface) {
+            mapped.send_configure();
+        } else if let Some(idx) = self.niri.find_minimized_window(surface) {
+            self.niri.minimized_windows[idx].mapped.send_configure();
+        } else {
+            toplevel.send_configure();
+        }
+    }
+
+    /// Returns whether the window prefers no CSD, taking its window rules into account.
+    fn prefer_no_csd_for(&self, toplevel: &ToplevelSurface) -> bool {
+        let surface = toplevel.wl_surface();
+        let rule = if let Some((mapped, _)) = self.niri.layout.find_window_and_output(surface) {
+            mapped.rules().prefer_no_csd
+        } else if let Some(Unmapped {
+            state: InitialConfigureState::Configured { rules, .. },
+            ..
+        }) = self.niri.unmapped_windows.get(surface)
+        {
+            rules.prefer_no_csd
+        } else {
+            None
+        };
+
+        rule.unwrap_or(self.niri.config.borrow().prefer_no_csd)
+    }
+
     pub fn send_initial_configure(&mut self, toplevel: &ToplevelSurface) {
         let _span = tracy_client::span!("State::send_initial_configure");
 
@@ -629,18 +718,30 @@ impl State {
             self.niri.is_at_startup,
         );
 
-        let Unmapped { window, state } = unmapped;
+        let Unmapped { window, state, .. } = unmapped;
 
         let InitialConfigureState::NotConfigured { wants_fullscreen } = state else {
             error!("window must not be already configured in send_initial_configure()");
             return;
         };
 
+        // The workspace from the window rules doesn't need to be in the config, create it on
+        // demand.
+        if let Some(open_on_workspace) = &rules.open_on_workspace {
+            self.niri
+                .layout
+                .ensure_named_workspace_on_demand(&WorkspaceConfig {
+                    name: WorkspaceName(open_on_workspace.name.clone()),
+                    open_on_output: rules.open_on_output.clone(),
+                    layout: Default::default(),
+                });
+        }
+
         // Pick the target monitor. First, check if we had a workspace set in the window rules.
         let mon = rules
             .open_on_workspace
-            .as_deref()
-            .and_then(|name| self.niri.layout.monitor_for_workspace(name));
+            .as_ref()
+            .and_then(|ws| self.niri.layout.monitor_for_workspace(&ws.name));
 
         // If not, check if we had an output set in the window rules.
         let mon = mon.or_else(|| {
@@ -691,8 +792,8 @@ impl State {
         // Tell the surface the preferred size and bounds for its likely output.
         let ws = rules
             .open_on_workspace
-            .as_deref()
-            .and_then(|name| mon.map(|mon| mon.find_named_workspace(name)))
+            .as_ref()
+            .and_then(|ws| mon.map(|mon| mon.find_named_workspace(&ws.name)))
             .unwrap_or_else(|| {
                 mon.map(|mon| mon.active_workspace_ref())
                     .or_else(|| self.niri.layout.active_workspace())
@@ -708,7 +809,25 @@ impl State {
                 });
             }
 
-            width = ws.resolve_default_width(rules.default_width);
+            // Window rules take priority over the remembered width, which in turn takes priority
+            // over the global default.
+            let remembered_width = if config.layout.remember_window_sizes {
+                app_id(toplevel).and_then(|app_id| self.niri.remembered_sizes.width(&app_id))
+            } else {
+                None
+            };
+
+            // Let dialogs pick their own width, unless a window rule says otherwise.
+            let is_dialog = is_dialog(toplevel);
+            width = if rules.default_width.is_some() {
+                ws.resolve_default_width(rules.default_width)
+            } else if is_dialog {
+                None
+            } else if let Some(width) = remembered_width {
+                Some(width)
+            } else {
+                ws.resolve_default_width(None)
+            };
 
             let configure_width = if is_full_width {
                 Some(ColumnWidth::Proportion(1.))
@@ -716,11 +835,36 @@ impl State {
                 width
             };
             ws.configure_new_window(window, configure_width, &rules);
+
+            // Dialogs also pick their own height.
+            if is_dialog {
+                toplevel.with_pending_state(|state| {
+                    if !state.states.contains(xdg_toplevel::State::Fullscreen) {
+                        if let Some(size) = &mut state.size {
+                            size.h = 0;
+                        }
+                    }
+                });
+            }
+        }
+
+        let prefer_no_csd = rules.prefer_no_csd.unwrap_or(config.prefer_no_csd);
+
+        // Apply the decoration mode from the window rules, unless the client asked for a specific
+        // mode (see the SDL2 bug in request_mode()).
+        if !decoration_mode_requested(toplevel) {
+            toplevel.with_pending_state(|state| {
+                if state.decoration_mode.is_some() {
+                    state.decoration_mode = Some(decoration_mode(prefer_no_csd));
+                }
+            });
         }
 
         // If the user prefers no CSD, it's a reasonable assumption that they would prefer to get
-        // rid of the various client-side rounded corners also by using the tiled state.
-        if config.prefer_no_csd {
+        // rid of the various client-side rounded corners also by using the tiled state. This also
+        // keeps the window geometry free of CSD shadows, which would otherwise show up as uneven
+        // gaps between tiles.
+        if prefer_no_csd {
             toplevel.with_pending_state(|state| {
                 state.states.set(xdg_toplevel::State::TiledLeft);
                 state.states.set(xdg_toplevel::State::TiledRight);
@@ -829,9 +973,19 @@ impl State {
         //
         // We try to keep regular window popups within the window itself horizontally (since the
         // window can be scrolled to both edges of the screen), but within the whole monitor's
-        // height.
-        let mut target =
-            Rectangle::from_loc_and_size((0, 0), (window_geo.size.w, output_geo.size.h)).to_f64();
+        // height. In a transposed layout, the columns scroll vertically instead.
+        //
+        // The window location is in the workspace layout, which a zoomed workspace scales down to
+        // the output, so bring the output size into the layout too.
+        let zoom = self.niri.layout.window_zoom(window).unwrap_or(1.);
+        let output_size = output_geo.size.to_f64().downscale(zoom);
+        let window_size = window_geo.size.to_f64();
+        let size = if self.niri.layout.is_transposed(output) {
+            Size::from((output_size.w, window_size.h))
+        } else {
+            Size::from((window_size.w, output_size.h))
+        };
+        let mut target = Rectangle::from_loc_and_size((0., 0.), size);
         target.loc -= self.niri.layout.window_loc(window).unwrap();
         target.loc -= get_popup_toplevel_coords(popup).to_f64();
 
@@ -1020,6 +1174,21 @@ pub fn add_mapped_toplevel_pre_commit_hook(toplevel: &ToplevelSurface) -> HookId
             false
         };
 
+        // Block the commit until the other windows in its transaction commit too.
+        let transaction =
+            commit_serial.and_then(|serial| mapped.take_transaction_for_commit(serial));
+        if let Some(transaction) = transaction {
+            let disable = state.niri.config.borrow().debug.disable_transactions;
+            if !disable && !got_unmapped && !transaction.is_completed() {
+                if let Some(client) = surface.client() {
+                    transaction.add_notification(&state.niri.event_loop, client);
+                    add_blocker(surface, transaction.blocker());
+                }
+            }
+
+            // Dropping our clone here completes the transaction if this was the last window.
+        }
+
         let window = mapped.window.clone();
         if got_unmapped {
             state.backend.with_primary_renderer(|renderer| {
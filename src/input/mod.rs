@@ -2,18 +2,21 @@ use std::any::Any;
 use std::cmp::min;
 use std::collections::hash_map::Entry;
 use std::collections::HashSet;
+use std::mem;
 use std::time::Duration;
 
 use calloop::timer::{TimeoutAction, Timer};
 use input::event::gesture::GestureEventCoordinates as _;
-use niri_config::{Action, Bind, Binds, Key, Modifiers, Trigger};
+use niri_config::{Action, Bind, Binds, Key, Modifiers, Trigger, WorkspaceReference};
 use niri_ipc::LayoutSwitchTarget;
+use portable_atomic::Ordering;
 use smithay::backend::input::{
     AbsolutePositionEvent, Axis, AxisSource, ButtonState, Device, DeviceCapability, Event,
     GestureBeginEvent, GestureEndEvent, GesturePinchUpdateEvent as _, GestureSwipeUpdateEvent as _,
     InputBackend, InputEvent, KeyState, KeyboardKeyEvent, MouseButton, PointerAxisEvent,
-    PointerButtonEvent, PointerMotionEvent, ProximityState, TabletToolButtonEvent, TabletToolEvent,
-    TabletToolProximityEvent, TabletToolTipEvent, TabletToolTipState, TouchEvent,
+    PointerButtonEvent, PointerMotionEvent, ProximityState, Switch, SwitchState, SwitchToggleEvent,
+    TabletToolButtonEvent, TabletToolEvent, TabletToolProximityEvent, TabletToolTipEvent,
+    TabletToolTipState, TouchEvent,
 };
 use smithay::backend::libinput::LibinputInputBackend;
 use smithay::input::keyboard::{keysyms, FilterResult, Keysym, ModifiersState};
@@ -24,17 +27,31 @@ use smithay::input::pointer::{
     GrabStartData as PointerGrabStartData, MotionEvent, RelativeMotionEvent,
 };
 use smithay::input::touch::{DownEvent, MotionEvent as TouchMotionEvent, UpEvent};
-use smithay::utils::{Logical, Point, Rectangle, SERIAL_COUNTER};
+use smithay::utils::{Logical, Point, Rectangle, Serial, SERIAL_COUNTER};
 use smithay::wayland::pointer_constraints::{with_pointer_constraint, PointerConstraint};
+use smithay::wayland::shell::wlr_layer::KeyboardInteractivity;
 use smithay::wayland::tablet_manager::{TabletDescriptor, TabletSeatTrait};
 
+use self::move_grab::MoveGrab;
 use self::resize_grab::ResizeGrab;
 use self::spatial_movement_grab::SpatialMovementGrab;
-use crate::niri::State;
+use crate::animation;
+use crate::clip_recorder;
+#[cfg(feature = "dbus")]
+use crate::dbus::freedesktop_notifications::CloseReason;
+#[cfg(feature = "dbus")]
+use crate::dbus::freedesktop_screenshot::PortalScreenshotReply;
+use crate::handlers::ActivationOrigin;
+use crate::layout::LayoutElement as _;
+use crate::niri::{KeyboardFocus, Niri, PickRequest, State};
+use crate::protocols::virtual_pointer::VirtualPointer;
+use crate::ui::resize_mode::ResizeMode;
 use crate::ui::screenshot_ui::ScreenshotUi;
+use crate::ui::window_hints::WindowHints;
 use crate::utils::spawning::spawn;
-use crate::utils::{center, get_monotonic_time, ResizeEdge};
+use crate::utils::{center, center_f64, get_monotonic_time, ResizeEdge};
 
+pub mod move_grab;
 pub mod resize_grab;
 pub mod scroll_tracker;
 pub mod spatial_movement_grab;
@@ -73,6 +90,7 @@ impl State {
                 self.niri
                     .idle_notifier_state
                     .notify_activity(&self.niri.seat);
+                self.niri.notify_idle_activity();
             }
         } else {
             // Power on monitors if they were off.
@@ -84,6 +102,7 @@ impl State {
                 self.niri
                     .idle_notifier_state
                     .notify_activity(&self.niri.seat);
+                self.niri.notify_idle_activity();
             }
         }
 
@@ -97,6 +116,17 @@ impl State {
             .map_or(false, |d| d.is_open())
             && should_hide_exit_confirm_dialog(&event);
 
+        let hide_close_confirm_dialog = self
+            .niri
+            .close_confirm_dialog
+            .as_ref()
+            .map_or(false, |d| d.is_open())
+            && should_hide_exit_confirm_dialog(&event);
+
+        #[cfg(feature = "dbus")]
+        let hide_global_shortcuts_dialog =
+            self.niri.global_shortcuts.is_dialog_open() && should_hide_exit_confirm_dialog(&event);
+
         use InputEvent::*;
         match event {
             DeviceAdded { device } => self.on_device_added(device),
@@ -123,7 +153,7 @@ impl State {
             TouchUp { event } => self.on_touch_up::<I>(event),
             TouchCancel { event } => self.on_touch_cancel::<I>(event),
             TouchFrame { event } => self.on_touch_frame::<I>(event),
-            SwitchToggle { .. } => (),
+            SwitchToggle { event } => self.on_switch_toggle::<I>(event),
             Special(_) => (),
         }
 
@@ -135,9 +165,25 @@ impl State {
 
         if let Some(dialog) = &mut self.niri.exit_confirm_dialog {
             if hide_exit_confirm_dialog && dialog.hide() {
+                self.niri.clear_backdrops();
                 self.niri.queue_redraw_all();
             }
         }
+
+        if let Some(dialog) = &mut self.niri.close_confirm_dialog {
+            if hide_close_confirm_dialog && dialog.hide() {
+                self.niri.window_to_close = None;
+                self.niri.clear_backdrops();
+                self.niri.queue_redraw_all();
+            }
+        }
+
+        // Accepting happens on Enter in on_keyboard(), anything else denies the request.
+        #[cfg(feature = "dbus")]
+        if hide_global_shortcuts_dialog {
+            self.niri.global_shortcuts.deny();
+            self.niri.queue_redraw_all();
+        }
     }
 
     pub fn process_libinput_event(&mut self, event: &mut InputEvent<LibinputInputBackend>) {
@@ -228,6 +274,63 @@ impl State {
         )
     }
 
+    /// Holds the pointer at the edge of its output until it was pushed far enough towards an
+    /// adjacent output.
+    fn apply_output_edge_resistance(
+        &mut self,
+        pos: Point<f64, Logical>,
+        new_pos: Point<f64, Logical>,
+    ) -> Point<f64, Logical> {
+        let resistance = self
+            .niri
+            .config
+            .borrow()
+            .input
+            .output_edge_resistance
+            .map_or(0., |r| r.0);
+        if resistance <= 0. {
+            self.niri.output_edge_push = 0.;
+            return new_pos;
+        }
+
+        let Some(output) = self.niri.global_space.output_under(pos).next() else {
+            self.niri.output_edge_push = 0.;
+            return new_pos;
+        };
+        let geom = self.niri.global_space.output_geometry(output).unwrap();
+
+        let crossing = self
+            .niri
+            .global_space
+            .output_under(new_pos)
+            .next()
+            .is_some_and(|new_output| new_output != output);
+        if !crossing {
+            // Either still on the same output, or going off a non-adjacent edge which is clamped
+            // separately.
+            self.niri.output_edge_push = 0.;
+            return new_pos;
+        }
+
+        let clamped = Point::from((
+            new_pos
+                .x
+                .clamp(geom.loc.x as f64, (geom.loc.x + geom.size.w - 1) as f64),
+            new_pos
+                .y
+                .clamp(geom.loc.y as f64, (geom.loc.y + geom.size.h - 1) as f64),
+        ));
+
+        let overshoot = new_pos - clamped;
+        self.niri.output_edge_push += overshoot.x.hypot(overshoot.y);
+        if self.niri.output_edge_push < resistance {
+            return clamped;
+        }
+
+        self.niri.output_edge_push = 0.;
+        new_pos
+    }
+
     /// Computes the cursor position for the tablet event.
     ///
     /// This function handles the tablet output mapping, as well as coordinate clamping and aspect
@@ -313,7 +416,52 @@ impl State {
                     }
                 }
 
-                should_intercept_key(
+                if let Some(dialog) = &this.niri.close_confirm_dialog {
+                    if dialog.is_open() && pressed {
+                        if raw == Some(Keysym::Return) {
+                            if let Some(window) = this.niri.window_to_close.take() {
+                                if let Some(toplevel) = window.toplevel() {
+                                    toplevel.send_close();
+                                }
+                            }
+                        }
+
+                        // The key answers the dialog, so the window mustn't get it.
+                        this.niri.suppressed_keys.insert(key_code);
+                        return FilterResult::Intercept(None);
+                    }
+                }
+
+                // Escape cancels an interactive pick, and Enter picks the focused output.
+                let is_picking_output = matches!(this.niri.pick, Some(PickRequest::Output(_)));
+                if pressed
+                    && ((this.niri.pick.is_some() && raw == Some(Keysym::Escape))
+                        || (is_picking_output && raw == Some(Keysym::Return)))
+                {
+                    if raw == Some(Keysym::Return) {
+                        if let Some(output) = this.niri.layout.active_output().cloned() {
+                            this.niri.pick_output(&output);
+                        }
+                    } else {
+                        this.niri.cancel_pick();
+                    }
+
+                    this.niri.suppressed_keys.insert(key_code);
+                    return FilterResult::Intercept(None);
+                }
+
+                #[cfg(feature = "dbus")]
+                if this.niri.global_shortcuts.is_dialog_open() && pressed {
+                    if raw == Some(Keysym::Return) {
+                        this.niri.global_shortcuts.accept();
+                    }
+
+                    // The key answers the dialog, so the focused window mustn't get it.
+                    this.niri.suppressed_keys.insert(key_code);
+                    return FilterResult::Intercept(None);
+                }
+
+                let res = should_intercept_key(
                     &mut this.niri.suppressed_keys,
                     bindings,
                     comp_mod,
@@ -323,8 +471,30 @@ impl State {
                     pressed,
                     *mods,
                     &this.niri.screenshot_ui,
+                    &this.niri.window_hints,
+                    &this.niri.resize_mode,
                     this.niri.config.borrow().input.disable_power_key_handling,
-                )
+                    this.niri.is_inhibiting_shortcuts(),
+                    // While a client takes all input, binds work like on the lock screen.
+                    this.niri.is_locked() || this.niri.is_input_inhibited(),
+                );
+
+                // Keys that niri didn't use can trigger shortcuts registered through the portal.
+                #[cfg(feature = "dbus")]
+                if matches!(res, FilterResult::Forward)
+                    && !this.niri.is_locked()
+                    && this.niri.global_shortcuts.on_key(
+                        key_code,
+                        raw,
+                        modifiers_from_state(*mods),
+                        pressed,
+                        time,
+                    )
+                {
+                    return FilterResult::Intercept(None);
+                }
+
+                res
             },
         ) else {
             return;
@@ -340,7 +510,7 @@ impl State {
 
     pub fn handle_bind(&mut self, bind: Bind) {
         let Some(cooldown) = bind.cooldown else {
-            self.do_action(bind.action, bind.allow_when_locked);
+            self.do_bind_action(bind.action, bind.allow_when_locked);
             return;
         };
 
@@ -366,23 +536,191 @@ impl State {
                     .unwrap();
                 entry.insert(token);
 
-                self.do_action(bind.action, bind.allow_when_locked);
+                self.do_bind_action(bind.action, bind.allow_when_locked);
             }
         }
     }
 
+    /// Runs an action from a bind.
+    ///
+    /// Unlike the actions that come from IPC, the directions of bound actions follow the screen,
+    /// so they are transposed in a transposed layout.
+    fn do_bind_action(&mut self, action: Action, allow_when_locked: bool) {
+        self.do_action_with_directions(action, allow_when_locked, true);
+    }
+
     pub fn do_action(&mut self, action: Action, allow_when_locked: bool) {
+        self.do_action_with_directions(action, allow_when_locked, false);
+    }
+
+    fn do_action_with_directions(
+        &mut self,
+        action: Action,
+        allow_when_locked: bool,
+        screen_directions: bool,
+    ) {
+        if self.niri.layer_shell_on_demand_focus.is_none() {
+            self.do_action_inner(action, allow_when_locked, screen_directions);
+            return;
+        }
+
+        // Actions that move the focus in the layout take it back from the on-demand layer
+        // surface.
+        let layout_focus = |niri: &Niri| {
+            let window = niri.layout.focus().map(|mapped| mapped.window.clone());
+            let workspace = niri.layout.active_workspace().map(|ws| ws.id());
+            (window, workspace)
+        };
+        let focus_before = layout_focus(&self.niri);
+
+        self.do_action_inner(action, allow_when_locked, screen_directions);
+
+        if layout_focus(&self.niri) != focus_before {
+            self.niri.clear_layer_shell_on_demand_focus(false);
+        }
+    }
+
+    /// Runs the compound directional actions that have no counterpart in a transposed layout.
+    ///
+    /// Returns whether the action was handled.
+    fn do_transposed_action(&mut self, action: &Action) -> bool {
+        let layout = &mut self.niri.layout;
+
+        match action {
+            Action::MoveWindowDownOrToWorkspaceDown | Action::MoveWindowUpOrToWorkspaceUp => {
+                let down = matches!(action, Action::MoveWindowDownOrToWorkspaceDown);
+                let at_end = layout.active_workspace().map_or(true, |ws| {
+                    if down {
+                        ws.active_column_idx + 1 >= ws.columns.len()
+                    } else {
+                        ws.active_column_idx == 0
+                    }
+                });
+                match (down, at_end) {
+                    (true, true) => layout.move_to_workspace_down(),
+                    (true, false) => layout.move_right(),
+                    (false, true) => layout.move_to_workspace_up(),
+                    (false, false) => layout.move_left(),
+                }
+                self.maybe_warp_cursor_to_focus();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+                return true;
+            }
+            _ => (),
+        }
+
+        // The rest first try to move the focus towards the direction on the screen, and fall back
+        // to something else when the focus can't move.
+        let focus_before = layout.focus().map(|mapped| mapped.window.clone());
+        match action {
+            Action::FocusColumnOrMonitorLeft | Action::FocusColumnLeftOrLast => layout.focus_up(),
+            Action::FocusColumnOrMonitorRight | Action::FocusColumnRightOrFirst => {
+                layout.focus_down()
+            }
+            Action::FocusWindowDownOrColumnLeft
+            | Action::FocusWindowDownOrColumnRight
+            | Action::FocusWindowOrWorkspaceDown => layout.focus_right(),
+            Action::FocusWindowUpOrColumnLeft
+            | Action::FocusWindowUpOrColumnRight
+            | Action::FocusWindowOrWorkspaceUp => layout.focus_left(),
+            _ => return false,
+        }
+
+        if layout.focus().map(|mapped| &mapped.window) == focus_before.as_ref() {
+            match action {
+                Action::FocusColumnOrMonitorLeft | Action::FocusColumnOrMonitorRight => {
+                    let output = if matches!(action, Action::FocusColumnOrMonitorLeft) {
+                        self.niri.output_left()
+                    } else {
+                        self.niri.output_right()
+                    };
+                    if let Some(output) = output {
+                        self.niri.layout.focus_output(&output);
+                        if !self.maybe_warp_cursor_to_focus_centered() {
+                            self.move_cursor_to_output(&output);
+                        }
+                        // FIXME: granular
+                        self.niri.queue_redraw_all();
+                        return true;
+                    }
+                }
+                Action::FocusColumnRightOrFirst | Action::FocusColumnLeftOrLast => {
+                    // Wrap around within the column.
+                    let first = matches!(action, Action::FocusColumnRightOrFirst);
+                    let window = layout
+                        .active_workspace()
+                        .and_then(|ws| ws.columns.get(ws.active_column_idx))
+                        .and_then(|col| {
+                            if first {
+                                col.tiles.first()
+                            } else {
+                                col.tiles.last()
+                            }
+                        })
+                        .map(|tile| tile.window().window.clone());
+                    if let Some(window) = window {
+                        layout.activate_window(&window);
+                    }
+                }
+                Action::FocusWindowDownOrColumnLeft | Action::FocusWindowUpOrColumnLeft => {
+                    layout.focus_up()
+                }
+                Action::FocusWindowDownOrColumnRight | Action::FocusWindowUpOrColumnRight => {
+                    layout.focus_down()
+                }
+                Action::FocusWindowOrWorkspaceDown => layout.switch_workspace_down(),
+                Action::FocusWindowOrWorkspaceUp => layout.switch_workspace_up(),
+                _ => (),
+            }
+        }
+
+        self.maybe_warp_cursor_to_focus();
+        // FIXME: granular
+        self.niri.queue_redraw_all();
+        true
+    }
+
+    fn do_action_inner(
+        &mut self,
+        action: Action,
+        allow_when_locked: bool,
+        screen_directions: bool,
+    ) {
         if self.niri.is_locked() && !(allow_when_locked || allowed_when_locked(&action)) {
             return;
         }
 
+        // Most actions change the layout.
+        self.niri.dbus_compositor_changed = true;
+
         if let Some(touch) = self.niri.seat.get_touch() {
             touch.cancel(self);
         }
 
+        // Directional actions follow the screen: in a transposed layout, the columns are stacked
+        // top to bottom and the windows of a column go left to right.
+        let is_transposed = screen_directions
+            && self
+                .niri
+                .layout
+                .active_output()
+                .is_some_and(|output| self.niri.layout.is_transposed(output));
+        let action = if is_transposed {
+            if self.do_transposed_action(&action) {
+                return;
+            }
+            transpose_action(action)
+        } else {
+            action
+        };
+
         match action {
             Action::Quit(skip_confirmation) => {
                 if !skip_confirmation {
+                    if self.niri.exit_confirm_dialog.is_some() {
+                        self.capture_backdrops_for_dialog();
+                    }
                     if let Some(dialog) = &mut self.niri.exit_confirm_dialog {
                         if dialog.show() {
                             self.niri.queue_redraw_all();
@@ -419,7 +757,25 @@ impl State {
                 self.niri.debug_toggle_damage();
             }
             Action::Spawn(command) => {
-                spawn(command);
+                let (token, data) = self.niri.activation_state.create_external_token(None);
+                // Windows spawned with the pointer in view open from the pointer.
+                if !self.niri.pointer_hidden {
+                    let pointer = self.niri.seat.get_pointer().unwrap();
+                    let location = pointer.current_location();
+                    data.user_data
+                        .insert_if_missing(|| ActivationOrigin(location));
+                }
+                spawn(command, Some(token.clone()));
+            }
+            Action::FocusOrSpawn(app_id, command) => {
+                if let Some(window) = self.niri.window_with_app_id(&app_id) {
+                    self.niri.layout.activate_window(&window);
+                    self.maybe_warp_cursor_to_focus();
+                    // FIXME: granular
+                    self.niri.queue_redraw_all();
+                } else {
+                    self.do_action(Action::Spawn(command), allow_when_locked);
+                }
             }
             Action::DoScreenTransition(delay_ms) => {
                 self.backend.with_primary_renderer(|renderer| {
@@ -436,10 +792,39 @@ impl State {
                     });
                 }
             }
+            Action::ConfirmScreenshot if self.niri.screenshot_ui.is_recording_clip() => {
+                if let Some((output, rect)) = self.niri.screenshot_ui.selection() {
+                    if let Err(err) = self.niri.start_clip_recording(output, rect) {
+                        warn!("error starting clip recording: {err:?}");
+                    }
+                }
+
+                self.niri.screenshot_ui.close();
+                self.niri
+                    .cursor_manager
+                    .set_cursor_image(CursorImageStatus::default_named());
+                self.niri.queue_redraw_all();
+            }
             Action::ConfirmScreenshot => {
+                #[cfg(feature = "dbus")]
+                let portal_reply = self.niri.portal_screenshot.take();
+
                 self.backend.with_primary_renderer(|renderer| {
+                    // Colors are picked at the pointer rather than from the selection.
+                    #[cfg(feature = "dbus")]
+                    if let Some(PortalScreenshotReply::PickColor(reply)) = portal_reply {
+                        self.niri.finish_portal_pick_color(renderer, reply);
+                        return;
+                    }
+
                     match self.niri.screenshot_ui.capture(renderer) {
                         Ok((size, pixels)) => {
+                            #[cfg(feature = "dbus")]
+                            if let Some(PortalScreenshotReply::Screenshot(reply)) = portal_reply {
+                                self.niri.finish_portal_screenshot(reply, size, pixels);
+                                return;
+                            }
+
                             if let Err(err) = self.niri.save_screenshot(size, pixels) {
                                 warn!("error saving screenshot: {err:?}");
                             }
@@ -458,6 +843,8 @@ impl State {
             }
             Action::CancelScreenshot => {
                 self.niri.screenshot_ui.close();
+                #[cfg(feature = "dbus")]
+                self.niri.portal_screenshot = None;
                 self.niri
                     .cursor_manager
                     .set_cursor_image(CursorImageStatus::default_named());
@@ -465,9 +852,47 @@ impl State {
             }
             Action::Screenshot => {
                 self.backend.with_primary_renderer(|renderer| {
-                    self.niri.open_screenshot_ui(renderer);
+                    self.niri.open_screenshot_ui(renderer, false);
                 });
             }
+            Action::ToggleKeyboardShortcutsInhibit => {
+                if let KeyboardFocus::Layout {
+                    surface: Some(surface),
+                } = &self.niri.keyboard_focus
+                {
+                    if let Some((mapped, _)) = self.niri.layout.find_window_and_output_mut(surface)
+                    {
+                        let inhibit = !mapped.is_inhibiting_shortcuts();
+                        mapped.set_is_inhibiting_shortcuts(inhibit);
+                        debug!(
+                            "{} compositor shortcuts for the focused window",
+                            if inhibit { "inhibiting" } else { "restoring" }
+                        );
+                    }
+                }
+            }
+            Action::BreakInputInhibit => {
+                if self.niri.input_inhibit_manager_state.break_inhibit() {
+                    info!("taking input back from the inhibiting client");
+                    // FIXME: granular
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::RecordClip => {
+                if !self.niri.stop_clip_recording() {
+                    // Don't let the user select a region only to fail afterwards.
+                    if !clip_recorder::is_ffmpeg_available() {
+                        warn!("not recording clip: ffmpeg is not installed or not in $PATH");
+                        #[cfg(feature = "dbus")]
+                        std::thread::spawn(crate::utils::show_missing_ffmpeg_notification);
+                        return;
+                    }
+
+                    self.backend.with_primary_renderer(|renderer| {
+                        self.niri.open_screenshot_ui(renderer, true);
+                    });
+                }
+            }
             Action::ScreenshotWindow => {
                 let active = self.niri.layout.active_window();
                 if let Some((mapped, output)) = active {
@@ -479,9 +904,25 @@ impl State {
                 }
             }
             Action::CloseWindow => {
-                if let Some(mapped) = self.niri.layout.focus() {
-                    mapped.toplevel().send_close();
+                let Some(mapped) = self.niri.layout.focus() else {
+                    return;
+                };
+
+                if mapped.rules().confirm_close == Some(true)
+                    && self.niri.close_confirm_dialog.is_some()
+                {
+                    let window = mapped.window.clone();
+                    self.capture_backdrops_for_dialog();
+                    if let Some(dialog) = &mut self.niri.close_confirm_dialog {
+                        self.niri.window_to_close = Some(window);
+                        if dialog.show() {
+                            self.niri.queue_redraw_all();
+                        }
+                        return;
+                    }
                 }
+
+                mapped.toplevel().send_close();
             }
             Action::FullscreenWindow => {
                 let focus = self.niri.layout.focus().map(|m| m.window.clone());
@@ -491,6 +932,17 @@ impl State {
                     self.niri.queue_redraw_all();
                 }
             }
+            Action::MinimizeWindow => {
+                let focus = self.niri.layout.focus().map(|m| m.window.clone());
+                if let Some(window) = focus {
+                    self.minimize_window(&window);
+                }
+            }
+            Action::RestoreMinimizedWindow => {
+                if let Some(idx) = self.niri.minimized_windows.len().checked_sub(1) {
+                    self.restore_minimized_window(idx);
+                }
+            }
             Action::SwitchLayout(action) => {
                 self.niri.seat.get_keyboard().unwrap().with_xkb_state(
                     self,
@@ -518,6 +970,53 @@ impl State {
                 // FIXME: granular
                 self.niri.queue_redraw_all();
             }
+            Action::MoveColumnToIndex(index) => {
+                self.niri.layout.move_column_to_index(index);
+                self.maybe_warp_cursor_to_focus();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::ShowWindowHints => {
+                let mut windows = vec![];
+                self.niri
+                    .layout
+                    .with_visible_windows(|mapped, output, geo| {
+                        windows.push((output.clone(), mapped.window.clone(), geo));
+                    });
+
+                if self.niri.window_hints.open(windows) {
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::FocusWindowHint(key) => {
+                if let Some(window) = self.niri.window_hints.take(key) {
+                    if self.niri.pick.is_some() {
+                        self.niri.pick_window(&window);
+                    } else {
+                        self.niri.layout.activate_window(&window);
+                        self.maybe_warp_cursor_to_focus();
+                    }
+                }
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::CancelWindowHints => {
+                self.niri.cancel_pick();
+                if self.niri.window_hints.close() {
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::EnterResizeMode => {
+                let config = self.niri.config.borrow().resize_mode;
+                if self.niri.resize_mode.open(config) {
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::ExitResizeMode => {
+                if self.niri.resize_mode.close() {
+                    self.niri.queue_redraw_all();
+                }
+            }
             Action::MoveColumnToLast => {
                 self.niri.layout.move_column_to_last();
                 self.maybe_warp_cursor_to_focus();
@@ -578,6 +1077,43 @@ impl State {
                 // FIXME: granular
                 self.niri.queue_redraw_all();
             }
+            Action::FocusBack => {
+                if let Some(window) = self.niri.focus_history_mut().and_then(|h| h.back()) {
+                    self.niri.layout.activate_window(&window);
+                    self.maybe_warp_cursor_to_focus();
+                    // FIXME: granular
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::FocusForward => {
+                if let Some(window) = self.niri.focus_history_mut().and_then(|h| h.forward()) {
+                    self.niri.layout.activate_window(&window);
+                    self.maybe_warp_cursor_to_focus();
+                    // FIXME: granular
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::SwapWithPrevious => {
+                let Some(focus) = self.niri.layout.focus().map(|m| m.window.clone()) else {
+                    return;
+                };
+                let previous = self
+                    .niri
+                    .focus_history_mut()
+                    .and_then(|h| h.previous().cloned());
+                if let Some(previous) = previous {
+                    // The focus stays in place, so it goes to the previous window.
+                    self.niri.layout.swap_windows(&focus, &previous);
+                    // FIXME: granular
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::FocusColumn(index) => {
+                self.niri.layout.focus_column(index);
+                self.maybe_warp_cursor_to_focus();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
             Action::FocusColumnLast => {
                 self.niri.layout.focus_column_last();
                 self.maybe_warp_cursor_to_focus();
@@ -773,6 +1309,22 @@ impl State {
                     self.niri.queue_redraw_all();
                 }
             }
+            Action::FocusWorkspaceFuzzy(query) => {
+                // An empty query would match every named workspace.
+                if query.is_empty() {
+                    return;
+                }
+
+                let name = self
+                    .niri
+                    .layout
+                    .find_workspace_by_fuzzy_name(&query)
+                    .and_then(|ws| ws.name.clone());
+                if let Some(name) = name {
+                    let reference = WorkspaceReference::Name(name);
+                    self.do_action(Action::FocusWorkspace(reference), allow_when_locked);
+                }
+            }
             Action::FocusWorkspacePrevious => {
                 self.niri.layout.switch_workspace_previous();
                 // FIXME: granular
@@ -812,6 +1364,16 @@ impl State {
             Action::MaximizeColumn => {
                 self.niri.layout.toggle_full_width();
             }
+            Action::ToggleAutoTile => {
+                self.niri.layout.toggle_auto_tile();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::ToggleColumnAlwaysCenter => {
+                self.niri.layout.toggle_column_always_center();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
             Action::FocusMonitorLeft => {
                 if let Some(output) = self.niri.output_left() {
                     self.niri.layout.focus_output(&output);
@@ -844,6 +1406,23 @@ impl State {
                     }
                 }
             }
+            Action::WarpCursor(x, y, output) => {
+                let output = match output {
+                    Some(name) => self.niri.output_by_name(&name),
+                    None => self.niri.layout.active_output().cloned(),
+                };
+                if let Some(output) = output {
+                    let pos = Point::from((f64::from(x), f64::from(y)));
+                    self.warp_cursor_within_output(&output, pos);
+                } else {
+                    warn!("output to warp the cursor to not found");
+                }
+            }
+            Action::WarpCursorToFocusedWindow => {
+                if let Some(rect) = self.niri.focused_tile_global_rect() {
+                    self.move_cursor(center_f64(rect));
+                }
+            }
             Action::MoveWindowToMonitorLeft => {
                 if let Some(output) = self.niri.output_left() {
                     self.niri.layout.move_to_output(&output);
@@ -919,12 +1498,55 @@ impl State {
             Action::SetColumnWidth(change) => {
                 self.niri.layout.set_column_width(change);
             }
+            Action::ToggleColumnWidth(normal, toggled) => {
+                self.niri.layout.toggle_column_width(normal, toggled);
+            }
             Action::SetWindowHeight(change) => {
                 self.niri.layout.set_window_height(change);
             }
             Action::ResetWindowHeight => {
                 self.niri.layout.reset_window_height();
             }
+            Action::SetWindowOpacity(change) => {
+                self.niri.layout.set_window_opacity(change);
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::ResetWindowOpacity => {
+                self.niri.layout.reset_window_opacity();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::SetWorkspaceGaps(change) => {
+                self.niri.layout.set_workspace_gaps(change);
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::SetWorkspaceDefaultColumnWidth(change) => {
+                self.niri.layout.set_workspace_default_column_width(change);
+            }
+            Action::SetWorkspaceCenterFocusedColumn(mode) => {
+                self.niri
+                    .layout
+                    .set_workspace_center_focused_column(mode.into());
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::SetWorkspaceZoom(zoom) => {
+                self.niri.layout.set_workspace_zoom(zoom.0);
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::ResetWorkspaceLayout => {
+                self.niri.layout.reset_workspace_layout();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::SetLayoutStrategy(strategy) => {
+                self.niri.layout.set_layout_strategy(strategy);
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
             Action::ShowHotkeyOverlay => {
                 if self.niri.hotkey_overlay.show() {
                     self.niri.queue_redraw_all();
@@ -962,6 +1584,77 @@ impl State {
                     }
                 }
             }
+            Action::SetPrimaryOutput(name) => {
+                if let Some(output) = self.niri.output_by_name(&name) {
+                    self.niri.layout.set_primary_output(&output);
+                    // FIXME: granular
+                    self.niri.queue_redraw_all();
+                } else {
+                    warn!("output {name} not found");
+                }
+            }
+            Action::SaveLayoutPreset(name) => {
+                self.niri.save_layout_preset(name);
+            }
+            Action::LoadLayoutPreset(name) => {
+                self.niri.load_layout_preset(&name);
+            }
+            Action::MagnifierZoomIn => {
+                self.niri
+                    .update_magnifier(|magnifier, config| magnifier.zoom_in(config));
+            }
+            Action::MagnifierZoomOut => {
+                self.niri
+                    .update_magnifier(|magnifier, config| magnifier.zoom_out(config));
+            }
+            Action::MagnifierReset => {
+                self.niri
+                    .update_magnifier(|magnifier, config| magnifier.reset(config));
+            }
+            Action::SetWindowGroup(name) => {
+                self.niri.set_focused_window_group(Some(name));
+            }
+            Action::UnsetWindowGroup => {
+                self.niri.set_focused_window_group(None);
+            }
+            Action::GatherWindowGroup(name) => {
+                self.gather_window_group(&name);
+                self.maybe_warp_cursor_to_focus();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::StashWindowGroup(name) => {
+                self.stash_window_group(&name);
+                self.maybe_warp_cursor_to_focus();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::ToggleReducedMotion => {
+                let reduced_motion = animation::REDUCED_MOTION.load(Ordering::Relaxed);
+                self.niri.set_reduced_motion(!reduced_motion);
+            }
+            Action::DismissNotification =>
+            {
+                #[cfg(feature = "dbus")]
+                if let Some(id) = self.niri.notifier.latest() {
+                    self.niri.close_notification(id, CloseReason::Dismissed);
+                }
+            }
+            Action::DismissAllNotifications =>
+            {
+                #[cfg(feature = "dbus")]
+                for id in self.niri.notifier.ids() {
+                    self.niri.close_notification(id, CloseReason::Dismissed);
+                }
+            }
+            Action::ToggleDoNotDisturb => {
+                #[cfg(feature = "dbus")]
+                {
+                    let notifier = &mut self.niri.notifier;
+                    notifier.set_do_not_disturb(!notifier.do_not_disturb());
+                    self.niri.queue_redraw_all();
+                }
+            }
         }
     }
 
@@ -1033,6 +1726,8 @@ impl State {
             }
         }
 
+        new_pos = self.apply_output_edge_resistance(pos, new_pos);
+
         if self
             .niri
             .global_space
@@ -1118,6 +1813,8 @@ impl State {
 
         self.niri.pointer_focus.clone_from(&under);
 
+        self.maybe_start_move_grab(new_pos, serial);
+
         pointer.motion(
             self,
             under.surface.clone(),
@@ -1140,16 +1837,29 @@ impl State {
 
         pointer.frame(self);
 
+        self.niri.update_resize_grip_cursor(new_pos);
+
         // Redraw to update the cursor position.
         // FIXME: redraw only outputs overlapping the cursor.
         self.niri.queue_redraw_all();
     }
 
-    fn on_pointer_motion_absolute<I: InputBackend>(
+    fn on_pointer_motion_absolute<I: InputBackend + 'static>(
         &mut self,
         event: I::PointerMotionAbsoluteEvent,
-    ) {
-        let Some(output_geo) = self.global_bounding_rectangle() else {
+    ) where
+        I::Device: 'static,
+    {
+        // Virtual pointers can map their absolute motion to a single output.
+        let device = event.device();
+        let output = (&device as &dyn Any)
+            .downcast_ref::<VirtualPointer>()
+            .and_then(|pointer| pointer.output());
+        let output_geo = match output {
+            Some(output) => self.niri.global_space.output_geometry(output),
+            None => self.global_bounding_rectangle(),
+        };
+        let Some(output_geo) = output_geo else {
             return;
         };
 
@@ -1181,6 +1891,8 @@ impl State {
         self.niri.maybe_activate_pointer_constraint(pos, &under);
         self.niri.pointer_focus.clone_from(&under);
 
+        self.maybe_start_move_grab(pos, serial);
+
         pointer.motion(
             self,
             under.surface,
@@ -1193,6 +1905,8 @@ impl State {
 
         pointer.frame(self);
 
+        self.niri.update_resize_grip_cursor(pos);
+
         // We moved the pointer, show it.
         self.niri.pointer_hidden = false;
 
@@ -1204,16 +1918,116 @@ impl State {
         self.niri.queue_redraw_all();
     }
 
+    /// Starts the pending move grab once the pointer is dragged far enough.
+    fn maybe_start_move_grab(&mut self, pos: Point<f64, Logical>, serial: Serial) {
+        let Some(grab) = &self.niri.pending_move_grab else {
+            return;
+        };
+
+        if !grab.is_past_drag_threshold(pos) {
+            return;
+        }
+
+        let grab = self.niri.pending_move_grab.take().unwrap();
+
+        let pointer = self.niri.seat.get_pointer().unwrap();
+        if pointer.is_grabbed() {
+            return;
+        }
+
+        let window = grab.window().clone();
+        if !self
+            .niri
+            .layout
+            .interactive_move_begin(window, grab.output())
+        {
+            return;
+        }
+
+        pointer.set_grab(self, grab, serial, Focus::Clear);
+        self.niri.pointer_grab_ongoing = true;
+        self.niri
+            .cursor_manager
+            .set_cursor_image(CursorImageStatus::Named(CursorIcon::Grabbing));
+    }
+
+    fn find_mouse_button_bind(&self, button: Option<MouseButton>) -> Option<Bind> {
+        // The screenshot UI handles the mouse buttons itself.
+        if self.niri.screenshot_ui.is_open() {
+            return None;
+        }
+
+        let trigger = match button? {
+            MouseButton::Left => Trigger::MouseLeft,
+            MouseButton::Right => Trigger::MouseRight,
+            MouseButton::Middle => Trigger::MouseMiddle,
+            MouseButton::Back => Trigger::MouseBack,
+            MouseButton::Forward => Trigger::MouseForward,
+        };
+
+        let mods = self.niri.seat.get_keyboard().unwrap().modifier_state();
+        let bind = find_configured_bind(
+            &self.niri.config.borrow().binds,
+            self.backend.mod_key(),
+            trigger,
+            mods,
+        )?;
+
+        // The focused window gets the buttons of all binds that can be inhibited.
+        if bind.allow_inhibiting && self.niri.is_inhibiting_shortcuts() {
+            return None;
+        }
+
+        // The lock surface, or the client that took all input, gets the buttons of binds that
+        // don't work while locked.
+        if (self.niri.is_locked() || self.niri.is_input_inhibited())
+            && !(bind.allow_when_locked || allowed_when_locked(&bind.action))
+        {
+            return None;
+        }
+
+        Some(bind)
+    }
+
     fn on_pointer_button<I: InputBackend>(&mut self, event: I::PointerButtonEvent) {
         let pointer = self.niri.seat.get_pointer().unwrap();
 
         let serial = SERIAL_COUNTER.next_serial();
 
-        let button = event.button_code();
+        let button = event.button_code();
+
+        let button_state = event.state();
+
+        // Handle mouse button binds.
+        if ButtonState::Pressed == button_state {
+            if let Some(bind) = self.find_mouse_button_bind(event.button()) {
+                self.niri.suppressed_buttons.insert(button);
+                self.handle_bind(bind);
+                return;
+            }
+        } else {
+            self.niri.pending_move_grab = None;
+
+            if self.niri.suppressed_buttons.remove(&button) {
+                // The press triggered a bind, so the client never saw it.
+                return;
+            }
+        }
 
-        let button_state = event.state();
+        // A click during an interactive pick picks what's under the cursor.
+        if ButtonState::Pressed == button_state && self.niri.pick_under_cursor() {
+            self.niri.suppressed_buttons.insert(button);
+            return;
+        }
 
         if ButtonState::Pressed == button_state {
+            // Clicking a layer surface with on-demand keyboard interactivity gives it the focus.
+            if let Some(layer) = &self.niri.pointer_focus.layer {
+                if layer.cached_state().keyboard_interactivity == KeyboardInteractivity::OnDemand {
+                    self.niri.set_layer_shell_on_demand_focus(layer.clone());
+                }
+            }
+
             if let Some(mapped) = self.niri.window_under_cursor() {
                 let window = mapped.window.clone();
 
@@ -1287,13 +2101,106 @@ impl State {
                     }
                 }
 
+                // Handle gestures on the window decorations, that is, the parts of the tile
+                // outside the window input region, like the border.
+                let on_decoration = self.niri.pointer_focus.window.is_none();
+                if on_decoration && !pointer.is_grabbed() {
+                    let mods = self.niri.seat.get_keyboard().unwrap().modifier_state();
+                    let mod_down = match self.backend.mod_key() {
+                        CompositorMod::Super => mods.logo,
+                        CompositorMod::Alt => mods.alt,
+                    };
+
+                    match event.button() {
+                        _ if mod_down => (),
+                        Some(MouseButton::Middle) => {
+                            // FIXME: don't activate once we can pass specific windows to actions.
+                            self.niri.layout.activate_window(&window);
+                            self.do_action(Action::CloseWindow, false);
+                        }
+                        Some(MouseButton::Left) => {
+                            let time = get_monotonic_time();
+                            let last = self
+                                .niri
+                                .last_decoration_click
+                                .replace((time, window.clone()));
+
+                            let is_double_click = last.map_or(false, |(last_time, last_window)| {
+                                last_window == window
+                                    && time.saturating_sub(last_time) <= DOUBLE_CLICK_TIME
+                            });
+
+                            if is_double_click {
+                                self.niri.last_decoration_click = None;
+
+                                // FIXME: don't activate once we can pass specific windows to
+                                // actions.
+                                self.niri.layout.activate_window(&window);
+                                self.niri.layout.toggle_full_width();
+                            } else {
+                                let location = pointer.current_location();
+                                let (output, _) = self.niri.output_under(location).unwrap();
+                                let output = output.clone();
+
+                                // The move starts once the pointer is dragged far enough, so that
+                                // a click doesn't take the pointer away.
+                                let start_data = PointerGrabStartData {
+                                    focus: None,
+                                    button: event.button_code(),
+                                    location,
+                                };
+                                let grab = MoveGrab::new(start_data, window.clone(), output);
+                                self.niri.pending_move_grab = Some(grab);
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+
+                self.niri.layout.activate_window(&window);
+                self.niri.clear_layer_shell_on_demand_focus(false);
+
+                // FIXME: granular.
+                self.niri.queue_redraw_all();
+            } else if let Some((window, edges)) = self
+                .niri
+                .resize_grip_under(pointer.current_location())
+                .filter(|_| event.button() == Some(MouseButton::Left) && !pointer.is_grabbed())
+                .map(|(mapped, edges)| (mapped.window.clone(), edges))
+            {
+                // Dragging the gap between tiles resizes the tile before it.
                 self.niri.layout.activate_window(&window);
 
+                if self
+                    .niri
+                    .layout
+                    .interactive_resize_begin(window.clone(), edges)
+                {
+                    let start_data = PointerGrabStartData {
+                        focus: None,
+                        button: event.button_code(),
+                        location: pointer.current_location(),
+                    };
+                    let grab = ResizeGrab::new(start_data, window);
+                    pointer.set_grab(self, grab, serial, Focus::Clear);
+                    self.niri.pointer_grab_ongoing = true;
+                    self.niri
+                        .cursor_manager
+                        .set_cursor_image(CursorImageStatus::Named(edges.cursor_icon()));
+                }
+
+                self.niri.clear_layer_shell_on_demand_focus(false);
+
                 // FIXME: granular.
                 self.niri.queue_redraw_all();
             } else if let Some(output) = self.niri.output_under_cursor() {
                 self.niri.layout.activate_output(&output);
 
+                // Clicking the empty workspace area takes the focus back from layer surfaces.
+                if self.niri.pointer_focus.layer.is_none() {
+                    self.niri.clear_layer_shell_on_demand_focus(false);
+                }
+
                 // FIXME: granular.
                 self.niri.queue_redraw_all();
             }
@@ -1374,7 +2281,9 @@ impl State {
             // Wayland. If there's no bind, reset the accumulator.
             let mods = self.niri.seat.get_keyboard().unwrap().modifier_state();
             let modifiers = modifiers_from_state(mods);
-            if self.niri.mods_with_wheel_binds.contains(&modifiers) {
+            if self.niri.mods_with_wheel_binds.contains(&modifiers)
+                && !self.niri.is_input_inhibited()
+            {
                 let comp_mod = self.backend.mod_key();
 
                 let horizontal = horizontal_amount_v120.unwrap_or(0.);
@@ -1437,7 +2346,9 @@ impl State {
         if source == AxisSource::Finger {
             let mods = self.niri.seat.get_keyboard().unwrap().modifier_state();
             let modifiers = modifiers_from_state(mods);
-            if self.niri.mods_with_finger_scroll_binds.contains(&modifiers) {
+            if self.niri.mods_with_finger_scroll_binds.contains(&modifiers)
+                && !self.niri.is_input_inhibited()
+            {
                 let comp_mod = self.backend.mod_key();
 
                 let horizontal = horizontal_amount.unwrap_or(0.);
@@ -1690,7 +2601,8 @@ impl State {
     }
 
     fn on_gesture_swipe_begin<I: InputBackend>(&mut self, event: I::GestureSwipeBeginEvent) {
-        if event.fingers() == 3 {
+        // While a client inhibits input, it gets the swipes like all other input.
+        if event.fingers() == 3 && !self.niri.is_input_inhibited() {
             self.niri.gesture_swipe_3f_cumulative = Some((0., 0.));
 
             // We handled this event.
@@ -1738,6 +2650,15 @@ impl State {
             }
         }
 
+        // In a transposed layout, workspaces switch horizontally and columns scroll vertically.
+        let transposed = self
+            .niri
+            .output_under_cursor()
+            .is_some_and(|output| self.niri.layout.is_transposed(&output));
+        if transposed {
+            mem::swap(&mut delta_x, &mut delta_y);
+        }
+
         if let Some((cx, cy)) = &mut self.niri.gesture_swipe_3f_cumulative {
             *cx += delta_x;
             *cy += delta_y;
@@ -1814,6 +2735,7 @@ impl State {
             .workspace_switch_gesture_end(event.cancelled(), Some(true));
         if let Some(output) = res {
             self.niri.queue_redraw(&output);
+            self.niri.dbus_compositor_changed = true;
             handled = true;
         }
 
@@ -2044,6 +2966,41 @@ impl State {
         };
         handle.cancel(self);
     }
+
+    fn on_switch_toggle<I: InputBackend>(&mut self, evt: I::SwitchToggleEvent) {
+        let Some(switch) = evt.switch() else {
+            return;
+        };
+        let is_on = evt.state() == SwitchState::On;
+
+        if switch == Switch::Lid {
+            debug!("lid switch {}", if is_on { "closed" } else { "opened" });
+
+            if self.niri.is_lid_closed != is_on {
+                self.niri.is_lid_closed = is_on;
+                // Turn the laptop panel off or back on.
+                self.backend.on_output_config_changed(&mut self.niri);
+            }
+        } else {
+            debug!("tablet mode switch {}", if is_on { "on" } else { "off" });
+        }
+
+        let action = {
+            let config = self.niri.config.borrow();
+            let events = &config.switch_events;
+            match (switch, is_on) {
+                (Switch::Lid, true) => events.lid_close.clone(),
+                (Switch::Lid, false) => events.lid_open.clone(),
+                (Switch::TabletMode, true) => events.tablet_mode_on.clone(),
+                (Switch::TabletMode, false) => events.tablet_mode_off.clone(),
+            }
+        };
+
+        // Switches are toggled physically, so their actions run on the lock screen too.
+        for action in action.into_iter().flat_map(|action| action.actions) {
+            self.do_action(action, true);
+        }
+    }
 }
 
 /// Check whether the key should be intercepted and mark intercepted
@@ -2060,7 +3017,11 @@ fn should_intercept_key(
     pressed: bool,
     mods: ModifiersState,
     screenshot_ui: &ScreenshotUi,
+    window_hints: &WindowHints,
+    resize_mode: &ResizeMode,
     disable_power_key_handling: bool,
+    is_inhibiting_shortcuts: bool,
+    is_locked: bool,
 ) -> FilterResult<Option<Bind>> {
     // Actions are only triggered on presses, release of the key
     // shouldn't try to intercept anything unless we have marked
@@ -2072,12 +3033,24 @@ fn should_intercept_key(
     let mut final_bind = find_bind(
         bindings,
         comp_mod,
+        key_code,
         modified,
         raw,
         mods,
         disable_power_key_handling,
     );
 
+    // The focused window gets the keys of all binds that can be inhibited.
+    if is_inhibiting_shortcuts {
+        final_bind = final_bind.filter(|bind| !bind.allow_inhibiting);
+    }
+
+    // The lock screen gets the keys of all binds that don't work while locked.
+    if is_locked {
+        final_bind =
+            final_bind.filter(|bind| bind.allow_when_locked || allowed_when_locked(&bind.action));
+    }
+
     // Allow only a subset of compositor actions while the screenshot UI is open, since the user
     // cannot see the screen.
     if screenshot_ui.is_open() {
@@ -2100,6 +3073,58 @@ fn should_intercept_key(
                     action,
                     cooldown: None,
                     allow_when_locked: false,
+                    allow_inhibiting: true,
+                });
+            }
+        }
+    }
+
+    // While window hints are shown, typed letters pick a window.
+    if window_hints.is_open() {
+        let mut use_window_hints_action = true;
+
+        if let Some(bind) = &final_bind {
+            if allowed_during_screenshot(&bind.action) {
+                use_window_hints_action = false;
+            }
+        }
+
+        if use_window_hints_action {
+            if let Some(raw) = raw {
+                final_bind = window_hints.action(raw).map(|action| Bind {
+                    key: Key {
+                        trigger: Trigger::Keysym(raw),
+                        modifiers: Modifiers::empty(),
+                    },
+                    action,
+                    cooldown: None,
+                    allow_when_locked: false,
+                    allow_inhibiting: true,
+                });
+            }
+        }
+    }
+
+    // In resize mode, unmodified resize keys take precedence over the binds, unless the focused
+    // window is inhibiting shortcuts.
+    let has_mods = mods.ctrl || mods.alt || mods.shift || mods.logo;
+    if resize_mode.is_open()
+        && !has_mods
+        && !is_locked
+        && !is_inhibiting_shortcuts
+        && !screenshot_ui.is_open()
+    {
+        if let Some(raw) = raw {
+            if let Some(action) = resize_mode.action(raw) {
+                final_bind = Some(Bind {
+                    key: Key {
+                        trigger: Trigger::Keysym(raw),
+                        modifiers: Modifiers::empty(),
+                    },
+                    action,
+                    cooldown: None,
+                    allow_when_locked: false,
+                    allow_inhibiting: true,
                 });
             }
         }
@@ -2121,6 +3146,7 @@ fn should_intercept_key(
 fn find_bind(
     bindings: &Binds,
     comp_mod: CompositorMod,
+    key_code: u32,
     modified: Keysym,
     raw: Option<Keysym>,
     mods: ModifiersState,
@@ -2149,11 +3175,19 @@ fn find_bind(
             action,
             cooldown: None,
             allow_when_locked: false,
+            allow_inhibiting: false,
         });
     }
 
-    let trigger = Trigger::Keysym(raw?);
-    find_configured_bind(bindings, comp_mod, trigger, mods)
+    if let Some(raw) = raw {
+        let bind = find_configured_bind(bindings, comp_mod, Trigger::Keysym(raw), mods);
+        if bind.is_some() {
+            return bind;
+        }
+    }
+
+    // Fall back to the raw key code for keys that have no keysym, or whose keysym isn't bound.
+    find_configured_bind(bindings, comp_mod, Trigger::KeyCode(key_code), mods)
 }
 
 fn find_configured_bind(
@@ -2279,9 +3313,31 @@ fn allowed_when_locked(action: &Action) -> bool {
             | Action::Suspend
             | Action::PowerOffMonitors
             | Action::SwitchLayout(_)
+            | Action::BreakInputInhibit
     )
 }
 
+/// Swaps the column and window directions of an action for a transposed layout.
+///
+/// The compound directional actions are handled in `State::do_transposed_action()` instead.
+fn transpose_action(action: Action) -> Action {
+    match action {
+        Action::FocusColumnLeft => Action::FocusWindowUp,
+        Action::FocusColumnRight => Action::FocusWindowDown,
+        Action::FocusWindowUp => Action::FocusColumnLeft,
+        Action::FocusWindowDown => Action::FocusColumnRight,
+        Action::MoveColumnLeft => Action::MoveWindowUp,
+        Action::MoveColumnRight => Action::MoveWindowDown,
+        Action::MoveWindowUp => Action::MoveColumnLeft,
+        Action::MoveWindowDown => Action::MoveColumnRight,
+        // These are the only actions that move a window between columns, which are stacked top to
+        // bottom, so they keep naming the previous and the next column.
+        Action::ConsumeOrExpelWindowLeft => Action::ConsumeOrExpelWindowLeft,
+        Action::ConsumeOrExpelWindowRight => Action::ConsumeOrExpelWindowRight,
+        action => action,
+    }
+}
+
 fn allowed_during_screenshot(action: &Action) -> bool {
     matches!(
         action,
@@ -2467,6 +3523,8 @@ pub fn mods_with_finger_scroll_binds(comp_mod: CompositorMod, binds: &Binds) ->
 
 #[cfg(test)]
 mod tests {
+    use niri_ipc::SizeChange;
+
     use super::*;
 
     #[test]
@@ -2480,12 +3538,15 @@ mod tests {
             action: Action::CloseWindow,
             cooldown: None,
             allow_when_locked: false,
+            allow_inhibiting: true,
         }]);
 
         let comp_mod = CompositorMod::Super;
         let mut suppressed_keys = HashSet::new();
 
         let screenshot_ui = ScreenshotUi::new();
+        let window_hints = WindowHints::new();
+        let resize_mode = ResizeMode::new();
         let disable_power_key_handling = false;
 
         // The key_code we pick is arbitrary, the only thing
@@ -2503,7 +3564,11 @@ mod tests {
                 pressed,
                 mods,
                 &screenshot_ui,
+                &window_hints,
+                &resize_mode,
                 disable_power_key_handling,
+                false,
+                false,
             )
         };
 
@@ -2519,7 +3584,11 @@ mod tests {
                 pressed,
                 mods,
                 &screenshot_ui,
+                &window_hints,
+                &resize_mode,
                 disable_power_key_handling,
+                false,
+                false,
             )
         };
 
@@ -2602,6 +3671,240 @@ mod tests {
         assert!(suppressed_keys.is_empty());
     }
 
+    #[test]
+    fn inhibited_bindings_are_forwarded() {
+        let bindings = Binds(vec![
+            Bind {
+                key: Key {
+                    trigger: Trigger::Keysym(Keysym::q),
+                    modifiers: Modifiers::COMPOSITOR,
+                },
+                action: Action::CloseWindow,
+                cooldown: None,
+                allow_when_locked: false,
+                allow_inhibiting: true,
+            },
+            Bind {
+                key: Key {
+                    trigger: Trigger::Keysym(Keysym::Escape),
+                    modifiers: Modifiers::COMPOSITOR,
+                },
+                action: Action::ToggleKeyboardShortcutsInhibit,
+                cooldown: None,
+                allow_when_locked: false,
+                allow_inhibiting: false,
+            },
+        ]);
+
+        let mods = ModifiersState {
+            logo: true,
+            ..Default::default()
+        };
+        let mut suppressed_keys = HashSet::new();
+        let screenshot_ui = ScreenshotUi::new();
+        let window_hints = WindowHints::new();
+        let resize_mode = ResizeMode::new();
+
+        let mut key_event = |keysym: Keysym| {
+            should_intercept_key(
+                &mut suppressed_keys,
+                &bindings,
+                CompositorMod::Super,
+                keysym.into(),
+                keysym,
+                Some(keysym),
+                true,
+                mods,
+                &screenshot_ui,
+                &window_hints,
+                &resize_mode,
+                false,
+                true,
+                false,
+            )
+        };
+
+        assert!(matches!(key_event(Keysym::q), FilterResult::Forward));
+        assert!(matches!(
+            key_event(Keysym::Escape),
+            FilterResult::Intercept(Some(Bind {
+                action: Action::ToggleKeyboardShortcutsInhibit,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn locked_session_forwards_keys() {
+        let bindings = Binds(vec![
+            Bind {
+                key: Key {
+                    trigger: Trigger::Keysym(Keysym::q),
+                    modifiers: Modifiers::COMPOSITOR,
+                },
+                action: Action::CloseWindow,
+                cooldown: None,
+                allow_when_locked: false,
+                allow_inhibiting: true,
+            },
+            Bind {
+                key: Key {
+                    trigger: Trigger::Keysym(Keysym::XF86_AudioMute),
+                    modifiers: Modifiers::empty(),
+                },
+                action: Action::Spawn(vec![String::from("wpctl")]),
+                cooldown: None,
+                allow_when_locked: true,
+                allow_inhibiting: true,
+            },
+        ]);
+
+        let mut suppressed_keys = HashSet::new();
+        let screenshot_ui = ScreenshotUi::new();
+        let window_hints = WindowHints::new();
+        let resize_mode = ResizeMode::new();
+
+        let mut key_event = |keysym: Keysym, mods: ModifiersState, pressed: bool| {
+            should_intercept_key(
+                &mut suppressed_keys,
+                &bindings,
+                CompositorMod::Super,
+                keysym.into(),
+                keysym,
+                Some(keysym),
+                pressed,
+                mods,
+                &screenshot_ui,
+                &window_hints,
+                &resize_mode,
+                false,
+                false,
+                true,
+            )
+        };
+
+        let logo = ModifiersState {
+            logo: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            key_event(Keysym::q, logo, true),
+            FilterResult::Forward
+        ));
+        assert!(matches!(
+            key_event(Keysym::q, logo, false),
+            FilterResult::Forward
+        ));
+
+        let none = ModifiersState::default();
+        assert!(matches!(
+            key_event(Keysym::XF86_AudioMute, none, true),
+            FilterResult::Intercept(Some(Bind {
+                action: Action::Spawn(_),
+                ..
+            }))
+        ));
+        assert!(matches!(
+            key_event(Keysym::XF86_AudioMute, none, false),
+            FilterResult::Intercept(None)
+        ));
+    }
+
+    #[test]
+    fn resize_mode_keys() {
+        let bindings = Binds(vec![Bind {
+            key: Key {
+                trigger: Trigger::Keysym(Keysym::Left),
+                modifiers: Modifiers::COMPOSITOR,
+            },
+            action: Action::FocusColumnLeft,
+            cooldown: None,
+            allow_when_locked: false,
+            allow_inhibiting: true,
+        }]);
+
+        let mut suppressed_keys = HashSet::new();
+        let screenshot_ui = ScreenshotUi::new();
+        let window_hints = WindowHints::new();
+        let mut resize_mode = ResizeMode::new();
+        resize_mode.open(niri_config::ResizeMode::default());
+
+        let mut key_event = |keysym: Keysym, mods: ModifiersState| {
+            should_intercept_key(
+                &mut suppressed_keys,
+                &bindings,
+                CompositorMod::Super,
+                keysym.into(),
+                keysym,
+                Some(keysym),
+                true,
+                mods,
+                &screenshot_ui,
+                &window_hints,
+                &resize_mode,
+                false,
+                false,
+                false,
+            )
+        };
+
+        let none = ModifiersState::default();
+        assert!(matches!(
+            key_event(Keysym::Left, none),
+            FilterResult::Intercept(Some(Bind {
+                action: Action::SetColumnWidth(SizeChange::AdjustProportion(x)),
+                ..
+            })) if x == -10.
+        ));
+        assert!(matches!(
+            key_event(Keysym::Down, none),
+            FilterResult::Intercept(Some(Bind {
+                action: Action::SetWindowHeight(SizeChange::AdjustProportion(x)),
+                ..
+            })) if x == 10.
+        ));
+        assert!(matches!(
+            key_event(Keysym::Escape, none),
+            FilterResult::Intercept(Some(Bind {
+                action: Action::ExitResizeMode,
+                ..
+            }))
+        ));
+        assert!(matches!(key_event(Keysym::a, none), FilterResult::Forward));
+
+        // Modified keys still go through the binds.
+        let logo = ModifiersState {
+            logo: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            key_event(Keysym::Left, logo),
+            FilterResult::Intercept(Some(Bind {
+                action: Action::FocusColumnLeft,
+                ..
+            }))
+        ));
+
+        // A window inhibiting shortcuts gets the resize keys.
+        let inhibited = should_intercept_key(
+            &mut suppressed_keys,
+            &bindings,
+            CompositorMod::Super,
+            Keysym::Right.into(),
+            Keysym::Right,
+            Some(Keysym::Right),
+            true,
+            none,
+            &screenshot_ui,
+            &window_hints,
+            &resize_mode,
+            false,
+            true,
+            false,
+        );
+        assert!(matches!(inhibited, FilterResult::Forward));
+    }
+
     #[test]
     fn comp_mod_handling() {
         let bindings = Binds(vec![
@@ -2613,6 +3916,7 @@ mod tests {
                 action: Action::CloseWindow,
                 cooldown: None,
                 allow_when_locked: false,
+                allow_inhibiting: true,
             },
             Bind {
                 key: Key {
@@ -2622,6 +3926,7 @@ mod tests {
                 action: Action::FocusColumnLeft,
                 cooldown: None,
                 allow_when_locked: false,
+                allow_inhibiting: true,
             },
             Bind {
                 key: Key {
@@ -2631,6 +3936,7 @@ mod tests {
                 action: Action::FocusWindowDown,
                 cooldown: None,
                 allow_when_locked: false,
+                allow_inhibiting: true,
             },
             Bind {
                 key: Key {
@@ -2640,6 +3946,7 @@ mod tests {
                 action: Action::FocusWindowUp,
                 cooldown: None,
                 allow_when_locked: false,
+                allow_inhibiting: true,
             },
             Bind {
                 key: Key {
@@ -2649,6 +3956,7 @@ mod tests {
                 action: Action::FocusColumnRight,
                 cooldown: None,
                 allow_when_locked: false,
+                allow_inhibiting: true,
             },
         ]);
 
@@ -2771,4 +4079,38 @@ mod tests {
             None,
         );
     }
+
+    #[test]
+    fn transpose_action_swaps_columns_and_windows() {
+        assert_eq!(
+            transpose_action(Action::FocusColumnRight),
+            Action::FocusWindowDown
+        );
+        assert_eq!(
+            transpose_action(Action::MoveWindowUp),
+            Action::MoveColumnLeft
+        );
+        for action in [
+            Action::FocusWorkspaceDown,
+            Action::ConsumeOrExpelWindowLeft,
+            Action::ConsumeOrExpelWindowRight,
+            Action::SwapWithPrevious,
+        ] {
+            assert_eq!(transpose_action(action.clone()), action);
+        }
+
+        let actions = [
+            Action::FocusColumnLeft,
+            Action::FocusColumnRight,
+            Action::FocusWindowUp,
+            Action::FocusWindowDown,
+            Action::MoveColumnLeft,
+            Action::MoveColumnRight,
+            Action::MoveWindowUp,
+            Action::MoveWindowDown,
+        ];
+        for action in actions {
+            assert_eq!(transpose_action(transpose_action(action.clone())), action);
+        }
+    }
 }
@@ -0,0 +1,264 @@
+use std::time::Duration;
+
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::RegistrationToken;
+use smithay::desktop::Window;
+use smithay::input::pointer::{
+    AxisFrame, ButtonEvent, CursorImageStatus, GestureHoldBeginEvent, GestureHoldEndEvent,
+    GesturePinchBeginEvent, GesturePinchEndEvent, GesturePinchUpdateEvent, GestureSwipeBeginEvent,
+    GestureSwipeEndEvent, GestureSwipeUpdateEvent, GrabStartData as PointerGrabStartData,
+    MotionEvent, PointerGrab, PointerInnerHandle, RelativeMotionEvent,
+};
+use smithay::input::SeatHandler;
+use smithay::output::Output;
+use smithay::utils::{IsAlive, Logical, Point};
+
+use crate::niri::State;
+use crate::utils::get_monotonic_time;
+
+pub struct MoveGrab {
+    start_data: PointerGrabStartData<State>,
+    window: Window,
+    output: Output,
+    /// Timer that moves the window held at the output edge to the workspace there, along with
+    /// the time it fires at.
+    edge_switch_timer: Option<(RegistrationToken, Duration)>,
+}
+
+impl MoveGrab {
+    pub fn new(start_data: PointerGrabStartData<State>, window: Window, output: Output) -> Self {
+        Self {
+            start_data,
+            window,
+            output,
+            edge_switch_timer: None,
+        }
+    }
+
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    pub fn output(&self) -> &Output {
+        &self.output
+    }
+
+    /// Returns whether the pointer moved far enough from the click to start moving.
+    pub fn is_past_drag_threshold(&self, location: Point<f64, Logical>) -> bool {
+        let c = location - self.start_data.location;
+
+        // Threshold copied from GTK 4.
+        c.x * c.x + c.y * c.y >= 8. * 8.
+    }
+
+    /// Keeps the edge switch timer in line with the window held at the output edge.
+    fn update_edge_switch_timer(&mut self, state: &mut State) {
+        let switch_time = state
+            .niri
+            .layout
+            .interactive_move_edge_switch_time(&self.output);
+        if self.edge_switch_timer.map(|(_, time)| time) == switch_time {
+            return;
+        }
+
+        if let Some((token, _)) = self.edge_switch_timer.take() {
+            state.niri.event_loop.remove(token);
+        }
+
+        let Some(switch_time) = switch_time else {
+            return;
+        };
+
+        let output = self.output.clone();
+        let timer = Timer::from_duration(switch_time.saturating_sub(get_monotonic_time()));
+        let token = state
+            .niri
+            .event_loop
+            .insert_source(timer, move |_, _, state| {
+                let now = get_monotonic_time();
+                let layout = &mut state.niri.layout;
+                if layout.interactive_move_switch_at_edge(&output, now) {
+                    state.niri.dbus_compositor_changed = true;
+                    // FIXME: granular.
+                    state.niri.queue_redraw_all();
+                    return TimeoutAction::Drop;
+                }
+
+                // The timer may fire a tiny bit early.
+                match layout.interactive_move_edge_switch_time(&output) {
+                    Some(time) => TimeoutAction::ToDuration(time.saturating_sub(now)),
+                    None => TimeoutAction::Drop,
+                }
+            })
+            .unwrap();
+        self.edge_switch_timer = Some((token, switch_time));
+    }
+
+    fn on_ungrab(&mut self, state: &mut State) {
+        if let Some((token, _)) = self.edge_switch_timer.take() {
+            state.niri.event_loop.remove(token);
+        }
+
+        state.niri.layout.interactive_move_end(&self.output);
+        // The move may have reordered the columns.
+        state.niri.dbus_compositor_changed = true;
+        state.niri.pointer_grab_ongoing = false;
+        state
+            .niri
+            .cursor_manager
+            .set_cursor_image(CursorImageStatus::default_named());
+    }
+}
+
+impl PointerGrab<State> for MoveGrab {
+    fn motion(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        _focus: Option<(<State as SeatHandler>::PointerFocus, Point<f64, Logical>)>,
+        event: &MotionEvent,
+    ) {
+        // While the grab is active, no client has pointer focus.
+        handle.motion(data, None, event);
+
+        if self.window.alive() {
+            let output_geo = data.niri.global_space.output_geometry(&self.output);
+            if let Some(output_geo) = output_geo {
+                let pos_within_output = event.location - output_geo.loc.to_f64();
+                let ongoing = data.niri.layout.interactive_move_update(
+                    &self.window,
+                    &self.output,
+                    pos_within_output,
+                );
+                if ongoing {
+                    self.update_edge_switch_timer(data);
+
+                    // FIXME: granular.
+                    data.niri.queue_redraw_all();
+                    return;
+                }
+            }
+        }
+
+        // The move is no longer ongoing.
+        handle.unset_grab(self, data, event.serial, event.time, true);
+    }
+
+    fn relative_motion(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        _focus: Option<(<State as SeatHandler>::PointerFocus, Point<f64, Logical>)>,
+        event: &RelativeMotionEvent,
+    ) {
+        // While the grab is active, no client has pointer focus.
+        handle.relative_motion(data, None, event);
+    }
+
+    fn button(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &ButtonEvent,
+    ) {
+        handle.button(data, event);
+
+        if handle.current_pressed().is_empty() {
+            // No more buttons are pressed, release the grab.
+            handle.unset_grab(self, data, event.serial, event.time, true);
+        }
+    }
+
+    fn axis(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        details: AxisFrame,
+    ) {
+        handle.axis(data, details);
+    }
+
+    fn frame(&mut self, data: &mut State, handle: &mut PointerInnerHandle<'_, State>) {
+        handle.frame(data);
+    }
+
+    fn gesture_swipe_begin(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GestureSwipeBeginEvent,
+    ) {
+        handle.gesture_swipe_begin(data, event);
+    }
+
+    fn gesture_swipe_update(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GestureSwipeUpdateEvent,
+    ) {
+        handle.gesture_swipe_update(data, event);
+    }
+
+    fn gesture_swipe_end(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GestureSwipeEndEvent,
+    ) {
+        handle.gesture_swipe_end(data, event);
+    }
+
+    fn gesture_pinch_begin(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GesturePinchBeginEvent,
+    ) {
+        handle.gesture_pinch_begin(data, event);
+    }
+
+    fn gesture_pinch_update(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GesturePinchUpdateEvent,
+    ) {
+        handle.gesture_pinch_update(data, event);
+    }
+
+    fn gesture_pinch_end(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GesturePinchEndEvent,
+    ) {
+        handle.gesture_pinch_end(data, event);
+    }
+
+    fn gesture_hold_begin(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GestureHoldBeginEvent,
+    ) {
+        handle.gesture_hold_begin(data, event);
+    }
+
+    fn gesture_hold_end(
+        &mut self,
+        data: &mut State,
+        handle: &mut PointerInnerHandle<'_, State>,
+        event: &GestureHoldEndEvent,
+    ) {
+        handle.gesture_hold_end(data, event);
+    }
+
+    fn start_data(&self) -> &PointerGrabStartData<State> {
+        &self.start_data
+    }
+
+    fn unset(&mut self, data: &mut State) {
+        self.on_ungrab(data);
+    }
+}
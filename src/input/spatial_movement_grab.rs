@@ -48,6 +48,7 @@ impl SpatialMovementGrab {
 
         if let Some(output) = res {
             state.niri.queue_redraw(&output);
+            state.niri.dbus_compositor_changed = true;
         }
 
         state.niri.pointer_grab_ongoing = false;
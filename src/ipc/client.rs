@@ -13,7 +13,14 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
         Msg::Outputs => Request::Outputs,
         Msg::FocusedWindow => Request::FocusedWindow,
         Msg::FocusedOutput => Request::FocusedOutput,
+        Msg::FocusHistory => Request::FocusHistory,
+        Msg::MinimizedWindows => Request::MinimizedWindows,
+        Msg::CursorPosition => Request::CursorPosition,
+        Msg::PickWindow => Request::PickWindow,
+        Msg::PickOutput => Request::PickOutput,
+        Msg::TextureCacheStats => Request::TextureCacheStats,
         Msg::Action { action } => Request::Action(action.clone()),
+        Msg::Actions { actions } => Request::Actions(actions.clone()),
         Msg::Output { output, action } => Request::Output {
             output: output.clone(),
             action: action.clone(),
@@ -168,7 +175,138 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
                 println!("No output is focused.");
             }
         }
-        Msg::Action { .. } => {
+        Msg::FocusHistory => {
+            let Response::FocusHistory(mut response) = response else {
+                bail!("unexpected response: expected FocusHistory, got {response:?}");
+            };
+
+            if json {
+                let response =
+                    serde_json::to_string(&response).context("error formatting response")?;
+                println!("{response}");
+                return Ok(());
+            }
+
+            response.sort_by(|a, b| a.output.cmp(&b.output));
+
+            for history in response {
+                println!("Output \"{}\":", history.output);
+
+                if history.windows.is_empty() {
+                    println!("  No windows.");
+                }
+
+                for (idx, window) in history.windows.iter().enumerate() {
+                    let is_current = if Some(idx) == history.current {
+                        " * "
+                    } else {
+                        "   "
+                    };
+                    let title = window.title.as_deref().unwrap_or("(unset)");
+                    let app_id = window.app_id.as_deref().unwrap_or("(unset)");
+                    println!("{is_current}\"{title}\" ({app_id})");
+                }
+
+                println!();
+            }
+        }
+        Msg::MinimizedWindows => {
+            let Response::MinimizedWindows(response) = response else {
+                bail!("unexpected response: expected MinimizedWindows, got {response:?}");
+            };
+
+            if json {
+                let response =
+                    serde_json::to_string(&response).context("error formatting response")?;
+                println!("{response}");
+                return Ok(());
+            }
+
+            if response.is_empty() {
+                println!("No minimized windows.");
+            }
+
+            for window in response {
+                let title = window.title.as_deref().unwrap_or("(unset)");
+                let app_id = window.app_id.as_deref().unwrap_or("(unset)");
+                println!("\"{title}\" ({app_id})");
+            }
+        }
+        Msg::CursorPosition => {
+            let Response::CursorPosition(response) = response else {
+                bail!("unexpected response: expected CursorPosition, got {response:?}");
+            };
+
+            if json {
+                let response =
+                    serde_json::to_string(&response).context("error formatting response")?;
+                println!("{response}");
+                return Ok(());
+            }
+
+            println!("Global: {}, {}", response.x, response.y);
+            if let Some(output) = response.output {
+                println!("On output \"{}\": {}, {}", output.name, output.x, output.y);
+            }
+        }
+        Msg::PickWindow => {
+            let Response::PickedWindow(response) = response else {
+                bail!("unexpected response: expected PickedWindow, got {response:?}");
+            };
+
+            if json {
+                let response =
+                    serde_json::to_string(&response).context("error formatting response")?;
+                println!("{response}");
+                return Ok(());
+            }
+
+            if let Some(window) = response {
+                let title = window.title.as_deref().unwrap_or("(unset)");
+                let app_id = window.app_id.as_deref().unwrap_or("(unset)");
+                println!("Window {}: \"{title}\" ({app_id})", window.id);
+            } else {
+                println!("No window picked.");
+            }
+        }
+        Msg::PickOutput => {
+            let Response::PickedOutput(response) = response else {
+                bail!("unexpected response: expected PickedOutput, got {response:?}");
+            };
+
+            if json {
+                let response =
+                    serde_json::to_string(&response).context("error formatting response")?;
+                println!("{response}");
+                return Ok(());
+            }
+
+            if let Some(output) = response {
+                println!("{output}");
+            } else {
+                println!("No output picked.");
+            }
+        }
+        Msg::TextureCacheStats => {
+            let Response::TextureCacheStats(response) = response else {
+                bail!("unexpected response: expected TextureCacheStats, got {response:?}");
+            };
+
+            if json {
+                let response =
+                    serde_json::to_string(&response).context("error formatting response")?;
+                println!("{response}");
+                return Ok(());
+            }
+
+            let mib = |bytes: u64| bytes as f64 / f64::from(1 << 20);
+            println!("Budget: {:.1} MiB", mib(response.budget));
+            println!("UI elements: {:.1} MiB", mib(response.ui));
+            println!("Closing windows: {:.1} MiB", mib(response.closing_windows));
+            println!("Screenshot UI: {:.1} MiB", mib(response.screenshot_ui));
+            println!("Evictions: {}", response.evictions);
+        }
+        Msg::Action { .. } | Msg::Actions { .. } => {
             let Response::Handled = response else {
                 bail!("unexpected response: expected Handled, got {response:?}");
             };
@@ -235,7 +373,14 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
                 } else {
                     String::new()
                 };
-                println!("{is_active}{idx}{name}");
+                let urgent = if ws.is_urgent { " (urgent)" } else { "" };
+                let count = ws.window_count;
+                let windows = if count == 1 { "window" } else { "windows" };
+                println!("{is_active}{idx}{name}: {count} {windows}{urgent}");
+
+                if let Some(title) = ws.active_window.as_ref().and_then(|w| w.title.as_deref()) {
+                    println!("       Active window: \"{title}\"");
+                }
             }
         }
     }
@@ -8,7 +8,7 @@ use calloop::io::Async;
 use directories::BaseDirs;
 use futures_util::io::{AsyncReadExt, BufReader};
 use futures_util::{AsyncBufReadExt, AsyncWriteExt};
-use niri_ipc::{OutputConfigChanged, Reply, Request, Response};
+use niri_ipc::{Action, OutputConfigChanged, Reply, Request, Response};
 use smithay::desktop::Window;
 use smithay::reexports::calloop::generic::Generic;
 use smithay::reexports::calloop::{Interest, LoopHandle, Mode, PostAction};
@@ -17,7 +17,7 @@ use smithay::wayland::compositor::with_states;
 use smithay::wayland::shell::xdg::XdgToplevelSurfaceData;
 
 use crate::backend::IpcOutputMap;
-use crate::niri::State;
+use crate::niri::{PickRequest, State};
 use crate::utils::version;
 
 pub struct IpcServer {
@@ -89,11 +89,7 @@ fn on_new_ipc_client(state: &mut State, stream: UnixStream) {
         }
     };
 
-    let ctx = ClientCtx {
-        event_loop: state.niri.event_loop.clone(),
-        ipc_outputs: state.backend.ipc_outputs(),
-        ipc_focused_window: state.niri.ipc_focused_window.clone(),
-    };
+    let ctx = ClientCtx::new(state);
 
     let future = async move {
         if let Err(err) = handle_client(ctx, stream).await {
@@ -105,6 +101,39 @@ fn on_new_ipc_client(state: &mut State, stream: UnixStream) {
     }
 }
 
+/// Processes a request that arrived through another transport, such as D-Bus.
+pub fn process_request(state: &mut State, request: Request, reply: async_channel::Sender<Reply>) {
+    let ctx = ClientCtx::new(state);
+
+    let future = async move {
+        let requested_error = matches!(request, Request::ReturnError);
+        let rv = process(&ctx, request).await;
+
+        if let Err(err) = &rv {
+            if !requested_error {
+                warn!("error processing IPC request: {err:?}");
+            }
+        }
+
+        if let Err(err) = reply.send(rv).await {
+            warn!("error sending IPC reply: {err:?}");
+        }
+    };
+    if let Err(err) = state.niri.scheduler.schedule(future) {
+        warn!("error scheduling IPC request future: {err:?}");
+    }
+}
+
+impl ClientCtx {
+    fn new(state: &State) -> Self {
+        Self {
+            event_loop: state.niri.event_loop.clone(),
+            ipc_outputs: state.backend.ipc_outputs(),
+            ipc_focused_window: state.niri.ipc_focused_window.clone(),
+        }
+    }
+}
+
 async fn handle_client(ctx: ClientCtx, stream: Async<'_, UnixStream>) -> anyhow::Result<()> {
     let (read, mut write) = stream.split();
     let mut buf = String::new();
@@ -147,25 +176,12 @@ async fn process(ctx: &ClientCtx, request: Request) -> Reply {
         }
         Request::FocusedWindow => {
             let window = ctx.ipc_focused_window.lock().unwrap().clone();
-            let window = window.map(|window| {
-                let wl_surface = window.toplevel().expect("no X11 support").wl_surface();
-                with_states(wl_surface, |states| {
-                    let role = states
-                        .data_map
-                        .get::<XdgToplevelSurfaceData>()
-                        .unwrap()
-                        .lock()
-                        .unwrap();
-
-                    niri_ipc::Window {
-                        title: role.title.clone(),
-                        app_id: role.app_id.clone(),
-                    }
-                })
-            });
+            let window = window.as_ref().map(ipc_window);
             Response::FocusedWindow(window)
         }
         Request::Action(action) => {
+            validate_action(&action)?;
+
             let (tx, rx) = async_channel::bounded(1);
 
             let action = niri_config::Action::from(action);
@@ -180,6 +196,27 @@ async fn process(ctx: &ClientCtx, request: Request) -> Reply {
             let _ = rx.recv().await;
             Response::Handled
         }
+        Request::Actions(actions) => {
+            for action in &actions {
+                validate_action(action)?;
+            }
+
+            let (tx, rx) = async_channel::bounded(1);
+
+            let actions: Vec<_> = actions.into_iter().map(niri_config::Action::from).collect();
+            ctx.event_loop.insert_idle(move |state| {
+                // Run the whole batch from a single idle callback, so that the layout refresh,
+                // the window configures and the animations happen once, with the final state.
+                for action in actions {
+                    state.do_action(action, false);
+                }
+                state.niri.layout.finish_batch();
+                let _ = tx.send_blocking(());
+            });
+
+            let _ = rx.recv().await;
+            Response::Handled
+        }
         Request::Output { output, action } => {
             let ipc_outputs = ctx.ipc_outputs.lock().unwrap();
             let found = ipc_outputs
@@ -198,10 +235,68 @@ async fn process(ctx: &ClientCtx, request: Request) -> Reply {
 
             Response::OutputConfigChanged(response)
         }
+        Request::FocusHistory => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let history = state.niri.ipc_focus_history();
+                let _ = tx.send_blocking(history);
+            });
+            let result = rx.recv().await;
+            let history = result.map_err(|_| String::from("error getting focus history"))?;
+            Response::FocusHistory(history)
+        }
+        Request::MinimizedWindows => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let windows = state.niri.ipc_minimized_windows();
+                let _ = tx.send_blocking(windows);
+            });
+            let result = rx.recv().await;
+            let windows = result.map_err(|_| String::from("error getting minimized windows"))?;
+            Response::MinimizedWindows(windows)
+        }
+        Request::CursorPosition => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let position = state.niri.ipc_cursor_position();
+                let _ = tx.send_blocking(position);
+            });
+            let result = rx.recv().await;
+            let position = result.map_err(|_| String::from("error getting cursor position"))?;
+            Response::CursorPosition(position)
+        }
+        Request::PickWindow => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                state.niri.start_pick(PickRequest::Window(tx));
+            });
+            let result = rx.recv().await;
+            let window = result.map_err(|_| String::from("error picking window"))?;
+            Response::PickedWindow(window)
+        }
+        Request::PickOutput => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                state.niri.start_pick(PickRequest::Output(tx));
+            });
+            let result = rx.recv().await;
+            let output = result.map_err(|_| String::from("error picking output"))?;
+            Response::PickedOutput(output)
+        }
+        Request::TextureCacheStats => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let stats = state.niri.ipc_texture_cache_stats();
+                let _ = tx.send_blocking(stats);
+            });
+            let result = rx.recv().await;
+            let stats = result.map_err(|_| String::from("error getting texture cache stats"))?;
+            Response::TextureCacheStats(stats)
+        }
         Request::Workspaces => {
             let (tx, rx) = async_channel::bounded(1);
             ctx.event_loop.insert_idle(move |state| {
-                let workspaces = state.niri.layout.ipc_workspaces();
+                let workspaces = state.niri.ipc_workspaces();
                 let _ = tx.send_blocking(workspaces);
             });
             let result = rx.recv().await;
@@ -237,3 +332,37 @@ async fn process(ctx: &ClientCtx, request: Request) -> Reply {
 
     Ok(response)
 }
+
+/// Checks the action arguments that the command line parser would have checked.
+fn validate_action(action: &Action) -> Result<(), String> {
+    if let Action::FocusWorkspace {
+        reference: None,
+        name,
+    } = action
+    {
+        if name.as_deref().map_or(true, str::is_empty) {
+            return Err(String::from(
+                "focus-workspace needs a workspace reference or a non-empty name",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn ipc_window(window: &Window) -> niri_ipc::Window {
+    let wl_surface = window.toplevel().expect("no X11 support").wl_surface();
+    with_states(wl_surface, |states| {
+        let role = states
+            .data_map
+            .get::<XdgToplevelSurfaceData>()
+            .unwrap()
+            .lock()
+            .unwrap();
+
+        niri_ipc::Window {
+            title: role.title.clone(),
+            app_id: role.app_id.clone(),
+        }
+    })
+}
@@ -20,6 +20,7 @@ use crate::render_helpers::shader_element::ShaderRenderElement;
 use crate::render_helpers::shaders::{mat3_uniform, ProgramType, Shaders};
 use crate::render_helpers::snapshot::RenderSnapshot;
 use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::render_helpers::texture_cache::texture_bytes;
 use crate::render_helpers::{render_to_encompassing_texture, RenderTarget};
 
 #[derive(Debug)]
@@ -120,6 +121,10 @@ impl ClosingWindow {
         !self.anim.is_done()
     }
 
+    pub fn texture_bytes(&self) -> u64 {
+        texture_bytes(self.buffer.texture()) + texture_bytes(self.blocked_out_buffer.texture())
+    }
+
     pub fn render(
         &self,
         renderer: &mut GlesRenderer,
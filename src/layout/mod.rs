@@ -23,48 +23,68 @@
 //! workspace just like any other. Then they come back, reconnect the second monitor, and now we
 //! don't want an unassuming workspace to end up on it.
 //!
-//! ## Workspaces-only-on-primary considerations
+//! ## Workspaces on secondary outputs
 //!
-//! If this logic results in more than one workspace present on a secondary output, then as a
-//! compromise we only keep the first workspace there, and move the rest to the primary output,
-//! making the primary output their original output.
+//! Every output has its own independent stack of workspaces, so this logic can result in more
+//! than one workspace on a secondary output. All of them move back when the output reconnects,
+//! keeping their order.
 
 use std::cmp::min;
+use std::iter::zip;
 use std::mem;
 use std::rc::Rc;
 use std::time::Duration;
 
-use niri_config::{CenterFocusedColumn, Config, FloatOrInt, Struts, Workspace as WorkspaceConfig};
-use niri_ipc::SizeChange;
+use niri_config::{
+    CenterFocusedColumn, Config, DialogPlacement, FloatOrInt, LayoutOverride, OutputIdentity,
+    Struts, Workspace as WorkspaceConfig,
+};
+use niri_ipc::{LayoutStrategyName, OpacityChange, SizeChange};
 use smithay::backend::renderer::element::surface::WaylandSurfaceRenderElement;
 use smithay::backend::renderer::element::Id;
 use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
 use smithay::output::{self, Output};
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
-use smithay::utils::{Logical, Point, Scale, Serial, Size, Transform};
+use smithay::utils::{Logical, Point, Rectangle, Scale, Serial, Size, Transform};
 
 pub use self::monitor::MonitorRenderElement;
 use self::monitor::{Monitor, WorkspaceSwitch};
-use self::workspace::{compute_working_area, Column, ColumnWidth, OutputId, Workspace};
+use self::snapshot::LayoutSnapshot;
+use self::strategy::strategy_from_name;
+use self::workspace::{
+    compute_working_area, Column, ColumnWidth, MovedWindow, OutputId, WindowHeight, Workspace,
+};
 use crate::niri_render_elements;
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::render_helpers::snapshot::RenderSnapshot;
 use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
 use crate::render_helpers::texture::TextureBuffer;
 use crate::render_helpers::{BakedBuffer, RenderTarget, SplitElements};
-use crate::utils::{output_size, round_logical_in_physical_max1, ResizeEdge};
+use crate::utils::transaction::Transaction;
+use crate::utils::{
+    fuzzy_match_score, output_size, round_logical_in_physical_max1, ResizeEdge, Transpose,
+};
 use crate::window::ResolvedWindowRules;
 
 pub mod closing_window;
 pub mod focus_ring;
 pub mod monitor;
 pub mod opening_window;
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod ops;
+pub mod preset;
+pub mod snapshot;
+pub mod strategy;
+pub mod tab_indicator;
 pub mod tile;
 pub mod workspace;
 
 /// Size changes up to this many pixels don't animate.
 pub const RESIZE_ANIMATION_THRESHOLD: f64 = 10.;
 
+/// Smallest workspace zoom, below which the windows become unreadable.
+pub const MIN_ZOOM: f64 = 0.25;
+
 niri_render_elements! {
     LayoutElementRenderElement<R> => {
         Wayland = WaylandSurfaceRenderElement<R>,
@@ -140,10 +160,26 @@ pub trait LayoutElement {
         self.render(renderer, location, scale, alpha, target).popups
     }
 
-    fn request_size(&mut self, size: Size<i32, Logical>, animate: bool);
+    /// Requests the element to change its size.
+    ///
+    /// The element should hold onto the transaction until it commits the new size.
+    fn request_size(
+        &mut self,
+        size: Size<i32, Logical>,
+        animate: bool,
+        transaction: Option<Transaction>,
+    );
     fn request_fullscreen(&self, size: Size<i32, Logical>);
+
+    /// Size last requested from the element, whether or not it was sent in a configure yet.
+    fn requested_size(&self) -> Option<Size<i32, Logical>>;
+
     fn min_size(&self) -> Size<i32, Logical>;
     fn max_size(&self) -> Size<i32, Logical>;
+
+    /// Whether the element is a dialog that should keep its own size rather than fill a column.
+    fn is_dialog(&self) -> bool;
+
     fn is_wl_surface(&self, wl_surface: &WlSurface) -> bool;
     fn has_ssd(&self) -> bool;
     fn set_preferred_scale_transform(&self, scale: output::Scale, transform: Transform);
@@ -154,8 +190,60 @@ pub trait LayoutElement {
     fn set_active_in_column(&mut self, active: bool);
     fn set_bounds(&self, bounds: Size<i32, Logical>);
 
+    /// Sets the edges along which the element is tiled against other elements.
+    ///
+    /// `None` means that the element isn't tiled at all, like fullscreen and floating elements.
+    fn set_tiled(&mut self, edges: Option<ResizeEdge>);
+
+    /// Sets whether the element takes up the whole working area on its own.
+    fn set_maximized(&mut self, maximized: bool);
+
+    /// Sets whether the element is hidden from view, so it can stop its animations.
+    fn set_suspended(&mut self, suspended: bool);
+
     fn send_pending_configure(&mut self);
 
+    /// Serial of the latest configure sent to the element.
+    fn configure_serial(&self) -> Option<Serial>;
+
+    /// Size requested in the latest configure sent to the element.
+    ///
+    /// A zero component means that the element was free to pick that dimension. Returns `None` if
+    /// the element was free to pick its whole size.
+    fn expected_size(&self) -> Option<Size<i32, Logical>>;
+
+    /// Serial of the latest configure that the element has acked and committed.
+    fn accepted_serial(&self) -> Option<Serial>;
+
+    /// Whether the element has yet to commit the latest configure sent to it.
+    fn has_pending_configure(&self) -> bool {
+        let Some(sent) = self.configure_serial() else {
+            return false;
+        };
+
+        !self
+            .accepted_serial()
+            .is_some_and(|accepted| accepted.is_no_older_than(&sent))
+    }
+
+    /// Whether the element has committed the latest configure, but with a different size.
+    ///
+    /// Unlike [`LayoutElement::has_pending_configure()`], waiting for longer won't help here: the
+    /// element has seen the requested size and decided against it, for example due to its own
+    /// size constraints.
+    fn is_refusing_expected_size(&self) -> bool {
+        if self.has_pending_configure() {
+            return false;
+        }
+
+        let Some(expected) = self.expected_size() else {
+            return false;
+        };
+
+        let size = self.size();
+        (expected.w != 0 && expected.w != size.w) || (expected.h != 0 && expected.h != size.h)
+    }
+
     /// Whether the element is currently fullscreen.
     ///
     /// This will *not* switch immediately after a [`LayoutElement::request_fullscreen()`] call.
@@ -186,6 +274,17 @@ pub struct Layout<W: LayoutElement> {
     monitor_set: MonitorSet<W>,
     /// Configurable properties of the layout.
     options: Rc<Options>,
+    /// Layout settings overridden per output, by output name.
+    output_layouts: Vec<(String, LayoutOverride)>,
+    /// Name of the output that should become primary when connected.
+    primary_output: Option<String>,
+    /// How workspaces recognize their original output.
+    output_identity: OutputIdentity,
+    /// Primary output that got disconnected, to make primary again when it comes back.
+    ///
+    /// This way, closing the laptop lid hands the primary role over to an external monitor, and
+    /// opening it takes the role back.
+    disconnected_primary: Option<OutputId>,
 }
 
 #[derive(Debug)]
@@ -206,6 +305,14 @@ enum MonitorSet<W: LayoutElement> {
     },
 }
 
+/// Resolved layout options.
+///
+/// Options are resolved in layers: the global layout config, then the overrides of the output,
+/// then the overrides of the workspace (both from the config and changed at runtime), and finally
+/// adjusted for the output scale. Tiles additionally resolve their window rules on top.
+///
+/// Monitors hold the options resolved up to the output layer, which serve as the base options of
+/// their workspaces.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Options {
     /// Padding around windows in logical pixels.
@@ -214,11 +321,28 @@ pub struct Options {
     pub struts: Struts,
     pub focus_ring: niri_config::FocusRing,
     pub border: niri_config::Border,
+    pub tab_indicator: niri_config::TabIndicator,
     pub center_focused_column: CenterFocusedColumn,
     /// Column widths that `toggle_width()` switches between.
     pub preset_widths: Vec<ColumnWidth>,
     /// Initial width for new columns.
     pub default_width: Option<ColumnWidth>,
+    /// Whether switching workspaces up from the first one goes to the last one, and vice versa.
+    pub workspace_wrap_around: bool,
+    /// Maximum number of columns that auto-tiling workspaces fit into the view.
+    pub auto_tile_max_columns: usize,
+    /// How much to darken unfocused windows, from 0 to 1.
+    pub dim_inactive: f64,
+    /// How much to darken windows behind a focused dialog, from 0 to 1.
+    pub dim_behind_modal: f64,
+    /// Where dialogs with a parent window open in the floating layer.
+    pub dialog_placement: DialogPlacement,
+    /// Render-time scale of the workspace contents, from `MIN_ZOOM` to 1.
+    pub zoom: f64,
+    /// Whether workspaces are arranged horizontally and columns vertically.
+    pub transposed: bool,
+    /// Whether to transpose the layout on outputs that are taller than they are wide.
+    pub transpose_portrait_outputs: bool,
     pub animations: niri_config::Animations,
 }
 
@@ -229,6 +353,7 @@ impl Default for Options {
             struts: Default::default(),
             focus_ring: Default::default(),
             border: Default::default(),
+            tab_indicator: Default::default(),
             center_focused_column: Default::default(),
             preset_widths: vec![
                 ColumnWidth::Proportion(1. / 3.),
@@ -236,6 +361,14 @@ impl Default for Options {
                 ColumnWidth::Proportion(2. / 3.),
             ],
             default_width: None,
+            workspace_wrap_around: false,
+            auto_tile_max_columns: 3,
+            dim_inactive: 0.,
+            dim_behind_modal: 0.,
+            dialog_placement: Default::default(),
+            zoom: 1.,
+            transposed: false,
+            transpose_portrait_outputs: false,
             animations: Default::default(),
         }
     }
@@ -269,19 +402,102 @@ impl Options {
             struts: layout.struts,
             focus_ring: layout.focus_ring,
             border: layout.border,
+            tab_indicator: layout.tab_indicator,
             center_focused_column: layout.center_focused_column,
             preset_widths,
             default_width,
+            workspace_wrap_around: layout.workspace_wrap_around,
+            auto_tile_max_columns: usize::from(layout.auto_tile_max_columns.0.get()),
+            dim_inactive: layout.dim_inactive.0,
+            dim_behind_modal: layout.dim_behind_modal.0,
+            dialog_placement: layout.dialog_placement,
+            zoom: 1.,
+            transposed: layout.transposed,
+            transpose_portrait_outputs: layout.transpose_portrait_outputs,
             animations: config.animations.clone(),
         }
     }
 
+    fn with_override(mut self, layout: &LayoutOverride) -> Self {
+        if let Some(gaps) = layout.gaps {
+            self.gaps = gaps.0;
+        }
+        if let Some(width) = &layout.default_column_width {
+            self.default_width = width.0.map(ColumnWidth::from);
+        }
+        if let Some(center) = layout.center_focused_column {
+            self.center_focused_column = center;
+        }
+        if let Some(dim) = layout.dim_inactive {
+            self.dim_inactive = dim.0;
+        }
+        if let Some(zoom) = layout.zoom {
+            self.zoom = zoom.0.max(MIN_ZOOM);
+        }
+
+        self
+    }
+
+    /// Swaps the axes of a value if the layout is transposed.
+    ///
+    /// This converts both from the output coordinates to the layout ones and back.
+    fn swap_axes<T: Transpose>(&self, value: T) -> T {
+        if self.transposed {
+            value.transpose()
+        } else {
+            value
+        }
+    }
+
+    /// Resolves the options for a workspace with these base options.
+    fn resolve_for_workspace(
+        &self,
+        layout: &LayoutOverride,
+        scale: f64,
+        output_size: Size<f64, Logical>,
+    ) -> Self {
+        Options::clone(self)
+            .with_override(layout)
+            .adjusted_for_scale(scale)
+            .adjusted_for_output_size(output_size)
+    }
+
+    /// Returns whether the layout is transposed on an output of this size.
+    fn is_transposed_for_output_size(&self, size: Size<f64, Logical>) -> bool {
+        self.transposed || (self.transpose_portrait_outputs && size.w < size.h)
+    }
+
+    /// Resolves the width of a new column for a window, falling back to the window size.
+    fn resolve_column_width<W: LayoutElement>(
+        &self,
+        window: &W,
+        width: Option<ColumnWidth>,
+    ) -> ColumnWidth {
+        let size = self.swap_axes(window.size());
+        let mut width = width.unwrap_or_else(|| ColumnWidth::Fixed(f64::from(size.w)));
+        if let ColumnWidth::Fixed(w) = &mut width {
+            let rules = window.rules();
+            let border_config = rules.border.resolve_against(self.border);
+            if !border_config.off {
+                *w += border_config.width.0 * 2.;
+            }
+        }
+        width
+    }
+
+    fn adjusted_for_output_size(mut self, size: Size<f64, Logical>) -> Self {
+        self.transposed = self.is_transposed_for_output_size(size);
+        self
+    }
+
     fn adjusted_for_scale(mut self, scale: f64) -> Self {
         let round = |logical: f64| round_logical_in_physical_max1(scale, logical);
 
         self.gaps = round(self.gaps);
         self.focus_ring.width = FloatOrInt(round(self.focus_ring.width.0));
         self.border.width = FloatOrInt(round(self.border.width.0));
+        self.tab_indicator.width = FloatOrInt(round(self.tab_indicator.width.0));
+        self.tab_indicator.gap = FloatOrInt(round(self.tab_indicator.gap.0));
 
         self
     }
@@ -296,6 +512,10 @@ impl<W: LayoutElement> Layout<W> {
         Self {
             monitor_set: MonitorSet::NoOutputs { workspaces: vec![] },
             options: Rc::new(options),
+            output_layouts: vec![],
+            primary_output: None,
+            output_identity: OutputIdentity::default(),
+            disconnected_primary: None,
         }
     }
 
@@ -311,11 +531,44 @@ impl<W: LayoutElement> Layout<W> {
         Self {
             monitor_set: MonitorSet::NoOutputs { workspaces },
             options: opts,
+            output_layouts: output_layouts_from_config(config),
+            primary_output: config.primary_output.clone(),
+            output_identity: config.output_identity,
+            disconnected_primary: None,
+        }
+    }
+
+    /// Returns the options for monitors and workspaces on this output.
+    fn options_for_output(&self, output: &Output) -> Rc<Options> {
+        let name = output.name();
+        let layout = self
+            .output_layouts
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(&name));
+
+        match layout {
+            Some((_, layout)) => Rc::new(Options::clone(&self.options).with_override(layout)),
+            None => self.options.clone(),
         }
     }
 
+    /// Returns whether this output is configured to be the primary one.
+    fn is_configured_primary(&self, output: &Output) -> bool {
+        let name = output.name();
+        self.primary_output
+            .as_ref()
+            .map_or(false, |n| n.eq_ignore_ascii_case(&name))
+    }
+
     pub fn add_output(&mut self, output: Output) {
         let id = OutputId::new(&output);
+        let options = self.options_for_output(&output);
+        let is_primary = self.is_configured_primary(&output);
+        let identity = self.output_identity;
+        let was_primary = self
+            .disconnected_primary
+            .as_ref()
+            .is_some_and(|primary| primary.matches(&id, identity));
 
         self.monitor_set = match mem::take(&mut self.monitor_set) {
             MonitorSet::Normal {
@@ -329,7 +582,7 @@ impl<W: LayoutElement> Layout<W> {
 
                 let mut workspaces = vec![];
                 for i in (0..primary.workspaces.len()).rev() {
-                    if primary.workspaces[i].original_output == id {
+                    if primary.workspaces[i].original_output.matches(&id, identity) {
                         let ws = primary.workspaces.remove(i);
 
                         // FIXME: this can be coded in a way that the workspace switch won't be
@@ -362,13 +615,14 @@ impl<W: LayoutElement> Layout<W> {
                 workspaces.reverse();
 
                 // Make sure there's always an empty workspace.
-                workspaces.push(Workspace::new(output.clone(), self.options.clone()));
+                workspaces.push(Workspace::new(output.clone(), options.clone()));
 
                 for ws in &mut workspaces {
                     ws.set_output(Some(output.clone()));
+                    ws.update_config(options.clone());
                 }
 
-                monitors.push(Monitor::new(output, workspaces, self.options.clone()));
+                monitors.push(Monitor::new(output.clone(), workspaces, options));
                 MonitorSet::Normal {
                     monitors,
                     primary_idx,
@@ -377,13 +631,14 @@ impl<W: LayoutElement> Layout<W> {
             }
             MonitorSet::NoOutputs { mut workspaces } => {
                 // We know there are no empty workspaces there, so add one.
-                workspaces.push(Workspace::new(output.clone(), self.options.clone()));
+                workspaces.push(Workspace::new(output.clone(), options.clone()));
 
                 for workspace in &mut workspaces {
                     workspace.set_output(Some(output.clone()));
+                    workspace.update_config(options.clone());
                 }
 
-                let monitor = Monitor::new(output, workspaces, self.options.clone());
+                let monitor = Monitor::new(output.clone(), workspaces, options);
 
                 MonitorSet::Normal {
                     monitors: vec![monitor],
@@ -391,6 +646,15 @@ impl<W: LayoutElement> Layout<W> {
                     active_monitor_idx: 0,
                 }
             }
+        };
+
+        // The configured primary output takes precedence over restoring the previous one.
+        let restore_primary = was_primary && !self.outputs().any(|o| self.is_configured_primary(o));
+        if is_primary || restore_primary {
+            self.set_primary_output(&output);
+        }
+        if was_primary {
+            self.disconnected_primary = None;
         }
     }
 
@@ -408,6 +672,11 @@ impl<W: LayoutElement> Layout<W> {
                 let monitor = monitors.remove(idx);
                 let mut workspaces = monitor.workspaces;
 
+                let removed_primary = idx == primary_idx;
+                if removed_primary && self.disconnected_primary.is_none() {
+                    self.disconnected_primary = Some(OutputId::new(output));
+                }
+
                 for ws in &mut workspaces {
                     ws.set_output(None);
                 }
@@ -417,6 +686,10 @@ impl<W: LayoutElement> Layout<W> {
 
                 if monitors.is_empty() {
                     // Removed the last monitor.
+                    for ws in &mut workspaces {
+                        ws.update_config(self.options.clone());
+                    }
+
                     MonitorSet::NoOutputs { workspaces }
                 } else {
                     if primary_idx >= idx {
@@ -424,6 +697,15 @@ impl<W: LayoutElement> Layout<W> {
                         // other monitor if the primary has been removed.
                         primary_idx = primary_idx.saturating_sub(1);
                     }
+                    if removed_primary {
+                        // Prefer the configured primary output, if it's connected.
+                        if let Some(configured_idx) = monitors
+                            .iter()
+                            .position(|mon| self.is_configured_primary(&mon.output))
+                        {
+                            primary_idx = configured_idx;
+                        }
+                    }
                     if active_monitor_idx >= idx {
                         // Update active_monitor_idx to either still point at the same monitor, or
                         // at some other monitor if the active monitor has
@@ -434,6 +716,7 @@ impl<W: LayoutElement> Layout<W> {
                     let primary = &mut monitors[primary_idx];
                     for ws in &mut workspaces {
                         ws.set_output(Some(primary.output.clone()));
+                        ws.update_config(primary.options.clone());
                     }
 
                     let empty_was_focused =
@@ -463,6 +746,101 @@ impl<W: LayoutElement> Layout<W> {
         }
     }
 
+    /// Makes the monitor of this output the primary one.
+    ///
+    /// The primary monitor holds the workspaces whose original output is disconnected, so these
+    /// move over from the old primary monitor to the new one.
+    pub fn set_primary_output(&mut self, output: &Output) {
+        let MonitorSet::Normal {
+            monitors,
+            primary_idx,
+            ..
+        } = &mut self.monitor_set
+        else {
+            return;
+        };
+
+        let new_idx = monitors
+            .iter()
+            .position(|mon| &mon.output == output)
+            .expect("trying to set non-existing output as primary");
+
+        // An explicit choice of the primary output overrides restoring the disconnected one.
+        self.disconnected_primary = None;
+
+        if new_idx == *primary_idx {
+            return;
+        }
+
+        let connected: Vec<_> = monitors
+            .iter()
+            .map(|mon| OutputId::new(&mon.output))
+            .collect();
+        let identity = self.output_identity;
+
+        let old_primary = &mut monitors[*primary_idx];
+
+        let mut stopped_ws_switch = false;
+
+        let mut workspaces = vec![];
+        // Skip the last, empty, workspace.
+        for i in (0..old_primary.workspaces.len() - 1).rev() {
+            let original_output = &old_primary.workspaces[i].original_output;
+            if connected
+                .iter()
+                .any(|id| id.matches(original_output, identity))
+            {
+                continue;
+            }
+
+            let ws = old_primary.workspaces.remove(i);
+
+            if old_primary.workspace_switch.is_some() {
+                old_primary.workspace_switch = None;
+                stopped_ws_switch = true;
+            }
+
+            if ws.has_windows() || ws.name.is_some() {
+                workspaces.push(ws);
+            }
+
+            if i <= old_primary.active_workspace_idx {
+                old_primary.active_workspace_idx =
+                    old_primary.active_workspace_idx.saturating_sub(1);
+            }
+        }
+
+        if stopped_ws_switch {
+            old_primary.clean_up_workspaces();
+        }
+
+        workspaces.reverse();
+
+        *primary_idx = new_idx;
+        let primary = &mut monitors[new_idx];
+
+        if workspaces.is_empty() {
+            return;
+        }
+
+        for ws in &mut workspaces {
+            ws.set_output(Some(primary.output.clone()));
+            ws.update_config(primary.options.clone());
+        }
+
+        let empty_was_focused = primary.active_workspace_idx == primary.workspaces.len() - 1;
+
+        // Push the workspaces in the end, right before the last, empty, workspace.
+        let empty = primary.workspaces.remove(primary.workspaces.len() - 1);
+        primary.workspaces.extend(workspaces);
+        primary.workspaces.push(empty);
+
+        // If the empty workspace was focused on the primary monitor, keep it focused.
+        if empty_was_focused {
+            primary.active_workspace_idx = primary.workspaces.len() - 1;
+        }
+    }
+
     pub fn add_window_by_idx(
         &mut self,
         monitor_idx: usize,
@@ -495,16 +873,8 @@ impl<W: LayoutElement> Layout<W> {
         window: W,
         width: Option<ColumnWidth>,
         is_full_width: bool,
+        focus: bool,
     ) -> Option<&Output> {
-        let mut width = width.unwrap_or_else(|| ColumnWidth::Fixed(f64::from(window.size().w)));
-        if let ColumnWidth::Fixed(w) = &mut width {
-            let rules = window.rules();
-            let border_config = rules.border.resolve_against(self.options.border);
-            if !border_config.off {
-                *w += border_config.width.0 * 2.;
-            }
-        }
-
         match &mut self.monitor_set {
             MonitorSet::Normal {
                 monitors,
@@ -523,19 +893,27 @@ impl<W: LayoutElement> Layout<W> {
                 // Don't steal focus from an active fullscreen window.
                 let mut activate = true;
                 let ws = &mon.workspaces[ws_idx];
+                let width = ws.options.resolve_column_width(&window, width);
                 if mon_idx == *active_monitor_idx
+                    && mon.active_workspace_idx == ws_idx
                     && !ws.columns.is_empty()
                     && ws.columns[ws.active_column_idx].is_fullscreen
                 {
                     activate = false;
                 }
 
-                // Don't activate if on a different workspace.
-                if mon.active_workspace_idx != ws_idx {
+                // Don't activate if on a different workspace, unless asked to focus the window.
+                if !focus && mon.active_workspace_idx != ws_idx {
                     activate = false;
                 }
 
                 mon.add_window(ws_idx, window, activate, width, is_full_width);
+
+                // Focusing the window switches to its workspace, and to its monitor as well.
+                if focus && activate {
+                    *active_monitor_idx = mon_idx;
+                }
+
                 Some(&mon.output)
             }
             MonitorSet::NoOutputs { workspaces } => {
@@ -547,6 +925,7 @@ impl<W: LayoutElement> Layout<W> {
                             .map_or(false, |name| name.eq_ignore_ascii_case(workspace_name))
                     })
                     .unwrap();
+                let width = ws.options.resolve_column_width(&window, width);
                 ws.add_window(window, true, width, is_full_width);
                 None
             }
@@ -585,15 +964,6 @@ impl<W: LayoutElement> Layout<W> {
         width: Option<ColumnWidth>,
         is_full_width: bool,
     ) -> Option<&Output> {
-        let mut width = width.unwrap_or_else(|| ColumnWidth::Fixed(f64::from(window.size().w)));
-        if let ColumnWidth::Fixed(w) = &mut width {
-            let rules = window.rules();
-            let border_config = rules.border.resolve_against(self.options.border);
-            if !border_config.off {
-                *w += border_config.width.0 * 2.;
-            }
-        }
-
         match &mut self.monitor_set {
             MonitorSet::Normal {
                 monitors,
@@ -605,6 +975,7 @@ impl<W: LayoutElement> Layout<W> {
                 // Don't steal focus from an active fullscreen window.
                 let mut activate = true;
                 let ws = &mon.workspaces[mon.active_workspace_idx];
+                let width = ws.options.resolve_column_width(&window, width);
                 if !ws.columns.is_empty() && ws.columns[ws.active_column_idx].is_fullscreen {
                     activate = false;
                 }
@@ -625,6 +996,7 @@ impl<W: LayoutElement> Layout<W> {
                     workspaces.push(Workspace::new_no_outputs(self.options.clone()));
                     &mut workspaces[0]
                 };
+                let width = ws.options.resolve_column_width(&window, width);
                 ws.add_window(window, true, width, is_full_width);
                 None
             }
@@ -643,15 +1015,6 @@ impl<W: LayoutElement> Layout<W> {
         width: Option<ColumnWidth>,
         is_full_width: bool,
     ) -> Option<&Output> {
-        let mut width = width.unwrap_or_else(|| ColumnWidth::Fixed(f64::from(window.size().w)));
-        if let ColumnWidth::Fixed(w) = &mut width {
-            let rules = window.rules();
-            let border_config = rules.border.resolve_against(self.options.border);
-            if !border_config.off {
-                *w += border_config.width.0 * 2.;
-            }
-        }
-
         match &mut self.monitor_set {
             MonitorSet::Normal { monitors, .. } => {
                 let mon = monitors
@@ -659,6 +1022,8 @@ impl<W: LayoutElement> Layout<W> {
                     .find(|mon| mon.workspaces.iter().any(|ws| ws.has_window(right_of)))
                     .unwrap();
 
+                let ws = mon.workspaces.iter().find(|ws| ws.has_window(right_of));
+                let width = ws.unwrap().options.resolve_column_width(&window, width);
                 mon.add_window_right_of(right_of, window, width, is_full_width);
                 Some(&mon.output)
             }
@@ -667,12 +1032,124 @@ impl<W: LayoutElement> Layout<W> {
                     .iter_mut()
                     .find(|ws| ws.has_window(right_of))
                     .unwrap();
+                let width = ws.options.resolve_column_width(&window, width);
                 ws.add_window_right_of(right_of, window, width, is_full_width);
                 None
             }
         }
     }
 
+    /// Adds a new window to the layout immediately to the left of another window.
+    ///
+    /// If that another window was active, activates the new window.
+    ///
+    /// Returns an output that the window was added to, if there were any outputs.
+    pub fn add_window_left_of(
+        &mut self,
+        left_of: &W::Id,
+        window: W,
+        width: Option<ColumnWidth>,
+        is_full_width: bool,
+    ) -> Option<&Output> {
+        match &mut self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => {
+                let mon = monitors
+                    .iter_mut()
+                    .find(|mon| mon.workspaces.iter().any(|ws| ws.has_window(left_of)))
+                    .unwrap();
+
+                let ws = mon.workspaces.iter().find(|ws| ws.has_window(left_of));
+                let width = ws.unwrap().options.resolve_column_width(&window, width);
+                mon.add_window_left_of(left_of, window, width, is_full_width);
+                Some(&mon.output)
+            }
+            MonitorSet::NoOutputs { workspaces } => {
+                let ws = workspaces
+                    .iter_mut()
+                    .find(|ws| ws.has_window(left_of))
+                    .unwrap();
+                let width = ws.options.resolve_column_width(&window, width);
+                ws.add_window_left_of(left_of, window, width, is_full_width);
+                None
+            }
+        }
+    }
+
+    /// Adds a new window to the bottom of the column containing `column_of`.
+    pub fn add_window_to_column(&mut self, column_of: &W::Id, window: W) -> Option<&Output> {
+        match &mut self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => {
+                let mon = monitors
+                    .iter_mut()
+                    .find(|mon| mon.workspaces.iter().any(|ws| ws.has_window(column_of)))
+                    .unwrap();
+
+                mon.add_window_to_column(column_of, window);
+                Some(&mon.output)
+            }
+            MonitorSet::NoOutputs { workspaces } => {
+                let ws = workspaces
+                    .iter_mut()
+                    .find(|ws| ws.has_window(column_of))
+                    .unwrap();
+                ws.add_window_to_column(column_of, window);
+                None
+            }
+        }
+    }
+
+    /// Adds a new window floating above the columns.
+    ///
+    /// The window opens on the workspace of its parent, if it has one, and on the active workspace
+    /// otherwise.
+    ///
+    /// `pointer` is the output under the pointer and the pointer position within it, for the
+    /// at-pointer dialog placement.
+    ///
+    /// Returns an output that the window was added to, if there were any outputs.
+    pub fn add_floating_window(
+        &mut self,
+        window: W,
+        parent: Option<&W::Id>,
+        pointer: Option<(&Output, Point<f64, Logical>)>,
+    ) -> Option<&Output> {
+        match &mut self.monitor_set {
+            MonitorSet::Normal {
+                monitors,
+                active_monitor_idx,
+                ..
+            } => {
+                let parent_idx = parent.and_then(|parent| {
+                    monitors.iter().enumerate().find_map(|(mon_idx, mon)| {
+                        let ws_idx = mon.workspaces.iter().position(|ws| ws.has_window(parent))?;
+                        Some((mon_idx, ws_idx))
+                    })
+                });
+                let (mon_idx, ws_idx) = parent_idx.unwrap_or_else(|| {
+                    let mon = &monitors[*active_monitor_idx];
+                    (*active_monitor_idx, mon.active_workspace_idx)
+                });
+
+                let mon = &mut monitors[mon_idx];
+                let pointer = pointer
+                    .filter(|(output, _)| **output == mon.output)
+                    .map(|(_, pos)| pos);
+                mon.add_floating_window(ws_idx, window, parent, pointer);
+                Some(&mon.output)
+            }
+            MonitorSet::NoOutputs { workspaces } => {
+                let idx = parent
+                    .and_then(|parent| workspaces.iter().position(|ws| ws.has_window(parent)))
+                    .unwrap_or(0);
+                if workspaces.is_empty() {
+                    workspaces.push(Workspace::new_no_outputs(self.options.clone()));
+                }
+                workspaces[idx].add_floating_window(window, parent, true, None);
+                None
+            }
+        }
+    }
+
     /// Adds a new window to the layout on a specific output.
     pub fn add_window_on_output(
         &mut self,
@@ -681,15 +1158,6 @@ impl<W: LayoutElement> Layout<W> {
         width: Option<ColumnWidth>,
         is_full_width: bool,
     ) {
-        let mut width = width.unwrap_or_else(|| ColumnWidth::Fixed(f64::from(window.size().w)));
-        if let ColumnWidth::Fixed(w) = &mut width {
-            let rules = window.rules();
-            let border_config = rules.border.resolve_against(self.options.border);
-            if !border_config.off {
-                *w += border_config.width.0 * 2.;
-            }
-        }
-
         let MonitorSet::Normal {
             monitors,
             active_monitor_idx,
@@ -708,6 +1176,7 @@ impl<W: LayoutElement> Layout<W> {
         // Don't steal focus from an active fullscreen window.
         let mut activate = true;
         let ws = &mon.workspaces[mon.active_workspace_idx];
+        let width = ws.options.resolve_column_width(&window, width);
         if mon_idx == *active_monitor_idx
             && !ws.columns.is_empty()
             && ws.columns[ws.active_column_idx].is_fullscreen
@@ -730,6 +1199,8 @@ impl<W: LayoutElement> Layout<W> {
         match &mut self.monitor_set {
             MonitorSet::Normal { monitors, .. } => {
                 for mon in monitors {
+                    mon.interactive_move_forget(window);
+
                     for (idx, ws) in mon.workspaces.iter_mut().enumerate() {
                         if ws.has_window(window) {
                             rv = Some(ws.remove_window(window));
@@ -772,6 +1243,28 @@ impl<W: LayoutElement> Layout<W> {
         rv
     }
 
+    /// Removes a window that is closing, moving focus according to its `on-close` rule.
+    pub fn close_window(&mut self, window: &W::Id) -> Option<W> {
+        match &mut self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => {
+                if let Some(ws) = monitors
+                    .iter_mut()
+                    .flat_map(|mon| &mut mon.workspaces)
+                    .find(|ws| ws.has_window(window))
+                {
+                    ws.prepare_close(window);
+                }
+            }
+            MonitorSet::NoOutputs { workspaces } => {
+                if let Some(ws) = workspaces.iter_mut().find(|ws| ws.has_window(window)) {
+                    ws.prepare_close(window);
+                }
+            }
+        }
+
+        self.remove_window(window)
+    }
+
     pub fn update_window(&mut self, window: &W::Id, serial: Option<Serial>) {
         match &mut self.monitor_set {
             MonitorSet::Normal { monitors, .. } => {
@@ -795,6 +1288,18 @@ impl<W: LayoutElement> Layout<W> {
         }
     }
 
+    pub fn has_window(&self, window: &W::Id) -> bool {
+        match &self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => monitors
+                .iter()
+                .flat_map(|mon| &mon.workspaces)
+                .any(|ws| ws.has_window(window)),
+            MonitorSet::NoOutputs { workspaces } => {
+                workspaces.iter().any(|ws| ws.has_window(window))
+            }
+        }
+    }
+
     pub fn find_window_and_output(&self, wl_surface: &WlSurface) -> Option<(&W, &Output)> {
         if let MonitorSet::Normal { monitors, .. } = &self.monitor_set {
             for mon in monitors {
@@ -838,13 +1343,35 @@ impl<W: LayoutElement> Layout<W> {
         None
     }
 
-    pub fn unname_workspace(&mut self, workspace_name: &str) {
-        match &mut self.monitor_set {
-            MonitorSet::Normal { monitors, .. } => {
-                for mon in monitors {
-                    if mon.unname_workspace(workspace_name) {
-                        if mon.workspace_switch.is_none() {
-                            mon.clean_up_workspaces();
+    /// Finds the named workspace best matching a fuzzy query.
+    ///
+    /// Ties are broken in favor of the most recently focused workspace.
+    pub fn find_workspace_by_fuzzy_name(&self, query: &str) -> Option<&Workspace<W>> {
+        let workspaces: Vec<&Workspace<W>> = match &self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => monitors
+                .iter()
+                .flat_map(|mon| mon.workspaces.iter())
+                .collect(),
+            MonitorSet::NoOutputs { workspaces } => workspaces.iter().collect(),
+        };
+
+        workspaces
+            .into_iter()
+            .filter_map(|ws| {
+                let score = fuzzy_match_score(query, ws.name.as_ref()?)?;
+                Some(((score, ws.focus_serial()), ws))
+            })
+            .max_by_key(|(key, _)| *key)
+            .map(|(_, ws)| ws)
+    }
+
+    pub fn unname_workspace(&mut self, workspace_name: &str) {
+        match &mut self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => {
+                for mon in monitors {
+                    if mon.unname_workspace(workspace_name) {
+                        if mon.workspace_switch.is_none() {
+                            mon.clean_up_workspaces();
                         }
                         return;
                     }
@@ -902,20 +1429,16 @@ impl<W: LayoutElement> Layout<W> {
             MonitorSet::Normal { monitors, .. } => {
                 for mon in monitors {
                     for ws in &mon.workspaces {
-                        for col in &ws.columns {
-                            if let Some(idx) = col.position(window) {
-                                return Some(col.window_loc(idx));
-                            }
+                        if let Some(loc) = ws.window_loc(window) {
+                            return Some(loc);
                         }
                     }
                 }
             }
             MonitorSet::NoOutputs { workspaces, .. } => {
                 for ws in workspaces {
-                    for col in &ws.columns {
-                        if let Some(idx) = col.position(window) {
-                            return Some(col.window_loc(idx));
-                        }
+                    if let Some(loc) = ws.window_loc(window) {
+                        return Some(loc);
                     }
                 }
             }
@@ -995,6 +1518,19 @@ impl<W: LayoutElement> Layout<W> {
         *active_monitor_idx = idx;
     }
 
+    pub fn primary_output(&self) -> Option<&Output> {
+        let MonitorSet::Normal {
+            monitors,
+            primary_idx,
+            ..
+        } = &self.monitor_set
+        else {
+            return None;
+        };
+
+        Some(&monitors[*primary_idx].output)
+    }
+
     pub fn active_output(&self) -> Option<&Output> {
         let MonitorSet::Normal {
             monitors,
@@ -1035,12 +1571,8 @@ impl<W: LayoutElement> Layout<W> {
         let mon = &monitors[*active_monitor_idx];
         let ws = &mon.workspaces[mon.active_workspace_idx];
 
-        if ws.columns.is_empty() {
-            return None;
-        }
-
-        let col = &ws.columns[ws.active_column_idx];
-        Some((col.tiles[col.active_tile_idx].window(), &mon.output))
+        let window = ws.active_window()?;
+        Some((window, &mon.output))
     }
 
     pub fn windows_for_output(&self, output: &Output) -> impl Iterator<Item = &W> + '_ {
@@ -1052,6 +1584,17 @@ impl<W: LayoutElement> Layout<W> {
         mon.workspaces.iter().flat_map(|ws| ws.windows())
     }
 
+    /// Returns the windows on the output that are at least partially within its view.
+    pub fn visible_windows_for_output(&self, output: &Output) -> impl Iterator<Item = &W> + '_ {
+        let MonitorSet::Normal { monitors, .. } = &self.monitor_set else {
+            panic!()
+        };
+
+        let mon = monitors.iter().find(|mon| &mon.output == output).unwrap();
+        mon.workspaces_with_render_positions()
+            .flat_map(|(ws, _)| ws.visible_windows().map(|(win, _)| win))
+    }
+
     pub fn with_windows(&self, mut f: impl FnMut(&W, Option<&Output>)) {
         match &self.monitor_set {
             MonitorSet::Normal { monitors, .. } => {
@@ -1073,6 +1616,51 @@ impl<W: LayoutElement> Layout<W> {
         }
     }
 
+    /// Returns the memory used by the textures of the closing window animations.
+    pub fn closing_windows_texture_bytes(&self) -> u64 {
+        match &self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => monitors
+                .iter()
+                .flat_map(|mon| &mon.workspaces)
+                .map(Workspace::closing_windows_texture_bytes)
+                .sum(),
+            MonitorSet::NoOutputs { workspaces } => workspaces
+                .iter()
+                .map(Workspace::closing_windows_texture_bytes)
+                .sum(),
+        }
+    }
+
+    pub fn clear_closing_windows(&mut self) {
+        match &mut self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => {
+                for mon in monitors {
+                    for ws in &mut mon.workspaces {
+                        ws.clear_closing_windows();
+                    }
+                }
+            }
+            MonitorSet::NoOutputs { workspaces } => {
+                for ws in workspaces {
+                    ws.clear_closing_windows();
+                }
+            }
+        }
+    }
+
+    /// Calls `f` for windows visible on the active workspaces, with output-local geometry.
+    pub fn with_visible_windows(&self, mut f: impl FnMut(&W, &Output, Rectangle<f64, Logical>)) {
+        let MonitorSet::Normal { monitors, .. } = &self.monitor_set else {
+            return;
+        };
+
+        for mon in monitors {
+            for (win, geo) in mon.active_workspace_ref().visible_windows() {
+                f(win, &mon.output, geo);
+            }
+        }
+    }
+
     pub fn with_windows_mut(&mut self, mut f: impl FnMut(&mut W, Option<&Output>)) {
         match &mut self.monitor_set {
             MonitorSet::Normal { monitors, .. } => {
@@ -1128,6 +1716,12 @@ impl<W: LayoutElement> Layout<W> {
         monitors.iter().find(|monitor| &monitor.output == output)
     }
 
+    /// Returns whether the layout on this output has horizontal workspaces and vertical columns.
+    pub fn is_transposed(&self, output: &Output) -> bool {
+        self.monitor_for_output(output)
+            .map_or(false, |monitor| monitor.is_transposed())
+    }
+
     pub fn monitor_for_workspace(&self, workspace_name: &str) -> Option<&Monitor<W>> {
         let MonitorSet::Normal { monitors, .. } = &self.monitor_set else {
             return None;
@@ -1173,6 +1767,13 @@ impl<W: LayoutElement> Layout<W> {
         monitor.move_column_to_first();
     }
 
+    pub fn move_column_to_index(&mut self, index: usize) {
+        let Some(monitor) = self.active_monitor() else {
+            return;
+        };
+        monitor.move_column_to_index(index);
+    }
+
     pub fn move_column_to_last(&mut self) {
         let Some(monitor) = self.active_monitor() else {
             return;
@@ -1243,6 +1844,13 @@ impl<W: LayoutElement> Layout<W> {
         monitor.focus_column_first();
     }
 
+    pub fn focus_column(&mut self, index: usize) {
+        let Some(monitor) = self.active_monitor() else {
+            return;
+        };
+        monitor.focus_column(index);
+    }
+
     pub fn focus_column_last(&mut self) {
         let Some(monitor) = self.active_monitor() else {
             return;
@@ -1494,6 +2102,15 @@ impl<W: LayoutElement> Layout<W> {
         mon.window_under(pos_within_output)
     }
 
+    /// Returns the window under the position in the global space, and the window position in the
+    /// global space.
+    pub fn element_under(
+        &self,
+        pos: Point<f64, Logical>,
+    ) -> Option<(&W, Option<Point<f64, Logical>>)> {
+        self.monitor_set.element_under(pos)
+    }
+
     pub fn resize_edges_under(
         &self,
         output: &Output,
@@ -1507,8 +2124,21 @@ impl<W: LayoutElement> Layout<W> {
         mon.resize_edges_under(pos_within_output)
     }
 
-    #[cfg(test)]
-    fn verify_invariants(&self) {
+    pub fn resize_grip_under(
+        &self,
+        output: &Output,
+        pos_within_output: Point<f64, Logical>,
+    ) -> Option<(&W, ResizeEdge)> {
+        let MonitorSet::Normal { monitors, .. } = &self.monitor_set else {
+            return None;
+        };
+
+        let mon = monitors.iter().find(|mon| &mon.output == output)?;
+        mon.resize_grip_under(pos_within_output)
+    }
+
+    #[cfg(any(test, feature = "fuzzing"))]
+    pub fn verify_invariants(&self) {
         use std::collections::HashSet;
 
         use crate::layout::monitor::WorkspaceSwitch;
@@ -1534,11 +2164,15 @@ impl<W: LayoutElement> Layout<W> {
                         "workspace base options must be synchronized with layout"
                     );
 
-                    let options = Options::clone(&workspace.base_options)
-                        .adjusted_for_scale(workspace.scale().fractional_scale());
+                    let options = workspace.base_options.resolve_for_workspace(
+                        &workspace.layout_override(),
+                        workspace.scale().fractional_scale(),
+                        workspace.output_view_size(),
+                    );
                     assert_eq!(
                         &*workspace.options, &options,
-                        "workspace options must be base options adjusted for workspace scale"
+                        "workspace options must be overridden base options adjusted for workspace \
+                         scale"
                     );
 
                     assert!(
@@ -1573,11 +2207,6 @@ impl<W: LayoutElement> Layout<W> {
             );
             assert!(monitor.active_workspace_idx < monitor.workspaces.len());
 
-            assert_eq!(
-                monitor.options, self.options,
-                "monitor options must be synchronized with layout"
-            );
-
             if let Some(WorkspaceSwitch::Animation(anim)) = &monitor.workspace_switch {
                 let before_idx = anim.from() as usize;
                 let after_idx = anim.to() as usize;
@@ -1590,14 +2219,17 @@ impl<W: LayoutElement> Layout<W> {
 
             if idx == primary_idx {
                 for ws in &monitor.workspaces {
-                    if ws.original_output == monitor_id {
+                    if ws
+                        .original_output
+                        .matches(&monitor_id, self.output_identity)
+                    {
                         // This is the primary monitor's own workspace.
                         continue;
                     }
 
-                    let own_monitor_exists = monitors
-                        .iter()
-                        .any(|m| OutputId::new(&m.output) == ws.original_output);
+                    let own_monitor_exists = monitors.iter().any(|m| {
+                        OutputId::new(&m.output).matches(&ws.original_output, self.output_identity)
+                    });
                     assert!(
                         !own_monitor_exists,
                         "primary monitor cannot have workspaces for which their own monitor exists"
@@ -1605,16 +2237,15 @@ impl<W: LayoutElement> Layout<W> {
                 }
             } else {
                 assert!(
-                    monitor
-                        .workspaces
-                        .iter()
-                        .any(|workspace| workspace.original_output == monitor_id),
+                    monitor.workspaces.iter().any(|workspace| workspace
+                        .original_output
+                        .matches(&monitor_id, self.output_identity)),
                     "secondary monitor must not have any non-own workspaces"
                 );
             }
 
             assert!(
-                monitor.workspaces.last().unwrap().columns.is_empty(),
+                !monitor.workspaces.last().unwrap().has_windows(),
                 "monitor must have an empty workspace in the end"
             );
 
@@ -1629,7 +2260,7 @@ impl<W: LayoutElement> Layout<W> {
                 for (idx, ws) in monitor.workspaces.iter().enumerate().rev().skip(1) {
                     if idx != monitor.active_workspace_idx {
                         assert!(
-                            !ws.columns.is_empty() || ws.name.is_some(),
+                            ws.has_windows() || ws.name.is_some(),
                             "non-active workspace can't be empty and unnamed except the last one"
                         );
                     }
@@ -1639,17 +2270,32 @@ impl<W: LayoutElement> Layout<W> {
             // FIXME: verify that primary doesn't have any workspaces for which their own monitor
             // exists.
 
+            assert_eq!(
+                monitor.options,
+                self.options_for_output(&monitor.output),
+                "monitor options must be synchronized with layout"
+            );
+
             for workspace in &monitor.workspaces {
                 assert_eq!(
-                    workspace.base_options, self.options,
-                    "workspace options must be synchronized with layout"
+                    workspace.base_options, monitor.options,
+                    "workspace options must be synchronized with monitor"
                 );
 
-                let options = Options::clone(&workspace.base_options)
-                    .adjusted_for_scale(workspace.scale().fractional_scale());
+                let options = workspace.base_options.resolve_for_workspace(
+                    &workspace.layout_override(),
+                    workspace.scale().fractional_scale(),
+                    workspace.output_view_size(),
+                );
                 assert_eq!(
                     &*workspace.options, &options,
-                    "workspace options must be base options adjusted for workspace scale"
+                    "workspace options must be overridden base options adjusted for workspace scale"
+                );
+
+                assert_eq!(
+                    workspace.is_transposed(),
+                    monitor.is_transposed(),
+                    "workspaces must share the transposition of their monitor"
                 );
 
                 assert!(
@@ -1672,6 +2318,21 @@ impl<W: LayoutElement> Layout<W> {
         }
     }
 
+    /// Finishes a batch of actions that were applied back to back.
+    ///
+    /// All actions in a batch start their animations at the same point in time, so consecutive
+    /// workspace switches and view movements already merge into a single animation towards the
+    /// final state. This drops the animations that ended up where they started.
+    pub fn finish_batch(&mut self) {
+        let MonitorSet::Normal { monitors, .. } = &mut self.monitor_set else {
+            return;
+        };
+
+        for mon in monitors {
+            mon.finish_batch();
+        }
+    }
+
     pub fn advance_animations(&mut self, current_time: Duration) {
         let _span = tracy_client::span!("Layout::advance_animations");
 
@@ -1710,6 +2371,29 @@ impl<W: LayoutElement> Layout<W> {
         }
     }
 
+    /// Takes a snapshot of the resolved layout geometry on the output.
+    ///
+    /// Should be called after [`Layout::update_render_elements()`] for the frame.
+    pub fn snapshot(&self, output: &Output) -> Option<LayoutSnapshot<W::Id>>
+    where
+        W::Id: Clone,
+    {
+        let MonitorSet::Normal {
+            monitors,
+            active_monitor_idx,
+            ..
+        } = &self.monitor_set
+        else {
+            return None;
+        };
+
+        monitors
+            .iter()
+            .enumerate()
+            .find(|(_, mon)| mon.output == *output)
+            .map(|(idx, mon)| mon.snapshot(idx == *active_monitor_idx))
+    }
+
     pub fn update_render_elements_all(&mut self) {
         let _span = tracy_client::span!("Layout::update_render_elements_all");
 
@@ -1746,7 +2430,38 @@ impl<W: LayoutElement> Layout<W> {
     }
 
     pub fn ensure_named_workspace(&mut self, ws_config: &WorkspaceConfig) {
-        if self.find_workspace_by_name(&ws_config.name.0).is_some() {
+        self.ensure_named_workspace_inner(ws_config, false);
+    }
+
+    /// Creates a named workspace that is not in the config, if it doesn't exist yet.
+    ///
+    /// The workspace is unnamed once it becomes empty.
+    pub fn ensure_named_workspace_on_demand(&mut self, ws_config: &WorkspaceConfig) {
+        self.ensure_named_workspace_inner(ws_config, true);
+    }
+
+    fn ensure_named_workspace_inner(&mut self, ws_config: &WorkspaceConfig, on_demand: bool) {
+        let name = &ws_config.name.0;
+        let existing = match &mut self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => monitors
+                .iter_mut()
+                .flat_map(|mon| &mut mon.workspaces)
+                .find(|ws| {
+                    ws.name
+                        .as_ref()
+                        .map_or(false, |n| n.eq_ignore_ascii_case(name))
+                }),
+            MonitorSet::NoOutputs { workspaces } => workspaces.iter_mut().find(|ws| {
+                ws.name
+                    .as_ref()
+                    .map_or(false, |n| n.eq_ignore_ascii_case(name))
+            }),
+        };
+        if let Some(ws) = existing {
+            // A workspace that made it into the config is no longer temporary.
+            if !on_demand {
+                ws.created_on_demand = false;
+            }
             return;
         }
 
@@ -1770,40 +2485,124 @@ impl<W: LayoutElement> Layout<W> {
                     .unwrap_or(*active_monitor_idx);
                 let mon = &mut monitors[mon_idx];
 
-                let ws = Workspace::new_with_config(
+                let mut ws = Workspace::new_with_config(
                     mon.output.clone(),
                     Some(ws_config.clone()),
-                    options,
+                    mon.options.clone(),
                 );
+                ws.created_on_demand = on_demand;
                 mon.workspaces.insert(0, ws);
                 mon.active_workspace_idx += 1;
                 mon.workspace_switch = None;
                 mon.clean_up_workspaces();
             }
             MonitorSet::NoOutputs { workspaces } => {
-                let ws = Workspace::new_with_config_no_outputs(Some(ws_config.clone()), options);
+                let mut ws =
+                    Workspace::new_with_config_no_outputs(Some(ws_config.clone()), options);
+                ws.created_on_demand = on_demand;
                 workspaces.insert(0, ws);
             }
         }
     }
 
     pub fn update_config(&mut self, config: &Config) {
-        let options = Rc::new(Options::from_config(config));
+        // Swap in the new global and output options first, then resolve every workspace against
+        // them exactly once. Workspaces whose resolved options did not change are left alone.
+        self.options = Rc::new(Options::from_config(config));
+        self.output_layouts = output_layouts_from_config(config);
+        let primary_output_changed = self.primary_output != config.primary_output;
+        self.primary_output = config.primary_output.clone();
+        let output_identity_changed = self.output_identity != config.output_identity;
+        self.output_identity = config.output_identity;
+
+        let layout_config = |ws: &Workspace<W>| {
+            ws.name
+                .as_ref()
+                .and_then(|name| {
+                    config
+                        .workspaces
+                        .iter()
+                        .find(|c| c.name.0.eq_ignore_ascii_case(name))
+                })
+                .map(|c| c.layout.clone())
+                .unwrap_or_default()
+        };
+
+        let output_options: Vec<_> = match &self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => monitors
+                .iter()
+                .map(|mon| self.options_for_output(&mon.output))
+                .collect(),
+            MonitorSet::NoOutputs { .. } => vec![],
+        };
 
         match &mut self.monitor_set {
             MonitorSet::Normal { monitors, .. } => {
-                for mon in monitors {
-                    mon.update_config(options.clone());
+                for (mon, options) in zip(monitors, output_options) {
+                    for ws in &mut mon.workspaces {
+                        let layout = layout_config(ws);
+                        ws.set_layout_config(layout);
+                    }
+                    mon.update_config(options);
                 }
             }
             MonitorSet::NoOutputs { workspaces } => {
                 for ws in workspaces {
-                    ws.update_config(options.clone());
+                    let layout = layout_config(ws);
+                    ws.set_layout_config(layout);
+                    ws.update_config(self.options.clone());
                 }
             }
         }
 
-        self.options = options;
+        // Under the new identity, workspaces may no longer recognize the outputs they are on.
+        // Rather than shuffling them around on reload, make them belong where they are now.
+        if output_identity_changed {
+            self.adopt_workspaces_on_current_outputs();
+        }
+
+        // Only apply the primary output when it changes in the config, so that reloading an
+        // unrelated setting doesn't undo a runtime change.
+        if primary_output_changed {
+            let output = self
+                .outputs()
+                .find(|o| self.is_configured_primary(o))
+                .cloned();
+            if let Some(output) = output {
+                self.set_primary_output(&output);
+            }
+        }
+    }
+
+    /// Makes every workspace belong to the output it is on, except for workspaces of disconnected
+    /// outputs, which keep waiting on the primary output.
+    fn adopt_workspaces_on_current_outputs(&mut self) {
+        let identity = self.output_identity;
+        let MonitorSet::Normal {
+            monitors,
+            primary_idx,
+            ..
+        } = &mut self.monitor_set
+        else {
+            return;
+        };
+
+        let connected: Vec<_> = monitors
+            .iter()
+            .map(|mon| OutputId::new(&mon.output))
+            .collect();
+
+        for (idx, mon) in monitors.iter_mut().enumerate() {
+            for ws in &mut mon.workspaces {
+                let dangling = idx == *primary_idx
+                    && !connected
+                        .iter()
+                        .any(|id| id.matches(&ws.original_output, identity));
+                if !dangling {
+                    ws.original_output = connected[idx].clone();
+                }
+            }
+        }
     }
 
     pub fn toggle_width(&mut self) {
@@ -1820,6 +2619,20 @@ impl<W: LayoutElement> Layout<W> {
         monitor.toggle_full_width();
     }
 
+    pub fn toggle_auto_tile(&mut self) {
+        let Some(monitor) = self.active_monitor() else {
+            return;
+        };
+        monitor.toggle_auto_tile();
+    }
+
+    pub fn toggle_column_always_center(&mut self) {
+        let Some(monitor) = self.active_monitor() else {
+            return;
+        };
+        monitor.toggle_column_always_center();
+    }
+
     pub fn set_column_width(&mut self, change: SizeChange) {
         let Some(monitor) = self.active_monitor() else {
             return;
@@ -1827,6 +2640,13 @@ impl<W: LayoutElement> Layout<W> {
         monitor.set_column_width(change);
     }
 
+    pub fn toggle_column_width(&mut self, a: SizeChange, b: SizeChange) {
+        let Some(monitor) = self.active_monitor() else {
+            return;
+        };
+        monitor.toggle_column_width(a, b);
+    }
+
     pub fn set_window_height(&mut self, change: SizeChange) {
         let Some(monitor) = self.active_monitor() else {
             return;
@@ -1834,6 +2654,20 @@ impl<W: LayoutElement> Layout<W> {
         monitor.set_window_height(change);
     }
 
+    pub fn set_window_opacity(&mut self, change: OpacityChange) {
+        let Some(monitor) = self.active_monitor() else {
+            return;
+        };
+        monitor.set_window_opacity(change);
+    }
+
+    pub fn reset_window_opacity(&mut self) {
+        let Some(monitor) = self.active_monitor() else {
+            return;
+        };
+        monitor.reset_window_opacity();
+    }
+
     pub fn reset_window_height(&mut self) {
         let Some(monitor) = self.active_monitor() else {
             return;
@@ -1841,6 +2675,50 @@ impl<W: LayoutElement> Layout<W> {
         monitor.reset_window_height();
     }
 
+    pub fn set_workspace_gaps(&mut self, change: SizeChange) {
+        let Some(monitor) = self.active_monitor() else {
+            return;
+        };
+        monitor.active_workspace().set_gaps(change);
+    }
+
+    pub fn set_workspace_default_column_width(&mut self, change: SizeChange) {
+        let Some(monitor) = self.active_monitor() else {
+            return;
+        };
+        monitor.active_workspace().set_default_column_width(change);
+    }
+
+    pub fn set_workspace_center_focused_column(&mut self, center: CenterFocusedColumn) {
+        let Some(monitor) = self.active_monitor() else {
+            return;
+        };
+        monitor.active_workspace().set_center_focused_column(center);
+    }
+
+    pub fn set_workspace_zoom(&mut self, zoom: f64) {
+        let Some(monitor) = self.active_monitor() else {
+            return;
+        };
+        monitor.active_workspace().set_zoom(zoom);
+    }
+
+    pub fn reset_workspace_layout(&mut self) {
+        let Some(monitor) = self.active_monitor() else {
+            return;
+        };
+        monitor.active_workspace().reset_layout();
+    }
+
+    pub fn set_layout_strategy(&mut self, name: LayoutStrategyName) {
+        let Some(monitor) = self.active_monitor() else {
+            return;
+        };
+        monitor
+            .active_workspace()
+            .set_strategy(strategy_from_name(name));
+    }
+
     pub fn focus_output(&mut self, output: &Output) {
         if let MonitorSet::Normal {
             monitors,
@@ -1870,19 +2748,13 @@ impl<W: LayoutElement> Layout<W> {
                 .unwrap();
 
             let current = &mut monitors[*active_monitor_idx];
-            let ws = current.active_workspace();
-            if !ws.has_windows() {
+            let Some(window) = current.active_workspace().remove_active_window() else {
                 return;
-            }
-            let column = &ws.columns[ws.active_column_idx];
-            let width = column.width;
-            let is_full_width = column.is_full_width;
-            let window = ws
-                .remove_tile_by_idx(ws.active_column_idx, column.active_tile_idx, None)
-                .into_window();
+            };
 
             let workspace_idx = monitors[new_idx].active_workspace_idx;
-            self.add_window_by_idx(new_idx, workspace_idx, window, true, width, is_full_width);
+            monitors[new_idx].add_moved_window(workspace_idx, window, true);
+            *active_monitor_idx = new_idx;
         }
     }
 
@@ -1900,7 +2772,7 @@ impl<W: LayoutElement> Layout<W> {
 
             let current = &mut monitors[*active_monitor_idx];
             let ws = current.active_workspace();
-            if !ws.has_windows() {
+            if ws.columns.is_empty() || ws.is_floating_active() {
                 return;
             }
             let column = ws.remove_column_by_idx(ws.active_column_idx);
@@ -1911,39 +2783,130 @@ impl<W: LayoutElement> Layout<W> {
     }
 
     pub fn move_window_to_output(&mut self, window: &W::Id, output: &Output) {
-        let mut width = None;
-        let mut is_full_width = false;
+        let MonitorSet::Normal { monitors, .. } = &self.monitor_set else {
+            return;
+        };
 
-        if let MonitorSet::Normal { monitors, .. } = &mut self.monitor_set {
-            for mon in &*monitors {
-                for ws in &mon.workspaces {
-                    for col in &ws.columns {
-                        if col.contains(window) {
-                            width = Some(col.width);
-                            is_full_width = col.is_full_width;
-                            break;
-                        }
-                    }
-                }
-            }
+        let column_width = self.column_width_of(window);
+        let is_floating = monitors
+            .iter()
+            .flat_map(|mon| &mon.workspaces)
+            .any(|ws| ws.is_floating(window));
+        if column_width.is_none() && !is_floating {
+            return;
         }
 
-        let Some(width) = width else { return };
-
         let window = self.remove_window(window).unwrap();
+        let window = match column_width {
+            Some((width, is_full_width)) => MovedWindow::Tiled {
+                window,
+                width,
+                is_full_width,
+            },
+            None => MovedWindow::Floating(window),
+        };
 
-        if let MonitorSet::Normal { monitors, .. } = &mut self.monitor_set {
-            let new_idx = monitors
-                .iter()
-                .position(|mon| &mon.output == output)
-                .unwrap();
+        if let MonitorSet::Normal {
+            monitors,
+            active_monitor_idx,
+            ..
+        } = &mut self.monitor_set
+        {
+            let new_idx = monitors
+                .iter()
+                .position(|mon| &mon.output == output)
+                .unwrap();
 
             let workspace_idx = monitors[new_idx].active_workspace_idx;
             // FIXME: activate only if it was already active and focused.
-            self.add_window_by_idx(new_idx, workspace_idx, window, true, width, is_full_width);
+            monitors[new_idx].add_moved_window(workspace_idx, window, true);
+            *active_monitor_idx = new_idx;
+        }
+    }
+
+    /// Moves the windows to the active workspace, into adjacent columns right of the active
+    /// column, in order.
+    pub fn gather_windows(&mut self, windows: &[W::Id]) {
+        let mut right_of = self.focus().map(|win| win.id().clone());
+
+        for id in windows {
+            if right_of.as_ref() == Some(id) {
+                continue;
+            }
+
+            let Some((width, is_full_width)) = self.column_width_of(id) else {
+                continue;
+            };
+            let window = self.remove_window(id).unwrap();
+
+            let MonitorSet::Normal {
+                monitors,
+                active_monitor_idx,
+                ..
+            } = &mut self.monitor_set
+            else {
+                unreachable!()
+            };
+            let mon = &mut monitors[*active_monitor_idx];
+
+            match &right_of {
+                Some(right_of) => mon.add_window_right_of(right_of, window, width, is_full_width),
+                None => {
+                    let idx = mon.active_workspace_idx;
+                    mon.add_window(idx, window, true, width, is_full_width);
+                }
+            }
+
+            right_of = Some(id.clone());
+        }
+    }
+
+    /// Exchanges the places of two windows, which can be on different workspaces and outputs.
+    ///
+    /// Each window takes the size of the other's place.
+    pub fn swap_windows(&mut self, a: &W::Id, b: &W::Id) {
+        if a == b {
+            return;
+        }
+
+        let workspaces: Vec<&mut Workspace<W>> = match &mut self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => monitors
+                .iter_mut()
+                .flat_map(|mon| &mut mon.workspaces)
+                .collect(),
+            MonitorSet::NoOutputs { workspaces } => workspaces.iter_mut().collect(),
+        };
+
+        let mut found = workspaces
+            .into_iter()
+            .filter(|ws| ws.has_window(a) || ws.has_window(b));
+        let Some(first) = found.next() else {
+            return;
+        };
+
+        match found.next() {
+            Some(second) => {
+                let (a, b) = if first.has_window(a) { (a, b) } else { (b, a) };
+                first.swap_windows_with(a, second, b);
+            }
+            None => first.swap_windows(a, b),
         }
     }
 
+    /// Returns the width of the column containing the window, if there are outputs.
+    fn column_width_of(&self, window: &W::Id) -> Option<(ColumnWidth, bool)> {
+        let MonitorSet::Normal { monitors, .. } = &self.monitor_set else {
+            return None;
+        };
+
+        monitors
+            .iter()
+            .flat_map(|mon| &mon.workspaces)
+            .flat_map(|ws| &ws.columns)
+            .find(|col| col.contains(window))
+            .map(|col| (col.width, col.is_full_width))
+    }
+
     pub fn move_workspace_to_output(&mut self, output: &Output) {
         let MonitorSet::Normal {
             monitors,
@@ -1974,6 +2937,8 @@ impl<W: LayoutElement> Layout<W> {
             .unwrap();
         let target = &mut monitors[target_idx];
 
+        ws.update_config(target.options.clone());
+
         target.previous_workspace_id = Some(target.workspaces[target.active_workspace_idx].id());
 
         // Insert the workspace after the currently active one. Unless the currently active one is
@@ -1987,6 +2952,85 @@ impl<W: LayoutElement> Layout<W> {
         *active_monitor_idx = target_idx;
     }
 
+    /// Moves or creates the named workspaces configured to open on this output.
+    ///
+    /// Runs after the output connects, on top of the return of the workspaces that were originally
+    /// on it. This way the rules also bring back workspaces that were moved to other outputs in the
+    /// meantime.
+    pub fn apply_open_on_output_rules(&mut self, output: &Output, ws_configs: &[WorkspaceConfig]) {
+        let name = output.name();
+        for ws_config in ws_configs {
+            let opens_here = ws_config
+                .open_on_output
+                .as_deref()
+                .map_or(false, |o| o.eq_ignore_ascii_case(&name));
+            if !opens_here {
+                continue;
+            }
+
+            self.ensure_named_workspace(ws_config);
+            self.move_named_workspace_to_output(&ws_config.name.0, output);
+        }
+    }
+
+    /// Moves the named workspace to the output without changing the focus.
+    ///
+    /// The workspace goes to the end of the output, just before the empty workspace. Returns
+    /// `false` if there's no such workspace or it's already on that output.
+    pub fn move_named_workspace_to_output(
+        &mut self,
+        workspace_name: &str,
+        output: &Output,
+    ) -> bool {
+        let MonitorSet::Normal { monitors, .. } = &mut self.monitor_set else {
+            return false;
+        };
+
+        let Some(target_idx) = monitors.iter().position(|mon| &mon.output == output) else {
+            return false;
+        };
+
+        let Some((mon_idx, ws_idx)) = monitors.iter().enumerate().find_map(|(mon_idx, mon)| {
+            mon.workspaces
+                .iter()
+                .position(|ws| {
+                    ws.name
+                        .as_ref()
+                        .map_or(false, |name| name.eq_ignore_ascii_case(workspace_name))
+                })
+                .map(|ws_idx| (mon_idx, ws_idx))
+        }) else {
+            return false;
+        };
+
+        if mon_idx == target_idx {
+            return false;
+        }
+
+        let current = &mut monitors[mon_idx];
+        let mut ws = current.workspaces.remove(ws_idx);
+        if ws_idx <= current.active_workspace_idx {
+            current.active_workspace_idx = current.active_workspace_idx.saturating_sub(1);
+        }
+        current.workspace_switch = None;
+        current.clean_up_workspaces();
+
+        ws.set_output(Some(output.clone()));
+        ws.original_output = OutputId::new(output);
+
+        let target = &mut monitors[target_idx];
+        ws.update_config(target.options.clone());
+
+        let target_ws_idx = target.workspaces.len() - 1;
+        target.workspaces.insert(target_ws_idx, ws);
+        if target_ws_idx <= target.active_workspace_idx {
+            target.active_workspace_idx += 1;
+        }
+        target.workspace_switch = None;
+
+        true
+    }
+
     pub fn set_fullscreen(&mut self, window: &W::Id, is_fullscreen: bool) {
         match &mut self.monitor_set {
             MonitorSet::Normal { monitors, .. } => {
@@ -2213,6 +3257,141 @@ impl<W: LayoutElement> Layout<W> {
         false
     }
 
+    /// Starts an interactive move of the window on this output.
+    ///
+    /// Returns `false` if the window is not on the active workspace of this output.
+    pub fn interactive_move_begin(&mut self, window: W::Id, output: &Output) -> bool {
+        let MonitorSet::Normal { monitors, .. } = &mut self.monitor_set else {
+            return false;
+        };
+
+        let Some(mon) = monitors.iter_mut().find(|mon| &mon.output == output) else {
+            return false;
+        };
+
+        mon.interactive_move_begin(window)
+    }
+
+    /// Moves the column with the window towards the column under the given position.
+    ///
+    /// Returns `false` if the window is no longer on the active workspace of this output, which
+    /// means the move should stop.
+    pub fn interactive_move_update(
+        &mut self,
+        window: &W::Id,
+        output: &Output,
+        pos_within_output: Point<f64, Logical>,
+    ) -> bool {
+        let MonitorSet::Normal { monitors, .. } = &mut self.monitor_set else {
+            return false;
+        };
+
+        let Some(mon) = monitors.iter_mut().find(|mon| &mon.output == output) else {
+            return false;
+        };
+
+        mon.interactive_move_update(window, pos_within_output)
+    }
+
+    /// Returns the time when the window held at the edge of this output is due to move to the
+    /// workspace there.
+    pub fn interactive_move_edge_switch_time(&self, output: &Output) -> Option<Duration> {
+        self.monitor_for_output(output)?
+            .interactive_move_edge_switch_time()
+    }
+
+    /// Moves the window held at the edge of this output to the workspace there if it has been
+    /// held long enough.
+    ///
+    /// Returns whether the window moved.
+    pub fn interactive_move_switch_at_edge(
+        &mut self,
+        output: &Output,
+        current_time: Duration,
+    ) -> bool {
+        let MonitorSet::Normal { monitors, .. } = &mut self.monitor_set else {
+            return false;
+        };
+
+        let Some(mon) = monitors.iter_mut().find(|mon| &mon.output == output) else {
+            return false;
+        };
+
+        mon.interactive_move_switch_at_edge(current_time)
+    }
+
+    /// Ends an interactive move on this output, cancelling any pending workspace switch.
+    pub fn interactive_move_end(&mut self, output: &Output) {
+        let MonitorSet::Normal { monitors, .. } = &mut self.monitor_set else {
+            return;
+        };
+
+        if let Some(mon) = monitors.iter_mut().find(|mon| &mon.output == output) {
+            mon.interactive_move_end();
+        }
+    }
+
+    pub fn set_window_height_of(&mut self, window: &W::Id, change: SizeChange) {
+        match &mut self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => {
+                for mon in monitors {
+                    for ws in &mut mon.workspaces {
+                        if ws.has_window(window) {
+                            ws.set_window_height_of(window, change);
+                            return;
+                        }
+                    }
+                }
+            }
+            MonitorSet::NoOutputs { workspaces, .. } => {
+                for ws in workspaces {
+                    if ws.has_window(window) {
+                        ws.set_window_height_of(window, change);
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the zoom of the workspace with the window.
+    pub fn window_zoom(&self, window: &W::Id) -> Option<f64> {
+        match &self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => monitors
+                .iter()
+                .flat_map(|mon| &mon.workspaces)
+                .find(|ws| ws.has_window(window))
+                .map(|ws| ws.zoom()),
+            MonitorSet::NoOutputs { workspaces, .. } => workspaces
+                .iter()
+                .find(|ws| ws.has_window(window))
+                .map(|ws| ws.zoom()),
+        }
+    }
+
+    pub fn window_size_preference(&self, window: &W::Id) -> Option<(ColumnWidth, WindowHeight)> {
+        match &self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => {
+                for mon in monitors {
+                    for ws in &mon.workspaces {
+                        if ws.has_window(window) {
+                            return ws.window_size_preference(window);
+                        }
+                    }
+                }
+            }
+            MonitorSet::NoOutputs { workspaces, .. } => {
+                for ws in workspaces {
+                    if ws.has_window(window) {
+                        return ws.window_size_preference(window);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
     pub fn interactive_resize_end(&mut self, window: &W::Id) {
         match &mut self.monitor_set {
             MonitorSet::Normal { monitors, .. } => {
@@ -2250,31 +3429,82 @@ impl<W: LayoutElement> Layout<W> {
         monitor.move_workspace_up();
     }
 
-    pub fn start_open_animation_for_window(&mut self, window: &W::Id) {
+    /// Starts the open animation for a new window.
+    ///
+    /// `origin` is the output-relative point the window was launched from, if any.
+    pub fn start_open_animation_for_window(
+        &mut self,
+        window: &W::Id,
+        origin: Option<Point<f64, Logical>>,
+    ) {
         match &mut self.monitor_set {
             MonitorSet::Normal { monitors, .. } => {
                 for mon in monitors {
                     for ws in &mut mon.workspaces {
-                        for col in &mut ws.columns {
-                            for tile in &mut col.tiles {
-                                if tile.window().id() == window {
-                                    tile.start_open_animation();
-                                    return;
-                                }
-                            }
+                        if ws.start_open_animation(window, origin) {
+                            return;
                         }
                     }
                 }
             }
             MonitorSet::NoOutputs { workspaces, .. } => {
                 for ws in workspaces {
-                    for col in &mut ws.columns {
-                        for tile in &mut col.tiles {
-                            if tile.window().id() == window {
-                                tile.start_open_animation();
-                                return;
-                            }
-                        }
+                    if ws.start_open_animation(window, origin) {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Shows a placeholder for a window that is about to open.
+    ///
+    /// The placeholder goes where a new column opens on the named workspace, or on the active
+    /// workspace of the output, or of the active output.
+    pub fn show_insert_hint(
+        &mut self,
+        window: W::Id,
+        output: Option<&Output>,
+        workspace_name: Option<&str>,
+        width: ColumnWidth,
+    ) {
+        self.hide_insert_hint(&window);
+
+        let MonitorSet::Normal {
+            monitors,
+            active_monitor_idx,
+            ..
+        } = &mut self.monitor_set
+        else {
+            return;
+        };
+
+        let named = workspace_name.and_then(|name| {
+            monitors.iter().enumerate().find_map(|(mon_idx, mon)| {
+                mon.find_named_workspace_index(name)
+                    .map(|ws_idx| (mon_idx, ws_idx))
+            })
+        });
+        let (mon_idx, ws_idx) = match named {
+            Some(idx) => idx,
+            None => {
+                let mon_idx = output
+                    .and_then(|output| monitors.iter().position(|mon| mon.output == *output))
+                    .unwrap_or(*active_monitor_idx);
+                (mon_idx, monitors[mon_idx].active_workspace_idx)
+            }
+        };
+
+        monitors[mon_idx].workspaces[ws_idx].add_insert_hint(window, width);
+    }
+
+    /// Hides the placeholder for a window that is about to open.
+    pub fn hide_insert_hint(&mut self, window: &W::Id) {
+        if let MonitorSet::Normal { monitors, .. } = &mut self.monitor_set {
+            for mon in monitors {
+                for ws in &mut mon.workspaces {
+                    if ws.remove_insert_hint(window) {
+                        return;
                     }
                 }
             }
@@ -2369,8 +3599,20 @@ impl<W: LayoutElement> Layout<W> {
             } => {
                 for (idx, mon) in monitors.iter_mut().enumerate() {
                     let is_active = idx == *active_monitor_idx;
+                    let render_idx = mon.render_idx();
+                    let wrap_target = mon.wrap_switch_target();
                     for (ws_idx, ws) in mon.workspaces.iter_mut().enumerate() {
-                        ws.refresh(is_active);
+                        let is_visible = match render_idx {
+                            Some(render_idx) => {
+                                let ws_render_idx = match wrap_target {
+                                    Some((idx, virtual_idx)) if idx == ws_idx => virtual_idx,
+                                    _ => ws_idx as f64,
+                                };
+                                (render_idx - ws_render_idx).abs() < 1.
+                            }
+                            None => ws_idx == mon.active_workspace_idx,
+                        };
+                        ws.refresh(is_active, is_visible);
 
                         // Cancel the view offset gesture after workspace switches, moves, etc.
                         if ws_idx != mon.active_workspace_idx {
@@ -2378,17 +3620,54 @@ impl<W: LayoutElement> Layout<W> {
                         }
                     }
                 }
+
+                // Keep track of the recently used workspace order.
+                let last_focus_serial = monitors
+                    .iter()
+                    .flat_map(|mon| &mon.workspaces)
+                    .map(|ws| ws.focus_serial())
+                    .max()
+                    .unwrap_or(0);
+                let mon = &mut monitors[*active_monitor_idx];
+                let ws = &mut mon.workspaces[mon.active_workspace_idx];
+                if ws.focus_serial() == 0 || ws.focus_serial() != last_focus_serial {
+                    ws.mark_focused();
+                }
             }
             MonitorSet::NoOutputs { workspaces, .. } => {
                 for ws in workspaces {
-                    ws.refresh(false);
+                    ws.refresh(false, false);
                     ws.view_offset_gesture_end(false, None);
                 }
             }
         }
     }
 
-    pub fn ipc_workspaces(&self) -> Vec<niri_ipc::Workspace> {
+    /// Returns the IPC state of all workspaces.
+    ///
+    /// `ipc_window` converts a window into its IPC id and representation, and `is_urgent` tells
+    /// whether a window requested attention.
+    pub fn ipc_workspaces(
+        &self,
+        ipc_window: impl Fn(&W) -> (u32, niri_ipc::Window),
+        is_urgent: impl Fn(&W) -> bool,
+    ) -> Vec<niri_ipc::Workspace> {
+        let make_workspace = |idx: usize, ws: &Workspace<W>, output, is_active, visibility| {
+            let (active_window_id, active_window) = ws.active_window().map(&ipc_window).unzip();
+
+            niri_ipc::Workspace {
+                idx: u8::try_from(idx + 1).unwrap_or(u8::MAX),
+                name: ws.name.clone(),
+                output,
+                is_active,
+                visibility,
+                window_count: u32::try_from(ws.windows().count()).unwrap_or(u32::MAX),
+                active_window_id,
+                active_window,
+                is_urgent: ws.windows().any(&is_urgent),
+            }
+        };
+
         match &self.monitor_set {
             MonitorSet::Normal {
                 monitors,
@@ -2398,13 +3677,24 @@ impl<W: LayoutElement> Layout<W> {
                 let mut workspaces = Vec::new();
 
                 for monitor in monitors {
+                    let render_idx = monitor
+                        .workspace_switch
+                        .as_ref()
+                        .map_or(monitor.active_workspace_idx as f64, |switch| {
+                            switch.current_idx()
+                        });
+
                     for (idx, workspace) in monitor.workspaces.iter().enumerate() {
-                        workspaces.push(niri_ipc::Workspace {
-                            idx: u8::try_from(idx + 1).unwrap_or(u8::MAX),
-                            name: workspace.name.clone(),
-                            output: Some(monitor.output.name()),
-                            is_active: monitor.active_workspace_idx == idx,
-                        })
+                        let ws_render_idx = monitor.render_idx_of(idx);
+                        let visibility = (1. - (render_idx - ws_render_idx).abs()).max(0.);
+                        let visibility = (visibility * 100.).round() as u8;
+                        workspaces.push(make_workspace(
+                            idx,
+                            workspace,
+                            Some(monitor.output.name()),
+                            monitor.active_workspace_idx == idx,
+                            visibility,
+                        ));
                     }
                 }
 
@@ -2413,35 +3703,70 @@ impl<W: LayoutElement> Layout<W> {
             MonitorSet::NoOutputs { workspaces } => workspaces
                 .iter()
                 .enumerate()
-                .map(|(idx, ws)| niri_ipc::Workspace {
-                    idx: u8::try_from(idx + 1).unwrap_or(u8::MAX),
-                    name: ws.name.clone(),
-                    output: None,
-                    is_active: false,
-                })
+                .map(|(idx, ws)| make_workspace(idx, ws, None, false, 0))
                 .collect(),
         }
     }
 }
 
+impl<W: LayoutElement> MonitorSet<W> {
+    /// Returns the monitor under the position in the global space, and the position within its
+    /// output.
+    fn monitor_under(
+        &self,
+        pos: Point<f64, Logical>,
+    ) -> Option<(&Monitor<W>, Point<f64, Logical>)> {
+        let MonitorSet::Normal { monitors, .. } = self else {
+            return None;
+        };
+
+        monitors.iter().find_map(|mon| {
+            let loc = mon.output.current_location().to_f64();
+            let geo = Rectangle::from_loc_and_size(loc, output_size(&mon.output));
+            geo.contains(pos).then(|| (mon, pos - loc))
+        })
+    }
+
+    /// Returns the window under the position in the global space, and the window position in the
+    /// global space.
+    ///
+    /// The position goes through the output geometries to the right monitor, and then to the
+    /// workspace visible under it, which during a workspace switch may be either of the two.
+    fn element_under(&self, pos: Point<f64, Logical>) -> Option<(&W, Option<Point<f64, Logical>>)> {
+        let (mon, pos_within_output) = self.monitor_under(pos)?;
+        let loc = pos - pos_within_output;
+        let (win, win_pos) = mon.window_under(pos_within_output)?;
+        Some((win, win_pos.map(|p| p + loc)))
+    }
+}
+
 impl<W: LayoutElement> Default for MonitorSet<W> {
     fn default() -> Self {
         Self::NoOutputs { workspaces: vec![] }
     }
 }
 
+fn output_layouts_from_config(config: &Config) -> Vec<(String, LayoutOverride)> {
+    config
+        .outputs
+        .iter()
+        .filter(|output| output.layout != LayoutOverride::default())
+        .map(|output| (output.name.clone(), output.layout.clone()))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use std::cell::Cell;
+    use std::fmt::Write as _;
 
-    use niri_config::{FloatOrInt, WorkspaceName};
+    use niri_config::{FloatOrInt, WorkspaceName, WorkspaceSwitchStyle};
     use proptest::prelude::*;
-    use proptest_derive::Arbitrary;
     use smithay::output::{Mode, PhysicalProperties, Subpixel};
-    use smithay::utils::Rectangle;
 
+    use super::ops::{Op, Recording, TestWindow};
     use super::*;
-    use crate::utils::round_logical_in_physical;
+    use crate::utils::clock::ManualClock;
+    use crate::utils::{get_monotonic_time, round_logical_in_physical};
 
     impl<W: LayoutElement> Default for Layout<W> {
         fn default() -> Self {
@@ -2449,800 +3774,18 @@ mod tests {
         }
     }
 
-    #[derive(Debug)]
-    struct TestWindowInner {
-        id: usize,
-        bbox: Cell<Rectangle<i32, Logical>>,
-        initial_bbox: Rectangle<i32, Logical>,
-        requested_size: Cell<Option<Size<i32, Logical>>>,
-        min_size: Size<i32, Logical>,
-        max_size: Size<i32, Logical>,
-        pending_fullscreen: Cell<bool>,
+    #[track_caller]
+    fn check_ops(ops: &[Op]) {
+        let mut layout = Layout::default();
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
     }
 
-    #[derive(Debug, Clone)]
-    struct TestWindow(Rc<TestWindowInner>);
-
-    impl TestWindow {
-        fn new(
-            id: usize,
-            bbox: Rectangle<i32, Logical>,
-            min_size: Size<i32, Logical>,
-            max_size: Size<i32, Logical>,
-        ) -> Self {
-            Self(Rc::new(TestWindowInner {
-                id,
-                bbox: Cell::new(bbox),
-                initial_bbox: bbox,
-                requested_size: Cell::new(None),
-                min_size,
-                max_size,
-                pending_fullscreen: Cell::new(false),
-            }))
-        }
-
-        fn communicate(&self) -> bool {
-            if let Some(size) = self.0.requested_size.take() {
-                assert!(size.w >= 0);
-                assert!(size.h >= 0);
-
-                let mut new_bbox = self.0.initial_bbox;
-                if size.w != 0 {
-                    new_bbox.size.w = size.w;
-                }
-                if size.h != 0 {
-                    new_bbox.size.h = size.h;
-                }
-
-                if self.0.bbox.get() != new_bbox {
-                    self.0.bbox.set(new_bbox);
-                    return true;
-                }
-            }
-
-            false
-        }
-    }
-
-    impl LayoutElement for TestWindow {
-        type Id = usize;
-
-        fn id(&self) -> &Self::Id {
-            &self.0.id
-        }
-
-        fn size(&self) -> Size<i32, Logical> {
-            self.0.bbox.get().size
-        }
-
-        fn buf_loc(&self) -> Point<i32, Logical> {
-            (0, 0).into()
-        }
-
-        fn is_in_input_region(&self, _point: Point<f64, Logical>) -> bool {
-            false
-        }
-
-        fn render<R: NiriRenderer>(
-            &self,
-            _renderer: &mut R,
-            _location: Point<f64, Logical>,
-            _scale: Scale<f64>,
-            _alpha: f32,
-            _target: RenderTarget,
-        ) -> SplitElements<LayoutElementRenderElement<R>> {
-            SplitElements::default()
-        }
-
-        fn request_size(&mut self, size: Size<i32, Logical>, _animate: bool) {
-            self.0.requested_size.set(Some(size));
-            self.0.pending_fullscreen.set(false);
-        }
-
-        fn request_fullscreen(&self, _size: Size<i32, Logical>) {
-            self.0.pending_fullscreen.set(true);
-        }
-
-        fn min_size(&self) -> Size<i32, Logical> {
-            self.0.min_size
-        }
-
-        fn max_size(&self) -> Size<i32, Logical> {
-            self.0.max_size
-        }
-
-        fn is_wl_surface(&self, _wl_surface: &WlSurface) -> bool {
-            false
-        }
-
-        fn set_preferred_scale_transform(&self, _scale: output::Scale, _transform: Transform) {}
-
-        fn has_ssd(&self) -> bool {
-            false
-        }
-
-        fn output_enter(&self, _output: &Output) {}
-
-        fn output_leave(&self, _output: &Output) {}
-
-        fn set_offscreen_element_id(&self, _id: Option<Id>) {}
-
-        fn set_activated(&mut self, _active: bool) {}
-
-        fn set_bounds(&self, _bounds: Size<i32, Logical>) {}
-
-        fn send_pending_configure(&mut self) {}
-
-        fn set_active_in_column(&mut self, _active: bool) {}
-
-        fn is_fullscreen(&self) -> bool {
-            false
-        }
-
-        fn is_pending_fullscreen(&self) -> bool {
-            self.0.pending_fullscreen.get()
-        }
-
-        fn refresh(&self) {}
-
-        fn rules(&self) -> &ResolvedWindowRules {
-            static EMPTY: ResolvedWindowRules = ResolvedWindowRules::empty();
-            &EMPTY
-        }
-
-        fn animation_snapshot(&self) -> Option<&LayoutElementRenderSnapshot> {
-            None
-        }
-
-        fn take_animation_snapshot(&mut self) -> Option<LayoutElementRenderSnapshot> {
-            None
-        }
-
-        fn set_interactive_resize(&mut self, _data: Option<InteractiveResizeData>) {}
-
-        fn cancel_interactive_resize(&mut self) {}
-
-        fn update_interactive_resize(&mut self, _serial: Serial) {}
-
-        fn interactive_resize_data(&self) -> Option<InteractiveResizeData> {
-            None
-        }
-    }
-
-    fn arbitrary_bbox() -> impl Strategy<Value = Rectangle<i32, Logical>> {
-        any::<(i16, i16, u16, u16)>().prop_map(|(x, y, w, h)| {
-            let loc: Point<i32, _> = Point::from((x.into(), y.into()));
-            let size: Size<i32, _> = Size::from((w.into(), h.into()));
-            Rectangle::from_loc_and_size(loc, size)
-        })
-    }
-
-    fn arbitrary_size_change() -> impl Strategy<Value = SizeChange> {
-        prop_oneof![
-            (0..).prop_map(SizeChange::SetFixed),
-            (0f64..).prop_map(SizeChange::SetProportion),
-            any::<i32>().prop_map(SizeChange::AdjustFixed),
-            any::<f64>().prop_map(SizeChange::AdjustProportion),
-        ]
-    }
-
-    fn arbitrary_min_max() -> impl Strategy<Value = (i32, i32)> {
-        prop_oneof![
-            Just((0, 0)),
-            (1..65536).prop_map(|n| (n, n)),
-            (1..65536).prop_map(|min| (min, 0)),
-            (1..).prop_map(|max| (0, max)),
-            (1..65536, 1..).prop_map(|(min, max): (i32, i32)| (min, max.max(min))),
-        ]
-    }
-
-    fn arbitrary_min_max_size() -> impl Strategy<Value = (Size<i32, Logical>, Size<i32, Logical>)> {
-        (arbitrary_min_max(), arbitrary_min_max()).prop_map(|((min_w, max_w), (min_h, max_h))| {
-            let min_size = Size::from((min_w, min_h));
-            let max_size = Size::from((max_w, max_h));
-            (min_size, max_size)
-        })
-    }
-
-    fn arbitrary_view_offset_gesture_delta() -> impl Strategy<Value = f64> {
-        prop_oneof![(-10f64..10f64), (-50000f64..50000f64),]
-    }
-
-    fn arbitrary_resize_edge() -> impl Strategy<Value = ResizeEdge> {
-        prop_oneof![
-            Just(ResizeEdge::RIGHT),
-            Just(ResizeEdge::BOTTOM),
-            Just(ResizeEdge::LEFT),
-            Just(ResizeEdge::TOP),
-            Just(ResizeEdge::BOTTOM_RIGHT),
-            Just(ResizeEdge::BOTTOM_LEFT),
-            Just(ResizeEdge::TOP_RIGHT),
-            Just(ResizeEdge::TOP_LEFT),
-            Just(ResizeEdge::empty()),
-        ]
-    }
-
-    fn arbitrary_scale() -> impl Strategy<Value = f64> {
-        prop_oneof![Just(1.), Just(1.5), Just(2.),]
-    }
-
-    #[derive(Debug, Clone, Copy, Arbitrary)]
-    enum Op {
-        AddOutput(#[proptest(strategy = "1..=5usize")] usize),
-        AddScaledOutput {
-            #[proptest(strategy = "1..=5usize")]
-            id: usize,
-            #[proptest(strategy = "arbitrary_scale()")]
-            scale: f64,
-        },
-        RemoveOutput(#[proptest(strategy = "1..=5usize")] usize),
-        FocusOutput(#[proptest(strategy = "1..=5usize")] usize),
-        AddNamedWorkspace {
-            #[proptest(strategy = "1..=5usize")]
-            ws_name: usize,
-            #[proptest(strategy = "prop::option::of(1..=5usize)")]
-            output_name: Option<usize>,
-        },
-        UnnameWorkspace {
-            #[proptest(strategy = "1..=5usize")]
-            ws_name: usize,
-        },
-        AddWindow {
-            #[proptest(strategy = "1..=5usize")]
-            id: usize,
-            #[proptest(strategy = "arbitrary_bbox()")]
-            bbox: Rectangle<i32, Logical>,
-            #[proptest(strategy = "arbitrary_min_max_size()")]
-            min_max_size: (Size<i32, Logical>, Size<i32, Logical>),
-        },
-        AddWindowRightOf {
-            #[proptest(strategy = "1..=5usize")]
-            id: usize,
-            #[proptest(strategy = "1..=5usize")]
-            right_of_id: usize,
-            #[proptest(strategy = "arbitrary_bbox()")]
-            bbox: Rectangle<i32, Logical>,
-            #[proptest(strategy = "arbitrary_min_max_size()")]
-            min_max_size: (Size<i32, Logical>, Size<i32, Logical>),
-        },
-        AddWindowToNamedWorkspace {
-            #[proptest(strategy = "1..=5usize")]
-            id: usize,
-            #[proptest(strategy = "1..=5usize")]
-            ws_name: usize,
-            #[proptest(strategy = "arbitrary_bbox()")]
-            bbox: Rectangle<i32, Logical>,
-            #[proptest(strategy = "arbitrary_min_max_size()")]
-            min_max_size: (Size<i32, Logical>, Size<i32, Logical>),
-        },
-        CloseWindow(#[proptest(strategy = "1..=5usize")] usize),
-        FullscreenWindow(#[proptest(strategy = "1..=5usize")] usize),
-        SetFullscreenWindow {
-            #[proptest(strategy = "1..=5usize")]
-            window: usize,
-            is_fullscreen: bool,
-        },
-        FocusColumnLeft,
-        FocusColumnRight,
-        FocusColumnFirst,
-        FocusColumnLast,
-        FocusColumnRightOrFirst,
-        FocusColumnLeftOrLast,
-        FocusColumnOrMonitorLeft(#[proptest(strategy = "1..=2u8")] u8),
-        FocusColumnOrMonitorRight(#[proptest(strategy = "1..=2u8")] u8),
-        FocusWindowDown,
-        FocusWindowUp,
-        FocusWindowDownOrColumnLeft,
-        FocusWindowDownOrColumnRight,
-        FocusWindowUpOrColumnLeft,
-        FocusWindowUpOrColumnRight,
-        FocusWindowOrWorkspaceDown,
-        FocusWindowOrWorkspaceUp,
-        MoveColumnLeft,
-        MoveColumnRight,
-        MoveColumnToFirst,
-        MoveColumnToLast,
-        MoveWindowDown,
-        MoveWindowUp,
-        MoveWindowDownOrToWorkspaceDown,
-        MoveWindowUpOrToWorkspaceUp,
-        ConsumeOrExpelWindowLeft,
-        ConsumeOrExpelWindowRight,
-        ConsumeWindowIntoColumn,
-        ExpelWindowFromColumn,
-        CenterColumn,
-        FocusWorkspaceDown,
-        FocusWorkspaceUp,
-        FocusWorkspace(#[proptest(strategy = "0..=4usize")] usize),
-        FocusWorkspaceAutoBackAndForth(#[proptest(strategy = "0..=4usize")] usize),
-        FocusWorkspacePrevious,
-        MoveWindowToWorkspaceDown,
-        MoveWindowToWorkspaceUp,
-        MoveWindowToWorkspace(#[proptest(strategy = "0..=4usize")] usize),
-        MoveColumnToWorkspaceDown,
-        MoveColumnToWorkspaceUp,
-        MoveColumnToWorkspace(#[proptest(strategy = "0..=4usize")] usize),
-        MoveWorkspaceDown,
-        MoveWorkspaceUp,
-        MoveWindowToOutput(#[proptest(strategy = "1..=5u8")] u8),
-        MoveColumnToOutput(#[proptest(strategy = "1..=5u8")] u8),
-        SwitchPresetColumnWidth,
-        MaximizeColumn,
-        SetColumnWidth(#[proptest(strategy = "arbitrary_size_change()")] SizeChange),
-        SetWindowHeight(#[proptest(strategy = "arbitrary_size_change()")] SizeChange),
-        ResetWindowHeight,
-        Communicate(#[proptest(strategy = "1..=5usize")] usize),
-        MoveWorkspaceToOutput(#[proptest(strategy = "1..=5u8")] u8),
-        ViewOffsetGestureBegin {
-            #[proptest(strategy = "1..=5usize")]
-            output_idx: usize,
-            is_touchpad: bool,
-        },
-        ViewOffsetGestureUpdate {
-            #[proptest(strategy = "arbitrary_view_offset_gesture_delta()")]
-            delta: f64,
-            timestamp: Duration,
-            is_touchpad: bool,
-        },
-        ViewOffsetGestureEnd {
-            is_touchpad: Option<bool>,
-        },
-        WorkspaceSwitchGestureBegin {
-            #[proptest(strategy = "1..=5usize")]
-            output_idx: usize,
-            is_touchpad: bool,
-        },
-        WorkspaceSwitchGestureUpdate {
-            #[proptest(strategy = "-400f64..400f64")]
-            delta: f64,
-            timestamp: Duration,
-            is_touchpad: bool,
-        },
-        WorkspaceSwitchGestureEnd {
-            cancelled: bool,
-            is_touchpad: Option<bool>,
-        },
-        InteractiveResizeBegin {
-            #[proptest(strategy = "1..=5usize")]
-            window: usize,
-            #[proptest(strategy = "arbitrary_resize_edge()")]
-            edges: ResizeEdge,
-        },
-        InteractiveResizeUpdate {
-            #[proptest(strategy = "1..=5usize")]
-            window: usize,
-            #[proptest(strategy = "-20000f64..20000f64")]
-            dx: f64,
-            #[proptest(strategy = "-20000f64..20000f64")]
-            dy: f64,
-        },
-        InteractiveResizeEnd {
-            #[proptest(strategy = "1..=5usize")]
-            window: usize,
-        },
-    }
-
-    impl Op {
-        fn apply(self, layout: &mut Layout<TestWindow>) {
-            match self {
-                Op::AddOutput(id) => {
-                    let name = format!("output{id}");
-                    if layout.outputs().any(|o| o.name() == name) {
-                        return;
-                    }
-
-                    let output = Output::new(
-                        name,
-                        PhysicalProperties {
-                            size: Size::from((1280, 720)),
-                            subpixel: Subpixel::Unknown,
-                            make: String::new(),
-                            model: String::new(),
-                        },
-                    );
-                    output.change_current_state(
-                        Some(Mode {
-                            size: Size::from((1280, 720)),
-                            refresh: 60000,
-                        }),
-                        None,
-                        None,
-                        None,
-                    );
-                    layout.add_output(output.clone());
-                }
-                Op::AddScaledOutput { id, scale } => {
-                    let name = format!("output{id}");
-                    if layout.outputs().any(|o| o.name() == name) {
-                        return;
-                    }
-
-                    let output = Output::new(
-                        name,
-                        PhysicalProperties {
-                            size: Size::from((1280, 720)),
-                            subpixel: Subpixel::Unknown,
-                            make: String::new(),
-                            model: String::new(),
-                        },
-                    );
-                    output.change_current_state(
-                        Some(Mode {
-                            size: Size::from((1280, 720)),
-                            refresh: 60000,
-                        }),
-                        None,
-                        Some(smithay::output::Scale::Fractional(scale)),
-                        None,
-                    );
-                    layout.add_output(output.clone());
-                }
-                Op::RemoveOutput(id) => {
-                    let name = format!("output{id}");
-                    let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
-                        return;
-                    };
-
-                    layout.remove_output(&output);
-                }
-                Op::FocusOutput(id) => {
-                    let name = format!("output{id}");
-                    let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
-                        return;
-                    };
-
-                    layout.focus_output(&output);
-                }
-                Op::AddNamedWorkspace {
-                    ws_name,
-                    output_name,
-                } => {
-                    layout.ensure_named_workspace(&WorkspaceConfig {
-                        name: WorkspaceName(format!("ws{ws_name}")),
-                        open_on_output: output_name.map(|name| format!("output{name}")),
-                    });
-                }
-                Op::UnnameWorkspace { ws_name } => {
-                    layout.unname_workspace(&format!("ws{ws_name}"));
-                }
-                Op::AddWindow {
-                    id,
-                    bbox,
-                    min_max_size,
-                } => {
-                    match &mut layout.monitor_set {
-                        MonitorSet::Normal { monitors, .. } => {
-                            for mon in monitors {
-                                for ws in &mut mon.workspaces {
-                                    for win in ws.windows() {
-                                        if win.0.id == id {
-                                            return;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        MonitorSet::NoOutputs { workspaces, .. } => {
-                            for ws in workspaces {
-                                for win in ws.windows() {
-                                    if win.0.id == id {
-                                        return;
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    let win = TestWindow::new(id, bbox, min_max_size.0, min_max_size.1);
-                    layout.add_window(win, None, false);
-                }
-                Op::AddWindowRightOf {
-                    id,
-                    right_of_id,
-                    bbox,
-                    min_max_size,
-                } => {
-                    let mut found_right_of = false;
-
-                    match &mut layout.monitor_set {
-                        MonitorSet::Normal { monitors, .. } => {
-                            for mon in monitors {
-                                for ws in &mut mon.workspaces {
-                                    for win in ws.windows() {
-                                        if win.0.id == id {
-                                            return;
-                                        }
-
-                                        if win.0.id == right_of_id {
-                                            found_right_of = true;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        MonitorSet::NoOutputs { workspaces, .. } => {
-                            for ws in workspaces {
-                                for win in ws.windows() {
-                                    if win.0.id == id {
-                                        return;
-                                    }
-
-                                    if win.0.id == right_of_id {
-                                        found_right_of = true;
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    if !found_right_of {
-                        return;
-                    }
-
-                    let win = TestWindow::new(id, bbox, min_max_size.0, min_max_size.1);
-                    layout.add_window_right_of(&right_of_id, win, None, false);
-                }
-                Op::AddWindowToNamedWorkspace {
-                    id,
-                    ws_name,
-                    bbox,
-                    min_max_size,
-                } => {
-                    let ws_name = format!("ws{ws_name}");
-                    let mut found_workspace = false;
-
-                    match &mut layout.monitor_set {
-                        MonitorSet::Normal { monitors, .. } => {
-                            for mon in monitors {
-                                for ws in &mut mon.workspaces {
-                                    for win in ws.windows() {
-                                        if win.0.id == id {
-                                            return;
-                                        }
-                                    }
-
-                                    if ws
-                                        .name
-                                        .as_ref()
-                                        .map_or(false, |name| name.eq_ignore_ascii_case(&ws_name))
-                                    {
-                                        found_workspace = true;
-                                    }
-                                }
-                            }
-                        }
-                        MonitorSet::NoOutputs { workspaces, .. } => {
-                            for ws in workspaces {
-                                for win in ws.windows() {
-                                    if win.0.id == id {
-                                        return;
-                                    }
-                                }
-
-                                if ws
-                                    .name
-                                    .as_ref()
-                                    .map_or(false, |name| name.eq_ignore_ascii_case(&ws_name))
-                                {
-                                    found_workspace = true;
-                                }
-                            }
-                        }
-                    }
-
-                    if !found_workspace {
-                        return;
-                    }
-
-                    let win = TestWindow::new(id, bbox, min_max_size.0, min_max_size.1);
-                    layout.add_window_to_named_workspace(&ws_name, win, None, false);
-                }
-                Op::CloseWindow(id) => {
-                    layout.remove_window(&id);
-                }
-                Op::FullscreenWindow(id) => {
-                    layout.toggle_fullscreen(&id);
-                }
-                Op::SetFullscreenWindow {
-                    window,
-                    is_fullscreen,
-                } => {
-                    layout.set_fullscreen(&window, is_fullscreen);
-                }
-                Op::FocusColumnLeft => layout.focus_left(),
-                Op::FocusColumnRight => layout.focus_right(),
-                Op::FocusColumnFirst => layout.focus_column_first(),
-                Op::FocusColumnLast => layout.focus_column_last(),
-                Op::FocusColumnRightOrFirst => layout.focus_column_right_or_first(),
-                Op::FocusColumnLeftOrLast => layout.focus_column_left_or_last(),
-                Op::FocusColumnOrMonitorLeft(id) => {
-                    let name = format!("output{id}");
-                    let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
-                        return;
-                    };
-
-                    layout.focus_column_left_or_output(&output);
-                }
-                Op::FocusColumnOrMonitorRight(id) => {
-                    let name = format!("output{id}");
-                    let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
-                        return;
-                    };
-
-                    layout.focus_column_right_or_output(&output);
-                }
-                Op::FocusWindowDown => layout.focus_down(),
-                Op::FocusWindowUp => layout.focus_up(),
-                Op::FocusWindowDownOrColumnLeft => layout.focus_down_or_left(),
-                Op::FocusWindowDownOrColumnRight => layout.focus_down_or_right(),
-                Op::FocusWindowUpOrColumnLeft => layout.focus_up_or_left(),
-                Op::FocusWindowUpOrColumnRight => layout.focus_up_or_right(),
-                Op::FocusWindowOrWorkspaceDown => layout.focus_window_or_workspace_down(),
-                Op::FocusWindowOrWorkspaceUp => layout.focus_window_or_workspace_up(),
-                Op::MoveColumnLeft => layout.move_left(),
-                Op::MoveColumnRight => layout.move_right(),
-                Op::MoveColumnToFirst => layout.move_column_to_first(),
-                Op::MoveColumnToLast => layout.move_column_to_last(),
-                Op::MoveWindowDown => layout.move_down(),
-                Op::MoveWindowUp => layout.move_up(),
-                Op::MoveWindowDownOrToWorkspaceDown => layout.move_down_or_to_workspace_down(),
-                Op::MoveWindowUpOrToWorkspaceUp => layout.move_up_or_to_workspace_up(),
-                Op::ConsumeOrExpelWindowLeft => layout.consume_or_expel_window_left(),
-                Op::ConsumeOrExpelWindowRight => layout.consume_or_expel_window_right(),
-                Op::ConsumeWindowIntoColumn => layout.consume_into_column(),
-                Op::ExpelWindowFromColumn => layout.expel_from_column(),
-                Op::CenterColumn => layout.center_column(),
-                Op::FocusWorkspaceDown => layout.switch_workspace_down(),
-                Op::FocusWorkspaceUp => layout.switch_workspace_up(),
-                Op::FocusWorkspace(idx) => layout.switch_workspace(idx),
-                Op::FocusWorkspaceAutoBackAndForth(idx) => {
-                    layout.switch_workspace_auto_back_and_forth(idx)
-                }
-                Op::FocusWorkspacePrevious => layout.switch_workspace_previous(),
-                Op::MoveWindowToWorkspaceDown => layout.move_to_workspace_down(),
-                Op::MoveWindowToWorkspaceUp => layout.move_to_workspace_up(),
-                Op::MoveWindowToWorkspace(idx) => layout.move_to_workspace(idx),
-                Op::MoveColumnToWorkspaceDown => layout.move_column_to_workspace_down(),
-                Op::MoveColumnToWorkspaceUp => layout.move_column_to_workspace_up(),
-                Op::MoveColumnToWorkspace(idx) => layout.move_column_to_workspace(idx),
-                Op::MoveWindowToOutput(id) => {
-                    let name = format!("output{id}");
-                    let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
-                        return;
-                    };
-
-                    layout.move_to_output(&output);
-                }
-                Op::MoveColumnToOutput(id) => {
-                    let name = format!("output{id}");
-                    let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
-                        return;
-                    };
-
-                    layout.move_column_to_output(&output);
-                }
-                Op::MoveWorkspaceDown => layout.move_workspace_down(),
-                Op::MoveWorkspaceUp => layout.move_workspace_up(),
-                Op::SwitchPresetColumnWidth => layout.toggle_width(),
-                Op::MaximizeColumn => layout.toggle_full_width(),
-                Op::SetColumnWidth(change) => layout.set_column_width(change),
-                Op::SetWindowHeight(change) => layout.set_window_height(change),
-                Op::ResetWindowHeight => layout.reset_window_height(),
-                Op::Communicate(id) => {
-                    let mut update = false;
-                    match &mut layout.monitor_set {
-                        MonitorSet::Normal { monitors, .. } => {
-                            'outer: for mon in monitors {
-                                for ws in &mut mon.workspaces {
-                                    for win in ws.windows() {
-                                        if win.0.id == id {
-                                            if win.communicate() {
-                                                update = true;
-                                            }
-                                            break 'outer;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        MonitorSet::NoOutputs { workspaces, .. } => {
-                            'outer: for ws in workspaces {
-                                for win in ws.windows() {
-                                    if win.0.id == id {
-                                        if win.communicate() {
-                                            update = true;
-                                        }
-                                        break 'outer;
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    if update {
-                        // FIXME: serial.
-                        layout.update_window(&id, None);
-                    }
-                }
-                Op::MoveWorkspaceToOutput(id) => {
-                    let name = format!("output{id}");
-                    let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
-                        return;
-                    };
-
-                    layout.move_workspace_to_output(&output);
-                }
-                Op::ViewOffsetGestureBegin {
-                    output_idx: id,
-                    is_touchpad: normalize,
-                } => {
-                    let name = format!("output{id}");
-                    let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
-                        return;
-                    };
-
-                    layout.view_offset_gesture_begin(&output, normalize);
-                }
-                Op::ViewOffsetGestureUpdate {
-                    delta,
-                    timestamp,
-                    is_touchpad,
-                } => {
-                    layout.view_offset_gesture_update(delta, timestamp, is_touchpad);
-                }
-                Op::ViewOffsetGestureEnd { is_touchpad } => {
-                    // We don't handle cancels in this gesture.
-                    layout.view_offset_gesture_end(false, is_touchpad);
-                }
-                Op::WorkspaceSwitchGestureBegin {
-                    output_idx: id,
-                    is_touchpad,
-                } => {
-                    let name = format!("output{id}");
-                    let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
-                        return;
-                    };
-
-                    layout.workspace_switch_gesture_begin(&output, is_touchpad);
-                }
-                Op::WorkspaceSwitchGestureUpdate {
-                    delta,
-                    timestamp,
-                    is_touchpad,
-                } => {
-                    layout.workspace_switch_gesture_update(delta, timestamp, is_touchpad);
-                }
-                Op::WorkspaceSwitchGestureEnd {
-                    cancelled,
-                    is_touchpad,
-                } => {
-                    layout.workspace_switch_gesture_end(cancelled, is_touchpad);
-                }
-                Op::InteractiveResizeBegin { window, edges } => {
-                    layout.interactive_resize_begin(window, edges);
-                }
-                Op::InteractiveResizeUpdate { window, dx, dy } => {
-                    layout.interactive_resize_update(&window, Point::from((dx, dy)));
-                }
-                Op::InteractiveResizeEnd { window } => {
-                    layout.interactive_resize_end(&window);
-                }
-            }
-        }
-    }
-
-    #[track_caller]
-    fn check_ops(ops: &[Op]) {
-        let mut layout = Layout::default();
-        for op in ops {
-            op.apply(&mut layout);
-            layout.verify_invariants();
-        }
-    }
-
-    #[track_caller]
-    fn check_ops_with_options(options: Options, ops: &[Op]) {
-        let mut layout = Layout::with_options(options);
+    #[track_caller]
+    fn check_ops_with_options(options: Options, ops: &[Op]) {
+        let mut layout = Layout::with_options(options);
 
         for op in ops {
             op.apply(&mut layout);
@@ -3256,17 +3799,25 @@ mod tests {
             Op::AddOutput(0),
             Op::AddOutput(1),
             Op::AddOutput(2),
+            Op::AddOutputWithMonitor { id: 1, monitor: 1 },
             Op::RemoveOutput(0),
             Op::RemoveOutput(1),
             Op::RemoveOutput(2),
             Op::FocusOutput(0),
             Op::FocusOutput(1),
             Op::FocusOutput(2),
+            Op::SetPrimaryOutput(0),
+            Op::SetPrimaryOutput(1),
+            Op::SetPrimaryOutput(2),
             Op::AddNamedWorkspace {
                 ws_name: 1,
                 output_name: Some(1),
             },
             Op::UnnameWorkspace { ws_name: 1 },
+            Op::MoveNamedWorkspaceToOutput {
+                ws_name: 1,
+                output_idx: 2,
+            },
             Op::AddWindow {
                 id: 0,
                 bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
@@ -3283,11 +3834,23 @@ mod tests {
                 bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
                 min_max_size: Default::default(),
             },
+            Op::AddWindowToColumnOf {
+                id: 4,
+                column_of_id: 2,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::AddFloatingWindow {
+                id: 5,
+                parent_id: Some(2),
+                bbox: Rectangle::from_loc_and_size((0, 0), (50, 40)),
+            },
             Op::AddWindowToNamedWorkspace {
                 id: 3,
                 ws_name: 1,
                 bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
                 min_max_size: Default::default(),
+                focus: true,
             },
             Op::CloseWindow(0),
             Op::CloseWindow(1),
@@ -3406,17 +3969,25 @@ mod tests {
             Op::AddOutput(0),
             Op::AddOutput(1),
             Op::AddOutput(2),
+            Op::AddOutputWithMonitor { id: 1, monitor: 1 },
             Op::RemoveOutput(0),
             Op::RemoveOutput(1),
             Op::RemoveOutput(2),
             Op::FocusOutput(0),
             Op::FocusOutput(1),
             Op::FocusOutput(2),
+            Op::SetPrimaryOutput(0),
+            Op::SetPrimaryOutput(1),
+            Op::SetPrimaryOutput(2),
             Op::AddNamedWorkspace {
                 ws_name: 1,
                 output_name: Some(1),
             },
             Op::UnnameWorkspace { ws_name: 1 },
+            Op::MoveNamedWorkspaceToOutput {
+                ws_name: 1,
+                output_idx: 2,
+            },
             Op::AddWindow {
                 id: 0,
                 bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
@@ -3444,11 +4015,23 @@ mod tests {
                 bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
                 min_max_size: Default::default(),
             },
+            Op::AddWindowToColumnOf {
+                id: 8,
+                column_of_id: 6,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::AddFloatingWindow {
+                id: 9,
+                parent_id: Some(1),
+                bbox: Rectangle::from_loc_and_size((0, 0), (50, 40)),
+            },
             Op::AddWindowToNamedWorkspace {
                 id: 5,
                 ws_name: 1,
                 bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
                 min_max_size: Default::default(),
+                focus: true,
             },
             Op::CloseWindow(0),
             Op::CloseWindow(1),
@@ -3604,31 +4187,7 @@ mod tests {
     }
 
     #[test]
-    fn move_to_workspace_by_idx_does_not_leave_empty_workspaces() {
-        let ops = [
-            Op::AddOutput(1),
-            Op::AddWindow {
-                id: 0,
-                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
-                min_max_size: Default::default(),
-            },
-            Op::MoveWindowToWorkspace(2),
-        ];
-
-        let mut layout = Layout::default();
-        for op in ops {
-            op.apply(&mut layout);
-        }
-
-        let MonitorSet::Normal { monitors, .. } = layout.monitor_set else {
-            unreachable!()
-        };
-
-        assert!(monitors[0].workspaces[0].has_windows());
-    }
-
-    #[test]
-    fn focus_workspace_by_idx_does_not_leave_empty_workspaces() {
+    fn snapshot_has_resolved_tiles() {
         let ops = [
             Op::AddOutput(1),
             Op::AddWindow {
@@ -3636,15 +4195,11 @@ mod tests {
                 bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
                 min_max_size: Default::default(),
             },
-            Op::FocusWorkspaceDown,
             Op::AddWindow {
                 id: 1,
                 bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
                 min_max_size: Default::default(),
             },
-            Op::FocusWorkspaceUp,
-            Op::CloseWindow(0),
-            Op::FocusWorkspace(3),
         ];
 
         let mut layout = Layout::default();
@@ -3652,21 +4207,224 @@ mod tests {
             op.apply(&mut layout);
         }
 
-        let MonitorSet::Normal { monitors, .. } = layout.monitor_set else {
-            unreachable!()
-        };
+        let output = layout.active_output().unwrap().clone();
+        let snapshot = layout.snapshot(&output).unwrap();
+        assert!(!snapshot.render_above_top_layer);
 
-        assert!(monitors[0].workspaces[0].has_windows());
+        let first = snapshot.tile(&0).unwrap();
+        let second = snapshot.tile(&1).unwrap();
+        assert!(!first.is_active);
+        assert!(second.is_active);
+        assert!(first.tile.contains_rect(first.window));
+
+        let center = second.window.loc + second.window.size.downscale(2.).to_point();
+        assert_eq!(snapshot.tile_under(center).unwrap().id, 1);
     }
 
-    #[test]
-    fn empty_workspaces_dont_move_back_to_original_output() {
-        let ops = [
-            Op::AddOutput(1),
-            Op::AddWindow {
-                id: 1,
-                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
-                min_max_size: Default::default(),
+    /// Lays out two 100 px wide windows with the given options and prints the resolved geometry.
+    ///
+    /// The windows commit the sizes the layout requested and all animations run to completion, so
+    /// the output describes the settled layout. It lists the tiles topmost first, followed by the
+    /// window under every 80 px along the vertical middle of the output.
+    fn golden_geometry(options: Options) -> String {
+        let mut layout = Layout::with_options(options);
+
+        let add_window = |id| Op::AddWindow {
+            id,
+            bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+            min_max_size: Default::default(),
+        };
+        for op in [Op::AddOutput(1), add_window(0), add_window(1)] {
+            op.apply(&mut layout);
+        }
+
+        let mut ids = Vec::new();
+        layout.with_windows(|win, _| ids.push(*win.id()));
+        for id in ids {
+            Op::Communicate(id).apply(&mut layout);
+        }
+
+        layout.advance_animations(get_monotonic_time() + Duration::from_secs(60));
+        layout.verify_invariants();
+
+        let output = layout.active_output().unwrap().clone();
+        let snapshot = layout.snapshot(&output).unwrap();
+
+        let mut geometry = String::new();
+        for tile in &snapshot.tiles {
+            let (t, w) = (tile.tile, tile.window);
+            writeln!(
+                geometry,
+                "{}: tile {},{} {}x{}, window {},{} {}x{}{}",
+                tile.id,
+                t.loc.x,
+                t.loc.y,
+                t.size.w,
+                t.size.h,
+                w.loc.x,
+                w.loc.y,
+                w.size.w,
+                w.size.h,
+                if tile.is_active { ", active" } else { "" },
+            )
+            .unwrap();
+        }
+
+        let under: Vec<_> = (0..16)
+            .map(|i| {
+                let pos = Point::from((f64::from(i * 80), 360.));
+                layout
+                    .window_under(&output, pos)
+                    .map_or_else(|| String::from("-"), |(win, _)| win.id().to_string())
+            })
+            .collect();
+        write!(geometry, "under: {}", under.join(" ")).unwrap();
+
+        geometry
+    }
+
+    #[test]
+    fn golden_default() {
+        assert_eq!(
+            golden_geometry(Options::default()),
+            "\
+1: tile 132,16 100x688, window 132,16 100x688, active
+0: tile 16,16 100x688, window 16,16 100x688
+under: - 0 1 - - - - - - - - - - - - -"
+        );
+    }
+
+    #[test]
+    fn golden_no_gaps() {
+        let options = Options {
+            gaps: 0.,
+            ..Default::default()
+        };
+        assert_eq!(
+            golden_geometry(options),
+            "\
+1: tile 100,0 100x720, window 100,0 100x720, active
+0: tile 0,0 100x720, window 0,0 100x720
+under: 0 0 1 - - - - - - - - - - - - -"
+        );
+    }
+
+    #[test]
+    fn golden_struts() {
+        let options = Options {
+            struts: Struts {
+                left: FloatOrInt(64.),
+                top: FloatOrInt(32.),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            golden_geometry(options),
+            "\
+1: tile 196,48 100x656, window 196,48 100x656, active
+0: tile 80,48 100x656, window 80,48 100x656
+under: - 0 0 1 - - - - - - - - - - - -"
+        );
+    }
+
+    #[test]
+    fn golden_center_focused_column() {
+        let options = Options {
+            center_focused_column: CenterFocusedColumn::Always,
+            ..Default::default()
+        };
+        assert_eq!(
+            golden_geometry(options),
+            "\
+1: tile 590,16 100x688, window 590,16 100x688, active
+0: tile 474,16 100x688, window 474,16 100x688
+under: - - - - - - 0 0 1 - - - - - - -"
+        );
+    }
+
+    #[test]
+    fn golden_border() {
+        let options = Options {
+            border: niri_config::Border {
+                off: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            golden_geometry(options),
+            "\
+1: tile 140,16 108x688, window 144,20 100x680, active
+0: tile 16,16 108x688, window 20,20 100x680
+under: - 0 1 1 - - - - - - - - - - - -"
+        );
+    }
+
+    #[test]
+    fn move_to_workspace_by_idx_does_not_leave_empty_workspaces() {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::MoveWindowToWorkspace(2),
+        ];
+
+        let mut layout = Layout::default();
+        for op in ops {
+            op.apply(&mut layout);
+        }
+
+        let MonitorSet::Normal { monitors, .. } = layout.monitor_set else {
+            unreachable!()
+        };
+
+        assert!(monitors[0].workspaces[0].has_windows());
+    }
+
+    #[test]
+    fn focus_workspace_by_idx_does_not_leave_empty_workspaces() {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::FocusWorkspaceDown,
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::FocusWorkspaceUp,
+            Op::CloseWindow(0),
+            Op::FocusWorkspace(3),
+        ];
+
+        let mut layout = Layout::default();
+        for op in ops {
+            op.apply(&mut layout);
+        }
+
+        let MonitorSet::Normal { monitors, .. } = layout.monitor_set else {
+            unreachable!()
+        };
+
+        assert!(monitors[0].workspaces[0].has_windows());
+    }
+
+    #[test]
+    fn empty_workspaces_dont_move_back_to_original_output() {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
             },
             Op::FocusWorkspaceDown,
             Op::AddWindow {
@@ -4051,103 +4809,2264 @@ mod tests {
     }
 
     #[test]
-    fn config_change_updates_cached_sizes() {
+    fn fuzzy_workspace_name_prefers_recently_used() {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddNamedWorkspace {
+                ws_name: 1,
+                output_name: None,
+            },
+            Op::AddNamedWorkspace {
+                ws_name: 2,
+                output_name: None,
+            },
+        ];
+
+        let mut layout = Layout::default();
+        for op in ops {
+            op.apply(&mut layout);
+        }
+
+        let fuzzy_name = |layout: &Layout<TestWindow>, query: &str| {
+            let ws = layout.find_workspace_by_fuzzy_name(query)?;
+            ws.name.clone()
+        };
+
+        let (idx, _) = layout.find_workspace_by_name("ws2").unwrap();
+        layout.switch_workspace(idx);
+        layout.refresh();
+        assert_eq!(fuzzy_name(&layout, "ws").as_deref(), Some("ws2"));
+
+        let (idx, _) = layout.find_workspace_by_name("ws1").unwrap();
+        layout.switch_workspace(idx);
+        layout.refresh();
+        assert_eq!(fuzzy_name(&layout, "ws").as_deref(), Some("ws1"));
+
+        assert_eq!(fuzzy_name(&layout, "W2").as_deref(), Some("ws2"));
+        assert_eq!(fuzzy_name(&layout, "ws3"), None);
+    }
+
+    #[test]
+    fn workspace_layout_overrides() {
         let mut config = Config::default();
-        config.layout.border.off = false;
-        config.layout.border.width = FloatOrInt(2.);
+        config.workspaces.push(WorkspaceConfig {
+            name: WorkspaceName(String::from("ws1")),
+            open_on_output: None,
+            layout: LayoutOverride {
+                gaps: Some(FloatOrInt(0.)),
+                ..Default::default()
+            },
+        });
 
         let mut layout = Layout::new(&config);
+        Op::AddOutput(1).apply(&mut layout);
+        layout.verify_invariants();
 
-        Op::AddWindow {
-            id: 1,
-            bbox: Rectangle::from_loc_and_size((0, 0), (1280, 200)),
-            min_max_size: Default::default(),
-        }
-        .apply(&mut layout);
+        let gaps = |layout: &Layout<TestWindow>| layout.active_workspace().unwrap().options.gaps;
 
-        config.layout.border.width = FloatOrInt(4.);
+        let (idx, _) = layout.find_workspace_by_name("ws1").unwrap();
+        layout.switch_workspace(idx);
+        assert_eq!(gaps(&layout), 0.);
+
+        layout.set_workspace_gaps(SizeChange::AdjustFixed(8));
+        layout.verify_invariants();
+        assert_eq!(gaps(&layout), 8.);
+
+        // Runtime overrides survive config reloads.
+        config.layout.gaps = FloatOrInt(32.);
         layout.update_config(&config);
+        layout.verify_invariants();
+        assert_eq!(gaps(&layout), 8.);
+
+        layout.reset_workspace_layout();
+        layout.verify_invariants();
+        assert_eq!(gaps(&layout), 0.);
 
+        config.workspaces[0].layout = LayoutOverride::default();
+        layout.update_config(&config);
         layout.verify_invariants();
+        assert_eq!(gaps(&layout), 32.);
     }
 
     #[test]
-    fn working_area_starts_at_physical_pixel() {
-        let struts = Struts {
-            left: FloatOrInt(0.5),
-            right: FloatOrInt(1.),
-            top: FloatOrInt(0.75),
-            bottom: FloatOrInt(1.),
+    fn workspace_default_column_width_and_centering_overrides() {
+        let mut layout = Layout::default();
+        Op::AddOutput(1).apply(&mut layout);
+
+        let options = |layout: &Layout<TestWindow>| {
+            let options = &layout.active_workspace().unwrap().options;
+            (options.default_width, options.center_focused_column)
         };
 
-        let output = Output::new(
-            String::from("output"),
-            PhysicalProperties {
-                size: Size::from((1280, 720)),
-                subpixel: Subpixel::Unknown,
-                make: String::new(),
-                model: String::new(),
-            },
-        );
-        output.change_current_state(
-            Some(Mode {
-                size: Size::from((1280, 720)),
-                refresh: 60000,
-            }),
-            None,
-            None,
-            None,
+        layout.set_workspace_default_column_width(SizeChange::SetProportion(25.));
+        layout.set_workspace_center_focused_column(CenterFocusedColumn::Always);
+        layout.verify_invariants();
+        assert_eq!(
+            options(&layout),
+            (
+                Some(ColumnWidth::Proportion(0.25)),
+                CenterFocusedColumn::Always
+            )
         );
 
-        let area = compute_working_area(&output, struts);
+        // (1280 - 16) * 0.25 - 16 = 300.
+        layout.set_workspace_default_column_width(SizeChange::AdjustFixed(10));
+        layout.verify_invariants();
+        assert_eq!(options(&layout).0, Some(ColumnWidth::Fixed(310.)));
 
-        assert_eq!(round_logical_in_physical(1., area.loc.x), area.loc.x);
-        assert_eq!(round_logical_in_physical(1., area.loc.y), area.loc.y);
+        layout.reset_workspace_layout();
+        layout.verify_invariants();
+        assert_eq!(
+            options(&layout),
+            (
+                Some(ColumnWidth::Proportion(0.5)),
+                CenterFocusedColumn::Never
+            )
+        );
     }
 
     #[test]
-    fn large_fractional_strut() {
-        let struts = Struts {
-            left: FloatOrInt(0.),
-            right: FloatOrInt(0.),
-            top: FloatOrInt(50000.5),
-            bottom: FloatOrInt(0.),
+    fn workspace_zoom_widens_view() {
+        let mut config = Config::default();
+        config.workspaces.push(WorkspaceConfig {
+            name: WorkspaceName(String::from("ws1")),
+            open_on_output: None,
+            layout: LayoutOverride {
+                zoom: Some(FloatOrInt(0.5)),
+                ..Default::default()
+            },
+        });
+
+        let mut layout = Layout::new(&config);
+        Op::AddOutput(1).apply(&mut layout);
+        layout.verify_invariants();
+
+        let view_size = |layout: &Layout<TestWindow>| {
+            let ws = layout.active_workspace().unwrap();
+            (ws.zoom(), ws.view_size())
         };
 
-        let output = Output::new(
-            String::from("output"),
-            PhysicalProperties {
-                size: Size::from((1280, 720)),
-                subpixel: Subpixel::Unknown,
-                make: String::new(),
-                model: String::new(),
+        let (idx, _) = layout.find_workspace_by_name("ws1").unwrap();
+        layout.switch_workspace(idx);
+        assert_eq!(view_size(&layout), (0.5, Size::from((2560., 1440.))));
+
+        // A fullscreen window suspends the zoom.
+        let ops = [
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
             },
-        );
-        output.change_current_state(
-            Some(Mode {
-                size: Size::from((1280, 720)),
-                refresh: 60000,
-            }),
-            None,
-            None,
-            None,
-        );
+            Op::FullscreenWindow(0),
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+        assert_eq!(view_size(&layout), (1., Size::from((1280., 720.))));
 
-        compute_working_area(&output, struts);
-    }
+        Op::FullscreenWindow(0).apply(&mut layout);
+        layout.verify_invariants();
+        assert_eq!(view_size(&layout), (0.5, Size::from((2560., 1440.))));
 
-    fn arbitrary_spacing() -> impl Strategy<Value = f64> {
-        // Give equal weight to:
-        // - 0: the element is disabled
-        // - 4: some reasonable value
-        // - random value, likely unreasonably big
-        prop_oneof![Just(0.), Just(4.), ((1.)..=65535.)]
+        layout.set_workspace_zoom(0.8);
+        layout.verify_invariants();
+        assert_eq!(view_size(&layout), (0.8, Size::from((1600., 900.))));
+
+        layout.reset_workspace_layout();
+        layout.verify_invariants();
+        assert_eq!(view_size(&layout), (0.5, Size::from((2560., 1440.))));
+
+        config.workspaces[0].layout = LayoutOverride::default();
+        layout.update_config(&config);
+        layout.verify_invariants();
+        assert_eq!(view_size(&layout), (1., Size::from((1280., 720.))));
     }
 
-    fn arbitrary_struts() -> impl Strategy<Value = Struts> {
-        (
-            arbitrary_spacing(),
-            arbitrary_spacing(),
+    #[test]
+    fn transposed_layout_stacks_columns_vertically() {
+        let options = Options {
+            transposed: true,
+            animations: niri_config::Animations {
+                off: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut layout = Layout::with_options(options);
+
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::Communicate(0),
+            Op::Communicate(1),
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let ws = layout.active_workspace().unwrap();
+        assert!(ws.is_transposed());
+
+        let geo = |id: usize| {
+            ws.visible_windows()
+                .find(|(win, _)| *win.id() == id)
+                .map(|(_, geo)| geo)
+                .unwrap()
+        };
+        let (first, second) = (geo(0), geo(1));
+
+        // Columns span the output width and split its height.
+        assert!(first.size.w > first.size.h);
+        assert_eq!(first.size, second.size);
+
+        // The second column goes below the first one.
+        assert_eq!(first.loc.x, second.loc.x);
+        assert!(first.loc.y + first.size.h <= second.loc.y);
+    }
+
+    #[test]
+    fn rotating_output_to_portrait_transposes_layout() {
+        let options = Options {
+            transpose_portrait_outputs: true,
+            ..Default::default()
+        };
+        let mut layout = Layout::with_options(options);
+
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let output = layout.outputs().next().unwrap().clone();
+        assert!(!layout.is_transposed(&output));
+
+        let rotate = |layout: &mut Layout<TestWindow>, transform| {
+            output.change_current_state(None, Some(transform), None, None);
+            layout.update_output_size(&output);
+            layout.verify_invariants();
+        };
+
+        rotate(&mut layout, Transform::_90);
+        assert!(layout.is_transposed(&output));
+        let ws = layout.active_workspace().unwrap();
+        assert_eq!(ws.view_size(), Size::from((1280., 720.)));
+
+        rotate(&mut layout, Transform::Normal);
+        assert!(!layout.is_transposed(&output));
+    }
+
+    #[test]
+    fn default_column_width_on_portrait_output_uses_window_height() {
+        let options = Options {
+            transpose_portrait_outputs: true,
+            ..Default::default()
+        };
+        let mut layout = Layout::with_options(options);
+
+        Op::AddOutput(1).apply(&mut layout);
+        let output = layout.outputs().next().unwrap().clone();
+        output.change_current_state(None, Some(Transform::_90), None, None);
+        layout.update_output_size(&output);
+        layout.verify_invariants();
+
+        let ops = [
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::Communicate(0),
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        // The column width runs along the output height, so it comes from the window height.
+        let ws = layout.active_workspace().unwrap();
+        assert!(ws.is_transposed());
+        let (_, geo) = ws.visible_windows().next().unwrap();
+        assert_eq!(geo.size.h, 200.);
+    }
+
+    #[test]
+    fn floating_window_opens_centered_over_parent() {
+        let mut layout = Layout::default();
+
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::AddFloatingWindow {
+                id: 1,
+                parent_id: Some(0),
+                bbox: Rectangle::from_loc_and_size((0, 0), (50, 40)),
+            },
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let ws = layout.active_workspace().unwrap();
+        assert!(ws.is_floating(&1));
+        assert!(!ws.is_floating(&0));
+
+        let center = |id: usize| {
+            let (_, geo) = ws
+                .visible_windows()
+                .find(|(win, _)| *win.id() == id)
+                .unwrap();
+            geo.loc + geo.size.to_point().downscale(2.)
+        };
+        assert_eq!(center(1), center(0));
+
+        // The floating window takes the focus, and gives it back once it closes.
+        assert_eq!(layout.active_window().map(|(win, _)| *win.id()), Some(1));
+        Op::CloseWindow(1).apply(&mut layout);
+        layout.verify_invariants();
+        assert_eq!(layout.active_window().map(|(win, _)| *win.id()), Some(0));
+    }
+
+    #[test]
+    fn cascaded_dialogs_step_down_from_each_other() {
+        let options = Options {
+            dialog_placement: DialogPlacement::Cascade,
+            ..Default::default()
+        };
+        let mut layout = Layout::with_options(options);
+
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::AddFloatingWindow {
+                id: 1,
+                parent_id: Some(0),
+                bbox: Rectangle::from_loc_and_size((0, 0), (50, 40)),
+            },
+            Op::AddFloatingWindow {
+                id: 2,
+                parent_id: Some(0),
+                bbox: Rectangle::from_loc_and_size((0, 0), (50, 40)),
+            },
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let ws = layout.active_workspace().unwrap();
+        let loc = |id: usize| {
+            let (_, geo) = ws
+                .visible_windows()
+                .find(|(win, _)| *win.id() == id)
+                .unwrap();
+            geo.loc
+        };
+        assert_eq!(loc(2) - loc(1), Point::from((32., 32.)));
+    }
+
+    /// Two tiled columns with a dialog of the first one floating above them, focused.
+    fn layout_with_focused_dialog() -> Layout<TestWindow> {
+        let mut layout = Layout::default();
+
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddOutput(2),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::FocusColumnLeft,
+            Op::AddFloatingWindow {
+                id: 2,
+                parent_id: Some(0),
+                bbox: Rectangle::from_loc_and_size((0, 0), (50, 40)),
+            },
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+        }
+        layout.verify_invariants();
+
+        assert_eq!(layout.focus().map(|win| *win.id()), Some(2));
+        layout
+    }
+
+    /// Returns the columns of the active workspace with their windows, and the active column.
+    fn tiled_state(layout: &Layout<TestWindow>) -> impl PartialEq + std::fmt::Debug {
+        let ws = layout.active_workspace().unwrap();
+        let columns = ws
+            .columns
+            .iter()
+            .map(|col| {
+                let tiles = col.tiles.iter().map(|tile| {
+                    let id = *tile.window().id();
+                    (id, ws.window_size_preference(&id).unwrap().1)
+                });
+                (
+                    tiles.collect::<Vec<_>>(),
+                    col.width,
+                    col.is_full_width,
+                    col.always_center,
+                )
+            })
+            .collect::<Vec<_>>();
+        (columns, ws.active_column_idx)
+    }
+
+    #[test]
+    fn column_actions_leave_columns_behind_focused_dialog_alone() {
+        let ops = [
+            Op::MoveColumnLeft,
+            Op::MoveColumnRight,
+            Op::MoveColumnToFirst,
+            Op::MoveColumnToLast,
+            Op::MoveColumnToIndex(2),
+            Op::MoveWindowDown,
+            Op::MoveWindowUp,
+            Op::ConsumeOrExpelWindowLeft,
+            Op::ConsumeOrExpelWindowRight,
+            Op::ConsumeWindowIntoColumn,
+            Op::ExpelWindowFromColumn,
+            Op::SwitchPresetColumnWidth,
+            Op::MaximizeColumn,
+            Op::ToggleColumnAlwaysCenter,
+            Op::SetColumnWidth(SizeChange::SetProportion(30.)),
+            Op::ToggleColumnWidth(SizeChange::SetFixed(300), SizeChange::SetFixed(400)),
+            Op::SetWindowHeight(SizeChange::SetFixed(300)),
+            Op::ResetWindowHeight,
+            Op::MoveColumnToWorkspaceDown,
+            Op::MoveColumnToWorkspace(1),
+            Op::MoveColumnToOutput(2),
+        ];
+
+        for op in ops {
+            let mut layout = layout_with_focused_dialog();
+            let before = tiled_state(&layout);
+
+            op.apply(&mut layout);
+            layout.verify_invariants();
+
+            assert_eq!(tiled_state(&layout), before, "{op:?}");
+            assert_eq!(layout.focus().map(|win| *win.id()), Some(2), "{op:?}");
+        }
+    }
+
+    #[test]
+    fn move_to_workspace_moves_focused_dialog() {
+        let ops = [
+            Op::MoveWindowToWorkspaceDown,
+            Op::MoveWindowToWorkspace(1),
+            Op::MoveWindowDownOrToWorkspaceDown,
+        ];
+
+        for op in ops {
+            let mut layout = layout_with_focused_dialog();
+            let before = tiled_state(&layout);
+
+            op.apply(&mut layout);
+            layout.verify_invariants();
+
+            // The dialog went along to the next workspace and stays floating there.
+            let ws = layout.active_workspace().unwrap();
+            assert!(ws.is_floating(&2), "{op:?}");
+            assert_eq!(layout.focus().map(|win| *win.id()), Some(2), "{op:?}");
+
+            Op::FocusWorkspaceUp.apply(&mut layout);
+            assert_eq!(tiled_state(&layout), before, "{op:?}");
+        }
+    }
+
+    #[test]
+    fn move_to_workspace_moves_dialog_from_workspace_without_columns() {
+        let mut layout = Layout::default();
+
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddFloatingWindow {
+                id: 0,
+                parent_id: None,
+                bbox: Rectangle::from_loc_and_size((0, 0), (50, 40)),
+            },
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+        }
+        let ws_id = layout.active_workspace().unwrap().id();
+
+        Op::MoveWindowToWorkspaceDown.apply(&mut layout);
+        layout.verify_invariants();
+
+        let ws = layout.active_workspace().unwrap();
+        assert_ne!(ws.id(), ws_id);
+        assert!(ws.is_floating(&0));
+    }
+
+    #[test]
+    fn move_to_output_moves_focused_dialog() {
+        let mut layout = layout_with_focused_dialog();
+        let before = tiled_state(&layout);
+
+        Op::MoveWindowToOutput(2).apply(&mut layout);
+        layout.verify_invariants();
+
+        let ws = layout.active_workspace().unwrap();
+        assert_eq!(ws.current_output().unwrap().name(), "output2");
+        assert!(ws.is_floating(&2));
+        assert_eq!(layout.focus().map(|win| *win.id()), Some(2));
+
+        Op::FocusOutput(1).apply(&mut layout);
+        assert_eq!(tiled_state(&layout), before);
+    }
+
+    #[test]
+    fn floating_resize_keeps_aspect_ratio_within_working_area() {
+        let mut layout = Layout::default();
+        Op::AddOutput(1).apply(&mut layout);
+
+        let mut rules = ResolvedWindowRules::empty();
+        rules.keep_aspect_ratio = Some(true);
+        let bbox = Rectangle::from_loc_and_size((0, 0), (160, 90));
+        let window = TestWindow::new(0, bbox, Size::default(), Size::default()).with_rules(rules);
+        layout.add_floating_window(window.clone(), None, None);
+
+        assert!(layout.interactive_resize_begin(0, ResizeEdge::RIGHT));
+        layout.interactive_resize_update(&0, Point::from((160., 0.)));
+        Op::Communicate(0).apply(&mut layout);
+        assert_eq!(window.size(), Size::from((320, 180)));
+
+        // Dragging a corner follows the axis that moved more.
+        layout.interactive_resize_end(&0);
+        assert!(layout.interactive_resize_begin(0, ResizeEdge::BOTTOM_RIGHT));
+        layout.interactive_resize_update(&0, Point::from((10., 90.)));
+        Op::Communicate(0).apply(&mut layout);
+        assert_eq!(window.size(), Size::from((480, 270)));
+
+        // The window stops growing at the edges of the working area, at 1280x720.
+        layout.interactive_resize_update(&0, Point::from((5000., 0.)));
+        Op::Communicate(0).apply(&mut layout);
+        assert_eq!(window.size(), Size::from((1280, 720)));
+        layout.verify_invariants();
+    }
+
+    #[test]
+    fn add_window_to_named_workspace_focus() {
+        let mut layout = Layout::default();
+
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddNamedWorkspace {
+                ws_name: 1,
+                output_name: Some(1),
+            },
+            Op::AddWindowToNamedWorkspace {
+                id: 0,
+                ws_name: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+                focus: false,
+            },
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let active_name =
+            |layout: &Layout<TestWindow>| layout.active_workspace().unwrap().name.clone();
+
+        // Without focus, the window opens in the background.
+        assert_eq!(active_name(&layout), None);
+        let (_, ws) = layout.find_workspace_by_name("ws1").unwrap();
+        assert!(ws.has_window(&0));
+
+        // With focus, the view follows the window to its workspace.
+        Op::AddWindowToNamedWorkspace {
+            id: 1,
+            ws_name: 1,
+            bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+            min_max_size: Default::default(),
+            focus: true,
+        }
+        .apply(&mut layout);
+        layout.verify_invariants();
+        assert_eq!(active_name(&layout).as_deref(), Some("ws1"));
+        assert_eq!(layout.focus().map(|win| *win.id()), Some(1));
+    }
+
+    #[test]
+    fn workspace_created_on_demand_is_unnamed_when_empty() {
+        let mut layout = Layout::default();
+
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddNamedWorkspaceOnDemand {
+                ws_name: 1,
+                output_name: None,
+            },
+            Op::AddWindowToNamedWorkspace {
+                id: 0,
+                ws_name: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+                focus: false,
+            },
+            Op::AddWindowToNamedWorkspace {
+                id: 1,
+                ws_name: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+                focus: false,
+            },
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        Op::CloseWindow(0).apply(&mut layout);
+        layout.verify_invariants();
+        assert!(layout.find_workspace_by_name("ws1").is_some());
+
+        // Once the last window leaves, the workspace goes away like an unnamed one.
+        Op::CloseWindow(1).apply(&mut layout);
+        layout.verify_invariants();
+        assert!(layout.find_workspace_by_name("ws1").is_none());
+        assert_eq!(layout.active_monitor_ref().unwrap().workspaces.len(), 1);
+    }
+
+    #[test]
+    fn toggle_column_width_restores_previous_width() {
+        let mut layout = Layout::default();
+
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::SetColumnWidth(SizeChange::SetFixed(300)),
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+        }
+
+        let width = |layout: &Layout<TestWindow>| {
+            let ws = layout.active_workspace().unwrap();
+            ws.columns[0].width
+        };
+        let fixed = width(&layout);
+        assert!(matches!(fixed, ColumnWidth::Fixed(_)));
+
+        let toggle = Op::ToggleColumnWidth(
+            SizeChange::SetProportion(50.),
+            SizeChange::SetProportion(95.),
+        );
+
+        toggle.apply(&mut layout);
+        layout.verify_invariants();
+        assert_eq!(width(&layout), ColumnWidth::Proportion(0.95));
+
+        // Toggling back restores the exact fixed width.
+        toggle.apply(&mut layout);
+        layout.verify_invariants();
+        assert_eq!(width(&layout), fixed);
+
+        // Without a width to go back to, the column goes to the normal width.
+        Op::SetColumnWidth(SizeChange::SetProportion(95.)).apply(&mut layout);
+        toggle.apply(&mut layout);
+        layout.verify_invariants();
+        assert_eq!(width(&layout), ColumnWidth::Proportion(0.5));
+    }
+
+    #[test]
+    fn refused_size_is_told_apart_from_pending_configure() {
+        let mut layout = Layout::default();
+
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::Communicate(0),
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+        }
+
+        let ws = layout.active_workspace().unwrap();
+        let window = ws.windows().next().unwrap().clone();
+        assert!(!window.has_pending_configure());
+        assert!(!window.is_refusing_expected_size());
+
+        // Right after the resize, the window simply hasn't caught up yet.
+        Op::SetColumnWidth(SizeChange::SetFixed(300)).apply(&mut layout);
+        assert!(window.has_pending_configure());
+        assert!(!window.is_refusing_expected_size());
+
+        // Committing the configure with the old size is a refusal.
+        window.refuse();
+        assert!(!window.has_pending_configure());
+        assert!(window.is_refusing_expected_size());
+
+        // A later configure gets another chance.
+        Op::SetColumnWidth(SizeChange::SetFixed(400)).apply(&mut layout);
+        assert!(window.has_pending_configure());
+        Op::Communicate(0).apply(&mut layout);
+        assert!(!window.has_pending_configure());
+        assert!(!window.is_refusing_expected_size());
+    }
+
+    #[test]
+    fn windows_get_states_from_layout() {
+        let options = Options {
+            animations: niri_config::Animations {
+                off: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut layout = Layout::with_options(options);
+
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+        }
+        layout.refresh();
+
+        let ws = layout.active_workspace().unwrap();
+        let window = ws.windows().next().unwrap().clone();
+        assert_eq!(window.tiled_edges(), Some(ResizeEdge::empty()));
+        assert!(!window.is_maximized());
+        assert!(!window.is_suspended());
+
+        Op::MaximizeColumn.apply(&mut layout);
+        layout.refresh();
+        assert!(window.is_maximized());
+        assert_eq!(window.tiled_edges(), Some(ResizeEdge::LEFT_RIGHT));
+
+        // Fullscreen drops the tiled state, and unfullscreening restores it.
+        Op::FullscreenWindow(0).apply(&mut layout);
+        layout.refresh();
+        assert_eq!(window.tiled_edges(), None);
+
+        Op::FullscreenWindow(0).apply(&mut layout);
+        layout.refresh();
+        assert_eq!(window.tiled_edges(), Some(ResizeEdge::LEFT_RIGHT));
+
+        // Windows on workspaces out of view are suspended.
+        Op::FocusWorkspaceDown.apply(&mut layout);
+        layout.refresh();
+        assert!(window.is_suspended());
+
+        Op::FocusWorkspaceUp.apply(&mut layout);
+        layout.refresh();
+        assert!(!window.is_suspended());
+    }
+
+    #[test]
+    fn tiled_edges_follow_neighboring_tiles() {
+        let mut layout = Layout::default();
+
+        Op::AddOutput(1).apply(&mut layout);
+        for id in 0..3 {
+            let op = Op::AddWindow {
+                id,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            };
+            op.apply(&mut layout);
+        }
+
+        // Stack the last window below the middle one.
+        Op::FocusColumnLeft.apply(&mut layout);
+        Op::ConsumeWindowIntoColumn.apply(&mut layout);
+        layout.refresh();
+
+        let ws = layout.active_workspace().unwrap();
+        let edges = |id: usize| {
+            let window = ws.windows().find(|win| *win.id() == id).unwrap();
+            window.tiled_edges().unwrap()
+        };
+        assert_eq!(edges(0), ResizeEdge::RIGHT);
+        assert_eq!(edges(1), ResizeEdge::LEFT | ResizeEdge::BOTTOM);
+        assert_eq!(edges(2), ResizeEdge::LEFT | ResizeEdge::TOP);
+    }
+
+    #[test]
+    fn windows_scrolled_out_of_view_are_suspended() {
+        let options = Options {
+            animations: niri_config::Animations {
+                off: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut layout = Layout::with_options(options);
+
+        Op::AddOutput(1).apply(&mut layout);
+        for id in 0..3 {
+            let ops = [
+                Op::AddWindow {
+                    id,
+                    bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                    min_max_size: Default::default(),
+                },
+                Op::SetColumnWidth(SizeChange::SetFixed(1000)),
+            ];
+            for op in ops {
+                op.apply(&mut layout);
+            }
+        }
+        layout.advance_animations(Duration::ZERO);
+        layout.refresh();
+
+        let ws = layout.active_workspace().unwrap();
+        let windows: Vec<_> = ws.windows().cloned().collect();
+        assert!(windows[0].is_suspended());
+        assert!(!windows[2].is_suspended());
+
+        Op::FocusColumnFirst.apply(&mut layout);
+        layout.advance_animations(Duration::ZERO);
+        layout.refresh();
+        assert!(!windows[0].is_suspended());
+        assert!(windows[2].is_suspended());
+    }
+
+    /// Records a touchpad workspace switch swipe with an update every 10 ms.
+    fn workspace_swipe(start: Duration, deltas: &[f64], end: bool) -> Recording {
+        let mut recording = Recording::new();
+        recording.push(
+            start,
+            Op::WorkspaceSwitchGestureBegin {
+                output_idx: 1,
+                is_touchpad: true,
+            },
+        );
+
+        let mut time = start;
+        for &delta in deltas {
+            time += Duration::from_millis(10);
+            recording.push(
+                time,
+                Op::WorkspaceSwitchGestureUpdate {
+                    delta,
+                    timestamp: time,
+                    is_touchpad: true,
+                },
+            );
+        }
+
+        if end {
+            recording.push(
+                time,
+                Op::WorkspaceSwitchGestureEnd {
+                    cancelled: false,
+                    is_touchpad: Some(true),
+                },
+            );
+        }
+
+        recording
+    }
+
+    /// Advances the clock frame by frame until the workspace switch finishes.
+    ///
+    /// Returns the workspace index at every frame.
+    fn settle_workspace_switch(layout: &mut Layout<TestWindow>, clock: &ManualClock) -> Vec<f64> {
+        let mut frames = Vec::new();
+        loop {
+            let mon = layout.active_monitor_ref().unwrap();
+            let Some(switch) = &mon.workspace_switch else {
+                return frames;
+            };
+            frames.push(switch.current_idx());
+            assert!(frames.len() < 1000, "workspace switch never settled");
+
+            clock.advance(Duration::from_nanos(16_666_667));
+            layout.advance_animations(clock.now());
+        }
+    }
+
+    /// Adds two windows to the first workspace and starts holding the second one at the bottom
+    /// edge of the output.
+    fn hold_window_at_bottom_edge(layout: &mut Layout<TestWindow>) {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::InteractiveMoveBegin {
+                window: 1,
+                output_idx: 1,
+            },
+            Op::InteractiveMoveUpdate {
+                window: 1,
+                output_idx: 1,
+                x: 640.,
+                y: 710.,
+            },
+        ];
+        for op in ops {
+            op.apply(layout);
+            layout.verify_invariants();
+        }
+    }
+
+    #[test]
+    fn interactive_move_at_edge_switches_workspace_after_dwell() {
+        let clock = ManualClock::new(Duration::from_secs(1));
+        let mut layout = Layout::default();
+        hold_window_at_bottom_edge(&mut layout);
+
+        let output = layout.outputs().next().unwrap().clone();
+        assert_eq!(
+            layout.interactive_move_edge_switch_time(&output),
+            Some(clock.now() + Duration::from_millis(600))
+        );
+
+        // Halfway through, the next workspace peeks in, but nothing has moved yet.
+        clock.advance(Duration::from_millis(300));
+        layout.advance_animations(clock.now());
+        assert!(!layout.interactive_move_switch_at_edge(&output, clock.now()));
+        let mon = layout.active_monitor_ref().unwrap();
+        assert_eq!(mon.active_workspace_idx, 0);
+        assert_eq!(mon.workspaces_with_render_positions().count(), 2);
+
+        // Animations alone don't move the window.
+        clock.advance(Duration::from_millis(300));
+        layout.advance_animations(clock.now());
+        assert_eq!(layout.active_monitor_ref().unwrap().active_workspace_idx, 0);
+
+        assert!(layout.interactive_move_switch_at_edge(&output, clock.now()));
+        layout.verify_invariants();
+        assert_eq!(layout.interactive_move_edge_switch_time(&output), None);
+        let mon = layout.active_monitor_ref().unwrap();
+        assert_eq!(mon.active_workspace_idx, 1);
+        assert!(mon.workspaces[0].has_window(&0));
+        assert!(mon.workspaces[1].has_window(&1));
+
+        settle_workspace_switch(&mut layout, &clock);
+        layout.verify_invariants();
+    }
+
+    #[test]
+    fn interactive_move_end_cancels_edge_switch() {
+        let clock = ManualClock::new(Duration::from_secs(1));
+        let mut layout = Layout::default();
+        hold_window_at_bottom_edge(&mut layout);
+
+        Op::InteractiveMoveEnd { output_idx: 1 }.apply(&mut layout);
+        clock.advance(Duration::from_secs(1));
+        layout.advance_animations(clock.now());
+        Op::InteractiveMoveSwitchAtEdge { output_idx: 1 }.apply(&mut layout);
+        layout.verify_invariants();
+
+        let mon = layout.active_monitor_ref().unwrap();
+        assert_eq!(mon.active_workspace_idx, 0);
+        assert!(mon.workspaces[0].has_window(&1));
+        assert!(!mon.are_animations_ongoing());
+    }
+
+    #[test]
+    fn interactive_move_update_needs_begin() {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+        ];
+
+        let mut layout = Layout::default();
+        for op in ops {
+            op.apply(&mut layout);
+        }
+
+        let output = layout.outputs().next().unwrap().clone();
+        assert!(!layout.interactive_move_update(&1, &output, Point::from((0., 360.))));
+
+        assert!(layout.interactive_move_begin(1, &output));
+        assert!(layout.interactive_move_update(&1, &output, Point::from((0., 360.))));
+
+        layout.interactive_move_end(&output);
+        assert!(!layout.interactive_move_update(&1, &output, Point::from((0., 360.))));
+    }
+
+    #[test]
+    fn closing_moved_window_ends_interactive_move() {
+        let clock = ManualClock::new(Duration::from_secs(1));
+        let mut layout = Layout::default();
+        hold_window_at_bottom_edge(&mut layout);
+
+        Op::CloseWindow(1).apply(&mut layout);
+        clock.advance(Duration::from_secs(1));
+        layout.advance_animations(clock.now());
+        Op::InteractiveMoveSwitchAtEdge { output_idx: 1 }.apply(&mut layout);
+        layout.verify_invariants();
+
+        // The held window is gone, so nothing switches and another window can be moved.
+        let mon = layout.active_monitor_ref().unwrap();
+        assert_eq!(mon.active_workspace_idx, 0);
+        let output = layout.outputs().next().unwrap().clone();
+        assert!(layout.interactive_move_begin(0, &output));
+    }
+
+    #[test]
+    fn interactive_move_below_output_is_not_at_edge() {
+        let clock = ManualClock::new(Duration::from_secs(1));
+        let mut layout = Layout::default();
+        hold_window_at_bottom_edge(&mut layout);
+
+        // Past the bottom of the output, e.g. on another output below, is not the edge zone.
+        Op::InteractiveMoveUpdate {
+            window: 1,
+            output_idx: 1,
+            x: 640.,
+            y: 800.,
+        }
+        .apply(&mut layout);
+        clock.advance(Duration::from_secs(1));
+        layout.advance_animations(clock.now());
+        Op::InteractiveMoveSwitchAtEdge { output_idx: 1 }.apply(&mut layout);
+        layout.verify_invariants();
+
+        let mon = layout.active_monitor_ref().unwrap();
+        assert_eq!(mon.active_workspace_idx, 0);
+        assert!(mon.workspaces[0].has_window(&1));
+    }
+
+    #[test]
+    fn interactive_resize_snaps_column_width() {
+        let mut layout = Layout::default();
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::InteractiveResizeBegin {
+                window: 0,
+                edges: ResizeEdge::RIGHT,
+            },
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+        }
+
+        let mut resize_to = |window_width: f64| {
+            Op::InteractiveResizeUpdate {
+                window: 0,
+                dx: window_width - 100.,
+                dy: 0.,
+            }
+            .apply(&mut layout);
+            layout.verify_invariants();
+            layout.window_size_preference(&0).unwrap().0
+        };
+
+        // Half of the working area is (1280 - 16) / 2 - 16 = 616 pixels.
+        assert_eq!(resize_to(610.), ColumnWidth::Proportion(0.5));
+        assert_eq!(resize_to(500.), ColumnWidth::Fixed(500.));
+        // The other column is 100 pixels wide.
+        assert_eq!(resize_to(108.), ColumnWidth::Fixed(100.));
+        assert_eq!(resize_to(130.), ColumnWidth::Fixed(130.));
+    }
+
+    #[test]
+    fn workspace_switch_gesture_replays_deterministically() {
+        let run = || {
+            let clock = ManualClock::new(Duration::from_secs(1));
+            let mut layout = Layout::default();
+
+            let ops = [
+                Op::AddOutput(1),
+                Op::AddWindow {
+                    id: 0,
+                    bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                    min_max_size: Default::default(),
+                },
+                Op::FocusWorkspaceDown,
+                Op::AddWindow {
+                    id: 1,
+                    bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                    min_max_size: Default::default(),
+                },
+                Op::FocusWorkspaceUp,
+            ];
+            for op in ops {
+                op.apply(&mut layout);
+            }
+            settle_workspace_switch(&mut layout, &clock);
+
+            workspace_swipe(clock.now(), &[10.; 12], true).replay(&mut layout, &clock);
+            layout.verify_invariants();
+
+            let frames = settle_workspace_switch(&mut layout, &clock);
+            let mon = layout.active_monitor_ref().unwrap();
+            (frames, mon.active_workspace_idx, clock.now())
+        };
+
+        let first = run();
+        let second = run();
+        assert_eq!(first, second);
+
+        let (frames, active_idx, _) = first;
+        assert_eq!(active_idx, 1);
+        assert!(frames.len() > 1, "switch should animate");
+        assert!(frames.windows(2).all(|w| w[0] <= w[1] + 1e-9));
+    }
+
+    #[test]
+    fn workspace_switch_gesture_rubber_bands_past_first_workspace() {
+        let clock = ManualClock::new(Duration::from_secs(1));
+        let mut layout = Layout::default();
+
+        Op::AddOutput(1).apply(&mut layout);
+        Op::AddWindow {
+            id: 0,
+            bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+            min_max_size: Default::default(),
+        }
+        .apply(&mut layout);
+
+        // Swipe up by a whole workspace height while on the first workspace.
+        workspace_swipe(clock.now(), &[-30.; 10], false).replay(&mut layout, &clock);
+
+        let mon = layout.active_monitor_ref().unwrap();
+        let idx = mon.workspace_switch.as_ref().unwrap().current_idx();
+        assert!(idx < 0., "gesture should move past the first workspace");
+        assert!(idx > -0.05, "rubber band should hold the gesture back");
+
+        clock.advance(Duration::from_millis(10));
+        Op::WorkspaceSwitchGestureEnd {
+            cancelled: false,
+            is_touchpad: Some(true),
+        }
+        .apply(&mut layout);
+
+        let frames = settle_workspace_switch(&mut layout, &clock);
+        assert!(frames.iter().all(|&idx| idx > -0.05 && idx <= 1e-9));
+
+        let mon = layout.active_monitor_ref().unwrap();
+        assert_eq!(mon.active_workspace_idx, 0);
+    }
+
+    #[test]
+    fn move_named_workspace_to_output_keeps_focus() {
+        let mut layout = Layout::default();
+
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddOutput(2),
+            Op::FocusOutput(1),
+            Op::AddNamedWorkspace {
+                ws_name: 1,
+                output_name: Some(1),
+            },
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::FocusOutput(2),
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::MoveNamedWorkspaceToOutput {
+                ws_name: 1,
+                output_idx: 2,
+            },
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let MonitorSet::Normal { monitors, .. } = &layout.monitor_set else {
+            unreachable!()
+        };
+        assert!(monitors[0].workspaces.iter().all(|ws| ws.name.is_none()));
+
+        let mon = &monitors[1];
+        let names: Vec<_> = mon.workspaces.iter().map(|ws| ws.name.as_deref()).collect();
+        assert_eq!(names, [None, Some("ws1"), None]);
+
+        // The focus stays on the window on the second output.
+        assert_eq!(mon.active_workspace_idx, 0);
+        assert_eq!(layout.focus().map(|win| *win.id()), Some(1));
+    }
+
+    #[test]
+    fn open_on_output_rules_move_and_create_workspaces() {
+        let mut layout = Layout::default();
+
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddOutput(3),
+            // Opens on the primary output since output2 isn't connected yet.
+            Op::AddNamedWorkspace {
+                ws_name: 1,
+                output_name: Some(2),
+            },
+            // Moving it away makes output3 its original output.
+            Op::MoveNamedWorkspaceToOutput {
+                ws_name: 1,
+                output_idx: 3,
+            },
+            Op::AddOutput(2),
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let ws_names = |layout: &Layout<TestWindow>, output: &str| -> Vec<String> {
+            let MonitorSet::Normal { monitors, .. } = &layout.monitor_set else {
+                unreachable!()
+            };
+            let mon = monitors
+                .iter()
+                .find(|mon| mon.output.name() == output)
+                .unwrap();
+            mon.workspaces
+                .iter()
+                .filter_map(|ws| ws.name.clone())
+                .collect()
+        };
+
+        // The original output logic alone leaves the workspace on output3.
+        assert_eq!(ws_names(&layout, "output3"), ["ws1"]);
+        assert!(ws_names(&layout, "output2").is_empty());
+
+        let output = layout
+            .outputs()
+            .find(|o| o.name() == "output2")
+            .unwrap()
+            .clone();
+        let ws_config = |name: &str| WorkspaceConfig {
+            name: WorkspaceName(name.to_owned()),
+            open_on_output: Some(String::from("Output2")),
+            layout: Default::default(),
+        };
+        layout.apply_open_on_output_rules(&output, &[ws_config("ws1"), ws_config("ws2")]);
+        layout.verify_invariants();
+
+        assert!(ws_names(&layout, "output3").is_empty());
+        let mut names = ws_names(&layout, "output2");
+        names.sort();
+        assert_eq!(names, ["ws1", "ws2"]);
+    }
+
+    #[test]
+    fn output_layout_overrides() {
+        let mut config = Config::default();
+        config.layout.gaps = FloatOrInt(16.);
+        config.outputs.push(niri_config::Output {
+            name: String::from("output1"),
+            layout: LayoutOverride {
+                gaps: Some(FloatOrInt(0.)),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let mut layout = Layout::new(&config);
+        Op::AddOutput(1).apply(&mut layout);
+        Op::AddOutput(2).apply(&mut layout);
+        Op::AddWindow {
+            id: 0,
+            bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+            min_max_size: Default::default(),
+        }
+        .apply(&mut layout);
+        layout.verify_invariants();
+
+        // Gaps of the workspace with the window.
+        let gaps = |layout: &Layout<TestWindow>| {
+            let MonitorSet::Normal { monitors, .. } = &layout.monitor_set else {
+                unreachable!()
+            };
+            let mut workspaces = monitors.iter().flat_map(|mon| &mon.workspaces);
+            workspaces.find(|ws| ws.has_windows()).unwrap().options.gaps
+        };
+        assert_eq!(gaps(&layout), 0.);
+
+        // The workspace picks up the settings of the output it moves to.
+        Op::MoveWorkspaceToOutput(2).apply(&mut layout);
+        layout.verify_invariants();
+        assert_eq!(gaps(&layout), 16.);
+
+        Op::RemoveOutput(2).apply(&mut layout);
+        layout.verify_invariants();
+        assert_eq!(gaps(&layout), 0.);
+
+        config.outputs[0].layout.gaps = Some(FloatOrInt(8.));
+        layout.update_config(&config);
+        layout.verify_invariants();
+        assert_eq!(gaps(&layout), 8.);
+    }
+
+    #[test]
+    fn config_reload_skips_unaffected_workspaces() {
+        let mut config = Config::default();
+        config.outputs.push(niri_config::Output {
+            name: String::from("output2"),
+            ..Default::default()
+        });
+
+        let mut layout = Layout::new(&config);
+        Op::AddOutput(1).apply(&mut layout);
+        Op::AddOutput(2).apply(&mut layout);
+
+        let options = |layout: &Layout<TestWindow>, idx: usize| {
+            let MonitorSet::Normal { monitors, .. } = &layout.monitor_set else {
+                unreachable!()
+            };
+            monitors[idx].workspaces[0].options.clone()
+        };
+        let before = [options(&layout, 0), options(&layout, 1)];
+
+        config.outputs[0].layout.gaps = Some(FloatOrInt(0.));
+        layout.update_config(&config);
+        layout.verify_invariants();
+
+        assert!(Rc::ptr_eq(&before[0], &options(&layout, 0)));
+        assert!(!Rc::ptr_eq(&before[1], &options(&layout, 1)));
+        assert_eq!(options(&layout, 1).gaps, 0.);
+    }
+
+    #[test]
+    fn secondary_output_keeps_multiple_workspaces() {
+        let mut layout = Layout::default();
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddOutput(2),
+            Op::FocusOutput(2),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::FocusWorkspaceDown,
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::RemoveOutput(2),
+            Op::AddOutput(2),
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let MonitorSet::Normal { monitors, .. } = &layout.monitor_set else {
+            unreachable!()
+        };
+        let ids = |mon: &Monitor<TestWindow>| {
+            mon.workspaces
+                .iter()
+                .map(|ws| ws.windows().map(|win| *win.id()).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&monitors[0]), [vec![]]);
+        assert_eq!(ids(&monitors[1]), [vec![0], vec![1], vec![]]);
+    }
+
+    #[test]
+    fn set_primary_output_moves_dangling_workspaces() {
+        let mut layout = Layout::default();
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddOutput(2),
+            Op::AddOutput(3),
+            Op::FocusOutput(3),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::RemoveOutput(3),
+            Op::FocusOutput(1),
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::SetPrimaryOutput(2),
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let MonitorSet::Normal {
+            monitors,
+            primary_idx,
+            ..
+        } = &layout.monitor_set
+        else {
+            unreachable!()
+        };
+        assert_eq!(*primary_idx, 1);
+
+        let ids = |mon: &Monitor<TestWindow>| {
+            mon.workspaces
+                .iter()
+                .map(|ws| ws.windows().map(|win| *win.id()).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        };
+        // The workspace of the disconnected output moves, while output1's own workspace stays.
+        assert_eq!(ids(&monitors[0]), [vec![1], vec![]]);
+        assert_eq!(ids(&monitors[1]), [vec![0], vec![]]);
+
+        // Reconnecting the output takes its workspace back from the new primary.
+        Op::AddOutput(3).apply(&mut layout);
+        layout.verify_invariants();
+
+        let MonitorSet::Normal { monitors, .. } = &layout.monitor_set else {
+            unreachable!()
+        };
+        assert_eq!(ids(&monitors[1]), [vec![]]);
+        assert_eq!(ids(&monitors[2]), [vec![0], vec![]]);
+    }
+
+    #[test]
+    fn disconnected_primary_output_becomes_primary_again() {
+        let mut layout = Layout::default();
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddOutput(2),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            // Close the lid.
+            Op::RemoveOutput(1),
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let primary_name = |layout: &Layout<TestWindow>| layout.primary_output().unwrap().name();
+        assert_eq!(primary_name(&layout), "output2");
+        assert_eq!(output_with_window(&layout, 0), "output2");
+
+        // Open the lid.
+        Op::AddOutput(1).apply(&mut layout);
+        layout.verify_invariants();
+        assert_eq!(primary_name(&layout), "output1");
+        assert_eq!(output_with_window(&layout, 0), "output1");
+
+        // Picking the primary output by hand while the old one is away sticks.
+        Op::RemoveOutput(1).apply(&mut layout);
+        Op::AddOutput(3).apply(&mut layout);
+        Op::SetPrimaryOutput(3).apply(&mut layout);
+        Op::AddOutput(1).apply(&mut layout);
+        layout.verify_invariants();
+        assert_eq!(primary_name(&layout), "output3");
+        assert_eq!(output_with_window(&layout, 0), "output1");
+    }
+
+    /// Opens a window on the monitor with serial `monitor1` plugged into output2, unplugs it, then
+    /// plugs it into output3.
+    fn move_monitor_to_other_connector(layout: &mut Layout<TestWindow>) {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddOutputWithMonitor { id: 2, monitor: 1 },
+            Op::FocusOutput(2),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::RemoveOutput(2),
+            // A different monitor on the same connector, like on another docking station.
+            Op::AddOutputWithMonitor { id: 2, monitor: 2 },
+            Op::AddOutputWithMonitor { id: 3, monitor: 1 },
+        ];
+        for op in ops {
+            op.apply(layout);
+            layout.verify_invariants();
+        }
+    }
+
+    /// Returns the windows of every column of the workspace, left to right.
+    fn column_windows(ws: &Workspace<TestWindow>) -> Vec<Vec<usize>> {
+        ws.columns
+            .iter()
+            .map(|col| col.tiles.iter().map(|tile| *tile.window().id()).collect())
+            .collect()
+    }
+
+    fn output_with_window(layout: &Layout<TestWindow>, id: usize) -> String {
+        let mut name = None;
+        layout.with_windows(|win, output| {
+            if *win.id() == id {
+                name = output.map(|o| o.name());
+            }
+        });
+        name.unwrap()
+    }
+
+    #[test]
+    fn strict_output_identity_requires_connector_and_monitor() {
+        let mut layout = Layout::default();
+        move_monitor_to_other_connector(&mut layout);
+
+        // Neither the other monitor on the same connector, nor the same monitor on another
+        // connector take the workspace from the primary output.
+        assert_eq!(output_with_window(&layout, 0), "output1");
+    }
+
+    #[test]
+    fn fuzzy_output_identity_follows_monitor() {
+        let mut config = Config::default();
+        config.output_identity = OutputIdentity::Fuzzy;
+        let mut layout = Layout::new(&config);
+        move_monitor_to_other_connector(&mut layout);
+
+        assert_eq!(output_with_window(&layout, 0), "output3");
+
+        // Switching back to strict leaves the workspace where it is.
+        config.output_identity = OutputIdentity::Strict;
+        layout.update_config(&config);
+        layout.verify_invariants();
+        assert_eq!(output_with_window(&layout, 0), "output3");
+    }
+
+    #[test]
+    fn element_under_maps_through_output_geometry() {
+        let mut layout = Layout::default();
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddOutput(2),
+            Op::FocusOutput(2),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+        }
+
+        let output = layout.outputs().find(|o| o.name() == "output2").unwrap();
+        output.change_current_state(None, None, None, Some(Point::from((1280, 0))));
+
+        assert!(layout.element_under(Point::from((50., 50.))).is_none());
+
+        let (win, loc) = layout.element_under(Point::from((1330., 50.))).unwrap();
+        assert_eq!(*win.id(), 0);
+        assert!(loc.unwrap().x >= 1280.);
+    }
+
+    #[test]
+    fn element_under_during_workspace_switch() {
+        let mut layout = Layout::default();
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::FocusWorkspaceDown,
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+        }
+
+        let output = layout.outputs().next().unwrap().clone();
+        layout.workspace_switch_gesture_begin(&output, true);
+        // Scroll 20% of the way up, to workspace index 0.8.
+        layout.workspace_switch_gesture_update(-60., Duration::ZERO, true);
+        layout.verify_invariants();
+
+        // The top 20% of the output shows the bottom of the first workspace, which is empty.
+        assert!(layout.element_under(Point::from((50., 50.))).is_none());
+
+        // The second workspace starts at 20% of the output height.
+        let (win, loc) = layout.element_under(Point::from((50., 200.))).unwrap();
+        assert_eq!(*win.id(), 1);
+        assert!(loc.unwrap().y >= 144.);
+    }
+
+    #[test]
+    fn element_under_during_depth_workspace_switch() {
+        let mut options = Options::default();
+        options.animations.workspace_switch.style = WorkspaceSwitchStyle::Depth;
+        let mut layout = Layout::with_options(options);
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::FocusWorkspaceDown,
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+        }
+
+        let output = layout.outputs().next().unwrap().clone();
+        layout.workspace_switch_gesture_begin(&output, true);
+        // Scroll 70% of the way up, to workspace index 0.3.
+        layout.workspace_switch_gesture_update(-210., Duration::ZERO, true);
+        layout.verify_invariants();
+
+        // The first workspace is on top, scaled down to 0.97 around the output center, so its
+        // window starts at x = 640 - (640 - 16) * 0.97 = 34.72.
+        assert!(layout.element_under(Point::from((25., 360.))).is_none());
+
+        let (win, loc) = layout.element_under(Point::from((40., 360.))).unwrap();
+        assert_eq!(*win.id(), 0);
+        let x = loc.unwrap().x;
+        assert!((34. ..35.).contains(&x), "{x}");
+
+        // Outside of the scaled workspace, nothing receives input.
+        assert!(layout.element_under(Point::from((5., 360.))).is_none());
+    }
+
+    #[test]
+    fn window_added_to_active_column_is_focused() {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::AddWindowToColumnOf {
+                id: 1,
+                column_of_id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+        ];
+
+        let mut layout = Layout::default();
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        assert_eq!(layout.focus().map(|win| *win.id()), Some(1));
+    }
+
+    #[test]
+    fn batched_workspace_switches_animate_once() {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::FocusWorkspaceDown,
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::FocusWorkspaceUp,
+        ];
+
+        let mut layout = Layout::default();
+        for op in ops {
+            op.apply(&mut layout);
+        }
+        layout.finish_batch();
+        layout.verify_invariants();
+
+        // Switching down and back up within one batch doesn't leave an animation behind.
+        let mon = layout.active_monitor_ref().unwrap();
+        assert_eq!(mon.render_idx(), None);
+
+        let ops = [Op::FocusWorkspaceDown, Op::FocusWorkspaceDown];
+        for op in ops {
+            op.apply(&mut layout);
+        }
+        layout.finish_batch();
+        layout.verify_invariants();
+
+        // Both switches merge into a single animation starting at the first workspace.
+        let mon = layout.active_monitor_ref().unwrap();
+        assert_eq!(mon.render_idx(), Some(0.));
+    }
+
+    #[test]
+    fn workspace_switch_wraps_around() {
+        let options = Options {
+            workspace_wrap_around: true,
+            ..Default::default()
+        };
+        let mut layout = Layout::with_options(options);
+
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::FocusWorkspaceDown,
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::FocusWorkspaceUp,
+            // Wraps to the last non-empty workspace, skipping the trailing empty one.
+            Op::FocusWorkspaceUp,
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        assert!(layout.active_workspace().unwrap().has_window(&1));
+
+        let ops = [Op::FocusWorkspaceDown, Op::FocusWorkspaceDown];
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        assert!(layout.active_workspace().unwrap().has_window(&0));
+    }
+
+    #[test]
+    fn workspace_switch_wrap_animates_past_the_ends() {
+        let options = Options {
+            workspace_wrap_around: true,
+            ..Default::default()
+        };
+        let mut layout = Layout::with_options(options);
+
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::FocusWorkspaceDown,
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::FocusWorkspaceDown,
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+        }
+        layout.advance_animations(get_monotonic_time() + Duration::from_secs(60));
+        layout.verify_invariants();
+
+        // Wrapping down starts at the current workspace and brings in the first one from below.
+        Op::FocusWorkspaceDown.apply(&mut layout);
+        let mon = layout.active_monitor_ref().unwrap();
+        let switch = mon.workspace_switch.as_ref().unwrap();
+        assert_eq!(switch.current_idx(), 2.);
+        assert_eq!(switch.target_idx(), 3.);
+        assert_eq!(mon.wrap_switch_target(), Some((0, 3.)));
+
+        layout.advance_animations(get_monotonic_time() + Duration::from_secs(60));
+        layout.verify_invariants();
+        let mon = layout.active_monitor_ref().unwrap();
+        assert_eq!(mon.render_idx(), None);
+        assert!(mon.active_workspace_ref().has_window(&0));
+
+        // Wrapping up brings in the last non-empty workspace from above.
+        Op::FocusWorkspaceUp.apply(&mut layout);
+        let mon = layout.active_monitor_ref().unwrap();
+        let switch = mon.workspace_switch.as_ref().unwrap();
+        assert_eq!(switch.current_idx(), 0.);
+        assert_eq!(switch.target_idx(), -1.);
+        assert_eq!(mon.wrap_switch_target(), Some((1, -1.)));
+
+        let visible: Vec<_> = mon
+            .workspaces_with_render_positions()
+            .map(|(ws, _)| ws.has_window(&1))
+            .collect();
+        assert_eq!(visible, [false]);
+    }
+
+    #[test]
+    fn auto_tile_balances_columns_up_to_max() {
+        let options = Options {
+            auto_tile_max_columns: 2,
+            ..Default::default()
+        };
+        let mut layout = Layout::with_options(options);
+
+        let add_window = |id| Op::AddWindow {
+            id,
+            bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+            min_max_size: Default::default(),
+        };
+
+        let ops = [
+            Op::AddOutput(1),
+            add_window(0),
+            Op::ToggleAutoTile,
+            add_window(1),
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+        }
+        layout.refresh();
+        layout.verify_invariants();
+
+        let widths = |layout: &Layout<TestWindow>| {
+            let MonitorSet::Normal { monitors, .. } = &layout.monitor_set else {
+                unreachable!()
+            };
+            let ws = &monitors[0].workspaces[0];
+            assert!(ws.is_auto_tile());
+            ws.columns.iter().map(|col| col.width).collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            widths(&layout),
+            [ColumnWidth::Proportion(0.5), ColumnWidth::Proportion(0.5)]
+        );
+
+        // A width set by hand stays until columns are added or removed.
+        Op::SetColumnWidth(SizeChange::SetProportion(30.)).apply(&mut layout);
+        layout.refresh();
+        layout.verify_invariants();
+        assert_eq!(widths(&layout)[1], ColumnWidth::Proportion(0.3));
+
+        // Past the maximum, columns keep their widths.
+        add_window(2).apply(&mut layout);
+        layout.refresh();
+        layout.verify_invariants();
+
+        let widths = widths(&layout);
+        assert_eq!(widths.len(), 3);
+        assert_eq!(widths[..2], [ColumnWidth::Proportion(0.5); 2]);
+    }
+
+    #[test]
+    fn master_stack_arranges_columns() {
+        let mut layout = Layout::default();
+
+        let add_window = |id| Op::AddWindow {
+            id,
+            bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+            min_max_size: Default::default(),
+        };
+
+        let ops = [
+            Op::AddOutput(1),
+            add_window(0),
+            add_window(1),
+            add_window(2),
+            Op::FocusColumnLeft,
+            Op::SetLayoutStrategy(LayoutStrategyName::MasterStack),
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+        }
+        layout.verify_invariants();
+
+        let shape = |layout: &Layout<TestWindow>| {
+            let MonitorSet::Normal { monitors, .. } = &layout.monitor_set else {
+                unreachable!()
+            };
+            let ws = &monitors[0].workspaces[0];
+            let shape = ws
+                .columns
+                .iter()
+                .map(|col| col.tiles.iter().map(|tile| *tile.window().id()).collect())
+                .collect::<Vec<Vec<_>>>();
+            (
+                shape,
+                ws.active_column_idx,
+                ws.columns[ws.active_column_idx].active_tile_idx,
+            )
+        };
+
+        // Window order and focus are preserved.
+        assert_eq!(shape(&layout), (vec![vec![0], vec![1, 2]], 1, 0));
+
+        // New windows go into the stack.
+        add_window(3).apply(&mut layout);
+        layout.refresh();
+        layout.verify_invariants();
+        assert_eq!(shape(&layout).0, [vec![0], vec![1, 2, 3]]);
+
+        // Closing the master window promotes the next one.
+        Op::CloseWindow(0).apply(&mut layout);
+        layout.refresh();
+        layout.verify_invariants();
+        assert_eq!(shape(&layout).0, [vec![1], vec![2, 3]]);
+
+        // Manual changes stick until a window is added or removed.
+        Op::FocusColumnRight.apply(&mut layout);
+        Op::ExpelWindowFromColumn.apply(&mut layout);
+        layout.refresh();
+        layout.verify_invariants();
+        assert_eq!(shape(&layout).0.len(), 3);
+    }
+
+    #[test]
+    fn focus_and_move_column_by_index() {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::AddWindow {
+                id: 2,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::FocusColumn(1),
+        ];
+
+        let mut layout = Layout::default();
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let ws = layout.active_workspace().unwrap();
+        assert_eq!(column_windows(ws), [vec![0], vec![1], vec![2]]);
+        assert_eq!(layout.focus().map(|win| *win.id()), Some(0));
+
+        // Indices past the end go to the last column.
+        Op::MoveColumnToIndex(10).apply(&mut layout);
+        layout.verify_invariants();
+        let ws = layout.active_workspace().unwrap();
+        assert_eq!(column_windows(ws), [vec![1], vec![2], vec![0]]);
+        assert_eq!(layout.focus().map(|win| *win.id()), Some(0));
+
+        let ops = [Op::FocusColumn(2), Op::MoveColumnToIndex(1)];
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        let ws = layout.active_workspace().unwrap();
+        assert_eq!(column_windows(ws), [vec![2], vec![1], vec![0]]);
+        assert_eq!(layout.focus().map(|win| *win.id()), Some(2));
+    }
+
+    #[test]
+    fn gather_windows_in_order() {
+        let mut layout = Layout::default();
+
+        let add_window = |id| Op::AddWindow {
+            id,
+            bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+            min_max_size: Default::default(),
+        };
+
+        let ops = [
+            Op::AddOutput(1),
+            add_window(0),
+            add_window(1),
+            Op::FocusColumnLeft,
+            Op::FocusWorkspaceDown,
+            add_window(2),
+            add_window(3),
+            Op::FocusWorkspaceUp,
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+        }
+
+        let columns = |layout: &Layout<TestWindow>, ws_idx: usize| {
+            let MonitorSet::Normal { monitors, .. } = &layout.monitor_set else {
+                unreachable!()
+            };
+            monitors[0].workspaces[ws_idx]
+                .columns
+                .iter()
+                .map(|col| *col.tiles[0].window().id())
+                .collect::<Vec<_>>()
+        };
+
+        // Gathered windows go right of the active column, in order.
+        Op::GatherWindows(3, 2).apply(&mut layout);
+        layout.verify_invariants();
+        assert_eq!(columns(&layout, 0), [0, 3, 2, 1]);
+    }
+
+    #[test]
+    fn swap_windows_keeps_places() {
+        let mut layout = Layout::default();
+
+        let add_window = |id| Op::AddWindow {
+            id,
+            bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+            min_max_size: Default::default(),
+        };
+
+        let ops = [
+            Op::AddOutput(1),
+            add_window(0),
+            add_window(1),
+            Op::SetColumnWidth(SizeChange::SetFixed(300)),
+            Op::FocusWorkspaceDown,
+            add_window(2),
+            Op::FocusWorkspaceUp,
+        ];
+        for op in ops {
+            op.apply(&mut layout);
+        }
+
+        let columns = |layout: &Layout<TestWindow>, ws_idx: usize| {
+            let MonitorSet::Normal { monitors, .. } = &layout.monitor_set else {
+                unreachable!()
+            };
+            monitors[0].workspaces[ws_idx]
+                .columns
+                .iter()
+                .map(|col| (*col.tiles[0].window().id(), col.width))
+                .collect::<Vec<_>>()
+        };
+        let before = columns(&layout, 0);
+        let fixed = before[1].1;
+        assert_eq!(before[1].0, 1);
+        assert!(matches!(fixed, ColumnWidth::Fixed(_)));
+
+        // On the same workspace, the windows trade places and the column widths stay.
+        Op::SwapWindows(0, 1).apply(&mut layout);
+        layout.verify_invariants();
+        let after = columns(&layout, 0);
+        assert_eq!(after, [(1, before[0].1), (0, fixed)]);
+
+        // Across workspaces too.
+        Op::SwapWindows(0, 2).apply(&mut layout);
+        layout.verify_invariants();
+        assert_eq!(columns(&layout, 0)[1], (2, fixed));
+        assert_eq!(columns(&layout, 1)[0].0, 0);
+    }
+
+    #[test]
+    fn swap_windows_keeps_fullscreen_in_place() {
+        let ops = [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                id: 0,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::AddWindow {
+                id: 1,
+                bbox: Rectangle::from_loc_and_size((0, 0), (100, 200)),
+                min_max_size: Default::default(),
+            },
+            Op::FullscreenWindow(0),
+            Op::SwapWindows(0, 1),
+        ];
+
+        let mut layout = Layout::default();
+        for op in ops {
+            op.apply(&mut layout);
+            layout.verify_invariants();
+        }
+
+        // The fullscreen column now holds window 1, which is asked to go fullscreen instead.
+        let mut fullscreen = Vec::new();
+        layout.with_windows(|win, _| {
+            if win.is_pending_fullscreen() {
+                fullscreen.push(*win.id());
+            }
+        });
+        assert_eq!(fullscreen, [1]);
+    }
+
+    #[test]
+    fn config_change_updates_cached_sizes() {
+        let mut config = Config::default();
+        config.layout.border.off = false;
+        config.layout.border.width = FloatOrInt(2.);
+
+        let mut layout = Layout::new(&config);
+
+        Op::AddWindow {
+            id: 1,
+            bbox: Rectangle::from_loc_and_size((0, 0), (1280, 200)),
+            min_max_size: Default::default(),
+        }
+        .apply(&mut layout);
+
+        config.layout.border.width = FloatOrInt(4.);
+        layout.update_config(&config);
+
+        layout.verify_invariants();
+    }
+
+    #[test]
+    fn working_area_starts_at_physical_pixel() {
+        let struts = Struts {
+            left: FloatOrInt(0.5),
+            right: FloatOrInt(1.),
+            top: FloatOrInt(0.75),
+            bottom: FloatOrInt(1.),
+        };
+
+        let output = Output::new(
+            String::from("output"),
+            PhysicalProperties {
+                size: Size::from((1280, 720)),
+                subpixel: Subpixel::Unknown,
+                make: String::new(),
+                model: String::new(),
+            },
+        );
+        output.change_current_state(
+            Some(Mode {
+                size: Size::from((1280, 720)),
+                refresh: 60000,
+            }),
+            None,
+            None,
+            None,
+        );
+
+        let area = compute_working_area(&output, struts);
+
+        assert_eq!(round_logical_in_physical(1., area.loc.x), area.loc.x);
+        assert_eq!(round_logical_in_physical(1., area.loc.y), area.loc.y);
+    }
+
+    #[test]
+    fn large_fractional_strut() {
+        let struts = Struts {
+            left: FloatOrInt(0.),
+            right: FloatOrInt(0.),
+            top: FloatOrInt(50000.5),
+            bottom: FloatOrInt(0.),
+        };
+
+        let output = Output::new(
+            String::from("output"),
+            PhysicalProperties {
+                size: Size::from((1280, 720)),
+                subpixel: Subpixel::Unknown,
+                make: String::new(),
+                model: String::new(),
+            },
+        );
+        output.change_current_state(
+            Some(Mode {
+                size: Size::from((1280, 720)),
+                refresh: 60000,
+            }),
+            None,
+            None,
+            None,
+        );
+
+        compute_working_area(&output, struts);
+    }
+
+    fn arbitrary_spacing() -> impl Strategy<Value = f64> {
+        // Give equal weight to:
+        // - 0: the element is disabled
+        // - 4: some reasonable value
+        // - random value, likely unreasonably big
+        prop_oneof![Just(0.), Just(4.), ((1.)..=65535.)]
+    }
+
+    fn arbitrary_struts() -> impl Strategy<Value = Struts> {
+        (
+            arbitrary_spacing(),
+            arbitrary_spacing(),
             arbitrary_spacing(),
             arbitrary_spacing(),
         )
@@ -4200,6 +7119,10 @@ mod tests {
             focus_ring in arbitrary_focus_ring(),
             border in arbitrary_border(),
             center_focused_column in arbitrary_center_focused_column(),
+            workspace_wrap_around in any::<bool>(),
+            auto_tile_max_columns in 1..=4usize,
+            transposed in any::<bool>(),
+            transpose_portrait_outputs in any::<bool>(),
         ) -> Options {
             Options {
                 gaps,
@@ -4207,6 +7130,10 @@ mod tests {
                 center_focused_column,
                 focus_ring,
                 border,
+                workspace_wrap_around,
+                auto_tile_max_columns,
+                transposed,
+                transpose_portrait_outputs,
                 ..Default::default()
             }
         }
@@ -4229,4 +7156,51 @@ mod tests {
             check_ops_with_options(options, &ops);
         }
     }
+
+    /// Checks that hit-testing during workspace switches agrees with the rendered positions.
+    fn check_workspace_offsets(layout: &Layout<TestWindow>) {
+        let MonitorSet::Normal { monitors, .. } = &layout.monitor_set else {
+            return;
+        };
+
+        for mon in monitors {
+            let size = output_size(&mon.output);
+            let visible: Vec<_> = mon
+                .workspaces_with_render_positions()
+                .map(|(ws, pos)| (ws.id(), pos))
+                .collect();
+            assert!(visible.len() <= 2);
+
+            for y in [0., size.h / 3., size.h / 2., size.h - 1.] {
+                let Some((ws, ws_pos)) = mon.workspace_under(Point::from((0., y))) else {
+                    continue;
+                };
+
+                assert!(
+                    visible.contains(&(ws.id(), ws_pos)),
+                    "workspace under {y} at {ws_pos:?} is not rendered there: {visible:?}"
+                );
+
+                let y_in_ws = y - ws_pos.y;
+                assert!(
+                    (0. ..size.h).contains(&y_in_ws),
+                    "position {y} maps outside of the workspace: {y_in_ws}"
+                );
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn workspace_switch_offsets_are_consistent(
+            ops in prop::collection::vec(any::<Op>(), 0..32),
+        ) {
+            let mut layout = Layout::default();
+            for op in ops {
+                layout.handle_op(op);
+                layout.verify_invariants();
+                check_workspace_offsets(&layout);
+            }
+        }
+    }
 }
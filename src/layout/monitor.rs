@@ -1,25 +1,34 @@
 use std::cmp::min;
+use std::ptr;
 use std::rc::Rc;
 use std::time::Duration;
 
-use niri_ipc::SizeChange;
+use niri_config::WorkspaceSwitchStyle;
+use niri_ipc::{OpacityChange, SizeChange};
+use smithay::backend::allocator::Fourcc;
 use smithay::backend::renderer::element::utils::{
-    CropRenderElement, Relocate, RelocateRenderElement,
+    CropRenderElement, Relocate, RelocateRenderElement, RescaleRenderElement,
 };
+use smithay::backend::renderer::element::Kind;
 use smithay::output::Output;
-use smithay::utils::{Logical, Point, Rectangle};
+use smithay::utils::{Logical, Point, Rectangle, Scale, Transform};
 
+use super::snapshot::LayoutSnapshot;
 use super::workspace::{
-    compute_working_area, Column, ColumnWidth, OutputId, Workspace, WorkspaceId,
+    compute_working_area, Column, ColumnWidth, MovedWindow, OutputId, Workspace, WorkspaceId,
     WorkspaceRenderElement,
 };
 use super::{LayoutElement, Options};
 use crate::animation::Animation;
 use crate::input::swipe_tracker::SwipeTracker;
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
 use crate::render_helpers::renderer::NiriRenderer;
-use crate::render_helpers::RenderTarget;
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::render_helpers::{render_to_encompassing_texture, RenderTarget};
 use crate::rubber_band::RubberBand;
-use crate::utils::{output_size, to_physical_precise_round, ResizeEdge};
+use crate::utils::{
+    get_monotonic_time, output_size, to_physical_precise_round, ResizeEdge, Transpose,
+};
 
 /// Amount of touchpad movement to scroll the height of one workspace.
 const WORKSPACE_GESTURE_MOVEMENT: f64 = 300.;
@@ -29,6 +38,19 @@ const WORKSPACE_GESTURE_RUBBER_BAND: RubberBand = RubberBand {
     limit: 0.05,
 };
 
+/// Height of the areas at the top and bottom of the output where holding an interactively moved
+/// window switches workspaces.
+const INTERACTIVE_MOVE_EDGE_SIZE: f64 = 24.;
+
+/// How long an interactively moved window has to stay at the edge to switch workspaces.
+const INTERACTIVE_MOVE_EDGE_DWELL: Duration = Duration::from_millis(600);
+
+/// How far the next workspace peeks in by the end of the dwell, in workspace heights.
+const INTERACTIVE_MOVE_EDGE_PEEK: f64 = 0.08;
+
+/// Scale of a workspace that is one full switch away with the depth workspace switch style.
+const WORKSPACE_SWITCH_DEPTH_SCALE: f64 = 0.9;
+
 #[derive(Debug)]
 pub struct Monitor<W: LayoutElement> {
     /// Output for this monitor.
@@ -41,10 +63,38 @@ pub struct Monitor<W: LayoutElement> {
     pub previous_workspace_id: Option<WorkspaceId>,
     /// In-progress switch between workspaces.
     pub workspace_switch: Option<WorkspaceSwitch>,
+    /// Window being interactively moved.
+    interactive_move: Option<W::Id>,
+    /// Interactively moved window being held at the top or bottom edge.
+    edge_dwell: Option<EdgeDwell>,
+    /// Target of the last wrap-around workspace switch.
+    switch_wrap: Option<SwitchWrap>,
     /// Configurable properties of the layout.
     pub options: Rc<Options>,
 }
 
+/// Wrap-around workspace switch.
+///
+/// The switch animates to a virtual index right past the end of the workspaces that the view
+/// moves over, and the target workspace is drawn there, so that it slides in next to the current
+/// one.
+#[derive(Debug, Clone, Copy)]
+struct SwitchWrap {
+    /// Virtual index of the target workspace: -1, or the number of workspaces at the start.
+    virtual_idx: f64,
+    /// Target workspace.
+    id: WorkspaceId,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct EdgeDwell {
+    /// Whether the window is held at the top edge rather than the bottom one.
+    up: bool,
+    start_time: Duration,
+    /// Progress towards the switch, from 0 to 1.
+    progress: f64,
+}
+
 #[derive(Debug)]
 pub enum WorkspaceSwitch {
     Animation(Animation),
@@ -63,7 +113,7 @@ pub struct WorkspaceSwitchGesture {
 }
 
 pub type MonitorRenderElement<R> =
-    RelocateRenderElement<CropRenderElement<WorkspaceRenderElement<R>>>;
+    RelocateRenderElement<CropRenderElement<RescaleRenderElement<WorkspaceRenderElement<R>>>>;
 
 impl WorkspaceSwitch {
     pub fn current_idx(&self) -> f64 {
@@ -97,6 +147,9 @@ impl<W: LayoutElement> Monitor<W> {
             active_workspace_idx: 0,
             previous_workspace_id: None,
             workspace_switch: None,
+            interactive_move: None,
+            edge_dwell: None,
+            switch_wrap: None,
             options,
         }
     }
@@ -126,16 +179,31 @@ impl<W: LayoutElement> Monitor<W> {
     }
 
     fn activate_workspace(&mut self, idx: usize) {
+        self.activate_workspace_to(idx, idx as f64);
+    }
+
+    /// Activates the workspace across the ends of the workspace list.
+    ///
+    /// `virtual_idx` is right past the end that the view moves over.
+    fn activate_workspace_wrapped(&mut self, idx: usize, virtual_idx: f64) {
+        if self.activate_workspace_to(idx, virtual_idx) {
+            self.switch_wrap = Some(SwitchWrap {
+                virtual_idx,
+                id: self.workspaces[idx].id(),
+            });
+        }
+    }
+
+    /// Activates the workspace, animating the view to `target_idx`.
+    ///
+    /// Returns `false` if the workspace was already active.
+    fn activate_workspace_to(&mut self, idx: usize, target_idx: f64) -> bool {
         if self.active_workspace_idx == idx {
-            return;
+            return false;
         }
 
         // FIXME: also compute and use current velocity.
-        let current_idx = self
-            .workspace_switch
-            .as_ref()
-            .map(|s| s.current_idx())
-            .unwrap_or(self.active_workspace_idx as f64);
+        let current_idx = self.current_switch_idx();
 
         self.previous_workspace_id = Some(self.workspaces[self.active_workspace_idx].id());
 
@@ -143,10 +211,83 @@ impl<W: LayoutElement> Monitor<W> {
 
         self.workspace_switch = Some(WorkspaceSwitch::Animation(Animation::new(
             current_idx,
-            idx as f64,
+            target_idx,
             0.,
-            self.options.animations.workspace_switch.0,
+            self.options.animations.workspace_switch.anim,
         )));
+
+        true
+    }
+
+    /// Returns the current view position to start a new workspace switch from.
+    fn current_switch_idx(&self) -> f64 {
+        let Some(switch) = &self.workspace_switch else {
+            return self.active_workspace_idx as f64;
+        };
+
+        let current_idx = switch.current_idx();
+        if self.wrap_switch_target().is_some() {
+            // The virtual index doesn't exist outside of the wrap-around switch, so continue from
+            // the closest real position.
+            return current_idx.clamp(0., (self.workspaces.len() - 1) as f64);
+        }
+
+        current_idx
+    }
+
+    /// Returns the index and the virtual index of the target of an ongoing wrap-around switch.
+    pub fn wrap_switch_target(&self) -> Option<(usize, f64)> {
+        let wrap = self.switch_wrap?;
+        let Some(WorkspaceSwitch::Animation(anim)) = &self.workspace_switch else {
+            return None;
+        };
+
+        // Any later switch animates elsewhere, since no real workspace has the virtual index.
+        if anim.to() != wrap.virtual_idx {
+            return None;
+        }
+
+        let idx = self.workspaces.iter().position(|ws| ws.id() == wrap.id)?;
+        Some((idx, wrap.virtual_idx))
+    }
+
+    /// Returns the index at which the workspace is drawn during a workspace switch.
+    pub fn render_idx_of(&self, idx: usize) -> f64 {
+        match self.wrap_switch_target() {
+            Some((target_idx, virtual_idx)) if target_idx == idx => virtual_idx,
+            _ => idx as f64,
+        }
+    }
+
+    /// Returns the workspace drawn at the whole render index.
+    fn workspace_at_render_idx(&self, render_idx: f64) -> Option<usize> {
+        if let Some((idx, virtual_idx)) = self.wrap_switch_target() {
+            if render_idx == virtual_idx {
+                return Some(idx);
+            }
+        }
+
+        if render_idx < 0. || render_idx as usize >= self.workspaces.len() {
+            return None;
+        }
+
+        Some(render_idx as usize)
+    }
+
+    /// Returns the workspaces drawn right before and right after the fractional render index.
+    ///
+    /// When the render index is whole, only the before workspace is visible.
+    fn workspaces_around_render_idx(&self, render_idx: f64) -> (Option<usize>, Option<usize>) {
+        let before_idx = render_idx.floor();
+        let after_idx = render_idx.ceil();
+
+        let before = self.workspace_at_render_idx(before_idx);
+        let after = if after_idx == before_idx {
+            None
+        } else {
+            self.workspace_at_render_idx(after_idx)
+        };
+        (before, after)
     }
 
     pub fn add_window(
@@ -195,6 +336,92 @@ impl<W: LayoutElement> Monitor<W> {
         workspace.original_output = OutputId::new(&self.output);
     }
 
+    pub fn add_window_left_of(
+        &mut self,
+        left_of: &W::Id,
+        window: W,
+        width: ColumnWidth,
+        is_full_width: bool,
+    ) {
+        let workspace_idx = self
+            .workspaces
+            .iter_mut()
+            .position(|ws| ws.has_window(left_of))
+            .unwrap();
+        let workspace = &mut self.workspaces[workspace_idx];
+
+        workspace.add_window_left_of(left_of, window, width, is_full_width);
+
+        // After adding a new window, workspace becomes this output's own.
+        workspace.original_output = OutputId::new(&self.output);
+    }
+
+    pub fn add_window_to_column(&mut self, column_of: &W::Id, window: W) {
+        let workspace_idx = self
+            .workspaces
+            .iter_mut()
+            .position(|ws| ws.has_window(column_of))
+            .unwrap();
+        let workspace = &mut self.workspaces[workspace_idx];
+
+        workspace.add_window_to_column(column_of, window);
+
+        // After adding a new window, workspace becomes this output's own.
+        workspace.original_output = OutputId::new(&self.output);
+
+        if workspace_idx == self.workspaces.len() - 1 {
+            // Insert a new empty workspace.
+            let ws = Workspace::new(self.output.clone(), self.options.clone());
+            self.workspaces.push(ws);
+        }
+    }
+
+    /// Adds a window floating above the columns of a workspace and focuses it there.
+    ///
+    /// Like for windows opening next to their parent, this doesn't switch to the workspace.
+    pub fn add_floating_window(
+        &mut self,
+        workspace_idx: usize,
+        window: W,
+        parent: Option<&W::Id>,
+        pointer: Option<Point<f64, Logical>>,
+    ) {
+        let workspace = &mut self.workspaces[workspace_idx];
+
+        workspace.add_floating_window(window, parent, true, pointer);
+
+        // After adding a new window, workspace becomes this output's own.
+        workspace.original_output = OutputId::new(&self.output);
+
+        if workspace_idx == self.workspaces.len() - 1 {
+            // Insert a new empty workspace.
+            let ws = Workspace::new(self.output.clone(), self.options.clone());
+            self.workspaces.push(ws);
+        }
+    }
+
+    /// Adds a window taken out of another workspace, keeping it floating if it was.
+    pub fn add_moved_window(
+        &mut self,
+        workspace_idx: usize,
+        window: MovedWindow<W>,
+        activate: bool,
+    ) {
+        match window {
+            MovedWindow::Tiled {
+                window,
+                width,
+                is_full_width,
+            } => self.add_window(workspace_idx, window, activate, width, is_full_width),
+            MovedWindow::Floating(window) => {
+                self.add_floating_window(workspace_idx, window, None, None);
+                if activate {
+                    self.activate_workspace(workspace_idx);
+                }
+            }
+        }
+    }
+
     pub fn add_column(&mut self, workspace_idx: usize, column: Column<W>, activate: bool) {
         let workspace = &mut self.workspaces[workspace_idx];
 
@@ -261,6 +488,10 @@ impl<W: LayoutElement> Monitor<W> {
         self.active_workspace().move_column_to_last();
     }
 
+    pub fn move_column_to_index(&mut self, index: usize) {
+        self.active_workspace().move_column_to_index(index);
+    }
+
     pub fn move_down(&mut self) {
         self.active_workspace().move_down();
     }
@@ -271,6 +502,11 @@ impl<W: LayoutElement> Monitor<W> {
 
     pub fn move_down_or_to_workspace_down(&mut self) {
         let workspace = self.active_workspace();
+        if workspace.is_floating_active() {
+            // Floating windows have no column to move within.
+            self.move_to_workspace_down();
+            return;
+        }
         if workspace.columns.is_empty() {
             return;
         }
@@ -286,6 +522,10 @@ impl<W: LayoutElement> Monitor<W> {
 
     pub fn move_up_or_to_workspace_up(&mut self) {
         let workspace = self.active_workspace();
+        if workspace.is_floating_active() {
+            self.move_to_workspace_up();
+            return;
+        }
         if workspace.columns.is_empty() {
             return;
         }
@@ -322,6 +562,10 @@ impl<W: LayoutElement> Monitor<W> {
         self.active_workspace().focus_column_last();
     }
 
+    pub fn focus_column(&mut self, index: usize) {
+        self.active_workspace().focus_column(index);
+    }
+
     pub fn focus_column_right_or_first(&mut self) {
         self.active_workspace().focus_column_right_or_first();
     }
@@ -434,18 +678,11 @@ impl<W: LayoutElement> Monitor<W> {
         }
 
         let workspace = &mut self.workspaces[source_workspace_idx];
-        if workspace.columns.is_empty() {
+        let Some(window) = workspace.remove_active_window() else {
             return;
-        }
-
-        let column = &workspace.columns[workspace.active_column_idx];
-        let width = column.width;
-        let is_full_width = column.is_full_width;
-        let window = workspace
-            .remove_tile_by_idx(workspace.active_column_idx, column.active_tile_idx, None)
-            .into_window();
+        };
 
-        self.add_window(new_idx, window, true, width, is_full_width);
+        self.add_moved_window(new_idx, window, true);
     }
 
     pub fn move_to_workspace_down(&mut self) {
@@ -457,18 +694,11 @@ impl<W: LayoutElement> Monitor<W> {
         }
 
         let workspace = &mut self.workspaces[source_workspace_idx];
-        if workspace.columns.is_empty() {
+        let Some(window) = workspace.remove_active_window() else {
             return;
-        }
-
-        let column = &workspace.columns[workspace.active_column_idx];
-        let width = column.width;
-        let is_full_width = column.is_full_width;
-        let window = workspace
-            .remove_tile_by_idx(workspace.active_column_idx, column.active_tile_idx, None)
-            .into_window();
+        };
 
-        self.add_window(new_idx, window, true, width, is_full_width);
+        self.add_moved_window(new_idx, window, true);
     }
 
     pub fn move_to_workspace(&mut self, idx: usize) {
@@ -480,18 +710,11 @@ impl<W: LayoutElement> Monitor<W> {
         }
 
         let workspace = &mut self.workspaces[source_workspace_idx];
-        if workspace.columns.is_empty() {
+        let Some(window) = workspace.remove_active_window() else {
             return;
-        }
-
-        let column = &workspace.columns[workspace.active_column_idx];
-        let width = column.width;
-        let is_full_width = column.is_full_width;
-        let window = workspace
-            .remove_tile_by_idx(workspace.active_column_idx, column.active_tile_idx, None)
-            .into_window();
+        };
 
-        self.add_window(new_idx, window, true, width, is_full_width);
+        self.add_moved_window(new_idx, window, true);
 
         // Don't animate this action.
         self.workspace_switch = None;
@@ -507,8 +730,9 @@ impl<W: LayoutElement> Monitor<W> {
             return;
         }
 
+        // The active column is behind the focused floating window, so leave it alone.
         let workspace = &mut self.workspaces[source_workspace_idx];
-        if workspace.columns.is_empty() {
+        if workspace.columns.is_empty() || workspace.is_floating_active() {
             return;
         }
 
@@ -524,8 +748,9 @@ impl<W: LayoutElement> Monitor<W> {
             return;
         }
 
+        // The active column is behind the focused floating window, so leave it alone.
         let workspace = &mut self.workspaces[source_workspace_idx];
-        if workspace.columns.is_empty() {
+        if workspace.columns.is_empty() || workspace.is_floating_active() {
             return;
         }
 
@@ -541,8 +766,9 @@ impl<W: LayoutElement> Monitor<W> {
             return;
         }
 
+        // The active column is behind the focused floating window, so leave it alone.
         let workspace = &mut self.workspaces[source_workspace_idx];
-        if workspace.columns.is_empty() {
+        if workspace.columns.is_empty() || workspace.is_floating_active() {
             return;
         }
 
@@ -556,14 +782,26 @@ impl<W: LayoutElement> Monitor<W> {
     }
 
     pub fn switch_workspace_up(&mut self) {
+        if self.active_workspace_idx == 0 && self.options.workspace_wrap_around {
+            // Skip the trailing empty workspace, and slide it in from above rather than scrolling
+            // through all of them.
+            let idx = (self.workspaces.len() - 1).saturating_sub(1);
+            self.activate_workspace_wrapped(idx, -1.);
+            return;
+        }
+
         self.activate_workspace(self.active_workspace_idx.saturating_sub(1));
     }
 
     pub fn switch_workspace_down(&mut self) {
-        self.activate_workspace(min(
-            self.active_workspace_idx + 1,
-            self.workspaces.len() - 1,
-        ));
+        let last = self.workspaces.len() - 1;
+        if self.active_workspace_idx == last && self.options.workspace_wrap_around {
+            // Slide the first workspace in from below, rather than scrolling through all of them.
+            self.activate_workspace_wrapped(0, (last + 1) as f64);
+            return;
+        }
+
+        self.activate_workspace(min(self.active_workspace_idx + 1, last));
     }
 
     fn previous_workspace_idx(&self) -> Option<usize> {
@@ -611,16 +849,16 @@ impl<W: LayoutElement> Monitor<W> {
     }
 
     pub fn focus(&self) -> Option<&W> {
-        let workspace = &self.workspaces[self.active_workspace_idx];
-        if !workspace.has_windows() {
-            return None;
-        }
-
-        let column = &workspace.columns[workspace.active_column_idx];
-        Some(column.tiles[column.active_tile_idx].window())
+        self.workspaces[self.active_workspace_idx].active_window()
     }
 
     pub fn advance_animations(&mut self, current_time: Duration) {
+        if let Some(dwell) = &mut self.edge_dwell {
+            let elapsed = current_time.saturating_sub(dwell.start_time);
+            dwell.progress =
+                (elapsed.as_secs_f64() / INTERACTIVE_MOVE_EDGE_DWELL.as_secs_f64()).clamp(0., 1.);
+        }
+
         if let Some(WorkspaceSwitch::Animation(anim)) = &mut self.workspace_switch {
             anim.set_current_time(current_time);
             if anim.is_done() {
@@ -634,13 +872,41 @@ impl<W: LayoutElement> Monitor<W> {
         }
     }
 
+    /// Drops a workspace switch animation that ended up where it started.
+    ///
+    /// Used after a batch of actions, where switching to a workspace and back leaves behind an
+    /// animation that goes nowhere.
+    pub fn finish_batch(&mut self) {
+        if let Some(WorkspaceSwitch::Animation(anim)) = &self.workspace_switch {
+            if anim.from() == anim.to() {
+                self.workspace_switch = None;
+                self.clean_up_workspaces();
+            }
+        }
+    }
+
     pub fn are_animations_ongoing(&self) -> bool {
         self.workspace_switch
             .as_ref()
             .is_some_and(|s| s.is_animation())
+            || self.edge_dwell.is_some()
             || self.workspaces.iter().any(|ws| ws.are_animations_ongoing())
     }
 
+    /// Returns the fractional index of the workspace to render, unless the view is exactly at the
+    /// active workspace.
+    pub fn render_idx(&self) -> Option<f64> {
+        if let Some(switch) = &self.workspace_switch {
+            return Some(switch.current_idx());
+        }
+
+        // Let the next workspace peek in while a window is held at the edge.
+        let dwell = self.edge_dwell?;
+        let peek = (1. - (1. - dwell.progress).powi(3)) * INTERACTIVE_MOVE_EDGE_PEEK;
+        let direction = if dwell.up { -1. } else { 1. };
+        Some(self.active_workspace_idx as f64 + direction * peek)
+    }
+
     pub fn are_transitions_ongoing(&self) -> bool {
         self.workspace_switch.is_some()
             || self
@@ -650,27 +916,12 @@ impl<W: LayoutElement> Monitor<W> {
     }
 
     pub fn update_render_elements(&mut self, is_active: bool) {
-        match &self.workspace_switch {
-            Some(switch) => {
-                let render_idx = switch.current_idx();
-                let before_idx = render_idx.floor();
-                let after_idx = render_idx.ceil();
-
-                if after_idx < 0. || before_idx as usize >= self.workspaces.len() {
-                    return;
+        match self.render_idx() {
+            Some(render_idx) => {
+                let (before, after) = self.workspaces_around_render_idx(render_idx);
+                for idx in before.into_iter().chain(after) {
+                    self.workspaces[idx].update_render_elements(is_active);
                 }
-
-                let after_idx = after_idx as usize;
-                if after_idx < self.workspaces.len() {
-                    self.workspaces[after_idx].update_render_elements(is_active);
-
-                    if before_idx < 0. {
-                        return;
-                    }
-                }
-
-                let before_idx = before_idx as usize;
-                self.workspaces[before_idx].update_render_elements(is_active);
             }
             None => {
                 self.workspaces[self.active_workspace_idx].update_render_elements(is_active);
@@ -705,10 +956,22 @@ impl<W: LayoutElement> Monitor<W> {
         self.active_workspace().toggle_full_width();
     }
 
+    pub fn toggle_auto_tile(&mut self) {
+        self.active_workspace().toggle_auto_tile();
+    }
+
+    pub fn toggle_column_always_center(&mut self) {
+        self.active_workspace().toggle_column_always_center();
+    }
+
     pub fn set_column_width(&mut self, change: SizeChange) {
         self.active_workspace().set_column_width(change);
     }
 
+    pub fn toggle_column_width(&mut self, a: SizeChange, b: SizeChange) {
+        self.active_workspace().toggle_column_width(a, b);
+    }
+
     pub fn set_window_height(&mut self, change: SizeChange) {
         self.active_workspace().set_window_height(change);
     }
@@ -717,6 +980,14 @@ impl<W: LayoutElement> Monitor<W> {
         self.active_workspace().reset_window_height();
     }
 
+    pub fn set_window_opacity(&mut self, change: OpacityChange) {
+        self.active_workspace().set_window_opacity(change);
+    }
+
+    pub fn reset_window_opacity(&mut self) {
+        self.active_workspace().reset_window_opacity();
+    }
+
     pub fn move_workspace_down(&mut self) {
         let new_idx = min(self.active_workspace_idx + 1, self.workspaces.len() - 1);
         if new_idx == self.active_workspace_idx {
@@ -770,99 +1041,263 @@ impl<W: LayoutElement> Monitor<W> {
         if let Some(switch) = &self.workspace_switch {
             let size = output_size(&self.output).to_f64();
 
-            let offset = switch.target_idx() - self.active_workspace_idx as f64;
-            let offset = offset * size.h;
+            let offset = switch.target_idx() - self.render_idx_of(self.active_workspace_idx);
+            let offset = offset * self.swap_axes(size).h;
 
-            let clip_rect = Rectangle::from_loc_and_size((0., -offset), size);
+            let clip_loc = self.swap_axes(Point::from((0., -offset)));
+            let clip_rect = Rectangle::from_loc_and_size(clip_loc, size);
             rect = rect.intersection(clip_rect)?;
         }
 
         Some(rect)
     }
 
+    /// Returns the workspace visible at this position, and the workspace position within the
+    /// output.
+    ///
+    /// During a workspace switch, two workspaces may be visible at once, and the position is
+    /// split between them according to the animated switch progress. With the depth switch style,
+    /// the returned position accounts for the workspace scale at this particular position, so
+    /// subtracting it from `pos_within_output` gives the position within the workspace.
+    pub fn workspace_under(
+        &self,
+        pos_within_output: Point<f64, Logical>,
+    ) -> Option<(&Workspace<W>, Point<f64, Logical>)> {
+        let Some(render_idx) = self.render_idx() else {
+            let ws = &self.workspaces[self.active_workspace_idx];
+            return Some((ws, Point::from((0., 0.))));
+        };
+
+        // With the depth style, the workspaces are stacked on top of each other and scaled
+        // around the output center. The more visible one is on top and larger, so it receives all
+        // input within its scaled geometry.
+        if self.is_depth_switch() {
+            let progress = render_idx - render_idx.floor();
+            let (before, after) = self.workspaces_around_render_idx(render_idx);
+            let (idx, distance) = if progress > 0.5 {
+                (after?, 1. - progress)
+            } else {
+                (before?, progress)
+            };
+
+            let size = output_size(&self.output);
+            let center = size.to_point().downscale(2.);
+            let depth = workspace_depth_scale(distance);
+            let pos = center + (pos_within_output - center).downscale(depth);
+            if !Rectangle::from_loc_and_size((0., 0.), size).contains(pos) {
+                return None;
+            }
+
+            // The offset maps the position into the unscaled workspace, so it depends on the
+            // position itself.
+            return Some((&self.workspaces[idx], pos_within_output - pos));
+        }
+
+        let size = self.swap_axes(output_size(&self.output));
+        let pos = self.swap_axes(pos_within_output);
+
+        let offset = (render_idx - render_idx.floor()) * size.h;
+        let (before, after) = self.workspaces_around_render_idx(render_idx);
+
+        let (idx, ws_pos) = if pos.y < size.h - offset {
+            (before?, Point::from((0., -offset)))
+        } else {
+            (after?, Point::from((0., size.h - offset)))
+        };
+
+        Some((&self.workspaces[idx], self.swap_axes(ws_pos)))
+    }
+
     pub fn window_under(
         &self,
         pos_within_output: Point<f64, Logical>,
     ) -> Option<(&W, Option<Point<f64, Logical>>)> {
-        match &self.workspace_switch {
-            Some(switch) => {
-                let size = output_size(&self.output).to_f64();
+        let (ws, ws_pos) = self.workspace_under(pos_within_output)?;
+        let (win, win_pos) = ws.window_under(pos_within_output - ws_pos)?;
+        Some((win, win_pos.map(|p| p + ws_pos)))
+    }
 
-                let render_idx = switch.current_idx();
-                let before_idx = render_idx.floor();
-                let after_idx = render_idx.ceil();
+    pub fn resize_edges_under(&self, pos_within_output: Point<f64, Logical>) -> Option<ResizeEdge> {
+        let (ws, ws_pos) = self.workspace_under(pos_within_output)?;
+        ws.resize_edges_under(pos_within_output - ws_pos)
+    }
 
-                let offset = (render_idx - before_idx) * size.h;
+    pub fn interactive_move_begin(&mut self, window: W::Id) -> bool {
+        if self.interactive_move.is_some() {
+            return false;
+        }
 
-                if after_idx < 0. || before_idx as usize >= self.workspaces.len() {
-                    return None;
-                }
+        if !self.workspaces[self.active_workspace_idx].has_window(&window) {
+            return false;
+        }
 
-                let after_idx = after_idx as usize;
+        self.interactive_move = Some(window);
+        true
+    }
 
-                let (idx, ws_offset) = if pos_within_output.y < size.h - offset {
-                    if before_idx < 0. {
-                        return None;
-                    }
+    pub fn interactive_move_update(
+        &mut self,
+        window: &W::Id,
+        pos_within_output: Point<f64, Logical>,
+    ) -> bool {
+        if self.interactive_move.as_ref() != Some(window) {
+            return false;
+        }
 
-                    (before_idx as usize, Point::from((0., offset)))
-                } else {
-                    if after_idx >= self.workspaces.len() {
-                        return None;
-                    }
+        if !self.workspaces[self.active_workspace_idx].has_window(window) {
+            // The window went away from under the move.
+            self.interactive_move_end();
+            return false;
+        }
 
-                    (after_idx, Point::from((0., -size.h + offset)))
-                };
+        // Wait for the workspace switch to finish before moving anything.
+        if self.workspace_switch.is_some() {
+            self.edge_dwell = None;
+            return true;
+        }
+
+        // Holding the window at the top or bottom edge moves it to the workspace there. In a
+        // transposed layout, these are the left and right edges.
+        let size = self.swap_axes(output_size(&self.output));
+        let pos = self.swap_axes(pos_within_output);
+        let up = if (0. ..INTERACTIVE_MOVE_EDGE_SIZE).contains(&pos.y) {
+            (self.active_workspace_idx > 0).then_some(true)
+        } else if (size.h - INTERACTIVE_MOVE_EDGE_SIZE..size.h).contains(&pos.y) {
+            Some(false)
+        } else {
+            None
+        };
+
+        let ws = &mut self.workspaces[self.active_workspace_idx];
+
+        // Floating windows move freely over the workspace instead.
+        let up = up.filter(|_| !ws.is_floating(window));
 
-                let ws = &self.workspaces[idx];
-                let (win, win_pos) = ws.window_under(pos_within_output + ws_offset)?;
-                Some((win, win_pos.map(|p| p - ws_offset)))
+        match up {
+            Some(up) => {
+                ws.activate_window(window);
+                if self.edge_dwell.map(|dwell| dwell.up) != Some(up) {
+                    self.edge_dwell = Some(EdgeDwell {
+                        up,
+                        start_time: get_monotonic_time(),
+                        progress: 0.,
+                    });
+                }
             }
-            None => {
-                let ws = &self.workspaces[self.active_workspace_idx];
-                ws.window_under(pos_within_output)
+            None => self.edge_dwell = None,
+        }
+
+        ws.interactive_move_update(window, pos_within_output);
+        true
+    }
+
+    /// Returns the time when the window held at the edge is due to move to the workspace there.
+    pub fn interactive_move_edge_switch_time(&self) -> Option<Duration> {
+        self.edge_dwell
+            .map(|dwell| dwell.start_time + INTERACTIVE_MOVE_EDGE_DWELL)
+    }
+
+    /// Moves the window held at the edge to the workspace there if it has been held long enough.
+    ///
+    /// Returns whether the window moved.
+    pub fn interactive_move_switch_at_edge(&mut self, current_time: Duration) -> bool {
+        let Some(dwell) = self.edge_dwell else {
+            return false;
+        };
+        if current_time < dwell.start_time + INTERACTIVE_MOVE_EDGE_DWELL {
+            return false;
+        }
+
+        self.edge_dwell = None;
+
+        // The moved window is kept active while it's held at the edge.
+        if dwell.up {
+            self.move_to_workspace_up();
+        } else {
+            self.move_to_workspace_down();
+        }
+        true
+    }
+
+    /// Ends the interactive move, stopping the workspace switch of a window held at the edge.
+    pub fn interactive_move_end(&mut self) {
+        if let Some(window) = self.interactive_move.take() {
+            for ws in &mut self.workspaces {
+                ws.interactive_move_end(&window);
             }
         }
+        self.edge_dwell = None;
     }
 
-    pub fn resize_edges_under(&self, pos_within_output: Point<f64, Logical>) -> Option<ResizeEdge> {
-        match &self.workspace_switch {
-            Some(switch) => {
-                let size = output_size(&self.output);
+    /// Ends the interactive move if it's of this window, which is going away.
+    pub fn interactive_move_forget(&mut self, window: &W::Id) {
+        if self.interactive_move.as_ref() == Some(window) {
+            self.interactive_move_end();
+        }
+    }
 
-                let render_idx = switch.current_idx();
-                let before_idx = render_idx.floor();
-                let after_idx = render_idx.ceil();
+    pub fn resize_grip_under(
+        &self,
+        pos_within_output: Point<f64, Logical>,
+    ) -> Option<(&W, ResizeEdge)> {
+        let (ws, ws_pos) = self.workspace_under(pos_within_output)?;
+        ws.resize_grip_under(pos_within_output - ws_pos)
+    }
 
-                let offset = (render_idx - before_idx) * size.h;
+    /// Returns the visible workspaces and their positions within the output.
+    pub fn workspaces_with_render_positions(
+        &self,
+    ) -> impl Iterator<Item = (&Workspace<W>, Point<f64, Logical>)> {
+        let mut rv = Vec::with_capacity(2);
+
+        let Some(render_idx) = self.render_idx() else {
+            rv.push((
+                &self.workspaces[self.active_workspace_idx],
+                Point::from((0., 0.)),
+            ));
+            return rv.into_iter();
+        };
 
-                if after_idx < 0. || before_idx as usize >= self.workspaces.len() {
-                    return None;
-                }
+        let size = self.swap_axes(output_size(&self.output));
 
-                let after_idx = after_idx as usize;
+        let offset = (render_idx - render_idx.floor()) * size.h;
+        let (before, after) = self.workspaces_around_render_idx(render_idx);
 
-                let (idx, ws_offset) = if pos_within_output.y < size.h - offset {
-                    if before_idx < 0. {
-                        return None;
-                    }
+        // With the depth style, the workspaces don't slide.
+        let (before_pos, after_pos) = if self.is_depth_switch() {
+            (Point::from((0., 0.)), Point::from((0., 0.)))
+        } else {
+            (
+                self.swap_axes(Point::from((0., -offset))),
+                self.swap_axes(Point::from((0., size.h - offset))),
+            )
+        };
 
-                    (before_idx as usize, Point::from((0., offset)))
-                } else {
-                    if after_idx >= self.workspaces.len() {
-                        return None;
-                    }
+        if let Some(idx) = before {
+            rv.push((&self.workspaces[idx], before_pos));
+        }
 
-                    (after_idx, Point::from((0., -size.h + offset)))
-                };
+        // When the switch is exactly at a workspace, the next one is fully off-screen.
+        if let Some(idx) = after {
+            rv.push((&self.workspaces[idx], after_pos));
+        }
 
-                let ws = &self.workspaces[idx];
-                ws.resize_edges_under(pos_within_output + ws_offset)
-            }
-            None => {
-                let ws = &self.workspaces[self.active_workspace_idx];
-                ws.resize_edges_under(pos_within_output)
-            }
+        rv.into_iter()
+    }
+
+    pub fn snapshot(&self, is_active: bool) -> LayoutSnapshot<W::Id>
+    where
+        W::Id: Clone,
+    {
+        let mut tiles = Vec::new();
+        for (ws, ws_pos) in self.workspaces_with_render_positions() {
+            let is_active = is_active && ptr::eq(ws, &self.workspaces[self.active_workspace_idx]);
+            ws.snapshot_tiles(ws_pos, is_active, &mut tiles);
+        }
+
+        LayoutSnapshot {
+            render_above_top_layer: self.render_above_top_layer(),
+            tiles,
         }
     }
 
@@ -876,6 +1311,98 @@ impl<W: LayoutElement> Monitor<W> {
         ws.render_above_top_layer()
     }
 
+    /// Returns whether the workspaces are laid out horizontally, in a transposed layout.
+    ///
+    /// This is decided per monitor, so that all of its workspaces, including the ones in a switch,
+    /// share the same axes.
+    pub fn is_transposed(&self) -> bool {
+        self.options
+            .is_transposed_for_output_size(output_size(&self.output))
+    }
+
+    /// Converts between the output coordinates and the ones where the workspaces are stacked
+    /// vertically.
+    fn swap_axes<T: Transpose>(&self, value: T) -> T {
+        if self.is_transposed() {
+            value.transpose()
+        } else {
+            value
+        }
+    }
+
+    /// Returns whether workspace switches use the depth style.
+    fn is_depth_switch(&self) -> bool {
+        self.options.animations.workspace_switch.style == WorkspaceSwitchStyle::Depth
+    }
+
+    /// Renders a workspace scaled down and faded out according to its distance from the view.
+    ///
+    /// The distance goes from 0 for a fully visible workspace to 1 for one a full switch away.
+    fn render_workspace_with_depth<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        target: RenderTarget,
+        idx: usize,
+        distance: f64,
+    ) -> Vec<MonitorRenderElement<R>> {
+        let scale = Scale::from(self.output.current_scale().fractional_scale());
+        let size = output_size(&self.output);
+
+        let ws = &self.workspaces[idx];
+        let zoom = ws.zoom();
+
+        // Fading the workspace as a whole needs it in a single texture, otherwise overlapping
+        // windows would show through each other.
+        let renderer = renderer.as_gles_renderer();
+        let elements = ws
+            .render_elements(renderer, target)
+            .into_iter()
+            .map(|elem| RescaleRenderElement::from_element(elem, Point::from((0, 0)), zoom))
+            .collect::<Vec<_>>();
+        let (texture, _sync_point, geo) = match render_to_encompassing_texture(
+            renderer,
+            scale,
+            Transform::Normal,
+            Fourcc::Abgr8888,
+            &elements,
+        ) {
+            Ok(rv) => rv,
+            Err(err) => {
+                warn!("error rendering workspace to texture: {err:?}");
+                return vec![];
+            }
+        };
+
+        let alpha = (1. - distance).clamp(0., 1.) as f32;
+        let buffer =
+            TextureBuffer::from_texture(renderer, texture, scale, Transform::Normal, Vec::new());
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            geo.loc.to_f64().to_logical(scale),
+            alpha,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+        let elem = WorkspaceRenderElement::from(PrimaryGpuTextureRenderElement(elem));
+
+        let depth = workspace_depth_scale(distance);
+        let center = size
+            .to_point()
+            .downscale(2.)
+            .to_physical_precise_round(scale);
+        let elem = RescaleRenderElement::from_element(elem, center, depth);
+
+        CropRenderElement::from_element(
+            elem,
+            scale,
+            Rectangle::from_loc_and_size((-i32::MAX / 2, -i32::MAX / 2), (i32::MAX, i32::MAX)),
+        )
+        .map(|elem| RelocateRenderElement::from_element(elem, (0, 0), Relocate::Relative))
+        .into_iter()
+        .collect()
+    }
+
     pub fn render_elements<R: NiriRenderer>(
         &self,
         renderer: &mut R,
@@ -884,78 +1411,99 @@ impl<W: LayoutElement> Monitor<W> {
         let _span = tracy_client::span!("Monitor::render_elements");
 
         let scale = self.output.current_scale().fractional_scale();
-        let size = output_size(&self.output);
+        // The workspaces slide along the height of this size, which is the output width in a
+        // transposed layout.
+        let size = self.swap_axes(output_size(&self.output));
 
-        match &self.workspace_switch {
-            Some(switch) => {
-                let render_idx = switch.current_idx();
-                let before_idx = render_idx.floor();
-                let after_idx = render_idx.ceil();
+        match self.render_idx() {
+            Some(render_idx) => {
+                let progress = render_idx - render_idx.floor();
+                let offset = progress * size.h;
 
-                let offset = (render_idx - before_idx) * size.h;
+                let (before_idx, after_idx) = self.workspaces_around_render_idx(render_idx);
 
-                if after_idx < 0. || before_idx as usize >= self.workspaces.len() {
-                    return vec![];
+                if self.is_depth_switch() {
+                    let mut before = vec![];
+                    if let Some(idx) = before_idx {
+                        before = self.render_workspace_with_depth(renderer, target, idx, progress);
+                    }
+
+                    let mut after = vec![];
+                    if let Some(idx) = after_idx {
+                        after =
+                            self.render_workspace_with_depth(renderer, target, idx, 1. - progress);
+                    }
+
+                    // Draw the more visible workspace on top.
+                    if progress > 0.5 {
+                        after.append(&mut before);
+                        return after;
+                    }
+                    before.append(&mut after);
+                    return before;
                 }
 
-                let after_idx = after_idx as usize;
-                let after = if after_idx < self.workspaces.len() {
+                let after = if let Some(after_idx) = after_idx {
                     let after = self.workspaces[after_idx].render_elements(renderer, target);
-                    let after = after.into_iter().filter_map(|elem| {
+                    let zoom = self.workspaces[after_idx].zoom();
+                    let after = after.into_iter().filter_map(move |elem| {
                         Some(RelocateRenderElement::from_element(
                             CropRenderElement::from_element(
-                                elem,
+                                RescaleRenderElement::from_element(elem, Point::from((0, 0)), zoom),
                                 scale,
                                 // HACK: crop to infinite bounds for all sides except the side
                                 // where the workspaces join,
                                 // otherwise it will cut pixel shaders and mess up
                                 // the coordinate space.
-                                Rectangle::from_extemities(
+                                self.swap_axes(Rectangle::from_extemities(
                                     (-i32::MAX / 2, 0),
                                     (i32::MAX / 2, i32::MAX / 2),
-                                ),
+                                )),
                             )?,
-                            Point::from((0., -offset + size.h)).to_physical_precise_round(scale),
+                            self.swap_axes(Point::from((0., -offset + size.h)))
+                                .to_physical_precise_round(scale),
                             Relocate::Relative,
                         ))
                     });
 
-                    if before_idx < 0. {
-                        return after.collect();
-                    }
-
                     Some(after)
                 } else {
                     None
                 };
 
-                let before_idx = before_idx as usize;
+                let Some(before_idx) = before_idx else {
+                    return after.into_iter().flatten().collect();
+                };
+
                 let before = self.workspaces[before_idx].render_elements(renderer, target);
-                let before = before.into_iter().filter_map(|elem| {
+                let zoom = self.workspaces[before_idx].zoom();
+                let before = before.into_iter().filter_map(move |elem| {
                     Some(RelocateRenderElement::from_element(
                         CropRenderElement::from_element(
-                            elem,
+                            RescaleRenderElement::from_element(elem, Point::from((0, 0)), zoom),
                             scale,
-                            Rectangle::from_extemities(
+                            self.swap_axes(Rectangle::from_extemities(
                                 (-i32::MAX / 2, -i32::MAX / 2),
                                 (i32::MAX / 2, to_physical_precise_round(scale, size.h)),
-                            ),
+                            )),
                         )?,
-                        Point::from((0., -offset)).to_physical_precise_round(scale),
+                        self.swap_axes(Point::from((0., -offset)))
+                            .to_physical_precise_round(scale),
                         Relocate::Relative,
                     ))
                 });
                 before.chain(after.into_iter().flatten()).collect()
             }
             None => {
-                let elements =
-                    self.workspaces[self.active_workspace_idx].render_elements(renderer, target);
+                let ws = &self.workspaces[self.active_workspace_idx];
+                let elements = ws.render_elements(renderer, target);
+                let zoom = ws.zoom();
                 elements
                     .into_iter()
                     .filter_map(|elem| {
                         Some(RelocateRenderElement::from_element(
                             CropRenderElement::from_element(
-                                elem,
+                                RescaleRenderElement::from_element(elem, Point::from((0, 0)), zoom),
                                 scale,
                                 // HACK: set infinite crop bounds due to a damage tracking bug
                                 // which causes glitched rendering for maximized GTK windows.
@@ -977,11 +1525,7 @@ impl<W: LayoutElement> Monitor<W> {
 
     pub fn workspace_switch_gesture_begin(&mut self, is_touchpad: bool) {
         let center_idx = self.active_workspace_idx;
-        let current_idx = self
-            .workspace_switch
-            .as_ref()
-            .map(|s| s.current_idx())
-            .unwrap_or(center_idx as f64);
+        let current_idx = self.current_switch_idx();
 
         let gesture = WorkspaceSwitchGesture {
             center_idx,
@@ -1077,9 +1621,15 @@ impl<W: LayoutElement> Monitor<W> {
             gesture.current_idx,
             new_idx as f64,
             velocity,
-            self.options.animations.workspace_switch.0,
+            self.options.animations.workspace_switch.anim,
         )));
 
         true
     }
 }
+
+/// Returns the scale of a workspace at this distance from the view with the depth workspace switch
+/// style.
+fn workspace_depth_scale(distance: f64) -> f64 {
+    1. - distance * (1. - WORKSPACE_SWITCH_DEPTH_SCALE)
+}
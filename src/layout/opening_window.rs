@@ -24,6 +24,8 @@ use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
 pub struct OpenAnimation {
     anim: Animation,
     random_seed: f32,
+    /// Point the window grows from, relative to the output, if it was launched from somewhere.
+    origin: Option<Point<f64, Logical>>,
 }
 
 niri_render_elements! {
@@ -34,10 +36,11 @@ niri_render_elements! {
 }
 
 impl OpenAnimation {
-    pub fn new(anim: Animation) -> Self {
+    pub fn new(anim: Animation, origin: Option<Point<f64, Logical>>) -> Self {
         Self {
             anim,
             random_seed: fastrand::f32(),
+            origin,
         }
     }
 
@@ -136,11 +139,19 @@ impl OpenAnimation {
 
         let elem = PrimaryGpuTextureRenderElement(elem);
 
-        let center = geo_size.to_point().downscale(2.);
+        // Custom shaders above draw their own effect, so only the default one grows from the
+        // origin.
+        let (rescale_origin, rescale) = match self.origin {
+            Some(origin) => (origin - location, progress.max(0.)),
+            None => (
+                geo_size.to_point().downscale(2.),
+                (progress / 2. + 0.5).max(0.),
+            ),
+        };
         let elem = RescaleRenderElement::from_element(
             elem,
-            (center - offset).to_physical_precise_round(scale),
-            (progress / 2. + 0.5).max(0.),
+            (rescale_origin - offset).to_physical_precise_round(scale),
+            rescale,
         );
 
         let elem = RelocateRenderElement::from_element(
@@ -0,0 +1,1199 @@
+//! Headless layout operations for property tests and fuzzing.
+//!
+//! Operations run against [`TestWindow`]s, which need no Wayland clients or renderer, and are
+//! deterministic: replaying the same sequence always produces the same layout. Fuzz targets
+//! should call [`Layout::verify_invariants()`] after every operation.
+
+use std::cell::Cell;
+
+use niri_config::WorkspaceName;
+use proptest::prelude::*;
+use proptest_derive::Arbitrary;
+use smithay::output::{Mode, PhysicalProperties, Subpixel};
+
+use super::*;
+use crate::utils::clock::ManualClock;
+use crate::utils::{get_monotonic_time, OutputSerial};
+
+#[derive(Debug)]
+struct TestWindowInner {
+    id: usize,
+    bbox: Cell<Rectangle<i32, Logical>>,
+    initial_bbox: Rectangle<i32, Logical>,
+    requested_size: Cell<Option<Size<i32, Logical>>>,
+    /// Serial and size of the latest configure, as if it was sent right away.
+    last_configure: Cell<Option<(u32, Size<i32, Logical>)>>,
+    accepted_serial: Cell<Option<u32>>,
+    min_size: Size<i32, Logical>,
+    max_size: Size<i32, Logical>,
+    pending_fullscreen: Cell<bool>,
+    tiled: Cell<Option<ResizeEdge>>,
+    maximized: Cell<bool>,
+    suspended: Cell<bool>,
+    rules: ResolvedWindowRules,
+}
+
+/// Fake window that records the sizes requested by the layout.
+#[derive(Debug, Clone)]
+pub struct TestWindow(Rc<TestWindowInner>);
+
+impl TestWindow {
+    pub fn new(
+        id: usize,
+        bbox: Rectangle<i32, Logical>,
+        min_size: Size<i32, Logical>,
+        max_size: Size<i32, Logical>,
+    ) -> Self {
+        Self(Rc::new(TestWindowInner {
+            id,
+            bbox: Cell::new(bbox),
+            initial_bbox: bbox,
+            requested_size: Cell::new(None),
+            last_configure: Cell::new(None),
+            accepted_serial: Cell::new(None),
+            min_size,
+            max_size,
+            pending_fullscreen: Cell::new(false),
+            tiled: Cell::new(None),
+            maximized: Cell::new(false),
+            suspended: Cell::new(false),
+            rules: ResolvedWindowRules::empty(),
+        }))
+    }
+
+    /// Gives the window these rules, before it's added to the layout.
+    pub fn with_rules(mut self, rules: ResolvedWindowRules) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().rules = rules;
+        self
+    }
+
+    /// Applies the last requested size, like a client committing in response to a configure.
+    ///
+    /// Returns `true` if the size changed.
+    pub fn communicate(&self) -> bool {
+        if let Some(size) = self.0.requested_size.take() {
+            self.accept_last_configure();
+
+            assert!(size.w >= 0);
+            assert!(size.h >= 0);
+
+            let mut new_bbox = self.0.initial_bbox;
+            if size.w != 0 {
+                new_bbox.size.w = size.w;
+            }
+            if size.h != 0 {
+                new_bbox.size.h = size.h;
+            }
+
+            if self.0.bbox.get() != new_bbox {
+                self.0.bbox.set(new_bbox);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    pub fn tiled_edges(&self) -> Option<ResizeEdge> {
+        self.0.tiled.get()
+    }
+
+    pub fn is_maximized(&self) -> bool {
+        self.0.maximized.get()
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.0.suspended.get()
+    }
+
+    /// Commits the last configure without changing the size, like a client that refuses it.
+    pub fn refuse(&self) {
+        self.0.requested_size.set(None);
+        self.accept_last_configure();
+    }
+
+    fn accept_last_configure(&self) {
+        let serial = self.0.last_configure.get().map(|(serial, _)| serial);
+        self.0.accepted_serial.set(serial);
+    }
+}
+
+impl LayoutElement for TestWindow {
+    type Id = usize;
+
+    fn id(&self) -> &Self::Id {
+        &self.0.id
+    }
+
+    fn size(&self) -> Size<i32, Logical> {
+        self.0.bbox.get().size
+    }
+
+    fn buf_loc(&self) -> Point<i32, Logical> {
+        (0, 0).into()
+    }
+
+    fn is_in_input_region(&self, _point: Point<f64, Logical>) -> bool {
+        false
+    }
+
+    fn render<R: NiriRenderer>(
+        &self,
+        _renderer: &mut R,
+        _location: Point<f64, Logical>,
+        _scale: Scale<f64>,
+        _alpha: f32,
+        _target: RenderTarget,
+    ) -> SplitElements<LayoutElementRenderElement<R>> {
+        SplitElements::default()
+    }
+
+    fn request_size(
+        &mut self,
+        size: Size<i32, Logical>,
+        _animate: bool,
+        _transaction: Option<Transaction>,
+    ) {
+        self.0.requested_size.set(Some(size));
+        self.0.pending_fullscreen.set(false);
+
+        match self.0.last_configure.get() {
+            Some((_, last)) if last == size => (),
+            last => {
+                let serial = last.map_or(1, |(serial, _)| serial + 1);
+                self.0.last_configure.set(Some((serial, size)));
+            }
+        }
+    }
+
+    fn request_fullscreen(&self, _size: Size<i32, Logical>) {
+        self.0.pending_fullscreen.set(true);
+    }
+
+    fn requested_size(&self) -> Option<Size<i32, Logical>> {
+        self.0.requested_size.get()
+    }
+
+    fn min_size(&self) -> Size<i32, Logical> {
+        self.0.min_size
+    }
+
+    fn max_size(&self) -> Size<i32, Logical> {
+        self.0.max_size
+    }
+
+    fn is_dialog(&self) -> bool {
+        let min_size = self.0.min_size;
+        min_size.w > 0 && min_size.h > 0 && min_size == self.0.max_size
+    }
+
+    fn is_wl_surface(&self, _wl_surface: &WlSurface) -> bool {
+        false
+    }
+
+    fn set_preferred_scale_transform(&self, _scale: output::Scale, _transform: Transform) {}
+
+    fn has_ssd(&self) -> bool {
+        false
+    }
+
+    fn output_enter(&self, _output: &Output) {}
+
+    fn output_leave(&self, _output: &Output) {}
+
+    fn set_offscreen_element_id(&self, _id: Option<Id>) {}
+
+    fn set_activated(&mut self, _active: bool) {}
+
+    fn set_bounds(&self, _bounds: Size<i32, Logical>) {}
+
+    fn set_tiled(&mut self, edges: Option<ResizeEdge>) {
+        self.0.tiled.set(edges);
+    }
+
+    fn set_maximized(&mut self, maximized: bool) {
+        self.0.maximized.set(maximized);
+    }
+
+    fn set_suspended(&mut self, suspended: bool) {
+        self.0.suspended.set(suspended);
+    }
+
+    fn send_pending_configure(&mut self) {}
+
+    fn configure_serial(&self) -> Option<Serial> {
+        self.0
+            .last_configure
+            .get()
+            .map(|(serial, _)| Serial::from(serial))
+    }
+
+    fn expected_size(&self) -> Option<Size<i32, Logical>> {
+        self.0.last_configure.get().map(|(_, size)| size)
+    }
+
+    fn accepted_serial(&self) -> Option<Serial> {
+        self.0.accepted_serial.get().map(Serial::from)
+    }
+
+    fn set_active_in_column(&mut self, _active: bool) {}
+
+    fn is_fullscreen(&self) -> bool {
+        false
+    }
+
+    fn is_pending_fullscreen(&self) -> bool {
+        self.0.pending_fullscreen.get()
+    }
+
+    fn refresh(&self) {}
+
+    fn rules(&self) -> &ResolvedWindowRules {
+        &self.0.rules
+    }
+
+    fn animation_snapshot(&self) -> Option<&LayoutElementRenderSnapshot> {
+        None
+    }
+
+    fn take_animation_snapshot(&mut self) -> Option<LayoutElementRenderSnapshot> {
+        None
+    }
+
+    fn set_interactive_resize(&mut self, _data: Option<InteractiveResizeData>) {}
+
+    fn cancel_interactive_resize(&mut self) {}
+
+    fn update_interactive_resize(&mut self, _serial: Serial) {}
+
+    fn interactive_resize_data(&self) -> Option<InteractiveResizeData> {
+        None
+    }
+}
+
+fn arbitrary_bbox() -> impl Strategy<Value = Rectangle<i32, Logical>> {
+    any::<(i16, i16, u16, u16)>().prop_map(|(x, y, w, h)| {
+        let loc: Point<i32, _> = Point::from((x.into(), y.into()));
+        let size: Size<i32, _> = Size::from((w.into(), h.into()));
+        Rectangle::from_loc_and_size(loc, size)
+    })
+}
+
+fn arbitrary_size_change() -> impl Strategy<Value = SizeChange> {
+    prop_oneof![
+        (0..).prop_map(SizeChange::SetFixed),
+        (0f64..).prop_map(SizeChange::SetProportion),
+        any::<i32>().prop_map(SizeChange::AdjustFixed),
+        any::<f64>().prop_map(SizeChange::AdjustProportion),
+    ]
+}
+
+fn arbitrary_opacity_change() -> impl Strategy<Value = OpacityChange> {
+    prop_oneof![
+        (0f64..=1.).prop_map(OpacityChange::SetOpacity),
+        any::<f64>().prop_map(OpacityChange::AdjustOpacity),
+    ]
+}
+
+fn arbitrary_layout_strategy() -> impl Strategy<Value = LayoutStrategyName> {
+    prop_oneof![
+        Just(LayoutStrategyName::Scrolling),
+        Just(LayoutStrategyName::MasterStack),
+    ]
+}
+
+fn arbitrary_center_focused_column() -> impl Strategy<Value = CenterFocusedColumn> {
+    prop_oneof![
+        Just(CenterFocusedColumn::Never),
+        Just(CenterFocusedColumn::Always),
+        Just(CenterFocusedColumn::OnOverflow),
+    ]
+}
+
+fn arbitrary_min_max() -> impl Strategy<Value = (i32, i32)> {
+    prop_oneof![
+        Just((0, 0)),
+        (1..65536).prop_map(|n| (n, n)),
+        (1..65536).prop_map(|min| (min, 0)),
+        (1..).prop_map(|max| (0, max)),
+        (1..65536, 1..).prop_map(|(min, max): (i32, i32)| (min, max.max(min))),
+    ]
+}
+
+fn arbitrary_min_max_size() -> impl Strategy<Value = (Size<i32, Logical>, Size<i32, Logical>)> {
+    (arbitrary_min_max(), arbitrary_min_max()).prop_map(|((min_w, max_w), (min_h, max_h))| {
+        let min_size = Size::from((min_w, min_h));
+        let max_size = Size::from((max_w, max_h));
+        (min_size, max_size)
+    })
+}
+
+fn arbitrary_view_offset_gesture_delta() -> impl Strategy<Value = f64> {
+    prop_oneof![(-10f64..10f64), (-50000f64..50000f64),]
+}
+
+fn arbitrary_resize_edge() -> impl Strategy<Value = ResizeEdge> {
+    prop_oneof![
+        Just(ResizeEdge::RIGHT),
+        Just(ResizeEdge::BOTTOM),
+        Just(ResizeEdge::LEFT),
+        Just(ResizeEdge::TOP),
+        Just(ResizeEdge::BOTTOM_RIGHT),
+        Just(ResizeEdge::BOTTOM_LEFT),
+        Just(ResizeEdge::TOP_RIGHT),
+        Just(ResizeEdge::TOP_LEFT),
+        Just(ResizeEdge::empty()),
+    ]
+}
+
+fn arbitrary_scale() -> impl Strategy<Value = f64> {
+    prop_oneof![Just(1.), Just(1.5), Just(2.),]
+}
+
+/// Operation on the layout, mirroring what the compositor does in response to clients and input.
+#[derive(Debug, Clone, Copy, Arbitrary)]
+pub enum Op {
+    AddOutput(#[proptest(strategy = "1..=5usize")] usize),
+    AddScaledOutput {
+        #[proptest(strategy = "1..=5usize")]
+        id: usize,
+        #[proptest(strategy = "arbitrary_scale()")]
+        scale: f64,
+    },
+    /// Adds an output whose monitor reports the serial number `monitor{monitor}`.
+    AddOutputWithMonitor {
+        #[proptest(strategy = "1..=5usize")]
+        id: usize,
+        #[proptest(strategy = "1..=3usize")]
+        monitor: usize,
+    },
+    RemoveOutput(#[proptest(strategy = "1..=5usize")] usize),
+    FocusOutput(#[proptest(strategy = "1..=5usize")] usize),
+    SetPrimaryOutput(#[proptest(strategy = "1..=5usize")] usize),
+    AddNamedWorkspace {
+        #[proptest(strategy = "1..=5usize")]
+        ws_name: usize,
+        #[proptest(strategy = "prop::option::of(1..=5usize)")]
+        output_name: Option<usize>,
+    },
+    AddNamedWorkspaceOnDemand {
+        #[proptest(strategy = "1..=5usize")]
+        ws_name: usize,
+        #[proptest(strategy = "prop::option::of(1..=5usize)")]
+        output_name: Option<usize>,
+    },
+    UnnameWorkspace {
+        #[proptest(strategy = "1..=5usize")]
+        ws_name: usize,
+    },
+    MoveNamedWorkspaceToOutput {
+        #[proptest(strategy = "1..=5usize")]
+        ws_name: usize,
+        #[proptest(strategy = "1..=5usize")]
+        output_idx: usize,
+    },
+    AddWindow {
+        #[proptest(strategy = "1..=5usize")]
+        id: usize,
+        #[proptest(strategy = "arbitrary_bbox()")]
+        bbox: Rectangle<i32, Logical>,
+        #[proptest(strategy = "arbitrary_min_max_size()")]
+        min_max_size: (Size<i32, Logical>, Size<i32, Logical>),
+    },
+    AddWindowRightOf {
+        #[proptest(strategy = "1..=5usize")]
+        id: usize,
+        #[proptest(strategy = "1..=5usize")]
+        right_of_id: usize,
+        #[proptest(strategy = "arbitrary_bbox()")]
+        bbox: Rectangle<i32, Logical>,
+        #[proptest(strategy = "arbitrary_min_max_size()")]
+        min_max_size: (Size<i32, Logical>, Size<i32, Logical>),
+    },
+    AddWindowToColumnOf {
+        #[proptest(strategy = "1..=5usize")]
+        id: usize,
+        #[proptest(strategy = "1..=5usize")]
+        column_of_id: usize,
+        #[proptest(strategy = "arbitrary_bbox()")]
+        bbox: Rectangle<i32, Logical>,
+        #[proptest(strategy = "arbitrary_min_max_size()")]
+        min_max_size: (Size<i32, Logical>, Size<i32, Logical>),
+    },
+    AddFloatingWindow {
+        #[proptest(strategy = "1..=5usize")]
+        id: usize,
+        #[proptest(strategy = "prop::option::of(1..=5usize)")]
+        parent_id: Option<usize>,
+        #[proptest(strategy = "arbitrary_bbox()")]
+        bbox: Rectangle<i32, Logical>,
+    },
+    AddWindowToNamedWorkspace {
+        #[proptest(strategy = "1..=5usize")]
+        id: usize,
+        #[proptest(strategy = "1..=5usize")]
+        ws_name: usize,
+        #[proptest(strategy = "arbitrary_bbox()")]
+        bbox: Rectangle<i32, Logical>,
+        #[proptest(strategy = "arbitrary_min_max_size()")]
+        min_max_size: (Size<i32, Logical>, Size<i32, Logical>),
+        focus: bool,
+    },
+    CloseWindow(#[proptest(strategy = "1..=5usize")] usize),
+    FullscreenWindow(#[proptest(strategy = "1..=5usize")] usize),
+    SetFullscreenWindow {
+        #[proptest(strategy = "1..=5usize")]
+        window: usize,
+        is_fullscreen: bool,
+    },
+    FocusColumnLeft,
+    FocusColumnRight,
+    FocusColumnFirst,
+    FocusColumnLast,
+    FocusColumn(#[proptest(strategy = "0..=5usize")] usize),
+    FocusColumnRightOrFirst,
+    FocusColumnLeftOrLast,
+    FocusColumnOrMonitorLeft(#[proptest(strategy = "1..=2u8")] u8),
+    FocusColumnOrMonitorRight(#[proptest(strategy = "1..=2u8")] u8),
+    FocusWindowDown,
+    FocusWindowUp,
+    FocusWindowDownOrColumnLeft,
+    FocusWindowDownOrColumnRight,
+    FocusWindowUpOrColumnLeft,
+    FocusWindowUpOrColumnRight,
+    FocusWindowOrWorkspaceDown,
+    FocusWindowOrWorkspaceUp,
+    MoveColumnLeft,
+    MoveColumnRight,
+    MoveColumnToFirst,
+    MoveColumnToLast,
+    MoveColumnToIndex(#[proptest(strategy = "0..=5usize")] usize),
+    MoveWindowDown,
+    MoveWindowUp,
+    MoveWindowDownOrToWorkspaceDown,
+    MoveWindowUpOrToWorkspaceUp,
+    ConsumeOrExpelWindowLeft,
+    ConsumeOrExpelWindowRight,
+    ConsumeWindowIntoColumn,
+    ExpelWindowFromColumn,
+    CenterColumn,
+    FocusWorkspaceDown,
+    FocusWorkspaceUp,
+    FocusWorkspace(#[proptest(strategy = "0..=4usize")] usize),
+    FocusWorkspaceAutoBackAndForth(#[proptest(strategy = "0..=4usize")] usize),
+    FocusWorkspacePrevious,
+    MoveWindowToWorkspaceDown,
+    MoveWindowToWorkspaceUp,
+    MoveWindowToWorkspace(#[proptest(strategy = "0..=4usize")] usize),
+    MoveColumnToWorkspaceDown,
+    MoveColumnToWorkspaceUp,
+    MoveColumnToWorkspace(#[proptest(strategy = "0..=4usize")] usize),
+    MoveWorkspaceDown,
+    MoveWorkspaceUp,
+    MoveWindowToOutput(#[proptest(strategy = "1..=5u8")] u8),
+    MoveColumnToOutput(#[proptest(strategy = "1..=5u8")] u8),
+    GatherWindows(
+        #[proptest(strategy = "1..=5usize")] usize,
+        #[proptest(strategy = "1..=5usize")] usize,
+    ),
+    SwapWindows(
+        #[proptest(strategy = "1..=5usize")] usize,
+        #[proptest(strategy = "1..=5usize")] usize,
+    ),
+    SwitchPresetColumnWidth,
+    MaximizeColumn,
+    ToggleAutoTile,
+    ToggleColumnAlwaysCenter,
+    SetColumnWidth(#[proptest(strategy = "arbitrary_size_change()")] SizeChange),
+    ToggleColumnWidth(
+        #[proptest(strategy = "arbitrary_size_change()")] SizeChange,
+        #[proptest(strategy = "arbitrary_size_change()")] SizeChange,
+    ),
+    SetWindowHeight(#[proptest(strategy = "arbitrary_size_change()")] SizeChange),
+    ResetWindowHeight,
+    SetWindowOpacity(#[proptest(strategy = "arbitrary_opacity_change()")] OpacityChange),
+    ResetWindowOpacity,
+    SetWorkspaceGaps(#[proptest(strategy = "arbitrary_size_change()")] SizeChange),
+    SetWorkspaceDefaultColumnWidth(#[proptest(strategy = "arbitrary_size_change()")] SizeChange),
+    SetWorkspaceCenterFocusedColumn(
+        #[proptest(strategy = "arbitrary_center_focused_column()")] CenterFocusedColumn,
+    ),
+    SetWorkspaceZoom(#[proptest(strategy = "0.1..=1f64")] f64),
+    ResetWorkspaceLayout,
+    SetLayoutStrategy(#[proptest(strategy = "arbitrary_layout_strategy()")] LayoutStrategyName),
+    Communicate(#[proptest(strategy = "1..=5usize")] usize),
+    MoveWorkspaceToOutput(#[proptest(strategy = "1..=5u8")] u8),
+    ViewOffsetGestureBegin {
+        #[proptest(strategy = "1..=5usize")]
+        output_idx: usize,
+        is_touchpad: bool,
+    },
+    ViewOffsetGestureUpdate {
+        #[proptest(strategy = "arbitrary_view_offset_gesture_delta()")]
+        delta: f64,
+        timestamp: Duration,
+        is_touchpad: bool,
+    },
+    ViewOffsetGestureEnd {
+        is_touchpad: Option<bool>,
+    },
+    WorkspaceSwitchGestureBegin {
+        #[proptest(strategy = "1..=5usize")]
+        output_idx: usize,
+        is_touchpad: bool,
+    },
+    WorkspaceSwitchGestureUpdate {
+        #[proptest(strategy = "-400f64..400f64")]
+        delta: f64,
+        timestamp: Duration,
+        is_touchpad: bool,
+    },
+    WorkspaceSwitchGestureEnd {
+        cancelled: bool,
+        is_touchpad: Option<bool>,
+    },
+    InteractiveResizeBegin {
+        #[proptest(strategy = "1..=5usize")]
+        window: usize,
+        #[proptest(strategy = "arbitrary_resize_edge()")]
+        edges: ResizeEdge,
+    },
+    InteractiveResizeUpdate {
+        #[proptest(strategy = "1..=5usize")]
+        window: usize,
+        #[proptest(strategy = "-20000f64..20000f64")]
+        dx: f64,
+        #[proptest(strategy = "-20000f64..20000f64")]
+        dy: f64,
+    },
+    InteractiveResizeEnd {
+        #[proptest(strategy = "1..=5usize")]
+        window: usize,
+    },
+    InteractiveMoveBegin {
+        #[proptest(strategy = "1..=5usize")]
+        window: usize,
+        #[proptest(strategy = "1..=5usize")]
+        output_idx: usize,
+    },
+    InteractiveMoveUpdate {
+        #[proptest(strategy = "1..=5usize")]
+        window: usize,
+        #[proptest(strategy = "1..=5usize")]
+        output_idx: usize,
+        #[proptest(strategy = "-20000f64..20000f64")]
+        x: f64,
+        #[proptest(strategy = "-20000f64..20000f64")]
+        y: f64,
+    },
+    InteractiveMoveSwitchAtEdge {
+        #[proptest(strategy = "1..=5usize")]
+        output_idx: usize,
+    },
+    InteractiveMoveEnd {
+        #[proptest(strategy = "1..=5usize")]
+        output_idx: usize,
+    },
+}
+
+impl Op {
+    pub fn apply(self, layout: &mut Layout<TestWindow>) {
+        match self {
+            Op::AddOutput(id) => {
+                let name = format!("output{id}");
+                if layout.outputs().any(|o| o.name() == name) {
+                    return;
+                }
+
+                let output = Output::new(
+                    name,
+                    PhysicalProperties {
+                        size: Size::from((1280, 720)),
+                        subpixel: Subpixel::Unknown,
+                        make: String::new(),
+                        model: String::new(),
+                    },
+                );
+                output.change_current_state(
+                    Some(Mode {
+                        size: Size::from((1280, 720)),
+                        refresh: 60000,
+                    }),
+                    None,
+                    None,
+                    None,
+                );
+                layout.add_output(output.clone());
+            }
+            Op::AddScaledOutput { id, scale } => {
+                let name = format!("output{id}");
+                if layout.outputs().any(|o| o.name() == name) {
+                    return;
+                }
+
+                let output = Output::new(
+                    name,
+                    PhysicalProperties {
+                        size: Size::from((1280, 720)),
+                        subpixel: Subpixel::Unknown,
+                        make: String::new(),
+                        model: String::new(),
+                    },
+                );
+                output.change_current_state(
+                    Some(Mode {
+                        size: Size::from((1280, 720)),
+                        refresh: 60000,
+                    }),
+                    None,
+                    Some(smithay::output::Scale::Fractional(scale)),
+                    None,
+                );
+                layout.add_output(output.clone());
+            }
+            Op::AddOutputWithMonitor { id, monitor } => {
+                let name = format!("output{id}");
+                if layout.outputs().any(|o| o.name() == name) {
+                    return;
+                }
+
+                let output = Output::new(
+                    name,
+                    PhysicalProperties {
+                        size: Size::from((1280, 720)),
+                        subpixel: Subpixel::Unknown,
+                        make: String::from("make"),
+                        model: String::from("model"),
+                    },
+                );
+                output.change_current_state(
+                    Some(Mode {
+                        size: Size::from((1280, 720)),
+                        refresh: 60000,
+                    }),
+                    None,
+                    None,
+                    None,
+                );
+                output
+                    .user_data()
+                    .insert_if_missing(|| OutputSerial(format!("monitor{monitor}")));
+                layout.add_output(output.clone());
+            }
+            Op::RemoveOutput(id) => {
+                let name = format!("output{id}");
+                let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
+                    return;
+                };
+
+                layout.remove_output(&output);
+            }
+            Op::FocusOutput(id) => {
+                let name = format!("output{id}");
+                let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
+                    return;
+                };
+
+                layout.focus_output(&output);
+            }
+            Op::SetPrimaryOutput(id) => {
+                let name = format!("output{id}");
+                let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
+                    return;
+                };
+
+                layout.set_primary_output(&output);
+            }
+            Op::AddNamedWorkspace {
+                ws_name,
+                output_name,
+            } => {
+                layout.ensure_named_workspace(&WorkspaceConfig {
+                    name: WorkspaceName(format!("ws{ws_name}")),
+                    open_on_output: output_name.map(|name| format!("output{name}")),
+                    layout: Default::default(),
+                });
+            }
+            Op::AddNamedWorkspaceOnDemand {
+                ws_name,
+                output_name,
+            } => {
+                layout.ensure_named_workspace_on_demand(&WorkspaceConfig {
+                    name: WorkspaceName(format!("ws{ws_name}")),
+                    open_on_output: output_name.map(|name| format!("output{name}")),
+                    layout: Default::default(),
+                });
+            }
+            Op::UnnameWorkspace { ws_name } => {
+                layout.unname_workspace(&format!("ws{ws_name}"));
+            }
+            Op::MoveNamedWorkspaceToOutput {
+                ws_name,
+                output_idx: id,
+            } => {
+                let name = format!("output{id}");
+                let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
+                    return;
+                };
+
+                layout.move_named_workspace_to_output(&format!("ws{ws_name}"), &output);
+            }
+            Op::AddWindow {
+                id,
+                bbox,
+                min_max_size,
+            } => {
+                match &mut layout.monitor_set {
+                    MonitorSet::Normal { monitors, .. } => {
+                        for mon in monitors {
+                            for ws in &mut mon.workspaces {
+                                for win in ws.windows() {
+                                    if win.0.id == id {
+                                        return;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    MonitorSet::NoOutputs { workspaces, .. } => {
+                        for ws in workspaces {
+                            for win in ws.windows() {
+                                if win.0.id == id {
+                                    return;
+                                }
+                            }
+                        }
+                    }
+                }
+
+                let win = TestWindow::new(id, bbox, min_max_size.0, min_max_size.1);
+                layout.add_window(win, None, false);
+            }
+            Op::AddWindowRightOf {
+                id,
+                right_of_id,
+                bbox,
+                min_max_size,
+            } => {
+                let mut found_right_of = false;
+
+                match &mut layout.monitor_set {
+                    MonitorSet::Normal { monitors, .. } => {
+                        for mon in monitors {
+                            for ws in &mut mon.workspaces {
+                                for win in ws.windows() {
+                                    if win.0.id == id {
+                                        return;
+                                    }
+
+                                    if win.0.id == right_of_id {
+                                        found_right_of = true;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    MonitorSet::NoOutputs { workspaces, .. } => {
+                        for ws in workspaces {
+                            for win in ws.windows() {
+                                if win.0.id == id {
+                                    return;
+                                }
+
+                                if win.0.id == right_of_id {
+                                    found_right_of = true;
+                                }
+                            }
+                        }
+                    }
+                }
+
+                if !found_right_of {
+                    return;
+                }
+
+                let win = TestWindow::new(id, bbox, min_max_size.0, min_max_size.1);
+                layout.add_window_right_of(&right_of_id, win, None, false);
+            }
+            Op::AddWindowToColumnOf {
+                id,
+                column_of_id,
+                bbox,
+                min_max_size,
+            } => {
+                if layout.has_window(&id) || !layout.has_window(&column_of_id) {
+                    return;
+                }
+
+                let win = TestWindow::new(id, bbox, min_max_size.0, min_max_size.1);
+                layout.add_window_to_column(&column_of_id, win);
+            }
+            Op::AddFloatingWindow {
+                id,
+                parent_id,
+                bbox,
+            } => {
+                if layout.has_window(&id) {
+                    return;
+                }
+
+                let parent_id = parent_id.filter(|parent_id| layout.has_window(parent_id));
+                let win = TestWindow::new(id, bbox, Size::default(), Size::default());
+                layout.add_floating_window(win, parent_id.as_ref(), None);
+            }
+            Op::AddWindowToNamedWorkspace {
+                id,
+                ws_name,
+                bbox,
+                min_max_size,
+                focus,
+            } => {
+                let ws_name = format!("ws{ws_name}");
+                let mut found_workspace = false;
+
+                match &mut layout.monitor_set {
+                    MonitorSet::Normal { monitors, .. } => {
+                        for mon in monitors {
+                            for ws in &mut mon.workspaces {
+                                for win in ws.windows() {
+                                    if win.0.id == id {
+                                        return;
+                                    }
+                                }
+
+                                if ws
+                                    .name
+                                    .as_ref()
+                                    .map_or(false, |name| name.eq_ignore_ascii_case(&ws_name))
+                                {
+                                    found_workspace = true;
+                                }
+                            }
+                        }
+                    }
+                    MonitorSet::NoOutputs { workspaces, .. } => {
+                        for ws in workspaces {
+                            for win in ws.windows() {
+                                if win.0.id == id {
+                                    return;
+                                }
+                            }
+
+                            if ws
+                                .name
+                                .as_ref()
+                                .map_or(false, |name| name.eq_ignore_ascii_case(&ws_name))
+                            {
+                                found_workspace = true;
+                            }
+                        }
+                    }
+                }
+
+                if !found_workspace {
+                    return;
+                }
+
+                let win = TestWindow::new(id, bbox, min_max_size.0, min_max_size.1);
+                layout.add_window_to_named_workspace(&ws_name, win, None, false, focus);
+            }
+            Op::CloseWindow(id) => {
+                layout.remove_window(&id);
+            }
+            Op::FullscreenWindow(id) => {
+                layout.toggle_fullscreen(&id);
+            }
+            Op::SetFullscreenWindow {
+                window,
+                is_fullscreen,
+            } => {
+                layout.set_fullscreen(&window, is_fullscreen);
+            }
+            Op::FocusColumnLeft => layout.focus_left(),
+            Op::FocusColumnRight => layout.focus_right(),
+            Op::FocusColumnFirst => layout.focus_column_first(),
+            Op::FocusColumn(index) => layout.focus_column(index),
+            Op::FocusColumnLast => layout.focus_column_last(),
+            Op::FocusColumnRightOrFirst => layout.focus_column_right_or_first(),
+            Op::FocusColumnLeftOrLast => layout.focus_column_left_or_last(),
+            Op::FocusColumnOrMonitorLeft(id) => {
+                let name = format!("output{id}");
+                let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
+                    return;
+                };
+
+                layout.focus_column_left_or_output(&output);
+            }
+            Op::FocusColumnOrMonitorRight(id) => {
+                let name = format!("output{id}");
+                let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
+                    return;
+                };
+
+                layout.focus_column_right_or_output(&output);
+            }
+            Op::FocusWindowDown => layout.focus_down(),
+            Op::FocusWindowUp => layout.focus_up(),
+            Op::FocusWindowDownOrColumnLeft => layout.focus_down_or_left(),
+            Op::FocusWindowDownOrColumnRight => layout.focus_down_or_right(),
+            Op::FocusWindowUpOrColumnLeft => layout.focus_up_or_left(),
+            Op::FocusWindowUpOrColumnRight => layout.focus_up_or_right(),
+            Op::FocusWindowOrWorkspaceDown => layout.focus_window_or_workspace_down(),
+            Op::FocusWindowOrWorkspaceUp => layout.focus_window_or_workspace_up(),
+            Op::MoveColumnLeft => layout.move_left(),
+            Op::MoveColumnRight => layout.move_right(),
+            Op::MoveColumnToFirst => layout.move_column_to_first(),
+            Op::MoveColumnToIndex(index) => layout.move_column_to_index(index),
+            Op::MoveColumnToLast => layout.move_column_to_last(),
+            Op::MoveWindowDown => layout.move_down(),
+            Op::MoveWindowUp => layout.move_up(),
+            Op::MoveWindowDownOrToWorkspaceDown => layout.move_down_or_to_workspace_down(),
+            Op::MoveWindowUpOrToWorkspaceUp => layout.move_up_or_to_workspace_up(),
+            Op::ConsumeOrExpelWindowLeft => layout.consume_or_expel_window_left(),
+            Op::ConsumeOrExpelWindowRight => layout.consume_or_expel_window_right(),
+            Op::ConsumeWindowIntoColumn => layout.consume_into_column(),
+            Op::ExpelWindowFromColumn => layout.expel_from_column(),
+            Op::CenterColumn => layout.center_column(),
+            Op::FocusWorkspaceDown => layout.switch_workspace_down(),
+            Op::FocusWorkspaceUp => layout.switch_workspace_up(),
+            Op::FocusWorkspace(idx) => layout.switch_workspace(idx),
+            Op::FocusWorkspaceAutoBackAndForth(idx) => {
+                layout.switch_workspace_auto_back_and_forth(idx)
+            }
+            Op::FocusWorkspacePrevious => layout.switch_workspace_previous(),
+            Op::MoveWindowToWorkspaceDown => layout.move_to_workspace_down(),
+            Op::MoveWindowToWorkspaceUp => layout.move_to_workspace_up(),
+            Op::MoveWindowToWorkspace(idx) => layout.move_to_workspace(idx),
+            Op::MoveColumnToWorkspaceDown => layout.move_column_to_workspace_down(),
+            Op::MoveColumnToWorkspaceUp => layout.move_column_to_workspace_up(),
+            Op::MoveColumnToWorkspace(idx) => layout.move_column_to_workspace(idx),
+            Op::MoveWindowToOutput(id) => {
+                let name = format!("output{id}");
+                let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
+                    return;
+                };
+
+                layout.move_to_output(&output);
+            }
+            Op::MoveColumnToOutput(id) => {
+                let name = format!("output{id}");
+                let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
+                    return;
+                };
+
+                layout.move_column_to_output(&output);
+            }
+            Op::GatherWindows(a, b) => layout.gather_windows(&[a, b]),
+            Op::SwapWindows(a, b) => layout.swap_windows(&a, &b),
+            Op::MoveWorkspaceDown => layout.move_workspace_down(),
+            Op::MoveWorkspaceUp => layout.move_workspace_up(),
+            Op::SwitchPresetColumnWidth => layout.toggle_width(),
+            Op::MaximizeColumn => layout.toggle_full_width(),
+            Op::ToggleAutoTile => layout.toggle_auto_tile(),
+            Op::ToggleColumnAlwaysCenter => layout.toggle_column_always_center(),
+            Op::SetColumnWidth(change) => layout.set_column_width(change),
+            Op::ToggleColumnWidth(normal, toggled) => layout.toggle_column_width(normal, toggled),
+            Op::SetWindowHeight(change) => layout.set_window_height(change),
+            Op::ResetWindowHeight => layout.reset_window_height(),
+            Op::SetWindowOpacity(change) => layout.set_window_opacity(change),
+            Op::ResetWindowOpacity => layout.reset_window_opacity(),
+            Op::SetWorkspaceGaps(change) => layout.set_workspace_gaps(change),
+            Op::SetWorkspaceDefaultColumnWidth(change) => {
+                layout.set_workspace_default_column_width(change)
+            }
+            Op::SetWorkspaceCenterFocusedColumn(center) => {
+                layout.set_workspace_center_focused_column(center)
+            }
+            Op::SetWorkspaceZoom(zoom) => layout.set_workspace_zoom(zoom),
+            Op::ResetWorkspaceLayout => layout.reset_workspace_layout(),
+            Op::SetLayoutStrategy(name) => layout.set_layout_strategy(name),
+            Op::Communicate(id) => {
+                let mut update = false;
+                match &mut layout.monitor_set {
+                    MonitorSet::Normal { monitors, .. } => {
+                        'outer: for mon in monitors {
+                            for ws in &mut mon.workspaces {
+                                for win in ws.windows() {
+                                    if win.0.id == id {
+                                        if win.communicate() {
+                                            update = true;
+                                        }
+                                        break 'outer;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    MonitorSet::NoOutputs { workspaces, .. } => {
+                        'outer: for ws in workspaces {
+                            for win in ws.windows() {
+                                if win.0.id == id {
+                                    if win.communicate() {
+                                        update = true;
+                                    }
+                                    break 'outer;
+                                }
+                            }
+                        }
+                    }
+                }
+
+                if update {
+                    // FIXME: serial.
+                    layout.update_window(&id, None);
+                }
+            }
+            Op::MoveWorkspaceToOutput(id) => {
+                let name = format!("output{id}");
+                let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
+                    return;
+                };
+
+                layout.move_workspace_to_output(&output);
+            }
+            Op::ViewOffsetGestureBegin {
+                output_idx: id,
+                is_touchpad: normalize,
+            } => {
+                let name = format!("output{id}");
+                let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
+                    return;
+                };
+
+                layout.view_offset_gesture_begin(&output, normalize);
+            }
+            Op::ViewOffsetGestureUpdate {
+                delta,
+                timestamp,
+                is_touchpad,
+            } => {
+                layout.view_offset_gesture_update(delta, timestamp, is_touchpad);
+            }
+            Op::ViewOffsetGestureEnd { is_touchpad } => {
+                // We don't handle cancels in this gesture.
+                layout.view_offset_gesture_end(false, is_touchpad);
+            }
+            Op::WorkspaceSwitchGestureBegin {
+                output_idx: id,
+                is_touchpad,
+            } => {
+                let name = format!("output{id}");
+                let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
+                    return;
+                };
+
+                layout.workspace_switch_gesture_begin(&output, is_touchpad);
+            }
+            Op::WorkspaceSwitchGestureUpdate {
+                delta,
+                timestamp,
+                is_touchpad,
+            } => {
+                layout.workspace_switch_gesture_update(delta, timestamp, is_touchpad);
+            }
+            Op::WorkspaceSwitchGestureEnd {
+                cancelled,
+                is_touchpad,
+            } => {
+                layout.workspace_switch_gesture_end(cancelled, is_touchpad);
+            }
+            Op::InteractiveResizeBegin { window, edges } => {
+                layout.interactive_resize_begin(window, edges);
+            }
+            Op::InteractiveResizeUpdate { window, dx, dy } => {
+                layout.interactive_resize_update(&window, Point::from((dx, dy)));
+            }
+            Op::InteractiveResizeEnd { window } => {
+                layout.interactive_resize_end(&window);
+            }
+            Op::InteractiveMoveBegin {
+                window,
+                output_idx: id,
+            } => {
+                let name = format!("output{id}");
+                let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
+                    return;
+                };
+
+                layout.interactive_move_begin(window, &output);
+            }
+            Op::InteractiveMoveUpdate {
+                window,
+                output_idx: id,
+                x,
+                y,
+            } => {
+                let name = format!("output{id}");
+                let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
+                    return;
+                };
+
+                layout.interactive_move_update(&window, &output, Point::from((x, y)));
+            }
+            Op::InteractiveMoveSwitchAtEdge { output_idx: id } => {
+                let name = format!("output{id}");
+                let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
+                    return;
+                };
+
+                layout.interactive_move_switch_at_edge(&output, get_monotonic_time());
+            }
+            Op::InteractiveMoveEnd { output_idx: id } => {
+                let name = format!("output{id}");
+                let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
+                    return;
+                };
+
+                layout.interactive_move_end(&output);
+            }
+        }
+    }
+}
+
+impl Layout<TestWindow> {
+    /// Applies an operation to the layout.
+    pub fn handle_op(&mut self, op: Op) {
+        op.apply(self);
+    }
+}
+
+/// Stream of operations along with the clock times they happened at.
+///
+/// This is meant for input that depends on timing, like the events of a touchpad gesture. The
+/// timestamps inside the operations are used as they are, so they should normally match the times
+/// they are recorded at.
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    events: Vec<(Duration, Op)>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records an operation happening at the given clock time.
+    pub fn push(&mut self, time: Duration, op: Op) {
+        if let Some((last, _)) = self.events.last() {
+            assert!(*last <= time, "events must be recorded in order");
+        }
+
+        self.events.push((time, op));
+    }
+
+    pub fn events(&self) -> &[(Duration, Op)] {
+        &self.events
+    }
+
+    /// Replays the recorded operations on the layout.
+    ///
+    /// Before every operation, the clock is moved to its time and the layout animations are
+    /// advanced to match, like the compositor does when an input event arrives.
+    pub fn replay(&self, layout: &mut Layout<TestWindow>, clock: &ManualClock) {
+        for &(time, op) in &self.events {
+            clock.set(time);
+            layout.advance_animations(time);
+            op.apply(layout);
+        }
+    }
+}
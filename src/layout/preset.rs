@@ -0,0 +1,351 @@
+use serde::{Deserialize, Serialize};
+
+use super::workspace::{ColumnWidth, Workspace};
+use super::LayoutElement;
+
+/// Snapshot of the column structure of a workspace.
+///
+/// Windows are stored as app id placeholders. When a preset is loaded, newly opened windows with
+/// matching app ids fill the placeholders, recreating the columns.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayoutPreset {
+    columns: Vec<PresetColumn>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct PresetColumn {
+    width: StoredWidth,
+    is_full_width: bool,
+    app_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum StoredWidth {
+    Proportion(f64),
+    Preset(usize),
+    Fixed(f64),
+}
+
+/// Loaded preset waiting for windows to fill its placeholders.
+#[derive(Debug)]
+pub struct PendingPreset<Id> {
+    columns: Vec<PendingColumn<Id>>,
+    /// Window that was focused when the preset was loaded.
+    ///
+    /// The preset columns go to the right of its column, in preset order.
+    anchor: Option<Id>,
+}
+
+#[derive(Debug)]
+struct PendingColumn<Id> {
+    width: ColumnWidth,
+    is_full_width: bool,
+    /// App ids of the placeholders that are still free.
+    app_ids: Vec<String>,
+    /// Windows that have filled placeholders of this column.
+    windows: Vec<Id>,
+}
+
+/// Where to put a window that filled a preset placeholder.
+#[derive(Debug, PartialEq)]
+pub enum PresetPlacement<Id> {
+    /// Into a new column in the usual place.
+    NewColumn {
+        width: ColumnWidth,
+        is_full_width: bool,
+    },
+    /// Into a new column right of the column of this window.
+    RightOf {
+        window: Id,
+        width: ColumnWidth,
+        is_full_width: bool,
+    },
+    /// Into a new column left of the column of this window.
+    LeftOf {
+        window: Id,
+        width: ColumnWidth,
+        is_full_width: bool,
+    },
+    /// Into the column of this window.
+    IntoColumn(Id),
+}
+
+impl From<ColumnWidth> for StoredWidth {
+    fn from(width: ColumnWidth) -> Self {
+        match width {
+            ColumnWidth::Proportion(prop) => Self::Proportion(prop),
+            ColumnWidth::Preset(idx) => Self::Preset(idx),
+            ColumnWidth::Fixed(fixed) => Self::Fixed(fixed),
+        }
+    }
+}
+
+impl From<StoredWidth> for ColumnWidth {
+    fn from(width: StoredWidth) -> Self {
+        match width {
+            StoredWidth::Proportion(prop) => Self::Proportion(prop),
+            StoredWidth::Preset(idx) => Self::Preset(idx),
+            StoredWidth::Fixed(fixed) => Self::Fixed(fixed),
+        }
+    }
+}
+
+impl LayoutPreset {
+    /// Takes a snapshot of the columns of this workspace.
+    ///
+    /// Windows without an app id can't be matched later, so they are left out.
+    pub fn from_workspace<W: LayoutElement>(
+        workspace: &Workspace<W>,
+        app_id: impl Fn(&W) -> Option<String>,
+    ) -> Self {
+        let columns = workspace
+            .columns
+            .iter()
+            .map(|col| PresetColumn {
+                width: col.width.into(),
+                is_full_width: col.is_full_width,
+                app_ids: col
+                    .tiles
+                    .iter()
+                    .filter_map(|tile| app_id(tile.window()))
+                    .collect(),
+            })
+            .filter(|col| !col.app_ids.is_empty())
+            .collect();
+
+        Self { columns }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+}
+
+impl<Id: Clone> PendingPreset<Id> {
+    /// Creates a pending preset whose columns go right of the column of `anchor`.
+    pub fn new(preset: &LayoutPreset, anchor: Option<Id>) -> Self {
+        let columns = preset
+            .columns
+            .iter()
+            .map(|col| PendingColumn {
+                width: col.width.into(),
+                is_full_width: col.is_full_width,
+                app_ids: col.app_ids.clone(),
+                windows: vec![],
+            })
+            .collect();
+
+        Self { columns, anchor }
+    }
+
+    /// Returns whether every placeholder has been filled.
+    pub fn is_done(&self) -> bool {
+        self.columns.iter().all(|col| col.app_ids.is_empty())
+    }
+
+    /// Fills the first free placeholder matching this app id with the window.
+    ///
+    /// The new column goes into its preset slot: right of the closest filled column before it,
+    /// right of the anchor if there's none, or left of the closest filled column after it, so
+    /// windows that open out of order still end up in preset order.
+    ///
+    /// `is_alive` tells whether a window that filled a placeholder earlier is still in the layout,
+    /// so that closed windows aren't used as placement anchors.
+    pub fn place(
+        &mut self,
+        app_id: &str,
+        window: Id,
+        is_alive: impl Fn(&Id) -> bool,
+    ) -> Option<PresetPlacement<Id>> {
+        for col in &mut self.columns {
+            col.windows.retain(&is_alive);
+        }
+        if !self.anchor.as_ref().is_some_and(&is_alive) {
+            self.anchor = None;
+        }
+
+        let col_idx = self
+            .columns
+            .iter()
+            .position(|col| col.app_ids.iter().any(|id| id == app_id))?;
+
+        let col = &mut self.columns[col_idx];
+        let slot_idx = col.app_ids.iter().position(|id| id == app_id).unwrap();
+        col.app_ids.remove(slot_idx);
+
+        let placement = if let Some(first) = col.windows.first() {
+            PresetPlacement::IntoColumn(first.clone())
+        } else {
+            let before = self.columns[..col_idx]
+                .iter()
+                .rev()
+                .find_map(|col| col.windows.first().cloned());
+            let after = self.columns[col_idx + 1..]
+                .iter()
+                .find_map(|col| col.windows.first().cloned());
+
+            let col = &self.columns[col_idx];
+            let (width, is_full_width) = (col.width, col.is_full_width);
+            match (before.or_else(|| self.anchor.clone()), after) {
+                (Some(window), _) => PresetPlacement::RightOf {
+                    window,
+                    width,
+                    is_full_width,
+                },
+                (None, Some(window)) => PresetPlacement::LeftOf {
+                    window,
+                    width,
+                    is_full_width,
+                },
+                (None, None) => PresetPlacement::NewColumn {
+                    width,
+                    is_full_width,
+                },
+            }
+        };
+
+        self.columns[col_idx].windows.push(window);
+        Some(placement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preset() -> LayoutPreset {
+        LayoutPreset {
+            columns: vec![
+                PresetColumn {
+                    width: StoredWidth::Proportion(0.5),
+                    is_full_width: false,
+                    app_ids: vec![String::from("editor")],
+                },
+                PresetColumn {
+                    width: StoredWidth::Proportion(0.25),
+                    is_full_width: false,
+                    app_ids: vec![String::from("terminal"), String::from("terminal")],
+                },
+                PresetColumn {
+                    width: StoredWidth::Fixed(400.),
+                    is_full_width: false,
+                    app_ids: vec![String::from("browser")],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn fills_placeholders_in_order() {
+        let mut pending = PendingPreset::new(&preset(), None);
+
+        assert_eq!(
+            pending.place("editor", 1, |_| true),
+            Some(PresetPlacement::NewColumn {
+                width: ColumnWidth::Proportion(0.5),
+                is_full_width: false,
+            })
+        );
+        assert_eq!(
+            pending.place("terminal", 2, |_| true),
+            Some(PresetPlacement::RightOf {
+                window: 1,
+                width: ColumnWidth::Proportion(0.25),
+                is_full_width: false,
+            })
+        );
+        assert_eq!(
+            pending.place("terminal", 3, |_| true),
+            Some(PresetPlacement::IntoColumn(2))
+        );
+        assert_eq!(pending.place("mail", 4, |_| true), None);
+        assert!(!pending.is_done());
+
+        assert_eq!(
+            pending.place("browser", 5, |_| true),
+            Some(PresetPlacement::RightOf {
+                window: 2,
+                width: ColumnWidth::Fixed(400.),
+                is_full_width: false,
+            })
+        );
+        assert!(pending.is_done());
+        assert_eq!(pending.place("terminal", 6, |_| true), None);
+    }
+
+    #[test]
+    fn fills_placeholders_out_of_order_into_their_slots() {
+        let mut pending = PendingPreset::new(&preset(), None);
+
+        assert_eq!(
+            pending.place("browser", 1, |_| true),
+            Some(PresetPlacement::NewColumn {
+                width: ColumnWidth::Fixed(400.),
+                is_full_width: false,
+            })
+        );
+        assert_eq!(
+            pending.place("terminal", 2, |_| true),
+            Some(PresetPlacement::LeftOf {
+                window: 1,
+                width: ColumnWidth::Proportion(0.25),
+                is_full_width: false,
+            })
+        );
+        assert_eq!(
+            pending.place("editor", 3, |_| true),
+            Some(PresetPlacement::LeftOf {
+                window: 2,
+                width: ColumnWidth::Proportion(0.5),
+                is_full_width: false,
+            })
+        );
+    }
+
+    #[test]
+    fn places_columns_right_of_the_anchor() {
+        let mut pending = PendingPreset::new(&preset(), Some(0));
+
+        // Without filled columns before it, the slot is right after the anchor.
+        for (app_id, id) in [("terminal", 1), ("editor", 2)] {
+            assert!(matches!(
+                pending.place(app_id, id, |_| true),
+                Some(PresetPlacement::RightOf { window: 0, .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn parse_stored_preset() {
+        let stored: LayoutPreset = serde_json::from_str(
+            r#"{"columns":[{"width":{"preset":1},"is_full_width":true,"app_ids":["editor"]}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            stored,
+            LayoutPreset {
+                columns: vec![PresetColumn {
+                    width: StoredWidth::Preset(1),
+                    is_full_width: true,
+                    app_ids: vec![String::from("editor")],
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn closed_windows_are_not_anchors() {
+        let mut pending = PendingPreset::new(&preset(), Some(0));
+
+        pending.place("editor", 1, |_| true);
+        assert_eq!(
+            pending.place("terminal", 2, |id| *id != 0 && *id != 1),
+            Some(PresetPlacement::NewColumn {
+                width: ColumnWidth::Proportion(0.25),
+                is_full_width: false,
+            })
+        );
+    }
+}
@@ -0,0 +1,38 @@
+use smithay::utils::{Logical, Point, Rectangle};
+
+/// Resolved geometry of the layout on one output, taken once per frame.
+///
+/// Hit-testing uses the snapshot of the last frame so that it matches what is on screen, rather
+/// than the live layout which may have moved on since.
+#[derive(Debug, Clone)]
+pub struct LayoutSnapshot<Id> {
+    /// Whether the layout is drawn above the top layer-shell layer.
+    pub render_above_top_layer: bool,
+    /// Visible tiles, workspace by workspace in column order.
+    pub tiles: Vec<TileSnapshot<Id>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TileSnapshot<Id> {
+    pub id: Id,
+    /// Tile geometry relative to the output, including the border.
+    pub tile: Rectangle<f64, Logical>,
+    /// Window geometry relative to the output.
+    pub window: Rectangle<f64, Logical>,
+    /// Whether this is the active window of the active workspace.
+    pub is_active: bool,
+    pub is_fullscreen: bool,
+}
+
+impl<Id: PartialEq> LayoutSnapshot<Id> {
+    /// Returns the tile containing the position within the output.
+    pub fn tile_under(&self, pos_within_output: Point<f64, Logical>) -> Option<&TileSnapshot<Id>> {
+        self.tiles
+            .iter()
+            .find(|tile| tile.tile.contains(pos_within_output))
+    }
+
+    pub fn tile(&self, id: &Id) -> Option<&TileSnapshot<Id>> {
+        self.tiles.iter().find(|tile| tile.id == *id)
+    }
+}
@@ -0,0 +1,108 @@
+use std::fmt;
+
+use niri_ipc::LayoutStrategyName;
+
+use super::workspace::ColumnWidth;
+
+/// Column that a layout strategy wants to exist.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColumnShape {
+    /// Number of windows in the column.
+    pub tiles: usize,
+    pub width: ColumnWidth,
+}
+
+/// Way of arranging the windows of a workspace into columns.
+///
+/// Strategies only decide the shape of the columns. The workspace moves windows between columns to
+/// match the shape, keeping the window order, whenever windows are added or removed. Until then, the
+/// user can freely resize the columns and move windows between them.
+pub trait LayoutStrategy: fmt::Debug {
+    /// Returns the columns that this many windows should be arranged into.
+    ///
+    /// `None` means that the user arranges the windows manually. Otherwise, the tile counts of the
+    /// returned columns must add up to `window_count`.
+    fn arrange(&self, window_count: usize) -> Option<Vec<ColumnShape>>;
+}
+
+/// The default scrolling layout where the user arranges columns manually.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scrolling;
+
+/// One master window on the left with all other windows stacked in a column on the right.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MasterStack {
+    /// Proportion of the view width taken by the master column.
+    pub master_proportion: f64,
+}
+
+impl Default for MasterStack {
+    fn default() -> Self {
+        Self {
+            master_proportion: 0.5,
+        }
+    }
+}
+
+impl LayoutStrategy for Scrolling {
+    fn arrange(&self, _window_count: usize) -> Option<Vec<ColumnShape>> {
+        None
+    }
+}
+
+impl LayoutStrategy for MasterStack {
+    fn arrange(&self, window_count: usize) -> Option<Vec<ColumnShape>> {
+        let columns = match window_count {
+            0 => vec![],
+            1 => vec![ColumnShape {
+                tiles: 1,
+                width: ColumnWidth::Proportion(1.),
+            }],
+            n => vec![
+                ColumnShape {
+                    tiles: 1,
+                    width: ColumnWidth::Proportion(self.master_proportion),
+                },
+                ColumnShape {
+                    tiles: n - 1,
+                    width: ColumnWidth::Proportion(1. - self.master_proportion),
+                },
+            ],
+        };
+
+        Some(columns)
+    }
+}
+
+pub fn strategy_from_name(name: LayoutStrategyName) -> Box<dyn LayoutStrategy> {
+    match name {
+        LayoutStrategyName::Scrolling => Box::new(Scrolling),
+        LayoutStrategyName::MasterStack => Box::new(MasterStack::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn master_stack_shapes() {
+        let strategy = MasterStack::default();
+
+        assert_eq!(strategy.arrange(0), Some(vec![]));
+        assert_eq!(
+            strategy.arrange(1),
+            Some(vec![ColumnShape {
+                tiles: 1,
+                width: ColumnWidth::Proportion(1.),
+            }])
+        );
+
+        let shape = strategy.arrange(4).unwrap();
+        assert_eq!(
+            shape.iter().map(|col| col.tiles).collect::<Vec<_>>(),
+            [1, 3]
+        );
+        assert_eq!(shape[0].width, ColumnWidth::Proportion(0.5));
+    }
+}
@@ -0,0 +1,128 @@
+use niri_config::TabIndicatorPosition;
+use smithay::backend::renderer::element::Kind;
+use smithay::utils::{Logical, Point, Rectangle, Size};
+
+use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
+use crate::utils::Transpose;
+
+/// Indicator of the windows in a column, drawn next to the column.
+///
+/// The indicator has one segment per tile, lined up with the tile, and highlights the active one.
+#[derive(Debug)]
+pub struct TabIndicator {
+    buffers: Vec<SolidColorBuffer>,
+    locations: Vec<Point<f64, Logical>>,
+    enabled: bool,
+    /// Whether the column is in a transposed layout.
+    ///
+    /// The locations are in the layout coordinates, while the buffers have the output size.
+    transposed: bool,
+    config: niri_config::TabIndicator,
+}
+
+impl TabIndicator {
+    pub fn new(config: niri_config::TabIndicator) -> Self {
+        Self {
+            buffers: vec![],
+            locations: vec![],
+            enabled: false,
+            transposed: false,
+            config,
+        }
+    }
+
+    pub fn update_config(&mut self, config: niri_config::TabIndicator) {
+        self.config = config;
+    }
+
+    /// Updates the indicator segments.
+    ///
+    /// `tiles` contains the vertical offset and height of every tile in the column, relative to
+    /// the column.
+    pub fn update_render_elements(
+        &mut self,
+        enabled: bool,
+        column_width: f64,
+        tiles: impl Iterator<Item = (f64, f64)>,
+        active_idx: usize,
+        transposed: bool,
+    ) {
+        self.enabled = enabled && !self.config.off;
+        self.transposed = transposed;
+        if !self.enabled {
+            return;
+        }
+
+        let width = self.config.width.0;
+        let gap = self.config.gap.0;
+        let x = match self.config.position {
+            TabIndicatorPosition::Left => -gap - width,
+            TabIndicatorPosition::Right => column_width + gap,
+        };
+
+        let mut count = 0;
+        for (idx, (y, height)) in tiles.enumerate() {
+            if self.buffers.len() <= idx {
+                self.buffers.push(SolidColorBuffer::default());
+                self.locations.push(Point::default());
+            }
+
+            let color = if idx == active_idx {
+                self.config.active_color
+            } else {
+                self.config.inactive_color
+            };
+
+            let size = Size::from((width, height));
+            let size = if transposed { size.transpose() } else { size };
+            self.buffers[idx].update(size, color.into());
+            self.locations[idx] = Point::from((x, y));
+            count += 1;
+        }
+
+        self.buffers.truncate(count);
+        self.locations.truncate(count);
+    }
+
+    pub fn render(
+        &self,
+        location: Point<f64, Logical>,
+    ) -> impl Iterator<Item = SolidColorRenderElement> + '_ {
+        let segments = if self.enabled { &self.buffers[..] } else { &[] };
+
+        segments.iter().zip(&self.locations).map(move |(buf, loc)| {
+            let loc = location + *loc;
+            let loc = if self.transposed {
+                loc.transpose()
+            } else {
+                loc
+            };
+            SolidColorRenderElement::from_buffer(buf, loc, 1., Kind::Unspecified)
+        })
+    }
+
+    /// Returns the index of the tile whose segment is under `pos`, relative to the column.
+    pub fn hit(&self, pos: Point<f64, Logical>) -> Option<usize> {
+        if !self.enabled {
+            return None;
+        }
+
+        // Accept clicks across the whole gap next to the segment, it is quite thin otherwise.
+        let gap = self.config.gap.0;
+        self.buffers
+            .iter()
+            .zip(&self.locations)
+            .position(|(buf, loc)| {
+                let size = buf.size();
+                let size = if self.transposed {
+                    size.transpose()
+                } else {
+                    size
+                };
+                let mut rect = Rectangle::from_loc_and_size(*loc, size);
+                rect.loc.x -= gap;
+                rect.size.w += gap * 2.;
+                rect.contains(pos)
+            })
+    }
+}
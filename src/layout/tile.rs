@@ -1,8 +1,12 @@
+use std::cmp::min;
+use std::mem;
 use std::rc::Rc;
 use std::time::Duration;
 
-use niri_config::CornerRadius;
+use niri_config::{CornerRadius, OversizedWindow};
+use niri_ipc::OpacityChange;
 use smithay::backend::allocator::Fourcc;
+use smithay::backend::renderer::element::utils::RescaleRenderElement;
 use smithay::backend::renderer::element::{Element, Kind};
 use smithay::backend::renderer::gles::GlesRenderer;
 use smithay::utils::{Logical, Point, Rectangle, Scale, Size, Transform};
@@ -23,8 +27,12 @@ use crate::render_helpers::resize::ResizeRenderElement;
 use crate::render_helpers::snapshot::RenderSnapshot;
 use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
 use crate::render_helpers::{render_to_encompassing_texture, RenderTarget};
+use crate::utils::transaction::Transaction;
 
 /// Toplevel window with decorations.
+///
+/// The tile takes and returns geometry in the coordinates of the layout, which has its axes
+/// swapped when it is transposed. Only the rendering happens in the output coordinates.
 #[derive(Debug)]
 pub struct Tile<W: LayoutElement> {
     /// The toplevel window itself.
@@ -51,6 +59,18 @@ pub struct Tile<W: LayoutElement> {
     /// The size we were requested to fullscreen into.
     fullscreen_size: Size<f64, Logical>,
 
+    /// The black overlay darkening unfocused windows.
+    dim_buffer: SolidColorBuffer,
+
+    /// How much the window should be darkened, from 0 to 1.
+    dim: f64,
+
+    /// The animation of the window dimming or undimming.
+    dim_animation: Option<Animation>,
+
+    /// Opacity set at runtime, overriding the one from the window rules.
+    opacity_override: Option<f64>,
+
     /// The animation upon opening a window.
     open_animation: Option<OpenAnimation>,
 
@@ -85,6 +105,7 @@ niri_render_elements! {
         Resize = ResizeRenderElement,
         Border = BorderRenderElement,
         ClippedSurface = ClippedSurfaceRenderElement<R>,
+        Rescaled = RescaleRenderElement<LayoutElementRenderElement<R>>,
         ExtraDamage = ExtraDamage,
     }
 }
@@ -118,6 +139,10 @@ impl<W: LayoutElement> Tile<W> {
             is_fullscreen: false, // FIXME: up-to-date fullscreen right away, but we need size.
             fullscreen_backdrop: SolidColorBuffer::new((0., 0.), [0., 0., 0., 1.]),
             fullscreen_size: Default::default(),
+            dim_buffer: SolidColorBuffer::new((0., 0.), [0., 0., 0., 1.]),
+            dim: 0.,
+            dim_animation: None,
+            opacity_override: None,
             open_animation: None,
             resize_animation: None,
             move_x_animation: None,
@@ -194,7 +219,7 @@ impl<W: LayoutElement> Tile<W> {
             .resolve_against(self.options.focus_ring.into());
         self.focus_ring.update_config(focus_ring_config.into());
 
-        let window_size = self.window_size();
+        let window_size = self.window_size_on_screen();
         let radius = rules
             .geometry_corner_radius
             .unwrap_or_default()
@@ -230,6 +255,13 @@ impl<W: LayoutElement> Tile<W> {
                 self.move_y_animation = None;
             }
         }
+
+        if let Some(anim) = &mut self.dim_animation {
+            anim.set_current_time(current_time);
+            if anim.is_done() {
+                self.dim_animation = None;
+            }
+        }
     }
 
     pub fn are_animations_ongoing(&self) -> bool {
@@ -237,9 +269,59 @@ impl<W: LayoutElement> Tile<W> {
             || self.resize_animation.is_some()
             || self.move_x_animation.is_some()
             || self.move_y_animation.is_some()
+            || self.dim_animation.is_some()
+    }
+
+    /// Sets how much the window should be darkened, animating the change.
+    pub fn set_dim(&mut self, dim: f64) {
+        if self.dim == dim {
+            return;
+        }
+
+        let from = self.dim_value();
+        self.dim = dim;
+        self.dim_animation = Some(Animation::new(
+            from,
+            dim,
+            0.,
+            self.options.animations.window_dim.0,
+        ));
+    }
+
+    /// Returns the opacity to draw the window with outside of fullscreen.
+    fn opacity(&self) -> f64 {
+        self.opacity_override
+            .or_else(|| self.window.rules().opacity.map(f64::from))
+            .unwrap_or(1.)
+            .clamp(0., 1.)
+    }
+
+    pub fn set_opacity(&mut self, change: OpacityChange) {
+        let opacity = match change {
+            OpacityChange::SetOpacity(opacity) => opacity,
+            OpacityChange::AdjustOpacity(delta) => self.opacity() + delta,
+        };
+
+        if !opacity.is_finite() {
+            return;
+        }
+
+        self.opacity_override = Some(opacity.clamp(0., 1.));
+    }
+
+    pub fn reset_opacity(&mut self) {
+        self.opacity_override = None;
+    }
+
+    fn dim_value(&self) -> f64 {
+        self.dim_animation
+            .as_ref()
+            .map_or(self.dim, |anim| anim.value())
+            .clamp(0., 1.)
     }
 
     pub fn update(&mut self, is_active: bool, view_rect: Rectangle<f64, Logical>) {
+        let view_rect = self.options.swap_axes(view_rect);
         let rules = self.window.rules();
 
         let draw_border_with_background = rules
@@ -288,6 +370,8 @@ impl<W: LayoutElement> Tile<W> {
             radius,
             self.scale,
         );
+
+        self.dim_buffer.resize(self.animated_window_size());
     }
 
     pub fn scale(&self) -> f64 {
@@ -307,13 +391,12 @@ impl<W: LayoutElement> Tile<W> {
         offset
     }
 
-    pub fn start_open_animation(&mut self) {
-        self.open_animation = Some(OpenAnimation::new(Animation::new(
-            0.,
-            1.,
-            0.,
-            self.options.animations.window_open.anim,
-        )));
+    /// Starts the open animation, growing the window from `origin` if set.
+    pub fn start_open_animation(&mut self, origin: Option<Point<f64, Logical>>) {
+        self.open_animation = Some(OpenAnimation::new(
+            Animation::new(0., 1., 0., self.options.animations.window_open.anim),
+            origin,
+        ));
     }
 
     pub fn resize_animation(&self) -> Option<&Animation> {
@@ -371,6 +454,25 @@ impl<W: LayoutElement> Tile<W> {
         &mut self.window
     }
 
+    /// Exchanges the windows of two tiles, which otherwise stay in place with their sizes.
+    ///
+    /// The fullscreen state stays with the tile to match its column, which requests it from the
+    /// new window.
+    pub fn swap_windows(&mut self, other: &mut Self) {
+        mem::swap(&mut self.window, &mut other.window);
+        mem::swap(&mut self.opacity_override, &mut other.opacity_override);
+        mem::swap(&mut self.unmap_snapshot, &mut other.unmap_snapshot);
+
+        for tile in [self, other] {
+            // These animate the previous window of the tile.
+            tile.open_animation = None;
+            tile.resize_animation = None;
+
+            // Border and focus ring rules come from the window.
+            tile.update_config(tile.scale, tile.options.clone());
+        }
+    }
+
     pub fn into_window(self) -> W {
         self.window
     }
@@ -394,11 +496,15 @@ impl<W: LayoutElement> Tile<W> {
 
     /// Returns the location of the window's visual geometry within this Tile.
     pub fn window_loc(&self) -> Point<f64, Logical> {
+        self.options.swap_axes(self.window_loc_on_screen())
+    }
+
+    fn window_loc_on_screen(&self) -> Point<f64, Logical> {
         let mut loc = Point::from((0., 0.));
 
         // In fullscreen, center the window in the given size.
         if self.is_fullscreen {
-            let window_size = self.window_size();
+            let window_size = self.window_size_on_screen();
             let target_size = self.fullscreen_size;
 
             // Windows aren't supposed to be larger than the fullscreen size, but in case we get
@@ -424,7 +530,11 @@ impl<W: LayoutElement> Tile<W> {
     }
 
     pub fn tile_size(&self) -> Size<f64, Logical> {
-        let mut size = self.window_size();
+        self.options.swap_axes(self.tile_size_on_screen())
+    }
+
+    fn tile_size_on_screen(&self) -> Size<f64, Logical> {
+        let mut size = self.window_size_on_screen();
 
         if self.is_fullscreen {
             // Normally we'd just return the fullscreen size here, but this makes things a bit
@@ -443,15 +553,67 @@ impl<W: LayoutElement> Tile<W> {
     }
 
     pub fn window_size(&self) -> Size<f64, Logical> {
-        let mut size = self.window.size().to_f64();
+        self.options.swap_axes(self.window_size_on_screen())
+    }
+
+    fn window_size_on_screen(&self) -> Size<f64, Logical> {
+        let size = self
+            .oversized_fit_size()
+            .unwrap_or_else(|| self.window.size());
+        let mut size = size.to_f64();
         size = size
             .to_physical_precise_round(self.scale)
             .to_logical(self.scale);
         size
     }
 
+    /// Returns the size to keep an oversized window to, if its rules ask for that.
+    ///
+    /// A window is oversized when it has committed the latest configure, but came out bigger than
+    /// the size it was asked to take.
+    fn oversized_fit_size(&self) -> Option<Size<i32, Logical>> {
+        if self.is_fullscreen {
+            return None;
+        }
+
+        // Only a window that came out at a different size after committing the configure can be
+        // oversized. One that has yet to commit it may still shrink.
+        let policy = self.window.rules().oversized_window.unwrap_or_default();
+        if policy == OversizedWindow::Expand || !self.window.is_refusing_expected_size() {
+            return None;
+        }
+
+        let expected = self.window.expected_size()?;
+        let size = self.window.size();
+
+        let mut fit = size;
+        if expected.w != 0 {
+            fit.w = min(fit.w, expected.w);
+        }
+        if expected.h != 0 {
+            fit.h = min(fit.h, expected.h);
+        }
+
+        (fit != size).then_some(fit)
+    }
+
+    /// Returns the scale at which the window is drawn.
+    ///
+    /// This is below 1 for an oversized window that is scaled down to fit its tile.
+    pub fn window_render_scale(&self) -> f64 {
+        if self.oversized_fit_size().is_none()
+            || self.window.rules().oversized_window != Some(OversizedWindow::ScaleDown)
+        {
+            return 1.;
+        }
+
+        let fit = self.window_size_on_screen();
+        let size = self.window.size().to_f64();
+        f64::min(fit.w / size.w, fit.h / size.h)
+    }
+
     fn animated_window_size(&self) -> Size<f64, Logical> {
-        let mut size = self.window_size();
+        let mut size = self.window_size_on_screen();
 
         if let Some(resize) = &self.resize_animation {
             let val = resize.anim.value();
@@ -488,13 +650,24 @@ impl<W: LayoutElement> Tile<W> {
 
     pub fn buf_loc(&self) -> Point<f64, Logical> {
         let mut loc = Point::from((0., 0.));
-        loc += self.window_loc();
+        loc += self.window_loc_on_screen();
         loc += self.window.buf_loc().to_f64();
-        loc
+        self.options.swap_axes(loc)
     }
 
-    pub fn is_in_input_region(&self, mut point: Point<f64, Logical>) -> bool {
-        point -= self.window_loc().to_f64();
+    pub fn is_in_input_region(&self, point: Point<f64, Logical>) -> bool {
+        let mut point = self.options.swap_axes(point);
+        point -= self.window_loc_on_screen().to_f64();
+
+        // An oversized window only takes input within the part that is drawn.
+        if self.oversized_fit_size().is_some() {
+            let fit = Rectangle::from_loc_and_size((0., 0.), self.window_size_on_screen());
+            if !fit.contains(point) {
+                return false;
+            }
+        }
+
+        let point = point.downscale(self.window_render_scale());
         self.window.is_in_input_region(point)
     }
 
@@ -503,7 +676,17 @@ impl<W: LayoutElement> Tile<W> {
         activation_region.contains(point)
     }
 
-    pub fn request_tile_size(&mut self, mut size: Size<f64, Logical>, animate: bool) {
+    /// Requests the window to change its size.
+    ///
+    /// If the window gets a new size, it joins the transaction, which is created on demand.
+    pub fn request_tile_size(
+        &mut self,
+        size: Size<f64, Logical>,
+        animate: bool,
+        transaction: &mut Option<Transaction>,
+    ) {
+        let mut size = self.options.swap_axes(size);
+
         // Can't go through effective_border_width() because we might be fullscreen.
         if !self.border.is_off() {
             let width = self.border.width();
@@ -514,7 +697,11 @@ impl<W: LayoutElement> Tile<W> {
         // The size request has to be i32 unfortunately, due to Wayland. We floor here instead of
         // round to avoid situations where proportionally-sized columns don't fit on the screen
         // exactly.
-        self.window.request_size(size.to_i32_floor(), animate);
+        let size = size.to_i32_floor();
+
+        let transaction = (self.window.requested_size() != Some(size))
+            .then(|| transaction.get_or_insert_with(Transaction::new).clone());
+        self.window.request_size(size, animate, transaction);
     }
 
     pub fn tile_width_for_window_width(&self, size: f64) -> f64 {
@@ -549,7 +736,9 @@ impl<W: LayoutElement> Tile<W> {
         }
     }
 
+    /// Requests the window to go fullscreen in the given view size of the workspace layout.
     pub fn request_fullscreen(&mut self, size: Size<f64, Logical>) {
+        let size = self.options.swap_axes(size);
         self.fullscreen_backdrop.resize(size);
         self.fullscreen_size = size;
         self.window.request_fullscreen(size.to_i32_round());
@@ -566,7 +755,7 @@ impl<W: LayoutElement> Tile<W> {
             size.h += width * 2.;
         }
 
-        size
+        self.options.swap_axes(size)
     }
 
     pub fn max_size(&self) -> Size<f64, Logical> {
@@ -581,7 +770,7 @@ impl<W: LayoutElement> Tile<W> {
             }
         }
 
-        size
+        self.options.swap_axes(size)
     }
 
     pub fn draw_border_with_background(&self) -> bool {
@@ -608,17 +797,21 @@ impl<W: LayoutElement> Tile<W> {
         let alpha = if self.is_fullscreen {
             1.
         } else {
-            self.window.rules().opacity.unwrap_or(1.).clamp(0., 1.)
+            self.opacity() as f32
         };
 
-        let window_loc = self.window_loc();
-        let window_size = self.window_size().to_f64();
+        let window_loc = self.window_loc_on_screen();
+        let window_size = self.window_size_on_screen().to_f64();
         let animated_window_size = self.animated_window_size();
         let window_render_loc = location + window_loc;
         let area = Rectangle::from_loc_and_size(window_render_loc, animated_window_size);
 
         let rules = self.window.rules();
-        let clip_to_geometry = !self.is_fullscreen && rules.clip_to_geometry == Some(true);
+        let oversized = self
+            .oversized_fit_size()
+            .map(|_| rules.oversized_window.unwrap_or_default());
+        let clip_to_geometry = !self.is_fullscreen
+            && (rules.clip_to_geometry == Some(true) || oversized == Some(OversizedWindow::Clip));
         let radius = rules.geometry_corner_radius.unwrap_or_default();
 
         // If we're resizing, try to render a shader, or a fallback.
@@ -720,69 +913,112 @@ impl<W: LayoutElement> Tile<W> {
             let clip_shader = ClippedSurfaceRenderElement::shader(renderer).cloned();
             let has_border_shader = BorderRenderElement::has_shader(renderer);
 
-            if clip_to_geometry && clip_shader.is_some() {
+            // Draw a scaled-down oversized window in the top-left corner of its geometry.
+            let scale_down = (oversized == Some(OversizedWindow::ScaleDown)).then(|| {
+                let origin = window_render_loc.to_physical_precise_round(scale);
+                (origin, self.window_render_scale())
+            });
+
+            if clip_to_geometry && clip_shader.is_some() && scale_down.is_none() {
                 let damage = self.rounded_corner_damage.element();
                 rounded_corner_damage = Some(damage.with_location(window_render_loc).into());
             }
 
-            window_surface = Some(window.normal.into_iter().map(move |elem| match elem {
-                LayoutElementRenderElement::Wayland(elem) => {
-                    // If we should clip to geometry, render a clipped window.
-                    if clip_to_geometry {
-                        if let Some(shader) = clip_shader.clone() {
-                            if ClippedSurfaceRenderElement::will_clip(&elem, scale, geo, radius) {
-                                return ClippedSurfaceRenderElement::new(
-                                    elem,
-                                    scale,
-                                    geo,
-                                    shader.clone(),
-                                    radius,
-                                )
-                                .into();
+            window_surface = Some(window.normal.into_iter().map(move |elem| {
+                if let Some((origin, factor)) = scale_down {
+                    return RescaleRenderElement::from_element(elem, origin, factor).into();
+                }
+
+                match elem {
+                    LayoutElementRenderElement::Wayland(elem) => {
+                        // If we should clip to geometry, render a clipped window.
+                        if clip_to_geometry {
+                            if let Some(shader) = clip_shader.clone() {
+                                if ClippedSurfaceRenderElement::will_clip(&elem, scale, geo, radius)
+                                {
+                                    return ClippedSurfaceRenderElement::new(
+                                        elem,
+                                        scale,
+                                        geo,
+                                        shader.clone(),
+                                        radius,
+                                    )
+                                    .into();
+                                }
                             }
                         }
-                    }
 
-                    // Otherwise, render it normally.
-                    LayoutElementRenderElement::Wayland(elem).into()
-                }
-                LayoutElementRenderElement::SolidColor(elem) => {
-                    // In this branch we're rendering a blocked-out window with a solid
-                    // color. We need to render it with a rounded corner shader even if
-                    // clip_to_geometry is false, because in this case we're assuming that
-                    // the unclipped window CSD already has corners rounded to the
-                    // user-provided radius, so our blocked-out rendering should match that
-                    // radius.
-                    if radius != CornerRadius::default() && has_border_shader {
-                        return BorderRenderElement::new(
-                            geo.size,
-                            Rectangle::from_loc_and_size((0., 0.), geo.size),
-                            elem.color(),
-                            elem.color(),
-                            0.,
-                            Rectangle::from_loc_and_size((0., 0.), geo.size),
-                            0.,
-                            radius,
-                            scale.x as f32,
-                        )
-                        .with_location(geo.loc)
-                        .into();
+                        // Otherwise, render it normally.
+                        LayoutElementRenderElement::Wayland(elem).into()
                     }
+                    LayoutElementRenderElement::SolidColor(elem) => {
+                        // In this branch we're rendering a blocked-out window with a solid
+                        // color. We need to render it with a rounded corner shader even if
+                        // clip_to_geometry is false, because in this case we're assuming that
+                        // the unclipped window CSD already has corners rounded to the
+                        // user-provided radius, so our blocked-out rendering should match that
+                        // radius.
+                        if radius != CornerRadius::default() && has_border_shader {
+                            return BorderRenderElement::new(
+                                geo.size,
+                                Rectangle::from_loc_and_size((0., 0.), geo.size),
+                                elem.color(),
+                                elem.color(),
+                                0.,
+                                Rectangle::from_loc_and_size((0., 0.), geo.size),
+                                0.,
+                                radius,
+                                scale.x as f32,
+                            )
+                            .with_location(geo.loc)
+                            .into();
+                        }
 
-                    // Otherwise, render the solid color as is.
-                    LayoutElementRenderElement::SolidColor(elem).into()
+                        // Otherwise, render the solid color as is.
+                        LayoutElementRenderElement::SolidColor(elem).into()
+                    }
                 }
             }));
 
             window_popups = Some(window.popups.into_iter().map(Into::into));
         }
 
+        // Darken the window on top of its surface, but below its popups.
+        let dim = self.dim_value() as f32 * alpha;
+        let dim_elem = (dim > 0.).then(|| {
+            let radius = if self.is_fullscreen {
+                CornerRadius::default()
+            } else {
+                radius.fit_to(area.size.w as f32, area.size.h as f32)
+            };
+            if radius != CornerRadius::default() && BorderRenderElement::has_shader(renderer) {
+                let color = [0., 0., 0., dim];
+                return BorderRenderElement::new(
+                    area.size,
+                    Rectangle::from_loc_and_size((0., 0.), area.size),
+                    color,
+                    color,
+                    0.,
+                    Rectangle::from_loc_and_size((0., 0.), area.size),
+                    0.,
+                    radius,
+                    scale.x as f32,
+                )
+                .with_location(area.loc)
+                .into();
+            }
+
+            SolidColorRenderElement::from_buffer(&self.dim_buffer, area.loc, dim, Kind::Unspecified)
+                .into()
+        });
+
         let rv = resize_popups
             .into_iter()
             .flatten()
+            .chain(window_popups.into_iter().flatten())
+            .chain(dim_elem)
             .chain(resize_shader)
             .chain(resize_fallback)
-            .chain(window_popups.into_iter().flatten())
             .chain(rounded_corner_damage)
             .chain(window_surface.into_iter().flatten());
 
@@ -818,6 +1054,8 @@ impl<W: LayoutElement> Tile<W> {
     ) -> impl Iterator<Item = TileRenderElement<R>> {
         let _span = tracy_client::span!("Tile::render");
 
+        let location = self.options.swap_axes(location);
+
         let mut open_anim_elem = None;
         let mut window_elems = None;
 
@@ -826,7 +1064,13 @@ impl<W: LayoutElement> Tile<W> {
             let elements =
                 self.render_inner(renderer, Point::from((0., 0.)), scale, focus_ring, target);
             let elements = elements.collect::<Vec<TileRenderElement<_>>>();
-            match open.render(renderer, &elements, self.tile_size(), location, scale) {
+            match open.render(
+                renderer,
+                &elements,
+                self.tile_size_on_screen(),
+                location,
+                scale,
+            ) {
                 Ok(elem) => {
                     self.window()
                         .set_offscreen_element_id(Some(elem.id().clone()));
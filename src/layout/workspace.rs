@@ -1,11 +1,16 @@
 use std::cmp::{max, min};
 use std::iter::{self, zip};
+use std::ptr;
 use std::rc::Rc;
 use std::time::Duration;
 
-use niri_config::{CenterFocusedColumn, PresetWidth, Struts, Workspace as WorkspaceConfig};
-use niri_ipc::SizeChange;
+use niri_config::{
+    CenterFocusedColumn, DefaultColumnWidth, DialogPlacement, FloatOrInt, LayoutOverride, OnClose,
+    OutputIdentity, PresetWidth, Struts, Workspace as WorkspaceConfig,
+};
+use niri_ipc::{OpacityChange, SizeChange};
 use ordered_float::NotNan;
+use smithay::backend::renderer::element::Kind;
 use smithay::backend::renderer::gles::GlesRenderer;
 use smithay::desktop::{layer_map_for_output, Window};
 use smithay::output::Output;
@@ -14,20 +19,44 @@ use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::utils::{Logical, Point, Rectangle, Scale, Serial, Size, Transform};
 
 use super::closing_window::{ClosingWindow, ClosingWindowRenderElement};
+use super::snapshot::TileSnapshot;
+use super::strategy::{LayoutStrategy, Scrolling};
+use super::tab_indicator::TabIndicator;
 use super::tile::{Tile, TileRenderElement};
 use super::{InteractiveResizeData, LayoutElement, Options};
-use crate::animation::Animation;
+use crate::animation::{Animation, Curve};
 use crate::input::swipe_tracker::SwipeTracker;
 use crate::niri_render_elements;
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
 use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
 use crate::render_helpers::RenderTarget;
 use crate::utils::id::IdCounter;
-use crate::utils::{output_size, send_scale_transform, ResizeEdge};
+use crate::utils::transaction::Transaction;
+use crate::utils::{output_serial, output_size, send_scale_transform, ResizeEdge};
 use crate::window::ResolvedWindowRules;
 
 /// Amount of touchpad movement to scroll the view for the width of one working area.
 const VIEW_GESTURE_WORKING_AREA_MOVEMENT: f64 = 1200.;
 
+/// Proportions of the working area that interactively resized columns snap to.
+const RESIZE_SNAP_PROPORTIONS: [f64; 3] = [1. / 3., 0.5, 2. / 3.];
+
+/// How close an interactively resized column has to get to a width to snap to it.
+const RESIZE_SNAP_DISTANCE: f64 = 12.;
+
+/// Width of the line flashed at the resized edge when the column snaps.
+const RESIZE_SNAP_INDICATOR_WIDTH: f64 = 2.;
+
+/// Duration of the snap indicator flash.
+const RESIZE_SNAP_INDICATOR_MS: u64 = 400;
+
+/// Opacity of the placeholder drawn for a window that is about to open.
+const INSERT_HINT_ALPHA: f32 = 0.5;
+
+/// How far down and to the right a cascaded dialog opens from the window before it.
+const DIALOG_CASCADE_OFFSET: f64 = 32.;
+
 #[derive(Debug)]
 pub struct Workspace<W: LayoutElement> {
     /// The original output of this workspace.
@@ -63,6 +92,23 @@ pub struct Workspace<W: LayoutElement> {
     /// zones.
     working_area: Rectangle<f64, Logical>,
 
+    /// Scale at which this workspace is rendered, from the `zoom` option.
+    ///
+    /// The view size and the working area are divided by the zoom, so that more columns fit into
+    /// the view while windows keep their logical sizes. Rendering scales everything back down,
+    /// and positions coming from input are scaled up to match.
+    ///
+    /// The zoom is suspended while the workspace has a fullscreen column, so that fullscreen
+    /// windows cover the output at their own size.
+    zoom: f64,
+
+    /// Whether the view size and the working area are transposed, from the `transposed` option.
+    ///
+    /// Columns then go from the top to the bottom of the output, and the tiles within a column
+    /// from left to right. The layout code works the same as usual, with the axes swapped at the
+    /// boundaries: in the tiles, for rendering, and for positions coming from input.
+    transposed: bool,
+
     /// Columns of windows on this workspace.
     pub columns: Vec<Column<W>>,
 
@@ -72,9 +118,24 @@ pub struct Workspace<W: LayoutElement> {
     /// Index of the currently active column, if any.
     pub active_column_idx: usize,
 
+    /// Windows floating above the columns, such as dialogs, with the topmost one last.
+    floating: Vec<FloatingTile<W>>,
+
+    /// Whether the topmost floating window, rather than the active column, has the focus.
+    floating_is_active: bool,
+
     /// Ongoing interactive resize.
     interactive_resize: Option<InteractiveResize<W>>,
 
+    /// Line flashed at the resized column edge when an interactive resize snaps.
+    snap_indicator: SolidColorBuffer,
+
+    /// Column widths of the windows about to open here, shown as a placeholder, oldest first.
+    insert_hints: Vec<(W::Id, ColumnWidth)>,
+
+    /// Placeholder drawn where the window that is about to open will appear.
+    insert_hint_buffer: SolidColorBuffer,
+
     /// Offset of the view computed from the active column.
     ///
     /// Any gaps, including left padding from work area left exclusive zone, is handled
@@ -112,14 +173,56 @@ pub struct Workspace<W: LayoutElement> {
     /// Optional name of this workspace.
     pub name: Option<String>,
 
+    /// Whether this workspace was created on demand for a window rule, rather than from the
+    /// config.
+    ///
+    /// Such workspaces lose their name once their last window leaves, so that they can be cleaned
+    /// up like any other empty workspace.
+    pub created_on_demand: bool,
+
+    /// Layout settings overridden in the named workspace config.
+    layout_config: LayoutOverride,
+
+    /// Layout settings overridden at runtime through actions.
+    layout_runtime: LayoutOverride,
+
     /// Unique ID of this workspace.
     id: WorkspaceId,
+
+    /// Serial of the last time this workspace became focused, used for recently used ordering.
+    ///
+    /// Zero means that the workspace has never been focused.
+    focus_serial: u32,
+
+    /// Whether column widths are balanced automatically to fit all columns into the view.
+    auto_tile: bool,
+
+    /// Number of columns as of the last auto-tile balancing.
+    ///
+    /// The columns are balanced again only once columns are added or removed.
+    auto_tile_column_count: usize,
+
+    /// How windows are arranged into columns.
+    strategy: Box<dyn LayoutStrategy>,
+
+    /// Number of windows that the columns were last arranged for by the strategy.
+    ///
+    /// The columns are only rearranged when windows are added or removed, so that manual changes
+    /// like consume and expel stick around until then.
+    arranged_window_count: Option<usize>,
 }
 
+/// Output that a workspace belongs to.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct OutputId(String);
+pub struct OutputId {
+    /// Connector name.
+    name: String,
+    /// Make, model and serial number of the monitor, if it reported a serial number.
+    monitor: Option<(String, String, String)>,
+}
 
 static WORKSPACE_ID_COUNTER: IdCounter = IdCounter::new();
+static WORKSPACE_FOCUS_COUNTER: IdCounter = IdCounter::new();
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct WorkspaceId(u32);
@@ -134,6 +237,8 @@ niri_render_elements! {
     WorkspaceRenderElement<R> => {
         Tile = TileRenderElement<R>,
         ClosingWindow = ClosingWindowRenderElement,
+        SolidColor = SolidColorRenderElement,
+        Offscreen = PrimaryGpuTextureRenderElement,
     }
 }
 
@@ -161,11 +266,40 @@ struct ViewGesture {
     is_touchpad: bool,
 }
 
+/// Window floating above the columns.
+#[derive(Debug)]
+struct FloatingTile<W: LayoutElement> {
+    tile: Tile<W>,
+    /// Position of the tile relative to the view.
+    ///
+    /// Floating windows stay in place when the columns scroll.
+    pos: Point<f64, Logical>,
+    /// Offset from the pointer to the tile during an interactive move.
+    move_offset: Option<Point<f64, Logical>>,
+}
+
+/// Focused window taken out of a workspace to be added to another one.
+#[derive(Debug)]
+pub enum MovedWindow<W> {
+    /// Window from a column, with the width of that column.
+    Tiled {
+        window: W,
+        width: ColumnWidth,
+        is_full_width: bool,
+    },
+    /// Floating window, which stays floating.
+    Floating(W),
+}
+
 #[derive(Debug)]
 struct InteractiveResize<W: LayoutElement> {
     window: W::Id,
     original_window_size: Size<f64, Logical>,
     data: InteractiveResizeData,
+    /// Width that the column is currently snapped to.
+    snapped_width: Option<ColumnWidth>,
+    /// Fading flash of the snap indicator, started when the column snaps.
+    snap_flash: Option<Animation>,
 }
 
 /// Width of a column.
@@ -226,9 +360,16 @@ pub struct Column<W: LayoutElement> {
     /// Whether this column is full-width.
     pub is_full_width: bool,
 
+    /// Width and full-width state to restore when toggling back from `toggle-column-width`.
+    width_before_toggle: Option<(ColumnWidth, bool)>,
+
     /// Whether this column contains a single full-screened window.
     pub is_fullscreen: bool,
 
+    /// Whether this column is centered whenever it is focused, regardless of
+    /// `center-focused-column`.
+    pub always_center: bool,
+
     /// Animation of the render offset during window swapping.
     move_animation: Option<Animation>,
 
@@ -241,6 +382,9 @@ pub struct Column<W: LayoutElement> {
     /// Scale of the output the column is on (and rounds its sizes to).
     scale: f64,
 
+    /// Indicator of the windows in this column.
+    tab_indicator: TabIndicator,
+
     /// Configurable properties of the layout.
     options: Rc<Options>,
 }
@@ -262,7 +406,37 @@ struct TileData {
 
 impl OutputId {
     pub fn new(output: &Output) -> Self {
-        Self(output.name())
+        let monitor = output_serial(output).map(|serial| {
+            let props = output.physical_properties();
+            (props.make, props.model, serial)
+        });
+
+        Self {
+            name: output.name(),
+            monitor,
+        }
+    }
+
+    /// Creates an id that refers to whatever monitor is connected to the connector.
+    pub fn from_name(name: String) -> Self {
+        Self {
+            name,
+            monitor: None,
+        }
+    }
+
+    /// Returns whether both ids refer to the same output.
+    ///
+    /// The monitors are only compared when both ids know them; otherwise the connector names
+    /// decide.
+    pub fn matches(&self, other: &OutputId, identity: OutputIdentity) -> bool {
+        match (&self.monitor, &other.monitor) {
+            (Some(a), Some(b)) => match identity {
+                OutputIdentity::Strict => a == b && self.name == other.name,
+                OutputIdentity::Fuzzy => a == b,
+            },
+            _ => self.name == other.name,
+        }
     }
 }
 
@@ -345,26 +519,47 @@ impl<W: LayoutElement> Workspace<W> {
         let original_output = config
             .as_ref()
             .and_then(|c| c.open_on_output.clone())
-            .map(OutputId)
+            .map(OutputId::from_name)
             .unwrap_or(OutputId::new(&output));
 
+        let layout_config = config
+            .as_ref()
+            .map(|c| c.layout.clone())
+            .unwrap_or_default();
+
         let scale = output.current_scale();
-        let options =
-            Rc::new(Options::clone(&base_options).adjusted_for_scale(scale.fractional_scale()));
+        let options = Rc::new(base_options.resolve_for_workspace(
+            &layout_config,
+            scale.fractional_scale(),
+            output_size(&output),
+        ));
 
         let working_area = compute_working_area(&output, options.struts);
+        let (view_size, working_area) = layout_view(
+            output_size(&output),
+            working_area,
+            options.zoom,
+            options.transposed,
+        );
 
         Self {
             original_output,
             scale,
             transform: output.current_transform(),
-            view_size: output_size(&output),
+            view_size,
             working_area,
+            zoom: options.zoom,
+            transposed: options.transposed,
             output: Some(output),
             columns: vec![],
             data: vec![],
             active_column_idx: 0,
+            floating: vec![],
+            floating_is_active: false,
             interactive_resize: None,
+            snap_indicator: SolidColorBuffer::default(),
+            insert_hints: Vec::new(),
+            insert_hint_buffer: SolidColorBuffer::default(),
             view_offset: 0.,
             view_offset_adj: None,
             activate_prev_column_on_removal: None,
@@ -373,7 +568,15 @@ impl<W: LayoutElement> Workspace<W> {
             base_options,
             options,
             name: config.map(|c| c.name.0),
+            created_on_demand: false,
+            layout_config,
+            layout_runtime: LayoutOverride::default(),
             id: WorkspaceId::next(),
+            focus_serial: 0,
+            auto_tile: false,
+            auto_tile_column_count: 0,
+            strategy: Box::new(Scrolling),
+            arranged_window_count: None,
         }
     }
 
@@ -381,28 +584,50 @@ impl<W: LayoutElement> Workspace<W> {
         config: Option<WorkspaceConfig>,
         base_options: Rc<Options>,
     ) -> Self {
-        let original_output = OutputId(
+        let original_output = OutputId::from_name(
             config
                 .clone()
                 .and_then(|c| c.open_on_output)
                 .unwrap_or_default(),
         );
 
+        let layout_config = config
+            .as_ref()
+            .map(|c| c.layout.clone())
+            .unwrap_or_default();
+
         let scale = smithay::output::Scale::Integer(1);
-        let options =
-            Rc::new(Options::clone(&base_options).adjusted_for_scale(scale.fractional_scale()));
+        let options = Rc::new(base_options.resolve_for_workspace(
+            &layout_config,
+            scale.fractional_scale(),
+            Size::from((1280., 720.)),
+        ));
+
+        let (view_size, working_area) = layout_view(
+            Size::from((1280., 720.)),
+            Rectangle::from_loc_and_size((0., 0.), (1280., 720.)),
+            options.zoom,
+            options.transposed,
+        );
 
         Self {
             output: None,
             scale,
             transform: Transform::Normal,
             original_output,
-            view_size: Size::from((1280., 720.)),
-            working_area: Rectangle::from_loc_and_size((0., 0.), (1280., 720.)),
+            view_size,
+            working_area,
+            zoom: options.zoom,
+            transposed: options.transposed,
             columns: vec![],
             data: vec![],
             active_column_idx: 0,
+            floating: vec![],
+            floating_is_active: false,
             interactive_resize: None,
+            snap_indicator: SolidColorBuffer::default(),
+            insert_hints: Vec::new(),
+            insert_hint_buffer: SolidColorBuffer::default(),
             view_offset: 0.,
             view_offset_adj: None,
             activate_prev_column_on_removal: None,
@@ -411,7 +636,15 @@ impl<W: LayoutElement> Workspace<W> {
             base_options,
             options,
             name: config.map(|c| c.name.0),
+            created_on_demand: false,
+            layout_config,
+            layout_runtime: LayoutOverride::default(),
             id: WorkspaceId::next(),
+            focus_serial: 0,
+            auto_tile: false,
+            auto_tile_column_count: 0,
+            strategy: Box::new(Scrolling),
+            arranged_window_count: None,
         }
     }
 
@@ -425,6 +658,102 @@ impl<W: LayoutElement> Workspace<W> {
 
     pub fn unname(&mut self) {
         self.name = None;
+        self.created_on_demand = false;
+    }
+
+    /// Returns the layout settings overridden for this workspace.
+    pub fn layout_override(&self) -> LayoutOverride {
+        let mut rv = self.layout_config.clone();
+        rv.merge_with(&self.layout_runtime);
+        rv
+    }
+
+    /// Sets the layout overrides from the config.
+    ///
+    /// Takes effect on the next `update_config()`.
+    pub fn set_layout_config(&mut self, layout: LayoutOverride) {
+        self.layout_config = layout;
+    }
+
+    pub fn set_gaps(&mut self, change: SizeChange) {
+        let current = self
+            .layout_override()
+            .gaps
+            .map_or(self.base_options.gaps, |gaps| gaps.0);
+
+        let gaps = match change {
+            SizeChange::SetFixed(value) => f64::from(value),
+            SizeChange::AdjustFixed(delta) => current + f64::from(delta),
+            SizeChange::SetProportion(_) | SizeChange::AdjustProportion(_) => return,
+        };
+
+        self.layout_runtime.gaps = Some(FloatOrInt(gaps.clamp(0., 65535.)));
+        self.resolve_options(false);
+    }
+
+    /// Changes the width that new columns on this workspace get by default.
+    ///
+    /// Adjustments do nothing while new windows pick their own width.
+    pub fn set_default_column_width(&mut self, change: SizeChange) {
+        let current = match self.options.default_width {
+            Some(ColumnWidth::Preset(idx)) => Some(self.options.preset_widths[idx]),
+            current => current,
+        };
+
+        let width = match (current, change) {
+            (_, SizeChange::SetFixed(fixed)) => PresetWidth::Fixed(fixed),
+            (_, SizeChange::SetProportion(proportion)) => {
+                PresetWidth::Proportion(proportion / 100.)
+            }
+            (None, SizeChange::AdjustFixed(_) | SizeChange::AdjustProportion(_)) => return,
+            (Some(ColumnWidth::Proportion(current)), SizeChange::AdjustProportion(delta)) => {
+                PresetWidth::Proportion(current + delta / 100.)
+            }
+            (Some(current), SizeChange::AdjustFixed(delta)) => {
+                let current_px = current.resolve(&self.options, self.working_area.size.w);
+                PresetWidth::Fixed((current_px.round() as i32).saturating_add(delta))
+            }
+            (Some(current), SizeChange::AdjustProportion(delta)) => {
+                let current_px = current.resolve(&self.options, self.working_area.size.w);
+                let full = self.working_area.size.w - self.options.gaps;
+                let current = if full == 0. {
+                    1.
+                } else {
+                    (current_px + self.options.gaps) / full
+                };
+                PresetWidth::Proportion(current + delta / 100.)
+            }
+        };
+
+        self.layout_runtime.default_column_width = Some(DefaultColumnWidth(Some(width)));
+        self.resolve_options(false);
+    }
+
+    pub fn set_center_focused_column(&mut self, center: CenterFocusedColumn) {
+        self.layout_runtime.center_focused_column = Some(center);
+        self.resolve_options(false);
+    }
+
+    pub fn set_zoom(&mut self, zoom: f64) {
+        self.layout_runtime.zoom = Some(FloatOrInt(zoom.clamp(0., 1.)));
+        self.resolve_options(false);
+    }
+
+    pub fn reset_layout(&mut self) {
+        if self.layout_runtime == LayoutOverride::default() {
+            return;
+        }
+
+        self.layout_runtime = LayoutOverride::default();
+        self.resolve_options(false);
+    }
+
+    pub fn focus_serial(&self) -> u32 {
+        self.focus_serial
+    }
+
+    pub fn mark_focused(&mut self) {
+        self.focus_serial = WORKSPACE_FOCUS_COUNTER.next();
     }
 
     pub fn scale(&self) -> smithay::output::Scale {
@@ -446,23 +775,54 @@ impl<W: LayoutElement> Workspace<W> {
             col.advance_animations(current_time);
         }
 
+        for floating in &mut self.floating {
+            floating.tile.advance_animations(current_time);
+        }
+
+        if let Some(resize) = &mut self.interactive_resize {
+            if let Some(flash) = &mut resize.snap_flash {
+                flash.set_current_time(current_time);
+                if flash.is_done() {
+                    resize.snap_flash = None;
+                }
+            }
+        }
+
         self.closing_windows.retain_mut(|closing| {
             closing.advance_animations(current_time);
             closing.are_animations_ongoing()
         });
     }
 
+    pub fn closing_windows_texture_bytes(&self) -> u64 {
+        self.closing_windows
+            .iter()
+            .map(ClosingWindow::texture_bytes)
+            .sum()
+    }
+
+    /// Ends the closing window animations right away, dropping their textures.
+    pub fn clear_closing_windows(&mut self) {
+        self.closing_windows.clear();
+    }
+
     pub fn are_animations_ongoing(&self) -> bool {
         self.view_offset_adj
             .as_ref()
             .is_some_and(|s| s.is_animation())
             || self.columns.iter().any(Column::are_animations_ongoing)
+            || self.floating_tiles().any(Tile::are_animations_ongoing)
             || !self.closing_windows.is_empty()
+            || self
+                .interactive_resize
+                .as_ref()
+                .is_some_and(|resize| resize.snap_flash.is_some())
     }
 
     pub fn are_transitions_ongoing(&self) -> bool {
         self.view_offset_adj.is_some()
             || self.columns.iter().any(Column::are_animations_ongoing)
+            || self.floating_tiles().any(Tile::are_animations_ongoing)
             || !self.closing_windows.is_empty()
     }
 
@@ -470,26 +830,167 @@ impl<W: LayoutElement> Workspace<W> {
         let view_pos = Point::from((self.view_pos(), 0.));
         let view_size = self.view_size();
         let active_idx = self.active_column_idx;
+        let floating_is_active = self.floating_is_active;
+
+        // Windows behind a focused dialog get dimmed more strongly.
+        let is_dialog_focused = floating_is_active
+            && self
+                .floating
+                .last()
+                .is_some_and(|floating| floating.tile.window().is_dialog());
+        let dim = if is_active && is_dialog_focused {
+            f64::max(self.options.dim_inactive, self.options.dim_behind_modal)
+        } else {
+            self.options.dim_inactive
+        };
+
         for (col_idx, (col, col_x)) in self.columns_mut().enumerate() {
-            let is_active = is_active && col_idx == active_idx;
+            let is_active = is_active && !floating_is_active && col_idx == active_idx;
             let col_off = Point::from((col_x, 0.));
             let col_pos = view_pos - col_off - col.render_offset();
             let view_rect = Rectangle::from_loc_and_size(col_pos, view_size);
-            col.update_render_elements(is_active, view_rect);
+            col.update_render_elements(is_active, dim, view_rect);
+        }
+
+        let topmost_idx = self.floating.len().saturating_sub(1);
+        for (idx, floating) in self.floating.iter_mut().enumerate() {
+            let is_active = is_active && floating_is_active && idx == topmost_idx;
+            let tile_pos = floating.pos + floating.tile.render_offset();
+            let view_pos = Point::from((-tile_pos.x, -tile_pos.y));
+            let view_rect = Rectangle::from_loc_and_size(view_pos, view_size);
+            floating.tile.update(is_active, view_rect);
+            floating.tile.set_dim(if is_active { 0. } else { dim });
+        }
+
+        let size = Size::from((RESIZE_SNAP_INDICATOR_WIDTH, self.working_area.size.h));
+        let size = self.options.swap_axes(size);
+        let color = self.options.focus_ring.active_color;
+        self.snap_indicator.update(size, color.into());
+
+        if let Some(area) = self.insert_hint_area() {
+            let size = self.options.swap_axes(area.size);
+            self.insert_hint_buffer.update(size, color.into());
         }
     }
 
+    /// Shows a placeholder where a new column of this width would open for the window.
+    pub fn add_insert_hint(&mut self, window: W::Id, width: ColumnWidth) {
+        self.remove_insert_hint(&window);
+        self.insert_hints.push((window, width));
+    }
+
+    /// Hides the placeholder of the window, returning whether there was one.
+    pub fn remove_insert_hint(&mut self, window: &W::Id) -> bool {
+        let len = self.insert_hints.len();
+        self.insert_hints.retain(|(id, _)| id != window);
+        self.insert_hints.len() != len
+    }
+
+    /// Returns the area of the insert hint relative to the view.
+    ///
+    /// With several windows waiting to open, the hint shows the most recent one.
+    fn insert_hint_area(&self) -> Option<Rectangle<f64, Logical>> {
+        let width = self.insert_hints.last()?.1;
+        let gaps = self.options.gaps;
+        let max_width = f64::max(self.working_area.size.w - gaps * 2., 1.);
+        let width = width
+            .resolve(&self.options, self.working_area.size.w)
+            .clamp(1., max_width);
+        let height = f64::max(self.working_area.size.h - gaps * 2., 1.);
+
+        // New columns open to the right of the active one.
+        let x = if self.columns.is_empty() {
+            self.working_area.loc.x + gaps
+        } else {
+            self.column_x(self.active_column_idx + 1) - self.view_pos()
+        };
+
+        let loc = Point::from((x, self.working_area.loc.y + gaps));
+        Some(Rectangle::from_loc_and_size(
+            loc,
+            Size::from((width, height)),
+        ))
+    }
+
     pub fn update_config(&mut self, base_options: Rc<Options>) {
+        self.base_options = base_options;
+        self.resolve_options(false);
+    }
+
+    /// Resolves the workspace options against the base options and pushes them to the columns.
+    ///
+    /// Unless `force` is set, nothing is updated when the resolved options did not change.
+    fn resolve_options(&mut self, force: bool) {
         let scale = self.scale.fractional_scale();
-        let options = Rc::new(Options::clone(&base_options).adjusted_for_scale(scale));
+        let options = self.base_options.resolve_for_workspace(
+            &self.layout_override(),
+            scale,
+            self.output_view_size(),
+        );
+        if !force && options == *self.options {
+            return;
+        }
 
+        let options = Rc::new(options);
         for (column, data) in zip(&mut self.columns, &mut self.data) {
             column.update_config(scale, options.clone());
             data.update(column);
         }
 
-        self.base_options = base_options;
+        for floating in &mut self.floating {
+            floating.tile.update_config(scale, options.clone());
+        }
+
         self.options = options;
+        self.update_view_transform();
+    }
+
+    fn update_view_transform(&mut self) {
+        let zoom = if self.columns.iter().any(|col| col.is_fullscreen) {
+            1.
+        } else {
+            self.options.zoom
+        };
+        self.set_view_transform(zoom, self.options.transposed);
+    }
+
+    fn set_view_transform(&mut self, zoom: f64, transposed: bool) {
+        if self.zoom == zoom && self.transposed == transposed {
+            return;
+        }
+
+        // Go back to the output view size and working area, then convert them anew.
+        let size = self.output_view_size();
+        let mut working_area = self.working_area;
+        if self.transposed {
+            working_area = working_area.transpose();
+        }
+        let working_area = Rectangle::from_loc_and_size(
+            working_area.loc.upscale(self.zoom),
+            working_area.size.upscale(self.zoom),
+        );
+
+        self.zoom = zoom;
+        self.transposed = transposed;
+        self.set_view_size(self.scale, self.transform, size, working_area);
+    }
+
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    pub fn is_transposed(&self) -> bool {
+        self.transposed
+    }
+
+    /// Returns the view size in the output coordinates, before the zoom and the transposition.
+    pub fn output_view_size(&self) -> Size<f64, Logical> {
+        let size = if self.transposed {
+            self.view_size.transpose()
+        } else {
+            self.view_size
+        };
+        size.upscale(self.zoom)
     }
 
     pub fn update_shaders(&mut self) {
@@ -498,22 +999,67 @@ impl<W: LayoutElement> Workspace<W> {
                 tile.update_shaders();
             }
         }
+
+        for floating in &mut self.floating {
+            floating.tile.update_shaders();
+        }
     }
 
     pub fn windows(&self) -> impl Iterator<Item = &W> + '_ {
         self.columns
             .iter()
             .flat_map(|col| col.tiles.iter())
+            .chain(self.floating_tiles())
             .map(Tile::window)
     }
 
+    pub fn active_window(&self) -> Option<&W> {
+        if self.floating_is_active {
+            return self.floating.last().map(|floating| floating.tile.window());
+        }
+
+        if self.columns.is_empty() {
+            return None;
+        }
+
+        let col = &self.columns[self.active_column_idx];
+        Some(col.tiles[col.active_tile_idx].window())
+    }
+
     pub fn windows_mut(&mut self) -> impl Iterator<Item = &mut W> + '_ {
         self.columns
             .iter_mut()
             .flat_map(|col| col.tiles.iter_mut())
+            .chain(self.floating.iter_mut().map(|floating| &mut floating.tile))
             .map(Tile::window_mut)
     }
 
+    /// Returns the floating tiles, from the bottom to the top.
+    fn floating_tiles(&self) -> impl Iterator<Item = &Tile<W>> + '_ {
+        self.floating.iter().map(|floating| &floating.tile)
+    }
+
+    pub fn is_floating(&self, window: &W::Id) -> bool {
+        self.floating_tiles()
+            .any(|tile| tile.window().id() == window)
+    }
+
+    fn floating_idx(&self, window: &W::Id) -> Option<usize> {
+        self.floating_tiles()
+            .position(|tile| tile.window().id() == window)
+    }
+
+    pub fn is_floating_active(&self) -> bool {
+        self.floating_is_active
+    }
+
+    /// Returns whether column actions have a column to act on.
+    ///
+    /// They don't while a floating window has the focus, since the active column is behind it.
+    fn has_active_column(&self) -> bool {
+        !self.floating_is_active && !self.columns.is_empty()
+    }
+
     pub fn current_output(&self) -> Option<&Output> {
         self.output.as_ref()
     }
@@ -557,6 +1103,8 @@ impl<W: LayoutElement> Workspace<W> {
         size: Size<f64, Logical>,
         working_area: Rectangle<f64, Logical>,
     ) {
+        let (size, working_area) = layout_view(size, working_area, self.zoom, self.transposed);
+
         let scale_transform_changed = self.transform != transform
             || self.scale.integer_scale() != scale.integer_scale()
             || self.scale.fractional_scale() != scale.fractional_scale();
@@ -572,12 +1120,25 @@ impl<W: LayoutElement> Workspace<W> {
         self.working_area = working_area;
 
         if fractional_scale_changed {
-            // Options need to be recomputed for the new scale.
-            self.update_config(self.base_options.clone());
+            // Options need to be recomputed for the new scale, and columns need the new scale
+            // even if the options round to the same values.
+            self.resolve_options(true);
+        } else if self.base_options.transpose_portrait_outputs {
+            // The output may have been rotated between landscape and portrait.
+            self.resolve_options(false);
         }
 
+        // Resize all columns together.
+        let transaction = Transaction::new();
         for col in &mut self.columns {
-            col.set_view_size(self.view_size, self.working_area);
+            col.set_view_size(self.view_size, self.working_area, transaction.clone());
+        }
+
+        // Keep the floating windows within the new working area.
+        for idx in 0..self.floating.len() {
+            let floating = &self.floating[idx];
+            let pos = self.clamp_floating_pos(floating.pos, floating.tile.tile_size());
+            self.floating[idx].pos = pos;
         }
 
         if scale_transform_changed {
@@ -593,7 +1154,9 @@ impl<W: LayoutElement> Workspace<W> {
 
     fn toplevel_bounds(&self, rules: &ResolvedWindowRules) -> Size<i32, Logical> {
         let border_config = rules.border.resolve_against(self.options.border);
-        compute_toplevel_bounds(border_config, self.working_area.size, self.options.gaps)
+        let bounds =
+            compute_toplevel_bounds(border_config, self.working_area.size, self.options.gaps);
+        self.options.swap_axes(bounds)
     }
 
     pub fn resolve_default_width(
@@ -633,7 +1196,8 @@ impl<W: LayoutElement> Workspace<W> {
             height -= border.width.0 * 2.;
         }
 
-        Size::from((width, max(height.floor() as i32, 1)))
+        let size = Size::from((width, max(height.floor() as i32, 1)));
+        self.options.swap_axes(size)
     }
 
     pub fn configure_new_window(
@@ -651,7 +1215,8 @@ impl<W: LayoutElement> Workspace<W> {
             .expect("no x11 support")
             .with_pending_state(|state| {
                 if state.states.contains(xdg_toplevel::State::Fullscreen) {
-                    state.size = Some(self.view_size.to_i32_round());
+                    // A fullscreen window suspends the zoom, so it gets the output size.
+                    state.size = Some(self.output_view_size().to_i32_round());
                 } else {
                     state.size = Some(self.new_window_size(width, rules));
                 }
@@ -790,6 +1355,11 @@ impl<W: LayoutElement> Workspace<W> {
         prev_idx: Option<usize>,
         config: niri_config::Animation,
     ) {
+        if self.columns[idx].always_center {
+            self.animate_view_offset_to_column_centered(current_x, idx, config);
+            return;
+        }
+
         match self.options.center_focused_column {
             CenterFocusedColumn::Always => {
                 self.animate_view_offset_to_column_centered(current_x, idx, config)
@@ -842,6 +1412,9 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     fn activate_column_with_anim_config(&mut self, idx: usize, config: niri_config::Animation) {
+        // Activating a column takes the focus away from the floating windows.
+        self.floating_is_active = false;
+
         if self.active_column_idx == idx {
             return;
         }
@@ -921,7 +1494,7 @@ impl<W: LayoutElement> Workspace<W> {
             // If this is the first window on an empty workspace, skip the animation from whatever
             // view_offset was left over.
             if was_empty {
-                if self.options.center_focused_column == CenterFocusedColumn::Always {
+                if self.is_centered(col_idx) {
                     self.view_offset =
                         -(self.working_area.size.w - width) / 2. - self.working_area.loc.x;
                 } else {
@@ -995,6 +1568,12 @@ impl<W: LayoutElement> Workspace<W> {
         width: ColumnWidth,
         is_full_width: bool,
     ) {
+        if self.is_floating(right_of) {
+            let activate = self.active_window().is_some_and(|win| win.id() == right_of);
+            self.add_window(window, activate, width, is_full_width);
+            return;
+        }
+
         self.enter_output_for_window(&window);
 
         let right_of_idx = self
@@ -1039,44 +1618,233 @@ impl<W: LayoutElement> Workspace<W> {
         }
     }
 
-    pub fn add_column(&mut self, mut column: Column<W>, activate: bool) {
-        for tile in &column.tiles {
-            self.enter_output_for_window(tile.window());
+    pub fn add_window_left_of(
+        &mut self,
+        left_of: &W::Id,
+        window: W,
+        width: ColumnWidth,
+        is_full_width: bool,
+    ) {
+        if self.is_floating(left_of) {
+            let activate = self.active_window().is_some_and(|win| win.id() == left_of);
+            self.add_window(window, activate, width, is_full_width);
+            return;
         }
 
-        let was_empty = self.columns.is_empty();
+        self.enter_output_for_window(&window);
 
-        let idx = if self.columns.is_empty() {
-            0
-        } else {
-            self.active_column_idx + 1
-        };
+        let idx = self
+            .columns
+            .iter()
+            .position(|col| col.contains(left_of))
+            .unwrap();
 
-        column.update_config(self.scale.fractional_scale(), self.options.clone());
-        column.set_view_size(self.view_size, self.working_area);
-        let width = column.width();
+        let column = Column::new(
+            window,
+            self.view_size,
+            self.working_area,
+            self.scale.fractional_scale(),
+            self.options.clone(),
+            width,
+            is_full_width,
+            true,
+        );
         self.data.insert(idx, ColumnData::new(&column));
         self.columns.insert(idx, column);
 
-        if activate {
-            // If this is the first window on an empty workspace, skip the animation from whatever
-            // view_offset was left over.
-            if was_empty {
-                if self.options.center_focused_column == CenterFocusedColumn::Always {
-                    self.view_offset =
-                        -(self.working_area.size.w - width) / 2. - self.working_area.loc.x;
-                } else {
-                    // Try to make the code produce a left-aligned offset, even in presence of left
-                    // exclusive zones.
-                    self.view_offset = self.compute_new_view_offset_for_column(self.column_x(0), 0);
-                }
-                self.view_offset_adj = None;
-            }
-
-            let prev_offset = (!was_empty).then(|| self.static_view_offset());
+        // The columns from left_of onwards moved one to the right.
+        let was_active = self.active_column_idx == idx;
+        if idx <= self.active_column_idx {
+            self.active_column_idx += 1;
+        }
 
+        // Activate the new window if left_of was active.
+        if was_active {
+            let prev_offset = self.static_view_offset();
             self.activate_column(idx);
-            self.activate_prev_column_on_removal = prev_offset;
+            self.activate_prev_column_on_removal = Some(prev_offset);
+        }
+
+        // Animate movement of other columns.
+        let offset = self.column_x(idx + 1) - self.column_x(idx);
+        if self.active_column_idx <= idx {
+            for col in &mut self.columns[idx + 1..] {
+                col.animate_move_from(-offset);
+            }
+        } else {
+            for col in &mut self.columns[..idx] {
+                col.animate_move_from(offset);
+            }
+        }
+    }
+
+    /// Adds a window at the bottom of the column containing `column_of`.
+    ///
+    /// A floating window has no column, so the window then opens in a new column instead.
+    pub fn add_window_to_column(&mut self, column_of: &W::Id, window: W) {
+        if self.is_floating(column_of) {
+            let activate = self
+                .active_window()
+                .is_some_and(|win| win.id() == column_of);
+            let width = self.options.resolve_column_width(&window, None);
+            self.add_window(window, activate, width, false);
+            return;
+        }
+
+        self.enter_output_for_window(&window);
+
+        let col_idx = self
+            .columns
+            .iter()
+            .position(|col| col.contains(column_of))
+            .unwrap();
+
+        let tile = Tile::new(window, self.scale.fractional_scale(), self.options.clone());
+
+        let prev_next_x = self.column_x(col_idx + 1);
+
+        let column = &mut self.columns[col_idx];
+        column.add_tile(tile, true);
+
+        // Activate the new window if column_of was active.
+        if self.active_column_idx == col_idx
+            && column.tiles[column.active_tile_idx].window().id() == column_of
+        {
+            column.active_tile_idx = column.tiles.len() - 1;
+        }
+
+        self.data[col_idx].update(column);
+
+        // The new window could've increased the column width if it had a larger min width. Move
+        // the next columns to account for this.
+        let offset_next = prev_next_x - self.column_x(col_idx + 1);
+        for col in &mut self.columns[col_idx + 1..] {
+            col.animate_move_from(offset_next);
+        }
+    }
+
+    /// Adds a window floating above the columns at its own size.
+    ///
+    /// A window with a parent on this workspace is placed according to the dialog placement
+    /// option, where `pointer` is the pointer position within the output if it's on this
+    /// workspace's output. Other windows are centered within the working area.
+    pub fn add_floating_window(
+        &mut self,
+        window: W,
+        parent: Option<&W::Id>,
+        activate: bool,
+        pointer: Option<Point<f64, Logical>>,
+    ) {
+        self.enter_output_for_window(&window);
+
+        let tile = Tile::new(window, self.scale.fractional_scale(), self.options.clone());
+        let size = tile.tile_size();
+
+        let parent_area = parent.and_then(|parent| {
+            self.tiles_with_render_positions()
+                .find(|(tile, _)| tile.window().id() == parent)
+                .map(|(tile, pos)| Rectangle::from_loc_and_size(pos, tile.tile_size()))
+        });
+        let center_in = |area: Rectangle<f64, Logical>| {
+            area.loc + (area.size.to_point() - size.to_point()).downscale(2.)
+        };
+
+        let pos = match (parent_area, self.options.dialog_placement) {
+            // Dialogs without a parent open in the middle of the working area.
+            (None, _) => center_in(self.working_area),
+            (Some(area), DialogPlacement::CenterOnParent) => center_in(area),
+            (Some(area), DialogPlacement::Cascade) => {
+                let loc = self
+                    .floating
+                    .last()
+                    .map_or(area.loc, |floating| floating.pos);
+                loc + Point::from((DIALOG_CASCADE_OFFSET, DIALOG_CASCADE_OFFSET))
+            }
+            (Some(area), DialogPlacement::AtPointer) => match pointer {
+                Some(pointer) => {
+                    let pointer = self.options.swap_axes(pointer.downscale(self.zoom));
+                    pointer - size.to_point().downscale(2.)
+                }
+                // The pointer is on another output.
+                None => center_in(area),
+            },
+        };
+        let pos = self.clamp_floating_pos(pos, size);
+
+        let floating = FloatingTile {
+            tile,
+            pos,
+            move_offset: None,
+        };
+
+        if activate || !self.floating_is_active {
+            self.floating.push(floating);
+        } else {
+            // Keep the focused floating window on top.
+            self.floating.insert(self.floating.len() - 1, floating);
+        }
+
+        if activate {
+            self.floating_is_active = true;
+        }
+    }
+
+    /// Moves a floating tile of this size into the working area as far as it fits.
+    fn clamp_floating_pos(
+        &self,
+        pos: Point<f64, Logical>,
+        size: Size<f64, Logical>,
+    ) -> Point<f64, Logical> {
+        let area = self.working_area;
+        let x = f64::max(
+            area.loc.x,
+            f64::min(pos.x, area.loc.x + area.size.w - size.w),
+        );
+        let y = f64::max(
+            area.loc.y,
+            f64::min(pos.y, area.loc.y + area.size.h - size.h),
+        );
+        Point::from((x, y))
+    }
+
+    pub fn add_column(&mut self, mut column: Column<W>, activate: bool) {
+        for tile in &column.tiles {
+            self.enter_output_for_window(tile.window());
+        }
+
+        let was_empty = self.columns.is_empty();
+
+        let idx = if self.columns.is_empty() {
+            0
+        } else {
+            self.active_column_idx + 1
+        };
+
+        column.update_config(self.scale.fractional_scale(), self.options.clone());
+        column.set_view_size(self.view_size, self.working_area, Transaction::new());
+        let width = column.width();
+        self.data.insert(idx, ColumnData::new(&column));
+        self.columns.insert(idx, column);
+
+        if activate {
+            // If this is the first window on an empty workspace, skip the animation from whatever
+            // view_offset was left over.
+            if was_empty {
+                if self.is_centered(idx) {
+                    self.view_offset =
+                        -(self.working_area.size.w - width) / 2. - self.working_area.loc.x;
+                } else {
+                    // Try to make the code produce a left-aligned offset, even in presence of left
+                    // exclusive zones.
+                    self.view_offset = self.compute_new_view_offset_for_column(self.column_x(0), 0);
+                }
+                self.view_offset_adj = None;
+            }
+
+            let prev_offset = (!was_empty).then(|| self.static_view_offset());
+
+            self.activate_column(idx);
+            self.activate_prev_column_on_removal = prev_offset;
         }
 
         // Animate movement of other columns.
@@ -1162,6 +1930,9 @@ impl<W: LayoutElement> Workspace<W> {
             self.columns.remove(column_idx);
             self.data.remove(column_idx);
             if self.columns.is_empty() {
+                if self.created_on_demand && self.floating.is_empty() {
+                    self.unname();
+                }
                 return tile;
             }
 
@@ -1255,6 +2026,9 @@ impl<W: LayoutElement> Workspace<W> {
         }
 
         if self.columns.is_empty() {
+            if self.created_on_demand && self.floating.is_empty() {
+                self.unname();
+            }
             return column;
         }
 
@@ -1286,6 +2060,10 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn remove_window(&mut self, window: &W::Id) -> W {
+        if let Some(idx) = self.floating_idx(window) {
+            return self.remove_floating_by_idx(idx).into_window();
+        }
+
         let column_idx = self
             .columns
             .iter()
@@ -1293,12 +2071,121 @@ impl<W: LayoutElement> Workspace<W> {
             .unwrap();
         let column = &self.columns[column_idx];
 
+        // Removing a tiled window doesn't take the focus away from the floating windows.
+        let floating_is_active = self.floating_is_active;
+
         let window_idx = column.position(window).unwrap();
-        self.remove_tile_by_idx(column_idx, window_idx, None)
-            .into_window()
+        let tile = self.remove_tile_by_idx(column_idx, window_idx, None);
+        self.floating_is_active = floating_is_active;
+        tile.into_window()
+    }
+
+    /// Removes the focused window, which is the topmost floating one if it has the focus.
+    pub fn remove_active_window(&mut self) -> Option<MovedWindow<W>> {
+        if self.floating_is_active {
+            let tile = self.remove_floating_by_idx(self.floating.len() - 1);
+            return Some(MovedWindow::Floating(tile.into_window()));
+        }
+
+        if self.columns.is_empty() {
+            return None;
+        }
+
+        let column = &self.columns[self.active_column_idx];
+        let width = column.width;
+        let is_full_width = column.is_full_width;
+        let window = self
+            .remove_tile_by_idx(self.active_column_idx, column.active_tile_idx, None)
+            .into_window();
+        Some(MovedWindow::Tiled {
+            window,
+            width,
+            is_full_width,
+        })
+    }
+
+    fn remove_floating_by_idx(&mut self, idx: usize) -> Tile<W> {
+        let tile = self.floating.remove(idx).tile;
+
+        if let Some(output) = &self.output {
+            tile.window().output_leave(output);
+        }
+
+        // Stop interactive resize.
+        if let Some(resize) = &self.interactive_resize {
+            if tile.window().id() == &resize.window {
+                self.interactive_resize = None;
+            }
+        }
+
+        // The next floating window down takes the focus, if there is one.
+        self.floating_is_active &= !self.floating.is_empty();
+
+        if self.created_on_demand && !self.has_windows() {
+            self.unname();
+        }
+
+        tile
+    }
+
+    /// Prepares for removing a closing window by applying its `on-close` focus rule.
+    ///
+    /// The rule only matters when the window is alone in the active column, since otherwise the
+    /// focus stays in the column.
+    pub fn prepare_close(&mut self, window: &W::Id) {
+        let col_idx = self.active_column_idx;
+        let Some(col) = self.columns.get(col_idx) else {
+            return;
+        };
+        if col.tiles.len() != 1 || !col.contains(window) {
+            return;
+        }
+
+        match col.tiles[0].window().rules().on_close {
+            Some(OnClose::MoveFocusLeft) if col_idx > 0 => {
+                // Keep the view in place while switching to the previous column.
+                let offset = self.column_x(col_idx) - self.column_x(col_idx - 1);
+                self.activate_prev_column_on_removal = Some(self.static_view_offset() + offset);
+            }
+            Some(OnClose::MoveFocusRight) => {
+                self.activate_prev_column_on_removal = None;
+            }
+            _ => (),
+        }
     }
 
     pub fn update_window(&mut self, window: &W::Id, serial: Option<Serial>) {
+        if let Some(idx) = self.floating_idx(window) {
+            let floating = &mut self.floating[idx];
+            let prev_size = floating.tile.tile_size();
+            let resize = floating.tile.window().interactive_resize_data();
+
+            if let Some(serial) = serial {
+                floating.tile.window_mut().update_interactive_resize(serial);
+            }
+            floating.tile.update_window();
+
+            // Keep the edges opposite to the dragged ones in place during an interactive resize,
+            // and the center of the window otherwise.
+            let size = floating.tile.tile_size();
+            let delta = prev_size.to_point() - size.to_point();
+            match resize {
+                Some(resize) => {
+                    if resize.edges.contains(ResizeEdge::LEFT) {
+                        floating.pos.x += delta.x;
+                    }
+                    if resize.edges.contains(ResizeEdge::TOP) {
+                        floating.pos.y += delta.y;
+                    }
+                }
+                None => floating.pos += delta.downscale(2.),
+            }
+
+            let pos = self.clamp_floating_pos(self.floating[idx].pos, size);
+            self.floating[idx].pos = pos;
+            return;
+        }
+
         let (col_idx, column) = self
             .columns
             .iter_mut()
@@ -1357,7 +2244,7 @@ impl<W: LayoutElement> Workspace<W> {
             if let Some(resize) = resize {
                 // If this is an interactive resize commit of an active window, then we need to
                 // either preserve the view offset or adjust it accordingly.
-                let centered = self.options.center_focused_column == CenterFocusedColumn::Always;
+                let centered = self.is_centered(col_idx);
 
                 let width = self.data[col_idx].width;
                 let offset = if centered {
@@ -1410,6 +2297,14 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn activate_window(&mut self, window: &W::Id) {
+        if let Some(idx) = self.floating_idx(window) {
+            // Raise the window above the other floating windows.
+            let floating = self.floating.remove(idx);
+            self.floating.push(floating);
+            self.floating_is_active = true;
+            return;
+        }
+
         let column_idx = self
             .columns
             .iter()
@@ -1421,6 +2316,115 @@ impl<W: LayoutElement> Workspace<W> {
         self.activate_column(column_idx);
     }
 
+    /// Exchanges the places of two windows on this workspace.
+    ///
+    /// Each window takes the size of the other's place.
+    pub fn swap_windows(&mut self, a: &W::Id, b: &W::Id) {
+        let (Some((col_a, _)), Some((col_b, _))) = (self.tile_idx(a), self.tile_idx(b)) else {
+            return;
+        };
+
+        let mut tiles = self
+            .columns
+            .iter_mut()
+            .flat_map(|col| &mut col.tiles)
+            .filter(|tile| tile.window().id() == a || tile.window().id() == b);
+        let (Some(x), Some(y)) = (tiles.next(), tiles.next()) else {
+            return;
+        };
+        x.swap_windows(y);
+
+        self.on_windows_swapped(&[col_a, col_b]);
+    }
+
+    /// Exchanges the places of a window on this workspace and a window on another workspace.
+    ///
+    /// Each window takes the size of the other's place.
+    pub fn swap_windows_with(&mut self, window: &W::Id, other: &mut Self, other_window: &W::Id) {
+        let (Some((col, tile)), Some((other_col, other_tile))) =
+            (self.tile_idx(window), other.tile_idx(other_window))
+        else {
+            return;
+        };
+
+        if let Some(output) = &self.output {
+            self.columns[col].tiles[tile].window().output_leave(output);
+        }
+        if let Some(output) = &other.output {
+            other.columns[other_col].tiles[other_tile]
+                .window()
+                .output_leave(output);
+        }
+
+        self.columns[col].tiles[tile].swap_windows(&mut other.columns[other_col].tiles[other_tile]);
+
+        self.enter_output_for_window(self.columns[col].tiles[tile].window());
+        other.enter_output_for_window(other.columns[other_col].tiles[other_tile].window());
+
+        self.on_windows_swapped(&[col]);
+        other.on_windows_swapped(&[other_col]);
+    }
+
+    fn on_windows_swapped(&mut self, column_idxs: &[usize]) {
+        // The resize would continue on the window that took the place.
+        self.interactive_resize = None;
+
+        for &idx in column_idxs {
+            let column = &mut self.columns[idx];
+            column.update_tile_sizes(false);
+            self.data[idx].update(column);
+        }
+    }
+
+    /// Returns the column and tile indices of a window.
+    fn tile_idx(&self, window: &W::Id) -> Option<(usize, usize)> {
+        self.columns
+            .iter()
+            .enumerate()
+            .find_map(|(col_idx, col)| Some((col_idx, col.position(window)?)))
+    }
+
+    /// Returns the static location of a window within its column or, for a floating window, within
+    /// the view.
+    ///
+    /// The horizontal location of a floating window is within its tile, same as for the columns,
+    /// which scroll horizontally.
+    pub fn window_loc(&self, window: &W::Id) -> Option<Point<f64, Logical>> {
+        if let Some(idx) = self.floating_idx(window) {
+            let floating = &self.floating[idx];
+            let loc = floating.tile.window_loc();
+            let loc = Point::from((loc.x, floating.pos.y + loc.y));
+            return Some(self.options.swap_axes(loc));
+        }
+
+        let (col_idx, tile_idx) = self.tile_idx(window)?;
+        Some(self.columns[col_idx].window_loc(tile_idx))
+    }
+
+    /// Starts the open animation of a new window.
+    ///
+    /// Returns whether the window is on this workspace.
+    pub fn start_open_animation(
+        &mut self,
+        window: &W::Id,
+        origin: Option<Point<f64, Logical>>,
+    ) -> bool {
+        let floating = self.floating.iter_mut().map(|floating| &mut floating.tile);
+        for tile in self
+            .columns
+            .iter_mut()
+            .flat_map(|col| &mut col.tiles)
+            .chain(floating)
+        {
+            if tile.window().id() == window {
+                tile.start_open_animation(origin);
+                return true;
+            }
+        }
+
+        false
+    }
+
     pub fn store_unmap_snapshot_if_empty(&mut self, renderer: &mut GlesRenderer, window: &W::Id) {
         let output_scale = Scale::from(self.scale.fractional_scale());
         let view_size = self.view_size();
@@ -1436,12 +2440,16 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn clear_unmap_snapshot(&mut self, window: &W::Id) {
-        for col in &mut self.columns {
-            for tile in &mut col.tiles {
-                if tile.window().id() == window {
-                    let _ = tile.take_unmap_snapshot();
-                    return;
-                }
+        let floating = self.floating.iter_mut().map(|floating| &mut floating.tile);
+        for tile in self
+            .columns
+            .iter_mut()
+            .flat_map(|col| &mut col.tiles)
+            .chain(floating)
+        {
+            if tile.window().id() == window {
+                let _ = tile.take_unmap_snapshot();
+                return;
             }
         }
     }
@@ -1464,24 +2472,15 @@ impl<W: LayoutElement> Workspace<W> {
 
         let tile_size = tile.tile_size();
 
-        let (col_idx, tile_idx) = self
-            .columns
-            .iter()
-            .enumerate()
-            .find_map(|(col_idx, col)| {
-                col.tiles
-                    .iter()
-                    .position(|tile| tile.window().id() == window)
-                    .map(move |tile_idx| (col_idx, tile_idx))
-            })
-            .unwrap();
-
-        let col = &self.columns[col_idx];
-        let removing_last = col.tiles.len() == 1;
-
         tile_pos.x += self.view_pos();
 
-        if col_idx < self.active_column_idx {
+        // Floating windows don't leave a gap in the columns.
+        let tile_idx = self.tile_idx(window);
+        if let Some((col_idx, tile_idx)) = tile_idx.filter(|(idx, _)| *idx < self.active_column_idx)
+        {
+            let col = &self.columns[col_idx];
+            let removing_last = col.tiles.len() == 1;
+
             let offset = if removing_last {
                 self.column_x(col_idx + 1) - self.column_x(col_idx)
             } else {
@@ -1502,6 +2501,8 @@ impl<W: LayoutElement> Workspace<W> {
 
         let anim = Animation::new(0., 1., 0., self.options.animations.window_close.anim);
 
+        let tile_size = self.options.swap_axes(tile_size);
+        let tile_pos = self.options.swap_axes(tile_pos);
         let res = ClosingWindow::new(renderer, snapshot, output_scale, tile_size, tile_pos, anim);
         match res {
             Ok(closing) => {
@@ -1513,7 +2514,7 @@ impl<W: LayoutElement> Workspace<W> {
         }
     }
 
-    #[cfg(test)]
+    #[cfg(any(test, feature = "fuzzing"))]
     pub fn verify_invariants(&self) {
         use approx::assert_abs_diff_eq;
 
@@ -1559,11 +2560,22 @@ impl<W: LayoutElement> Workspace<W> {
             }
         }
 
+        assert!(
+            !self.floating_is_active || !self.floating.is_empty(),
+            "floating windows can only be active when there are some"
+        );
+
+        for floating in &self.floating {
+            assert!(Rc::ptr_eq(&self.options, &floating.tile.options));
+            assert_eq!(self.scale.fractional_scale(), floating.tile.scale());
+        }
+
         if let Some(resize) = &self.interactive_resize {
             assert!(
                 self.columns
                     .iter()
                     .flat_map(|col| &col.tiles)
+                    .chain(self.floating_tiles())
                     .any(|tile| tile.window().id() == &resize.window),
                 "interactive resize window must be present on the workspace"
             );
@@ -1594,6 +2606,16 @@ impl<W: LayoutElement> Workspace<W> {
         self.activate_column(self.columns.len() - 1);
     }
 
+    /// Focuses the column at the index counting from 1, or the last column if there are fewer.
+    pub fn focus_column(&mut self, index: usize) {
+        if self.columns.is_empty() {
+            return;
+        }
+
+        let idx = min(index.saturating_sub(1), self.columns.len() - 1);
+        self.activate_column(idx);
+    }
+
     pub fn focus_column_right_or_first(&mut self) {
         if self.columns.is_empty() {
             return;
@@ -1621,6 +2643,7 @@ impl<W: LayoutElement> Workspace<W> {
             return;
         }
 
+        self.floating_is_active = false;
         self.columns[self.active_column_idx].focus_down();
     }
 
@@ -1629,11 +2652,12 @@ impl<W: LayoutElement> Workspace<W> {
             return;
         }
 
+        self.floating_is_active = false;
         self.columns[self.active_column_idx].focus_up();
     }
 
     fn move_column_to(&mut self, new_idx: usize) {
-        if self.active_column_idx == new_idx {
+        if !self.has_active_column() || self.active_column_idx == new_idx {
             return;
         }
 
@@ -1678,7 +2702,7 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn move_right(&mut self) {
-        if self.columns.is_empty() {
+        if !self.has_active_column() {
             return;
         }
 
@@ -1691,7 +2715,7 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn move_column_to_last(&mut self) {
-        if self.columns.is_empty() {
+        if !self.has_active_column() {
             return;
         }
 
@@ -1699,8 +2723,18 @@ impl<W: LayoutElement> Workspace<W> {
         self.move_column_to(new_idx);
     }
 
+    /// Moves the active column to the index counting from 1, or to the end if there are fewer.
+    pub fn move_column_to_index(&mut self, index: usize) {
+        if !self.has_active_column() {
+            return;
+        }
+
+        let new_idx = min(index.saturating_sub(1), self.columns.len() - 1);
+        self.move_column_to(new_idx);
+    }
+
     pub fn move_down(&mut self) {
-        if self.columns.is_empty() {
+        if !self.has_active_column() {
             return;
         }
 
@@ -1708,7 +2742,7 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn move_up(&mut self) {
-        if self.columns.is_empty() {
+        if !self.has_active_column() {
             return;
         }
 
@@ -1716,7 +2750,7 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn consume_or_expel_window_left(&mut self) {
-        if self.columns.is_empty() {
+        if !self.has_active_column() {
             return;
         }
 
@@ -1794,7 +2828,7 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn consume_or_expel_window_right(&mut self) {
-        if self.columns.is_empty() {
+        if !self.has_active_column() {
             return;
         }
 
@@ -1865,7 +2899,7 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn consume_into_column(&mut self) {
-        if self.columns.len() < 2 {
+        if !self.has_active_column() || self.columns.len() < 2 {
             return;
         }
 
@@ -1912,7 +2946,7 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn expel_from_column(&mut self) {
-        if self.columns.is_empty() {
+        if !self.has_active_column() {
             return;
         }
 
@@ -2021,26 +3055,40 @@ impl<W: LayoutElement> Workspace<W> {
         let offsets = self.column_xs_in_render_order(self.data.iter().copied());
 
         let (first, rest) = self.columns.split_at(self.active_column_idx);
-        let (active, rest) = rest.split_at(1);
+        let (active, rest) = rest.split_at(min(1, rest.len()));
 
         let tiles = active.iter().chain(first).chain(rest);
         zip(tiles, offsets)
     }
 
-    fn columns_in_render_order_mut(&mut self) -> impl Iterator<Item = (&mut Column<W>, f64)> + '_ {
-        let offsets = self.column_xs_in_render_order(self.data.iter().copied());
-
-        let (first, rest) = self.columns.split_at_mut(self.active_column_idx);
-        let (active, rest) = rest.split_at_mut(1);
-
-        let tiles = active.iter_mut().chain(first).chain(rest);
-        zip(tiles, offsets)
+    fn columns_with_render_positions(
+        &self,
+    ) -> impl Iterator<Item = (&Column<W>, Point<f64, Logical>)> {
+        let scale = self.scale.fractional_scale();
+        let view_off = Point::from((-self.view_pos(), 0.));
+        self.columns_in_render_order().map(move |(col, col_x)| {
+            let col_off = Point::from((col_x, 0.));
+            let pos = view_off + col_off + col.render_offset();
+            // Round to physical pixels.
+            let pos = pos.to_physical_precise_round(scale).to_logical(scale);
+            (col, pos)
+        })
     }
 
+    /// Returns the tiles with their positions relative to the view, from the top to the bottom.
+    ///
+    /// The floating tiles come first, above the columns.
     fn tiles_with_render_positions(&self) -> impl Iterator<Item = (&Tile<W>, Point<f64, Logical>)> {
         let scale = self.scale.fractional_scale();
         let view_off = Point::from((-self.view_pos(), 0.));
-        self.columns_in_render_order()
+        let floating = self.floating.iter().rev().map(move |floating| {
+            let pos = floating.pos + floating.tile.render_offset();
+            // Round to physical pixels.
+            let pos = pos.to_physical_precise_round(scale).to_logical(scale);
+            (&floating.tile, pos)
+        });
+        let tiled = self
+            .columns_in_render_order()
             .flat_map(move |(col, col_x)| {
                 let col_off = Point::from((col_x, 0.));
                 let col_render_off = col.render_offset();
@@ -2050,7 +3098,8 @@ impl<W: LayoutElement> Workspace<W> {
                     let pos = pos.to_physical_precise_round(scale).to_logical(scale);
                     (tile, pos)
                 })
-            })
+            });
+        floating.chain(tiled)
     }
 
     fn tiles_with_render_positions_mut(
@@ -2059,20 +3108,46 @@ impl<W: LayoutElement> Workspace<W> {
     ) -> impl Iterator<Item = (&mut Tile<W>, Point<f64, Logical>)> {
         let scale = self.scale.fractional_scale();
         let view_off = Point::from((-self.view_pos(), 0.));
-        self.columns_in_render_order_mut()
-            .flat_map(move |(col, col_x)| {
-                let col_off = Point::from((col_x, 0.));
-                let col_render_off = col.render_offset();
-                col.tiles_in_render_order_mut()
-                    .map(move |(tile, tile_off)| {
-                        let mut pos =
-                            view_off + col_off + col_render_off + tile_off + tile.render_offset();
-                        // Round to physical pixels.
-                        if round {
-                            pos = pos.to_physical_precise_round(scale).to_logical(scale);
-                        }
-                        (tile, pos)
-                    })
+
+        // Borrow the columns on their own, so that the floating tiles can be borrowed alongside.
+        let offsets = self.column_xs_in_render_order(self.data.iter().copied());
+        let (first, rest) = self.columns.split_at_mut(self.active_column_idx);
+        let (active, rest) = rest.split_at_mut(min(1, rest.len()));
+        let columns = zip(active.iter_mut().chain(first).chain(rest), offsets);
+
+        let floating = self.floating.iter_mut().rev().map(move |floating| {
+            let mut pos = floating.pos + floating.tile.render_offset();
+            // Round to physical pixels.
+            if round {
+                pos = pos.to_physical_precise_round(scale).to_logical(scale);
+            }
+            (&mut floating.tile, pos)
+        });
+        let tiled = columns.flat_map(move |(col, col_x)| {
+            let col_off = Point::from((col_x, 0.));
+            let col_render_off = col.render_offset();
+            col.tiles_in_render_order_mut()
+                .map(move |(tile, tile_off)| {
+                    let mut pos =
+                        view_off + col_off + col_render_off + tile_off + tile.render_offset();
+                    // Round to physical pixels.
+                    if round {
+                        pos = pos.to_physical_precise_round(scale).to_logical(scale);
+                    }
+                    (tile, pos)
+                })
+        });
+        floating.chain(tiled)
+    }
+
+    /// Returns the windows visible in the view along with their geometry relative to the view.
+    pub fn visible_windows(&self) -> impl Iterator<Item = (&W, Rectangle<f64, Logical>)> {
+        let view = Rectangle::from_loc_and_size((0., 0.), self.view_size);
+        self.tiles_with_render_positions()
+            .filter_map(move |(tile, pos)| {
+                let geo = Rectangle::from_loc_and_size(pos + tile.window_loc(), tile.window_size());
+                let geo_on_output = self.options.swap_axes(geo);
+                geo.overlaps(view).then_some((tile.window(), geo_on_output))
             })
     }
 
@@ -2080,52 +3155,124 @@ impl<W: LayoutElement> Workspace<W> {
     ///
     /// During animations, assumes the final view position.
     pub fn active_tile_visual_rectangle(&self) -> Option<Rectangle<f64, Logical>> {
-        let col = self.columns.get(self.active_column_idx)?;
+        let tile_rect = if self.floating_is_active {
+            let floating = self.floating.last()?;
+            Rectangle::from_loc_and_size(floating.pos, floating.tile.tile_size())
+        } else {
+            let col = self.columns.get(self.active_column_idx)?;
 
-        let final_view_offset = self
-            .view_offset_adj
-            .as_ref()
-            .map_or(self.view_offset, |adj| adj.target_view_offset());
-        let view_off = Point::from((-final_view_offset, 0.));
+            let final_view_offset = self
+                .view_offset_adj
+                .as_ref()
+                .map_or(self.view_offset, |adj| adj.target_view_offset());
+            let view_off = Point::from((-final_view_offset, 0.));
 
-        let (tile, tile_off) = col.tiles().nth(col.active_tile_idx).unwrap();
+            let (tile, tile_off) = col.tiles().nth(col.active_tile_idx).unwrap();
 
-        let tile_pos = view_off + tile_off;
-        let tile_size = tile.tile_size();
-        let tile_rect = Rectangle::from_loc_and_size(tile_pos, tile_size);
+            let tile_pos = view_off + tile_off;
+            let tile_size = tile.tile_size();
+            Rectangle::from_loc_and_size(tile_pos, tile_size)
+        };
 
         let view = Rectangle::from_loc_and_size((0., 0.), self.view_size);
-        view.intersection(tile_rect)
+        let rect = view.intersection(tile_rect)?;
+        let rect = self.options.swap_axes(rect);
+        Some(Rectangle::from_loc_and_size(
+            rect.loc.upscale(self.zoom),
+            rect.size.upscale(self.zoom),
+        ))
     }
 
     pub fn window_under(
         &self,
         pos: Point<f64, Logical>,
     ) -> Option<(&W, Option<Point<f64, Logical>>)> {
-        if self.columns.is_empty() {
+        if !self.has_windows() {
             return None;
         }
 
+        let output_pos = pos;
+        let layout_pos = pos.downscale(self.zoom);
+        let pos = self.options.swap_axes(layout_pos);
+
         self.tiles_with_render_positions()
             .find_map(|(tile, tile_pos)| {
                 let pos_within_tile = pos - tile_pos;
 
                 if tile.is_in_input_region(pos_within_tile) {
-                    let pos_within_surface = tile_pos + tile.buf_loc();
-                    return Some((tile.window(), Some(pos_within_surface)));
+                    // Place the surface such that the pointer lands at the right spot within it
+                    // when the workspace is zoomed or the window is scaled down. This moves
+                    // together with the pointer, which works because the surface location is
+                    // recomputed on every motion.
+                    let window_loc = self.options.swap_axes(tile_pos + tile.window_loc());
+                    let buf_loc = self.options.swap_axes(tile.buf_loc() - tile.window_loc());
+                    let pos_within_window =
+                        (layout_pos - window_loc).downscale(tile.window_render_scale());
+                    let surface_pos = output_pos - (pos_within_window - buf_loc);
+                    return Some((tile.window(), Some(surface_pos)));
                 } else if tile.is_in_activation_region(pos_within_tile) {
                     return Some((tile.window(), None));
                 }
 
                 None
             })
+            .or_else(|| {
+                // Clicking a tab indicator segment activates the corresponding window.
+                self.columns_with_render_positions()
+                    .find_map(|(col, col_pos)| {
+                        let idx = col.tab_indicator.hit(pos - col_pos)?;
+                        Some((col.tiles[idx].window(), None))
+                    })
+            })
+    }
+
+    /// Appends the visible tiles of this workspace to the snapshot, in column order.
+    ///
+    /// `ws_pos` is the workspace position within the output.
+    pub fn snapshot_tiles(
+        &self,
+        ws_pos: Point<f64, Logical>,
+        is_active: bool,
+        tiles: &mut Vec<TileSnapshot<W::Id>>,
+    ) where
+        W::Id: Clone,
+    {
+        let active = self.active_window().map(|win| win.id());
+        let view = Rectangle::from_loc_and_size((0., 0.), self.view_size);
+
+        for (tile, tile_pos) in self.tiles_with_render_positions() {
+            let tile_rect = Rectangle::from_loc_and_size(tile_pos, tile.tile_size());
+            if !view.overlaps(tile_rect) {
+                continue;
+            }
+
+            let window_rect =
+                Rectangle::from_loc_and_size(tile_pos + tile.window_loc(), tile.window_size());
+            let zoomed = |rect: Rectangle<f64, Logical>| {
+                let rect = self.options.swap_axes(rect);
+                Rectangle::from_loc_and_size(
+                    rect.loc.upscale(self.zoom) + ws_pos,
+                    rect.size.upscale(self.zoom),
+                )
+            };
+            let id = tile.window().id();
+            tiles.push(TileSnapshot {
+                id: id.clone(),
+                tile: zoomed(tile_rect),
+                window: zoomed(window_rect),
+                is_active: is_active && active == Some(id),
+                is_fullscreen: tile.is_fullscreen(),
+            });
+        }
     }
 
     pub fn resize_edges_under(&self, pos: Point<f64, Logical>) -> Option<ResizeEdge> {
-        if self.columns.is_empty() {
+        if !self.has_windows() {
             return None;
         }
 
+        let pos = self.options.swap_axes(pos.downscale(self.zoom));
+
         self.tiles_with_render_positions()
             .find_map(|(tile, tile_pos)| {
                 let pos_within_tile = pos - tile_pos;
@@ -2148,48 +3295,280 @@ impl<W: LayoutElement> Workspace<W> {
                     } else if 2. * size.h / 3. < pos_within_tile.y {
                         edges |= ResizeEdge::BOTTOM;
                     }
-                    return Some(edges);
+                    return Some(self.options.swap_axes(edges));
                 }
 
-                None
-            })
+                None
+            })
+    }
+
+    /// Returns the window and edge to resize for the gap between tiles under `pos`.
+    ///
+    /// The gap to the right of a column resizes that column's width, and the gap below a tile
+    /// resizes that tile's height. Gaps at the outer edges of the workspace are not resize grips.
+    pub fn resize_grip_under(&self, pos: Point<f64, Logical>) -> Option<(&W, ResizeEdge)> {
+        if self.columns.is_empty() || self.options.gaps <= 0. {
+            return None;
+        }
+
+        let pos = self.options.swap_axes(pos.downscale(self.zoom));
+        let gaps = self.options.gaps;
+        let last_col = self.columns.last().unwrap();
+
+        self.columns_with_render_positions()
+            .find_map(|(col, col_pos)| {
+                if col.is_fullscreen {
+                    return None;
+                }
+
+                let pos = pos - col_pos;
+                let width = col.width();
+
+                if !ptr::eq(col, last_col) && width <= pos.x && pos.x < width + gaps {
+                    let tile = &col.tiles[col.active_tile_idx];
+                    let edges = self.options.swap_axes(ResizeEdge::RIGHT);
+                    return Some((tile.window(), edges));
+                }
+
+                if pos.x < 0. || width <= pos.x {
+                    return None;
+                }
+
+                let last_tile = col.tiles.last().unwrap();
+                col.tiles().find_map(|(tile, tile_off)| {
+                    if ptr::eq(tile, last_tile) {
+                        return None;
+                    }
+
+                    let y = pos.y - tile_off.y - tile.render_offset().y;
+                    let height = tile.tile_size().h;
+                    let edges = self.options.swap_axes(ResizeEdge::BOTTOM);
+                    (height <= y && y < height + gaps).then(|| (tile.window(), edges))
+                })
+            })
+    }
+
+    pub fn toggle_width(&mut self) {
+        if !self.has_active_column() {
+            return;
+        }
+
+        let col = &mut self.columns[self.active_column_idx];
+        col.toggle_width();
+
+        cancel_resize_for_column(&mut self.interactive_resize, col);
+    }
+
+    pub fn toggle_full_width(&mut self) {
+        if !self.has_active_column() {
+            return;
+        }
+
+        let col = &mut self.columns[self.active_column_idx];
+        col.toggle_full_width();
+
+        cancel_resize_for_column(&mut self.interactive_resize, col);
+    }
+
+    pub fn set_strategy(&mut self, strategy: Box<dyn LayoutStrategy>) {
+        self.strategy = strategy;
+        self.arranged_window_count = None;
+        self.arrange_columns();
+    }
+
+    /// Moves windows between columns to match the shape requested by the layout strategy.
+    ///
+    /// Windows keep their order and the focused window stays focused. Does nothing unless windows
+    /// were added or removed since the last arrangement, or the strategy changed.
+    fn arrange_columns(&mut self) {
+        let window_count = self.columns.iter().map(|col| col.tiles.len()).sum();
+        if self.arranged_window_count == Some(window_count) {
+            return;
+        }
+
+        let Some(shape) = self.strategy.arrange(window_count) else {
+            self.arranged_window_count = Some(window_count);
+            return;
+        };
+
+        let matches = self.columns.len() == shape.len()
+            && zip(&self.columns, &shape).all(|(col, shape)| col.tiles.len() == shape.tiles);
+        if matches {
+            self.arranged_window_count = Some(window_count);
+            return;
+        }
+
+        // Don't fight the user while they are resizing or have a fullscreen window. The columns
+        // are arranged once that is over.
+        if self.interactive_resize.is_some() || self.columns.iter().any(|col| col.is_fullscreen) {
+            return;
+        }
+
+        self.arranged_window_count = Some(window_count);
+
+        let mut active_idx = self.columns[..self.active_column_idx]
+            .iter()
+            .map(|col| col.tiles.len())
+            .sum::<usize>();
+        active_idx += self.columns[self.active_column_idx].active_tile_idx;
+
+        let current_x = self.view_pos();
+        self.activate_prev_column_on_removal = None;
+
+        for (col_idx, shape) in shape.iter().enumerate() {
+            // Pull windows from the next columns.
+            while self.columns[col_idx].tiles.len() < shape.tiles {
+                let tile = self.remove_tile_by_idx(col_idx + 1, 0, None);
+                let column = &mut self.columns[col_idx];
+                column.add_tile(tile, true);
+                self.data[col_idx].update(column);
+            }
+
+            // Push extra windows into new columns, which the next iterations will fill.
+            while self.columns[col_idx].tiles.len() > shape.tiles {
+                let last = self.columns[col_idx].tiles.len() - 1;
+                let tile = self.remove_tile_by_idx(col_idx, last, None);
+                self.add_tile_at(col_idx + 1, tile, false, shape.width, false, None);
+            }
+
+            let column = &mut self.columns[col_idx];
+            column.set_width(shape.width, true);
+            self.data[col_idx].update(column);
+        }
+
+        // Restore focus.
+        let mut col_idx = 0;
+        while active_idx >= self.columns[col_idx].tiles.len() {
+            active_idx -= self.columns[col_idx].tiles.len();
+            col_idx += 1;
+        }
+        self.columns[col_idx].active_tile_idx = active_idx;
+        self.active_column_idx = col_idx;
+        self.view_offset_before_fullscreen = None;
+        self.animate_view_offset_to_column(current_x, col_idx, None);
+    }
+
+    pub fn set_window_opacity(&mut self, change: OpacityChange) {
+        if self.floating_is_active {
+            self.floating.last_mut().unwrap().tile.set_opacity(change);
+            return;
+        }
+
+        if self.columns.is_empty() {
+            return;
+        }
+
+        let col = &mut self.columns[self.active_column_idx];
+        col.tiles[col.active_tile_idx].set_opacity(change);
+    }
+
+    pub fn reset_window_opacity(&mut self) {
+        if self.floating_is_active {
+            self.floating.last_mut().unwrap().tile.reset_opacity();
+            return;
+        }
+
+        if self.columns.is_empty() {
+            return;
+        }
+
+        let col = &mut self.columns[self.active_column_idx];
+        col.tiles[col.active_tile_idx].reset_opacity();
+    }
+
+    pub fn toggle_column_always_center(&mut self) {
+        if !self.has_active_column() {
+            return;
+        }
+
+        let col = &mut self.columns[self.active_column_idx];
+        col.always_center = !col.always_center;
+
+        self.animate_view_offset_to_column(self.view_pos(), self.active_column_idx, None);
+    }
+
+    /// Returns whether the column at this index is kept centered when focused.
+    fn is_centered(&self, col_idx: usize) -> bool {
+        self.options.center_focused_column == CenterFocusedColumn::Always
+            || self.columns[col_idx].always_center
+    }
+
+    pub fn toggle_auto_tile(&mut self) {
+        self.auto_tile = !self.auto_tile;
+        self.balance_columns();
+    }
+
+    pub fn is_auto_tile(&self) -> bool {
+        self.auto_tile
     }
 
-    pub fn toggle_width(&mut self) {
-        if self.columns.is_empty() {
+    /// Gives all columns equal widths that fill the view, if auto-tiling.
+    ///
+    /// This only happens while there are at most `auto_tile_max_columns` columns. Past that, the
+    /// columns keep their widths and the view scrolls as usual.
+    fn balance_columns(&mut self) {
+        // Don't fight the user while they are dragging the view or resizing.
+        if self.interactive_resize.is_some()
+            || matches!(self.view_offset_adj, Some(ViewOffsetAdjustment::Gesture(_)))
+        {
             return;
         }
 
-        let col = &mut self.columns[self.active_column_idx];
-        col.toggle_width();
+        let count = self.columns.len();
+        self.auto_tile_column_count = count;
 
-        cancel_resize_for_column(&mut self.interactive_resize, col);
+        if !self.auto_tile || count == 0 || count > self.options.auto_tile_max_columns {
+            return;
+        }
+
+        let width = ColumnWidth::Proportion(1. / count as f64);
+        let current_x = self.view_pos();
+
+        let mut changed = false;
+        for (col, data) in zip(&mut self.columns, &mut self.data) {
+            if col.is_fullscreen || (col.width == width && !col.is_full_width) {
+                continue;
+            }
+
+            col.set_width(width, true);
+            data.update(col);
+            changed = true;
+        }
+
+        if !changed {
+            return;
+        }
+
+        // Show all columns starting from the first one.
+        let idx = self.active_column_idx;
+        let new_view_offset = -self.options.gaps - self.working_area.loc.x - self.column_x(idx);
+        self.animate_view_offset(current_x, idx, new_view_offset);
     }
 
-    pub fn toggle_full_width(&mut self) {
-        if self.columns.is_empty() {
+    pub fn set_column_width(&mut self, change: SizeChange) {
+        if !self.has_active_column() {
             return;
         }
 
         let col = &mut self.columns[self.active_column_idx];
-        col.toggle_full_width();
+        col.set_column_width(change, None, true);
 
         cancel_resize_for_column(&mut self.interactive_resize, col);
     }
 
-    pub fn set_column_width(&mut self, change: SizeChange) {
-        if self.columns.is_empty() {
+    pub fn toggle_column_width(&mut self, a: SizeChange, b: SizeChange) {
+        if !self.has_active_column() {
             return;
         }
 
         let col = &mut self.columns[self.active_column_idx];
-        col.set_column_width(change, None, true);
+        col.toggle_column_width(a, b);
 
         cancel_resize_for_column(&mut self.interactive_resize, col);
     }
 
     pub fn set_window_height(&mut self, change: SizeChange) {
-        if self.columns.is_empty() {
+        if !self.has_active_column() {
             return;
         }
 
@@ -2199,8 +3578,33 @@ impl<W: LayoutElement> Workspace<W> {
         cancel_resize_for_column(&mut self.interactive_resize, col);
     }
 
+    /// Sets the height of a specific window, rather than the active one.
+    pub fn set_window_height_of(&mut self, window: &W::Id, change: SizeChange) {
+        let Some(col) = self.columns.iter_mut().find(|col| col.contains(window)) else {
+            return;
+        };
+
+        let tile_idx = col.position(window);
+        col.set_window_height(change, tile_idx, false);
+
+        cancel_resize_for_column(&mut self.interactive_resize, col);
+    }
+
+    /// Returns the column width and window height of the window, with presets resolved.
+    pub fn window_size_preference(&self, window: &W::Id) -> Option<(ColumnWidth, WindowHeight)> {
+        let col = self.columns.iter().find(|col| col.contains(window))?;
+        let tile_idx = col.position(window)?;
+
+        let width = match col.width {
+            ColumnWidth::Preset(idx) => self.options.preset_widths[idx],
+            width => width,
+        };
+
+        Some((width, col.data[tile_idx].height))
+    }
+
     pub fn reset_window_height(&mut self) {
-        if self.columns.is_empty() {
+        if !self.has_active_column() {
             return;
         }
 
@@ -2211,6 +3615,11 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn set_fullscreen(&mut self, window: &W::Id, is_fullscreen: bool) {
+        // Floating windows keep their own size.
+        if self.is_floating(window) {
+            return;
+        }
+
         let (mut col_idx, tile_idx) = self
             .columns
             .iter()
@@ -2270,6 +3679,9 @@ impl<W: LayoutElement> Workspace<W> {
         }
 
         col.set_fullscreen(is_fullscreen);
+        self.update_view_transform();
+
+        let col = &self.columns[col_idx];
 
         // If we quickly fullscreen and unfullscreen before any window has a chance to receive the
         // request, we need to reset the offset.
@@ -2285,6 +3697,10 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn toggle_fullscreen(&mut self, window: &W::Id) {
+        if self.is_floating(window) {
+            return;
+        }
+
         let col = self
             .columns
             .iter_mut()
@@ -2316,22 +3732,26 @@ impl<W: LayoutElement> Workspace<W> {
 
         let mut rv = vec![];
 
-        // Draw the closing windows on top.
+        // Draw the closing windows on top. They are positioned in the output coordinates.
         let view_rect = Rectangle::from_loc_and_size((self.view_pos(), 0.), self.view_size);
+        let view_rect = self.options.swap_axes(view_rect);
         for closing in self.closing_windows.iter().rev() {
             let elem = closing.render(renderer.as_gles_renderer(), view_rect, output_scale, target);
             rv.push(elem.into());
         }
 
-        if self.columns.is_empty() {
+        if let Some(elem) = self.render_insert_hint() {
+            rv.push(elem.into());
+        }
+
+        if !self.has_windows() {
             return rv;
         }
 
-        let mut first = true;
+        let active = self.active_window().map(|win| win.id());
         for (tile, tile_pos) in self.tiles_with_render_positions() {
-            // For the active tile (which comes first), draw the focus ring.
-            let focus_ring = first;
-            first = false;
+            // For the active tile, draw the focus ring.
+            let focus_ring = Some(tile.window().id()) == active;
 
             rv.extend(
                 tile.render(renderer, tile_pos, output_scale, focus_ring, target)
@@ -2339,9 +3759,58 @@ impl<W: LayoutElement> Workspace<W> {
             );
         }
 
+        for (col, col_pos) in self.columns_with_render_positions() {
+            rv.extend(col.tab_indicator.render(col_pos).map(Into::into));
+        }
+
+        if let Some(elem) = self.render_snap_indicator() {
+            rv.push(elem.into());
+        }
+
         rv
     }
 
+    fn render_insert_hint(&self) -> Option<SolidColorRenderElement> {
+        let area = self.insert_hint_area()?;
+        let loc = self.options.swap_axes(area.loc);
+
+        Some(SolidColorRenderElement::from_buffer(
+            &self.insert_hint_buffer,
+            loc,
+            INSERT_HINT_ALPHA,
+            Kind::Unspecified,
+        ))
+    }
+
+    fn render_snap_indicator(&self) -> Option<SolidColorRenderElement> {
+        let resize = self.interactive_resize.as_ref()?;
+        let alpha = resize.snap_flash.as_ref()?.clamped_value().clamp(0., 1.) as f32;
+
+        let (col, col_pos) = self
+            .columns_with_render_positions()
+            .find(|(col, _)| col.contains(&resize.window))?;
+
+        // Draw the line in the middle of the gap next to the resized edge.
+        let half_gap = self.options.gaps / 2.;
+        let edge_x = if resize.data.edges.contains(ResizeEdge::LEFT) {
+            col_pos.x - half_gap
+        } else {
+            col_pos.x + col.width() + half_gap
+        };
+        let loc = Point::from((
+            edge_x - RESIZE_SNAP_INDICATOR_WIDTH / 2.,
+            self.working_area.loc.y,
+        ));
+        let loc = self.options.swap_axes(loc);
+
+        Some(SolidColorRenderElement::from_buffer(
+            &self.snap_indicator,
+            loc,
+            alpha,
+            Kind::Unspecified,
+        ))
+    }
+
     pub fn view_offset_gesture_begin(&mut self, is_touchpad: bool) {
         if self.columns.is_empty() {
             return;
@@ -2436,11 +3905,12 @@ impl<W: LayoutElement> Workspace<W> {
         let left_strut = self.working_area.loc.x;
         let right_strut = self.view_size.w - self.working_area.size.w - self.working_area.loc.x;
 
-        if self.options.center_focused_column == CenterFocusedColumn::Always {
-            let mut col_x = 0.;
-            for (col_idx, col) in self.columns.iter().enumerate() {
-                let col_w = col.width();
+        let view_width = self.view_size.w;
+        let mut col_x = 0.;
+        for (col_idx, col) in self.columns.iter().enumerate() {
+            let col_w = col.width();
 
+            if self.is_centered(col_idx) {
                 let view_pos = if col.is_fullscreen {
                     col_x
                 } else if self.working_area.size.w <= col_w {
@@ -2449,12 +3919,20 @@ impl<W: LayoutElement> Workspace<W> {
                     col_x - (self.working_area.size.w - col_w) / 2. - left_strut
                 };
                 snapping_points.push(Snap { view_pos, col_idx });
-
-                col_x += col_w + self.options.gaps;
-            }
-        } else {
-            let view_width = self.view_size.w;
-            let mut push = |col_idx, left, right| {
+            } else {
+                // Normal columns align with the working area, but fullscreen columns align with the
+                // view size.
+                let (left, right) = if col.is_fullscreen {
+                    (col_x, col_x + col_w)
+                } else {
+                    // Logic from compute_new_view_offset.
+                    let padding =
+                        ((self.working_area.size.w - col_w) / 2.).clamp(0., self.options.gaps);
+                    (
+                        col_x - padding - left_strut,
+                        col_x + col_w + padding + right_strut,
+                    )
+                };
                 snapping_points.push(Snap {
                     view_pos: left,
                     col_idx,
@@ -2463,29 +3941,9 @@ impl<W: LayoutElement> Workspace<W> {
                     view_pos: right - view_width,
                     col_idx,
                 });
-            };
-
-            let mut col_x = 0.;
-            for (col_idx, col) in self.columns.iter().enumerate() {
-                let col_w = col.width();
-
-                // Normal columns align with the working area, but fullscreen columns align with the
-                // view size.
-                if col.is_fullscreen {
-                    let left = col_x;
-                    let right = col_x + col_w;
-                    push(col_idx, left, right);
-                } else {
-                    // Logic from compute_new_view_offset.
-                    let padding =
-                        ((self.working_area.size.w - col_w) / 2.).clamp(0., self.options.gaps);
-                    let left = col_x - padding - left_strut;
-                    let right = col_x + col_w + padding + right_strut;
-                    push(col_idx, left, right);
-                }
-
-                col_x += col_w + self.options.gaps;
             }
+
+            col_x += col_w + self.options.gaps;
         }
 
         // Find the closest snapping point.
@@ -2500,11 +3958,15 @@ impl<W: LayoutElement> Workspace<W> {
 
         let mut new_col_idx = target_snap.col_idx;
 
-        if self.options.center_focused_column != CenterFocusedColumn::Always {
-            // Focus the furthest window towards the direction of the gesture.
+        if !self.is_centered(new_col_idx) {
+            // Focus the furthest window towards the direction of the gesture. Centered columns
+            // only take the focus through their own snapping point.
             if target_view_offset >= current_view_offset {
                 for col_idx in (new_col_idx + 1)..self.columns.len() {
                     let col = &self.columns[col_idx];
+                    if self.is_centered(col_idx) {
+                        break;
+                    }
                     let col_x = self.column_x(col_idx);
                     let col_w = col.width();
 
@@ -2527,6 +3989,9 @@ impl<W: LayoutElement> Workspace<W> {
             } else {
                 for col_idx in (0..new_col_idx).rev() {
                     let col = &self.columns[col_idx];
+                    if self.is_centered(col_idx) {
+                        break;
+                    }
                     let col_x = self.column_x(col_idx);
                     let col_w = col.width();
 
@@ -2573,6 +4038,20 @@ impl<W: LayoutElement> Workspace<W> {
     }
 
     pub fn interactive_resize_begin(&mut self, window: W::Id, edges: ResizeEdge) -> bool {
+        let edges = self.options.swap_axes(edges);
+
+        if let Some(idx) = self.floating_idx(&window) {
+            let original_window_size = self.floating[idx].tile.window_size();
+            self.interactive_resize = Some(InteractiveResize {
+                window,
+                original_window_size,
+                data: InteractiveResizeData { edges },
+                snapped_width: None,
+                snap_flash: None,
+            });
+            return true;
+        }
+
         let col = self
             .columns
             .iter_mut()
@@ -2595,6 +4074,8 @@ impl<W: LayoutElement> Workspace<W> {
             window,
             original_window_size,
             data: InteractiveResizeData { edges },
+            snapped_width: None,
+            snap_flash: None,
         };
         self.interactive_resize = Some(resize);
 
@@ -2604,6 +4085,55 @@ impl<W: LayoutElement> Workspace<W> {
         true
     }
 
+    /// Moves the column with the window one step towards the column under `pos`.
+    ///
+    /// A floating window follows `pos` instead.
+    pub fn interactive_move_update(&mut self, window: &W::Id, pos: Point<f64, Logical>) {
+        if self.interactive_resize.is_some() {
+            return;
+        }
+
+        if let Some(idx) = self.floating_idx(window) {
+            let pos = self.options.swap_axes(pos.downscale(self.zoom));
+            let floating = &mut self.floating[idx];
+            let offset = *floating.move_offset.get_or_insert(floating.pos - pos);
+            floating.pos = pos + offset;
+            return;
+        }
+
+        let Some(col_idx) = self.columns.iter().position(|col| col.contains(window)) else {
+            return;
+        };
+
+        // Use the final column positions so that the ongoing movement animations don't make the
+        // target bounce back and forth.
+        let pos = self.options.swap_axes(pos);
+        let x = pos.x / self.zoom + self.view_pos();
+        let target_idx = zip(self.column_xs(self.data.iter().copied()), &self.data)
+            .position(|(col_x, data)| col_x <= x && x < col_x + data.width);
+        let Some(target_idx) = target_idx else {
+            return;
+        };
+
+        if target_idx == col_idx {
+            return;
+        }
+
+        self.activate_window(window);
+        if target_idx < col_idx {
+            self.move_left();
+        } else {
+            self.move_right();
+        }
+    }
+
+    /// Forgets where the pointer held a floating window that was being moved.
+    pub fn interactive_move_end(&mut self, window: &W::Id) {
+        if let Some(idx) = self.floating_idx(window) {
+            self.floating[idx].move_offset = None;
+        }
+    }
+
     pub fn interactive_resize_update(
         &mut self,
         window: &W::Id,
@@ -2617,11 +4147,21 @@ impl<W: LayoutElement> Workspace<W> {
             return false;
         }
 
-        let col = self
+        let delta = self.options.swap_axes(delta);
+        let edges = resize.data.edges;
+        let original_window_size = resize.original_window_size;
+
+        if let Some(idx) = self.floating_idx(window) {
+            self.floating_resize_update(idx, edges, original_window_size, delta);
+            return true;
+        }
+
+        let col_idx = self
             .columns
-            .iter_mut()
-            .find(|col| col.contains(window))
+            .iter()
+            .position(|col| col.contains(window))
             .unwrap();
+        let col = &self.columns[col_idx];
 
         let tile_idx = col
             .tiles
@@ -2629,33 +4169,60 @@ impl<W: LayoutElement> Workspace<W> {
             .position(|tile| tile.window().id() == window)
             .unwrap();
 
-        if resize.data.edges.intersects(ResizeEdge::LEFT_RIGHT) {
+        if edges.intersects(ResizeEdge::LEFT_RIGHT) {
             let mut dx = delta.x;
-            if resize.data.edges.contains(ResizeEdge::LEFT) {
+            if edges.contains(ResizeEdge::LEFT) {
                 dx = -dx;
             };
 
-            if self.options.center_focused_column == CenterFocusedColumn::Always {
+            if self.options.center_focused_column == CenterFocusedColumn::Always
+                || col.always_center
+            {
                 dx *= 2.;
             }
 
-            let window_width = (resize.original_window_size.w + dx).round() as i32;
-            col.set_column_width(SizeChange::SetFixed(window_width), Some(tile_idx), false);
+            let window_width = (original_window_size.w + dx).round();
+            let tile_width = col.tiles[tile_idx].tile_width_for_window_width(window_width);
+            let snapped_width = self.resize_snap_width(col_idx, tile_width);
+            let flash = !self.options.animations.off;
+
+            let col = &mut self.columns[col_idx];
+            match snapped_width {
+                Some(width) => col.set_width(width, false),
+                None => col.set_column_width(
+                    SizeChange::SetFixed(window_width as i32),
+                    Some(tile_idx),
+                    false,
+                ),
+            }
+
+            let resize = self.interactive_resize.as_mut().unwrap();
+            if flash && snapped_width.is_some() && snapped_width != resize.snapped_width {
+                resize.snap_flash = Some(Animation::ease(
+                    1.,
+                    0.,
+                    0.,
+                    RESIZE_SNAP_INDICATOR_MS,
+                    Curve::EaseOutCubic,
+                ));
+            }
+            resize.snapped_width = snapped_width;
         }
 
-        if resize.data.edges.intersects(ResizeEdge::TOP_BOTTOM) {
+        if edges.intersects(ResizeEdge::TOP_BOTTOM) {
             // Prevent the simplest case of weird resizing (top edge when this is the topmost
             // window).
-            if !(resize.data.edges.contains(ResizeEdge::TOP) && tile_idx == 0) {
+            if !(edges.contains(ResizeEdge::TOP) && tile_idx == 0) {
                 let mut dy = delta.y;
-                if resize.data.edges.contains(ResizeEdge::TOP) {
+                if edges.contains(ResizeEdge::TOP) {
                     dy = -dy;
                 };
 
                 // FIXME: some smarter height distribution would be nice here so that vertical
                 // resizes work as expected in more cases.
 
-                let window_height = (resize.original_window_size.h + dy).round() as i32;
+                let window_height = (original_window_size.h + dy).round() as i32;
+                let col = &mut self.columns[col_idx];
                 col.set_window_height(SizeChange::SetFixed(window_height), Some(tile_idx), false);
             }
         }
@@ -2663,6 +4230,84 @@ impl<W: LayoutElement> Workspace<W> {
         true
     }
 
+    /// Resizes a floating window by the pointer movement since the interactive resize started.
+    ///
+    /// The window can't grow past the working area.
+    fn floating_resize_update(
+        &mut self,
+        idx: usize,
+        edges: ResizeEdge,
+        original_window_size: Size<f64, Logical>,
+        delta: Point<f64, Logical>,
+    ) {
+        let mut size = original_window_size;
+        if edges.intersects(ResizeEdge::LEFT_RIGHT) {
+            let dx = if edges.contains(ResizeEdge::LEFT) {
+                -delta.x
+            } else {
+                delta.x
+            };
+            size.w += dx;
+        }
+        if edges.intersects(ResizeEdge::TOP_BOTTOM) {
+            let dy = if edges.contains(ResizeEdge::TOP) {
+                -delta.y
+            } else {
+                delta.y
+            };
+            size.h += dy;
+        }
+
+        let tile = &mut self.floating[idx].tile;
+        let max_size = Size::from((
+            tile.window_width_for_tile_width(self.working_area.size.w),
+            tile.window_height_for_tile_height(self.working_area.size.h),
+        ));
+
+        let size = if tile.window().rules().keep_aspect_ratio == Some(true) {
+            fit_aspect_ratio(size, original_window_size, edges, max_size)
+        } else {
+            Size::from((
+                size.w.round().clamp(1., f64::max(max_size.w, 1.)),
+                size.h.round().clamp(1., f64::max(max_size.h, 1.)),
+            ))
+        };
+
+        let tile_size = Size::from((
+            tile.tile_width_for_window_width(size.w),
+            tile.tile_height_for_window_height(size.h),
+        ));
+        tile.request_tile_size(tile_size, false, &mut None);
+    }
+
+    /// Returns the width that an interactively resized column with this tile width snaps to.
+    ///
+    /// Columns snap to common proportions of the working area, and to the widths of the other
+    /// visible columns.
+    fn resize_snap_width(&self, col_idx: usize, tile_width: f64) -> Option<ColumnWidth> {
+        let proportions = RESIZE_SNAP_PROPORTIONS.map(ColumnWidth::Proportion);
+
+        let view_x = self.view_pos();
+        let view_w = self.view_size.w;
+        let visible_columns = zip(self.column_xs(self.data.iter().copied()), &self.data)
+            .enumerate()
+            .filter(|(idx, (col_x, data))| {
+                *idx != col_idx && view_x < col_x + data.width && *col_x < view_x + view_w
+            })
+            .map(|(_, (_, data))| ColumnWidth::Fixed(data.width));
+
+        proportions
+            .into_iter()
+            .chain(visible_columns)
+            .map(|width| {
+                let px = width.resolve(&self.options, self.working_area.size.w);
+                (width, (px - tile_width).abs())
+            })
+            .filter(|(_, distance)| *distance <= RESIZE_SNAP_DISTANCE)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(width, _)| width)
+    }
+
     pub fn interactive_resize_end(&mut self, window: Option<&W::Id>) {
         let Some(resize) = &self.interactive_resize else {
             return;
@@ -2674,7 +4319,7 @@ impl<W: LayoutElement> Workspace<W> {
             }
 
             // Animate the active window into view right away.
-            if self.columns[self.active_column_idx].contains(window) {
+            if !self.columns.is_empty() && self.columns[self.active_column_idx].contains(window) {
                 self.animate_view_offset_to_column(self.view_pos(), self.active_column_idx, None);
             }
         }
@@ -2682,8 +4327,22 @@ impl<W: LayoutElement> Workspace<W> {
         self.interactive_resize = None;
     }
 
-    pub fn refresh(&mut self, is_active: bool) {
-        for (col_idx, col) in self.columns.iter_mut().enumerate() {
+    pub fn refresh(&mut self, is_active: bool, is_visible: bool) {
+        // Columns can stop or start being fullscreen in many ways, such as a fullscreen window
+        // closing, so catch up with the zoom here.
+        self.update_view_transform();
+
+        self.arrange_columns();
+        if self.columns.len() != self.auto_tile_column_count {
+            self.balance_columns();
+        }
+
+        // Windows scrolled out of the view are suspended, just like on hidden workspaces.
+        let view_rect = Rectangle::from_loc_and_size((self.view_pos(), 0.), self.view_size);
+
+        let column_count = self.columns.len();
+        let col_xs = self.column_xs(self.data.iter().copied());
+        for (col_idx, (col, col_x)) in zip(&mut self.columns, col_xs).enumerate() {
             let mut col_resize_data = None;
             if let Some(resize) = &self.interactive_resize {
                 if col.contains(&resize.window) {
@@ -2691,9 +4350,41 @@ impl<W: LayoutElement> Workspace<W> {
                 }
             }
 
-            for (tile_idx, tile) in col.tiles.iter_mut().enumerate() {
+            let is_maximized = col.is_full_width && !col.is_fullscreen && col.tiles.len() == 1;
+            let col_pos = Point::from((col_x, 0.)) + col.render_offset();
+
+            let tile_count = col.tiles.len();
+            let tile_offsets = col.tile_offsets_iter(col.data.iter().copied());
+            for (tile_idx, (tile, tile_off)) in zip(&mut col.tiles, tile_offsets).enumerate() {
+                let tile_pos = col_pos + tile_off + tile.render_offset();
+                let tile_rect = Rectangle::from_loc_and_size(tile_pos, tile.tile_size());
+                let is_tile_visible = is_visible && view_rect.overlaps(tile_rect);
+
+                // An edge is tiled when another tile is next to it. Full-width columns span the
+                // whole working area, so their sides count as tiled too.
+                let tiled_edges = (!col.is_fullscreen).then(|| {
+                    let mut edges = ResizeEdge::empty();
+                    if col_idx > 0 || col.is_full_width {
+                        edges |= ResizeEdge::LEFT;
+                    }
+                    if col_idx + 1 < column_count || col.is_full_width {
+                        edges |= ResizeEdge::RIGHT;
+                    }
+                    if tile_idx > 0 {
+                        edges |= ResizeEdge::TOP;
+                    }
+                    if tile_idx + 1 < tile_count {
+                        edges |= ResizeEdge::BOTTOM;
+                    }
+                    self.options.swap_axes(edges)
+                });
+
                 let win = tile.window_mut();
 
+                win.set_tiled(tiled_edges);
+                win.set_maximized(is_maximized);
+                win.set_suspended(!is_tile_visible);
+
                 let active_in_column = col.active_tile_idx == tile_idx;
                 win.set_active_in_column(active_in_column);
 
@@ -2708,12 +4399,42 @@ impl<W: LayoutElement> Workspace<W> {
                     self.working_area.size,
                     self.options.gaps,
                 );
-                win.set_bounds(bounds);
+                win.set_bounds(self.options.swap_axes(bounds));
 
                 win.send_pending_configure();
                 win.refresh();
             }
         }
+
+        let view_rect = Rectangle::from_loc_and_size((0., 0.), self.view_size);
+        let topmost_idx = self.floating.len().saturating_sub(1);
+        for (idx, floating) in self.floating.iter_mut().enumerate() {
+            let tile_pos = floating.pos + floating.tile.render_offset();
+            let tile_rect = Rectangle::from_loc_and_size(tile_pos, floating.tile.tile_size());
+            let is_tile_visible = is_visible && view_rect.overlaps(tile_rect);
+
+            let win = floating.tile.window_mut();
+
+            win.set_tiled(None);
+            win.set_maximized(false);
+            win.set_suspended(!is_tile_visible);
+            win.set_active_in_column(true);
+            win.set_activated(is_active && self.floating_is_active && idx == topmost_idx);
+
+            let resize = self.interactive_resize.as_ref();
+            let resize_data = resize
+                .filter(|resize| win.id() == &resize.window)
+                .map(|resize| resize.data);
+            win.set_interactive_resize(resize_data);
+
+            let border_config = win.rules().border.resolve_against(self.options.border);
+            let bounds =
+                compute_toplevel_bounds(border_config, self.working_area.size, self.options.gaps);
+            win.set_bounds(self.options.swap_axes(bounds));
+
+            win.send_pending_configure();
+            win.refresh();
+        }
     }
 }
 
@@ -2759,11 +4480,14 @@ impl<W: LayoutElement> Column<W> {
             active_tile_idx: 0,
             width,
             is_full_width,
+            width_before_toggle: None,
             is_fullscreen: false,
+            always_center: tile.window().rules().always_center == Some(true),
             move_animation: None,
             view_size,
             working_area,
             scale,
+            tab_indicator: TabIndicator::new(options.tab_indicator),
             options,
         };
 
@@ -2778,7 +4502,12 @@ impl<W: LayoutElement> Column<W> {
         rv
     }
 
-    fn set_view_size(&mut self, size: Size<f64, Logical>, working_area: Rectangle<f64, Logical>) {
+    fn set_view_size(
+        &mut self,
+        size: Size<f64, Logical>,
+        working_area: Rectangle<f64, Logical>,
+        transaction: Transaction,
+    ) {
         if self.view_size == size && self.working_area == working_area {
             return;
         }
@@ -2786,7 +4515,7 @@ impl<W: LayoutElement> Column<W> {
         self.view_size = size;
         self.working_area = working_area;
 
-        self.update_tile_sizes(false);
+        self.update_tile_sizes_with_transaction(false, Some(transaction));
     }
 
     fn update_config(&mut self, scale: f64, options: Rc<Options>) {
@@ -2809,11 +4538,16 @@ impl<W: LayoutElement> Column<W> {
             update_sizes = true;
         }
 
+        if self.options.transposed != options.transposed {
+            update_sizes = true;
+        }
+
         for (tile, data) in zip(&mut self.tiles, &mut self.data) {
             tile.update_config(scale, options.clone());
             data.update(tile);
         }
 
+        self.tab_indicator.update_config(options.tab_indicator);
         self.scale = scale;
         self.options = options;
 
@@ -2848,7 +4582,13 @@ impl<W: LayoutElement> Column<W> {
         self.move_animation.is_some() || self.tiles.iter().any(Tile::are_animations_ongoing)
     }
 
-    pub fn update_render_elements(&mut self, is_active: bool, view_rect: Rectangle<f64, Logical>) {
+    /// Updates the render elements of the tiles, darkening inactive ones by `dim`.
+    pub fn update_render_elements(
+        &mut self,
+        is_active: bool,
+        dim: f64,
+        view_rect: Rectangle<f64, Logical>,
+    ) {
         let active_idx = self.active_tile_idx;
         for (tile_idx, (tile, tile_off)) in self.tiles_mut().enumerate() {
             let is_active = is_active && tile_idx == active_idx;
@@ -2856,7 +4596,24 @@ impl<W: LayoutElement> Column<W> {
             let mut tile_view_rect = view_rect;
             tile_view_rect.loc -= tile_off + tile.render_offset();
             tile.update(is_active, tile_view_rect);
+            tile.set_dim(if is_active { 0. } else { dim });
         }
+
+        let show_tab_indicator = self.tiles.len() > 1 && !self.is_fullscreen;
+        let tiles: Vec<_> = if show_tab_indicator {
+            self.tiles()
+                .map(|(tile, tile_off)| (tile_off.y + tile.render_offset().y, tile.tile_size().h))
+                .collect()
+        } else {
+            vec![]
+        };
+        self.tab_indicator.update_render_elements(
+            show_tab_indicator,
+            self.width(),
+            tiles.into_iter(),
+            self.active_tile_idx,
+            self.options.transposed,
+        );
     }
 
     pub fn render_offset(&self) -> Point<f64, Logical> {
@@ -2925,11 +4682,11 @@ impl<W: LayoutElement> Column<W> {
             .find(|(_, tile)| tile.window().id() == window)
             .unwrap();
 
-        let height = f64::from(tile.window().size().h);
+        let height = f64::from(self.options.swap_axes(tile.window().size()).h);
         let offset = tile
             .window()
             .animation_snapshot()
-            .map_or(0., |from| from.size.h - height);
+            .map_or(0., |from| self.options.swap_axes(from.size).h - height);
 
         tile.update_window();
         self.data[tile_idx].update(tile);
@@ -2946,6 +4703,17 @@ impl<W: LayoutElement> Column<W> {
     }
 
     fn update_tile_sizes(&mut self, animate: bool) {
+        self.update_tile_sizes_with_transaction(animate, None);
+    }
+
+    /// Requests the tile sizes, with a transaction covering the windows that get a new size.
+    ///
+    /// Without a transaction, one is created once a window gets a new size.
+    fn update_tile_sizes_with_transaction(
+        &mut self,
+        animate: bool,
+        mut transaction: Option<Transaction>,
+    ) {
         if self.is_fullscreen {
             self.tiles[0].request_fullscreen(self.view_size);
             return;
@@ -3098,7 +4866,7 @@ impl<W: LayoutElement> Column<W> {
             };
 
             let size = Size::from((width, height));
-            tile.request_tile_size(size, animate);
+            tile.request_tile_size(size, animate, &mut transaction);
         }
     }
 
@@ -3165,7 +4933,7 @@ impl<W: LayoutElement> Column<W> {
         self.tiles[new_idx - 1].animate_move_y_from(next_y - active_y);
     }
 
-    #[cfg(test)]
+    #[cfg(any(test, feature = "fuzzing"))]
     fn verify_invariants(&self) {
         use approx::assert_abs_diff_eq;
 
@@ -3226,6 +4994,44 @@ impl<W: LayoutElement> Column<W> {
     }
 
     fn set_column_width(&mut self, change: SizeChange, tile_idx: Option<usize>, animate: bool) {
+        let width = self.width_for_change(change, tile_idx);
+        self.set_width(width, animate);
+    }
+
+    /// Toggles between width `b` and the width the column had before, or `a` if it started at `b`.
+    fn toggle_column_width(&mut self, a: SizeChange, b: SizeChange) {
+        let width = if self.is_full_width {
+            ColumnWidth::Proportion(1.)
+        } else {
+            self.width
+        };
+        let current_px = width.resolve(&self.options, self.working_area.size.w);
+
+        let target = self.width_for_change(b, None);
+        let target_px = target.resolve(&self.options, self.working_area.size.w);
+
+        // Some allowance for fractional scaling purposes.
+        if (current_px - target_px).abs() >= 1. {
+            self.width_before_toggle = Some((self.width, self.is_full_width));
+            self.set_width(target, true);
+            return;
+        }
+
+        match self.width_before_toggle.take() {
+            Some((width, is_full_width)) => {
+                self.width = width;
+                self.is_full_width = is_full_width;
+                self.update_tile_sizes(true);
+            }
+            None => {
+                let width = self.width_for_change(a, None);
+                self.set_width(width, true);
+            }
+        }
+    }
+
+    /// Computes the column width resulting from the change.
+    fn width_for_change(&self, change: SizeChange, tile_idx: Option<usize>) -> ColumnWidth {
         let width = if self.is_full_width {
             ColumnWidth::Proportion(1.)
         } else {
@@ -3243,7 +5049,7 @@ impl<W: LayoutElement> Column<W> {
         const MAX_PX: f64 = 100000.;
         const MAX_F: f64 = 10000.;
 
-        let width = match (current, change) {
+        match (current, change) {
             (_, SizeChange::SetFixed(fixed)) => {
                 // As a special case, setting a fixed column width will compute it in such a way
                 // that the specified (usually active) window gets that width. This is the
@@ -3277,9 +5083,7 @@ impl<W: LayoutElement> Column<W> {
                 ColumnWidth::Proportion(proportion)
             }
             (ColumnWidth::Preset(_), _) => unreachable!(),
-        };
-
-        self.set_width(width, animate);
+        }
     }
 
     fn set_window_height(&mut self, change: SizeChange, tile_idx: Option<usize>, animate: bool) {
@@ -3320,8 +5124,8 @@ impl<W: LayoutElement> Column<W> {
 
         // Clamp it against the window height constraints.
         let win = &self.tiles[tile_idx].window();
-        let min_h = win.min_size().h;
-        let max_h = win.max_size().h;
+        let min_h = self.options.swap_axes(win.min_size()).h;
+        let max_h = self.options.swap_axes(win.max_size()).h;
 
         if max_h > 0 {
             window_height = f64::min(window_height, f64::from(max_h));
@@ -3351,9 +5155,11 @@ impl<W: LayoutElement> Column<W> {
     }
 
     /// Returns the static window location, not taking the render offset into account.
+    ///
+    /// The location is in the output coordinates, i.e. transposed back for a transposed layout.
     pub fn window_loc(&self, tile_idx: usize) -> Point<f64, Logical> {
         let (tile, pos) = self.tiles().nth(tile_idx).unwrap();
-        pos + tile.window_loc()
+        self.options.swap_axes(pos + tile.window_loc())
     }
 
     // HACK: pass a self.data iterator in manually as a workaround for the lack of method partial
@@ -3481,6 +5287,64 @@ fn compute_new_view_offset(
     }
 }
 
+/// Converts an output view size and working area into the coordinates of the workspace layout.
+fn layout_view(
+    size: Size<f64, Logical>,
+    working_area: Rectangle<f64, Logical>,
+    zoom: f64,
+    transposed: bool,
+) -> (Size<f64, Logical>, Rectangle<f64, Logical>) {
+    let working_area = Rectangle::from_loc_and_size(
+        working_area.loc.downscale(zoom),
+        working_area.size.downscale(zoom),
+    );
+    let size = size.downscale(zoom);
+
+    if transposed {
+        (size.transpose(), working_area.transpose())
+    } else {
+        (size, working_area)
+    }
+}
+
+/// Fits a window size from an interactive resize to the aspect ratio of the original size.
+///
+/// The size follows the dragged edge, or the axis that changed more when dragging a corner, and
+/// then shrinks to fit within `max_size`.
+fn fit_aspect_ratio(
+    size: Size<f64, Logical>,
+    original: Size<f64, Logical>,
+    edges: ResizeEdge,
+    max_size: Size<f64, Logical>,
+) -> Size<f64, Logical> {
+    let max_w = f64::max(max_size.w, 1.);
+    let max_h = f64::max(max_size.h, 1.);
+    if original.w <= 0. || original.h <= 0. {
+        return Size::from((
+            size.w.round().clamp(1., max_w),
+            size.h.round().clamp(1., max_h),
+        ));
+    }
+
+    let ratio = original.w / original.h;
+    let follow_width = if !edges.intersects(ResizeEdge::TOP_BOTTOM) {
+        true
+    } else if !edges.intersects(ResizeEdge::LEFT_RIGHT) {
+        false
+    } else {
+        (size.w / original.w - 1.).abs() >= (size.h / original.h - 1.).abs()
+    };
+
+    let mut w = if follow_width { size.w } else { size.h * ratio };
+    w = f64::min(w, max_w);
+    w = f64::min(w, max_h * ratio);
+
+    // Snap the height to the ratio of the rounded width.
+    let w = w.round().max(1.);
+    let h = (w / ratio).round().clamp(1., max_h);
+    Size::from((w, h))
+}
+
 pub fn compute_working_area(output: &Output, struts: Struts) -> Rectangle<f64, Logical> {
     // Start with the layer-shell non-exclusive zone.
     let mut working_area = layer_map_for_output(output).non_exclusive_zone().to_f64();
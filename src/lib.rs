@@ -4,15 +4,20 @@ extern crate tracing;
 pub mod animation;
 pub mod backend;
 pub mod cli;
+pub mod clip_recorder;
 pub mod cursor;
 #[cfg(feature = "dbus")]
 pub mod dbus;
 pub mod frame_clock;
+#[cfg(feature = "dbus")]
+pub mod global_shortcuts;
 pub mod handlers;
 pub mod input;
 pub mod ipc;
 pub mod layout;
 pub mod niri;
+#[cfg(feature = "dbus")]
+pub mod notifications;
 pub mod protocols;
 pub mod render_helpers;
 pub mod rubber_band;
@@ -20,6 +25,9 @@ pub mod ui;
 pub mod utils;
 pub mod window;
 
+#[cfg(test)]
+mod tests;
+
 #[cfg(not(feature = "xdp-gnome-screencast"))]
 pub mod dummy_pw_utils;
 #[cfg(feature = "xdp-gnome-screencast")]
@@ -173,6 +173,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         config.animations.slowdown.clamp(0., 100.)
     };
     animation::ANIMATION_SLOWDOWN.store(slowdown, Ordering::Relaxed);
+    animation::REDUCED_MOTION.store(config.animations.off, Ordering::Relaxed);
 
     let spawn_at_startup = mem::take(&mut config.spawn_at_startup);
     *CHILD_ENV.write().unwrap() = mem::take(&mut config.environment);
@@ -187,6 +188,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         event_loop.handle(),
         event_loop.get_signal(),
         display,
+        cli.benchmark,
     )
     .unwrap();
 
@@ -247,10 +249,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
 
     // Spawn commands from cli and auto-start.
-    spawn(cli.command);
+    spawn(cli.command, None);
 
     for elem in spawn_at_startup {
-        spawn(elem.command);
+        spawn(elem.command, None);
     }
 
     // Show the config error notification right away if needed.
@@ -12,9 +12,10 @@ use _server_decoration::server::org_kde_kwin_server_decoration_manager::Mode as
 use anyhow::{ensure, Context};
 use calloop::futures::Scheduler;
 use niri_config::{
-    Config, FloatOrInt, Key, Modifiers, PreviewRender, TrackLayout, WorkspaceReference,
+    Config, FloatOrInt, Key, Modifiers, PreviewRender, PrivilegedProtocol, StartupPointerPosition,
+    TrackLayout, WorkspaceReference,
 };
-use niri_ipc::Workspace;
+use niri_ipc::{SizeChange, Workspace};
 use smithay::backend::allocator::Fourcc;
 use smithay::backend::renderer::damage::OutputDamageTracker;
 use smithay::backend::renderer::element::memory::MemoryRenderBufferRenderElement;
@@ -23,13 +24,13 @@ use smithay::backend::renderer::element::surface::{
     render_elements_from_surface_tree, WaylandSurfaceRenderElement,
 };
 use smithay::backend::renderer::element::utils::{
-    select_dmabuf_feedback, Relocate, RelocateRenderElement,
+    select_dmabuf_feedback, Relocate, RelocateRenderElement, RescaleRenderElement,
 };
 use smithay::backend::renderer::element::{
     default_primary_scanout_output_compare, AsRenderElements, Element as _, Id, Kind,
-    PrimaryScanoutOutput, RenderElementStates,
+    PrimaryScanoutOutput, RenderElementState, RenderElementStates,
 };
-use smithay::backend::renderer::gles::GlesRenderer;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
 use smithay::desktop::utils::{
     bbox_from_surface_tree, output_update, send_dmabuf_feedback_surface_tree,
     send_frames_surface_tree, surface_presentation_feedback_flags_from_states,
@@ -50,6 +51,7 @@ use smithay::reexports::calloop::{
     Interest, LoopHandle, LoopSignal, Mode, PostAction, RegistrationToken,
 };
 use smithay::reexports::wayland_protocols::ext::session_lock::v1::server::ext_session_lock_v1::ExtSessionLockV1;
+use smithay::reexports::wayland_protocols::wp::content_type::v1::server::wp_content_type_v1::Type as ContentType;
 use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel::WmCapabilities;
 use smithay::reexports::wayland_protocols_misc::server_decoration as _server_decoration;
 use smithay::reexports::wayland_server::backend::{
@@ -59,13 +61,14 @@ use smithay::reexports::wayland_server::protocol::wl_shm;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::reexports::wayland_server::{Display, DisplayHandle, Resource};
 use smithay::utils::{
-    ClockSource, Logical, Monotonic, Physical, Point, Rectangle, Scale, Size, Transform,
+    ClockSource, IsAlive, Logical, Monotonic, Physical, Point, Rectangle, Scale, Size, Transform,
     SERIAL_COUNTER,
 };
 use smithay::wayland::compositor::{
     with_states, with_surface_tree_downward, CompositorClientState, CompositorState, SurfaceData,
     TraversalAction,
 };
+use smithay::wayland::content_type::{ContentTypeState, ContentTypeSurfaceCachedState};
 use smithay::wayland::cursor_shape::CursorShapeManagerState;
 use smithay::wayland::dmabuf::DmabufState;
 use smithay::wayland::fractional_scale::FractionalScaleManagerState;
@@ -87,79 +90,129 @@ use smithay::wayland::shell::wlr_layer::{self, Layer, WlrLayerShellState};
 use smithay::wayland::shell::xdg::decoration::XdgDecorationState;
 use smithay::wayland::shell::xdg::XdgShellState;
 use smithay::wayland::shm::ShmState;
+use smithay::wayland::single_pixel_buffer::SinglePixelBufferState;
 use smithay::wayland::socket::ListeningSocketSource;
 use smithay::wayland::tablet_manager::TabletManagerState;
 use smithay::wayland::text_input::TextInputManagerState;
 use smithay::wayland::viewporter::ViewporterState;
 use smithay::wayland::virtual_keyboard::VirtualKeyboardManagerState;
 use smithay::wayland::xdg_activation::XdgActivationState;
-use smithay::wayland::xdg_foreign::XdgForeignState;
 
 use crate::backend::tty::SurfaceDmabufFeedback;
 use crate::backend::{Backend, RenderResult, Tty, Winit};
-use crate::cursor::{CursorManager, CursorTextureCache, RenderCursor, XCursor};
+use crate::clip_recorder::ClipRecorder;
+use crate::cursor::{bucketed_scale, CursorManager, CursorTextureCache, RenderCursor, XCursor};
+#[cfg(feature = "dbus")]
+use crate::dbus::freedesktop_global_shortcuts::GlobalShortcutsToNiri;
+#[cfg(feature = "dbus")]
+use crate::dbus::freedesktop_notifications::{CloseReason, Notification, NotificationsToNiri};
+#[cfg(feature = "dbus")]
+use crate::dbus::freedesktop_screenshot::{PortalScreenshotReply, PortalScreenshotToNiri};
 #[cfg(feature = "dbus")]
 use crate::dbus::gnome_shell_introspect::{self, IntrospectToNiri, NiriToIntrospect};
 #[cfg(feature = "dbus")]
 use crate::dbus::gnome_shell_screenshot::{NiriToScreenshot, ScreenshotToNiri};
 #[cfg(feature = "xdp-gnome-screencast")]
 use crate::dbus::mutter_screen_cast::{self, ScreenCastToNiri};
+#[cfg(feature = "dbus")]
+use crate::dbus::niri_compositor::{CompositorState, CompositorToNiri};
 use crate::frame_clock::FrameClock;
+#[cfg(feature = "dbus")]
+use crate::global_shortcuts::GlobalShortcuts;
 use crate::handlers::configure_lock_surface;
+use crate::input::move_grab::MoveGrab;
 use crate::input::scroll_tracker::ScrollTracker;
 use crate::input::{
     apply_libinput_settings, mods_with_finger_scroll_binds, mods_with_wheel_binds, TabletData,
 };
-use crate::ipc::server::IpcServer;
+use crate::ipc::server::{ipc_window, IpcServer};
+use crate::layout::preset::{LayoutPreset, PendingPreset, PresetPlacement};
+use crate::layout::snapshot::LayoutSnapshot;
+use crate::layout::workspace::ColumnWidth;
 use crate::layout::{Layout, LayoutElement as _, MonitorRenderElement};
+#[cfg(feature = "dbus")]
+use crate::notifications::Notifier;
 use crate::protocols::foreign_toplevel::{self, ForeignToplevelManagerState};
 use crate::protocols::gamma_control::GammaControlManagerState;
+use crate::protocols::input_inhibit::InputInhibitManagerState;
+use crate::protocols::output_management::OutputManagementManagerState;
 use crate::protocols::screencopy::{Screencopy, ScreencopyManagerState};
+use crate::protocols::virtual_pointer::VirtualPointerManagerState;
+use crate::protocols::xdg_foreign::XdgForeignState;
 use crate::pw_utils::{Cast, PipeWire};
 #[cfg(feature = "xdp-gnome-screencast")]
 use crate::pw_utils::{CastSizeChange, CastTarget, PwToNiri};
 use crate::render_helpers::debug::draw_opaque_regions;
 use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
 use crate::render_helpers::renderer::NiriRenderer;
-use crate::render_helpers::texture::TextureBuffer;
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::render_helpers::texture_cache::{
+    Eviction, TextureCache, TextureCacheUsage, DEFAULT_BUDGET_MIB,
+};
 use crate::render_helpers::{
-    render_to_shm, render_to_texture, render_to_vec, shaders, RenderTarget,
+    blur, render_to_shm, render_to_texture, render_to_vec, shaders, RenderTarget,
 };
 use crate::ui::config_error_notification::ConfigErrorNotification;
-use crate::ui::exit_confirm_dialog::ExitConfirmDialog;
+use crate::ui::exit_confirm_dialog::{ExitConfirmDialog, CLOSE_WINDOW_TEXT};
 use crate::ui::hotkey_overlay::HotkeyOverlay;
+use crate::ui::magnifier::Magnifier;
+use crate::ui::resize_mode::ResizeMode;
 use crate::ui::screen_transition::{self, ScreenTransition};
 use crate::ui::screenshot_ui::{ScreenshotUi, ScreenshotUiRenderElement};
+use crate::ui::window_hints::WindowHints;
+use crate::utils::benchmark::{Benchmark, FrameTimings};
+use crate::utils::focus_history::FocusHistory;
+use crate::utils::layout_presets::LayoutPresets;
+use crate::utils::remembered_sizes::RememberedSizes;
 use crate::utils::scale::{closest_representable_scale, guess_monitor_scale};
-use crate::utils::spawning::CHILD_ENV;
+use crate::utils::spawning::{spawn, CHILD_ENV};
+use crate::utils::window_groups::WindowGroups;
 use crate::utils::{
     center, center_f64, get_monotonic_time, ipc_transform_to_smithay, logical_output,
-    make_screenshot_path, output_size, send_scale_transform, write_png_rgba8,
+    make_clip_path, make_screenshot_path, output_size, send_scale_transform, write_png_rgba8,
+    ResizeEdge,
+};
+use crate::window::{
+    app_id, InitialConfigureState, Mapped, ResolvedWindowRules, Unmapped, WindowRef,
 };
-use crate::window::{InitialConfigureState, Mapped, ResolvedWindowRules, Unmapped, WindowRef};
 use crate::{animation, niri_render_elements};
 
 const CLEAR_COLOR: [f32; 4] = [0.2, 0.2, 0.2, 1.];
 const CLEAR_COLOR_LOCKED: [f32; 4] = [0.3, 0.1, 0.1, 1.];
+const PICK_HIGHLIGHT_COLOR: [f32; 4] = [0.5, 0.7, 1., 1.];
+const PICK_HIGHLIGHT_ALPHA: f32 = 0.3;
+
+// After this time, an activation token can no longer mark its window urgent, so it is dropped.
+const ACTIVATION_TOKEN_LIFETIME: Duration = Duration::from_secs(60);
 
 // We'll try to send frame callbacks at least once a second. We'll make a timer that fires once a
 // second, so with the worst timing the maximum interval between two frame callbacks for a surface
 // should be ~1.995 seconds.
 const FRAME_CALLBACK_THROTTLE: Option<Duration> = Some(Duration::from_millis(995));
 
+// How long to wait for more window size changes before saving the remembered sizes.
+const REMEMBERED_SIZES_SAVE_DELAY: Duration = Duration::from_secs(1);
+
 pub struct Niri {
     pub config: Rc<RefCell<Config>>,
 
     /// Output config from the config file.
     ///
-    /// This does not include transient output config changes done via IPC. It is only used when
-    /// reloading the config from disk to determine if the output configuration should be reloaded
-    /// (and transient changes dropped).
+    /// This does not include transient output config changes done via IPC, nor the active output
+    /// profile. It is used when reloading the config from disk to determine if the output
+    /// configuration should be reloaded (and transient changes dropped), and as the base when
+    /// switching output profiles.
     pub config_file_output_config: Vec<niri_config::Output>,
+    /// Name of the output profile matching the connected outputs, if any.
+    pub active_output_profile: Option<String>,
+    /// Names of the connected outputs as of the last output profile check, sorted.
+    pub connected_outputs: Vec<String>,
 
     pub event_loop: LoopHandle<'static, State>,
     pub scheduler: Scheduler<()>,
     pub stop_signal: LoopSignal,
+    /// Benchmark mode of the nested backend.
+    pub benchmark: Option<Benchmark>,
     pub display_handle: DisplayHandle,
     pub socket_name: OsString,
 
@@ -168,10 +221,26 @@ pub struct Niri {
     /// Whether the at-startup=true window rules are active.
     pub is_at_startup: bool,
 
+    /// Whether the laptop lid is closed, which turns off the laptop panel.
+    pub is_lid_closed: bool,
+
     // Each workspace corresponds to a Space. Each workspace generally has one Output mapped to it,
     // however it may have none (when there are no outputs connected) or multiple (when mirroring).
     pub layout: Layout<Mapped>,
 
+    /// Layout presets saved by name, persisted across sessions.
+    pub layout_presets: LayoutPresets,
+    /// Loaded layout preset waiting for windows to open.
+    pub pending_layout_preset: Option<PendingPreset<Window>>,
+    /// Last used window sizes per app id, for the `remember-window-sizes` layout option.
+    pub remembered_sizes: RememberedSizes,
+    /// Timer saving the remembered window sizes, while there are changes to save.
+    remembered_sizes_save_timer: Option<RegistrationToken>,
+    /// Window group per app id, given to the windows of that app as they open.
+    pub window_groups: WindowGroups,
+    /// Timer sending the configures held back by the resize throttling, and when it fires.
+    resize_throttle_timer: Option<(RegistrationToken, Duration)>,
+
     // This space does not actually contain any windows, but all outputs are mapped into it
     // according to their global position.
     pub global_space: Space<Window>,
@@ -179,6 +248,9 @@ pub struct Niri {
     // Windows which don't have a buffer attached yet.
     pub unmapped_windows: HashMap<WlSurface, Unmapped>,
 
+    // Windows taken out of the layout by minimizing, oldest first.
+    pub minimized_windows: Vec<MinimizedWindow>,
+
     // Cached root surface for every surface, so that we can access it in destroyed() where the
     // normal get_parent() is cleared out.
     pub root_surface: HashMap<WlSurface, WlSurface>,
@@ -188,6 +260,7 @@ pub struct Niri {
 
     // When false, we're idling with monitors powered off.
     pub monitors_active: bool,
+    pub idle: IdleState,
 
     pub devices: HashSet<input::Device>,
     pub tablets: HashMap<input::Device, TabletData>,
@@ -203,6 +276,7 @@ pub struct Niri {
     pub foreign_toplevel_state: ForeignToplevelManagerState,
     pub screencopy_state: ScreencopyManagerState,
     pub viewporter_state: ViewporterState,
+    pub single_pixel_buffer_state: SinglePixelBufferState,
     pub xdg_foreign_state: XdgForeignState,
     pub shm_state: ShmState,
     pub output_manager_state: OutputManagerState,
@@ -213,11 +287,13 @@ pub struct Niri {
     pub text_input_state: TextInputManagerState,
     pub input_method_state: InputMethodManagerState,
     pub virtual_keyboard_state: VirtualKeyboardManagerState,
+    pub virtual_pointer_state: VirtualPointerManagerState,
     pub pointer_gestures_state: PointerGesturesState,
     pub relative_pointer_state: RelativePointerManagerState,
     pub pointer_constraints_state: PointerConstraintsState,
     pub idle_notifier_state: IdleNotifierState<State>,
     pub idle_inhibit_manager_state: IdleInhibitManagerState,
+    pub content_type_state: ContentTypeState,
     pub data_device_state: DataDeviceState,
     pub primary_selection_state: PrimarySelectionState,
     pub data_control_state: DataControlState,
@@ -226,13 +302,24 @@ pub struct Niri {
     pub presentation_state: PresentationState,
     pub security_context_state: SecurityContextState,
     pub gamma_control_manager_state: GammaControlManagerState,
+    pub input_inhibit_manager_state: InputInhibitManagerState,
+    pub output_management_state: OutputManagementManagerState,
     pub activation_state: XdgActivationState,
 
     pub seat: Seat<State>,
     /// Scancodes of the keys to suppress.
     pub suppressed_keys: HashSet<u32>,
+    /// Pressed mouse buttons that triggered a bind, so that their release is not forwarded.
+    pub suppressed_buttons: HashSet<u32>,
     pub bind_cooldown_timers: HashMap<Key, RegistrationToken>,
     pub keyboard_focus: KeyboardFocus,
+    /// Layer surface with on-demand keyboard interactivity that was last clicked.
+    ///
+    /// It keeps the keyboard focus until the user clicks a window, moves the focus in the layout,
+    /// a new window takes the focus, or it stops accepting focus.
+    pub layer_shell_on_demand_focus: Option<LayerSurface>,
+    /// Window that had the keyboard focus before the on-demand layer surface took it.
+    layer_shell_on_demand_focus_return: Option<Window>,
     pub idle_inhibiting_surfaces: HashSet<WlSurface>,
     pub is_fdo_idle_inhibited: Arc<AtomicBool>,
 
@@ -246,8 +333,20 @@ pub struct Niri {
     /// When this happens, the pointer also loses any focus. This is so that touch can prevent
     /// various tooltips from sticking around.
     pub pointer_hidden: bool,
+    /// Where to move the pointer after the outputs were repositioned.
+    ///
+    /// The pointer follows the output it was on, or moves to an output if it ended up outside.
+    pub pointer_location_after_reposition: Option<Point<f64, Logical>>,
+    /// How far the pointer was pushed past the edge of its output towards another output.
+    pub output_edge_push: f64,
     // FIXME: this should be able to be removed once PointerFocus takes grabs into account.
     pub pointer_grab_ongoing: bool,
+    /// Time and window of the last left click on window decorations, for double-click detection.
+    pub last_decoration_click: Option<(Duration, Window)>,
+    /// Move grab on a window decoration waiting for the pointer to be dragged far enough.
+    pub pending_move_grab: Option<MoveGrab>,
+    /// Whether the resize cursor is shown for a resize grip between tiles.
+    pub resize_grip_hovered: bool,
     pub tablet_cursor_location: Option<Point<f64, Logical>>,
     pub gesture_swipe_3f_cumulative: Option<(f64, f64)>,
     pub vertical_wheel_tracker: ScrollTracker,
@@ -260,9 +359,19 @@ pub struct Niri {
     pub lock_state: LockState,
 
     pub screenshot_ui: ScreenshotUi,
+    /// Clip being recorded, along with the timer capturing its frames.
+    pub clip_recorder: Option<(ClipRecorder, RegistrationToken)>,
     pub config_error_notification: ConfigErrorNotification,
     pub hotkey_overlay: HotkeyOverlay,
+    pub texture_cache: TextureCache,
     pub exit_confirm_dialog: Option<ExitConfirmDialog>,
+    pub close_confirm_dialog: Option<ExitConfirmDialog>,
+    /// Window that the close confirm dialog is asking about.
+    pub window_to_close: Option<Window>,
+    pub window_hints: WindowHints,
+    pub resize_mode: ResizeMode,
+    /// Interactive pick requested over IPC, waiting for the user.
+    pub pick: Option<PickRequest>,
 
     pub debug_draw_opaque_regions: bool,
     pub debug_draw_damage: bool,
@@ -271,9 +380,21 @@ pub struct Niri {
     pub dbus: Option<crate::dbus::DBusServers>,
     #[cfg(feature = "dbus")]
     pub inhibit_power_key_fd: Option<zbus::zvariant::OwnedFd>,
+    #[cfg(feature = "dbus")]
+    pub global_shortcuts: GlobalShortcuts,
+    #[cfg(feature = "dbus")]
+    pub notifier: Notifier,
+    /// Screenshot portal request waiting for the screenshot UI.
+    #[cfg(feature = "dbus")]
+    pub portal_screenshot: Option<PortalScreenshotReply>,
+    /// State served by niri's own D-Bus interface.
+    #[cfg(feature = "dbus")]
+    pub compositor_state: Arc<Mutex<CompositorState>>,
 
     pub ipc_server: Option<IpcServer>,
     pub ipc_outputs_changed: bool,
+    /// Whether the windows, workspaces or focus may have changed since the last D-Bus update.
+    pub dbus_compositor_changed: bool,
     pub ipc_focused_window: Arc<Mutex<Option<Window>>>,
 
     // Casts are dropped before PipeWire to prevent a double-free (yay).
@@ -285,6 +406,13 @@ pub struct Niri {
     pub mapped_cast_output: HashMap<Window, Output>,
 }
 
+/// A window taken out of the layout by minimizing.
+pub struct MinimizedWindow {
+    pub mapped: Mapped,
+    /// Width of the window's column, given back to it on restore.
+    pub width: ColumnWidth,
+}
+
 pub struct OutputState {
     pub global: GlobalId,
     pub frame_clock: FrameClock,
@@ -319,9 +447,20 @@ pub struct OutputState {
     pub lock_render_state: LockRenderState,
     pub lock_surface: Option<LockSurface>,
     pub lock_color_buffer: SolidColorBuffer,
+    /// Solid color buffer for dimming the output when the user is idle.
+    pub idle_dim_buffer: SolidColorBuffer,
+    /// Solid color buffer for highlighting the output during an interactive output pick.
+    pub pick_highlight_buffer: SolidColorBuffer,
     screen_transition: Option<ScreenTransition>,
+    /// Blurred snapshot of the output shown behind niri's modal dialogs.
+    backdrop: Option<TextureBuffer<GlesTexture>>,
+    pub magnifier: Magnifier,
     /// Damage tracker used for the debug damage visualization.
     pub debug_damage_tracker: OutputDamageTracker,
+    /// Windows focused on this output, for back and forward navigation.
+    pub focus_history: FocusHistory<Window>,
+    /// Layout geometry of the last frame, used by hit-testing.
+    pub layout_snapshot: Option<LayoutSnapshot<Window>>,
 }
 
 #[derive(Default)]
@@ -364,6 +503,25 @@ pub struct PointerFocus {
     pub surface: Option<(WlSurface, Point<f64, Logical>)>,
     // If surface belongs to a window, this is that window.
     pub window: Option<Window>,
+    // If surface belongs to a layer surface, this is that layer surface.
+    pub layer: Option<LayerSurface>,
+}
+
+/// Opacity of the black overlay over the dimmed outputs.
+const IDLE_DIM_ALPHA: f32 = 0.5;
+
+/// State of the built-in idle actions.
+pub struct IdleState {
+    /// When the user was last active.
+    last_activity: Instant,
+    /// Timer for the next idle action.
+    timer: Option<RegistrationToken>,
+    /// Whether the outputs are dimmed due to inactivity.
+    pub is_dimmed: bool,
+    did_power_off_monitors: bool,
+    did_lock: bool,
+    /// Whether some client is inhibiting idle.
+    is_inhibited: bool,
 }
 
 #[derive(Default)]
@@ -390,6 +548,12 @@ struct SurfaceFrameThrottlingState {
     last_sent_at: RefCell<Option<(Output, u32)>>,
 }
 
+/// Interactive pick requested over IPC, with the channel to send the result to.
+pub enum PickRequest {
+    Window(async_channel::Sender<Option<niri_ipc::PickedWindow>>),
+    Output(async_channel::Sender<Option<String>>),
+}
+
 pub enum CenterCoords {
     Separately,
     Both,
@@ -462,6 +626,7 @@ impl State {
         event_loop: LoopHandle<'static, State>,
         stop_signal: LoopSignal,
         display: Display<State>,
+        benchmark_frames: Option<u32>,
     ) -> Result<Self, Box<dyn std::error::Error>> {
         let _span = tracy_client::span!("State::new");
 
@@ -470,8 +635,9 @@ impl State {
         let has_display =
             env::var_os("WAYLAND_DISPLAY").is_some() || env::var_os("DISPLAY").is_some();
 
-        let mut backend = if has_display {
-            let winit = Winit::new(config.clone(), event_loop.clone())?;
+        let backend = if has_display {
+            let vsync = benchmark_frames.is_none();
+            let winit = Winit::new(config.clone(), event_loop.clone(), vsync)?;
             Backend::Winit(winit)
         } else {
             let tty = Tty::new(config.clone(), event_loop.clone())
@@ -479,10 +645,42 @@ impl State {
             Backend::Tty(tty)
         };
 
+        Ok(Self::with_backend(
+            config,
+            backend,
+            event_loop,
+            stop_signal,
+            display,
+            benchmark_frames,
+        ))
+    }
+
+    /// Creates the state around an already created backend.
+    ///
+    /// This is what the integration tests use to run on the headless backend.
+    pub fn with_backend(
+        config: Rc<RefCell<Config>>,
+        mut backend: Backend,
+        event_loop: LoopHandle<'static, State>,
+        stop_signal: LoopSignal,
+        display: Display<State>,
+        benchmark_frames: Option<u32>,
+    ) -> Self {
+        let _span = tracy_client::span!("State::with_backend");
+
         let mut niri = Niri::new(config.clone(), event_loop, stop_signal, display, &backend);
+        if let Some(frames) = benchmark_frames {
+            if matches!(backend, Backend::Winit(_)) {
+                niri.benchmark = Some(Benchmark::new(frames));
+            } else {
+                warn!("benchmark mode is only supported when running nested, ignoring");
+            }
+        }
         backend.init(&mut niri);
+        niri.apply_startup_config();
+        niri.schedule_idle_timer();
 
-        Ok(Self { backend, niri })
+        Self { backend, niri }
     }
 
     pub fn refresh_and_flush_clients(&mut self) {
@@ -502,18 +700,26 @@ impl State {
         let _span = tracy_client::span!("State::refresh");
 
         // These should be called periodically, before flushing the clients.
+        self.map_windows_past_deadline();
         self.niri.layout.refresh();
+        self.niri.update_resize_throttle_timer();
         self.niri.cursor_manager.check_cursor_image_surface_alive();
         self.niri.refresh_pointer_outputs();
         self.niri.popups.cleanup();
         self.niri.global_space.refresh();
         self.niri.refresh_idle_inhibit();
+        self.niri.refresh_activation_tokens();
         self.refresh_popup_grab();
         self.update_keyboard_focus();
+        self.move_cursor_after_reposition();
         self.refresh_pointer_focus();
         foreign_toplevel::refresh(self);
         self.niri.refresh_window_rules();
         self.refresh_ipc_outputs();
+        self.niri.enforce_texture_budget();
+
+        #[cfg(feature = "dbus")]
+        self.niri.refresh_compositor_dbus();
 
         #[cfg(feature = "xdp-gnome-screencast")]
         self.niri.refresh_mapped_cast_outputs();
@@ -544,6 +750,57 @@ impl State {
         self.niri.queue_redraw_all();
     }
 
+    fn on_idle_timer(&mut self) {
+        let _span = tracy_client::span!("State::on_idle_timer");
+
+        if self.niri.idle.is_inhibited {
+            // Treat inhibited idle as activity, so that the timeouts start over once it ends.
+            self.niri.idle.last_activity = Instant::now();
+            self.niri.schedule_idle_timer();
+            return;
+        }
+
+        let idle = self.niri.config.borrow().idle.clone();
+        let elapsed = self.niri.idle.last_activity.elapsed();
+        let reached =
+            |secs: Option<u32>| secs.is_some_and(|s| elapsed >= Duration::from_secs(u64::from(s)));
+
+        if reached(idle.dim_after_sec) && !self.niri.idle.is_dimmed {
+            self.niri.idle.is_dimmed = true;
+            self.niri.queue_redraw_all();
+        }
+
+        if reached(idle.power_off_monitors_after_sec) && !self.niri.idle.did_power_off_monitors {
+            self.niri.idle.did_power_off_monitors = true;
+            self.niri.deactivate_monitors(&mut self.backend);
+        }
+
+        if reached(idle.lock_after_sec) && !self.niri.idle.did_lock {
+            self.niri.idle.did_lock = true;
+
+            if let Some(lock_command) = idle.lock_command {
+                if !self.niri.is_locked() {
+                    spawn(lock_command.command, None);
+                }
+            } else {
+                warn!("lock-after-sec is set, but lock-command is not");
+            }
+        }
+
+        self.niri.schedule_idle_timer();
+    }
+
+    fn move_cursor_after_reposition(&mut self) {
+        let Some(location) = self.niri.pointer_location_after_reposition.take() else {
+            return;
+        };
+
+        // Outputs moving around is not a reason to show a hidden pointer.
+        let pointer_hidden = self.niri.pointer_hidden;
+        self.move_cursor(location);
+        self.niri.pointer_hidden = pointer_hidden;
+    }
+
     /// Moves cursor within the specified rectangle, only adjusting coordinates if needed.
     fn move_cursor_to_rect(&mut self, rect: Rectangle<f64, Logical>, mode: CenterCoords) -> bool {
         let pointer = &self.niri.seat.get_pointer().unwrap();
@@ -589,23 +846,112 @@ impl State {
             return false;
         }
 
-        let Some(output) = self.niri.layout.active_output() else {
+        let Some(rect) = self.niri.focused_tile_global_rect() else {
             return false;
         };
+
+        self.move_cursor_to_rect(rect, mode)
+    }
+
+    /// Captures the backdrops for a modal dialog that is about to open.
+    pub fn capture_backdrops_for_dialog(&mut self) {
+        if self.niri.is_modal_dialog_open() {
+            return;
+        }
+
+        self.backend.with_primary_renderer(|renderer| {
+            self.niri.capture_backdrops(renderer);
+        });
+    }
+
+    /// Takes a window out of the layout and puts it on the minimized list.
+    pub fn minimize_window(&mut self, window: &Window) {
+        let Some((_, output)) = self
+            .niri
+            .layout
+            .find_window_and_output(window.toplevel().expect("no x11 support").wl_surface())
+        else {
+            return;
+        };
         let output = output.clone();
-        let monitor = self.niri.layout.monitor_for_output(&output).unwrap();
 
-        let mut rv = false;
-        let rect = monitor.active_tile_visual_rectangle();
+        // The window animates out the same way as a closing window.
+        self.backend.with_primary_renderer(|renderer| {
+            self.niri.layout.store_unmap_snapshot(renderer, window);
+            self.niri
+                .layout
+                .start_close_animation_for_window(renderer, window);
+        });
+
+        let active_window = self.niri.layout.active_window().map(|(m, _)| &m.window);
+        let was_active = active_window == Some(window);
+
+        let Some((width, _)) = self.niri.layout.window_size_preference(window) else {
+            return;
+        };
+        let Some(mut mapped) = self.niri.layout.remove_window(window) else {
+            return;
+        };
+        mapped.set_activated(false);
+        mapped.send_pending_configure();
+        self.niri.forget_focused_window(window);
+        self.niri
+            .minimized_windows
+            .push(MinimizedWindow { mapped, width });
 
-        if let Some(rect) = rect {
-            let output_geo = self.niri.global_space.output_geometry(&output).unwrap();
-            let mut rect = rect;
-            rect.loc += output_geo.loc.to_f64();
-            rv = self.move_cursor_to_rect(rect, mode);
+        if was_active {
+            self.maybe_warp_cursor_to_focus();
         }
 
-        rv
+        self.niri.queue_redraw(&output);
+    }
+
+    /// Puts a minimized window back into the layout and focuses it.
+    pub fn restore_minimized_window(&mut self, idx: usize) {
+        let MinimizedWindow { mapped, width } = self.niri.minimized_windows.remove(idx);
+        let window = mapped.window.clone();
+
+        self.niri.layout.add_window(mapped, Some(width), false);
+        self.niri.layout.activate_window(&window);
+        self.maybe_warp_cursor_to_focus();
+
+        // FIXME: granular
+        self.niri.queue_redraw_all();
+    }
+
+    /// Moves all windows of a group next to the active column, restoring the minimized ones.
+    pub fn gather_window_group(&mut self, name: &str) {
+        let mut idx = 0;
+        while idx < self.niri.minimized_windows.len() {
+            if self.niri.minimized_windows[idx].mapped.group() != Some(name) {
+                idx += 1;
+                continue;
+            }
+
+            let MinimizedWindow { mapped, width } = self.niri.minimized_windows.remove(idx);
+            self.niri.layout.add_window(mapped, Some(width), false);
+        }
+
+        let windows = self.niri.window_group(name);
+        if windows.is_empty() {
+            warn!("window group {name} has no windows");
+            return;
+        }
+
+        self.niri.layout.gather_windows(&windows);
+    }
+
+    /// Minimizes all windows of a group.
+    pub fn stash_window_group(&mut self, name: &str) {
+        let windows = self.niri.window_group(name);
+        if windows.is_empty() {
+            warn!("window group {name} has no windows");
+            return;
+        }
+
+        for window in &windows {
+            self.minimize_window(window);
+        }
     }
 
     pub fn maybe_warp_cursor_to_focus(&mut self) -> bool {
@@ -631,10 +977,13 @@ impl State {
         let location = pointer.current_location();
 
         if !self.niri.is_locked() && !self.niri.screenshot_ui.is_open() {
-            // Don't refresh cursor focus during transitions.
+            // Don't refresh cursor focus during transitions within a workspace. During a workspace
+            // switch however, the whole workspace slides away from under the cursor, so keep the
+            // focus on whatever is visually there, otherwise scrolling and clicking would go to a
+            // window that is no longer under the cursor.
             if let Some((output, _)) = self.niri.output_under(location) {
                 let monitor = self.niri.layout.monitor_for_output(output).unwrap();
-                if monitor.are_transitions_ongoing() {
+                if monitor.workspace_switch.is_none() && monitor.are_transitions_ongoing() {
                     return;
                 }
             }
@@ -684,6 +1033,18 @@ impl State {
         true
     }
 
+    /// Moves the cursor to a position relative to the top-left corner of the output.
+    pub fn warp_cursor_within_output(&mut self, output: &Output, pos: Point<f64, Logical>) {
+        let geo = self.niri.global_space.output_geometry(output).unwrap();
+        let size = geo.size.to_f64();
+        // Keep the cursor within the output, the bottom and right edges are outside.
+        let pos = Point::from((
+            pos.x.clamp(0., f64::max(0., size.w - 1.)),
+            pos.y.clamp(0., f64::max(0., size.h - 1.)),
+        ));
+        self.move_cursor(pos + geo.loc.to_f64());
+    }
+
     pub fn move_cursor_to_output(&mut self, output: &Output) {
         let geo = self.niri.global_space.output_geometry(output).unwrap();
         self.move_cursor(center(geo).to_f64());
@@ -711,12 +1072,39 @@ impl State {
     }
 
     pub fn update_keyboard_focus(&mut self) {
+        // Forget the on-demand layer surface once it can no longer take the focus, and give the
+        // focus back to the window that had it.
+        if let Some(surface) = &self.niri.layer_shell_on_demand_focus {
+            if !surface.alive()
+                || surface.cached_state().keyboard_interactivity
+                    != wlr_layer::KeyboardInteractivity::OnDemand
+            {
+                self.niri.clear_layer_shell_on_demand_focus(true);
+            }
+        }
+
         let focus = if self.niri.is_locked() {
             KeyboardFocus::LockScreen {
                 surface: self.niri.lock_surface_focus(),
             }
         } else if self.niri.screenshot_ui.is_open() {
             KeyboardFocus::ScreenshotUi
+        } else if let Some(client) = self.niri.input_inhibit_manager_state.inhibiting_client() {
+            // Only the inhibiting client's layer surfaces can get the focus.
+            self.niri
+                .global_space
+                .outputs()
+                .find_map(|output| {
+                    let layers = layer_map_for_output(output);
+                    let surface = layers.layers().find(|layer| {
+                        layer.cached_state().keyboard_interactivity
+                            != wlr_layer::KeyboardInteractivity::None
+                            && layer.wl_surface().client().map(|c| c.id()) == Some(client.clone())
+                    });
+                    surface.map(|layer| layer.wl_surface().clone())
+                })
+                .map(|surface| KeyboardFocus::LayerShell { surface })
+                .unwrap_or(KeyboardFocus::Layout { surface: None })
         } else if let Some(output) = self.niri.layout.active_output() {
             let mon = self.niri.layout.monitor_for_output(output).unwrap();
             let layers = layer_map_for_output(output);
@@ -743,14 +1131,26 @@ impl State {
                         surface: Some(surface),
                     })
             };
+            let on_demand_focus = self.niri.layer_shell_on_demand_focus.as_ref();
+            let is_on_demand_focus = |surface: &LayerSurface| on_demand_focus == Some(surface);
             let layer_focus = |surface: &LayerSurface| {
-                // FIXME: support on-demand.
-                let can_receive_keyboard_focus = surface.cached_state().keyboard_interactivity
-                    == wlr_layer::KeyboardInteractivity::Exclusive;
+                let can_receive_keyboard_focus = match surface.cached_state().keyboard_interactivity
+                {
+                    wlr_layer::KeyboardInteractivity::Exclusive => true,
+                    wlr_layer::KeyboardInteractivity::OnDemand => is_on_demand_focus(surface),
+                    _ => false,
+                };
                 can_receive_keyboard_focus
                     .then(|| surface.wl_surface().clone())
                     .map(|surface| KeyboardFocus::LayerShell { surface })
             };
+            // Bottom and background layer surfaces only get the focus when clicked, even if they
+            // ask for exclusive focus.
+            let layer_focus_below = |surface: &LayerSurface| {
+                is_on_demand_focus(surface)
+                    .then(|| surface.wl_surface().clone())
+                    .map(|surface| KeyboardFocus::LayerShell { surface })
+            };
 
             let mut surface = grab_on_layer(Layer::Overlay);
             // FIXME: we shouldn't prioritize the top layer grabs over regular overlay input or a
@@ -765,6 +1165,12 @@ impl State {
                 surface = surface.or_else(|| layers.layers_on(Layer::Top).find_map(layer_focus));
             } else {
                 surface = surface.or_else(|| layers.layers_on(Layer::Top).find_map(layer_focus));
+                surface = surface.or_else(|| {
+                    layers
+                        .layers_on(Layer::Bottom)
+                        .chain(layers.layers_on(Layer::Background))
+                        .find_map(layer_focus_below)
+                });
                 surface = surface.or_else(layout_focus);
             }
 
@@ -796,13 +1202,19 @@ impl State {
                 surface: Some(surface),
             } = &focus
             {
-                if let Some((mapped, _)) = self.niri.layout.find_window_and_output_mut(surface) {
+                if let Some((mapped, output)) = self.niri.layout.find_window_and_output_mut(surface)
+                {
                     mapped.set_is_focused(true);
                     newly_focused_window = Some(mapped.window.clone());
+
+                    if let Some(state) = output.and_then(|o| self.niri.output_state.get_mut(o)) {
+                        state.focus_history.push(mapped.window.clone());
+                    }
                 }
             }
 
             *self.niri.ipc_focused_window.lock().unwrap() = newly_focused_window;
+            self.niri.dbus_compositor_changed = true;
 
             if let Some(grab) = self.niri.popup_grab.as_mut() {
                 if Some(&grab.root) != focus.surface() {
@@ -864,9 +1276,28 @@ impl State {
         }
     }
 
+    fn record_clip_frame(&mut self) -> TimeoutAction {
+        let mut next = None;
+        self.backend.with_primary_renderer(|renderer| {
+            next = self.niri.record_clip_frame(renderer);
+        });
+
+        match next {
+            Some(interval) => TimeoutAction::ToDuration(interval),
+            None => {
+                // The backend has no renderer right now, or the recording finished.
+                self.niri.clip_recorder = None;
+                TimeoutAction::Drop
+            }
+        }
+    }
+
     pub fn reload_config(&mut self, path: PathBuf) {
         let _span = tracy_client::span!("State::reload_config");
 
+        // Workspace names may change.
+        self.niri.dbus_compositor_changed = true;
+
         let mut config = match Config::load(&path) {
             Ok(config) => config,
             Err(err) => {
@@ -946,6 +1377,8 @@ impl State {
             libinput_config_changed = true;
         }
 
+        let output_profiles_changed = config.output_profiles != old_config.output_profiles;
+
         if config.outputs != self.niri.config_file_output_config {
             output_config_changed = true;
             self.niri
@@ -999,8 +1432,23 @@ impl State {
             shaders_changed = true;
         }
 
+        let idle_config_changed = config.idle != old_config.idle;
+        // Only reset reduced motion when the config changes, to keep an IPC toggle otherwise.
+        let reduced_motion_changed = config.animations.off != old_config.animations.off;
+        let reduced_motion = config.animations.off;
+        #[cfg(feature = "dbus")]
+        let appearance_changed = config.appearance != old_config.appearance;
+        #[cfg(feature = "dbus")]
+        self.niri.notifier.update_config(config.notifications);
+
         if config.debug != old_config.debug {
             debug_config_changed = true;
+
+            if config.debug.keep_laptop_panel_on_when_lid_is_closed
+                != old_config.debug.keep_laptop_panel_on_when_lid_is_closed
+            {
+                output_config_changed = true;
+            }
         }
 
         *old_config = config;
@@ -1027,12 +1475,31 @@ impl State {
             }
         }
 
-        if output_config_changed {
-            self.reload_output_config();
+        if output_config_changed || output_profiles_changed {
+            self.reload_output_profile();
+        }
+
+        if idle_config_changed {
+            self.niri.schedule_idle_timer();
+        }
+
+        if reduced_motion_changed {
+            self.niri.set_reduced_motion(reduced_motion);
+        }
+
+        #[cfg(feature = "dbus")]
+        if appearance_changed {
+            self.niri.on_appearance_changed();
         }
 
         if debug_config_changed {
             self.backend.on_debug_config_changed();
+
+            let config = self.niri.config.borrow();
+            let budget = config.debug.texture_cache_budget_mib;
+            self.niri
+                .texture_cache
+                .set_budget(budget.unwrap_or(DEFAULT_BUDGET_MIB));
         }
 
         if window_rules_changed {
@@ -1105,6 +1572,140 @@ impl State {
         }
     }
 
+    /// Switches to the output profile matching the connected outputs after a hotplug.
+    fn refresh_output_profile(&mut self) {
+        let mut connected: Vec<_> = self
+            .backend
+            .ipc_outputs()
+            .lock()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        connected.sort_unstable();
+        if connected == self.niri.connected_outputs {
+            return;
+        }
+        self.niri.connected_outputs = connected;
+
+        let profile = self.matching_output_profile();
+        if profile == self.niri.active_output_profile {
+            return;
+        }
+
+        match &profile {
+            Some(name) => info!("activating output profile {name:?}"),
+            None => info!("no output profile matches the connected outputs"),
+        }
+        self.niri.active_output_profile = profile;
+
+        self.apply_output_profile();
+        self.reload_output_config();
+        self.run_output_profile_hooks();
+    }
+
+    /// Re-applies the matching output profile after the config file changed.
+    ///
+    /// Hooks run only if a different profile became active.
+    fn reload_output_profile(&mut self) {
+        let profile = self.matching_output_profile();
+        let changed = profile != self.niri.active_output_profile;
+        self.niri.active_output_profile = profile;
+
+        // Without a profile before or after, keep the transient changes like a normal reload.
+        if changed || self.niri.active_output_profile.is_some() {
+            self.apply_output_profile();
+        }
+        self.reload_output_config();
+
+        if changed {
+            self.run_output_profile_hooks();
+        }
+    }
+
+    fn matching_output_profile(&self) -> Option<String> {
+        let config = self.niri.config.borrow();
+        let connected = &self.niri.connected_outputs;
+        config
+            .output_profiles
+            .iter()
+            .find(|profile| profile.matches(connected))
+            .map(|profile| profile.name.clone())
+    }
+
+    /// Rebuilds the output config from the config file and the active output profile.
+    ///
+    /// This drops the transient output config changes.
+    fn apply_output_profile(&mut self) {
+        let mut config = self.niri.config.borrow_mut();
+        let mut outputs = self.niri.config_file_output_config.clone();
+
+        let profile = self.niri.active_output_profile.as_ref().and_then(|name| {
+            config
+                .output_profiles
+                .iter()
+                .find(|profile| profile.name == *name)
+        });
+        if let Some(profile) = profile {
+            for output in &profile.outputs {
+                if let Some(existing) = outputs
+                    .iter_mut()
+                    .find(|o| o.name.eq_ignore_ascii_case(&output.name))
+                {
+                    // Keep the layout overrides from the top-level output section, unless the
+                    // profile overrides them too.
+                    let mut layout = mem::take(&mut existing.layout);
+                    layout.merge_with(&output.layout);
+                    *existing = niri_config::Output {
+                        layout,
+                        ..output.clone()
+                    };
+                } else {
+                    outputs.push(output.clone());
+                }
+            }
+        }
+
+        config.outputs = outputs;
+    }
+
+    fn run_output_profile_hooks(&mut self) {
+        let config = self.niri.config.borrow();
+        let Some(profile) = self.niri.active_output_profile.as_ref().and_then(|name| {
+            config
+                .output_profiles
+                .iter()
+                .find(|profile| profile.name == *name)
+        }) else {
+            return;
+        };
+        let workspaces = profile.workspaces.clone();
+        let exec = profile.exec.clone();
+        drop(config);
+
+        for ws in workspaces {
+            let Some(output) = self.niri.output_by_name(&ws.output) else {
+                warn!(
+                    "output profile: cannot move workspace {:?}, output {:?} is not enabled",
+                    ws.name, ws.output
+                );
+                continue;
+            };
+
+            if self
+                .niri
+                .layout
+                .move_named_workspace_to_output(&ws.name, &output)
+            {
+                self.niri.queue_redraw_all();
+            }
+        }
+
+        for command in exec {
+            spawn(command.command, None);
+        }
+    }
+
     pub fn apply_transient_output_config(&mut self, name: &str, action: niri_ipc::OutputAction) {
         {
             let mut config = self.niri.config.borrow_mut();
@@ -1158,11 +1759,45 @@ impl State {
         self.reload_output_config();
     }
 
+    /// Applies output configurations coming from a display configuration tool.
+    ///
+    /// Like [`Self::apply_transient_output_config()`], the changes last until the config file is
+    /// reloaded.
+    pub fn apply_output_management_config(&mut self, new_configs: Vec<niri_config::Output>) {
+        {
+            let mut config = self.niri.config.borrow_mut();
+            for new_config in new_configs {
+                if let Some(config) = config
+                    .outputs
+                    .iter_mut()
+                    .find(|o| o.name.eq_ignore_ascii_case(&new_config.name))
+                {
+                    // Keep the layout overrides from the config file.
+                    let layout = mem::take(&mut config.layout);
+                    *config = niri_config::Output {
+                        layout,
+                        ..new_config
+                    };
+                } else {
+                    config.outputs.push(new_config);
+                }
+            }
+        }
+
+        self.reload_output_config();
+
+        // Make sure the tool gets its reply even if nothing ended up changing.
+        self.niri.ipc_outputs_changed = true;
+    }
+
     pub fn refresh_ipc_outputs(&mut self) {
         if !self.niri.ipc_outputs_changed {
             return;
         }
         self.niri.ipc_outputs_changed = false;
+        self.niri.dbus_compositor_changed = true;
+
+        self.refresh_output_profile();
 
         let _span = tracy_client::span!("State::refresh_ipc_outputs");
 
@@ -1176,6 +1811,11 @@ impl State {
             ipc_output.logical = logical;
         }
 
+        let new_state = self.backend.ipc_outputs().lock().unwrap().clone();
+        self.niri
+            .output_management_state
+            .notify_changes::<State>(new_state);
+
         #[cfg(feature = "dbus")]
         self.niri.on_ipc_outputs_changed();
     }
@@ -1386,22 +2026,97 @@ impl State {
             warn!("error sending windows to introspect: {err:?}");
         }
     }
-}
-
-impl Niri {
-    pub fn new(
-        config: Rc<RefCell<Config>>,
-        event_loop: LoopHandle<'static, State>,
-        stop_signal: LoopSignal,
-        display: Display<State>,
-        backend: &Backend,
-    ) -> Self {
-        let _span = tracy_client::span!("Niri::new");
 
-        let (executor, scheduler) = calloop::futures::executor().unwrap();
-        event_loop.insert_source(executor, |_, _, _| ()).unwrap();
+    #[cfg(feature = "dbus")]
+    pub fn on_global_shortcuts_msg(&mut self, msg: GlobalShortcutsToNiri) {
+        match msg {
+            GlobalShortcutsToNiri::BindShortcuts {
+                session_handle,
+                app_id,
+                shortcuts,
+                reply,
+            } => {
+                let global_shortcuts = &mut self.niri.global_shortcuts;
+                if global_shortcuts.request(session_handle, &app_id, shortcuts, reply) {
+                    self.niri.queue_redraw_all();
+                }
+            }
+            GlobalShortcutsToNiri::ListShortcuts {
+                session_handle,
+                reply,
+            } => {
+                let shortcuts = self.niri.global_shortcuts.list(&session_handle);
+                if let Err(err) = reply.send_blocking(shortcuts) {
+                    warn!("error sending shortcuts to GlobalShortcuts: {err:?}");
+                }
+            }
+            GlobalShortcutsToNiri::CloseSession { session_handle } => {
+                let was_open = self.niri.global_shortcuts.is_dialog_open();
+                self.niri.global_shortcuts.close_session(&session_handle);
+                if was_open && !self.niri.global_shortcuts.is_dialog_open() {
+                    self.niri.queue_redraw_all();
+                }
+            }
+        }
+    }
 
-        let display_handle = display.handle();
+    #[cfg(feature = "dbus")]
+    pub fn on_notifications_msg(&mut self, msg: NotificationsToNiri) {
+        match msg {
+            NotificationsToNiri::Notify(notification) => {
+                self.niri.show_notification(notification);
+            }
+            NotificationsToNiri::Close { id } => {
+                self.niri.close_notification(id, CloseReason::ClosedByCall);
+            }
+        }
+    }
+
+    #[cfg(feature = "dbus")]
+    pub fn on_compositor_msg(&mut self, msg: CompositorToNiri) {
+        let CompositorToNiri::Request { request, reply } = msg;
+        crate::ipc::server::process_request(self, request, reply);
+    }
+
+    #[cfg(feature = "dbus")]
+    pub fn on_portal_screenshot_msg(&mut self, msg: PortalScreenshotToNiri) {
+        // Dropping the reply sender without sending anything cancels the request.
+        let reply = match msg {
+            PortalScreenshotToNiri::Screenshot { reply } => {
+                PortalScreenshotReply::Screenshot(reply)
+            }
+            PortalScreenshotToNiri::PickColor { reply } => PortalScreenshotReply::PickColor(reply),
+        };
+
+        if self.niri.screenshot_ui.is_open() {
+            debug!("refusing portal screenshot as the screenshot UI is already open");
+            return;
+        }
+
+        self.backend.with_primary_renderer(|renderer| {
+            self.niri.open_screenshot_ui(renderer, false);
+        });
+
+        if self.niri.screenshot_ui.is_open() {
+            self.niri.portal_screenshot = Some(reply);
+        }
+    }
+}
+
+impl Niri {
+    pub fn new(
+        config: Rc<RefCell<Config>>,
+        event_loop: LoopHandle<'static, State>,
+        stop_signal: LoopSignal,
+        display: Display<State>,
+        backend: &Backend,
+    ) -> Self {
+        let _span = tracy_client::span!("Niri::new");
+
+        let (executor, scheduler) = calloop::futures::executor().unwrap();
+        event_loop.insert_source(executor, |_, _, _| ()).unwrap();
+
+        let display_handle = display.handle();
         let config_ = config.borrow();
         let config_file_output_config = config_.outputs.clone();
 
@@ -1427,16 +2142,22 @@ impl Niri {
                 client
                     .get_data::<ClientState>()
                     .unwrap()
-                    .can_view_decoration_globals
+                    .can_view_kde_decoration_global
             },
         );
         let layer_shell_state =
             WlrLayerShellState::new_with_filter::<State, _>(&display_handle, |client| {
-                !client.get_data::<ClientState>().unwrap().restricted
+                client
+                    .get_data::<ClientState>()
+                    .unwrap()
+                    .can_use(PrivilegedProtocol::LayerShell)
             });
         let session_lock_state =
             SessionLockManagerState::new::<State, _>(&display_handle, |client| {
-                !client.get_data::<ClientState>().unwrap().restricted
+                client
+                    .get_data::<ClientState>()
+                    .unwrap()
+                    .can_use(PrivilegedProtocol::SessionLock)
             });
         let shm_state = ShmState::new::<State>(
             &display_handle,
@@ -1454,44 +2175,91 @@ impl Niri {
         let pointer_constraints_state = PointerConstraintsState::new::<State>(&display_handle);
         let idle_notifier_state = IdleNotifierState::new(&display_handle, event_loop.clone());
         let idle_inhibit_manager_state = IdleInhibitManagerState::new::<State>(&display_handle);
+        let content_type_state = ContentTypeState::new::<State>(&display_handle);
         let data_device_state = DataDeviceState::new::<State>(&display_handle);
         let primary_selection_state = PrimarySelectionState::new::<State>(&display_handle);
         let data_control_state = DataControlState::new::<State, _>(
             &display_handle,
             Some(&primary_selection_state),
-            |client| !client.get_data::<ClientState>().unwrap().restricted,
+            |client| {
+                client
+                    .get_data::<ClientState>()
+                    .unwrap()
+                    .can_use(PrivilegedProtocol::DataControl)
+            },
         );
         let presentation_state =
             PresentationState::new::<State>(&display_handle, Monotonic::ID as u32);
         let security_context_state =
             SecurityContextState::new::<State, _>(&display_handle, |client| {
-                !client.get_data::<ClientState>().unwrap().restricted
+                client
+                    .get_data::<ClientState>()
+                    .unwrap()
+                    .can_use(PrivilegedProtocol::SecurityContext)
             });
 
         let text_input_state = TextInputManagerState::new::<State>(&display_handle);
         let input_method_state =
             InputMethodManagerState::new::<State, _>(&display_handle, |client| {
-                !client.get_data::<ClientState>().unwrap().restricted
+                client
+                    .get_data::<ClientState>()
+                    .unwrap()
+                    .can_use(PrivilegedProtocol::InputMethod)
             });
         let virtual_keyboard_state =
             VirtualKeyboardManagerState::new::<State, _>(&display_handle, |client| {
-                !client.get_data::<ClientState>().unwrap().restricted
+                client
+                    .get_data::<ClientState>()
+                    .unwrap()
+                    .can_use(PrivilegedProtocol::VirtualKeyboard)
+            });
+        let virtual_pointer_state =
+            VirtualPointerManagerState::new::<State, _>(&display_handle, |client| {
+                client
+                    .get_data::<ClientState>()
+                    .unwrap()
+                    .can_use(PrivilegedProtocol::VirtualPointer)
             });
 
         let foreign_toplevel_state =
             ForeignToplevelManagerState::new::<State, _>(&display_handle, |client| {
-                !client.get_data::<ClientState>().unwrap().restricted
+                client
+                    .get_data::<ClientState>()
+                    .unwrap()
+                    .can_use(PrivilegedProtocol::ForeignToplevel)
             });
         let screencopy_state = ScreencopyManagerState::new::<State, _>(&display_handle, |client| {
-            !client.get_data::<ClientState>().unwrap().restricted
+            client
+                .get_data::<ClientState>()
+                .unwrap()
+                .can_use(PrivilegedProtocol::Screencopy)
         });
         let viewporter_state = ViewporterState::new::<State>(&display_handle);
+        let single_pixel_buffer_state = SinglePixelBufferState::new::<State>(&display_handle);
         let xdg_foreign_state = XdgForeignState::new::<State>(&display_handle);
 
         let is_tty = matches!(backend, Backend::Tty(_));
         let gamma_control_manager_state =
             GammaControlManagerState::new::<State, _>(&display_handle, move |client| {
-                is_tty && !client.get_data::<ClientState>().unwrap().restricted
+                is_tty
+                    && client
+                        .get_data::<ClientState>()
+                        .unwrap()
+                        .can_use(PrivilegedProtocol::GammaControl)
+            });
+        let input_inhibit_manager_state =
+            InputInhibitManagerState::new::<State, _>(&display_handle, |client| {
+                client
+                    .get_data::<ClientState>()
+                    .unwrap()
+                    .can_use(PrivilegedProtocol::InputInhibit)
+            });
+        let output_management_state =
+            OutputManagementManagerState::new::<State, _>(&display_handle, |client| {
+                client
+                    .get_data::<ClientState>()
+                    .unwrap()
+                    .can_use(PrivilegedProtocol::OutputManagement)
             });
         let activation_state = XdgActivationState::new::<State>(&display_handle);
 
@@ -1516,6 +2284,12 @@ impl Niri {
         let config_error_notification = ConfigErrorNotification::new(config.clone());
 
         let mut hotkey_overlay = HotkeyOverlay::new(config.clone(), backend.mod_key());
+        let texture_cache = TextureCache::new(
+            config_
+                .debug
+                .texture_cache_budget_mib
+                .unwrap_or(DEFAULT_BUDGET_MIB),
+        );
         if !config_.hotkey_overlay.skip_at_startup {
             hotkey_overlay.show();
         }
@@ -1528,6 +2302,14 @@ impl Niri {
             }
         };
 
+        let close_confirm_dialog = match ExitConfirmDialog::with_text(CLOSE_WINDOW_TEXT) {
+            Ok(x) => Some(x),
+            Err(err) => {
+                warn!("error creating the close confirm dialog: {err:?}");
+                None
+            }
+        };
+
         event_loop
             .insert_source(
                 Timer::from_duration(Duration::from_secs(1)),
@@ -1545,8 +2327,10 @@ impl Niri {
                 let config = state.niri.config.borrow();
                 let data = Arc::new(ClientState {
                     compositor_state: Default::default(),
-                    can_view_decoration_globals: config.prefer_no_csd,
+                    can_view_decoration_globals: config.any_prefer_no_csd(),
+                    can_view_kde_decoration_global: config.prefer_no_csd,
                     restricted: false,
+                    allowed_protocols: Vec::new(),
                 });
 
                 if let Err(err) = state.niri.display_handle.insert_client(client, data) {
@@ -1594,26 +2378,48 @@ impl Niri {
             )
             .unwrap();
 
+        #[cfg(feature = "dbus")]
+        let notifier = Notifier::new(config_.notifications);
+
         drop(config_);
         Self {
             config,
             config_file_output_config,
+            active_output_profile: None,
+            connected_outputs: Vec::new(),
 
             event_loop,
             scheduler,
             stop_signal,
+            benchmark: None,
             socket_name,
             display_handle,
             start_time: Instant::now(),
             is_at_startup: true,
+            is_lid_closed: false,
 
             layout,
+            layout_presets: LayoutPresets::load(),
+            pending_layout_preset: None,
+            remembered_sizes: RememberedSizes::load(),
+            remembered_sizes_save_timer: None,
+            window_groups: WindowGroups::load(),
+            resize_throttle_timer: None,
             global_space: Space::default(),
             output_state: HashMap::new(),
             output_by_name: HashMap::new(),
             unmapped_windows: HashMap::new(),
+            minimized_windows: Vec::new(),
             root_surface: HashMap::new(),
             monitors_active: true,
+            idle: IdleState {
+                last_activity: Instant::now(),
+                timer: None,
+                is_dimmed: false,
+                did_power_off_monitors: false,
+                did_lock: false,
+                is_inhibited: false,
+            },
 
             devices: HashSet::new(),
             tablets: HashMap::new(),
@@ -1628,10 +2434,12 @@ impl Niri {
             foreign_toplevel_state,
             screencopy_state,
             viewporter_state,
+            single_pixel_buffer_state,
             xdg_foreign_state,
             text_input_state,
             input_method_state,
             virtual_keyboard_state,
+            virtual_pointer_state,
             shm_state,
             output_manager_state,
             dmabuf_state,
@@ -1643,20 +2451,26 @@ impl Niri {
             pointer_constraints_state,
             idle_notifier_state,
             idle_inhibit_manager_state,
+            content_type_state,
             data_device_state,
             primary_selection_state,
             data_control_state,
             popups: PopupManager::default(),
             popup_grab: None,
             suppressed_keys: HashSet::new(),
+            suppressed_buttons: HashSet::new(),
             bind_cooldown_timers: HashMap::new(),
             presentation_state,
             security_context_state,
             gamma_control_manager_state,
+            input_inhibit_manager_state,
+            output_management_state,
             activation_state,
 
             seat,
             keyboard_focus: KeyboardFocus::Layout { surface: None },
+            layer_shell_on_demand_focus: None,
+            layer_shell_on_demand_focus_return: None,
             idle_inhibiting_surfaces: HashSet::new(),
             is_fdo_idle_inhibited: Arc::new(AtomicBool::new(false)),
             cursor_manager,
@@ -1665,7 +2479,12 @@ impl Niri {
             dnd_icon: None,
             pointer_focus: PointerFocus::default(),
             pointer_hidden: false,
+            pointer_location_after_reposition: None,
+            output_edge_push: 0.,
             pointer_grab_ongoing: false,
+            last_decoration_click: None,
+            pending_move_grab: None,
+            resize_grip_hovered: false,
             tablet_cursor_location: None,
             gesture_swipe_3f_cumulative: None,
             vertical_wheel_tracker: ScrollTracker::new(120),
@@ -1680,9 +2499,16 @@ impl Niri {
             lock_state: LockState::Unlocked,
 
             screenshot_ui,
+            clip_recorder: None,
             config_error_notification,
             hotkey_overlay,
+            texture_cache,
             exit_confirm_dialog,
+            close_confirm_dialog,
+            window_to_close: None,
+            window_hints: WindowHints::new(),
+            resize_mode: ResizeMode::new(),
+            pick: None,
 
             debug_draw_opaque_regions: false,
             debug_draw_damage: false,
@@ -1691,9 +2517,18 @@ impl Niri {
             dbus: None,
             #[cfg(feature = "dbus")]
             inhibit_power_key_fd: None,
+            #[cfg(feature = "dbus")]
+            global_shortcuts: GlobalShortcuts::new(),
+            #[cfg(feature = "dbus")]
+            notifier,
+            #[cfg(feature = "dbus")]
+            portal_screenshot: None,
+            #[cfg(feature = "dbus")]
+            compositor_state: Arc::new(Mutex::new(CompositorState::default())),
 
             ipc_server,
             ipc_outputs_changed: false,
+            dbus_compositor_changed: true,
             ipc_focused_window: Arc::new(Mutex::new(None)),
 
             pipewire,
@@ -1754,6 +2589,17 @@ impl Niri {
         }
         drop(config);
 
+        // Remember where the pointer is on its output, so that it can follow the output.
+        let pointer_location = self.seat.get_pointer().unwrap().current_location();
+        let pointer_output =
+            self.global_space
+                .output_under(pointer_location)
+                .next()
+                .map(|output| {
+                    let geo = self.global_space.output_geometry(output).unwrap();
+                    (output.clone(), pointer_location - geo.loc.to_f64())
+                });
+
         for Data { output, .. } in &outputs {
             self.global_space.unmap_output(output);
         }
@@ -1815,15 +2661,17 @@ impl Niri {
                     }
                 })
                 .unwrap_or_else(|| {
-                    let x = self
+                    // Place the output to the right of all outputs, aligned to the top.
+                    let placed = self
                         .global_space
                         .outputs()
-                        .map(|output| self.global_space.output_geometry(output).unwrap())
-                        .map(|geom| geom.loc.x + geom.size.w)
-                        .max()
-                        .unwrap_or(0);
+                        .map(|output| self.global_space.output_geometry(output).unwrap());
+                    let bbox = placed.reduce(|acc, geom| acc.merge(geom));
 
-                    Point::from((x, 0))
+                    match bbox {
+                        Some(bbox) => Point::from((bbox.loc.x + bbox.size.w, bbox.loc.y)),
+                        None => Point::from((0, 0)),
+                    }
                 });
 
             self.global_space.map_output(&output, new_position);
@@ -1840,6 +2688,33 @@ impl Niri {
                 self.queue_redraw(&output);
             }
         }
+
+        let new_pointer_location = match pointer_output {
+            Some((output, offset)) => self
+                .global_space
+                .output_geometry(&output)
+                .map(|geo| geo.loc.to_f64() + offset),
+            None => None,
+        };
+        let new_pointer_location = new_pointer_location.or_else(|| {
+            // The pointer's output went away; if the pointer is now outside all outputs, move it
+            // to the first one.
+            if self
+                .global_space
+                .output_under(pointer_location)
+                .next()
+                .is_some()
+            {
+                return None;
+            }
+
+            let output = self.global_space.outputs().next()?;
+            let geo = self.global_space.output_geometry(output).unwrap();
+            Some(center(geo).to_f64())
+        });
+        if let Some(location) = new_pointer_location.filter(|loc| *loc != pointer_location) {
+            self.pointer_location_after_reposition = Some(location);
+        }
     }
 
     pub fn add_output(&mut self, output: Output, refresh_interval: Option<Duration>, vrr: bool) {
@@ -1877,6 +2752,10 @@ impl Niri {
         );
 
         self.layout.add_output(output.clone());
+        let config = self.config.borrow();
+        self.layout
+            .apply_open_on_output_rules(&output, &config.workspaces);
+        drop(config);
 
         let lock_render_state = if self.is_locked() {
             // We haven't rendered anything yet so it's as good as locked.
@@ -1897,8 +2776,14 @@ impl Niri {
             lock_render_state,
             lock_surface: None,
             lock_color_buffer: SolidColorBuffer::new(size, CLEAR_COLOR_LOCKED),
+            idle_dim_buffer: SolidColorBuffer::new(size, [0., 0., 0., 1.]),
+            pick_highlight_buffer: SolidColorBuffer::new(size, PICK_HIGHLIGHT_COLOR),
             screen_transition: None,
+            backdrop: None,
+            magnifier: Magnifier::new(),
             debug_damage_tracker: OutputDamageTracker::from_output(&output),
+            focus_history: FocusHistory::new(),
+            layout_snapshot: None,
         };
         let rv = self.output_state.insert(output.clone(), state);
         assert!(rv.is_none(), "output was already tracked");
@@ -1971,6 +2856,8 @@ impl Niri {
         }
 
         if self.screenshot_ui.close() {
+            #[cfg(feature = "dbus")]
+            self.portal_screenshot = None;
             self.cursor_manager
                 .set_cursor_image(CursorImageStatus::default_named());
             self.queue_redraw_all();
@@ -1988,6 +2875,8 @@ impl Niri {
             state.background_buffer.resize(output_size);
 
             state.lock_color_buffer.resize(output_size);
+            state.idle_dim_buffer.resize(output_size);
+            state.pick_highlight_buffer.resize(output_size);
             if is_locked {
                 if let Some(lock_surface) = &state.lock_surface {
                     configure_lock_surface(lock_surface, output);
@@ -2006,6 +2895,8 @@ impl Niri {
             // physical coordinates.
             if old_size != size || old_scale != scale || old_transform != transform {
                 self.screenshot_ui.close();
+                #[cfg(feature = "dbus")]
+                self.portal_screenshot = None;
                 self.cursor_manager
                     .set_cursor_image(CursorImageStatus::default_named());
                 self.queue_redraw_all();
@@ -2025,6 +2916,68 @@ impl Niri {
         backend.set_monitors_active(false);
     }
 
+    /// Restarts the built-in idle actions after user activity.
+    pub fn notify_idle_activity(&mut self) {
+        self.idle.last_activity = Instant::now();
+
+        let was_dimmed = self.idle.is_dimmed;
+        if was_dimmed {
+            self.idle.is_dimmed = false;
+            self.queue_redraw_all();
+        }
+
+        // Once an action has run, the timer points past it, so it has to start over from the
+        // first action.
+        if self.idle.timer.is_none()
+            || was_dimmed
+            || self.idle.did_power_off_monitors
+            || self.idle.did_lock
+        {
+            self.idle.did_power_off_monitors = false;
+            self.idle.did_lock = false;
+            self.schedule_idle_timer();
+        }
+    }
+
+    /// Schedules the timer for the next pending built-in idle action.
+    pub fn schedule_idle_timer(&mut self) {
+        if let Some(token) = self.idle.timer.take() {
+            self.event_loop.remove(token);
+        }
+
+        let config = self.config.borrow();
+        let next = [
+            (config.idle.dim_after_sec, self.idle.is_dimmed),
+            (
+                config.idle.power_off_monitors_after_sec,
+                self.idle.did_power_off_monitors,
+            ),
+            (config.idle.lock_after_sec, self.idle.did_lock),
+        ]
+        .into_iter()
+        .filter(|(_, done)| !done)
+        .filter_map(|(secs, _)| secs)
+        .min();
+        drop(config);
+
+        let Some(next) = next else {
+            return;
+        };
+
+        let elapsed = self.idle.last_activity.elapsed();
+        let timer =
+            Timer::from_duration(Duration::from_secs(u64::from(next)).saturating_sub(elapsed));
+        let token = self
+            .event_loop
+            .insert_source(timer, |_, _, state| {
+                state.niri.idle.timer = None;
+                state.on_idle_timer();
+                TimeoutAction::Drop
+            })
+            .unwrap();
+        self.idle.timer = Some(token);
+    }
+
     pub fn activate_monitors(&mut self, backend: &mut Backend) {
         if self.monitors_active {
             return;
@@ -2036,6 +2989,40 @@ impl Niri {
         self.queue_redraw_all();
     }
 
+    /// Keeps the resize throttle timer in line with the earliest held back configure.
+    ///
+    /// The configures go out on the refresh after the timer fires.
+    fn update_resize_throttle_timer(&mut self) {
+        let mut deadline: Option<Duration> = None;
+        self.layout.with_windows(|mapped, _| {
+            if let Some(time) = mapped.throttled_configure_deadline() {
+                deadline = Some(deadline.map_or(time, |d| d.min(time)));
+            }
+        });
+
+        if self.resize_throttle_timer.map(|(_, time)| time) == deadline {
+            return;
+        }
+
+        if let Some((token, _)) = self.resize_throttle_timer.take() {
+            self.event_loop.remove(token);
+        }
+
+        let Some(deadline) = deadline else {
+            return;
+        };
+
+        let timer = Timer::from_duration(deadline.saturating_sub(get_monotonic_time()));
+        let token = self
+            .event_loop
+            .insert_source(timer, |_, _, state| {
+                state.niri.resize_throttle_timer = None;
+                TimeoutAction::Drop
+            })
+            .unwrap();
+        self.resize_throttle_timer = Some((token, deadline));
+    }
+
     pub fn output_under(&self, pos: Point<f64, Logical>) -> Option<(&Output, Point<f64, Logical>)> {
         let output = self.global_space.output_under(pos).next()?;
         let pos_within_output = pos
@@ -2054,37 +3041,152 @@ impl Niri {
     /// The cursor may be inside the window's activation region, but not within the window's input
     /// region.
     pub fn window_under(&self, pos: Point<f64, Logical>) -> Option<&Mapped> {
-        if self.is_locked() || self.screenshot_ui.is_open() {
+        if self.is_locked() || self.screenshot_ui.is_open() || self.is_input_inhibited() {
             return None;
         }
 
         let (output, pos_within_output) = self.output_under(pos)?;
 
-        // Check if some layer-shell surface is on top.
+        // While zoomed in, look up what is drawn at this position. The pointer is the zoom
+        // origin, so this matters for touch and tablet positions elsewhere on the output.
+        let output_loc = pos - pos_within_output;
+        let pos_within_output = self.unmagnified_pos(output, pos_within_output);
+        if self.is_layout_covered(output, pos_within_output) {
+            return None;
+        }
+
+        // Activate the tile that was drawn here in the last frame.
+        if let Some(snapshot) = self.layout_snapshot(output) {
+            let tile = snapshot.tile_under(pos_within_output)?;
+            return self
+                .layout
+                .windows_for_output(output)
+                .find(|mapped| mapped.window == tile.id);
+        }
+
+        let (window, _loc) = self.layout.element_under(output_loc + pos_within_output)?;
+        Some(window)
+    }
+
+    /// Returns the window and edges to resize for the resize grip between tiles at the position.
+    pub fn resize_grip_under(&self, pos: Point<f64, Logical>) -> Option<(&Mapped, ResizeEdge)> {
+        if self.is_locked() || self.screenshot_ui.is_open() || self.is_input_inhibited() {
+            return None;
+        }
+
+        let (output, pos_within_output) = self.output_under(pos)?;
+        let pos_within_output = self.unmagnified_pos(output, pos_within_output);
+        if self.is_layout_covered(output, pos_within_output) {
+            return None;
+        }
+
+        self.layout.resize_grip_under(output, pos_within_output)
+    }
+
+    /// Returns whether some layer-shell surface is on top of the layout at the position.
+    fn is_layout_covered(&self, output: &Output, pos_within_output: Point<f64, Logical>) -> bool {
         let layers = layer_map_for_output(output);
         let layer_under = |layer| layers.layer_under(layer, pos_within_output).is_some();
         if layer_under(Layer::Overlay) {
-            return None;
+            return true;
         }
 
-        let mon = self.layout.monitor_for_output(output).unwrap();
-        if !mon.render_above_top_layer() && layer_under(Layer::Top) {
-            return None;
+        !self.render_above_top_layer(output) && layer_under(Layer::Top)
+    }
+
+    /// Returns whether the layout on the output is drawn above the top layer-shell layer.
+    ///
+    /// Uses the layout snapshot of the last frame, so that hit-testing matches what is on screen.
+    pub fn render_above_top_layer(&self, output: &Output) -> bool {
+        match self.layout_snapshot(output) {
+            Some(snapshot) => snapshot.render_above_top_layer,
+            None => {
+                let mon = self.layout.monitor_for_output(output).unwrap();
+                mon.render_above_top_layer()
+            }
         }
+    }
 
-        let (window, _loc) = self.layout.window_under(output, pos_within_output)?;
-        Some(window)
+    fn layout_snapshot(&self, output: &Output) -> Option<&LayoutSnapshot<Window>> {
+        self.output_state
+            .get(output)
+            .and_then(|state| state.layout_snapshot.as_ref())
+    }
+
+    /// Shows a resize cursor while the pointer hovers a resize grip between tiles.
+    pub fn update_resize_grip_cursor(&mut self, pos: Point<f64, Logical>) {
+        if self.pointer_grab_ongoing {
+            return;
+        }
+
+        // Layer surfaces above the layout are handled in resize_grip_under().
+        let edges = if self.pointer_focus.window.is_none() {
+            self.resize_grip_under(pos).map(|(_, edges)| edges)
+        } else {
+            None
+        };
+
+        if let Some(edges) = edges {
+            self.cursor_manager
+                .set_cursor_image(CursorImageStatus::Named(edges.cursor_icon()));
+            self.resize_grip_hovered = true;
+        } else if mem::take(&mut self.resize_grip_hovered) && self.pointer_focus.window.is_none() {
+            self.cursor_manager
+                .set_cursor_image(CursorImageStatus::default_named());
+        }
     }
 
     /// Returns the window under the cursor to be activated.
     ///
     /// The cursor may be inside the window's activation region, but not within the window's input
     /// region.
+    /// Gives the keyboard focus to a layer surface with on-demand keyboard interactivity.
+    pub fn set_layer_shell_on_demand_focus(&mut self, surface: LayerSurface) {
+        if self.layer_shell_on_demand_focus.is_none() {
+            self.layer_shell_on_demand_focus_return =
+                self.layout.focus().map(|mapped| mapped.window.clone());
+        }
+        self.layer_shell_on_demand_focus = Some(surface);
+    }
+
+    /// Takes the keyboard focus away from the on-demand layer surface.
+    ///
+    /// With `restore`, the focus goes back to the window that had it before the layer surface took
+    /// it. Otherwise, the focus stays wherever the layout has it now.
+    pub fn clear_layer_shell_on_demand_focus(&mut self, restore: bool) {
+        if self.layer_shell_on_demand_focus.take().is_none() {
+            return;
+        }
+
+        let window = self.layer_shell_on_demand_focus_return.take();
+        if let Some(window) = window.filter(|_| restore) {
+            if self.layout.has_window(&window) {
+                self.layout.activate_window(&window);
+            }
+        }
+    }
+
     pub fn window_under_cursor(&self) -> Option<&Mapped> {
         let pos = self.seat.get_pointer().unwrap().current_location();
         self.window_under(pos)
     }
 
+    /// Returns whether the keyboard-focused window has inhibited compositor shortcuts.
+    ///
+    /// Binds without `allow-inhibiting=false` should go to the window in this case.
+    pub fn is_inhibiting_shortcuts(&self) -> bool {
+        let KeyboardFocus::Layout {
+            surface: Some(surface),
+        } = &self.keyboard_focus
+        else {
+            return false;
+        };
+
+        self.layout
+            .find_window_and_output(surface)
+            .is_some_and(|(mapped, _)| mapped.is_inhibiting_shortcuts())
+    }
+
     /// Returns the surface under cursor and its position in the global space.
     ///
     /// Pointer needs location in global space, and focused window location compatible with that
@@ -2128,6 +3230,10 @@ impl Niri {
             return rv;
         }
 
+        // While zoomed in, look up what is drawn at this position.
+        let screen_pos_within_output = pos_within_output;
+        let pos_within_output = self.unmagnified_pos(output, pos_within_output);
+
         let layers = layer_map_for_output(output);
         let layer_surface_under = |layer| {
             layers
@@ -2143,8 +3249,8 @@ impl Niri {
                         .map(|(surface, pos_within_layer)| {
                             (surface, pos_within_layer.to_f64() + layer_pos_within_output)
                         })
+                        .map(|s| (s, None, Some(layer.clone())))
                 })
-                .map(|s| (s, None))
         };
 
         let window_under = || {
@@ -2161,93 +3267,536 @@ impl Niri {
                         .map(|(s, pos_within_window)| {
                             (s, pos_within_window.to_f64() + win_pos_within_output)
                         })
-                        .map(|s| (s, Some(window.clone())))
+                        .map(|s| (s, Some(window.clone()), None))
                 })
         };
 
-        let mon = self.layout.monitor_for_output(output).unwrap();
+        let mut under = layer_surface_under(Layer::Overlay);
+
+        if self.render_above_top_layer(output) {
+            under = under
+                .or_else(window_under)
+                .or_else(|| layer_surface_under(Layer::Top));
+        } else {
+            under = under
+                .or_else(|| layer_surface_under(Layer::Top))
+                .or_else(window_under);
+        }
+
+        let Some(((surface, surface_pos_within_output), window, layer)) = under
+            .or_else(|| layer_surface_under(Layer::Bottom))
+            .or_else(|| layer_surface_under(Layer::Background))
+        else {
+            return rv;
+        };
+
+        // While input is inhibited, only the inhibiting client gets the pointer.
+        if let Some(client) = self.input_inhibit_manager_state.inhibiting_client() {
+            if surface.client().map(|c| c.id()) != Some(client) {
+                return rv;
+            }
+        }
+
+        // Shift the surface so that the position within it matches the unzoomed one.
+        let surface_pos_within_output =
+            surface_pos_within_output + (screen_pos_within_output - pos_within_output);
+
+        let surface_loc_in_global_space =
+            surface_pos_within_output + output_pos_in_global_space.to_f64();
+
+        rv.surface = Some((surface, surface_loc_in_global_space));
+        rv.window = window;
+        rv.layer = layer;
+        rv
+    }
+
+    /// Returns the point that the magnifier zooms around: the cursor, clamped to the output.
+    fn magnifier_origin(&self, output: &Output) -> Point<f64, Logical> {
+        let output_geo = self.global_space.output_geometry(output).unwrap();
+        let pointer = self.seat.get_pointer().unwrap().current_location();
+        let pos = pointer - output_geo.loc.to_f64();
+        let size = output_geo.size.to_f64();
+        Point::from((pos.x.clamp(0., size.w), pos.y.clamp(0., size.h)))
+    }
+
+    /// Maps a position on the zoomed output to the position in the unzoomed output contents.
+    fn unmagnified_pos(
+        &self,
+        output: &Output,
+        pos_within_output: Point<f64, Logical>,
+    ) -> Point<f64, Logical> {
+        let origin = self.magnifier_origin(output);
+        self.output_state[output]
+            .magnifier
+            .unmap(origin, pos_within_output)
+    }
+
+    /// Changes the magnifier zoom of the output under the cursor.
+    pub fn update_magnifier(&mut self, f: impl FnOnce(&mut Magnifier, niri_config::Animation)) {
+        let Some(output) = self.output_under_cursor() else {
+            return;
+        };
+
+        let config = self.config.borrow().animations.magnifier_zoom.0;
+        let state = self.output_state.get_mut(&output).unwrap();
+        f(&mut state.magnifier, config);
+        self.queue_redraw(&output);
+    }
+
+    pub fn output_under_cursor(&self) -> Option<Output> {
+        let pos = self.seat.get_pointer().unwrap().current_location();
+        self.global_space.output_under(pos).next().cloned()
+    }
+
+    pub fn output_left(&self) -> Option<Output> {
+        let active = self.layout.active_output()?;
+        let active_geo = self.global_space.output_geometry(active).unwrap();
+        let extended_geo = Rectangle::from_loc_and_size(
+            (i32::MIN / 2, active_geo.loc.y),
+            (i32::MAX, active_geo.size.h),
+        );
+
+        self.global_space
+            .outputs()
+            .map(|output| (output, self.global_space.output_geometry(output).unwrap()))
+            .filter(|(_, geo)| center(*geo).x < center(active_geo).x && geo.overlaps(extended_geo))
+            .min_by_key(|(_, geo)| center(active_geo).x - center(*geo).x)
+            .map(|(output, _)| output)
+            .cloned()
+    }
+
+    pub fn output_right(&self) -> Option<Output> {
+        let active = self.layout.active_output()?;
+        let active_geo = self.global_space.output_geometry(active).unwrap();
+        let extended_geo = Rectangle::from_loc_and_size(
+            (i32::MIN / 2, active_geo.loc.y),
+            (i32::MAX, active_geo.size.h),
+        );
+
+        self.global_space
+            .outputs()
+            .map(|output| (output, self.global_space.output_geometry(output).unwrap()))
+            .filter(|(_, geo)| center(*geo).x > center(active_geo).x && geo.overlaps(extended_geo))
+            .min_by_key(|(_, geo)| center(*geo).x - center(active_geo).x)
+            .map(|(output, _)| output)
+            .cloned()
+    }
+
+    pub fn output_up(&self) -> Option<Output> {
+        let active = self.layout.active_output()?;
+        let active_geo = self.global_space.output_geometry(active).unwrap();
+        let extended_geo = Rectangle::from_loc_and_size(
+            (active_geo.loc.x, i32::MIN / 2),
+            (active_geo.size.w, i32::MAX),
+        );
+
+        self.global_space
+            .outputs()
+            .map(|output| (output, self.global_space.output_geometry(output).unwrap()))
+            .filter(|(_, geo)| center(*geo).y < center(active_geo).y && geo.overlaps(extended_geo))
+            .min_by_key(|(_, geo)| center(active_geo).y - center(*geo).y)
+            .map(|(output, _)| output)
+            .cloned()
+    }
+
+    /// Saves the column structure of the active workspace as a named layout preset.
+    pub fn save_layout_preset(&mut self, name: String) {
+        let Some(ws) = self.layout.active_workspace() else {
+            return;
+        };
+
+        let preset = LayoutPreset::from_workspace(ws, |mapped| app_id(mapped.toplevel()));
+        if preset.is_empty() {
+            warn!("not saving layout preset {name} with no windows");
+            return;
+        }
+
+        self.layout_presets.insert(name, preset);
+    }
+
+    /// Loads a named layout preset, so that newly opened windows fill its placeholders.
+    ///
+    /// The preset columns go right of the focused window.
+    pub fn load_layout_preset(&mut self, name: &str) {
+        let Some(preset) = self.layout_presets.get(name) else {
+            warn!("layout preset {name} not found");
+            return;
+        };
+
+        let anchor = self.layout.focus().map(|mapped| mapped.window.clone());
+        self.pending_layout_preset = Some(PendingPreset::new(preset, anchor));
+    }
+
+    /// Fills a placeholder of the pending layout preset with a newly mapped window.
+    pub fn take_preset_placement(&mut self, window: &Window) -> Option<PresetPlacement<Window>> {
+        let pending = self.pending_layout_preset.as_mut()?;
+        let app_id = app_id(window.toplevel().expect("no X11 support"))?;
+
+        let layout = &self.layout;
+        let placement = pending.place(&app_id, window.clone(), |w| layout.has_window(w));
+
+        if pending.is_done() {
+            self.pending_layout_preset = None;
+        }
+
+        placement
+    }
+
+    /// Adds the focused window to a window group, or removes it from its group.
+    pub fn set_focused_window_group(&mut self, group: Option<String>) {
+        let Some(window) = self.layout.focus().map(|mapped| mapped.window.clone()) else {
+            return;
+        };
+
+        let toplevel = window.toplevel().expect("no X11 support");
+        if let Some(app_id) = app_id(toplevel) {
+            self.window_groups.set_group(app_id, group.clone());
+        }
+
+        if let Some((mapped, _)) = self
+            .layout
+            .find_window_and_output_mut(toplevel.wl_surface())
+        {
+            mapped.set_group(group);
+        }
+    }
+
+    /// Returns the windows of a window group, in layout order.
+    pub fn window_group(&self, name: &str) -> Vec<Window> {
+        let mut windows = vec![];
+        self.layout.with_windows(|mapped, _| {
+            if mapped.group() == Some(name) {
+                windows.push(mapped.window.clone());
+            }
+        });
+        windows
+    }
+
+    /// Returns a window with this app ID, preferring the focused one.
+    pub fn window_with_app_id(&self, id: &str) -> Option<Window> {
+        let matches = |window: &Window| {
+            let toplevel = window.toplevel().expect("no X11 support");
+            app_id(toplevel).as_deref() == Some(id)
+        };
+
+        if let Some(focus) = self.layout.focus() {
+            if matches(&focus.window) {
+                return Some(focus.window.clone());
+            }
+        }
+
+        let mut rv = None;
+        self.layout.with_windows(|mapped, _| {
+            if rv.is_none() && matches(&mapped.window) {
+                rv = Some(mapped.window.clone());
+            }
+        });
+        rv
+    }
+
+    /// Remembers the size of a window that is going away, if enabled in the config.
+    pub fn remember_window_size(&mut self, window: &Window) {
+        if !self.config.borrow().layout.remember_window_sizes {
+            return;
+        }
+
+        let Some(app_id) = app_id(window.toplevel().expect("no X11 support")) else {
+            return;
+        };
+        let Some((width, height)) = self.layout.window_size_preference(window) else {
+            return;
+        };
+
+        if !self.remembered_sizes.remember(app_id, width, height) {
+            return;
+        }
+
+        // Batch up the changes from several windows closing in a row into one save.
+        if self.remembered_sizes_save_timer.is_some() {
+            return;
+        }
+
+        let timer = Timer::from_duration(REMEMBERED_SIZES_SAVE_DELAY);
+        let token = self
+            .event_loop
+            .insert_source(timer, |_, _, state| {
+                state.niri.remembered_sizes_save_timer = None;
+                state.niri.remembered_sizes.save();
+                TimeoutAction::Drop
+            })
+            .unwrap();
+        self.remembered_sizes_save_timer = Some(token);
+    }
+
+    /// Applies the remembered window height to a newly mapped window, if enabled in the config.
+    pub fn restore_window_height(&mut self, window: &Window) {
+        if !self.config.borrow().layout.remember_window_sizes {
+            return;
+        }
+
+        let Some(app_id) = app_id(window.toplevel().expect("no X11 support")) else {
+            return;
+        };
+        let Some(height) = self.remembered_sizes.height(&app_id) else {
+            return;
+        };
+
+        self.layout
+            .set_window_height_of(window, SizeChange::SetFixed(height.round() as i32));
+    }
+
+    /// Returns the focus history of the active output.
+    pub fn focus_history_mut(&mut self) -> Option<&mut FocusHistory<Window>> {
+        let output = self.layout.active_output()?;
+        let state = self.output_state.get_mut(output)?;
+        Some(&mut state.focus_history)
+    }
+
+    /// Removes a window that went away from the focus histories.
+    pub fn forget_focused_window(&mut self, window: &Window) {
+        for state in self.output_state.values_mut() {
+            state.focus_history.retain(|win| win != window);
+        }
+    }
+
+    pub fn ipc_focus_history(&self) -> Vec<niri_ipc::FocusHistory> {
+        self.output_state
+            .iter()
+            .map(|(output, state)| niri_ipc::FocusHistory {
+                output: output.name(),
+                windows: state
+                    .focus_history
+                    .entries()
+                    .iter()
+                    .map(ipc_window)
+                    .collect(),
+                current: state.focus_history.current(),
+            })
+            .collect()
+    }
+
+    pub fn find_minimized_window(&self, wl_surface: &WlSurface) -> Option<usize> {
+        self.minimized_windows
+            .iter()
+            .position(|minimized| minimized.mapped.toplevel().wl_surface() == wl_surface)
+    }
+
+    /// Returns the visual rectangle of the focused tile in the global space.
+    pub fn focused_tile_global_rect(&self) -> Option<Rectangle<f64, Logical>> {
+        let output = self.layout.active_output()?;
+        let monitor = self.layout.monitor_for_output(output).unwrap();
+        let mut rect = monitor.active_tile_visual_rectangle()?;
+        let output_geo = self.global_space.output_geometry(output).unwrap();
+        rect.loc += output_geo.loc.to_f64();
+        Some(rect)
+    }
+
+    pub fn ipc_cursor_position(&self) -> niri_ipc::CursorPosition {
+        let pos = self.seat.get_pointer().unwrap().current_location();
+        let output = self.output_under(pos).map(|(output, pos_within_output)| {
+            niri_ipc::OutputCursorPosition {
+                name: output.name(),
+                x: pos_within_output.x,
+                y: pos_within_output.y,
+            }
+        });
+
+        niri_ipc::CursorPosition {
+            x: pos.x,
+            y: pos.y,
+            output,
+        }
+    }
 
-        let mut under = layer_surface_under(Layer::Overlay);
+    pub fn ipc_minimized_windows(&self) -> Vec<niri_ipc::Window> {
+        self.minimized_windows
+            .iter()
+            .map(|minimized| ipc_window(&minimized.mapped.window))
+            .collect()
+    }
 
-        if mon.render_above_top_layer() {
-            under = under
-                .or_else(window_under)
-                .or_else(|| layer_surface_under(Layer::Top));
-        } else {
-            under = under
-                .or_else(|| layer_surface_under(Layer::Top))
-                .or_else(window_under);
+    /// Starts an interactive pick, cancelling the previous one.
+    pub fn start_pick(&mut self, pick: PickRequest) {
+        self.cancel_pick();
+
+        if let PickRequest::Window(_) = pick {
+            let mut windows = vec![];
+            self.layout.with_visible_windows(|mapped, output, geo| {
+                windows.push((output.clone(), mapped.window.clone(), geo));
+            });
+            self.window_hints.open(windows);
         }
 
-        let Some(((surface, surface_pos_within_output), window)) = under
-            .or_else(|| layer_surface_under(Layer::Bottom))
-            .or_else(|| layer_surface_under(Layer::Background))
-        else {
-            return rv;
+        self.pick = Some(pick);
+        self.queue_redraw_all();
+    }
+
+    /// Cancels the interactive pick, replying with nothing.
+    pub fn cancel_pick(&mut self) {
+        let Some(pick) = self.pick.take() else {
+            return;
         };
 
-        let surface_loc_in_global_space =
-            surface_pos_within_output + output_pos_in_global_space.to_f64();
+        match pick {
+            PickRequest::Window(tx) => {
+                let _ = tx.send_blocking(None);
+                self.window_hints.close();
+            }
+            PickRequest::Output(tx) => {
+                let _ = tx.send_blocking(None);
+            }
+        }
 
-        rv.surface = Some((surface, surface_loc_in_global_space));
-        rv.window = window;
-        rv
+        self.queue_redraw_all();
     }
 
-    pub fn output_under_cursor(&self) -> Option<Output> {
-        let pos = self.seat.get_pointer().unwrap().current_location();
-        self.global_space.output_under(pos).next().cloned()
+    /// Finishes an interactive window pick with this window.
+    pub fn pick_window(&mut self, window: &Window) {
+        let Some(PickRequest::Window(tx)) = &self.pick else {
+            return;
+        };
+        let tx = tx.clone();
+        self.pick = None;
+
+        let wl_surface = window.toplevel().expect("no X11 support").wl_surface();
+        let picked = self
+            .layout
+            .find_window_and_output(wl_surface)
+            .map(|(mapped, _)| {
+                let niri_ipc::Window { title, app_id } = ipc_window(&mapped.window);
+                niri_ipc::PickedWindow {
+                    id: mapped.id().get(),
+                    title,
+                    app_id,
+                }
+            });
+        let _ = tx.send_blocking(picked);
+
+        self.window_hints.close();
+        self.queue_redraw_all();
     }
 
-    pub fn output_left(&self) -> Option<Output> {
-        let active = self.layout.active_output()?;
-        let active_geo = self.global_space.output_geometry(active).unwrap();
-        let extended_geo = Rectangle::from_loc_and_size(
-            (i32::MIN / 2, active_geo.loc.y),
-            (i32::MAX, active_geo.size.h),
-        );
+    /// Finishes an interactive output pick with this output.
+    pub fn pick_output(&mut self, output: &Output) {
+        let Some(PickRequest::Output(tx)) = &self.pick else {
+            return;
+        };
+        let tx = tx.clone();
+        self.pick = None;
 
-        self.global_space
-            .outputs()
-            .map(|output| (output, self.global_space.output_geometry(output).unwrap()))
-            .filter(|(_, geo)| center(*geo).x < center(active_geo).x && geo.overlaps(extended_geo))
-            .min_by_key(|(_, geo)| center(active_geo).x - center(*geo).x)
-            .map(|(output, _)| output)
-            .cloned()
+        let _ = tx.send_blocking(Some(output.name()));
+        self.queue_redraw_all();
     }
 
-    pub fn output_right(&self) -> Option<Output> {
-        let active = self.layout.active_output()?;
-        let active_geo = self.global_space.output_geometry(active).unwrap();
-        let extended_geo = Rectangle::from_loc_and_size(
-            (i32::MIN / 2, active_geo.loc.y),
-            (i32::MAX, active_geo.size.h),
-        );
+    /// Handles a click during an interactive pick.
+    ///
+    /// Returns `false` if there's no pick in progress.
+    pub fn pick_under_cursor(&mut self) -> bool {
+        match &self.pick {
+            None => return false,
+            Some(PickRequest::Window(_)) => {
+                if let Some(window) = self.window_under_cursor().map(|m| m.window.clone()) {
+                    self.pick_window(&window);
+                } else {
+                    self.cancel_pick();
+                }
+            }
+            Some(PickRequest::Output(_)) => {
+                if let Some(output) = self.output_under_cursor() {
+                    self.pick_output(&output);
+                } else {
+                    self.cancel_pick();
+                }
+            }
+        }
 
-        self.global_space
-            .outputs()
-            .map(|output| (output, self.global_space.output_geometry(output).unwrap()))
-            .filter(|(_, geo)| center(*geo).x > center(active_geo).x && geo.overlaps(extended_geo))
-            .min_by_key(|(_, geo)| center(*geo).x - center(active_geo).x)
-            .map(|(output, _)| output)
-            .cloned()
+        true
     }
 
-    pub fn output_up(&self) -> Option<Output> {
-        let active = self.layout.active_output()?;
-        let active_geo = self.global_space.output_geometry(active).unwrap();
-        let extended_geo = Rectangle::from_loc_and_size(
-            (active_geo.loc.x, i32::MIN / 2),
-            (active_geo.size.w, i32::MAX),
-        );
+    fn texture_cache_usage(&self) -> TextureCacheUsage {
+        let (screenshot_ui, screenshot_ui_inactive) = self.screenshot_ui.texture_bytes();
+        TextureCacheUsage {
+            ui: self.hotkey_overlay.evictable_texture_bytes()
+                + self.config_error_notification.evictable_texture_bytes(),
+            closing_windows: self.layout.closing_windows_texture_bytes(),
+            screenshot_ui_inactive,
+            screenshot_ui,
+        }
+    }
 
-        self.global_space
-            .outputs()
-            .map(|output| (output, self.global_space.output_geometry(output).unwrap()))
-            .filter(|(_, geo)| center(*geo).y < center(active_geo).y && geo.overlaps(extended_geo))
-            .min_by_key(|(_, geo)| center(active_geo).y - center(*geo).y)
-            .map(|(output, _)| output)
-            .cloned()
+    /// Evicts cached textures if they exceed the memory budget.
+    pub fn enforce_texture_budget(&mut self) {
+        let usage = self.texture_cache_usage();
+        let eviction = self.texture_cache.plan(usage);
+        if eviction == Eviction::default() {
+            return;
+        }
+
+        debug!("evicting cached textures, usage: {usage:?}, evicting: {eviction:?}");
+
+        if eviction.ui {
+            self.hotkey_overlay.evict_textures();
+            self.config_error_notification.evict_textures();
+        }
+        if eviction.closing_windows {
+            self.layout.clear_closing_windows();
+        }
+        if eviction.screenshot_ui_inactive {
+            self.screenshot_ui.evict_inactive_outputs();
+        }
+
+        self.queue_redraw_all();
+    }
+
+    pub fn ipc_texture_cache_stats(&self) -> niri_ipc::TextureCacheStats {
+        let usage = self.texture_cache_usage();
+        niri_ipc::TextureCacheStats {
+            budget: self.texture_cache.budget(),
+            ui: usage.ui,
+            closing_windows: usage.closing_windows,
+            screenshot_ui: usage.screenshot_ui,
+            evictions: self.texture_cache.evictions(),
+        }
+    }
+
+    /// Focuses the output and places the pointer as set in the `startup` config section.
+    ///
+    /// Called once the backend has connected the initial outputs.
+    fn apply_startup_config(&mut self) {
+        let startup = self.config.borrow().startup.clone();
+
+        let output = match startup.focus_output.as_deref() {
+            Some(name) if name.eq_ignore_ascii_case("primary") => {
+                self.layout.primary_output().cloned()
+            }
+            Some(name) => {
+                let output = self.output_by_name(name);
+                if output.is_none() {
+                    warn!("startup output {name} is not connected");
+                }
+                output
+            }
+            None => None,
+        };
+        if let Some(output) = &output {
+            self.layout.focus_output(output);
+        }
+
+        let Some(position) = startup.pointer_position else {
+            return;
+        };
+        let Some(output) = output.or_else(|| self.layout.active_output().cloned()) else {
+            return;
+        };
+        let Some(geo) = self.global_space.output_geometry(&output) else {
+            return;
+        };
+
+        let location = match position {
+            StartupPointerPosition::Center => center(geo).to_f64(),
+            StartupPointerPosition::TopLeft => geo.loc.to_f64(),
+        };
+        self.pointer_location_after_reposition = Some(location);
     }
 
     pub fn output_by_name(&self, name: &str) -> Option<Output> {
@@ -2313,6 +3862,17 @@ impl Niri {
             .or_else(|| self.global_space.outputs().next())
     }
 
+    /// Returns the center of a layer-shell surface in global coordinates.
+    pub fn layer_surface_center(&self, surface: &WlSurface) -> Option<Point<f64, Logical>> {
+        self.layout.outputs().find_map(|output| {
+            let map = layer_map_for_output(output);
+            let layer = map.layer_for_surface(surface, WindowSurfaceType::TOPLEVEL)?;
+            let geo = map.layer_geometry(layer)?;
+            let output_geo = self.global_space.output_geometry(output)?;
+            Some(output_geo.loc.to_f64() + center(geo).to_f64())
+        })
+    }
+
     pub fn output_for_root(&self, root: &WlSurface) -> Option<&Output> {
         // Check the main layout.
         let win_out = self.layout.find_window_and_output(root);
@@ -2387,7 +3947,7 @@ impl Niri {
         let pointer_pos = pointer_pos - output_pos.to_f64();
 
         // Get the render cursor to draw.
-        let cursor_scale = output_scale.integer_scale();
+        let cursor_scale = output_scale.fractional_scale();
         let render_cursor = self.cursor_manager.get_render_cursor(cursor_scale);
 
         let output_scale = Scale::from(output.current_scale().fractional_scale());
@@ -2415,10 +3975,12 @@ impl Niri {
                 cursor,
             } => {
                 let (idx, frame) = cursor.frame(self.start_time.elapsed().as_millis() as u32);
-                let hotspot = XCursor::hotspot(frame).to_logical(scale);
-                let pointer_pos =
-                    (pointer_pos - hotspot.to_f64()).to_physical_precise_round(output_scale);
+                let hotspot = XCursor::hotspot(frame).to_f64().to_logical(scale);
+                let pointer_pos = (pointer_pos - hotspot).to_physical_precise_round(output_scale);
 
+                // The texture is at buffer scale 1, so size it explicitly, which also makes it
+                // come out right on fractional scales.
+                let size = XCursor::logical_size(frame, scale);
                 let texture = self.cursor_texture_cache.get(icon, scale, &cursor, idx);
                 let mut pointer_elements = vec![];
                 let pointer_element = match MemoryRenderBufferRenderElement::from_buffer(
@@ -2427,7 +3989,7 @@ impl Niri {
                     &texture,
                     None,
                     None,
-                    None,
+                    Some(size),
                     Kind::Cursor,
                 ) {
                     Ok(element) => Some(element),
@@ -2566,7 +4128,7 @@ impl Niri {
 
                     // The default cursor is rendered at the right scale for each output, which
                     // means that it may have a different hotspot for each output.
-                    let output_scale = output.current_scale().integer_scale();
+                    let output_scale = bucketed_scale(output.current_scale().fractional_scale());
                     let cursor = self
                         .cursor_manager
                         .get_cursor_with_name(icon, output_scale)
@@ -2574,7 +4136,10 @@ impl Niri {
 
                     // For simplicity, we always use frame 0 for this computation. Let's hope the
                     // hotspot doesn't change between frames.
-                    let hotspot = XCursor::hotspot(&cursor.frames()[0]).to_logical(output_scale);
+                    let hotspot = XCursor::hotspot(&cursor.frames()[0])
+                        .to_f64()
+                        .to_logical(output_scale)
+                        .to_i32_round();
 
                     let surface_pos = pointer_pos.to_i32_round() - hotspot;
                     let bbox = bbox_from_surface_tree(surface, surface_pos);
@@ -2603,6 +4168,15 @@ impl Niri {
         }
     }
 
+    /// Drops old activation tokens, including the ones created for spawned commands that never
+    /// used them.
+    pub fn refresh_activation_tokens(&mut self) {
+        let _span = tracy_client::span!("Niri::refresh_activation_tokens");
+
+        self.activation_state
+            .retain_tokens(|_, data| data.timestamp.elapsed() < ACTIVATION_TOKEN_LIFETIME);
+    }
+
     pub fn refresh_idle_inhibit(&mut self) {
         let _span = tracy_client::span!("Niri::refresh_idle_inhibit");
 
@@ -2613,8 +4187,66 @@ impl Niri {
                 with_states(surface, |states| {
                     surface_primary_scanout_output(surface, states).is_some()
                 })
-            });
+            })
+            || (self.config.borrow().idle.inhibit_while_playing && self.is_media_playing());
         self.idle_notifier_state.set_is_inhibited(is_inhibited);
+        self.idle.is_inhibited = is_inhibited;
+    }
+
+    /// Returns whether a visible window is playing audio or showing a video.
+    ///
+    /// This is a heuristic for apps that don't inhibit idle themselves.
+    fn is_media_playing(&self) -> bool {
+        let playing_pids = self
+            .pipewire
+            .as_ref()
+            .map(|pw| pw.playing_pids())
+            .unwrap_or_default();
+
+        let mut rv = false;
+        self.layout.with_windows(|mapped, _| {
+            if rv {
+                return;
+            }
+
+            let surface = mapped.toplevel().wl_surface();
+            let is_visible = with_states(surface, |states| {
+                surface_primary_scanout_output(surface, states).is_some()
+            });
+            if !is_visible {
+                return;
+            }
+
+            if !playing_pids.is_empty() {
+                let pid = surface
+                    .client()
+                    .and_then(|client| client.get_credentials(&self.display_handle).ok())
+                    .map(|credentials| credentials.pid);
+                if pid.is_some_and(|pid| playing_pids.contains(&pid)) {
+                    rv = true;
+                    return;
+                }
+            }
+
+            // Video players often show the video in a subsurface.
+            with_surface_tree_downward(
+                surface,
+                (),
+                |_, _, _| TraversalAction::DoChildren(()),
+                |_, states, _| {
+                    let content_type = *states
+                        .cached_state
+                        .current::<ContentTypeSurfaceCachedState>()
+                        .content_type();
+                    if content_type == ContentType::Video {
+                        rv = true;
+                    }
+                },
+                |_, _, _| !rv,
+            );
+        });
+
+        rv
     }
 
     pub fn refresh_window_rules(&mut self) {
@@ -2638,9 +4270,10 @@ impl Niri {
 
         for win in windows {
             self.layout.update_window(&win, None);
-            win.toplevel()
-                .expect("no X11 support")
-                .send_pending_configure();
+            let surface = win.toplevel().expect("no X11 support").wl_surface();
+            if let Some((mapped, _)) = self.layout.find_window_and_output_mut(surface) {
+                mapped.send_pending_configure();
+            }
         }
         for output in outputs {
             self.queue_redraw(&output);
@@ -2721,6 +4354,21 @@ impl Niri {
             elements = self.pointer_element(renderer, output);
         }
 
+        // Next, the idle dimming. It doesn't apply to screencasts and screenshots.
+        if self.idle.is_dimmed && target == RenderTarget::Output {
+            let state = self.output_state.get(output).unwrap();
+            elements.push(
+                SolidColorRenderElement::from_buffer(
+                    &state.idle_dim_buffer,
+                    (0, 0),
+                    output_scale,
+                    IDLE_DIM_ALPHA,
+                    Kind::Unspecified,
+                )
+                .into(),
+            );
+        }
+
         // Next, the screen transition texture.
         {
             let state = self.output_state.get(output).unwrap();
@@ -2736,6 +4384,61 @@ impl Niri {
             }
         }
 
+        // Next, the close confirm dialog.
+        if let Some(dialog) = &self.close_confirm_dialog {
+            if let Some(element) = dialog.render(renderer, output) {
+                elements.push(element.into());
+            }
+        }
+
+        // Next, the global shortcuts consent dialog.
+        #[cfg(feature = "dbus")]
+        if let Some(element) = self.global_shortcuts.render(renderer, output) {
+            elements.push(element.into());
+        }
+
+        // Next, the interactive output pick highlight.
+        if matches!(self.pick, Some(PickRequest::Output(_)))
+            && target == RenderTarget::Output
+            && self.output_under_cursor().as_ref() == Some(output)
+        {
+            let state = self.output_state.get(output).unwrap();
+            elements.push(
+                SolidColorRenderElement::from_buffer(
+                    &state.pick_highlight_buffer,
+                    (0, 0),
+                    output_scale,
+                    PICK_HIGHLIGHT_ALPHA,
+                    Kind::Unspecified,
+                )
+                .into(),
+            );
+        }
+
+        // Next, the window hints.
+        elements.extend(
+            self.window_hints
+                .render(renderer, output)
+                .into_iter()
+                .map(Into::into),
+        );
+
+        // Next, the resize mode indicator.
+        if let Some(element) = self.resize_mode.render(renderer, output) {
+            elements.push(element.into());
+        }
+
+        // Next, the notifications, shown on the active output.
+        #[cfg(feature = "dbus")]
+        if self.layout.active_output() == Some(output) {
+            elements.extend(
+                self.notifier
+                    .render(renderer, output)
+                    .into_iter()
+                    .map(Into::into),
+            );
+        }
+
         // Next, the config error notification too.
         if let Some(element) = self.config_error_notification.render(renderer, output) {
             elements.push(element.into());
@@ -2807,6 +4510,24 @@ impl Niri {
             elements.push(element.into());
         }
 
+        // Draw the blurred backdrop behind the modal dialogs.
+        if target == RenderTarget::Output && self.is_modal_dialog_open() {
+            if let Some(backdrop) = &state.backdrop {
+                let elem = TextureRenderElement::from_texture_buffer(
+                    backdrop.clone(),
+                    (0., 0.),
+                    1.,
+                    None,
+                    None,
+                    Kind::Unspecified,
+                );
+                elements.push(PrimaryGpuTextureRenderElement(elem).into());
+            }
+        }
+
+        // Everything below is the desktop, which the magnifier zooms.
+        let desktop_start = elements.len();
+
         // Get monitor elements.
         let mon = self.layout.monitor_for_output(output).unwrap();
         let monitor_elements = mon.render_elements(renderer, target);
@@ -2839,7 +4560,7 @@ impl Niri {
         extend_from_layer(&mut elements, Layer::Overlay);
 
         // Then the regular monitor elements and the top layer in varying order.
-        if mon.render_above_top_layer() {
+        if self.render_above_top_layer(output) {
             elements.extend(monitor_elements.into_iter().map(OutputRenderElements::from));
             extend_from_layer(&mut elements, Layer::Top);
         } else {
@@ -2854,6 +4575,17 @@ impl Niri {
         // Then the background.
         elements.push(background);
 
+        // Zoom the desktop around the cursor. Screencasts and screenshots show it unzoomed.
+        if target == RenderTarget::Output && state.magnifier.is_zoomed() {
+            let zoom = state.magnifier.current_zoom();
+            let origin = self
+                .magnifier_origin(output)
+                .to_physical_precise_round(output_scale);
+
+            let desktop = elements.split_off(desktop_start);
+            elements.extend(desktop.into_iter().map(|elem| magnify(elem, origin, zoom)));
+        }
+
         if self.debug_draw_opaque_regions {
             draw_opaque_regions(&mut elements, output_scale);
         }
@@ -2871,10 +4603,14 @@ impl Niri {
             RedrawState::Queued | RedrawState::WaitingForEstimatedVBlankAndQueued(_)
         ));
 
+        let benchmark_clock = self.benchmark.as_ref().map(Benchmark::start_frame);
+
         let target_presentation_time = state.frame_clock.next_presentation_time();
 
         let mut res = RenderResult::Skipped;
         if self.monitors_active {
+            let layout_start = Instant::now();
+
             // Update from the config and advance the animations.
             self.layout.advance_animations(target_presentation_time);
 
@@ -2885,6 +4621,8 @@ impl Niri {
                 }
             }
 
+            state.magnifier.advance_animations(target_presentation_time);
+
             state.unfinished_animations_remain = self
                 .layout
                 .monitor_for_output(output)
@@ -2899,16 +4637,34 @@ impl Niri {
             // Also keep redrawing if the current cursor is animated.
             state.unfinished_animations_remain |= self
                 .cursor_manager
-                .is_current_cursor_animated(output.current_scale().integer_scale());
+                .is_current_cursor_animated(output.current_scale().fractional_scale());
 
             // Also keep redrawing during a screen transition.
             state.unfinished_animations_remain |= state.screen_transition.is_some();
 
+            state.unfinished_animations_remain |= state.magnifier.are_animations_ongoing();
+
             self.layout.update_render_elements(output);
+            state.layout_snapshot = self.layout.snapshot(output);
+            let layout_time = layout_start.elapsed();
 
             // Render.
+            let render_start = Instant::now();
             res = backend.render(self, output, target_presentation_time);
+            let render_time = render_start.elapsed();
+
+            if let Some(benchmark) = &mut self.benchmark {
+                let timings = FrameTimings {
+                    layout: layout_time,
+                    render: render_time,
+                };
+                if benchmark.end_frame(timings) {
+                    benchmark.print_summary();
+                    self.stop_signal.stop();
+                }
+            }
         }
+        drop(benchmark_clock);
 
         let is_locked = self.is_locked();
         let state = self.output_state.get_mut(output).unwrap();
@@ -2993,12 +4749,8 @@ impl Niri {
         output: &Output,
         render_element_states: &RenderElementStates,
     ) {
-        // FIXME: potentially tweak the compare function. The default one currently always prefers a
-        // higher refresh-rate output, which is not always desirable (i.e. with a very small
-        // overlap).
-        //
-        // While we only have cursors and DnD icons crossing output boundaries though, it doesn't
-        // matter all that much.
+        // Surfaces crossing output boundaries are assigned to the output where most of them is
+        // visible, so that frame callbacks and presentation feedback follow that output.
         if let CursorImageStatus::Surface(surface) = &self.cursor_manager.cursor_image() {
             with_surface_tree_downward(
                 surface,
@@ -3010,7 +4762,7 @@ impl Niri {
                         output,
                         states,
                         render_element_states,
-                        default_primary_scanout_output_compare,
+                        largest_visible_area_compare,
                     );
                 },
                 |_, _, _| true,
@@ -3028,18 +4780,19 @@ impl Niri {
                         output,
                         states,
                         render_element_states,
-                        default_primary_scanout_output_compare,
+                        largest_visible_area_compare,
                     );
                 },
                 |_, _, _| true,
             );
         }
 
-        // We're only updating the current output's windows and layer surfaces. This should be fine
-        // as in niri they can only be rendered on a single output at a time.
+        // We're only updating the current output's windows and layer surfaces. Windows can still
+        // end up visible on two outputs at once, for example during an interactive move or right
+        // after moving to a different monitor, so they go through the same visible area comparison.
         //
-        // The reason to do this at all is that it keeps track of whether the surface is visible or
-        // not in a unified way with the pointer surfaces, which makes the logic elsewhere simpler.
+        // This also keeps track of whether the surface is visible or not in a unified way with the
+        // pointer surfaces, which makes the logic elsewhere simpler.
 
         for mapped in self.layout.windows_for_output(output) {
             let win = &mapped.window;
@@ -3058,15 +4811,28 @@ impl Niri {
                     .data_map
                     .get::<Mutex<PrimaryScanoutOutput>>()
                     .unwrap();
-                surface_primary_scanout_output
-                    .lock()
-                    .unwrap()
-                    .update_from_render_element_states(
-                        offscreen_id.cloned().unwrap_or_else(|| surface.into()),
-                        output,
-                        render_element_states,
-                        |_, _, output, _| output,
-                    );
+                let mut primary_scanout_output = surface_primary_scanout_output.lock().unwrap();
+
+                // The visible area stored for another output is only refreshed when that output
+                // redraws. Once the window has moved away from it, that area is stale, so start
+                // over from the current output.
+                if let Some(primary) = primary_scanout_output.current_output() {
+                    let still_there = self.layout.outputs().any(|o| *o == primary)
+                        && self
+                            .layout
+                            .windows_for_output(&primary)
+                            .any(|m| m.window == *win);
+                    if primary != *output && !still_there {
+                        *primary_scanout_output = PrimaryScanoutOutput::default();
+                    }
+                }
+
+                primary_scanout_output.update_from_render_element_states(
+                    offscreen_id.cloned().unwrap_or_else(|| surface.into()),
+                    output,
+                    render_element_states,
+                    largest_visible_area_compare,
+                );
             });
         }
 
@@ -3094,7 +4860,7 @@ impl Niri {
                         output,
                         states,
                         render_element_states,
-                        default_primary_scanout_output_compare,
+                        largest_visible_area_compare,
                     );
                 },
                 |_, _, _| true,
@@ -3110,14 +4876,35 @@ impl Niri {
     ) {
         let _span = tracy_client::span!("Niri::send_dmabuf_feedbacks");
 
-        // We can unconditionally send the current output's feedback to regular and layer-shell
-        // surfaces, as they can only be displayed on a single output at a time. Even if a surface
-        // is currently invisible, this is the DMABUF feedback that it should know about.
+        // We can send the current output's feedback to regular and layer-shell surfaces, as they
+        // can only be displayed on a single output at a time. Windows on hidden workspaces or
+        // scrolled out of view get it when they show up, except for windows that just moved to
+        // this output, which should know about its feedback right away.
+        let visible: HashSet<_> = self
+            .layout
+            .visible_windows_for_output(output)
+            .map(|mapped| mapped.id())
+            .collect();
         for mapped in self.layout.windows_for_output(output) {
+            if !mapped.take_entered_output() && !visible.contains(&mapped.id()) {
+                continue;
+            }
+
+            // Fullscreen windows are the ones most likely to go onto a plane, so offer them the
+            // scanout tranche even while they are composited. Otherwise a client that picked a
+            // format we can't scan out would never get a reason to switch.
+            let toplevel = mapped
+                .is_fullscreen()
+                .then(|| mapped.toplevel().wl_surface());
+
             mapped.window.send_dmabuf_feedback(
                 output,
                 |_, _| Some(output.clone()),
                 |surface, _| {
+                    if Some(surface) == toplevel {
+                        return &feedback.scanout;
+                    }
+
                     select_dmabuf_feedback(
                         surface,
                         render_element_states,
@@ -3299,7 +5086,23 @@ impl Niri {
 
         let frame_callback_time = get_monotonic_time();
 
-        self.layout.with_windows(|mapped, _| {
+        let skip_invisible = self
+            .config
+            .borrow()
+            .debug
+            .disable_frame_callbacks_for_invisible_windows;
+
+        self.layout.with_windows(|mapped, _| {
+            // Windows that are scrolled out of view or on inactive workspaces have no primary
+            // scanout output, since they weren't rendered anywhere.
+            if skip_invisible
+                && with_states(mapped.toplevel().wl_surface(), |states| {
+                    surface_primary_scanout_output(mapped.toplevel().wl_surface(), states).is_none()
+                })
+            {
+                return;
+            }
+
             mapped.window.send_frame(
                 output,
                 frame_callback_time,
@@ -3725,7 +5528,7 @@ impl Niri {
         self.queue_redraw_all();
     }
 
-    pub fn open_screenshot_ui(&mut self, renderer: &mut GlesRenderer) {
+    pub fn open_screenshot_ui(&mut self, renderer: &mut GlesRenderer, record_clip: bool) {
         if self.is_locked() || self.screenshot_ui.is_open() {
             return;
         }
@@ -3785,12 +5588,98 @@ impl Niri {
             .collect();
 
         self.screenshot_ui
-            .open(renderer, screenshots, default_output);
+            .open(renderer, screenshots, default_output, record_clip);
         self.cursor_manager
             .set_cursor_image(CursorImageStatus::Named(CursorIcon::Crosshair));
         self.queue_redraw_all();
     }
 
+    pub fn start_clip_recording(
+        &mut self,
+        output: Output,
+        rect: Rectangle<i32, Physical>,
+    ) -> anyhow::Result<()> {
+        self.stop_clip_recording();
+
+        let config = self.config.borrow();
+        let path = make_clip_path(&config).context("error making clip path")?;
+        debug!("recording clip to {path:?}");
+
+        let recorder = ClipRecorder::start(
+            output,
+            rect,
+            path,
+            &config.clip_recorder,
+            get_monotonic_time(),
+        )?;
+        drop(config);
+
+        let token = self
+            .event_loop
+            .insert_source(Timer::immediate(), |_, _, state| state.record_clip_frame())
+            .unwrap();
+        self.clip_recorder = Some((recorder, token));
+
+        Ok(())
+    }
+
+    /// Stops the current clip recording, returning whether there was one.
+    pub fn stop_clip_recording(&mut self) -> bool {
+        let Some((_, token)) = self.clip_recorder.take() else {
+            return false;
+        };
+
+        self.event_loop.remove(token);
+        true
+    }
+
+    /// Captures the next clip frame, returning when to capture the one after it.
+    ///
+    /// Returns `None` once the recording is over.
+    fn record_clip_frame(&mut self, renderer: &mut GlesRenderer) -> Option<Duration> {
+        let _span = tracy_client::span!("Niri::record_clip_frame");
+
+        let (recorder, _) = self.clip_recorder.as_ref()?;
+        let output = recorder.output().clone();
+        let rect = recorder.rect();
+        let interval = recorder.frame_interval();
+
+        if recorder.is_finished(get_monotonic_time()) || !self.output_state.contains_key(&output) {
+            debug!("finished recording clip");
+            return None;
+        }
+
+        self.layout.update_render_elements(&output);
+
+        let scale = Scale::from(output.current_scale().fractional_scale());
+        let elements =
+            self.render::<GlesRenderer>(renderer, &output, true, RenderTarget::ScreenCapture);
+        let elements = elements.iter().rev().map(|elem| {
+            RelocateRenderElement::from_element(elem, rect.loc.upscale(-1), Relocate::Relative)
+        });
+        let res = render_to_vec(
+            renderer,
+            rect.size,
+            scale,
+            Transform::Normal,
+            Fourcc::Abgr8888,
+            elements,
+        );
+
+        match res {
+            Ok(pixels) => {
+                let (recorder, _) = self.clip_recorder.as_ref()?;
+                if !recorder.push_frame(pixels) {
+                    warn!("clip encoder stopped unexpectedly");
+                    return None;
+                }
+            }
+            Err(err) => warn!("error rendering clip frame: {err:?}"),
+        }
+
+        Some(interval)
+    }
+
     pub fn screenshot(
         &mut self,
         renderer: &mut GlesRenderer,
@@ -3982,6 +5871,70 @@ impl Niri {
             elements,
         )?;
 
+        self.write_screenshot_file(size, pixels, on_done);
+
+        Ok(())
+    }
+
+    /// Finishes a screenshot portal request with the screenshot UI selection.
+    #[cfg(feature = "dbus")]
+    pub fn finish_portal_screenshot(
+        &self,
+        reply: async_channel::Sender<Option<PathBuf>>,
+        size: Size<i32, Physical>,
+        pixels: Vec<u8>,
+    ) {
+        self.write_screenshot_file(size, pixels, move |path| {
+            if let Err(err) = reply.send_blocking(Some(path)) {
+                warn!("error sending path to the screenshot portal: {err:?}");
+            }
+        });
+    }
+
+    /// Finishes a color pick portal request with the color under the pointer.
+    #[cfg(feature = "dbus")]
+    pub fn finish_portal_pick_color(
+        &self,
+        renderer: &mut GlesRenderer,
+        reply: async_channel::Sender<Option<[f64; 3]>>,
+    ) {
+        let pos = self.seat.get_pointer().unwrap().current_location();
+        let Some((output, pos_within_output)) = self.output_under(pos) else {
+            return;
+        };
+
+        let mut point = pos_within_output
+            .to_physical(output.current_scale().fractional_scale())
+            .to_i32_round();
+
+        let size = output.current_mode().unwrap().size;
+        let transform = output.current_transform();
+        let size = transform.transform_size(size);
+        point.x = point.x.clamp(0, size.w - 1);
+        point.y = point.y.clamp(0, size.h - 1);
+
+        let [r, g, b, _] = match self.screenshot_ui.capture_pixel(renderer, output, point) {
+            Ok(pixel) => pixel,
+            Err(err) => {
+                warn!("error picking color: {err:?}");
+                return;
+            }
+        };
+
+        let color = [r, g, b].map(|x| f64::from(x) / 255.);
+        if let Err(err) = reply.send_blocking(Some(color)) {
+            warn!("error sending color to the screenshot portal: {err:?}");
+        }
+    }
+
+    /// Saves the screenshot to the screenshot path, or to a temporary file if it's unset.
+    #[cfg(feature = "dbus")]
+    fn write_screenshot_file(
+        &self,
+        size: Size<i32, Physical>,
+        pixels: Vec<u8>,
+        on_done: impl FnOnce(PathBuf) + Send + 'static,
+    ) {
         let path = make_screenshot_path(&self.config.borrow())
             .ok()
             .flatten()
@@ -4009,14 +5962,19 @@ impl Niri {
 
             on_done(path);
         });
-
-        Ok(())
     }
 
     pub fn is_locked(&self) -> bool {
         !matches!(self.lock_state, LockState::Unlocked)
     }
 
+    /// Returns whether a client took all input through wlr-input-inhibitor.
+    pub fn is_input_inhibited(&self) -> bool {
+        self.input_inhibit_manager_state
+            .inhibiting_client()
+            .is_some()
+    }
+
     pub fn lock(&mut self, confirmation: SessionLocker) {
         // Check if another client is in the process of locking.
         if matches!(self.lock_state, LockState::Locking(_)) {
@@ -4037,6 +5995,8 @@ impl Niri {
         info!("locking session");
 
         self.screenshot_ui.close();
+        #[cfg(feature = "dbus")]
+        self.portal_screenshot = None;
         self.cursor_manager
             .set_cursor_image(CursorImageStatus::default_named());
 
@@ -4099,11 +6059,262 @@ impl Niri {
         });
     }
 
+    /// Updates the state served by niri's own D-Bus interface and signals the changes.
+    #[cfg(feature = "dbus")]
+    pub fn refresh_compositor_dbus(&mut self) {
+        use crate::dbus::niri_compositor::{
+            Compositor, WindowProperties, WorkspaceProperties, OBJECT_PATH,
+        };
+
+        if !self.dbus_compositor_changed {
+            return;
+        }
+        self.dbus_compositor_changed = false;
+
+        let Some(conn) = self
+            .dbus
+            .as_ref()
+            .and_then(|dbus| dbus.conn_compositor.as_ref())
+        else {
+            return;
+        };
+
+        let _span = tracy_client::span!("Niri::refresh_compositor_dbus");
+
+        let mut windows = HashMap::new();
+        self.layout.with_windows(|mapped, output| {
+            let niri_ipc::Window { title, app_id } = ipc_window(&mapped.window);
+            let props = WindowProperties {
+                title: title.unwrap_or_default(),
+                app_id: app_id.unwrap_or_default(),
+                output: output.map(Output::name),
+                group: mapped.group().map(String::from),
+            };
+            windows.insert(u64::from(mapped.id().get()), props);
+        });
+
+        let workspaces = self
+            .ipc_workspaces()
+            .into_iter()
+            .map(|ws| WorkspaceProperties {
+                idx: ws.idx,
+                name: ws.name,
+                output: ws.output,
+                is_active: ws.is_active,
+                window_count: ws.window_count,
+                active_window_id: ws.active_window_id.map(u64::from),
+            })
+            .collect();
+
+        let focused_window = self.ipc_focused_window.lock().unwrap().clone();
+        let focused_window = focused_window
+            .and_then(|window| {
+                let wl_surface = window.toplevel().expect("no X11 support").wl_surface();
+                self.layout.find_window_and_output(wl_surface)
+            })
+            .map_or(0, |(mapped, _)| u64::from(mapped.id().get()));
+
+        let focused_output = self
+            .layout
+            .active_output()
+            .map(Output::name)
+            .unwrap_or_default();
+
+        let new_state = CompositorState {
+            windows,
+            workspaces,
+            focused_window,
+            focused_output,
+        };
+
+        let mut state = self.compositor_state.lock().unwrap();
+        if *state == new_state {
+            return;
+        }
+
+        let windows_changed = state.windows != new_state.windows;
+        let workspaces_changed = state.workspaces != new_state.workspaces;
+        let focused_window_changed = state.focused_window != new_state.focused_window;
+        let focused_output_changed = state.focused_output != new_state.focused_output;
+        let announcements = state.announcements(&new_state);
+        *state = new_state;
+        drop(state);
+
+        let iface = match conn.object_server().interface::<_, Compositor>(OBJECT_PATH) {
+            Ok(iface) => iface,
+            Err(err) => {
+                warn!("error getting Compositor interface: {err:?}");
+                return;
+            }
+        };
+
+        let ctxt = iface.signal_context();
+        let res = async_io::block_on(async {
+            if windows_changed {
+                Compositor::windows_changed(ctxt).await?;
+            }
+            if workspaces_changed {
+                Compositor::workspaces_changed(ctxt).await?;
+            }
+
+            let iface = iface.get().await;
+            if focused_window_changed {
+                iface.focused_window_changed(ctxt).await?;
+            }
+            if focused_output_changed {
+                iface.focused_output_changed(ctxt).await?;
+            }
+
+            for text in &announcements {
+                Compositor::announcement(ctxt, text).await?;
+            }
+
+            zbus::Result::Ok(())
+        });
+
+        if let Err(err) = res {
+            warn!("error emitting Compositor signals: {err:?}");
+        }
+    }
+
+    #[cfg(feature = "dbus")]
+    pub fn show_notification(&mut self, notification: Notification) {
+        let id = notification.id;
+        let timer = self.notifier.timeout(&notification).map(|timeout| {
+            self.event_loop
+                .insert_source(Timer::from_duration(timeout), move |_, _, state| {
+                    state.niri.close_notification(id, CloseReason::Expired);
+                    TimeoutAction::Drop
+                })
+                .unwrap()
+        });
+
+        if let Some(old_timer) = self.notifier.add(notification, timer) {
+            self.event_loop.remove(old_timer);
+        }
+
+        self.queue_redraw_all();
+    }
+
+    #[cfg(feature = "dbus")]
+    pub fn close_notification(&mut self, id: u32, reason: CloseReason) {
+        let Some(timer) = self.notifier.close(id, reason) else {
+            return;
+        };
+
+        // An expired timer has already dropped itself.
+        if let Some(timer) = timer.filter(|_| reason != CloseReason::Expired) {
+            self.event_loop.remove(timer);
+        }
+
+        self.queue_redraw_all();
+    }
+
+    /// Turns reduced motion on or off, making all animations finish instantly.
+    pub fn set_reduced_motion(&mut self, reduced_motion: bool) {
+        animation::REDUCED_MOTION.store(reduced_motion, Ordering::Relaxed);
+
+        #[cfg(feature = "dbus")]
+        self.on_reduced_motion_changed(reduced_motion);
+
+        // Finish the ongoing animations.
+        self.queue_redraw_all();
+    }
+
+    /// Follows the `enable-animations` setting read from the xdg-desktop-portal.
+    #[cfg(feature = "dbus")]
+    pub fn on_enable_animations_changed(&mut self, enable_animations: bool) {
+        // Turning off all animations in the config always wins.
+        let reduced_motion = self.config.borrow().animations.off || !enable_animations;
+        if reduced_motion != animation::REDUCED_MOTION.load(Ordering::Relaxed) {
+            self.set_reduced_motion(reduced_motion);
+        }
+    }
+
+    #[cfg(feature = "dbus")]
+    fn on_reduced_motion_changed(&self, reduced_motion: bool) {
+        use crate::dbus::freedesktop_portal::OBJECT_PATH;
+        use crate::dbus::freedesktop_settings::Settings;
+
+        let Some(dbus) = &self.dbus else { return };
+        let Some(conn) = &dbus.conn_portal else {
+            return;
+        };
+
+        let iface = match conn.object_server().interface::<_, Settings>(OBJECT_PATH) {
+            Ok(iface) => iface,
+            Err(err) => {
+                warn!("error getting Settings interface: {err:?}");
+                return;
+            }
+        };
+
+        async_io::block_on(async {
+            let res = iface
+                .get_mut()
+                .await
+                .set_reduced_motion(iface.signal_context(), reduced_motion)
+                .await;
+            if let Err(err) = res {
+                warn!("error emitting SettingChanged: {err:?}");
+            }
+        });
+    }
+
+    #[cfg(feature = "dbus")]
+    pub fn on_appearance_changed(&self) {
+        use crate::dbus::freedesktop_portal::OBJECT_PATH;
+        use crate::dbus::freedesktop_settings::Settings;
+
+        let _span = tracy_client::span!("Niri::on_appearance_changed");
+
+        let Some(dbus) = &self.dbus else { return };
+        let Some(conn) = &dbus.conn_portal else {
+            return;
+        };
+
+        let iface = match conn.object_server().interface::<_, Settings>(OBJECT_PATH) {
+            Ok(iface) => iface,
+            Err(err) => {
+                warn!("error getting Settings interface: {err:?}");
+                return;
+            }
+        };
+
+        let appearance = self.config.borrow().appearance;
+        async_io::block_on(async {
+            let res = iface
+                .get_mut()
+                .await
+                .set_appearance(iface.signal_context(), appearance)
+                .await;
+            if let Err(err) = res {
+                warn!("error emitting SettingChanged: {err:?}");
+            }
+        });
+    }
+
     #[cfg(feature = "dbus")]
     pub fn on_ipc_outputs_changed(&self) {
         let _span = tracy_client::span!("Niri::on_ipc_outputs_changed");
 
         let Some(dbus) = &self.dbus else { return };
+
+        if let Some(conn) = &dbus.conn_compositor {
+            use crate::dbus::niri_compositor::{Compositor, OBJECT_PATH};
+
+            match conn.object_server().interface::<_, Compositor>(OBJECT_PATH) {
+                Ok(iface) => {
+                    let res =
+                        async_io::block_on(Compositor::outputs_changed(iface.signal_context()));
+                    if let Err(err) = res {
+                        warn!("error emitting OutputsChanged: {err:?}");
+                    }
+                }
+                Err(err) => warn!("error getting Compositor interface: {err:?}"),
+            }
+        }
+
         let Some(conn_display_config) = dbus.conn_display_config.clone() else {
             return;
         };
@@ -4159,6 +6370,76 @@ impl Niri {
         }
     }
 
+    pub fn is_modal_dialog_open(&self) -> bool {
+        self.exit_confirm_dialog
+            .as_ref()
+            .is_some_and(|dialog| dialog.is_open())
+            || self
+                .close_confirm_dialog
+                .as_ref()
+                .is_some_and(|dialog| dialog.is_open())
+    }
+
+    /// Captures blurred snapshots of the outputs to show behind a modal dialog.
+    ///
+    /// Does nothing unless blur is turned on in the config, since blurring every output is costly.
+    pub fn capture_backdrops(&mut self, renderer: &mut GlesRenderer) {
+        let _span = tracy_client::span!("Niri::capture_backdrops");
+
+        let blur = self.config.borrow().blur;
+        if !blur.on {
+            return;
+        }
+
+        self.layout.update_render_elements_all();
+
+        let outputs: Vec<_> = self.output_state.keys().cloned().collect();
+        for output in outputs {
+            let size = output.current_mode().unwrap().size;
+            let transform = output.current_transform();
+            let size = transform.transform_size(size);
+            let scale = output.current_scale().fractional_scale();
+
+            let elements =
+                self.render::<GlesRenderer>(renderer, &output, false, RenderTarget::Output);
+            let res = render_to_texture(
+                renderer,
+                size,
+                Scale::from(scale),
+                Transform::Normal,
+                Fourcc::Abgr8888,
+                elements.iter().rev(),
+            )
+            .and_then(|(texture, _)| {
+                blur::blur(renderer, texture, blur.passes, blur.offset.0 as f32)
+            });
+
+            let backdrop = match res {
+                Ok(texture) => Some(TextureBuffer::from_texture(
+                    renderer,
+                    texture,
+                    scale,
+                    Transform::Normal,
+                    Vec::new(), // We want windows below to get frame callbacks.
+                )),
+                Err(err) => {
+                    warn!("error capturing backdrop of {}: {err:?}", output.name());
+                    None
+                }
+            };
+
+            if let Some(state) = self.output_state.get_mut(&output) {
+                state.backdrop = backdrop;
+            }
+        }
+    }
+
+    pub fn clear_backdrops(&mut self) {
+        for state in self.output_state.values_mut() {
+            state.backdrop = None;
+        }
+    }
+
     pub fn do_screen_transition(&mut self, renderer: &mut GlesRenderer, delay_ms: Option<u16>) {
         let _span = tracy_client::span!("Niri::do_screen_transition");
 
@@ -4268,15 +6549,32 @@ impl Niri {
     }
 
     pub fn ipc_workspaces(&self) -> Vec<Workspace> {
-        self.layout.ipc_workspaces()
+        self.layout.ipc_workspaces(
+            |mapped| (mapped.id().get(), ipc_window(&mapped.window)),
+            Mapped::is_urgent,
+        )
     }
 }
 
 pub struct ClientState {
     pub compositor_state: CompositorClientState,
     pub can_view_decoration_globals: bool,
+    /// Whether this client can see the KDE server decoration global.
+    ///
+    /// Unlike xdg-decoration, that protocol only advertises a single default mode, so window rules
+    /// cannot pick the mode per window there.
+    pub can_view_kde_decoration_global: bool,
     /// Whether this client is denied from the restricted protocols such as security-context.
     pub restricted: bool,
+    /// Restricted protocols that this client is allowed to use anyway.
+    pub allowed_protocols: Vec<PrivilegedProtocol>,
+}
+
+impl ClientState {
+    /// Returns whether this client can see the global of this privileged protocol.
+    pub fn can_use(&self, protocol: PrivilegedProtocol) -> bool {
+        !self.restricted || self.allowed_protocols.contains(&protocol)
+    }
 }
 
 impl ClientData for ClientState {
@@ -4284,6 +6582,30 @@ impl ClientData for ClientState {
     fn disconnected(&self, _client_id: ClientId, _reason: DisconnectReason) {}
 }
 
+/// Picks the output where more of the surface is visible as its primary scanout output.
+///
+/// Ties, such as a surface fully visible on two mirrored outputs, fall back to preferring the
+/// higher refresh rate.
+fn largest_visible_area_compare<'a>(
+    current_output: &'a Output,
+    current_state: &RenderElementState,
+    next_output: &'a Output,
+    next_state: &RenderElementState,
+) -> &'a Output {
+    if next_state.visible_area > current_state.visible_area {
+        next_output
+    } else if next_state.visible_area < current_state.visible_area {
+        current_output
+    } else {
+        default_primary_scanout_output_compare(
+            current_output,
+            current_state,
+            next_output,
+            next_state,
+        )
+    }
+}
+
 niri_render_elements! {
     OutputRenderElements<R> => {
         Monitor = MonitorRenderElement<R>,
@@ -4294,5 +6616,29 @@ niri_render_elements! {
         Texture = PrimaryGpuTextureRenderElement,
         // Used for the CPU-rendered panels.
         RelocatedMemoryBuffer = RelocateRenderElement<MemoryRenderBufferRenderElement<R>>,
+        // The desktop zoomed in by the magnifier.
+        MagnifiedMonitor = RescaleRenderElement<MonitorRenderElement<R>>,
+        MagnifiedWayland = RescaleRenderElement<WaylandSurfaceRenderElement<R>>,
+        MagnifiedSolidColor = RescaleRenderElement<SolidColorRenderElement>,
+    }
+}
+
+/// Wraps a desktop element to draw it zoomed in around `origin`.
+fn magnify<R: NiriRenderer>(
+    elem: OutputRenderElements<R>,
+    origin: Point<i32, Physical>,
+    zoom: f64,
+) -> OutputRenderElements<R> {
+    match elem {
+        OutputRenderElements::Monitor(elem) => OutputRenderElements::MagnifiedMonitor(
+            RescaleRenderElement::from_element(elem, origin, zoom),
+        ),
+        OutputRenderElements::Wayland(elem) => OutputRenderElements::MagnifiedWayland(
+            RescaleRenderElement::from_element(elem, origin, zoom),
+        ),
+        OutputRenderElements::SolidColor(elem) => OutputRenderElements::MagnifiedSolidColor(
+            RescaleRenderElement::from_element(elem, origin, zoom),
+        ),
+        elem => elem,
     }
 }
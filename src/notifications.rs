@@ -0,0 +1,265 @@
+use std::time::Duration;
+
+use niri_config::NotificationPosition;
+use pangocairo::pango::glib::markup_escape_text;
+use pangocairo::pango::Alignment;
+use smithay::backend::renderer::element::Kind;
+use smithay::output::Output;
+use smithay::reexports::calloop::RegistrationToken;
+use smithay::utils::Point;
+
+use crate::dbus::freedesktop_notifications::{
+    self, CloseReason, Notification, Notifications, Urgency,
+};
+use crate::render_helpers::memory::MemoryBuffer;
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::text::{render_text_box, TextBoxStyle, TextCache};
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::utils::output_size;
+
+const STYLE: TextBoxStyle = TextBoxStyle {
+    font: "sans 14px",
+    alignment: Alignment::Left,
+    padding: 12,
+    border: 2,
+    background: [0.1, 0.1, 0.1, 0.9],
+    text_color: [1., 1., 1.],
+    border_color: [0.5, 0.5, 0.5],
+};
+const CRITICAL_BORDER_COLOR: [f64; 3] = [1., 0.3, 0.3];
+
+/// Distance between the notifications and the output edges.
+const MARGIN: f64 = 16.;
+/// Distance between stacked notifications.
+const GAP: f64 = 8.;
+/// Notifications shown at once; older ones wait until the newer ones go away.
+const MAX_VISIBLE: usize = 5;
+/// Characters of the body shown before it gets cut off.
+const MAX_BODY_CHARS: usize = 300;
+
+/// Notifications received through the org.freedesktop.Notifications interface.
+///
+/// They are stacked in a corner of the active output, newest closest to the corner.
+pub struct Notifier {
+    /// Shown notifications, oldest first.
+    entries: Vec<Entry>,
+    do_not_disturb: bool,
+    config: niri_config::Notifications,
+    /// Connection that serves the interface, used to emit the signals.
+    conn: Option<zbus::blocking::Connection>,
+}
+
+struct Entry {
+    notification: Notification,
+    buffers: TextCache,
+    /// Timer closing the notification once it expires.
+    timer: Option<RegistrationToken>,
+}
+
+impl Notifier {
+    pub fn new(config: niri_config::Notifications) -> Self {
+        Self {
+            entries: Vec::new(),
+            do_not_disturb: false,
+            config,
+            conn: None,
+        }
+    }
+
+    pub fn update_config(&mut self, config: niri_config::Notifications) {
+        self.config = config;
+    }
+
+    pub fn set_connection(&mut self, conn: zbus::blocking::Connection) {
+        self.conn = Some(conn);
+    }
+
+    /// Returns how long the notification is shown, or `None` if it doesn't expire by itself.
+    pub fn timeout(&self, notification: &Notification) -> Option<Duration> {
+        if notification.urgency == Urgency::Critical {
+            return None;
+        }
+
+        match notification.expire_timeout {
+            0 => None,
+            ms if ms < 0 => {
+                let ms = self.config.default_timeout_ms;
+                (ms > 0).then(|| Duration::from_millis(u64::from(ms)))
+            }
+            ms => Some(Duration::from_millis(ms as u64)),
+        }
+    }
+
+    /// Adds a notification, or replaces the one with the same id.
+    ///
+    /// Returns the expiry timer of the replaced notification.
+    pub fn add(
+        &mut self,
+        notification: Notification,
+        timer: Option<RegistrationToken>,
+    ) -> Option<RegistrationToken> {
+        let entry = Entry {
+            notification,
+            buffers: TextCache::new(),
+            timer,
+        };
+
+        let id = entry.notification.id;
+        if let Some(old) = self.entries.iter_mut().find(|e| e.notification.id == id) {
+            let old = std::mem::replace(old, entry);
+            return old.timer;
+        }
+
+        self.entries.push(entry);
+        None
+    }
+
+    /// Removes a notification and tells its app why.
+    ///
+    /// Returns `None` if there was no such notification, otherwise its expiry timer.
+    pub fn close(&mut self, id: u32, reason: CloseReason) -> Option<Option<RegistrationToken>> {
+        let idx = self.entries.iter().position(|e| e.notification.id == id)?;
+        let entry = self.entries.remove(idx);
+        self.emit_closed(id, reason);
+        Some(entry.timer)
+    }
+
+    /// Returns the id of the newest notification that is currently shown.
+    pub fn latest(&self) -> Option<u32> {
+        self.visible().next().map(|e| e.notification.id)
+    }
+
+    pub fn ids(&self) -> Vec<u32> {
+        self.entries.iter().map(|e| e.notification.id).collect()
+    }
+
+    pub fn do_not_disturb(&self) -> bool {
+        self.do_not_disturb
+    }
+
+    pub fn set_do_not_disturb(&mut self, do_not_disturb: bool) {
+        self.do_not_disturb = do_not_disturb;
+    }
+
+    /// Shown notifications, newest first.
+    fn visible(&self) -> impl Iterator<Item = &Entry> {
+        let do_not_disturb = self.do_not_disturb;
+        self.entries
+            .iter()
+            .rev()
+            .filter(move |e| !do_not_disturb || e.notification.urgency == Urgency::Critical)
+            .take(MAX_VISIBLE)
+    }
+
+    fn emit_closed(&self, id: u32, reason: CloseReason) {
+        let Some(conn) = &self.conn else {
+            return;
+        };
+
+        let server = conn.object_server();
+        let iface =
+            match server.interface::<_, Notifications>(freedesktop_notifications::OBJECT_PATH) {
+                Ok(iface) => iface,
+                Err(err) => {
+                    warn!("error getting Notifications interface: {err:?}");
+                    return;
+                }
+            };
+
+        let res = async_io::block_on(Notifications::notification_closed(
+            iface.signal_context(),
+            id,
+            reason as u32,
+        ));
+        if let Err(err) = res {
+            warn!("error emitting NotificationClosed: {err:?}");
+        }
+    }
+
+    pub fn render<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Vec<PrimaryGpuTextureRenderElement> {
+        let scale = output.current_scale().fractional_scale();
+        let output_size = output_size(output).to_f64();
+        let position = self.config.position;
+
+        let mut elements = vec![];
+        let mut y = MARGIN;
+        for entry in self.visible() {
+            let Some(buffer) = entry
+                .buffers
+                .get(scale, |scale| render(&entry.notification, scale))
+            else {
+                continue;
+            };
+
+            let size = buffer.logical_size();
+            let Ok(buffer) =
+                TextureBuffer::from_memory_buffer(renderer.as_gles_renderer(), &buffer)
+            else {
+                continue;
+            };
+
+            let x = match position {
+                NotificationPosition::TopLeft | NotificationPosition::BottomLeft => MARGIN,
+                NotificationPosition::TopRight | NotificationPosition::BottomRight => {
+                    output_size.w - size.w - MARGIN
+                }
+            };
+            let location_y = match position {
+                NotificationPosition::TopLeft | NotificationPosition::TopRight => y,
+                NotificationPosition::BottomLeft | NotificationPosition::BottomRight => {
+                    output_size.h - size.h - y
+                }
+            };
+            y += size.h + GAP;
+
+            let location = Point::from((f64::max(0., x), location_y));
+            let location = location.to_physical_precise_round(scale).to_logical(scale);
+
+            let elem = TextureRenderElement::from_texture_buffer(
+                buffer,
+                location,
+                1.,
+                None,
+                None,
+                Kind::Unspecified,
+            );
+            elements.push(PrimaryGpuTextureRenderElement(elem));
+        }
+
+        elements
+    }
+}
+
+fn render(notification: &Notification, scale: f64) -> anyhow::Result<MemoryBuffer> {
+    let _span = tracy_client::span!("notifications::render");
+
+    let mut text = format!("<b>{}</b>", markup_escape_text(&notification.summary));
+    if !notification.app_name.is_empty() {
+        text = format!(
+            "<span alpha=\"60%\">{}</span>\n{text}",
+            markup_escape_text(&notification.app_name)
+        );
+    }
+
+    let body = notification.body.trim();
+    if !body.is_empty() {
+        let mut body: String = body.chars().take(MAX_BODY_CHARS).collect();
+        if body.len() < notification.body.trim().len() {
+            body.push('…');
+        }
+        text.push('\n');
+        text.push_str(&markup_escape_text(&body));
+    }
+
+    let mut style = STYLE;
+    if notification.urgency == Urgency::Critical {
+        style.border_color = CRITICAL_BORDER_COLOR;
+    }
+
+    render_text_box(&text, &style, scale)
+}
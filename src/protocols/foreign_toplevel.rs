@@ -37,12 +37,14 @@ pub trait ForeignToplevelHandler {
     fn close(&mut self, wl_surface: WlSurface);
     fn set_fullscreen(&mut self, wl_surface: WlSurface, wl_output: Option<WlOutput>);
     fn unset_fullscreen(&mut self, wl_surface: WlSurface);
+    fn set_minimized(&mut self, wl_surface: WlSurface);
+    fn unset_minimized(&mut self, wl_surface: WlSurface);
 }
 
 struct ToplevelData {
     title: Option<String>,
     app_id: Option<String>,
-    states: ArrayVec<u32, 3>,
+    states: ArrayVec<u32, 4>,
     output: Option<Output>,
     instances: HashMap<ZwlrForeignToplevelHandleV1, Vec<WlOutput>>,
     // FIXME: parent.
@@ -79,7 +81,9 @@ pub fn refresh(state: &mut State) {
 
     // Handle closed windows.
     protocol_state.toplevels.retain(|surface, data| {
-        if state.niri.layout.find_window_and_output(surface).is_some() {
+        if state.niri.layout.find_window_and_output(surface).is_some()
+            || state.niri.find_minimized_window(surface).is_some()
+        {
             return true;
         }
 
@@ -109,11 +113,27 @@ pub fn refresh(state: &mut State) {
             if state.niri.keyboard_focus.surface() == Some(wl_surface) {
                 focused = Some((mapped.window.clone(), output.cloned()));
             } else {
-                refresh_toplevel(protocol_state, wl_surface, &role, output, false);
+                refresh_toplevel(protocol_state, wl_surface, &role, output, false, false);
             }
         });
     });
 
+    // Minimized windows are not on any output.
+    for minimized in &state.niri.minimized_windows {
+        let wl_surface = minimized.mapped.toplevel().wl_surface();
+
+        with_states(wl_surface, |states| {
+            let role = states
+                .data_map
+                .get::<XdgToplevelSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap();
+
+            refresh_toplevel(protocol_state, wl_surface, &role, None, false, true);
+        });
+    }
+
     // Finally, refresh the focused window.
     if let Some((window, output)) = focused {
         let wl_surface = window.toplevel().expect("no x11 support").wl_surface();
@@ -126,7 +146,14 @@ pub fn refresh(state: &mut State) {
                 .lock()
                 .unwrap();
 
-            refresh_toplevel(protocol_state, wl_surface, &role, output.as_ref(), true);
+            refresh_toplevel(
+                protocol_state,
+                wl_surface,
+                &role,
+                output.as_ref(),
+                true,
+                false,
+            );
         });
     }
 }
@@ -162,8 +189,9 @@ fn refresh_toplevel(
     role: &XdgToplevelSurfaceRoleAttributes,
     output: Option<&Output>,
     has_focus: bool,
+    is_minimized: bool,
 ) {
-    let states = to_state_vec(&role.current.states, has_focus);
+    let states = to_state_vec(&role.current.states, has_focus, is_minimized);
 
     match protocol_state.toplevels.entry(wl_surface.clone()) {
         Entry::Occupied(entry) => {
@@ -393,8 +421,12 @@ where
         match request {
             zwlr_foreign_toplevel_handle_v1::Request::SetMaximized => (),
             zwlr_foreign_toplevel_handle_v1::Request::UnsetMaximized => (),
-            zwlr_foreign_toplevel_handle_v1::Request::SetMinimized => (),
-            zwlr_foreign_toplevel_handle_v1::Request::UnsetMinimized => (),
+            zwlr_foreign_toplevel_handle_v1::Request::SetMinimized => {
+                state.set_minimized(surface);
+            }
+            zwlr_foreign_toplevel_handle_v1::Request::UnsetMinimized => {
+                state.unset_minimized(surface);
+            }
             zwlr_foreign_toplevel_handle_v1::Request::Activate { .. } => {
                 state.activate(surface);
             }
@@ -426,11 +458,18 @@ where
     }
 }
 
-fn to_state_vec(states: &ToplevelStateSet, has_focus: bool) -> ArrayVec<u32, 3> {
+fn to_state_vec(
+    states: &ToplevelStateSet,
+    has_focus: bool,
+    is_minimized: bool,
+) -> ArrayVec<u32, 4> {
     let mut rv = ArrayVec::new();
     if states.contains(xdg_toplevel::State::Maximized) {
         rv.push(zwlr_foreign_toplevel_handle_v1::State::Maximized as u32);
     }
+    if is_minimized {
+        rv.push(zwlr_foreign_toplevel_handle_v1::State::Minimized as u32);
+    }
     if states.contains(xdg_toplevel::State::Fullscreen) {
         rv.push(zwlr_foreign_toplevel_handle_v1::State::Fullscreen as u32);
     }
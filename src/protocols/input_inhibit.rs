@@ -0,0 +1,166 @@
+use smithay::reexports::wayland_protocols_wlr;
+use smithay::reexports::wayland_server::backend::ClientId;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+use wayland_protocols_wlr::input_inhibitor::v1::server::{
+    zwlr_input_inhibit_manager_v1, zwlr_input_inhibitor_v1,
+};
+use zwlr_input_inhibit_manager_v1::ZwlrInputInhibitManagerV1;
+use zwlr_input_inhibitor_v1::ZwlrInputInhibitorV1;
+
+const VERSION: u32 = 1;
+
+pub struct InputInhibitManagerState {
+    // The inhibitor that currently takes all input, if any.
+    active: Option<ZwlrInputInhibitorV1>,
+}
+
+pub struct InputInhibitManagerGlobalData {
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+pub trait InputInhibitHandler {
+    fn input_inhibit_manager_state(&mut self) -> &mut InputInhibitManagerState;
+}
+
+impl InputInhibitManagerState {
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ZwlrInputInhibitManagerV1, InputInhibitManagerGlobalData>,
+        D: Dispatch<ZwlrInputInhibitManagerV1, ()>,
+        D: Dispatch<ZwlrInputInhibitorV1, ()>,
+        D: InputInhibitHandler,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let global_data = InputInhibitManagerGlobalData {
+            filter: Box::new(filter),
+        };
+        display.create_global::<D, ZwlrInputInhibitManagerV1, _>(VERSION, global_data);
+
+        Self { active: None }
+    }
+
+    /// Returns the client that all input goes to, if input is inhibited.
+    pub fn inhibiting_client(&self) -> Option<ClientId> {
+        self.active
+            .as_ref()
+            .and_then(|inhibitor| inhibitor.client())
+            .map(|c| c.id())
+    }
+
+    /// Stops the input inhibition without waiting for the client.
+    ///
+    /// The protocol has no event for this, so the client keeps its inhibitor object, which just
+    /// doesn't do anything anymore. Returns `false` if input wasn't inhibited.
+    pub fn break_inhibit(&mut self) -> bool {
+        self.active.take().is_some()
+    }
+}
+
+impl<D> GlobalDispatch<ZwlrInputInhibitManagerV1, InputInhibitManagerGlobalData, D>
+    for InputInhibitManagerState
+where
+    D: GlobalDispatch<ZwlrInputInhibitManagerV1, InputInhibitManagerGlobalData>,
+    D: Dispatch<ZwlrInputInhibitManagerV1, ()>,
+    D: Dispatch<ZwlrInputInhibitorV1, ()>,
+    D: InputInhibitHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        manager: New<ZwlrInputInhibitManagerV1>,
+        _manager_state: &InputInhibitManagerGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(manager, ());
+    }
+
+    fn can_view(client: Client, global_data: &InputInhibitManagerGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ZwlrInputInhibitManagerV1, (), D> for InputInhibitManagerState
+where
+    D: Dispatch<ZwlrInputInhibitManagerV1, ()>,
+    D: Dispatch<ZwlrInputInhibitorV1, ()>,
+    D: InputInhibitHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &ZwlrInputInhibitManagerV1,
+        request: <ZwlrInputInhibitManagerV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_input_inhibit_manager_v1::Request::GetInhibitor { id } => {
+                let manager_state = state.input_inhibit_manager_state();
+                if manager_state.active.is_some() {
+                    resource.post_error(
+                        zwlr_input_inhibit_manager_v1::Error::AlreadyInhibited,
+                        "input is already inhibited by another client",
+                    );
+                    return;
+                }
+
+                let inhibitor = data_init.init(id, ());
+                manager_state.active = Some(inhibitor);
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZwlrInputInhibitorV1, (), D> for InputInhibitManagerState
+where
+    D: Dispatch<ZwlrInputInhibitorV1, ()>,
+    D: InputInhibitHandler,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &ZwlrInputInhibitorV1,
+        request: <ZwlrInputInhibitorV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_input_inhibitor_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, resource: &ZwlrInputInhibitorV1, _data: &()) {
+        let manager_state = state.input_inhibit_manager_state();
+        if manager_state.active.as_ref() == Some(resource) {
+            manager_state.active = None;
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! delegate_input_inhibit {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::input_inhibitor::v1::server::zwlr_input_inhibit_manager_v1::ZwlrInputInhibitManagerV1: $crate::protocols::input_inhibit::InputInhibitManagerGlobalData
+        ] => $crate::protocols::input_inhibit::InputInhibitManagerState);
+
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::input_inhibitor::v1::server::zwlr_input_inhibit_manager_v1::ZwlrInputInhibitManagerV1: ()
+        ] => $crate::protocols::input_inhibit::InputInhibitManagerState);
+
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::input_inhibitor::v1::server::zwlr_input_inhibitor_v1::ZwlrInputInhibitorV1: ()
+        ] => $crate::protocols::input_inhibit::InputInhibitManagerState);
+    };
+}
@@ -1,3 +1,7 @@
 pub mod foreign_toplevel;
 pub mod gamma_control;
+pub mod input_inhibit;
+pub mod output_management;
 pub mod screencopy;
+pub mod virtual_pointer;
+pub mod xdg_foreign;
@@ -0,0 +1,790 @@
+use std::collections::HashMap;
+use std::mem;
+use std::sync::Mutex;
+
+use niri_config::FloatOrInt;
+use niri_ipc::ConfiguredMode;
+use smithay::reexports::wayland_protocols_wlr;
+use smithay::reexports::wayland_server::backend::ClientId;
+use smithay::reexports::wayland_server::protocol::wl_output;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, WEnum,
+};
+use smithay::utils::{Logical, Physical, Rectangle, Size, Transform};
+use wayland_protocols_wlr::output_management::v1::server::{
+    zwlr_output_configuration_head_v1, zwlr_output_configuration_v1, zwlr_output_head_v1,
+    zwlr_output_manager_v1, zwlr_output_mode_v1,
+};
+use zwlr_output_configuration_head_v1::ZwlrOutputConfigurationHeadV1;
+use zwlr_output_configuration_v1::ZwlrOutputConfigurationV1;
+use zwlr_output_head_v1::{AdaptiveSyncState, ZwlrOutputHeadV1};
+use zwlr_output_manager_v1::ZwlrOutputManagerV1;
+use zwlr_output_mode_v1::ZwlrOutputModeV1;
+
+use crate::backend::IpcOutputMap;
+use crate::utils::{ipc_transform_to_smithay, smithay_transform_to_ipc};
+
+const VERSION: u32 = 4;
+
+pub struct OutputManagementManagerState {
+    display: DisplayHandle,
+    serial: u32,
+    /// Heads sent to every bound manager, by output name.
+    managers: HashMap<ZwlrOutputManagerV1, HashMap<String, HeadState>>,
+    current_state: IpcOutputMap,
+    /// Applied configurations waiting for the backend to report the new output state.
+    pending_applies: Vec<(
+        ZwlrOutputConfigurationV1,
+        HashMap<String, niri_config::Output>,
+    )>,
+}
+
+pub struct OutputManagementManagerGlobalData {
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+pub trait OutputManagementHandler {
+    fn output_management_state(&mut self) -> &mut OutputManagementManagerState;
+    fn apply_output_config(&mut self, config: Vec<niri_config::Output>);
+}
+
+struct HeadState {
+    head: ZwlrOutputHeadV1,
+    modes: Vec<ZwlrOutputModeV1>,
+}
+
+pub struct OutputModeData {
+    output: String,
+    mode: niri_ipc::Mode,
+}
+
+pub struct OutputConfigurationData {
+    serial: u32,
+    state: Mutex<OutputConfigurationState>,
+}
+
+enum OutputConfigurationState {
+    /// Configured heads by output name.
+    Ongoing(HashMap<String, niri_config::Output>),
+    /// The configuration was applied or tested and can't be used anymore.
+    Finished,
+}
+
+pub struct OutputConfigurationHeadData {
+    output: String,
+    configuration: ZwlrOutputConfigurationV1,
+}
+
+impl OutputManagementManagerState {
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ZwlrOutputManagerV1, OutputManagementManagerGlobalData>,
+        D: Dispatch<ZwlrOutputManagerV1, ()>,
+        D: Dispatch<ZwlrOutputHeadV1, String>,
+        D: Dispatch<ZwlrOutputModeV1, OutputModeData>,
+        D: Dispatch<ZwlrOutputConfigurationV1, OutputConfigurationData>,
+        D: Dispatch<ZwlrOutputConfigurationHeadV1, OutputConfigurationHeadData>,
+        D: OutputManagementHandler,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let global_data = OutputManagementManagerGlobalData {
+            filter: Box::new(filter),
+        };
+        display.create_global::<D, ZwlrOutputManagerV1, _>(VERSION, global_data);
+
+        Self {
+            display: display.clone(),
+            serial: 0,
+            managers: HashMap::new(),
+            current_state: HashMap::new(),
+            pending_applies: Vec::new(),
+        }
+    }
+
+    /// Sends the new output state to all clients.
+    pub fn notify_changes<D>(&mut self, new_state: IpcOutputMap)
+    where
+        D: Dispatch<ZwlrOutputHeadV1, String>,
+        D: Dispatch<ZwlrOutputModeV1, OutputModeData>,
+        D: 'static,
+    {
+        self.serial += 1;
+
+        // Now that the backend has applied the changes, tell the clients whether it worked.
+        for (conf, configs) in self.pending_applies.drain(..) {
+            if !conf.is_alive() {
+                continue;
+            }
+
+            if configs
+                .iter()
+                .all(|(name, config)| is_applied(&new_state, name, config))
+            {
+                conf.succeeded();
+            } else {
+                debug!("output configuration was not applied by the backend");
+                conf.failed();
+            }
+        }
+
+        for (manager, heads) in &mut self.managers {
+            heads.retain(|name, head| {
+                let keep = match (self.current_state.get(name), new_state.get(name)) {
+                    (Some(old), Some(new)) => modes_equal(old, new),
+                    _ => false,
+                };
+                if !keep {
+                    head.finish();
+                }
+                keep
+            });
+
+            for (name, output) in &new_state {
+                if let Some(head) = heads.get(name) {
+                    send_head_state(head, output);
+                } else if let Some(head) = create_head::<D>(&self.display, manager, name, output) {
+                    heads.insert(name.clone(), head);
+                }
+            }
+
+            manager.done(self.serial);
+        }
+
+        self.current_state = new_state;
+    }
+}
+
+impl HeadState {
+    fn finish(&self) {
+        for mode in &self.modes {
+            if mode.is_alive() {
+                mode.finished();
+            }
+        }
+        if self.head.is_alive() {
+            self.head.finished();
+        }
+    }
+}
+
+impl<D> GlobalDispatch<ZwlrOutputManagerV1, OutputManagementManagerGlobalData, D>
+    for OutputManagementManagerState
+where
+    D: GlobalDispatch<ZwlrOutputManagerV1, OutputManagementManagerGlobalData>,
+    D: Dispatch<ZwlrOutputManagerV1, ()>,
+    D: Dispatch<ZwlrOutputHeadV1, String>,
+    D: Dispatch<ZwlrOutputModeV1, OutputModeData>,
+    D: OutputManagementHandler,
+    D: 'static,
+{
+    fn bind(
+        state: &mut D,
+        display: &DisplayHandle,
+        _client: &Client,
+        manager: New<ZwlrOutputManagerV1>,
+        _manager_state: &OutputManagementManagerGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let manager = data_init.init(manager, ());
+
+        let g_state = state.output_management_state();
+        let mut heads = HashMap::new();
+        for (name, output) in &g_state.current_state {
+            if let Some(head) = create_head::<D>(display, &manager, name, output) {
+                heads.insert(name.clone(), head);
+            }
+        }
+        manager.done(g_state.serial);
+
+        g_state.managers.insert(manager, heads);
+    }
+
+    fn can_view(client: Client, global_data: &OutputManagementManagerGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ZwlrOutputManagerV1, (), D> for OutputManagementManagerState
+where
+    D: Dispatch<ZwlrOutputManagerV1, ()>,
+    D: Dispatch<ZwlrOutputConfigurationV1, OutputConfigurationData>,
+    D: OutputManagementHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        manager: &ZwlrOutputManagerV1,
+        request: <ZwlrOutputManagerV1 as Resource>::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_output_manager_v1::Request::CreateConfiguration { id, serial } => {
+                let data = OutputConfigurationData {
+                    serial,
+                    state: Mutex::new(OutputConfigurationState::Ongoing(HashMap::new())),
+                };
+                data_init.init(id, data);
+            }
+            zwlr_output_manager_v1::Request::Stop => {
+                let g_state = state.output_management_state();
+                if let Some(heads) = g_state.managers.remove(manager) {
+                    for head in heads.values() {
+                        head.finish();
+                    }
+                }
+                manager.finished();
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, manager: &ZwlrOutputManagerV1, _data: &()) {
+        state.output_management_state().managers.remove(manager);
+    }
+}
+
+impl<D> Dispatch<ZwlrOutputHeadV1, String, D> for OutputManagementManagerState
+where
+    D: Dispatch<ZwlrOutputHeadV1, String>,
+    D: OutputManagementHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        head: &ZwlrOutputHeadV1,
+        request: <ZwlrOutputHeadV1 as Resource>::Request,
+        _data: &String,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_output_head_v1::Request::Release => {
+                let g_state = state.output_management_state();
+                for heads in g_state.managers.values_mut() {
+                    heads.retain(|_, x| x.head != *head);
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZwlrOutputModeV1, OutputModeData, D> for OutputManagementManagerState
+where
+    D: Dispatch<ZwlrOutputModeV1, OutputModeData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _mode: &ZwlrOutputModeV1,
+        request: <ZwlrOutputModeV1 as Resource>::Request,
+        _data: &OutputModeData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            // We check that modes are alive before sending anything referring to them.
+            zwlr_output_mode_v1::Request::Release => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZwlrOutputConfigurationV1, OutputConfigurationData, D>
+    for OutputManagementManagerState
+where
+    D: Dispatch<ZwlrOutputConfigurationV1, OutputConfigurationData>,
+    D: Dispatch<ZwlrOutputConfigurationHeadV1, OutputConfigurationHeadData>,
+    D: OutputManagementHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        conf: &ZwlrOutputConfigurationV1,
+        request: <ZwlrOutputConfigurationV1 as Resource>::Request,
+        data: &OutputConfigurationData,
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let g_state = state.output_management_state();
+
+        match request {
+            zwlr_output_configuration_v1::Request::EnableHead { id, head } => {
+                let output = head.data::<String>().unwrap().clone();
+                let conf_head = data_init.init(
+                    id,
+                    OutputConfigurationHeadData {
+                        output: output.clone(),
+                        configuration: conf.clone(),
+                    },
+                );
+
+                let mut config = match g_state.current_state.get(&output) {
+                    Some(current) => current_config(current),
+                    None => niri_config::Output {
+                        name: output.clone(),
+                        ..Default::default()
+                    },
+                };
+                config.off = false;
+
+                if let Err((code, message)) = configure_head(data, output, config) {
+                    conf_head.post_error(code, message);
+                }
+            }
+            zwlr_output_configuration_v1::Request::DisableHead { head } => {
+                let output = head.data::<String>().unwrap().clone();
+                let config = niri_config::Output {
+                    off: true,
+                    name: output.clone(),
+                    ..Default::default()
+                };
+
+                if let Err((code, message)) = configure_head(data, output, config) {
+                    conf.post_error(code, message);
+                }
+            }
+            request @ (zwlr_output_configuration_v1::Request::Apply
+            | zwlr_output_configuration_v1::Request::Test) => {
+                let is_test = matches!(request, zwlr_output_configuration_v1::Request::Test);
+
+                let mut conf_state = data.state.lock().unwrap();
+                let configs =
+                    match mem::replace(&mut *conf_state, OutputConfigurationState::Finished) {
+                        OutputConfigurationState::Ongoing(configs) => configs,
+                        OutputConfigurationState::Finished => {
+                            conf.post_error(
+                                zwlr_output_configuration_v1::Error::AlreadyUsed,
+                                "configuration had already been applied or tested",
+                            );
+                            return;
+                        }
+                    };
+                drop(conf_state);
+
+                if data.serial != g_state.serial {
+                    conf.cancelled();
+                    return;
+                }
+
+                if let Some(name) = g_state
+                    .current_state
+                    .keys()
+                    .find(|name| !configs.contains_key(*name))
+                {
+                    conf.post_error(
+                        zwlr_output_configuration_v1::Error::UnconfiguredHead,
+                        format!("head {name} was not configured"),
+                    );
+                    return;
+                }
+
+                if let Err(err) = validate_config(&g_state.current_state, &configs) {
+                    debug!("rejecting output configuration: {err}");
+                    conf.failed();
+                    return;
+                }
+
+                if is_test {
+                    conf.succeeded();
+                    return;
+                }
+
+                // Reply once the backend reports the new output state in notify_changes().
+                state.apply_output_config(configs.values().cloned().collect());
+                state
+                    .output_management_state()
+                    .pending_applies
+                    .push((conf.clone(), configs));
+            }
+            zwlr_output_configuration_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZwlrOutputConfigurationHeadV1, OutputConfigurationHeadData, D>
+    for OutputManagementManagerState
+where
+    D: Dispatch<ZwlrOutputConfigurationHeadV1, OutputConfigurationHeadData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        conf_head: &ZwlrOutputConfigurationHeadV1,
+        request: <ZwlrOutputConfigurationHeadV1 as Resource>::Request,
+        data: &OutputConfigurationHeadData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        let Some(conf_data) = data.configuration.data::<OutputConfigurationData>() else {
+            return;
+        };
+        let mut conf_state = conf_data.state.lock().unwrap();
+        let OutputConfigurationState::Ongoing(configs) = &mut *conf_state else {
+            conf_head.post_error(
+                zwlr_output_configuration_v1::Error::AlreadyUsed,
+                "configuration had already been applied or tested",
+            );
+            return;
+        };
+        let Some(config) = configs.get_mut(&data.output) else {
+            return;
+        };
+
+        match request {
+            zwlr_output_configuration_head_v1::Request::SetMode { mode } => {
+                let mode_data = mode.data::<OutputModeData>().unwrap();
+                if mode_data.output != data.output {
+                    conf_head.post_error(
+                        zwlr_output_configuration_head_v1::Error::InvalidMode,
+                        "mode belongs to a different head",
+                    );
+                    return;
+                }
+
+                config.mode = Some(ConfiguredMode {
+                    width: mode_data.mode.width,
+                    height: mode_data.mode.height,
+                    refresh: Some(f64::from(mode_data.mode.refresh_rate) / 1000.),
+                });
+            }
+            zwlr_output_configuration_head_v1::Request::SetCustomMode {
+                width,
+                height,
+                refresh,
+            } => {
+                let (Ok(width), Ok(height), Ok(refresh)) = (
+                    u16::try_from(width),
+                    u16::try_from(height),
+                    u32::try_from(refresh),
+                ) else {
+                    conf_head.post_error(
+                        zwlr_output_configuration_head_v1::Error::InvalidCustomMode,
+                        "invalid custom mode",
+                    );
+                    return;
+                };
+                if width == 0 || height == 0 {
+                    conf_head.post_error(
+                        zwlr_output_configuration_head_v1::Error::InvalidCustomMode,
+                        "invalid custom mode",
+                    );
+                    return;
+                }
+
+                config.mode = Some(ConfiguredMode {
+                    width,
+                    height,
+                    refresh: (refresh != 0).then(|| f64::from(refresh) / 1000.),
+                });
+            }
+            zwlr_output_configuration_head_v1::Request::SetPosition { x, y } => {
+                config.position = Some(niri_config::Position { x, y });
+            }
+            zwlr_output_configuration_head_v1::Request::SetTransform { transform } => {
+                let WEnum::Value(transform) = transform else {
+                    conf_head.post_error(
+                        zwlr_output_configuration_head_v1::Error::InvalidTransform,
+                        "invalid transform",
+                    );
+                    return;
+                };
+                config.transform = smithay_transform_to_ipc(Transform::from(transform));
+            }
+            zwlr_output_configuration_head_v1::Request::SetScale { scale } => {
+                if !scale.is_finite() || scale <= 0. {
+                    conf_head.post_error(
+                        zwlr_output_configuration_head_v1::Error::InvalidScale,
+                        "scale must be positive",
+                    );
+                    return;
+                }
+                config.scale = Some(FloatOrInt(scale));
+            }
+            zwlr_output_configuration_head_v1::Request::SetAdaptiveSync { state } => {
+                config.variable_refresh_rate = match state {
+                    WEnum::Value(AdaptiveSyncState::Enabled) => true,
+                    WEnum::Value(AdaptiveSyncState::Disabled) => false,
+                    _ => {
+                        conf_head.post_error(
+                            zwlr_output_configuration_head_v1::Error::InvalidAdaptiveSyncState,
+                            "invalid adaptive sync state",
+                        );
+                        return;
+                    }
+                };
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+fn configure_head(
+    data: &OutputConfigurationData,
+    output: String,
+    config: niri_config::Output,
+) -> Result<(), (zwlr_output_configuration_v1::Error, &'static str)> {
+    let mut conf_state = data.state.lock().unwrap();
+    let OutputConfigurationState::Ongoing(configs) = &mut *conf_state else {
+        return Err((
+            zwlr_output_configuration_v1::Error::AlreadyUsed,
+            "configuration had already been applied or tested",
+        ));
+    };
+
+    if configs.contains_key(&output) {
+        return Err((
+            zwlr_output_configuration_v1::Error::AlreadyConfiguredHead,
+            "head was already configured",
+        ));
+    }
+
+    configs.insert(output, config);
+    Ok(())
+}
+
+/// Checks that the configuration can be applied to the current outputs.
+fn validate_config(
+    current: &IpcOutputMap,
+    configs: &HashMap<String, niri_config::Output>,
+) -> Result<(), String> {
+    let mut rects: Vec<(&str, Rectangle<f64, Logical>)> = Vec::new();
+
+    for (name, config) in configs {
+        if config.off {
+            continue;
+        }
+
+        let Some(output) = current.get(name) else {
+            return Err(format!("output {name} is not connected"));
+        };
+
+        let mode = match config.mode {
+            Some(target) => {
+                let refresh = target.refresh.map(|r| (r * 1000.).round() as u32);
+                output.modes.iter().find(|m| {
+                    m.width == target.width
+                        && m.height == target.height
+                        && refresh.map_or(true, |r| m.refresh_rate == r)
+                })
+            }
+            None => output
+                .current_mode
+                .or_else(|| output.modes.iter().position(|m| m.is_preferred))
+                .and_then(|idx| output.modes.get(idx)),
+        };
+        let Some(mode) = mode else {
+            return Err(format!("mode {:?} is not supported by {name}", config.mode));
+        };
+
+        if config.variable_refresh_rate && !output.vrr_supported {
+            return Err(format!("output {name} does not support adaptive sync"));
+        }
+
+        let scale = config.scale.map_or(1., |s| s.0);
+        if !(0.1..=10.).contains(&scale) {
+            return Err(format!("scale {scale} is out of range"));
+        }
+
+        if let Some(position) = config.position {
+            let size = Size::<f64, Physical>::from((f64::from(mode.width), f64::from(mode.height)))
+                .to_logical(scale);
+            let size = ipc_transform_to_smithay(config.transform).transform_size(size);
+            let rect =
+                Rectangle::from_loc_and_size((f64::from(position.x), f64::from(position.y)), size);
+            rects.push((name, rect));
+        }
+    }
+
+    for (idx, (name, rect)) in rects.iter().enumerate() {
+        for (other_name, other_rect) in &rects[idx + 1..] {
+            if rect.overlaps(*other_rect) {
+                return Err(format!("outputs {name} and {other_name} overlap"));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the config matching the current state of the output.
+fn current_config(output: &niri_ipc::Output) -> niri_config::Output {
+    let mode = output
+        .current_mode
+        .and_then(|idx| output.modes.get(idx))
+        .map(|mode| ConfiguredMode {
+            width: mode.width,
+            height: mode.height,
+            refresh: Some(f64::from(mode.refresh_rate) / 1000.),
+        });
+
+    niri_config::Output {
+        off: output.current_mode.is_none(),
+        name: output.name.clone(),
+        scale: output.logical.map(|logical| FloatOrInt(logical.scale)),
+        transform: output
+            .logical
+            .map_or(niri_ipc::Transform::Normal, |logical| logical.transform),
+        position: output.logical.map(|logical| niri_config::Position {
+            x: logical.x,
+            y: logical.y,
+        }),
+        mode,
+        variable_refresh_rate: output.vrr_enabled,
+        ..Default::default()
+    }
+}
+
+fn is_applied(state: &IpcOutputMap, name: &str, config: &niri_config::Output) -> bool {
+    let Some(output) = state.get(name) else {
+        return false;
+    };
+
+    let Some(current) = output.current_mode.and_then(|idx| output.modes.get(idx)) else {
+        return config.off;
+    };
+    if config.off {
+        return false;
+    }
+
+    config.mode.map_or(true, |mode| {
+        current.width == mode.width && current.height == mode.height
+    })
+}
+
+fn modes_equal(a: &niri_ipc::Output, b: &niri_ipc::Output) -> bool {
+    a.modes.len() == b.modes.len()
+        && a.modes.iter().zip(&b.modes).all(|(a, b)| {
+            a.width == b.width
+                && a.height == b.height
+                && a.refresh_rate == b.refresh_rate
+                && a.is_preferred == b.is_preferred
+        })
+}
+
+fn create_head<D>(
+    display: &DisplayHandle,
+    manager: &ZwlrOutputManagerV1,
+    name: &str,
+    output: &niri_ipc::Output,
+) -> Option<HeadState>
+where
+    D: Dispatch<ZwlrOutputHeadV1, String>,
+    D: Dispatch<ZwlrOutputModeV1, OutputModeData>,
+    D: 'static,
+{
+    let client = manager.client()?;
+    let version = manager.version();
+
+    let head = client
+        .create_resource::<ZwlrOutputHeadV1, _, D>(display, version, name.to_owned())
+        .ok()?;
+    manager.head(&head);
+
+    head.name(name.to_owned());
+    head.description(format!("{} - {} - {name}", output.make, output.model));
+    if let Some((width, height)) = output.physical_size {
+        head.physical_size(
+            i32::try_from(width).unwrap_or(i32::MAX),
+            i32::try_from(height).unwrap_or(i32::MAX),
+        );
+    }
+    if version >= zwlr_output_head_v1::EVT_MAKE_SINCE {
+        head.make(output.make.clone());
+        head.model(output.model.clone());
+    }
+
+    let mut modes = Vec::with_capacity(output.modes.len());
+    for mode in &output.modes {
+        let data = OutputModeData {
+            output: name.to_owned(),
+            mode: *mode,
+        };
+        let Ok(wl_mode) = client.create_resource::<ZwlrOutputModeV1, _, D>(display, version, data)
+        else {
+            continue;
+        };
+        head.mode(&wl_mode);
+
+        wl_mode.size(i32::from(mode.width), i32::from(mode.height));
+        wl_mode.refresh(i32::try_from(mode.refresh_rate).unwrap_or(i32::MAX));
+        if mode.is_preferred {
+            wl_mode.preferred();
+        }
+
+        modes.push(wl_mode);
+    }
+
+    let head = HeadState { head, modes };
+    send_head_state(&head, output);
+    Some(head)
+}
+
+fn send_head_state(head: &HeadState, output: &niri_ipc::Output) {
+    let HeadState { head, modes } = head;
+    if !head.is_alive() {
+        return;
+    }
+
+    let current_mode = output.current_mode.and_then(|idx| modes.get(idx));
+    let logical = output.logical.filter(|_| current_mode.is_some());
+
+    head.enabled(i32::from(logical.is_some()));
+
+    if let Some(logical) = logical {
+        if let Some(mode) = current_mode.filter(|mode| mode.is_alive()) {
+            head.current_mode(mode);
+        }
+        head.position(logical.x, logical.y);
+        head.transform(wl_output::Transform::from(ipc_transform_to_smithay(
+            logical.transform,
+        )));
+        head.scale(logical.scale);
+    }
+
+    if head.version() >= zwlr_output_head_v1::EVT_ADAPTIVE_SYNC_SINCE {
+        head.adaptive_sync(if output.vrr_enabled {
+            AdaptiveSyncState::Enabled
+        } else {
+            AdaptiveSyncState::Disabled
+        });
+    }
+}
+
+#[macro_export]
+macro_rules! delegate_output_management {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_manager_v1::ZwlrOutputManagerV1: $crate::protocols::output_management::OutputManagementManagerGlobalData
+        ] => $crate::protocols::output_management::OutputManagementManagerState);
+
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_manager_v1::ZwlrOutputManagerV1: ()
+        ] => $crate::protocols::output_management::OutputManagementManagerState);
+
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_head_v1::ZwlrOutputHeadV1: String
+        ] => $crate::protocols::output_management::OutputManagementManagerState);
+
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_mode_v1::ZwlrOutputModeV1: $crate::protocols::output_management::OutputModeData
+        ] => $crate::protocols::output_management::OutputManagementManagerState);
+
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_configuration_v1::ZwlrOutputConfigurationV1: $crate::protocols::output_management::OutputConfigurationData
+        ] => $crate::protocols::output_management::OutputManagementManagerState);
+
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_configuration_head_v1::ZwlrOutputConfigurationHeadV1: $crate::protocols::output_management::OutputConfigurationHeadData
+        ] => $crate::protocols::output_management::OutputManagementManagerState);
+    };
+}
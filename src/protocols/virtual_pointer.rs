@@ -0,0 +1,579 @@
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use smithay::backend::input::{
+    AbsolutePositionEvent, Axis, AxisRelativeDirection, AxisSource, ButtonState, Device,
+    DeviceCapability, Event, InputBackend, InputEvent, PointerAxisEvent, PointerButtonEvent,
+    PointerMotionAbsoluteEvent, PointerMotionEvent, UnusedEvent,
+};
+use smithay::output::Output;
+use smithay::reexports::wayland_protocols_wlr;
+use smithay::reexports::wayland_server::backend::{ClientId, ObjectId};
+use smithay::reexports::wayland_server::protocol::wl_pointer;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, WEnum,
+};
+use wayland_protocols_wlr::virtual_pointer::v1::server::{
+    zwlr_virtual_pointer_manager_v1, zwlr_virtual_pointer_v1,
+};
+use zwlr_virtual_pointer_manager_v1::ZwlrVirtualPointerManagerV1;
+use zwlr_virtual_pointer_v1::ZwlrVirtualPointerV1;
+
+const VERSION: u32 = 2;
+
+pub struct VirtualPointerManagerState;
+
+pub struct VirtualPointerManagerGlobalData {
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+pub trait VirtualPointerHandler {
+    /// Processes an event from a virtual pointer like one from a real device.
+    fn process_virtual_pointer_event(&mut self, event: InputEvent<VirtualPointerInputBackend>);
+}
+
+pub struct VirtualPointerUserData {
+    /// Output that absolute motion maps to, or all outputs if `None`.
+    output: Option<Output>,
+    /// Axis events waiting for the frame request.
+    axis: Mutex<VirtualPointerAxisEvent>,
+    /// Buttons held down, released when the virtual pointer goes away.
+    buttons: Mutex<PressedButtons>,
+}
+
+#[derive(Debug, Default)]
+struct PressedButtons {
+    /// Time of the latest button event.
+    time: u32,
+    pressed: Vec<u32>,
+}
+
+/// Input backend for the events of virtual pointers.
+#[derive(Debug)]
+pub struct VirtualPointerInputBackend;
+
+/// Virtual pointer created by a client.
+#[derive(Debug, Clone)]
+pub struct VirtualPointer {
+    id: ObjectId,
+    output: Option<Output>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VirtualPointerMotionEvent {
+    pointer: VirtualPointer,
+    time: u32,
+    dx: f64,
+    dy: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct VirtualPointerMotionAbsoluteEvent {
+    pointer: VirtualPointer,
+    time: u32,
+    x: u32,
+    y: u32,
+    x_extent: u32,
+    y_extent: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct VirtualPointerButtonEvent {
+    pointer: VirtualPointer,
+    time: u32,
+    button: u32,
+    state: ButtonState,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VirtualPointerAxisEvent {
+    pointer: Option<VirtualPointer>,
+    time: u32,
+    source: Option<AxisSource>,
+    horizontal: Option<f64>,
+    vertical: Option<f64>,
+    horizontal_v120: Option<f64>,
+    vertical_v120: Option<f64>,
+}
+
+impl VirtualPointerManagerState {
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ZwlrVirtualPointerManagerV1, VirtualPointerManagerGlobalData>,
+        D: Dispatch<ZwlrVirtualPointerManagerV1, ()>,
+        D: Dispatch<ZwlrVirtualPointerV1, VirtualPointerUserData>,
+        D: VirtualPointerHandler,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let global_data = VirtualPointerManagerGlobalData {
+            filter: Box::new(filter),
+        };
+        display.create_global::<D, ZwlrVirtualPointerManagerV1, _>(VERSION, global_data);
+
+        Self
+    }
+}
+
+impl<D> GlobalDispatch<ZwlrVirtualPointerManagerV1, VirtualPointerManagerGlobalData, D>
+    for VirtualPointerManagerState
+where
+    D: GlobalDispatch<ZwlrVirtualPointerManagerV1, VirtualPointerManagerGlobalData>,
+    D: Dispatch<ZwlrVirtualPointerManagerV1, ()>,
+    D: Dispatch<ZwlrVirtualPointerV1, VirtualPointerUserData>,
+    D: VirtualPointerHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        manager: New<ZwlrVirtualPointerManagerV1>,
+        _manager_state: &VirtualPointerManagerGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(manager, ());
+    }
+
+    fn can_view(client: Client, global_data: &VirtualPointerManagerGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ZwlrVirtualPointerManagerV1, (), D> for VirtualPointerManagerState
+where
+    D: Dispatch<ZwlrVirtualPointerManagerV1, ()>,
+    D: Dispatch<ZwlrVirtualPointerV1, VirtualPointerUserData>,
+    D: VirtualPointerHandler,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &ZwlrVirtualPointerManagerV1,
+        request: <ZwlrVirtualPointerManagerV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        // We have a single seat, so the requested one doesn't matter.
+        let (id, output) = match request {
+            zwlr_virtual_pointer_manager_v1::Request::CreateVirtualPointer { seat: _, id } => {
+                (id, None)
+            }
+            zwlr_virtual_pointer_manager_v1::Request::CreateVirtualPointerWithOutput {
+                seat: _,
+                output,
+                id,
+            } => (id, output.as_ref().and_then(Output::from_resource)),
+            zwlr_virtual_pointer_manager_v1::Request::Destroy => return,
+            _ => unreachable!(),
+        };
+
+        let data = VirtualPointerUserData {
+            output,
+            axis: Mutex::new(VirtualPointerAxisEvent::default()),
+            buttons: Mutex::new(PressedButtons::default()),
+        };
+        data_init.init(id, data);
+    }
+}
+
+impl<D> Dispatch<ZwlrVirtualPointerV1, VirtualPointerUserData, D> for VirtualPointerManagerState
+where
+    D: Dispatch<ZwlrVirtualPointerV1, VirtualPointerUserData>,
+    D: VirtualPointerHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &ZwlrVirtualPointerV1,
+        request: <ZwlrVirtualPointerV1 as Resource>::Request,
+        data: &VirtualPointerUserData,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        let pointer = VirtualPointer {
+            id: resource.id(),
+            output: data.output.clone(),
+        };
+
+        match request {
+            zwlr_virtual_pointer_v1::Request::Motion { time, dx, dy } => {
+                let event = VirtualPointerMotionEvent {
+                    pointer,
+                    time,
+                    dx,
+                    dy,
+                };
+                state.process_virtual_pointer_event(InputEvent::PointerMotion { event });
+            }
+            zwlr_virtual_pointer_v1::Request::MotionAbsolute {
+                time,
+                x,
+                y,
+                x_extent,
+                y_extent,
+            } => {
+                if x_extent == 0 || y_extent == 0 {
+                    return;
+                }
+
+                let event = VirtualPointerMotionAbsoluteEvent {
+                    pointer,
+                    time,
+                    x,
+                    y,
+                    x_extent,
+                    y_extent,
+                };
+                state.process_virtual_pointer_event(InputEvent::PointerMotionAbsolute { event });
+            }
+            zwlr_virtual_pointer_v1::Request::Button {
+                time,
+                button,
+                state: button_state,
+            } => {
+                let button_state = match button_state {
+                    WEnum::Value(wl_pointer::ButtonState::Pressed) => ButtonState::Pressed,
+                    WEnum::Value(wl_pointer::ButtonState::Released) => ButtonState::Released,
+                    _ => return,
+                };
+
+                let mut buttons = data.buttons.lock().unwrap();
+                buttons.time = time;
+                match button_state {
+                    ButtonState::Pressed => {
+                        if !buttons.pressed.contains(&button) {
+                            buttons.pressed.push(button);
+                        }
+                    }
+                    ButtonState::Released => buttons.pressed.retain(|b| *b != button),
+                }
+                drop(buttons);
+
+                let event = VirtualPointerButtonEvent {
+                    pointer,
+                    time,
+                    button,
+                    state: button_state,
+                };
+                state.process_virtual_pointer_event(InputEvent::PointerButton { event });
+            }
+            zwlr_virtual_pointer_v1::Request::Axis { time, axis, value } => {
+                let Some(axis) = convert_axis(resource, axis) else {
+                    return;
+                };
+
+                let mut frame = data.axis.lock().unwrap();
+                frame.pointer = Some(pointer);
+                frame.time = time;
+                *frame.amount_mut(axis) = Some(value);
+            }
+            zwlr_virtual_pointer_v1::Request::AxisSource { axis_source } => {
+                let source = match axis_source {
+                    WEnum::Value(wl_pointer::AxisSource::Wheel) => AxisSource::Wheel,
+                    WEnum::Value(wl_pointer::AxisSource::Finger) => AxisSource::Finger,
+                    WEnum::Value(wl_pointer::AxisSource::Continuous) => AxisSource::Continuous,
+                    WEnum::Value(wl_pointer::AxisSource::WheelTilt) => AxisSource::WheelTilt,
+                    _ => {
+                        resource.post_error(
+                            zwlr_virtual_pointer_v1::Error::InvalidAxisSource,
+                            "invalid axis source",
+                        );
+                        return;
+                    }
+                };
+
+                data.axis.lock().unwrap().source = Some(source);
+            }
+            zwlr_virtual_pointer_v1::Request::AxisStop { time, axis } => {
+                let Some(axis) = convert_axis(resource, axis) else {
+                    return;
+                };
+
+                let mut frame = data.axis.lock().unwrap();
+                frame.pointer = Some(pointer);
+                frame.time = time;
+                *frame.amount_mut(axis) = Some(0.);
+            }
+            zwlr_virtual_pointer_v1::Request::AxisDiscrete {
+                time,
+                axis,
+                value,
+                discrete,
+            } => {
+                let Some(axis) = convert_axis(resource, axis) else {
+                    return;
+                };
+
+                let mut frame = data.axis.lock().unwrap();
+                frame.pointer = Some(pointer);
+                frame.time = time;
+                *frame.amount_mut(axis) = Some(value);
+                *frame.amount_v120_mut(axis) = Some(f64::from(discrete) * 120.);
+            }
+            zwlr_virtual_pointer_v1::Request::Frame => {
+                let event = std::mem::take(&mut *data.axis.lock().unwrap());
+                if event.pointer.is_some() {
+                    state.process_virtual_pointer_event(InputEvent::PointerAxis { event });
+                }
+            }
+            zwlr_virtual_pointer_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut D,
+        _client: ClientId,
+        resource: &ZwlrVirtualPointerV1,
+        data: &VirtualPointerUserData,
+    ) {
+        // Release the buttons that the client left held down, so that they don't stay stuck.
+        let buttons = std::mem::take(&mut *data.buttons.lock().unwrap());
+        for button in buttons.pressed {
+            let event = VirtualPointerButtonEvent {
+                pointer: VirtualPointer {
+                    id: resource.id(),
+                    output: data.output.clone(),
+                },
+                time: buttons.time,
+                button,
+                state: ButtonState::Released,
+            };
+            state.process_virtual_pointer_event(InputEvent::PointerButton { event });
+        }
+    }
+}
+
+fn convert_axis(resource: &ZwlrVirtualPointerV1, axis: WEnum<wl_pointer::Axis>) -> Option<Axis> {
+    match axis {
+        WEnum::Value(wl_pointer::Axis::HorizontalScroll) => Some(Axis::Horizontal),
+        WEnum::Value(wl_pointer::Axis::VerticalScroll) => Some(Axis::Vertical),
+        _ => {
+            resource.post_error(zwlr_virtual_pointer_v1::Error::InvalidAxis, "invalid axis");
+            None
+        }
+    }
+}
+
+impl VirtualPointer {
+    /// Returns the output that absolute motion of this pointer maps to.
+    pub fn output(&self) -> Option<&Output> {
+        self.output.as_ref()
+    }
+}
+
+impl PartialEq for VirtualPointer {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for VirtualPointer {}
+
+impl Hash for VirtualPointer {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl Device for VirtualPointer {
+    fn id(&self) -> String {
+        format!("virtual-pointer-{}", self.id.protocol_id())
+    }
+
+    fn name(&self) -> String {
+        String::from("virtual pointer")
+    }
+
+    fn has_capability(&self, capability: DeviceCapability) -> bool {
+        capability == DeviceCapability::Pointer
+    }
+
+    fn usb_id(&self) -> Option<(u32, u32)> {
+        None
+    }
+
+    fn syspath(&self) -> Option<PathBuf> {
+        None
+    }
+}
+
+impl InputBackend for VirtualPointerInputBackend {
+    type Device = VirtualPointer;
+    type KeyboardKeyEvent = UnusedEvent;
+    type PointerAxisEvent = VirtualPointerAxisEvent;
+    type PointerButtonEvent = VirtualPointerButtonEvent;
+    type PointerMotionEvent = VirtualPointerMotionEvent;
+    type PointerMotionAbsoluteEvent = VirtualPointerMotionAbsoluteEvent;
+    type GestureSwipeBeginEvent = UnusedEvent;
+    type GestureSwipeUpdateEvent = UnusedEvent;
+    type GestureSwipeEndEvent = UnusedEvent;
+    type GesturePinchBeginEvent = UnusedEvent;
+    type GesturePinchUpdateEvent = UnusedEvent;
+    type GesturePinchEndEvent = UnusedEvent;
+    type GestureHoldBeginEvent = UnusedEvent;
+    type GestureHoldEndEvent = UnusedEvent;
+    type TouchDownEvent = UnusedEvent;
+    type TouchUpEvent = UnusedEvent;
+    type TouchMotionEvent = UnusedEvent;
+    type TouchCancelEvent = UnusedEvent;
+    type TouchFrameEvent = UnusedEvent;
+    type TabletToolAxisEvent = UnusedEvent;
+    type TabletToolProximityEvent = UnusedEvent;
+    type TabletToolTipEvent = UnusedEvent;
+    type TabletToolButtonEvent = UnusedEvent;
+    type SwitchToggleEvent = UnusedEvent;
+    type SpecialEvent = UnusedEvent;
+}
+
+impl Event<VirtualPointerInputBackend> for VirtualPointerMotionEvent {
+    fn time(&self) -> u64 {
+        u64::from(self.time) * 1000
+    }
+
+    fn device(&self) -> VirtualPointer {
+        self.pointer.clone()
+    }
+}
+
+impl PointerMotionEvent<VirtualPointerInputBackend> for VirtualPointerMotionEvent {
+    fn delta_x(&self) -> f64 {
+        self.dx
+    }
+
+    fn delta_y(&self) -> f64 {
+        self.dy
+    }
+
+    fn delta_x_unaccel(&self) -> f64 {
+        self.dx
+    }
+
+    fn delta_y_unaccel(&self) -> f64 {
+        self.dy
+    }
+}
+
+impl Event<VirtualPointerInputBackend> for VirtualPointerMotionAbsoluteEvent {
+    fn time(&self) -> u64 {
+        u64::from(self.time) * 1000
+    }
+
+    fn device(&self) -> VirtualPointer {
+        self.pointer.clone()
+    }
+}
+
+impl AbsolutePositionEvent<VirtualPointerInputBackend> for VirtualPointerMotionAbsoluteEvent {
+    fn x(&self) -> f64 {
+        f64::from(self.x)
+    }
+
+    fn y(&self) -> f64 {
+        f64::from(self.y)
+    }
+
+    fn x_transformed(&self, width: i32) -> f64 {
+        f64::from(self.x) / f64::from(self.x_extent) * f64::from(width)
+    }
+
+    fn y_transformed(&self, height: i32) -> f64 {
+        f64::from(self.y) / f64::from(self.y_extent) * f64::from(height)
+    }
+}
+
+impl PointerMotionAbsoluteEvent<VirtualPointerInputBackend> for VirtualPointerMotionAbsoluteEvent {}
+
+impl Event<VirtualPointerInputBackend> for VirtualPointerButtonEvent {
+    fn time(&self) -> u64 {
+        u64::from(self.time) * 1000
+    }
+
+    fn device(&self) -> VirtualPointer {
+        self.pointer.clone()
+    }
+}
+
+impl PointerButtonEvent<VirtualPointerInputBackend> for VirtualPointerButtonEvent {
+    fn button_code(&self) -> u32 {
+        self.button
+    }
+
+    fn state(&self) -> ButtonState {
+        self.state
+    }
+}
+
+impl VirtualPointerAxisEvent {
+    fn amount_mut(&mut self, axis: Axis) -> &mut Option<f64> {
+        match axis {
+            Axis::Horizontal => &mut self.horizontal,
+            Axis::Vertical => &mut self.vertical,
+        }
+    }
+
+    fn amount_v120_mut(&mut self, axis: Axis) -> &mut Option<f64> {
+        match axis {
+            Axis::Horizontal => &mut self.horizontal_v120,
+            Axis::Vertical => &mut self.vertical_v120,
+        }
+    }
+}
+
+impl Event<VirtualPointerInputBackend> for VirtualPointerAxisEvent {
+    fn time(&self) -> u64 {
+        u64::from(self.time) * 1000
+    }
+
+    fn device(&self) -> VirtualPointer {
+        // Axis events are only sent once a request has filled in the pointer.
+        self.pointer.clone().unwrap()
+    }
+}
+
+impl PointerAxisEvent<VirtualPointerInputBackend> for VirtualPointerAxisEvent {
+    fn amount(&self, axis: Axis) -> Option<f64> {
+        match axis {
+            Axis::Horizontal => self.horizontal,
+            Axis::Vertical => self.vertical,
+        }
+    }
+
+    fn amount_v120(&self, axis: Axis) -> Option<f64> {
+        match axis {
+            Axis::Horizontal => self.horizontal_v120,
+            Axis::Vertical => self.vertical_v120,
+        }
+    }
+
+    fn source(&self) -> AxisSource {
+        // The protocol makes the source optional, and wheels are the most likely to be emulated.
+        self.source.unwrap_or(AxisSource::Wheel)
+    }
+
+    fn relative_direction(&self, _axis: Axis) -> AxisRelativeDirection {
+        AxisRelativeDirection::Identical
+    }
+}
+
+#[macro_export]
+macro_rules! delegate_virtual_pointer {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::virtual_pointer::v1::server::zwlr_virtual_pointer_manager_v1::ZwlrVirtualPointerManagerV1: $crate::protocols::virtual_pointer::VirtualPointerManagerGlobalData
+        ] => $crate::protocols::virtual_pointer::VirtualPointerManagerState);
+
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::virtual_pointer::v1::server::zwlr_virtual_pointer_manager_v1::ZwlrVirtualPointerManagerV1: ()
+        ] => $crate::protocols::virtual_pointer::VirtualPointerManagerState);
+
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::virtual_pointer::v1::server::zwlr_virtual_pointer_v1::ZwlrVirtualPointerV1: $crate::protocols::virtual_pointer::VirtualPointerUserData
+        ] => $crate::protocols::virtual_pointer::VirtualPointerManagerState);
+    };
+}
@@ -0,0 +1,595 @@
+//! xdg-foreign, both the unstable v1 and v2 versions.
+//!
+//! Clients often use different versions for exporting and importing (e.g. a GTK 4 app exports
+//! with v2, while a GTK 3 portal backend imports with v1), so both versions share the same
+//! handles.
+
+use std::collections::HashMap;
+
+use smithay::reexports::wayland_protocols::xdg::foreign::zv1::server::{
+    zxdg_exported_v1, zxdg_exporter_v1, zxdg_imported_v1, zxdg_importer_v1,
+};
+use smithay::reexports::wayland_protocols::xdg::foreign::zv2::server::{
+    zxdg_exported_v2, zxdg_exporter_v2, zxdg_imported_v2, zxdg_importer_v2,
+};
+use smithay::reexports::wayland_server::backend::ClientId;
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+use smithay::wayland::shell::xdg::{ToplevelSurface, XdgShellHandler};
+use zxdg_exported_v1::ZxdgExportedV1;
+use zxdg_exported_v2::ZxdgExportedV2;
+use zxdg_exporter_v1::ZxdgExporterV1;
+use zxdg_exporter_v2::ZxdgExporterV2;
+use zxdg_imported_v1::ZxdgImportedV1;
+use zxdg_imported_v2::ZxdgImportedV2;
+use zxdg_importer_v1::ZxdgImporterV1;
+use zxdg_importer_v2::ZxdgImporterV2;
+
+const VERSION: u32 = 1;
+
+pub struct XdgForeignState {
+    exports: HashMap<String, Export>,
+}
+
+pub trait XdgForeignHandler: XdgShellHandler {
+    fn xdg_foreign_state(&mut self) -> &mut XdgForeignState;
+}
+
+struct Export {
+    surface: WlSurface,
+    imports: Vec<Import>,
+}
+
+struct Import {
+    resource: Imported,
+    /// Toplevels that got the exported surface as their parent through this import.
+    children: Vec<ToplevelSurface>,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+enum Imported {
+    V1(ZxdgImportedV1),
+    V2(ZxdgImportedV2),
+}
+
+/// Handle of an exported or imported surface.
+///
+/// `None` for imports of unknown handles and for surfaces that aren't toplevels, which stay inert.
+pub struct XdgForeignHandle(Option<String>);
+
+impl XdgForeignState {
+    pub fn new<D>(display: &DisplayHandle) -> Self
+    where
+        D: GlobalDispatch<ZxdgExporterV1, ()>,
+        D: GlobalDispatch<ZxdgExporterV2, ()>,
+        D: GlobalDispatch<ZxdgImporterV1, ()>,
+        D: GlobalDispatch<ZxdgImporterV2, ()>,
+        D: 'static,
+    {
+        display.create_global::<D, ZxdgExporterV1, _>(VERSION, ());
+        display.create_global::<D, ZxdgExporterV2, _>(VERSION, ());
+        display.create_global::<D, ZxdgImporterV1, _>(VERSION, ());
+        display.create_global::<D, ZxdgImporterV2, _>(VERSION, ());
+
+        Self {
+            exports: HashMap::new(),
+        }
+    }
+
+    fn export(&mut self, surface: WlSurface) -> String {
+        let handle = loop {
+            let handle = format!("{:016x}{:016x}", fastrand::u64(..), fastrand::u64(..));
+            if !self.exports.contains_key(&handle) {
+                break handle;
+            }
+        };
+
+        let export = Export {
+            surface,
+            imports: Vec::new(),
+        };
+        self.exports.insert(handle.clone(), export);
+
+        handle
+    }
+
+    /// Destroys the export, making its imports inert.
+    fn unexport(&mut self, handle: &str) {
+        let Some(export) = self.exports.remove(handle) else {
+            return;
+        };
+
+        for import in export.imports {
+            import.resource.destroyed();
+            unset_parent(&import.children, &export.surface);
+        }
+    }
+
+    /// Records the import, returning whether the handle is valid.
+    fn import(&mut self, handle: &str, resource: Imported) -> bool {
+        let Some(export) = self.exports.get_mut(handle) else {
+            return false;
+        };
+
+        if !export.surface.is_alive() {
+            self.unexport(handle);
+            return false;
+        }
+
+        export.imports.push(Import {
+            resource,
+            children: Vec::new(),
+        });
+        true
+    }
+
+    fn unimport(&mut self, handle: &str, resource: &Imported) {
+        let Some(export) = self.exports.get_mut(handle) else {
+            return;
+        };
+
+        let Some(idx) = export
+            .imports
+            .iter()
+            .position(|import| import.resource == *resource)
+        else {
+            return;
+        };
+
+        // Relationships set up through this import are invalidated together with it.
+        let import = export.imports.remove(idx);
+        unset_parent(&import.children, &export.surface);
+    }
+}
+
+impl Imported {
+    fn destroyed(&self) {
+        match self {
+            Imported::V1(imported) => imported.destroyed(),
+            Imported::V2(imported) => imported.destroyed(),
+        }
+    }
+}
+
+fn find_toplevel<D: XdgShellHandler>(
+    state: &mut D,
+    surface: &WlSurface,
+) -> Option<ToplevelSurface> {
+    state
+        .xdg_shell_state()
+        .toplevel_surfaces()
+        .iter()
+        .find(|toplevel| toplevel.wl_surface() == surface)
+        .cloned()
+}
+
+fn unset_parent(children: &[ToplevelSurface], parent: &WlSurface) {
+    for child in children {
+        // The child could have been parented to something else in the meantime.
+        if child.alive() && child.parent().as_ref() == Some(parent) {
+            child.set_parent(None);
+        }
+    }
+}
+
+/// Exports the surface, returning its handle, or `None` if it isn't a toplevel.
+fn export<D: XdgForeignHandler>(state: &mut D, surface: WlSurface) -> Option<String> {
+    find_toplevel(state, &surface)?;
+    Some(state.xdg_foreign_state().export(surface))
+}
+
+/// Makes the exported surface the parent of the child, returning whether the child is a toplevel.
+fn set_parent_of<D: XdgForeignHandler>(
+    state: &mut D,
+    handle: &XdgForeignHandle,
+    resource: &Imported,
+    child: WlSurface,
+) -> bool {
+    let Some(toplevel) = find_toplevel(state, &child) else {
+        return false;
+    };
+
+    let Some(handle) = &handle.0 else {
+        return true;
+    };
+    let Some(export) = state.xdg_foreign_state().exports.get_mut(handle) else {
+        return true;
+    };
+    let Some(import) = export
+        .imports
+        .iter_mut()
+        .find(|import| import.resource == *resource)
+    else {
+        return true;
+    };
+
+    if toplevel.set_parent(Some(&export.surface)) && !import.children.contains(&toplevel) {
+        import.children.push(toplevel);
+    }
+
+    true
+}
+
+impl<D> GlobalDispatch<ZxdgExporterV1, (), D> for XdgForeignState
+where
+    D: GlobalDispatch<ZxdgExporterV1, ()>,
+    D: Dispatch<ZxdgExporterV1, ()>,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZxdgExporterV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl<D> GlobalDispatch<ZxdgExporterV2, (), D> for XdgForeignState
+where
+    D: GlobalDispatch<ZxdgExporterV2, ()>,
+    D: Dispatch<ZxdgExporterV2, ()>,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZxdgExporterV2>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl<D> GlobalDispatch<ZxdgImporterV1, (), D> for XdgForeignState
+where
+    D: GlobalDispatch<ZxdgImporterV1, ()>,
+    D: Dispatch<ZxdgImporterV1, ()>,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZxdgImporterV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl<D> GlobalDispatch<ZxdgImporterV2, (), D> for XdgForeignState
+where
+    D: GlobalDispatch<ZxdgImporterV2, ()>,
+    D: Dispatch<ZxdgImporterV2, ()>,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZxdgImporterV2>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl<D> Dispatch<ZxdgExporterV1, (), D> for XdgForeignState
+where
+    D: Dispatch<ZxdgExporterV1, ()>,
+    D: Dispatch<ZxdgExportedV1, XdgForeignHandle>,
+    D: XdgForeignHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _resource: &ZxdgExporterV1,
+        request: <ZxdgExporterV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zxdg_exporter_v1::Request::Export { id, surface } => {
+                // v1 has no error for non-toplevel surfaces, so they just never get a handle.
+                let handle = export(state, surface);
+                let exported = data_init.init(id, XdgForeignHandle(handle.clone()));
+                if let Some(handle) = handle {
+                    exported.handle(handle);
+                }
+            }
+            zxdg_exporter_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZxdgExporterV2, (), D> for XdgForeignState
+where
+    D: Dispatch<ZxdgExporterV2, ()>,
+    D: Dispatch<ZxdgExportedV2, XdgForeignHandle>,
+    D: XdgForeignHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &ZxdgExporterV2,
+        request: <ZxdgExporterV2 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zxdg_exporter_v2::Request::ExportToplevel { id, surface } => {
+                let handle = export(state, surface);
+                let exported = data_init.init(id, XdgForeignHandle(handle.clone()));
+                match handle {
+                    Some(handle) => exported.handle(handle),
+                    None => resource.post_error(
+                        zxdg_exporter_v2::Error::InvalidSurface,
+                        "surface must be an xdg_toplevel",
+                    ),
+                }
+            }
+            zxdg_exporter_v2::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZxdgExportedV1, XdgForeignHandle, D> for XdgForeignState
+where
+    D: Dispatch<ZxdgExportedV1, XdgForeignHandle>,
+    D: XdgForeignHandler,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &ZxdgExportedV1,
+        _request: <ZxdgExportedV1 as Resource>::Request,
+        _data: &XdgForeignHandle,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+    }
+
+    fn destroyed(
+        state: &mut D,
+        _client: ClientId,
+        _resource: &ZxdgExportedV1,
+        data: &XdgForeignHandle,
+    ) {
+        if let Some(handle) = &data.0 {
+            state.xdg_foreign_state().unexport(handle);
+        }
+    }
+}
+
+impl<D> Dispatch<ZxdgExportedV2, XdgForeignHandle, D> for XdgForeignState
+where
+    D: Dispatch<ZxdgExportedV2, XdgForeignHandle>,
+    D: XdgForeignHandler,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &ZxdgExportedV2,
+        _request: <ZxdgExportedV2 as Resource>::Request,
+        _data: &XdgForeignHandle,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+    }
+
+    fn destroyed(
+        state: &mut D,
+        _client: ClientId,
+        _resource: &ZxdgExportedV2,
+        data: &XdgForeignHandle,
+    ) {
+        if let Some(handle) = &data.0 {
+            state.xdg_foreign_state().unexport(handle);
+        }
+    }
+}
+
+impl<D> Dispatch<ZxdgImporterV1, (), D> for XdgForeignState
+where
+    D: Dispatch<ZxdgImporterV1, ()>,
+    D: Dispatch<ZxdgImportedV1, XdgForeignHandle>,
+    D: XdgForeignHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _resource: &ZxdgImporterV1,
+        request: <ZxdgImporterV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zxdg_importer_v1::Request::Import { id, handle } => {
+                let imported = data_init.init(id, XdgForeignHandle(Some(handle.clone())));
+                let resource = Imported::V1(imported.clone());
+                if !state.xdg_foreign_state().import(&handle, resource) {
+                    imported.destroyed();
+                }
+            }
+            zxdg_importer_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZxdgImporterV2, (), D> for XdgForeignState
+where
+    D: Dispatch<ZxdgImporterV2, ()>,
+    D: Dispatch<ZxdgImportedV2, XdgForeignHandle>,
+    D: XdgForeignHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _resource: &ZxdgImporterV2,
+        request: <ZxdgImporterV2 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zxdg_importer_v2::Request::ImportToplevel { id, handle } => {
+                let imported = data_init.init(id, XdgForeignHandle(Some(handle.clone())));
+                let resource = Imported::V2(imported.clone());
+                if !state.xdg_foreign_state().import(&handle, resource) {
+                    imported.destroyed();
+                }
+            }
+            zxdg_importer_v2::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZxdgImportedV1, XdgForeignHandle, D> for XdgForeignState
+where
+    D: Dispatch<ZxdgImportedV1, XdgForeignHandle>,
+    D: XdgForeignHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &ZxdgImportedV1,
+        request: <ZxdgImportedV1 as Resource>::Request,
+        data: &XdgForeignHandle,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zxdg_imported_v1::Request::SetParentOf { surface } => {
+                let imported = Imported::V1(resource.clone());
+                if !set_parent_of(state, data, &imported, surface) {
+                    debug!("ignoring xdg-foreign v1 set_parent_of for a non-toplevel surface");
+                }
+            }
+            zxdg_imported_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut D,
+        _client: ClientId,
+        resource: &ZxdgImportedV1,
+        data: &XdgForeignHandle,
+    ) {
+        if let Some(handle) = &data.0 {
+            let imported = Imported::V1(resource.clone());
+            state.xdg_foreign_state().unimport(handle, &imported);
+        }
+    }
+}
+
+impl<D> Dispatch<ZxdgImportedV2, XdgForeignHandle, D> for XdgForeignState
+where
+    D: Dispatch<ZxdgImportedV2, XdgForeignHandle>,
+    D: XdgForeignHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &ZxdgImportedV2,
+        request: <ZxdgImportedV2 as Resource>::Request,
+        data: &XdgForeignHandle,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zxdg_imported_v2::Request::SetParentOf { surface } => {
+                let imported = Imported::V2(resource.clone());
+                if !set_parent_of(state, data, &imported, surface) {
+                    resource.post_error(
+                        zxdg_imported_v2::Error::InvalidSurface,
+                        "surface must be an xdg_toplevel",
+                    );
+                }
+            }
+            zxdg_imported_v2::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut D,
+        _client: ClientId,
+        resource: &ZxdgImportedV2,
+        data: &XdgForeignHandle,
+    ) {
+        if let Some(handle) = &data.0 {
+            let imported = Imported::V2(resource.clone());
+            state.xdg_foreign_state().unimport(handle, &imported);
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! delegate_xdg_foreign {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::xdg::foreign::zv1::server::zxdg_exporter_v1::ZxdgExporterV1: ()
+        ] => $crate::protocols::xdg_foreign::XdgForeignState);
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::xdg::foreign::zv2::server::zxdg_exporter_v2::ZxdgExporterV2: ()
+        ] => $crate::protocols::xdg_foreign::XdgForeignState);
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::xdg::foreign::zv1::server::zxdg_importer_v1::ZxdgImporterV1: ()
+        ] => $crate::protocols::xdg_foreign::XdgForeignState);
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::xdg::foreign::zv2::server::zxdg_importer_v2::ZxdgImporterV2: ()
+        ] => $crate::protocols::xdg_foreign::XdgForeignState);
+
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::xdg::foreign::zv1::server::zxdg_exporter_v1::ZxdgExporterV1: ()
+        ] => $crate::protocols::xdg_foreign::XdgForeignState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::xdg::foreign::zv2::server::zxdg_exporter_v2::ZxdgExporterV2: ()
+        ] => $crate::protocols::xdg_foreign::XdgForeignState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::xdg::foreign::zv1::server::zxdg_importer_v1::ZxdgImporterV1: ()
+        ] => $crate::protocols::xdg_foreign::XdgForeignState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::xdg::foreign::zv2::server::zxdg_importer_v2::ZxdgImporterV2: ()
+        ] => $crate::protocols::xdg_foreign::XdgForeignState);
+
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::xdg::foreign::zv1::server::zxdg_exported_v1::ZxdgExportedV1: $crate::protocols::xdg_foreign::XdgForeignHandle
+        ] => $crate::protocols::xdg_foreign::XdgForeignState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::xdg::foreign::zv2::server::zxdg_exported_v2::ZxdgExportedV2: $crate::protocols::xdg_foreign::XdgForeignHandle
+        ] => $crate::protocols::xdg_foreign::XdgForeignState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::xdg::foreign::zv1::server::zxdg_imported_v1::ZxdgImportedV1: $crate::protocols::xdg_foreign::XdgForeignHandle
+        ] => $crate::protocols::xdg_foreign::XdgForeignState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::xdg::foreign::zv2::server::zxdg_imported_v2::ZxdgImportedV2: $crate::protocols::xdg_foreign::XdgForeignHandle
+        ] => $crate::protocols::xdg_foreign::XdgForeignState);
+    };
+}
@@ -1,16 +1,18 @@
 use std::cell::{Cell, RefCell};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::Cursor;
 use std::mem;
 use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
 use std::time::Duration;
 
 use anyhow::Context as _;
 use pipewire::context::Context;
 use pipewire::core::Core;
 use pipewire::main_loop::MainLoop;
+use pipewire::node::{Node, NodeListener, NodeState};
 use pipewire::properties::Properties;
+use pipewire::registry::{self, Registry};
 use pipewire::spa::buffer::DataType;
 use pipewire::spa::param::format::{FormatProperties, MediaSubtype, MediaType};
 use pipewire::spa::param::format_utils::parse_format;
@@ -23,6 +25,7 @@ use pipewire::spa::utils::{
     Choice, ChoiceEnum, ChoiceFlags, Direction, Fraction, Rectangle, SpaTypes,
 };
 use pipewire::stream::{Stream, StreamFlags, StreamListener, StreamState};
+use pipewire::types::ObjectType;
 use smithay::backend::allocator::dmabuf::{AsDmabuf, Dmabuf};
 use smithay::backend::allocator::gbm::{GbmBuffer, GbmBufferFlags, GbmDevice};
 use smithay::backend::allocator::Fourcc;
@@ -44,6 +47,27 @@ pub struct PipeWire {
     _context: Context,
     pub core: Core,
     to_niri: calloop::channel::Sender<PwToNiri>,
+    playback: Option<PlaybackStreams>,
+}
+
+/// Audio playback streams of all apps, used to inhibit idle while media is playing.
+struct PlaybackStreams {
+    _registry: Rc<Registry>,
+    _listener: registry::Listener,
+    streams: Rc<RefCell<HashMap<u32, PlaybackStream>>>,
+}
+
+struct PlaybackStream {
+    _node: Node,
+    _listener: NodeListener,
+    info: Rc<Cell<PlaybackStreamInfo>>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct PlaybackStreamInfo {
+    /// Process that owns the stream.
+    pid: Option<i32>,
+    is_running: bool,
 }
 
 pub enum PwToNiri {
@@ -88,6 +112,80 @@ pub enum CastTarget {
     Window { id: u64 },
 }
 
+impl PlaybackStreams {
+    fn new(core: &Core) -> anyhow::Result<Self> {
+        let registry = Rc::new(core.get_registry().context("error getting registry")?);
+        let streams = Rc::new(RefCell::new(HashMap::new()));
+
+        let listener = registry
+            .add_listener_local()
+            .global({
+                let registry = Rc::downgrade(&registry);
+                let streams = streams.clone();
+                move |global| {
+                    if global.type_ != ObjectType::Node {
+                        return;
+                    }
+
+                    let media_class = global.props.and_then(|props| props.get("media.class"));
+                    if media_class != Some("Stream/Output/Audio") {
+                        return;
+                    }
+
+                    let Some(registry) = Weak::upgrade(&registry) else {
+                        return;
+                    };
+                    let node: Node = match registry.bind(global) {
+                        Ok(node) => node,
+                        Err(err) => {
+                            warn!("error binding PipeWire node: {err:?}");
+                            return;
+                        }
+                    };
+
+                    let info = Rc::new(Cell::new(PlaybackStreamInfo::default()));
+                    let listener = node
+                        .add_listener_local()
+                        .info({
+                            let info = info.clone();
+                            move |node_info| {
+                                let mut new_info = info.get();
+                                new_info.is_running =
+                                    matches!(node_info.state(), NodeState::Running);
+                                if let Some(props) = node_info.props() {
+                                    if let Some(pid) = props.get("application.process.id") {
+                                        new_info.pid = pid.parse().ok();
+                                    }
+                                }
+                                info.set(new_info);
+                            }
+                        })
+                        .register();
+
+                    let stream = PlaybackStream {
+                        _node: node,
+                        _listener: listener,
+                        info,
+                    };
+                    streams.borrow_mut().insert(global.id, stream);
+                }
+            })
+            .global_remove({
+                let streams = streams.clone();
+                move |id| {
+                    streams.borrow_mut().remove(&id);
+                }
+            })
+            .register();
+
+        Ok(Self {
+            _registry: registry,
+            _listener: listener,
+            streams,
+        })
+    }
+}
+
 macro_rules! make_params {
     ($params:ident, $size:expr, $refresh:expr, $alpha:expr) => {
         let mut b1 = Vec::new();
@@ -146,13 +244,38 @@ impl PipeWire {
             })
             .unwrap();
 
+        let playback = match PlaybackStreams::new(&core) {
+            Ok(playback) => Some(playback),
+            Err(err) => {
+                warn!("error tracking PipeWire playback streams: {err:?}");
+                None
+            }
+        };
+
         Ok(Self {
             _context: context,
             core,
             to_niri,
+            playback,
         })
     }
 
+    /// Returns the processes that are currently playing audio.
+    pub fn playing_pids(&self) -> HashSet<i32> {
+        let Some(playback) = &self.playback else {
+            return HashSet::new();
+        };
+
+        playback
+            .streams
+            .borrow()
+            .values()
+            .map(|stream| stream.info.get())
+            .filter(|info| info.is_running)
+            .filter_map(|info| info.pid)
+            .collect()
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn start_cast(
         &self,
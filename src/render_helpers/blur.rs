@@ -0,0 +1,84 @@
+use anyhow::{bail, Context};
+use smithay::backend::allocator::Fourcc;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexProgram, GlesTexture, Uniform};
+use smithay::backend::renderer::{Bind, Frame, Offscreen, Renderer, Texture};
+use smithay::utils::{Buffer, Physical, Rectangle, Size, Transform};
+
+use super::shaders::Shaders;
+
+/// Blurs a texture with the dual Kawase algorithm.
+///
+/// Every pass halves the texture size on the way down and doubles it on the way up, so each
+/// additional pass widens the blur considerably. `offset` spreads the samples of every pass.
+pub fn blur(
+    renderer: &mut GlesRenderer,
+    texture: GlesTexture,
+    passes: u8,
+    offset: f32,
+) -> anyhow::Result<GlesTexture> {
+    let _span = tracy_client::span!("blur");
+
+    let shaders = Shaders::get(renderer);
+    let (Some(down), Some(up)) = (shaders.blur_down.clone(), shaders.blur_up.clone()) else {
+        bail!("blur shaders are missing");
+    };
+
+    let mut sizes = vec![texture.size()];
+    for _ in 0..passes {
+        let prev = sizes[sizes.len() - 1];
+        sizes.push(Size::from((
+            i32::max(1, prev.w / 2),
+            i32::max(1, prev.h / 2),
+        )));
+    }
+
+    let mut texture = texture;
+    for &size in &sizes[1..] {
+        texture = render_pass(renderer, &texture, size, &down, offset)?;
+    }
+    for &size in sizes[..sizes.len() - 1].iter().rev() {
+        texture = render_pass(renderer, &texture, size, &up, offset)?;
+    }
+
+    Ok(texture)
+}
+
+fn render_pass(
+    renderer: &mut GlesRenderer,
+    texture: &GlesTexture,
+    size: Size<i32, Buffer>,
+    program: &GlesTexProgram,
+    offset: f32,
+) -> anyhow::Result<GlesTexture> {
+    let target: GlesTexture = renderer
+        .create_buffer(Fourcc::Abgr8888, size)
+        .context("error creating texture")?;
+    renderer
+        .bind(target.clone())
+        .context("error binding texture")?;
+
+    let src_size = texture.size();
+    let half_pixel = (0.5 / src_size.w as f32, 0.5 / src_size.h as f32);
+    let src = Rectangle::from_loc_and_size((0., 0.), src_size.to_f64());
+
+    let dst_size = Size::<i32, Physical>::from((size.w, size.h));
+    let dst = Rectangle::from_loc_and_size((0, 0), dst_size);
+
+    let mut frame = renderer
+        .render(dst_size, Transform::Normal)
+        .context("error starting frame")?;
+
+    frame.override_default_tex_program(
+        program.clone(),
+        vec![
+            Uniform::new("half_pixel", half_pixel),
+            Uniform::new("offset", offset),
+        ],
+    );
+    let res = frame.render_texture_from_to(texture, src, dst, &[dst], &[], Transform::Normal, 1.);
+    frame.clear_tex_program_override();
+    res.context("error rendering blur pass")?;
+
+    frame.finish().context("error finishing frame")?;
+    Ok(target)
+}
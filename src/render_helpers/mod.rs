@@ -17,6 +17,7 @@ use solid_color::{SolidColorBuffer, SolidColorRenderElement};
 use self::primary_gpu_texture::PrimaryGpuTextureRenderElement;
 use self::texture::{TextureBuffer, TextureRenderElement};
 
+pub mod blur;
 pub mod border;
 pub mod clipped_surface;
 pub mod damage;
@@ -33,7 +34,9 @@ pub mod shaders;
 pub mod snapshot;
 pub mod solid_color;
 pub mod surface;
+pub mod text;
 pub mod texture;
+pub mod texture_cache;
 
 /// What we're rendering for.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
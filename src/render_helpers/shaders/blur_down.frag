@@ -0,0 +1,38 @@
+#version 100
+
+//_DEFINES_
+
+#if defined(EXTERNAL)
+#extension GL_OES_EGL_image_external : require
+#endif
+
+precision mediump float;
+#if defined(EXTERNAL)
+uniform samplerExternalOES tex;
+#else
+uniform sampler2D tex;
+#endif
+
+uniform float alpha;
+varying vec2 v_coords;
+
+#if defined(DEBUG_FLAGS)
+uniform float tint;
+#endif
+
+uniform vec2 half_pixel;
+uniform float offset;
+
+// Dual Kawase downsampling: the center and four diagonal samples.
+void main() {
+    vec2 uv = v_coords;
+    vec2 step = half_pixel * offset;
+
+    vec4 sum = texture2D(tex, uv) * 4.0;
+    sum += texture2D(tex, uv - step);
+    sum += texture2D(tex, uv + step);
+    sum += texture2D(tex, uv + vec2(step.x, -step.y));
+    sum += texture2D(tex, uv - vec2(step.x, -step.y));
+
+    gl_FragColor = sum / 8.0 * alpha;
+}
@@ -0,0 +1,41 @@
+#version 100
+
+//_DEFINES_
+
+#if defined(EXTERNAL)
+#extension GL_OES_EGL_image_external : require
+#endif
+
+precision mediump float;
+#if defined(EXTERNAL)
+uniform samplerExternalOES tex;
+#else
+uniform sampler2D tex;
+#endif
+
+uniform float alpha;
+varying vec2 v_coords;
+
+#if defined(DEBUG_FLAGS)
+uniform float tint;
+#endif
+
+uniform vec2 half_pixel;
+uniform float offset;
+
+// Dual Kawase upsampling: four edge and four diagonal samples, the diagonal ones weighted twice.
+void main() {
+    vec2 uv = v_coords;
+    vec2 step = half_pixel * offset;
+
+    vec4 sum = texture2D(tex, uv + vec2(-step.x * 2.0, 0.0));
+    sum += texture2D(tex, uv + vec2(-step.x, step.y)) * 2.0;
+    sum += texture2D(tex, uv + vec2(0.0, step.y * 2.0));
+    sum += texture2D(tex, uv + vec2(step.x, step.y)) * 2.0;
+    sum += texture2D(tex, uv + vec2(step.x * 2.0, 0.0));
+    sum += texture2D(tex, uv + vec2(step.x, -step.y)) * 2.0;
+    sum += texture2D(tex, uv + vec2(0.0, -step.y * 2.0));
+    sum += texture2D(tex, uv + vec2(-step.x, -step.y)) * 2.0;
+
+    gl_FragColor = sum / 12.0 * alpha;
+}
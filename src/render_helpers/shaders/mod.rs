@@ -12,6 +12,8 @@ use super::shader_element::ShaderProgram;
 pub struct Shaders {
     pub border: Option<ShaderProgram>,
     pub clipped_surface: Option<GlesTexProgram>,
+    pub blur_down: Option<GlesTexProgram>,
+    pub blur_up: Option<GlesTexProgram>,
     pub resize: Option<ShaderProgram>,
     pub custom_resize: RefCell<Option<ShaderProgram>>,
     pub custom_close: RefCell<Option<ShaderProgram>>,
@@ -66,6 +68,18 @@ impl Shaders {
             })
             .ok();
 
+        let blur_down = compile_blur_program(renderer, include_str!("blur_down.frag"))
+            .map_err(|err| {
+                warn!("error compiling blur downsample shader: {err:?}");
+            })
+            .ok();
+
+        let blur_up = compile_blur_program(renderer, include_str!("blur_up.frag"))
+            .map_err(|err| {
+                warn!("error compiling blur upsample shader: {err:?}");
+            })
+            .ok();
+
         let resize = compile_resize_program(renderer, include_str!("resize.frag"))
             .map_err(|err| {
                 warn!("error compiling resize shader: {err:?}");
@@ -75,6 +89,8 @@ impl Shaders {
         Self {
             border,
             clipped_surface,
+            blur_down,
+            blur_up,
             resize,
             custom_resize: RefCell::new(None),
             custom_close: RefCell::new(None),
@@ -138,6 +154,19 @@ pub fn init(renderer: &mut GlesRenderer) {
     }
 }
 
+fn compile_blur_program(
+    renderer: &mut GlesRenderer,
+    src: &str,
+) -> Result<GlesTexProgram, GlesError> {
+    renderer.compile_custom_texture_shader(
+        src,
+        &[
+            UniformName::new("half_pixel", UniformType::_2f),
+            UniformName::new("offset", UniformType::_1f),
+        ],
+    )
+}
+
 fn compile_resize_program(
     renderer: &mut GlesRenderer,
     src: &str,
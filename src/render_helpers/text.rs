@@ -0,0 +1,133 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use ordered_float::NotNan;
+use pangocairo::cairo::{self, ImageSurface};
+use pangocairo::pango::{Alignment, FontDescription};
+use smithay::reexports::gbm::Format as Fourcc;
+use smithay::utils::Transform;
+
+use super::memory::MemoryBuffer;
+use crate::utils::to_physical_precise_round;
+
+/// Looks of a text box: Pango markup on a solid background, optionally with a border.
+///
+/// All sizes are in logical pixels and get scaled to the output scale when rendering.
+#[derive(Debug, Clone, Copy)]
+pub struct TextBoxStyle {
+    /// Pango font description, with the size in pixels.
+    pub font: &'static str,
+    pub alignment: Alignment,
+    /// Space between the text and the edges of the box.
+    pub padding: i32,
+    /// Border width, or 0 for no border.
+    pub border: i32,
+    pub background: [f64; 4],
+    pub text_color: [f64; 3],
+    pub border_color: [f64; 3],
+}
+
+/// Text boxes rendered at every output scale they were needed at.
+#[derive(Default)]
+pub struct TextCache {
+    buffers: RefCell<HashMap<NotNan<f64>, Option<MemoryBuffer>>>,
+}
+
+impl TextCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the buffer for this scale, rendering it on first use.
+    ///
+    /// Rendering errors are remembered, so a failing render doesn't repeat every frame.
+    pub fn get(
+        &self,
+        scale: f64,
+        render: impl FnOnce(f64) -> anyhow::Result<MemoryBuffer>,
+    ) -> Option<MemoryBuffer> {
+        let mut buffers = self.buffers.borrow_mut();
+        buffers
+            .entry(NotNan::new(scale).unwrap())
+            .or_insert_with(|| match render(scale) {
+                Ok(buffer) => Some(buffer),
+                Err(err) => {
+                    warn!("error rendering text: {err:?}");
+                    None
+                }
+            })
+            .clone()
+    }
+
+    /// Drops all rendered buffers, for example after the text changed.
+    pub fn clear(&self) {
+        self.buffers.borrow_mut().clear();
+    }
+}
+
+/// Returns the font with its pixel size scaled to the output scale.
+pub fn scaled_font(font: &str, scale: f64) -> FontDescription {
+    let mut font = FontDescription::from_string(font);
+    font.set_absolute_size(to_physical_precise_round(scale, font.size()));
+    font
+}
+
+/// Renders Pango markup into a text box at the output scale.
+pub fn render_text_box(
+    markup: &str,
+    style: &TextBoxStyle,
+    scale: f64,
+) -> anyhow::Result<MemoryBuffer> {
+    let _span = tracy_client::span!("text::render_text_box");
+
+    let padding: i32 = to_physical_precise_round(scale, style.padding);
+    let font = scaled_font(style.font, scale);
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, 0, 0)?;
+    let cr = cairo::Context::new(&surface)?;
+    let layout = pangocairo::functions::create_layout(&cr);
+    layout.set_font_description(Some(&font));
+    layout.set_alignment(style.alignment);
+    layout.set_markup(markup);
+
+    let (mut width, mut height) = layout.pixel_size();
+    width += padding * 2;
+    height += padding * 2;
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, width, height)?;
+    let cr = cairo::Context::new(&surface)?;
+    let [r, g, b, a] = style.background;
+    cr.set_source_rgba(r, g, b, a);
+    cr.paint()?;
+
+    cr.move_to(padding.into(), padding.into());
+    let layout = pangocairo::functions::create_layout(&cr);
+    layout.set_font_description(Some(&font));
+    layout.set_alignment(style.alignment);
+    layout.set_markup(markup);
+
+    let [r, g, b] = style.text_color;
+    cr.set_source_rgb(r, g, b);
+    pangocairo::functions::show_layout(&cr, &layout);
+
+    if style.border > 0 {
+        cr.rectangle(0., 0., width.into(), height.into());
+        let [r, g, b] = style.border_color;
+        cr.set_source_rgb(r, g, b);
+        // Keep the border width even to avoid blurry edges.
+        cr.set_line_width((f64::from(style.border) / 2. * scale).round() * 2.);
+        cr.stroke()?;
+    }
+    drop(cr);
+
+    let data = surface.take_data().unwrap();
+    let buffer = MemoryBuffer::new(
+        data.to_vec(),
+        Fourcc::Argb8888,
+        (width, height),
+        scale,
+        Transform::Normal,
+    );
+
+    Ok(buffer)
+}
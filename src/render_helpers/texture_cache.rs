@@ -0,0 +1,165 @@
+use smithay::backend::renderer::gles::GlesTexture;
+use smithay::backend::renderer::Texture;
+
+/// Default memory budget for the texture caches, in MiB.
+pub const DEFAULT_BUDGET_MIB: u32 = 512;
+
+/// Keeps niri's own cached textures within a memory budget.
+///
+/// Under memory pressure the caches are evicted cheapest to lose first: the UI textures that are
+/// re-rendered on demand, then the textures of closing window animations, and finally the
+/// screenshot UI freezes of the outputs that don't hold the selection.
+#[derive(Debug)]
+pub struct TextureCache {
+    /// Budget in bytes.
+    budget: u64,
+    /// Number of times some cache was evicted.
+    evictions: u64,
+    /// Whether the usage was over the budget at the last check.
+    is_over_budget: bool,
+}
+
+/// Estimated memory used by the texture caches, in bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TextureCacheUsage {
+    /// Hidden UI elements, like the hotkey overlay.
+    pub ui: u64,
+    pub closing_windows: u64,
+    /// Screenshot UI freezes of the outputs without the selection.
+    pub screenshot_ui_inactive: u64,
+    /// All screenshot UI freezes, including the inactive ones.
+    pub screenshot_ui: u64,
+}
+
+/// Caches to evict to get back within the budget.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Eviction {
+    pub ui: bool,
+    pub closing_windows: bool,
+    pub screenshot_ui_inactive: bool,
+}
+
+impl TextureCache {
+    pub fn new(budget_mib: u32) -> Self {
+        Self {
+            budget: u64::from(budget_mib) << 20,
+            evictions: 0,
+            is_over_budget: false,
+        }
+    }
+
+    pub fn set_budget(&mut self, budget_mib: u32) {
+        self.budget = u64::from(budget_mib) << 20;
+    }
+
+    pub fn budget(&self) -> u64 {
+        self.budget
+    }
+
+    pub fn evictions(&self) -> u64 {
+        self.evictions
+    }
+
+    /// Picks the caches to evict so that the usage fits into the budget.
+    pub fn plan(&mut self, usage: TextureCacheUsage) -> Eviction {
+        let mut eviction = Eviction::default();
+
+        let mut total = usage.ui + usage.closing_windows + usage.screenshot_ui;
+        if total <= self.budget {
+            self.is_over_budget = false;
+            return eviction;
+        }
+
+        // Evict once when going over the budget. Evicting again on every check while staying
+        // over it (say, because of a large screenshot UI freeze) would throw away every new
+        // closing window animation right away.
+        if self.is_over_budget {
+            return eviction;
+        }
+        self.is_over_budget = true;
+
+        let steps = [
+            (usage.ui, &mut eviction.ui),
+            (usage.closing_windows, &mut eviction.closing_windows),
+            (
+                usage.screenshot_ui_inactive,
+                &mut eviction.screenshot_ui_inactive,
+            ),
+        ];
+        for (bytes, evict) in steps {
+            if total <= self.budget {
+                break;
+            }
+
+            if bytes > 0 {
+                *evict = true;
+                total -= bytes;
+                self.evictions += 1;
+            }
+        }
+
+        eviction
+    }
+}
+
+/// Estimates the memory used by a texture, assuming 4 bytes per pixel.
+pub fn texture_bytes(texture: &GlesTexture) -> u64 {
+    let size = texture.size();
+    u64::from(size.w.unsigned_abs()) * u64::from(size.h.unsigned_abs()) * 4
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_cheapest_first() {
+        let mut cache = TextureCache::new(1);
+        let mib = 1 << 20;
+
+        let usage = TextureCacheUsage {
+            ui: mib / 2,
+            ..Default::default()
+        };
+        assert_eq!(cache.plan(usage), Eviction::default());
+
+        let usage = TextureCacheUsage {
+            ui: mib,
+            closing_windows: mib,
+            ..Default::default()
+        };
+        let eviction = cache.plan(usage);
+        assert!(eviction.ui);
+        assert!(!eviction.closing_windows);
+        assert_eq!(cache.evictions(), 1);
+
+        // Staying over the budget doesn't evict again.
+        assert_eq!(cache.plan(usage), Eviction::default());
+        assert_eq!(cache.evictions(), 1);
+
+        // Going back within the budget allows the next eviction.
+        assert_eq!(
+            cache.plan(TextureCacheUsage::default()),
+            Eviction::default()
+        );
+
+        let usage = TextureCacheUsage {
+            ui: mib,
+            closing_windows: mib,
+            screenshot_ui_inactive: mib,
+            screenshot_ui: mib * 2,
+        };
+        let eviction = cache.plan(usage);
+        assert!(eviction.ui);
+        assert!(eviction.closing_windows);
+        assert!(eviction.screenshot_ui_inactive);
+        assert_eq!(cache.evictions(), 4);
+
+        // The active screenshot freeze is never evicted.
+        let usage = TextureCacheUsage {
+            screenshot_ui: mib * 2,
+            ..Default::default()
+        };
+        assert_eq!(cache.plan(usage), Eviction::default());
+    }
+}
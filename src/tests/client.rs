@@ -0,0 +1,366 @@
+//! Minimal Wayland client for the integration tests.
+//!
+//! It speaks just enough xdg-shell to map windows with shm buffers, and it never blocks: all
+//! reading and writing happens in [`Client::dispatch`], so the client and the compositor can be
+//! driven from the same thread.
+
+use std::ffi::CStr;
+use std::fs::File;
+use std::io;
+use std::os::fd::{AsFd, FromRawFd, OwnedFd};
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use wayland_client::backend::WaylandError;
+use wayland_client::protocol::wl_buffer::{self, WlBuffer};
+use wayland_client::protocol::wl_callback::{self, WlCallback};
+use wayland_client::protocol::wl_compositor::WlCompositor;
+use wayland_client::protocol::wl_registry::{self, WlRegistry};
+use wayland_client::protocol::wl_shm::{self, WlShm};
+use wayland_client::protocol::wl_shm_pool::WlShmPool;
+use wayland_client::protocol::wl_surface::WlSurface;
+use wayland_client::{delegate_noop, Connection, Dispatch, EventQueue, QueueHandle};
+use wayland_protocols::xdg::shell::client::xdg_surface::{self, XdgSurface};
+use wayland_protocols::xdg::shell::client::xdg_toplevel::{self, XdgToplevel};
+use wayland_protocols::xdg::shell::client::xdg_wm_base::{self, XdgWmBase};
+
+pub struct Client {
+    connection: Connection,
+    event_queue: EventQueue<State>,
+    qh: QueueHandle<State>,
+    state: State,
+}
+
+#[derive(Default)]
+struct State {
+    compositor: Option<WlCompositor>,
+    xdg_wm_base: Option<XdgWmBase>,
+    shm: Option<WlShm>,
+    windows: Vec<Window>,
+}
+
+pub struct Window {
+    pub surface: WlSurface,
+    pub xdg_surface: XdgSurface,
+    pub xdg_toplevel: XdgToplevel,
+    /// Configures received so far, oldest first, along with their serials.
+    pub configures: Vec<(u32, Configure)>,
+    /// Whether the compositor asked the window to close.
+    pub close_requested: bool,
+    pending_configure: Configure,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Configure {
+    pub size: (i32, i32),
+    pub states: Vec<xdg_toplevel::State>,
+}
+
+/// Handle to a `wl_display.sync` request.
+pub struct SyncCallback(Arc<AtomicBool>);
+
+impl Client {
+    pub fn new(stream: UnixStream) -> Self {
+        let connection = Connection::from_socket(stream).unwrap();
+        let event_queue = connection.new_event_queue();
+        let qh = event_queue.handle();
+
+        connection.display().get_registry(&qh, ());
+
+        Self {
+            connection,
+            event_queue,
+            qh,
+            state: State::default(),
+        }
+    }
+
+    /// Flushes the requests, then reads and handles all events that are already available.
+    pub fn dispatch(&mut self) {
+        self.event_queue.flush().unwrap();
+
+        if let Some(guard) = self.event_queue.prepare_read() {
+            match guard.read() {
+                Ok(_) => (),
+                Err(WaylandError::Io(err)) if err.kind() == io::ErrorKind::WouldBlock => (),
+                Err(err) => panic!("error reading Wayland events: {err:?}"),
+            }
+        }
+
+        self.event_queue.dispatch_pending(&mut self.state).unwrap();
+        self.event_queue.flush().unwrap();
+    }
+
+    /// Sends a `wl_display.sync` that is done once the compositor processed all prior requests.
+    pub fn send_sync(&self) -> SyncCallback {
+        let done = Arc::new(AtomicBool::new(false));
+        self.connection.display().sync(&self.qh, done.clone());
+        SyncCallback(done)
+    }
+
+    pub fn has_globals(&self) -> bool {
+        let state = &self.state;
+        state.compositor.is_some() && state.xdg_wm_base.is_some() && state.shm.is_some()
+    }
+
+    /// Creates a toplevel with no role-specific state and without committing it.
+    pub fn create_window(&mut self) -> &mut Window {
+        let compositor = self.state.compositor.as_ref().expect("no wl_compositor");
+        let xdg_wm_base = self.state.xdg_wm_base.as_ref().expect("no xdg_wm_base");
+
+        let surface = compositor.create_surface(&self.qh, ());
+        let xdg_surface = xdg_wm_base.get_xdg_surface(&surface, &self.qh, ());
+        let xdg_toplevel = xdg_surface.get_toplevel(&self.qh, ());
+
+        self.state.windows.push(Window {
+            surface,
+            xdg_surface,
+            xdg_toplevel,
+            configures: Vec::new(),
+            close_requested: false,
+            pending_configure: Configure::default(),
+        });
+        self.state.windows.last_mut().unwrap()
+    }
+
+    pub fn window(&mut self, surface: &WlSurface) -> &mut Window {
+        self.state
+            .windows
+            .iter_mut()
+            .find(|w| w.surface == *surface)
+            .unwrap()
+    }
+
+    /// Attaches a fresh buffer of the last configured size to the window.
+    ///
+    /// If the compositor left the size up to the client, a 100×100 buffer is used.
+    pub fn attach_new_buffer(&mut self, surface: &WlSurface) {
+        let window = self.window(surface);
+        let (mut w, mut h) = window.last_configure().map_or((0, 0), |c| c.size);
+        if w == 0 {
+            w = 100;
+        }
+        if h == 0 {
+            h = 100;
+        }
+
+        self.attach_buffer(surface, (w, h));
+    }
+
+    /// Attaches a fresh buffer of the given size to the window, regardless of the configure.
+    pub fn attach_buffer(&mut self, surface: &WlSurface, (w, h): (i32, i32)) {
+        let shm = self.state.shm.clone().expect("no wl_shm");
+        let buffer = create_buffer(&shm, &self.qh, w, h);
+        self.window(surface).surface.attach(Some(&buffer), 0, 0);
+    }
+
+    /// Attaches a null buffer, which unmaps the window on the next commit.
+    pub fn attach_null_buffer(&mut self, surface: &WlSurface) {
+        self.window(surface).surface.attach(None, 0, 0);
+    }
+
+    /// Destroys the window along with its surface.
+    pub fn destroy_window(&mut self, surface: &WlSurface) {
+        let idx = self
+            .state
+            .windows
+            .iter()
+            .position(|w| w.surface == *surface)
+            .unwrap();
+        let window = self.state.windows.remove(idx);
+        window.xdg_toplevel.destroy();
+        window.xdg_surface.destroy();
+        window.surface.destroy();
+    }
+}
+
+impl Window {
+    pub fn last_configure(&self) -> Option<&Configure> {
+        self.configures.last().map(|(_, configure)| configure)
+    }
+
+    pub fn ack_last(&self) {
+        let (serial, _) = self.configures.last().expect("no configure to ack");
+        self.xdg_surface.ack_configure(*serial);
+    }
+
+    pub fn commit(&self) {
+        self.surface.commit();
+    }
+
+    pub fn ack_last_and_commit(&self) {
+        self.ack_last();
+        self.commit();
+    }
+}
+
+impl SyncCallback {
+    pub fn is_done(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+fn create_buffer(shm: &WlShm, qh: &QueueHandle<State>, w: i32, h: i32) -> WlBuffer {
+    let stride = w * 4;
+    let size = stride * h;
+
+    let name = CStr::from_bytes_with_nul(b"niri-test-buffer\0").unwrap();
+    // SAFETY: the name is a valid C string, and we take ownership of the returned fd.
+    let fd = unsafe { libc::memfd_create(name.as_ptr(), libc::MFD_CLOEXEC) };
+    assert!(
+        fd >= 0,
+        "error creating memfd: {}",
+        io::Error::last_os_error()
+    );
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+    let file = File::from(fd);
+    file.set_len(size as u64).unwrap();
+
+    let pool = shm.create_pool(file.as_fd(), size, qh, ());
+    let buffer = pool.create_buffer(0, w, h, stride, wl_shm::Format::Argb8888, qh, ());
+    pool.destroy();
+    buffer
+}
+
+impl Dispatch<WlRegistry, ()> for State {
+    fn event(
+        state: &mut Self,
+        registry: &WlRegistry,
+        event: wl_registry::Event,
+        _data: &(),
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        if let wl_registry::Event::Global {
+            name,
+            interface,
+            version,
+        } = event
+        {
+            match &interface[..] {
+                "wl_compositor" => {
+                    let version = version.min(6);
+                    state.compositor = Some(registry.bind(name, version, qh, ()));
+                }
+                "xdg_wm_base" => {
+                    let version = version.min(6);
+                    state.xdg_wm_base = Some(registry.bind(name, version, qh, ()));
+                }
+                "wl_shm" => {
+                    state.shm = Some(registry.bind(name, 1, qh, ()));
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+impl Dispatch<WlCallback, Arc<AtomicBool>> for State {
+    fn event(
+        _state: &mut Self,
+        _callback: &WlCallback,
+        event: wl_callback::Event,
+        done: &Arc<AtomicBool>,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let wl_callback::Event::Done { .. } = event {
+            done.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Dispatch<XdgWmBase, ()> for State {
+    fn event(
+        _state: &mut Self,
+        xdg_wm_base: &XdgWmBase,
+        event: xdg_wm_base::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let xdg_wm_base::Event::Ping { serial } = event {
+            xdg_wm_base.pong(serial);
+        }
+    }
+}
+
+impl Dispatch<XdgSurface, ()> for State {
+    fn event(
+        state: &mut Self,
+        xdg_surface: &XdgSurface,
+        event: xdg_surface::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let xdg_surface::Event::Configure { serial } = event {
+            let window = state
+                .windows
+                .iter_mut()
+                .find(|w| w.xdg_surface == *xdg_surface)
+                .unwrap();
+            let configure = window.pending_configure.clone();
+            window.configures.push((serial, configure));
+        }
+    }
+}
+
+impl Dispatch<XdgToplevel, ()> for State {
+    fn event(
+        state: &mut Self,
+        xdg_toplevel: &XdgToplevel,
+        event: xdg_toplevel::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let window = state
+            .windows
+            .iter_mut()
+            .find(|w| w.xdg_toplevel == *xdg_toplevel)
+            .unwrap();
+
+        match event {
+            xdg_toplevel::Event::Configure {
+                width,
+                height,
+                states,
+            } => {
+                let states = states
+                    .chunks_exact(4)
+                    .map(|chunk| u32::from_ne_bytes(chunk.try_into().unwrap()))
+                    .filter_map(|state| xdg_toplevel::State::try_from(state).ok())
+                    .collect();
+                window.pending_configure = Configure {
+                    size: (width, height),
+                    states,
+                };
+            }
+            xdg_toplevel::Event::Close => window.close_requested = true,
+            _ => (),
+        }
+    }
+}
+
+impl Dispatch<WlBuffer, ()> for State {
+    fn event(
+        _state: &mut Self,
+        buffer: &WlBuffer,
+        event: wl_buffer::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Every buffer is attached just once.
+        if let wl_buffer::Event::Release = event {
+            buffer.destroy();
+        }
+    }
+}
+
+delegate_noop!(State: ignore WlCompositor);
+delegate_noop!(State: ignore WlSurface);
+delegate_noop!(State: ignore WlShm);
+delegate_noop!(State: ignore WlShmPool);
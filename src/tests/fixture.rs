@@ -0,0 +1,227 @@
+use std::cell::RefCell;
+use std::os::unix::net::UnixStream;
+use std::rc::Rc;
+use std::sync::{Arc, Once};
+use std::time::Duration;
+use std::{env, fs, thread};
+
+use niri_config::Config;
+use niri_ipc::{Request, Response, Socket};
+use smithay::reexports::calloop::EventLoop;
+use smithay::reexports::wayland_server::Display;
+use wayland_client::protocol::wl_surface::WlSurface;
+
+use super::client::Client;
+use super::input::Recording;
+use crate::backend::{Backend, Headless};
+use crate::niri::{ClientState, Niri, State};
+use crate::utils::clock::ManualClock;
+
+/// The compositor running on the headless backend, together with its test clients.
+///
+/// Everything runs on the test thread. Nothing happens by itself: call [`Fixture::dispatch`] (or
+/// the helpers built on it) to let the compositor and the clients process their messages.
+pub struct Fixture {
+    pub event_loop: EventLoop<'static, State>,
+    pub state: State,
+    clients: Vec<Client>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientId(usize);
+
+/// Upper bound on dispatch iterations when waiting for something to happen.
+const MAX_ITERATIONS: usize = 1000;
+
+impl Fixture {
+    pub fn new() -> Self {
+        Self::with_config(Config::default())
+    }
+
+    pub fn with_config(config: Config) -> Self {
+        ensure_runtime_dir();
+
+        let event_loop = EventLoop::try_new().unwrap();
+        let display = Display::new().unwrap();
+        let state = State::with_backend(
+            Rc::new(RefCell::new(config)),
+            Backend::Headless(Headless::new()),
+            event_loop.handle(),
+            event_loop.get_signal(),
+            display,
+            None,
+        );
+
+        Self {
+            event_loop,
+            state,
+            clients: Vec::new(),
+        }
+    }
+
+    pub fn niri(&mut self) -> &mut Niri {
+        &mut self.state.niri
+    }
+
+    pub fn add_output(&mut self, n: u8, size: (u16, u16)) {
+        let state = &mut self.state;
+        state
+            .backend
+            .headless()
+            .add_output(&mut state.niri, n, size);
+        self.dispatch();
+    }
+
+    /// Connects a new client and waits until it has received the globals.
+    pub fn add_client(&mut self) -> ClientId {
+        let (server, client) = UnixStream::pair().unwrap();
+
+        let data = Arc::new(ClientState {
+            compositor_state: Default::default(),
+            can_view_decoration_globals: false,
+            can_view_kde_decoration_global: false,
+            restricted: false,
+            allowed_protocols: Vec::new(),
+        });
+        self.niri()
+            .display_handle
+            .insert_client(server, data)
+            .unwrap();
+
+        let id = ClientId(self.clients.len());
+        self.clients.push(Client::new(client));
+        self.roundtrip(id);
+        assert!(self.client(id).has_globals(), "missing required globals");
+
+        id
+    }
+
+    pub fn client(&mut self, id: ClientId) -> &mut Client {
+        &mut self.clients[id.0]
+    }
+
+    /// Runs one iteration of the compositor event loop and lets every client handle its events.
+    pub fn dispatch(&mut self) {
+        self.event_loop
+            .dispatch(Duration::ZERO, &mut self.state)
+            .unwrap();
+        self.state.refresh_and_flush_clients();
+
+        for client in &mut self.clients {
+            client.dispatch();
+        }
+    }
+
+    /// Dispatches until the compositor has processed all requests that the client sent so far,
+    /// and the client has handled the replies.
+    pub fn roundtrip(&mut self, id: ClientId) {
+        let client = self.client(id);
+        client.dispatch();
+        let sync = client.send_sync();
+
+        for _ in 0..MAX_ITERATIONS {
+            self.dispatch();
+            if sync.is_done() {
+                return;
+            }
+        }
+
+        panic!("roundtrip didn't complete");
+    }
+
+    /// Creates a window, goes through the initial configure sequence, and maps it.
+    pub fn map_window(&mut self, id: ClientId) -> WlSurface {
+        let window = self.client(id).create_window();
+        let surface = window.surface.clone();
+        window.commit();
+        self.roundtrip(id);
+
+        let client = self.client(id);
+        client.attach_new_buffer(&surface);
+        client.window(&surface).ack_last_and_commit();
+        self.roundtrip(id);
+
+        surface
+    }
+
+    pub fn destroy_window(&mut self, id: ClientId, surface: &WlSurface) {
+        self.client(id).destroy_window(surface);
+        self.roundtrip(id);
+    }
+
+    /// Feeds the recorded input events to the compositor, moving the clock to the time of each one
+    /// first.
+    pub fn replay(&mut self, recording: &Recording, clock: &ManualClock) {
+        for &(time, event) in recording.events() {
+            clock.set(time);
+            self.state.process_input_event(event.at(time));
+            self.dispatch();
+        }
+    }
+
+    /// Sends a request through the IPC socket and returns the response.
+    ///
+    /// Panics if niri replies with an error.
+    pub fn ipc(&mut self, request: Request) -> Response {
+        let socket_path = self
+            .niri()
+            .ipc_server
+            .as_ref()
+            .expect("IPC server is not running")
+            .socket_path
+            .clone();
+
+        // The socket helper is blocking, so it has to run on another thread while this one drives
+        // the compositor.
+        let handle = thread::spawn(move || Socket::connect_to(socket_path)?.send(request));
+
+        for _ in 0..MAX_ITERATIONS {
+            if handle.is_finished() {
+                let reply = handle
+                    .join()
+                    .unwrap()
+                    .expect("error communicating with niri");
+                let response = reply.expect("niri returned an error");
+
+                // Let the clients see the effects of the request.
+                self.dispatch();
+                return response;
+            }
+
+            self.dispatch();
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        panic!("IPC request didn't complete");
+    }
+
+    pub fn ipc_action(&mut self, action: niri_ipc::Action) {
+        let response = self.ipc(Request::Action(action));
+        assert!(matches!(response, Response::Handled));
+    }
+
+    pub fn ipc_workspaces(&mut self) -> Vec<niri_ipc::Workspace> {
+        let Response::Workspaces(mut workspaces) = self.ipc(Request::Workspaces) else {
+            panic!("unexpected response");
+        };
+
+        workspaces.sort_by(|a, b| (&a.output, a.idx).cmp(&(&b.output, b.idx)));
+        workspaces
+    }
+}
+
+/// Makes sure the Wayland and IPC sockets have somewhere to go.
+///
+/// The compositor binds its sockets in `$XDG_RUNTIME_DIR`, which may be unset on CI machines.
+fn ensure_runtime_dir() {
+    static ONCE: Once = Once::new();
+    ONCE.call_once(|| {
+        if env::var_os("XDG_RUNTIME_DIR").is_some() {
+            return;
+        }
+
+        let dir = env::temp_dir().join(format!("niri-tests-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        env::set_var("XDG_RUNTIME_DIR", dir);
+    });
+}
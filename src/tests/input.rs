@@ -0,0 +1,239 @@
+//! Input events for the compositor under test, and recordings of them.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use smithay::backend::input::{
+    Device, DeviceCapability, Event, GestureBeginEvent, GestureEndEvent, GestureSwipeBeginEvent,
+    GestureSwipeEndEvent, GestureSwipeUpdateEvent, InputBackend, InputEvent, PointerMotionEvent,
+    UnusedEvent,
+};
+
+/// Input backend of the events that the tests send.
+#[derive(Debug)]
+pub struct TestInputBackend;
+
+/// The touchpad that all test events come from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TestTouchpad;
+
+/// An input event, without its time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TestEvent {
+    PointerMotion { dx: f64, dy: f64 },
+    GestureSwipeBegin { fingers: u32 },
+    GestureSwipeUpdate { dx: f64, dy: f64 },
+    GestureSwipeEnd { cancelled: bool },
+}
+
+#[derive(Debug)]
+pub struct TestPointerMotionEvent {
+    time: Duration,
+    dx: f64,
+    dy: f64,
+}
+
+#[derive(Debug)]
+pub struct TestGestureSwipeBeginEvent {
+    time: Duration,
+    fingers: u32,
+}
+
+#[derive(Debug)]
+pub struct TestGestureSwipeUpdateEvent {
+    time: Duration,
+    dx: f64,
+    dy: f64,
+}
+
+#[derive(Debug)]
+pub struct TestGestureSwipeEndEvent {
+    time: Duration,
+    cancelled: bool,
+}
+
+/// Stream of input events along with the clock times they happened at.
+///
+/// Replaying a recording with [`Fixture::replay`](super::fixture::Fixture::replay) feeds the
+/// events to the compositor at their times, so that timing-dependent input, like the velocity of a
+/// touchpad swipe, behaves the same on every run.
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    events: Vec<(Duration, TestEvent)>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records an input event happening at the given clock time.
+    pub fn push(&mut self, time: Duration, event: TestEvent) {
+        if let Some((last, _)) = self.events.last() {
+            assert!(*last <= time, "events must be recorded in order");
+        }
+
+        self.events.push((time, event));
+    }
+
+    pub fn events(&self) -> &[(Duration, TestEvent)] {
+        &self.events
+    }
+}
+
+impl TestEvent {
+    /// Returns the compositor input event for this event happening at the given time.
+    pub fn at(self, time: Duration) -> InputEvent<TestInputBackend> {
+        match self {
+            TestEvent::PointerMotion { dx, dy } => InputEvent::PointerMotion {
+                event: TestPointerMotionEvent { time, dx, dy },
+            },
+            TestEvent::GestureSwipeBegin { fingers } => InputEvent::GestureSwipeBegin {
+                event: TestGestureSwipeBeginEvent { time, fingers },
+            },
+            TestEvent::GestureSwipeUpdate { dx, dy } => InputEvent::GestureSwipeUpdate {
+                event: TestGestureSwipeUpdateEvent { time, dx, dy },
+            },
+            TestEvent::GestureSwipeEnd { cancelled } => InputEvent::GestureSwipeEnd {
+                event: TestGestureSwipeEndEvent { time, cancelled },
+            },
+        }
+    }
+}
+
+impl Device for TestTouchpad {
+    fn id(&self) -> String {
+        String::from("test-touchpad")
+    }
+
+    fn name(&self) -> String {
+        String::from("test touchpad")
+    }
+
+    fn has_capability(&self, capability: DeviceCapability) -> bool {
+        matches!(
+            capability,
+            DeviceCapability::Pointer | DeviceCapability::Gesture
+        )
+    }
+
+    fn usb_id(&self) -> Option<(u32, u32)> {
+        None
+    }
+
+    fn syspath(&self) -> Option<PathBuf> {
+        None
+    }
+}
+
+impl InputBackend for TestInputBackend {
+    type Device = TestTouchpad;
+    type KeyboardKeyEvent = UnusedEvent;
+    type PointerAxisEvent = UnusedEvent;
+    type PointerButtonEvent = UnusedEvent;
+    type PointerMotionEvent = TestPointerMotionEvent;
+    type PointerMotionAbsoluteEvent = UnusedEvent;
+    type GestureSwipeBeginEvent = TestGestureSwipeBeginEvent;
+    type GestureSwipeUpdateEvent = TestGestureSwipeUpdateEvent;
+    type GestureSwipeEndEvent = TestGestureSwipeEndEvent;
+    type GesturePinchBeginEvent = UnusedEvent;
+    type GesturePinchUpdateEvent = UnusedEvent;
+    type GesturePinchEndEvent = UnusedEvent;
+    type GestureHoldBeginEvent = UnusedEvent;
+    type GestureHoldEndEvent = UnusedEvent;
+    type TouchDownEvent = UnusedEvent;
+    type TouchUpEvent = UnusedEvent;
+    type TouchMotionEvent = UnusedEvent;
+    type TouchCancelEvent = UnusedEvent;
+    type TouchFrameEvent = UnusedEvent;
+    type TabletToolAxisEvent = UnusedEvent;
+    type TabletToolProximityEvent = UnusedEvent;
+    type TabletToolTipEvent = UnusedEvent;
+    type TabletToolButtonEvent = UnusedEvent;
+    type SwitchToggleEvent = UnusedEvent;
+    type SpecialEvent = UnusedEvent;
+}
+
+impl Event<TestInputBackend> for TestPointerMotionEvent {
+    fn time(&self) -> u64 {
+        self.time.as_micros() as u64
+    }
+
+    fn device(&self) -> TestTouchpad {
+        TestTouchpad
+    }
+}
+
+impl PointerMotionEvent<TestInputBackend> for TestPointerMotionEvent {
+    fn delta_x(&self) -> f64 {
+        self.dx
+    }
+
+    fn delta_y(&self) -> f64 {
+        self.dy
+    }
+
+    fn delta_x_unaccel(&self) -> f64 {
+        self.dx
+    }
+
+    fn delta_y_unaccel(&self) -> f64 {
+        self.dy
+    }
+}
+
+impl Event<TestInputBackend> for TestGestureSwipeBeginEvent {
+    fn time(&self) -> u64 {
+        self.time.as_micros() as u64
+    }
+
+    fn device(&self) -> TestTouchpad {
+        TestTouchpad
+    }
+}
+
+impl GestureBeginEvent<TestInputBackend> for TestGestureSwipeBeginEvent {
+    fn fingers(&self) -> u32 {
+        self.fingers
+    }
+}
+
+impl GestureSwipeBeginEvent<TestInputBackend> for TestGestureSwipeBeginEvent {}
+
+impl Event<TestInputBackend> for TestGestureSwipeUpdateEvent {
+    fn time(&self) -> u64 {
+        self.time.as_micros() as u64
+    }
+
+    fn device(&self) -> TestTouchpad {
+        TestTouchpad
+    }
+}
+
+impl GestureSwipeUpdateEvent<TestInputBackend> for TestGestureSwipeUpdateEvent {
+    fn delta_x(&self) -> f64 {
+        self.dx
+    }
+
+    fn delta_y(&self) -> f64 {
+        self.dy
+    }
+}
+
+impl Event<TestInputBackend> for TestGestureSwipeEndEvent {
+    fn time(&self) -> u64 {
+        self.time.as_micros() as u64
+    }
+
+    fn device(&self) -> TestTouchpad {
+        TestTouchpad
+    }
+}
+
+impl GestureEndEvent<TestInputBackend> for TestGestureSwipeEndEvent {
+    fn cancelled(&self) -> bool {
+        self.cancelled
+    }
+}
+
+impl GestureSwipeEndEvent<TestInputBackend> for TestGestureSwipeEndEvent {}
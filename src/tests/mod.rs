@@ -0,0 +1,9 @@
+//! Integration tests running the whole compositor on the headless backend.
+
+mod client;
+mod fixture;
+mod input;
+
+mod outputs;
+mod windows;
+mod workspaces;
@@ -0,0 +1,46 @@
+use niri_config::{Config, FloatOrInt, LayoutOverride, Output, OutputProfile};
+
+use super::fixture::Fixture;
+
+#[test]
+fn output_profile_keeps_layout_overrides_of_output_section() {
+    let mut config = Config::default();
+    config.outputs.push(Output {
+        name: String::from("headless-1"),
+        layout: LayoutOverride {
+            gaps: Some(FloatOrInt(4.)),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    config.output_profiles.push(OutputProfile {
+        name: String::from("single"),
+        outputs: vec![Output {
+            name: String::from("headless-1"),
+            scale: Some(FloatOrInt(2.)),
+            layout: LayoutOverride {
+                zoom: Some(FloatOrInt(0.5)),
+                ..Default::default()
+            },
+            ..Default::default()
+        }],
+        workspaces: Vec::new(),
+        exec: Vec::new(),
+    });
+
+    let mut f = Fixture::with_config(config);
+    f.add_output(1, (1920, 1080));
+    assert_eq!(f.niri().active_output_profile.as_deref(), Some("single"));
+
+    let config = f.niri().config.borrow();
+    let output = &config.outputs[0];
+    assert_eq!(output.scale, Some(FloatOrInt(2.)));
+    assert_eq!(
+        output.layout,
+        LayoutOverride {
+            gaps: Some(FloatOrInt(4.)),
+            zoom: Some(FloatOrInt(0.5)),
+            ..Default::default()
+        }
+    );
+}
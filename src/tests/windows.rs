@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use niri_ipc::Action;
+use wayland_protocols::xdg::shell::client::xdg_toplevel;
+
+use super::fixture::Fixture;
+use crate::handlers::MAP_WAIT_TIMEOUT;
+use crate::utils::clock::ManualClock;
+use crate::utils::get_monotonic_time;
+
+#[test]
+fn mapped_window_is_activated() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    let id = f.add_client();
+
+    let surface = f.map_window(id);
+
+    let window = f.client(id).window(&surface);
+    let configure = window.last_configure().unwrap();
+    assert!(configure.states.contains(&xdg_toplevel::State::Activated));
+}
+
+#[test]
+fn close_window_action_asks_client_to_close() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    let id = f.add_client();
+
+    let surface = f.map_window(id);
+    f.ipc_action(Action::CloseWindow);
+    f.roundtrip(id);
+
+    assert!(f.client(id).window(&surface).close_requested);
+}
+
+#[test]
+fn window_with_wrong_size_maps_once_it_commits_configured_size() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    let id = f.add_client();
+
+    let window = f.client(id).create_window();
+    let surface = window.surface.clone();
+    window.commit();
+    f.roundtrip(id);
+
+    // A buffer of a different size than configured doesn't map the window yet.
+    let client = f.client(id);
+    client.attach_buffer(&surface, (10, 10));
+    client.window(&surface).ack_last_and_commit();
+    f.roundtrip(id);
+    assert!(f.niri().layout.active_window().is_none());
+
+    let client = f.client(id);
+    client.attach_new_buffer(&surface);
+    client.window(&surface).commit();
+    f.roundtrip(id);
+    assert!(f.niri().layout.active_window().is_some());
+}
+
+#[test]
+fn window_with_wrong_size_maps_after_timeout() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    let clock = ManualClock::new(get_monotonic_time());
+    let id = f.add_client();
+
+    let window = f.client(id).create_window();
+    let surface = window.surface.clone();
+    window.commit();
+    f.roundtrip(id);
+
+    let client = f.client(id);
+    client.attach_buffer(&surface, (10, 10));
+    client.window(&surface).ack_last_and_commit();
+    f.roundtrip(id);
+    assert!(f.niri().layout.active_window().is_none());
+
+    clock.advance(MAP_WAIT_TIMEOUT - Duration::from_millis(1));
+    f.dispatch();
+    assert!(f.niri().layout.active_window().is_none());
+
+    // The window never commits the configured size, so it maps once the wait runs out.
+    clock.advance(Duration::from_millis(1));
+    f.dispatch();
+    assert!(f.niri().layout.active_window().is_some());
+}
+
+#[test]
+fn window_unmapped_while_waiting_gets_fresh_initial_configure() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    let clock = ManualClock::new(get_monotonic_time());
+    let id = f.add_client();
+
+    let window = f.client(id).create_window();
+    let surface = window.surface.clone();
+    window.commit();
+    f.roundtrip(id);
+
+    let client = f.client(id);
+    client.attach_buffer(&surface, (10, 10));
+    client.window(&surface).ack_last_and_commit();
+    f.roundtrip(id);
+
+    // Unmap, then redo the initial commit.
+    clock.advance(MAP_WAIT_TIMEOUT / 2);
+    let client = f.client(id);
+    client.attach_null_buffer(&surface);
+    client.window(&surface).commit();
+    f.roundtrip(id);
+    let configures = f.client(id).window(&surface).configures.len();
+
+    f.client(id).window(&surface).commit();
+    f.roundtrip(id);
+    assert_eq!(
+        f.client(id).window(&surface).configures.len(),
+        configures + 1
+    );
+
+    // A buffer of the wrong size starts a new wait.
+    let client = f.client(id);
+    client.attach_buffer(&surface, (10, 10));
+    client.window(&surface).ack_last_and_commit();
+    f.roundtrip(id);
+
+    // The deadline of the earlier buffer no longer applies.
+    clock.advance(MAP_WAIT_TIMEOUT / 2 + Duration::from_millis(1));
+    f.dispatch();
+    assert!(f.niri().layout.active_window().is_none());
+
+    clock.advance(MAP_WAIT_TIMEOUT / 2);
+    f.dispatch();
+    assert!(f.niri().layout.active_window().is_some());
+}
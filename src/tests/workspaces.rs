@@ -0,0 +1,190 @@
+use std::time::Duration;
+
+use niri_ipc::{Action, Workspace, WorkspaceReferenceArg};
+
+use super::fixture::Fixture;
+use super::input::{Recording, TestEvent};
+use crate::layout::monitor::WorkspaceSwitch;
+use crate::utils::clock::ManualClock;
+use crate::utils::get_monotonic_time;
+
+fn assert_indices_contiguous(workspaces: &[Workspace]) {
+    let indices: Vec<_> = workspaces.iter().map(|ws| ws.idx).collect();
+    let expected: Vec<_> = (1..=workspaces.len() as u8).collect();
+    assert_eq!(indices, expected, "workspace indices drifted");
+
+    let active = workspaces.iter().filter(|ws| ws.is_active).count();
+    assert_eq!(active, 1, "expected exactly one active workspace");
+}
+
+#[test]
+fn single_output_starts_with_one_workspace() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+
+    let workspaces = f.ipc_workspaces();
+    assert_indices_contiguous(&workspaces);
+    assert_eq!(workspaces.len(), 1);
+    assert_eq!(workspaces[0].output.as_deref(), Some("headless-1"));
+    assert_eq!(workspaces[0].window_count, 0);
+}
+
+#[test]
+fn opening_window_adds_empty_workspace() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    let id = f.add_client();
+
+    f.map_window(id);
+
+    let workspaces = f.ipc_workspaces();
+    assert_indices_contiguous(&workspaces);
+    let counts: Vec<_> = workspaces.iter().map(|ws| ws.window_count).collect();
+    assert_eq!(counts, [1, 0]);
+    assert!(workspaces[0].is_active);
+}
+
+#[test]
+fn indices_shift_when_workspace_above_is_removed() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    let id = f.add_client();
+
+    let first = f.map_window(id);
+    f.ipc_action(Action::FocusWorkspaceDown);
+    f.map_window(id);
+
+    let workspaces = f.ipc_workspaces();
+    assert_indices_contiguous(&workspaces);
+    let counts: Vec<_> = workspaces.iter().map(|ws| ws.window_count).collect();
+    assert_eq!(counts, [1, 1, 0]);
+    assert!(workspaces[1].is_active);
+    let second_id = workspaces[1].active_window_id.unwrap();
+
+    // Closing the only window on the inactive first workspace removes that workspace, so the
+    // workspace of the second window becomes the first one.
+    f.destroy_window(id, &first);
+
+    let workspaces = f.ipc_workspaces();
+    assert_indices_contiguous(&workspaces);
+    let counts: Vec<_> = workspaces.iter().map(|ws| ws.window_count).collect();
+    assert_eq!(counts, [1, 0]);
+    assert!(workspaces[0].is_active);
+    assert_eq!(workspaces[0].active_window_id, Some(second_id));
+
+    // The reported index must be usable to refer to the same workspace.
+    f.ipc_action(Action::FocusWorkspace {
+        reference: Some(WorkspaceReferenceArg::Index(1)),
+        name: None,
+    });
+
+    let workspaces = f.ipc_workspaces();
+    assert_indices_contiguous(&workspaces);
+    assert!(workspaces[0].is_active);
+    assert_eq!(workspaces[0].active_window_id, Some(second_id));
+}
+
+#[test]
+fn indices_are_per_output() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    f.add_output(2, (1280, 720));
+    let id = f.add_client();
+
+    f.map_window(id);
+
+    let workspaces = f.ipc_workspaces();
+    for output in ["headless-1", "headless-2"] {
+        let on_output: Vec<_> = workspaces
+            .iter()
+            .filter(|ws| ws.output.as_deref() == Some(output))
+            .cloned()
+            .collect();
+        assert_indices_contiguous(&on_output);
+    }
+}
+
+/// Records a three-finger touchpad swipe moving by `dy` in total over 100 ms.
+fn swipe(start: Duration, dy: f64, end: bool) -> Recording {
+    let mut recording = Recording::new();
+    recording.push(start, TestEvent::GestureSwipeBegin { fingers: 3 });
+
+    for i in 1..=10 {
+        let time = start + Duration::from_millis(10 * i);
+        let event = TestEvent::GestureSwipeUpdate {
+            dx: 0.,
+            dy: dy / 10.,
+        };
+        recording.push(time, event);
+    }
+
+    if end {
+        let time = start + Duration::from_millis(110);
+        recording.push(time, TestEvent::GestureSwipeEnd { cancelled: false });
+    }
+
+    recording
+}
+
+/// Returns the fractional workspace index of the ongoing workspace switch gesture.
+fn gesture_idx(f: &mut Fixture) -> f64 {
+    let mon = f.niri().layout.active_monitor_ref().unwrap();
+    let Some(WorkspaceSwitch::Gesture(gesture)) = &mon.workspace_switch else {
+        panic!("no workspace switch gesture");
+    };
+    gesture.current_idx
+}
+
+fn settle(f: &mut Fixture, clock: &ManualClock) {
+    clock.advance(Duration::from_secs(2));
+    f.niri().layout.advance_animations(clock.now());
+    f.dispatch();
+
+    let mon = f.niri().layout.active_monitor_ref().unwrap();
+    assert!(
+        mon.workspace_switch.is_none(),
+        "workspace switch didn't settle"
+    );
+}
+
+#[test]
+fn touchpad_swipe_switches_workspace() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    let id = f.add_client();
+    f.map_window(id);
+
+    let clock = ManualClock::new(get_monotonic_time());
+    let recording = swipe(clock.now(), 400., true);
+    f.replay(&recording, &clock);
+    settle(&mut f, &clock);
+
+    let workspaces = f.ipc_workspaces();
+    assert_indices_contiguous(&workspaces);
+    assert!(workspaces[1].is_active);
+}
+
+#[test]
+fn touchpad_swipe_rubber_bands_past_first_workspace() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    let id = f.add_client();
+    f.map_window(id);
+
+    let clock = ManualClock::new(get_monotonic_time());
+    let recording = swipe(clock.now(), -400., false);
+    f.replay(&recording, &clock);
+
+    // There's no workspace above, so the view only stretches a little.
+    let idx = gesture_idx(&mut f);
+    assert!(-0.05 < idx && idx < 0., "{idx}");
+
+    let mut end = Recording::new();
+    end.push(clock.now(), TestEvent::GestureSwipeEnd { cancelled: false });
+    f.replay(&end, &clock);
+    settle(&mut f, &clock);
+
+    let workspaces = f.ipc_workspaces();
+    assert_indices_contiguous(&workspaces);
+    assert!(workspaces[0].is_active);
+}
@@ -6,26 +6,33 @@ use std::time::Duration;
 
 use niri_config::Config;
 use ordered_float::NotNan;
-use pangocairo::cairo::{self, ImageSurface};
-use pangocairo::pango::FontDescription;
+use pangocairo::pango::Alignment;
 use smithay::backend::renderer::element::Kind;
 use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
 use smithay::output::Output;
-use smithay::reexports::gbm::Format as Fourcc;
-use smithay::utils::{Point, Transform};
+use smithay::utils::Point;
 
 use crate::animation::Animation;
 use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
 use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::text::{render_text_box, TextBoxStyle};
 use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
-use crate::utils::{output_size, to_physical_precise_round};
+use crate::render_helpers::texture_cache::texture_bytes;
+use crate::utils::output_size;
 
 const TEXT: &str = "Failed to parse the config file. \
                     Please run <span face='monospace' bgcolor='#000000'>niri validate</span> \
                     to see the errors.";
 const PADDING: i32 = 8;
-const FONT: &str = "sans 14px";
-const BORDER: i32 = 4;
+const STYLE: TextBoxStyle = TextBoxStyle {
+    font: "sans 14px",
+    alignment: Alignment::Left,
+    padding: PADDING,
+    border: 4,
+    background: [0.1, 0.1, 0.1, 1.],
+    text_color: [1., 1., 1.],
+    border_color: [1., 0.3, 0.3],
+};
 
 pub struct ConfigErrorNotification {
     state: State,
@@ -122,6 +129,27 @@ impl ConfigErrorNotification {
         !matches!(self.state, State::Hidden)
     }
 
+    /// Returns the memory used by the cached textures while the notification is hidden.
+    pub fn evictable_texture_bytes(&self) -> u64 {
+        if !matches!(self.state, State::Hidden) {
+            return 0;
+        }
+
+        self.buffers
+            .borrow()
+            .values()
+            .flatten()
+            .map(|buffer| texture_bytes(buffer.texture()))
+            .sum()
+    }
+
+    /// Drops the cached textures if the notification is hidden.
+    pub fn evict_textures(&self) {
+        if matches!(self.state, State::Hidden) {
+            self.buffers.borrow_mut().clear();
+        }
+    }
+
     pub fn render<R: NiriRenderer>(
         &self,
         renderer: &mut R,
@@ -173,67 +201,18 @@ fn render(
 ) -> anyhow::Result<TextureBuffer<GlesTexture>> {
     let _span = tracy_client::span!("config_error_notification::render");
 
-    let padding: i32 = to_physical_precise_round(scale, PADDING);
-
     let mut text = String::from(TEXT);
-    let mut border_color = (1., 0.3, 0.3);
+    let mut style = STYLE;
     if let Some(path) = created_path {
         text = format!(
             "Created a default config file at \
              <span face='monospace' bgcolor='#000000'>{:?}</span>",
             path
         );
-        border_color = (0.5, 1., 0.5);
+        style.border_color = [0.5, 1., 0.5];
     };
 
-    let mut font = FontDescription::from_string(FONT);
-    font.set_absolute_size(to_physical_precise_round(scale, font.size()));
-
-    let surface = ImageSurface::create(cairo::Format::ARgb32, 0, 0)?;
-    let cr = cairo::Context::new(&surface)?;
-    let layout = pangocairo::functions::create_layout(&cr);
-    layout.set_font_description(Some(&font));
-    layout.set_markup(&text);
-
-    let (mut width, mut height) = layout.pixel_size();
-    width += padding * 2;
-    height += padding * 2;
-
-    let surface = ImageSurface::create(cairo::Format::ARgb32, width, height)?;
-    let cr = cairo::Context::new(&surface)?;
-    cr.set_source_rgb(0.1, 0.1, 0.1);
-    cr.paint()?;
-
-    cr.move_to(padding.into(), padding.into());
-    let layout = pangocairo::functions::create_layout(&cr);
-    layout.set_font_description(Some(&font));
-    layout.set_markup(&text);
-
-    cr.set_source_rgb(1., 1., 1.);
-    pangocairo::functions::show_layout(&cr, &layout);
-
-    cr.move_to(0., 0.);
-    cr.line_to(width.into(), 0.);
-    cr.line_to(width.into(), height.into());
-    cr.line_to(0., height.into());
-    cr.line_to(0., 0.);
-    cr.set_source_rgb(border_color.0, border_color.1, border_color.2);
-    // Keep the border width even to avoid blurry edges.
-    cr.set_line_width((f64::from(BORDER) / 2. * scale).round() * 2.);
-    cr.stroke()?;
-    drop(cr);
-
-    let data = surface.take_data().unwrap();
-    let buffer = TextureBuffer::from_memory(
-        renderer,
-        &data,
-        Fourcc::Argb8888,
-        (width, height),
-        false,
-        scale,
-        Transform::Normal,
-        Vec::new(),
-    )?;
-
+    let buffer = render_text_box(&text, &style, scale)?;
+    let buffer = TextureBuffer::from_memory_buffer(renderer, &buffer)?;
     Ok(buffer)
 }
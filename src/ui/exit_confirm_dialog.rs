@@ -2,41 +2,63 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 
 use ordered_float::NotNan;
-use pangocairo::cairo::{self, ImageSurface};
-use pangocairo::pango::{Alignment, FontDescription};
+use pangocairo::pango::Alignment;
 use smithay::backend::renderer::element::Kind;
 use smithay::output::Output;
-use smithay::reexports::gbm::Format as Fourcc;
-use smithay::utils::Transform;
 
 use crate::render_helpers::memory::MemoryBuffer;
 use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
 use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::text::{render_text_box, TextBoxStyle};
 use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
-use crate::utils::{output_size, to_physical_precise_round};
+use crate::utils::output_size;
 
 const TEXT: &str = "Are you sure you want to exit niri?\n\n\
                     Press <span face='mono' bgcolor='#2C2C2C'> Enter </span> to confirm.";
-const PADDING: i32 = 16;
-const FONT: &str = "sans 14px";
-const BORDER: i32 = 8;
+pub const CLOSE_WINDOW_TEXT: &str = "Are you sure you want to close this window?\n\n\
+                                     Press <span face='mono' bgcolor='#2C2C2C'> Enter </span> \
+                                     to confirm.";
+const STYLE: TextBoxStyle = TextBoxStyle {
+    font: "sans 14px",
+    alignment: Alignment::Center,
+    padding: 16,
+    border: 8,
+    background: [0.1, 0.1, 0.1, 1.],
+    text_color: [1., 1., 1.],
+    border_color: [1., 0.3, 0.3],
+};
 
 pub struct ExitConfirmDialog {
     is_open: bool,
+    text: String,
     buffers: RefCell<HashMap<NotNan<f64>, Option<MemoryBuffer>>>,
 }
 
 impl ExitConfirmDialog {
     pub fn new() -> anyhow::Result<Self> {
+        Self::with_text(TEXT)
+    }
+
+    /// Creates a confirmation dialog with a different Pango markup text.
+    pub fn with_text(text: &'static str) -> anyhow::Result<Self> {
         Ok(Self {
             is_open: false,
+            text: text.to_owned(),
             buffers: RefCell::new(HashMap::from([(
                 NotNan::new(1.).unwrap(),
-                Some(render(1.)?),
+                Some(render(text, 1.)?),
             )])),
         })
     }
 
+    /// Replaces the Pango markup text, for dialogs whose contents depend on the request.
+    pub fn set_text(&mut self, text: String) -> anyhow::Result<()> {
+        let buffer = render(&text, 1.)?;
+        self.buffers = RefCell::new(HashMap::from([(NotNan::new(1.).unwrap(), Some(buffer))]));
+        self.text = text;
+        Ok(())
+    }
+
     pub fn show(&mut self) -> bool {
         if !self.is_open {
             self.is_open = true;
@@ -75,7 +97,7 @@ impl ExitConfirmDialog {
         let fallback = buffers[&NotNan::new(1.).unwrap()].clone().unwrap();
         let buffer = buffers
             .entry(NotNan::new(scale).unwrap())
-            .or_insert_with(|| render(scale).ok());
+            .or_insert_with(|| render(&self.text, scale).ok());
         let buffer = buffer.as_ref().unwrap_or(&fallback);
 
         let size = buffer.logical_size();
@@ -98,58 +120,7 @@ impl ExitConfirmDialog {
     }
 }
 
-fn render(scale: f64) -> anyhow::Result<MemoryBuffer> {
+fn render(text: &str, scale: f64) -> anyhow::Result<MemoryBuffer> {
     let _span = tracy_client::span!("exit_confirm_dialog::render");
-
-    let padding: i32 = to_physical_precise_round(scale, PADDING);
-
-    let mut font = FontDescription::from_string(FONT);
-    font.set_absolute_size(to_physical_precise_round(scale, font.size()));
-
-    let surface = ImageSurface::create(cairo::Format::ARgb32, 0, 0)?;
-    let cr = cairo::Context::new(&surface)?;
-    let layout = pangocairo::functions::create_layout(&cr);
-    layout.set_font_description(Some(&font));
-    layout.set_alignment(Alignment::Center);
-    layout.set_markup(TEXT);
-
-    let (mut width, mut height) = layout.pixel_size();
-    width += padding * 2;
-    height += padding * 2;
-
-    let surface = ImageSurface::create(cairo::Format::ARgb32, width, height)?;
-    let cr = cairo::Context::new(&surface)?;
-    cr.set_source_rgb(0.1, 0.1, 0.1);
-    cr.paint()?;
-
-    cr.move_to(padding.into(), padding.into());
-    let layout = pangocairo::functions::create_layout(&cr);
-    layout.set_font_description(Some(&font));
-    layout.set_alignment(Alignment::Center);
-    layout.set_markup(TEXT);
-
-    cr.set_source_rgb(1., 1., 1.);
-    pangocairo::functions::show_layout(&cr, &layout);
-
-    cr.move_to(0., 0.);
-    cr.line_to(width.into(), 0.);
-    cr.line_to(width.into(), height.into());
-    cr.line_to(0., height.into());
-    cr.line_to(0., 0.);
-    cr.set_source_rgb(1., 0.3, 0.3);
-    // Keep the border width even to avoid blurry edges.
-    cr.set_line_width((f64::from(BORDER) / 2. * scale).round() * 2.);
-    cr.stroke()?;
-    drop(cr);
-
-    let data = surface.take_data().unwrap();
-    let buffer = MemoryBuffer::new(
-        data.to_vec(),
-        Fourcc::Argb8888,
-        (width, height),
-        scale,
-        Transform::Normal,
-    );
-
-    Ok(buffer)
+    render_text_box(text, &STYLE, scale)
 }
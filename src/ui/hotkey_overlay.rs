@@ -6,7 +6,7 @@ use std::rc::Rc;
 
 use niri_config::{Action, Config, Key, Modifiers, Trigger};
 use pangocairo::cairo::{self, ImageSurface};
-use pangocairo::pango::{AttrColor, AttrInt, AttrList, AttrString, FontDescription, Weight};
+use pangocairo::pango::{AttrColor, AttrInt, AttrList, AttrString, Weight};
 use smithay::backend::renderer::element::Kind;
 use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
 use smithay::input::keyboard::xkb::keysym_get_name;
@@ -17,7 +17,9 @@ use smithay::utils::{Scale, Transform};
 use crate::input::CompositorMod;
 use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
 use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::text::scaled_font;
 use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::render_helpers::texture_cache::texture_bytes;
 use crate::utils::{output_size, to_physical_precise_round};
 
 const PADDING: i32 = 8;
@@ -74,6 +76,27 @@ impl HotkeyOverlay {
         self.buffers.borrow_mut().clear();
     }
 
+    /// Returns the memory used by the cached textures while the overlay is hidden.
+    pub fn evictable_texture_bytes(&self) -> u64 {
+        if self.is_open {
+            return 0;
+        }
+
+        self.buffers
+            .borrow()
+            .values()
+            .filter_map(|rendered| rendered.buffer.as_ref())
+            .map(|buffer| texture_bytes(buffer.texture()))
+            .sum()
+    }
+
+    /// Drops the cached textures if the overlay is hidden; they are re-rendered on next show.
+    pub fn evict_textures(&self) {
+        if !self.is_open {
+            self.buffers.borrow_mut().clear();
+        }
+    }
+
     pub fn render<R: NiriRenderer>(
         &self,
         renderer: &mut R,
@@ -213,11 +236,11 @@ fn render(
     // Add the spawn actions.
     let mut spawn_actions = Vec::new();
     for bind in binds.iter().filter(|bind| {
-        matches!(bind.action, Action::Spawn(_))
+        matches!(bind.action, Action::Spawn(_) | Action::FocusOrSpawn(..))
             // Only show binds with Mod or Super to filter out stuff like volume up/down.
             && (bind.key.modifiers.contains(Modifiers::COMPOSITOR)
                 || bind.key.modifiers.contains(Modifiers::SUPER))
-            // Also filter out wheel and touchpad scroll binds.
+            // Also filter out wheel, touchpad scroll and mouse button binds.
             && matches!(bind.key.trigger, Trigger::Keysym(_))
     }) {
         let action = &bind.action;
@@ -244,8 +267,7 @@ fn render(
         })
         .collect::<Vec<_>>();
 
-    let mut font = FontDescription::from_string(FONT);
-    font.set_absolute_size(to_physical_precise_round(scale, font.size()));
+    let font = scaled_font(FONT, scale);
 
     let surface = ImageSurface::create(cairo::Format::ARgb32, 0, 0)?;
     let cr = cairo::Context::new(&surface)?;
@@ -381,6 +403,10 @@ fn action_name(action: &Action) -> String {
             "Spawn <span face='monospace' bgcolor='#000000'>{}</span>",
             args.first().unwrap_or(&String::new())
         ),
+        Action::FocusOrSpawn(app_id, _) => format!(
+            "Focus or Spawn <span face='monospace' bgcolor='#000000'>{}</span>",
+            app_id
+        ),
         _ => String::from("FIXME: Unknown"),
     }
 }
@@ -410,6 +436,12 @@ fn key_name(comp_mod: CompositorMod, key: &Key) -> String {
 
     let pretty = match key.trigger {
         Trigger::Keysym(keysym) => prettify_keysym_name(&keysym_get_name(keysym)),
+        Trigger::KeyCode(code) => format!("Key Code {code}"),
+        Trigger::MouseLeft => String::from("Mouse Left"),
+        Trigger::MouseRight => String::from("Mouse Right"),
+        Trigger::MouseMiddle => String::from("Mouse Middle"),
+        Trigger::MouseBack => String::from("Mouse Back"),
+        Trigger::MouseForward => String::from("Mouse Forward"),
         Trigger::WheelScrollDown => String::from("Wheel Scroll Down"),
         Trigger::WheelScrollUp => String::from("Wheel Scroll Up"),
         Trigger::WheelScrollLeft => String::from("Wheel Scroll Left"),
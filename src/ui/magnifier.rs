@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use smithay::utils::{Logical, Point};
+
+use crate::animation::Animation;
+
+/// Zoom factor of a single zoom in or zoom out step.
+const STEP: f64 = 1.25;
+const MAX_ZOOM: f64 = 16.;
+
+/// Screen magnifier of one output.
+///
+/// The magnifier zooms the desktop around the cursor, so the content under the cursor stays in
+/// place and everything else moves away from it.
+#[derive(Debug)]
+pub struct Magnifier {
+    /// Zoom level to settle at, 1 when not zoomed in.
+    zoom: f64,
+    anim: Option<Animation>,
+}
+
+impl Magnifier {
+    pub fn new() -> Self {
+        Self {
+            zoom: 1.,
+            anim: None,
+        }
+    }
+
+    pub fn zoom_in(&mut self, config: niri_config::Animation) {
+        self.set_zoom(self.zoom * STEP, config);
+    }
+
+    pub fn zoom_out(&mut self, config: niri_config::Animation) {
+        self.set_zoom(self.zoom / STEP, config);
+    }
+
+    pub fn reset(&mut self, config: niri_config::Animation) {
+        self.set_zoom(1., config);
+    }
+
+    fn set_zoom(&mut self, zoom: f64, config: niri_config::Animation) {
+        let zoom = zoom.clamp(1., MAX_ZOOM);
+        if zoom == self.zoom {
+            return;
+        }
+
+        self.anim = Some(Animation::new(self.current_zoom(), zoom, 0., config));
+        self.zoom = zoom;
+    }
+
+    pub fn advance_animations(&mut self, current_time: Duration) {
+        if let Some(anim) = &mut self.anim {
+            anim.set_current_time(current_time);
+            if anim.is_done() {
+                self.anim = None;
+            }
+        }
+    }
+
+    pub fn are_animations_ongoing(&self) -> bool {
+        self.anim.is_some()
+    }
+
+    pub fn current_zoom(&self) -> f64 {
+        self.anim
+            .as_ref()
+            .map_or(self.zoom, |anim| f64::max(1., anim.value()))
+    }
+
+    pub fn is_zoomed(&self) -> bool {
+        self.current_zoom() > 1.
+    }
+
+    /// Maps a position on the screen to the position on the unzoomed output.
+    pub fn unmap(
+        &self,
+        origin: Point<f64, Logical>,
+        pos: Point<f64, Logical>,
+    ) -> Point<f64, Logical> {
+        origin + (pos - origin).downscale(self.current_zoom())
+    }
+}
+
+impl Default for Magnifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
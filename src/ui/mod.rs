@@ -1,5 +1,8 @@
 pub mod config_error_notification;
 pub mod exit_confirm_dialog;
 pub mod hotkey_overlay;
+pub mod magnifier;
+pub mod resize_mode;
 pub mod screen_transition;
 pub mod screenshot_ui;
+pub mod window_hints;
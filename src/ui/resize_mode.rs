@@ -0,0 +1,152 @@
+use niri_config::Action;
+use niri_ipc::SizeChange;
+use pangocairo::pango::Alignment;
+use smithay::backend::renderer::element::Kind;
+use smithay::input::keyboard::Keysym;
+use smithay::output::Output;
+
+use crate::render_helpers::memory::MemoryBuffer;
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::text::{render_text_box, TextBoxStyle, TextCache};
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::utils::output_size;
+
+const TEXT: &str = "Resize mode";
+const STYLE: TextBoxStyle = TextBoxStyle {
+    font: "sans 14px",
+    alignment: Alignment::Left,
+    padding: 8,
+    border: 2,
+    background: [0.1, 0.1, 0.1, 0.8],
+    text_color: [1., 1., 1.],
+    border_color: [0.5, 0.8, 1.],
+};
+
+/// Distance between the indicator and the bottom edge of the output.
+const MARGIN: i32 = 32;
+
+/// Modal keyboard resizing of the active column and window.
+///
+/// While the mode is on, the arrow keys (and hjkl) resize instead of going to the focused window,
+/// and a small indicator is shown at the bottom of every output.
+pub struct ResizeMode {
+    is_open: bool,
+    /// Resize steps, taken from the config when the mode is entered.
+    config: niri_config::ResizeMode,
+    buffers: TextCache,
+}
+
+impl ResizeMode {
+    pub fn new() -> Self {
+        Self {
+            is_open: false,
+            config: niri_config::ResizeMode::default(),
+            buffers: TextCache::new(),
+        }
+    }
+
+    pub fn open(&mut self, config: niri_config::ResizeMode) -> bool {
+        self.config = config;
+
+        if self.is_open {
+            return false;
+        }
+
+        self.is_open = true;
+        true
+    }
+
+    pub fn close(&mut self) -> bool {
+        if !self.is_open {
+            return false;
+        }
+
+        self.is_open = false;
+        true
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    /// Returns the action for a key pressed in resize mode.
+    ///
+    /// Keys without a resize mode action return `None` and go through the regular binds.
+    pub fn action(&self, raw: Keysym) -> Option<Action> {
+        if !self.is_open {
+            return None;
+        }
+
+        let config = &self.config;
+        let action = match raw {
+            Keysym::Escape | Keysym::Return => Action::ExitResizeMode,
+            Keysym::Left | Keysym::h => Action::SetColumnWidth(step(config.width_step, false)),
+            Keysym::Right | Keysym::l => Action::SetColumnWidth(step(config.width_step, true)),
+            Keysym::Up | Keysym::k => Action::SetWindowHeight(step(config.height_step, false)),
+            Keysym::Down | Keysym::j => Action::SetWindowHeight(step(config.height_step, true)),
+            _ => return None,
+        };
+        Some(action)
+    }
+
+    pub fn render<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        if !self.is_open {
+            return None;
+        }
+
+        let scale = output.current_scale().fractional_scale();
+        let output_size = output_size(output);
+
+        let buffer = self.buffers.get(scale, render)?;
+
+        let size = buffer.logical_size();
+        let buffer =
+            TextureBuffer::from_memory_buffer(renderer.as_gles_renderer(), &buffer).ok()?;
+
+        let mut location = output_size.to_f64().to_point() - size.to_point();
+        location.x /= 2.;
+        location.y -= f64::from(MARGIN);
+        let mut location = location.to_physical_precise_round(scale).to_logical(scale);
+        location.x = f64::max(0., location.x);
+        location.y = f64::max(0., location.y);
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            location,
+            1.,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+}
+
+impl Default for ResizeMode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Converts a configured step into a change that grows or shrinks by its magnitude.
+fn step(step: SizeChange, grow: bool) -> SizeChange {
+    let sign = if grow { 1 } else { -1 };
+    match step {
+        SizeChange::SetFixed(x) | SizeChange::AdjustFixed(x) => {
+            SizeChange::AdjustFixed(x.abs() * sign)
+        }
+        SizeChange::SetProportion(x) | SizeChange::AdjustProportion(x) => {
+            SizeChange::AdjustProportion(x.abs() * f64::from(sign))
+        }
+    }
+}
+
+fn render(scale: f64) -> anyhow::Result<MemoryBuffer> {
+    let _span = tracy_client::span!("resize_mode::render");
+    render_text_box(TEXT, &STYLE, scale)
+}
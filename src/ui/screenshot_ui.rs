@@ -19,6 +19,7 @@ use crate::niri_render_elements;
 use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
 use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
 use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::render_helpers::texture_cache::texture_bytes;
 use crate::render_helpers::RenderTarget;
 use crate::utils::to_physical_precise_round;
 
@@ -37,6 +38,8 @@ pub enum ScreenshotUi {
         selection: (Output, Point<i32, Physical>, Point<i32, Physical>),
         output_data: HashMap<Output, OutputData>,
         mouse_down: bool,
+        /// Whether the selection is for recording a clip rather than for a screenshot.
+        record_clip: bool,
     },
 }
 
@@ -71,6 +74,7 @@ impl ScreenshotUi {
         // Output, screencast, screen capture.
         screenshots: HashMap<Output, [GlesTexture; 3]>,
         default_output: Output,
+        record_clip: bool,
     ) -> bool {
         if screenshots.is_empty() {
             return false;
@@ -151,6 +155,7 @@ impl ScreenshotUi {
             selection,
             output_data,
             mouse_down: false,
+            record_clip,
         };
 
         self.update_buffers();
@@ -182,6 +187,69 @@ impl ScreenshotUi {
         matches!(self, ScreenshotUi::Open { .. })
     }
 
+    /// Returns the memory used by all frozen screenshots, and by those of the outputs without the
+    /// selection.
+    pub fn texture_bytes(&self) -> (u64, u64) {
+        let Self::Open {
+            selection,
+            output_data,
+            ..
+        } = self
+        else {
+            return (0, 0);
+        };
+
+        let mut total = 0;
+        let mut inactive = 0;
+        for (output, data) in output_data {
+            let bytes = data.texture.iter().map(texture_bytes).sum::<u64>();
+            total += bytes;
+            if *output != selection.0 {
+                inactive += bytes;
+            }
+        }
+
+        (total, inactive)
+    }
+
+    /// Drops the frozen screenshots of the outputs without the selection.
+    ///
+    /// These outputs show only the background until the UI is closed, and the selection can no
+    /// longer move to them.
+    pub fn evict_inactive_outputs(&mut self) {
+        if let Self::Open {
+            selection,
+            output_data,
+            ..
+        } = self
+        {
+            output_data.retain(|output, _| *output == selection.0);
+        }
+    }
+
+    pub fn is_recording_clip(&self) -> bool {
+        matches!(
+            self,
+            ScreenshotUi::Open {
+                record_clip: true,
+                ..
+            }
+        )
+    }
+
+    /// Returns the selected output and region in output-local physical coordinates.
+    pub fn selection(&self) -> Option<(Output, Rectangle<i32, Physical>)> {
+        if let Self::Open {
+            selection: (output, a, b),
+            ..
+        } = self
+        {
+            Some((output.clone(), rect_from_corner_points(*a, *b)))
+        } else {
+            None
+        }
+    }
+
     fn update_buffers(&mut self) {
         let Self::Open {
             selection,
@@ -335,6 +403,40 @@ impl ScreenshotUi {
         Ok((rect.size, copy.to_vec()))
     }
 
+    /// Copies the pixel at this point of the frozen output contents.
+    pub fn capture_pixel(
+        &self,
+        renderer: &mut GlesRenderer,
+        output: &Output,
+        point: Point<i32, Physical>,
+    ) -> anyhow::Result<[u8; 4]> {
+        let _span = tracy_client::span!("ScreenshotUi::capture_pixel");
+
+        let Self::Open { output_data, .. } = self else {
+            panic!("screenshot UI must be open to capture");
+        };
+
+        let data = output_data
+            .get(output)
+            .context("output is missing from the screenshot UI")?;
+        let rect = Rectangle::from_loc_and_size(point, (1, 1));
+        let buf_rect = rect
+            .to_logical(1)
+            .to_buffer(1, Transform::Normal, &data.size.to_logical(1));
+
+        let mapping = renderer
+            .copy_texture(&data.texture[0], buf_rect, Fourcc::Abgr8888)
+            .context("error copying texture")?;
+        let copy = renderer
+            .map_texture(&mapping)
+            .context("error mapping texture")?;
+
+        match copy {
+            &[r, g, b, a, ..] => Ok([r, g, b, a]),
+            _ => anyhow::bail!("copied texture is empty"),
+        }
+    }
+
     pub fn action(&self, raw: Keysym, mods: ModifiersState) -> Option<Action> {
         if !matches!(self, Self::Open { .. }) {
             return None;
@@ -390,6 +492,7 @@ impl ScreenshotUi {
             selection,
             output_data,
             mouse_down,
+            ..
         } = self
         else {
             return false;
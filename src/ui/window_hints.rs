@@ -0,0 +1,163 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use niri_config::Action;
+use ordered_float::NotNan;
+use pangocairo::pango::Alignment;
+use smithay::backend::renderer::element::Kind;
+use smithay::desktop::Window;
+use smithay::input::keyboard::Keysym;
+use smithay::output::Output;
+use smithay::utils::{Logical, Point, Rectangle};
+
+use crate::render_helpers::memory::MemoryBuffer;
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::text::{render_text_box, TextBoxStyle};
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+
+/// Keys used for hints, in the order they are assigned to windows.
+const KEYS: &str = "asdfghjklqwertyuiopzxcvbnm";
+const STYLE: TextBoxStyle = TextBoxStyle {
+    font: "monospace bold 20px",
+    alignment: Alignment::Left,
+    padding: 8,
+    border: 4,
+    background: [0.1, 0.1, 0.1, 1.],
+    text_color: [1., 1., 1.],
+    border_color: [1., 0.8, 0.2],
+};
+
+/// Letters shown over visible windows to focus them with the keyboard.
+pub struct WindowHints {
+    hints: Vec<Hint>,
+    buffers: RefCell<HashMap<(char, NotNan<f64>), Option<MemoryBuffer>>>,
+}
+
+struct Hint {
+    key: char,
+    output: Output,
+    /// Center of the window in output-local coordinates.
+    center: Point<f64, Logical>,
+    window: Window,
+}
+
+impl WindowHints {
+    pub fn new() -> Self {
+        Self {
+            hints: Vec::new(),
+            buffers: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Shows hints over these windows, given with their output-local geometry.
+    ///
+    /// Windows beyond the number of available keys don't get a hint.
+    pub fn open(
+        &mut self,
+        windows: impl IntoIterator<Item = (Output, Window, Rectangle<f64, Logical>)>,
+    ) -> bool {
+        self.hints = KEYS
+            .chars()
+            .zip(windows)
+            .map(|(key, (output, window, geo))| Hint {
+                key,
+                output,
+                center: geo.loc + geo.size.downscale(2.).to_point(),
+                window,
+            })
+            .collect();
+
+        !self.hints.is_empty()
+    }
+
+    pub fn close(&mut self) -> bool {
+        if self.hints.is_empty() {
+            return false;
+        }
+
+        self.hints.clear();
+        true
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.hints.is_empty()
+    }
+
+    pub fn action(&self, raw: Keysym) -> Option<Action> {
+        if !self.is_open() {
+            return None;
+        }
+
+        if raw == Keysym::Escape {
+            return Some(Action::CancelWindowHints);
+        }
+
+        // Let modifier presses through, and cancel on any other key without a hint.
+        let key = raw.key_char()?;
+        if self.hints.iter().any(|hint| hint.key == key) {
+            Some(Action::FocusWindowHint(key))
+        } else {
+            Some(Action::CancelWindowHints)
+        }
+    }
+
+    /// Closes the hints and returns the window with this hint key.
+    pub fn take(&mut self, key: char) -> Option<Window> {
+        let hint = self.hints.drain(..).find(|hint| hint.key == key)?;
+        Some(hint.window)
+    }
+
+    pub fn render<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Vec<PrimaryGpuTextureRenderElement> {
+        let scale = output.current_scale().fractional_scale();
+
+        let mut buffers = self.buffers.borrow_mut();
+
+        let mut elements = vec![];
+        for hint in self.hints.iter().filter(|hint| &hint.output == output) {
+            let buffer = buffers
+                .entry((hint.key, NotNan::new(scale).unwrap()))
+                .or_insert_with(|| render(hint.key, scale).ok());
+            let Some(buffer) = buffer else {
+                continue;
+            };
+
+            let size = buffer.logical_size();
+            let Ok(buffer) = TextureBuffer::from_memory_buffer(renderer.as_gles_renderer(), buffer)
+            else {
+                continue;
+            };
+
+            let location = hint.center - size.downscale(2.).to_point();
+            let location = location.to_physical_precise_round(scale).to_logical(scale);
+
+            let elem = TextureRenderElement::from_texture_buffer(
+                buffer,
+                location,
+                1.,
+                None,
+                None,
+                Kind::Unspecified,
+            );
+            elements.push(PrimaryGpuTextureRenderElement(elem));
+        }
+
+        elements
+    }
+}
+
+impl Default for WindowHints {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn render(key: char, scale: f64) -> anyhow::Result<MemoryBuffer> {
+    let _span = tracy_client::span!("window_hints::render");
+    let text = key.to_uppercase().to_string();
+    render_text_box(&text, &STYLE, scale)
+}
@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use super::clock::ManualClock;
+use super::get_monotonic_time;
+
+/// Refresh interval of the synthetic frame clock (60 Hz).
+const FRAME_INTERVAL: Duration = Duration::from_nanos(16_666_667);
+
+/// Most frame timings to allocate for up front; longer runs grow the buffer as they go.
+const MAX_PREALLOCATED_FRAMES: u32 = 10_000;
+
+/// Benchmark mode of the nested backend.
+///
+/// Frames are rendered back to back without vsync. The monotonic clock is frozen during every
+/// frame and advances by exactly one refresh interval from one frame to the next, so animations
+/// progress the same way regardless of how fast the frames render. Outside of frames, the clock
+/// reads the real time as usual.
+pub struct Benchmark {
+    /// Number of frames to render before exiting.
+    frames: u32,
+    start_time: Duration,
+    timings: Vec<FrameTimings>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameTimings {
+    /// Advancing the animations and updating the layout render elements.
+    pub layout: Duration,
+    /// Rendering and submitting the frame.
+    pub render: Duration,
+}
+
+impl Benchmark {
+    pub fn new(frames: u32) -> Self {
+        Self {
+            frames,
+            start_time: get_monotonic_time(),
+            timings: Vec::with_capacity(frames.min(MAX_PREALLOCATED_FRAMES) as usize),
+        }
+    }
+
+    /// Freezes the clock at the time of the next frame.
+    ///
+    /// The clock goes back to the real time once the returned clock is dropped.
+    #[must_use]
+    pub fn start_frame(&self) -> ManualClock {
+        let frame = self.timings.len() as u32;
+        ManualClock::new(self.start_time + FRAME_INTERVAL * frame)
+    }
+
+    /// Records the timings of a frame.
+    ///
+    /// Returns `true` when the benchmark is done.
+    pub fn end_frame(&mut self, timings: FrameTimings) -> bool {
+        let frame = self.timings.len();
+        println!(
+            "frame {frame}: layout {:.3} ms, render {:.3} ms",
+            ms(timings.layout),
+            ms(timings.render)
+        );
+
+        self.timings.push(timings);
+        self.timings.len() as u32 >= self.frames
+    }
+
+    pub fn print_summary(&self) {
+        if self.timings.is_empty() {
+            return;
+        }
+
+        println!("{} frames:", self.timings.len());
+        print_stats("layout", self.timings.iter().map(|t| t.layout));
+        print_stats("render", self.timings.iter().map(|t| t.render));
+    }
+}
+
+fn print_stats(name: &str, times: impl Iterator<Item = Duration>) {
+    let mut times: Vec<_> = times.collect();
+    times.sort_unstable();
+
+    let total: Duration = times.iter().sum();
+    let mean = total / times.len() as u32;
+    let percentile = |p: usize| times[(times.len() - 1) * p / 100];
+
+    println!(
+        "  {name}: mean {:.3} ms, median {:.3} ms, p99 {:.3} ms, max {:.3} ms",
+        ms(mean),
+        ms(percentile(50)),
+        ms(percentile(99)),
+        ms(times[times.len() - 1]),
+    );
+}
+
+fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.
+}
@@ -0,0 +1,81 @@
+//! Manually advanced clock for deterministic tests and replays.
+
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use super::{clear_synthetic_time, set_synthetic_time, synthetic_time};
+
+/// Clock that only moves when told to.
+///
+/// While it exists, it sets the synthetic time, so
+/// [`get_monotonic_time()`](super::get_monotonic_time) on the same thread returns its time instead
+/// of reading the system clock, and animations started and advanced on this thread progress exactly
+/// the same way on every run.
+///
+/// The clock stops overriding the time when dropped.
+pub struct ManualClock {
+    // The override is per-thread, so the clock must stay on the thread that created it.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl ManualClock {
+    /// Starts a manual clock on this thread at the given time.
+    ///
+    /// Panics if this thread already has a synthetic time.
+    pub fn new(start: Duration) -> Self {
+        assert!(synthetic_time().is_none(), "synthetic time is already set");
+        set_synthetic_time(start);
+
+        Self {
+            _not_send: PhantomData,
+        }
+    }
+
+    pub fn now(&self) -> Duration {
+        synthetic_time().unwrap()
+    }
+
+    /// Moves the clock to the given time.
+    ///
+    /// Panics if that would move the clock backwards.
+    pub fn set(&self, new: Duration) {
+        assert!(new >= self.now(), "manual clock cannot go backwards");
+        set_synthetic_time(new);
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.set(self.now() + by);
+    }
+}
+
+impl Drop for ManualClock {
+    fn drop(&mut self) {
+        clear_synthetic_time();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::get_monotonic_time;
+
+    #[test]
+    fn overrides_monotonic_time_while_alive() {
+        {
+            let clock = ManualClock::new(Duration::from_secs(5));
+            assert_eq!(get_monotonic_time(), Duration::from_secs(5));
+
+            clock.advance(Duration::from_millis(16));
+            assert_eq!(get_monotonic_time(), Duration::from_millis(5016));
+        }
+
+        assert_eq!(synthetic_time(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn cannot_go_backwards() {
+        let clock = ManualClock::new(Duration::from_secs(5));
+        clock.set(Duration::from_secs(4));
+    }
+}
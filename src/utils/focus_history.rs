@@ -0,0 +1,160 @@
+/// Maximum number of entries to remember.
+const MAX_LEN: usize = 64;
+
+/// Browser-like history of focused items with back and forward navigation.
+#[derive(Debug)]
+pub struct FocusHistory<T> {
+    entries: Vec<T>,
+    /// Index of the current entry, meaningless when there are no entries.
+    current: usize,
+}
+
+impl<T: PartialEq + Clone> FocusHistory<T> {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            current: 0,
+        }
+    }
+
+    /// Records a newly focused item.
+    ///
+    /// Like in a browser, entries after the current one are dropped.
+    pub fn push(&mut self, item: T) {
+        if self.entries.get(self.current) == Some(&item) {
+            return;
+        }
+
+        self.entries.truncate(self.current + 1);
+        self.entries.push(item);
+
+        if self.entries.len() > MAX_LEN {
+            self.entries.remove(0);
+        }
+
+        self.current = self.entries.len() - 1;
+    }
+
+    /// Moves to the previous entry and returns it.
+    pub fn back(&mut self) -> Option<T> {
+        if self.current == 0 || self.entries.is_empty() {
+            return None;
+        }
+
+        self.current -= 1;
+        Some(self.entries[self.current].clone())
+    }
+
+    /// Moves to the next entry and returns it.
+    pub fn forward(&mut self) -> Option<T> {
+        if self.current + 1 >= self.entries.len() {
+            return None;
+        }
+
+        self.current += 1;
+        Some(self.entries[self.current].clone())
+    }
+
+    /// Returns the entry before the current one without moving to it.
+    pub fn previous(&self) -> Option<&T> {
+        self.current
+            .checked_sub(1)
+            .and_then(|idx| self.entries.get(idx))
+    }
+
+    /// Removes entries not matching the predicate, for example windows that were closed.
+    ///
+    /// If the current entry is removed, the closest earlier entry becomes current.
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        let old_current = self.current;
+        let mut current = 0;
+
+        let mut kept: Vec<T> = Vec::with_capacity(self.entries.len());
+        for (idx, item) in self.entries.drain(..).enumerate() {
+            if !f(&item) {
+                continue;
+            }
+
+            // Removing an entry can leave the same item twice in a row.
+            if kept.last() != Some(&item) {
+                kept.push(item);
+            }
+
+            if idx <= old_current {
+                current = kept.len() - 1;
+            }
+        }
+
+        self.entries = kept;
+        self.current = current;
+    }
+
+    /// Returns all entries, oldest first.
+    pub fn entries(&self) -> &[T] {
+        &self.entries
+    }
+
+    /// Returns the index of the current entry.
+    pub fn current(&self) -> Option<usize> {
+        (!self.entries.is_empty()).then_some(self.current)
+    }
+}
+
+impl<T: PartialEq + Clone> Default for FocusHistory<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn back_and_forward() {
+        let mut history = FocusHistory::new();
+        assert_eq!(history.back(), None);
+
+        history.push(1);
+        history.push(2);
+        history.push(2);
+        history.push(3);
+        assert_eq!(history.entries(), [1, 2, 3]);
+
+        assert_eq!(history.previous(), Some(&2));
+        assert_eq!(history.back(), Some(2));
+        assert_eq!(history.back(), Some(1));
+        assert_eq!(history.previous(), None);
+        assert_eq!(history.back(), None);
+        assert_eq!(history.forward(), Some(2));
+
+        // Focusing the current entry again doesn't drop the forward entries.
+        history.push(2);
+        assert_eq!(history.entries(), [1, 2, 3]);
+
+        // Focusing something else does.
+        history.push(4);
+        assert_eq!(history.entries(), [1, 2, 4]);
+        assert_eq!(history.forward(), None);
+    }
+
+    #[test]
+    fn retain_prunes_and_keeps_position() {
+        let mut history = FocusHistory::new();
+        for item in [1, 2, 1, 3, 4] {
+            history.push(item);
+        }
+        history.back();
+        history.back();
+        assert_eq!(history.current(), Some(2));
+
+        history.retain(|item| *item != 2);
+        assert_eq!(history.entries(), [1, 3, 4]);
+        assert_eq!(history.current(), Some(0));
+
+        history.retain(|_| false);
+        assert_eq!(history.current(), None);
+        assert_eq!(history.back(), None);
+        assert_eq!(history.forward(), None);
+    }
+}
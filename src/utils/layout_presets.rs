@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use directories::BaseDirs;
+
+use crate::layout::preset::LayoutPreset;
+
+/// Named layout presets, persisted across sessions.
+#[derive(Debug, Default)]
+pub struct LayoutPresets {
+    /// File to save the presets to, if any.
+    path: Option<PathBuf>,
+    presets: HashMap<String, LayoutPreset>,
+}
+
+impl LayoutPresets {
+    /// Loads the layout presets from the niri state directory.
+    pub fn load() -> Self {
+        let Some(path) = default_path() else {
+            return Self::default();
+        };
+
+        let presets = match read(&path) {
+            Ok(presets) => presets,
+            Err(err) => {
+                let not_found = err
+                    .downcast_ref::<std::io::Error>()
+                    .is_some_and(|err| err.kind() == ErrorKind::NotFound);
+                if !not_found {
+                    warn!("error loading layout presets: {err:?}");
+                }
+                HashMap::new()
+            }
+        };
+
+        Self {
+            path: Some(path),
+            presets,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&LayoutPreset> {
+        self.presets.get(name)
+    }
+
+    /// Saves the preset under the name, replacing any previous one, and writes the presets to
+    /// disk.
+    pub fn insert(&mut self, name: String, preset: LayoutPreset) {
+        if self.presets.get(&name) == Some(&preset) {
+            return;
+        }
+        self.presets.insert(name, preset);
+
+        if let Some(path) = &self.path {
+            if let Err(err) = write(path, &self.presets) {
+                warn!("error saving layout presets: {err:?}");
+            }
+        }
+    }
+}
+
+fn default_path() -> Option<PathBuf> {
+    let dirs = BaseDirs::new()?;
+    let state_dir = dirs.state_dir()?;
+    Some(state_dir.join("niri").join("layout-presets.json"))
+}
+
+fn read(path: &Path) -> anyhow::Result<HashMap<String, LayoutPreset>> {
+    let contents = fs::read_to_string(path).context("error reading file")?;
+    let presets = serde_json::from_str(&contents).context("error parsing file")?;
+    Ok(presets)
+}
+
+fn write(path: &Path, presets: &HashMap<String, LayoutPreset>) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("error creating directory")?;
+    }
+
+    let contents = serde_json::to_string(presets).context("error formatting presets")?;
+    fs::write(path, contents).context("error writing file")?;
+    Ok(())
+}
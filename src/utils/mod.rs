@@ -1,3 +1,4 @@
+use std::cell::Cell;
 use std::ffi::{CString, OsStr};
 use std::io::Write;
 use std::os::unix::prelude::OsStrExt;
@@ -20,13 +21,28 @@ use smithay::utils::{Coordinate, Logical, Point, Rectangle, Size, Transform};
 use smithay::wayland::compositor::{send_surface_state, SurfaceData};
 use smithay::wayland::fractional_scale::with_fractional_scale;
 
+pub mod benchmark;
+pub mod clock;
+pub mod focus_history;
 pub mod id;
+pub mod layout_presets;
+pub mod remembered_sizes;
 pub mod scale;
 pub mod spawning;
+pub mod transaction;
 pub mod watcher;
+pub mod window_groups;
 
 pub static IS_SYSTEMD_SERVICE: AtomicBool = AtomicBool::new(false);
 
+thread_local! {
+    /// Synthetic monotonic time, used instead of the real clock when set.
+    ///
+    /// It is per-thread, so that tests running in parallel don't disturb each other. The
+    /// compositor only reads the time on the main thread.
+    static SYNTHETIC_TIME: Cell<Option<Duration>> = const { Cell::new(None) };
+}
+
 bitflags! {
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
     pub struct ResizeEdge: u32 {
@@ -69,6 +85,50 @@ impl ResizeEdge {
     }
 }
 
+/// Swapping of the horizontal and vertical axes, used by the transposed layout.
+///
+/// Swapping the axes twice gives back the original value.
+pub trait Transpose {
+    fn transpose(self) -> Self;
+}
+
+impl<N: Coordinate, Kind> Transpose for Point<N, Kind> {
+    fn transpose(self) -> Self {
+        Point::from((self.y, self.x))
+    }
+}
+
+impl<N: Coordinate, Kind> Transpose for Size<N, Kind> {
+    fn transpose(self) -> Self {
+        Size::from((self.h, self.w))
+    }
+}
+
+impl<N: Coordinate, Kind> Transpose for Rectangle<N, Kind> {
+    fn transpose(self) -> Self {
+        Rectangle::from_loc_and_size(self.loc.transpose(), self.size.transpose())
+    }
+}
+
+impl Transpose for ResizeEdge {
+    fn transpose(self) -> Self {
+        let mut rv = ResizeEdge::empty();
+        if self.contains(ResizeEdge::TOP) {
+            rv |= ResizeEdge::LEFT;
+        }
+        if self.contains(ResizeEdge::BOTTOM) {
+            rv |= ResizeEdge::RIGHT;
+        }
+        if self.contains(ResizeEdge::LEFT) {
+            rv |= ResizeEdge::TOP;
+        }
+        if self.contains(ResizeEdge::RIGHT) {
+            rv |= ResizeEdge::BOTTOM;
+        }
+        rv
+    }
+}
+
 pub fn version() -> String {
     format!(
         "{} ({})",
@@ -78,10 +138,30 @@ pub fn version() -> String {
 }
 
 pub fn get_monotonic_time() -> Duration {
+    if let Some(time) = synthetic_time() {
+        return time;
+    }
+
     let ts = clock_gettime(ClockId::Monotonic);
     Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
 }
 
+/// Makes [`get_monotonic_time()`] on this thread return this time, until it is set again or
+/// cleared with [`clear_synthetic_time()`].
+pub fn set_synthetic_time(time: Duration) {
+    SYNTHETIC_TIME.with(|synthetic| synthetic.set(Some(time)));
+}
+
+/// Makes [`get_monotonic_time()`] on this thread read the real clock again.
+pub fn clear_synthetic_time() {
+    SYNTHETIC_TIME.with(|synthetic| synthetic.set(None));
+}
+
+/// Returns the synthetic time set on this thread, if any.
+pub fn synthetic_time() -> Option<Duration> {
+    SYNTHETIC_TIME.with(Cell::get)
+}
+
 pub fn center(rect: Rectangle<i32, Logical>) -> Point<i32, Logical> {
     rect.loc + rect.size.downscale(2).to_point()
 }
@@ -115,19 +195,22 @@ pub fn output_size(output: &Output) -> Size<f64, Logical> {
     output_transform.transform_size(logical_size)
 }
 
+/// Serial number of the monitor behind an output, as reported in its EDID.
+///
+/// Backends put it into the output user data when the monitor reports one.
+pub struct OutputSerial(pub String);
+
+pub fn output_serial(output: &Output) -> Option<String> {
+    output
+        .user_data()
+        .get::<OutputSerial>()
+        .map(|serial| serial.0.clone())
+}
+
 pub fn logical_output(output: &Output) -> niri_ipc::LogicalOutput {
     let loc = output.current_location();
     let size = output_size(output);
-    let transform = match output.current_transform() {
-        Transform::Normal => niri_ipc::Transform::Normal,
-        Transform::_90 => niri_ipc::Transform::_90,
-        Transform::_180 => niri_ipc::Transform::_180,
-        Transform::_270 => niri_ipc::Transform::_270,
-        Transform::Flipped => niri_ipc::Transform::Flipped,
-        Transform::Flipped90 => niri_ipc::Transform::Flipped90,
-        Transform::Flipped180 => niri_ipc::Transform::Flipped180,
-        Transform::Flipped270 => niri_ipc::Transform::Flipped270,
-    };
+    let transform = smithay_transform_to_ipc(output.current_transform());
     niri_ipc::LogicalOutput {
         x: loc.x,
         y: loc.y,
@@ -151,6 +234,19 @@ pub fn ipc_transform_to_smithay(transform: niri_ipc::Transform) -> Transform {
     }
 }
 
+pub fn smithay_transform_to_ipc(transform: Transform) -> niri_ipc::Transform {
+    match transform {
+        Transform::Normal => niri_ipc::Transform::Normal,
+        Transform::_90 => niri_ipc::Transform::_90,
+        Transform::_180 => niri_ipc::Transform::_180,
+        Transform::_270 => niri_ipc::Transform::_270,
+        Transform::Flipped => niri_ipc::Transform::Flipped,
+        Transform::Flipped90 => niri_ipc::Transform::Flipped90,
+        Transform::Flipped180 => niri_ipc::Transform::Flipped180,
+        Transform::Flipped270 => niri_ipc::Transform::Flipped270,
+    }
+}
+
 pub fn send_scale_transform(
     surface: &WlSurface,
     data: &SurfaceData,
@@ -163,6 +259,12 @@ pub fn send_scale_transform(
     });
 }
 
+/// Returns whether the output with this connector name is a built-in laptop panel.
+pub fn is_laptop_panel(connector: &str) -> bool {
+    // Loosely matches the check in Mutter.
+    matches!(connector.get(..4), Some("eDP-" | "LVDS" | "DSI-"))
+}
+
 pub fn expand_home(path: &Path) -> anyhow::Result<Option<PathBuf>> {
     if let Ok(rest) = path.strip_prefix("~") {
         let dirs = UserDirs::new().context("error retrieving home directory")?;
@@ -177,7 +279,16 @@ pub fn make_screenshot_path(config: &Config) -> anyhow::Result<Option<PathBuf>>
         return Ok(None);
     };
 
-    let format = CString::new(path.clone()).context("path must not contain nul bytes")?;
+    format_path(path).map(Some)
+}
+
+pub fn make_clip_path(config: &Config) -> anyhow::Result<PathBuf> {
+    format_path(&config.clip_recorder.path)
+}
+
+/// Formats the path with strftime(3) and expands ~ at the front.
+fn format_path(path: &str) -> anyhow::Result<PathBuf> {
+    let format = CString::new(path).context("path must not contain nul bytes")?;
 
     let mut buf = [0u8; 2048];
     let mut path;
@@ -198,7 +309,7 @@ pub fn make_screenshot_path(config: &Config) -> anyhow::Result<Option<PathBuf>>
         path = expanded;
     }
 
-    Ok(Some(path))
+    Ok(path)
 }
 
 pub fn write_png_rgba8(
@@ -215,6 +326,65 @@ pub fn write_png_rgba8(
     writer.write_image_data(pixels)
 }
 
+/// Scores how well `candidate` matches a fuzzy `query`.
+///
+/// All characters of the query must appear in the candidate in order (case-insensitively),
+/// otherwise `None` is returned. Higher scores are better: consecutive matches and matches at
+/// the start of words are rewarded, while skipped characters are penalized.
+pub fn fuzzy_match_score(query: &str, candidate: &str) -> Option<i32> {
+    let mut score = 0;
+    let mut query = query.chars().flat_map(char::to_lowercase).peekable();
+
+    let mut prev = None;
+    let mut prev_matched = false;
+    for c in candidate.chars() {
+        let Some(&q) = query.peek() else {
+            // Prefer shorter candidates when the whole query has already matched.
+            score -= 1;
+            continue;
+        };
+
+        let is_word_start = prev.map_or(true, |p: char| !p.is_alphanumeric());
+        if c.to_lowercase().eq([q]) {
+            query.next();
+
+            score += 1;
+            if prev_matched {
+                score += 4;
+            }
+            if is_word_start {
+                score += 8;
+            }
+            prev_matched = true;
+        } else {
+            score -= 1;
+            prev_matched = false;
+        }
+
+        prev = Some(c);
+    }
+
+    if query.peek().is_some() {
+        return None;
+    }
+
+    Some(score)
+}
+
+#[cfg(feature = "dbus")]
+pub fn show_missing_ffmpeg_notification() {
+    let mut notification = notify_rust::Notification::new();
+    notification
+        .summary("Can't record clip")
+        .body("Recording clips needs ffmpeg to be installed.")
+        .urgency(notify_rust::Urgency::Normal)
+        .hint(notify_rust::Hint::Transient(true));
+
+    if let Err(err) = notification.show() {
+        warn!("error showing clip notification: {err:?}");
+    }
+}
+
 #[cfg(feature = "dbus")]
 pub fn show_screenshot_notification(image_path: Option<PathBuf>) {
     let mut notification = notify_rust::Notification::new();
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+
+use anyhow::Context;
+use directories::BaseDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::layout::workspace::{ColumnWidth, WindowHeight};
+
+/// Last used column width and window height for each app id, persisted across sessions.
+///
+/// Saving happens on a separate thread, so that a slow disk doesn't hold up the compositor.
+/// Unsaved changes are saved when this is dropped.
+#[derive(Debug, Default)]
+pub struct RememberedSizes {
+    /// File to save the sizes to, if any.
+    path: Option<PathBuf>,
+    entries: HashMap<String, Entry>,
+    /// Whether the entries changed since the last save.
+    unsaved: bool,
+    /// Thread writing the file, started on the first save, and the channel feeding it.
+    writer: Option<(mpsc::Sender<String>, JoinHandle<()>)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct Entry {
+    width: StoredWidth,
+    /// Fixed window height, if any.
+    #[serde(default)]
+    height: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum StoredWidth {
+    Proportion(f64),
+    Fixed(f64),
+}
+
+impl RememberedSizes {
+    /// Loads the remembered sizes from the niri state directory.
+    pub fn load() -> Self {
+        let Some(path) = default_path() else {
+            return Self::default();
+        };
+
+        let entries = match read(&path) {
+            Ok(entries) => entries,
+            Err(err) => {
+                let not_found = err
+                    .downcast_ref::<std::io::Error>()
+                    .is_some_and(|err| err.kind() == ErrorKind::NotFound);
+                if !not_found {
+                    warn!("error loading remembered window sizes: {err:?}");
+                }
+                HashMap::new()
+            }
+        };
+
+        Self {
+            path: Some(path),
+            entries,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the remembered column width for the app.
+    pub fn width(&self, app_id: &str) -> Option<ColumnWidth> {
+        let entry = self.entries.get(app_id)?;
+        Some(match entry.width {
+            StoredWidth::Proportion(prop) => ColumnWidth::Proportion(prop),
+            StoredWidth::Fixed(fixed) => ColumnWidth::Fixed(fixed),
+        })
+    }
+
+    /// Returns the remembered fixed window height for the app.
+    pub fn height(&self, app_id: &str) -> Option<f64> {
+        self.entries.get(app_id)?.height
+    }
+
+    /// Records the sizes for the app.
+    ///
+    /// Preset widths must be resolved by the caller, since the presets can change between
+    /// sessions.
+    ///
+    /// Returns `true` if the sizes changed and need to be saved with [`Self::save()`].
+    pub fn remember(&mut self, app_id: String, width: ColumnWidth, height: WindowHeight) -> bool {
+        let width = match width {
+            ColumnWidth::Proportion(prop) => StoredWidth::Proportion(prop),
+            ColumnWidth::Fixed(fixed) => StoredWidth::Fixed(fixed),
+            ColumnWidth::Preset(_) => return false,
+        };
+        let height = match height {
+            WindowHeight::Auto => None,
+            WindowHeight::Fixed(fixed) => Some(fixed),
+        };
+
+        let entry = Entry { width, height };
+        if self.entries.insert(app_id, entry) == Some(entry) {
+            return false;
+        }
+
+        self.unsaved = true;
+        true
+    }
+
+    /// Saves the sizes to disk on the writer thread, if they changed.
+    pub fn save(&mut self) {
+        if !self.unsaved {
+            return;
+        }
+        self.unsaved = false;
+
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let contents = match serde_json::to_string(&self.entries) {
+            Ok(contents) => contents,
+            Err(err) => {
+                warn!("error formatting remembered window sizes: {err:?}");
+                return;
+            }
+        };
+
+        let (sender, _) = self.writer.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel::<String>();
+            let path = path.clone();
+            // Writes go through a single thread, so that they land in order.
+            let thread = thread::spawn(move || {
+                for contents in receiver {
+                    if let Err(err) = write(&path, &contents) {
+                        warn!("error saving remembered window sizes: {err:?}");
+                    }
+                }
+            });
+            (sender, thread)
+        });
+
+        if sender.send(contents).is_err() {
+            warn!("error saving remembered window sizes: writer thread exited");
+        }
+    }
+}
+
+impl Drop for RememberedSizes {
+    fn drop(&mut self) {
+        self.save();
+
+        // Wait for the pending writes to finish.
+        if let Some((sender, thread)) = self.writer.take() {
+            drop(sender);
+            let _ = thread.join();
+        }
+    }
+}
+
+fn default_path() -> Option<PathBuf> {
+    let dirs = BaseDirs::new()?;
+    let state_dir = dirs.state_dir()?;
+    Some(state_dir.join("niri").join("window-sizes.json"))
+}
+
+fn read(path: &Path) -> anyhow::Result<HashMap<String, Entry>> {
+    let contents = fs::read_to_string(path).context("error reading file")?;
+    let entries = serde_json::from_str(&contents).context("error parsing file")?;
+    Ok(entries)
+}
+
+fn write(path: &Path, contents: &str) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("error creating directory")?;
+    }
+
+    fs::write(path, contents).context("error writing file")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remember_and_lookup() {
+        let mut sizes = RememberedSizes::default();
+        sizes.remember(
+            String::from("foot"),
+            ColumnWidth::Fixed(800.),
+            WindowHeight::Fixed(400.),
+        );
+        sizes.remember(
+            String::from("firefox"),
+            ColumnWidth::Proportion(0.5),
+            WindowHeight::Auto,
+        );
+        sizes.remember(
+            String::from("mpv"),
+            ColumnWidth::Preset(0),
+            WindowHeight::Auto,
+        );
+
+        assert_eq!(sizes.width("foot"), Some(ColumnWidth::Fixed(800.)));
+        assert_eq!(sizes.height("foot"), Some(400.));
+        assert_eq!(sizes.width("firefox"), Some(ColumnWidth::Proportion(0.5)));
+        assert_eq!(sizes.height("firefox"), None);
+        assert_eq!(sizes.width("mpv"), None);
+    }
+
+    #[test]
+    fn remember_reports_changes() {
+        let mut sizes = RememberedSizes::default();
+        let remember = |sizes: &mut RememberedSizes, width| {
+            sizes.remember(String::from("foot"), width, WindowHeight::Auto)
+        };
+
+        assert!(remember(&mut sizes, ColumnWidth::Fixed(800.)));
+        assert!(!remember(&mut sizes, ColumnWidth::Fixed(800.)));
+        assert!(remember(&mut sizes, ColumnWidth::Fixed(900.)));
+        assert!(!remember(&mut sizes, ColumnWidth::Preset(0)));
+    }
+
+    #[test]
+    fn parse_stored_entries() {
+        let entries: HashMap<String, Entry> = serde_json::from_str(
+            r#"{"foot":{"width":{"fixed":800.0},"height":400.0},"firefox":{"width":{"proportion":0.5}}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            entries["foot"],
+            Entry {
+                width: StoredWidth::Fixed(800.),
+                height: Some(400.),
+            }
+        );
+        assert_eq!(
+            entries["firefox"],
+            Entry {
+                width: StoredWidth::Proportion(0.5),
+                height: None,
+            }
+        );
+    }
+}
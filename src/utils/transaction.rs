@@ -0,0 +1,141 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
+use smithay::reexports::calloop::LoopHandle;
+use smithay::reexports::wayland_server::Client;
+use smithay::wayland::compositor::{Blocker, BlockerState, CompositorHandler};
+
+use crate::niri::State;
+
+/// Time after which a transaction completes even if some windows haven't committed yet.
+const TRANSACTION_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// Transaction between windows changing size together.
+///
+/// Every window that was sent a configure as part of the transaction holds a clone of it until it
+/// commits in response. Commits that arrive earlier are blocked until the transaction completes,
+/// which happens once every clone is dropped, or after a timeout. This way, all windows show up at
+/// their new size at once.
+#[derive(Clone)]
+pub struct Transaction {
+    inner: Arc<Inner>,
+    notifications: Rc<RefCell<Notifications>>,
+}
+
+/// Blocker for a surface commit that is part of a transaction.
+#[derive(Debug)]
+pub struct TransactionBlocker(Weak<Inner>);
+
+#[derive(Debug)]
+struct Inner {
+    completed: AtomicBool,
+}
+
+#[derive(Default)]
+struct Notifications {
+    event_loop: Option<LoopHandle<'static, State>>,
+    /// Clients with commits blocked on this transaction.
+    clients: Vec<Client>,
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                completed: AtomicBool::new(false),
+            }),
+            notifications: Rc::new(RefCell::new(Notifications::default())),
+        }
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.inner.completed.load(Ordering::Relaxed)
+    }
+
+    pub fn blocker(&self) -> TransactionBlocker {
+        TransactionBlocker(Arc::downgrade(&self.inner))
+    }
+
+    /// Notifies the client once the transaction completes, so that its blocked commits apply.
+    ///
+    /// The first call also starts the transaction timeout.
+    pub fn add_notification(&self, event_loop: &LoopHandle<'static, State>, client: Client) {
+        let mut notifications = self.notifications.borrow_mut();
+
+        if notifications.event_loop.is_none() {
+            notifications.event_loop = Some(event_loop.clone());
+
+            let inner = Arc::downgrade(&self.inner);
+            let notifications = self.notifications.clone();
+            event_loop
+                .insert_source(Timer::from_duration(TRANSACTION_TIMEOUT), move |_, _, _| {
+                    if let Some(inner) = inner.upgrade() {
+                        trace!("transaction timed out");
+                        complete(&inner, &notifications);
+                    }
+                    TimeoutAction::Drop
+                })
+                .unwrap();
+        }
+
+        notifications.clients.push(client);
+    }
+}
+
+impl Default for Transaction {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        // The last window in the transaction has committed.
+        if Arc::strong_count(&self.inner) == 1 {
+            complete(&self.inner, &self.notifications);
+        }
+    }
+}
+
+impl fmt::Debug for Transaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transaction")
+            .field("completed", &self.is_completed())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Blocker for TransactionBlocker {
+    fn state(&self) -> BlockerState {
+        match self.0.upgrade() {
+            Some(inner) if !inner.completed.load(Ordering::Relaxed) => BlockerState::Pending,
+            _ => BlockerState::Released,
+        }
+    }
+}
+
+fn complete(inner: &Inner, notifications: &RefCell<Notifications>) {
+    if inner.completed.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let mut notifications = notifications.borrow_mut();
+    let Some(event_loop) = notifications.event_loop.clone() else {
+        return;
+    };
+
+    // We might be deep inside a commit handler, so clear the blockers from an idle callback.
+    for client in notifications.clients.drain(..) {
+        event_loop.insert_idle(move |state| {
+            let display_handle = state.niri.display_handle.clone();
+            state
+                .client_compositor_state(&client)
+                .blocker_cleared(state, &display_handle);
+        });
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use directories::BaseDirs;
+
+/// Window group of each app id, persisted across sessions.
+///
+/// Windows don't outlive the session, so the groups are remembered by app id and given to the
+/// windows of that app as they open.
+#[derive(Debug, Default)]
+pub struct WindowGroups {
+    /// File to save the groups to, if any.
+    path: Option<PathBuf>,
+    groups: HashMap<String, String>,
+}
+
+impl WindowGroups {
+    /// Loads the window groups from the niri state directory.
+    pub fn load() -> Self {
+        let Some(path) = default_path() else {
+            return Self::default();
+        };
+
+        let groups = match read(&path) {
+            Ok(groups) => groups,
+            Err(err) => {
+                let not_found = err
+                    .downcast_ref::<std::io::Error>()
+                    .is_some_and(|err| err.kind() == ErrorKind::NotFound);
+                if !not_found {
+                    warn!("error loading window groups: {err:?}");
+                }
+                HashMap::new()
+            }
+        };
+
+        Self {
+            path: Some(path),
+            groups,
+        }
+    }
+
+    /// Returns the window group of the app.
+    pub fn group(&self, app_id: &str) -> Option<&str> {
+        self.groups.get(app_id).map(String::as_str)
+    }
+
+    /// Records the window group of the app, or that it has none, and saves the groups to disk if
+    /// they changed.
+    pub fn set_group(&mut self, app_id: String, group: Option<String>) {
+        let changed = match group {
+            Some(group) => self.groups.insert(app_id, group.clone()) != Some(group),
+            None => self.groups.remove(&app_id).is_some(),
+        };
+        if !changed {
+            return;
+        }
+
+        if let Some(path) = &self.path {
+            if let Err(err) = write(path, &self.groups) {
+                warn!("error saving window groups: {err:?}");
+            }
+        }
+    }
+}
+
+fn default_path() -> Option<PathBuf> {
+    let dirs = BaseDirs::new()?;
+    let state_dir = dirs.state_dir()?;
+    Some(state_dir.join("niri").join("window-groups.json"))
+}
+
+fn read(path: &Path) -> anyhow::Result<HashMap<String, String>> {
+    let contents = fs::read_to_string(path).context("error reading file")?;
+    let groups = serde_json::from_str(&contents).context("error parsing file")?;
+    Ok(groups)
+}
+
+fn write(path: &Path, groups: &HashMap<String, String>) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("error creating directory")?;
+    }
+
+    let contents = serde_json::to_string(groups).context("error formatting groups")?;
+    fs::write(path, contents).context("error writing file")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_and_unset_group() {
+        let mut groups = WindowGroups::default();
+        groups.set_group(String::from("foot"), Some(String::from("work")));
+        groups.set_group(String::from("firefox"), Some(String::from("work")));
+        groups.set_group(String::from("firefox"), Some(String::from("web")));
+        groups.set_group(String::from("mpv"), Some(String::from("media")));
+        groups.set_group(String::from("mpv"), None);
+
+        assert_eq!(groups.group("foot"), Some("work"));
+        assert_eq!(groups.group("firefox"), Some("web"));
+        assert_eq!(groups.group("mpv"), None);
+    }
+}
@@ -14,9 +14,9 @@ use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::utils::{Logical, Point, Rectangle, Scale, Serial, Size, Transform};
 use smithay::wayland::compositor::{remove_pre_commit_hook, with_states, HookId};
-use smithay::wayland::shell::xdg::{SurfaceCachedState, ToplevelSurface};
+use smithay::wayland::shell::xdg::{SurfaceCachedState, ToplevelSurface, XdgToplevelSurfaceData};
 
-use super::{ResolvedWindowRules, WindowRef};
+use super::{is_dialog, ResolvedWindowRules, WindowRef};
 use crate::handlers::KdeDecorationsModeState;
 use crate::layout::{
     InteractiveResizeData, LayoutElement, LayoutElementRenderElement, LayoutElementRenderSnapshot,
@@ -30,7 +30,8 @@ use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderEleme
 use crate::render_helpers::surface::render_snapshot_from_surface_tree;
 use crate::render_helpers::{BakedBuffer, RenderTarget, SplitElements};
 use crate::utils::id::IdCounter;
-use crate::utils::{send_scale_transform, ResizeEdge};
+use crate::utils::transaction::Transaction;
+use crate::utils::{get_monotonic_time, send_scale_transform, ResizeEdge};
 
 #[derive(Debug)]
 pub struct Mapped {
@@ -57,6 +58,18 @@ pub struct Mapped {
     /// Whether this window is the active window in its column.
     is_active_in_column: bool,
 
+    /// Whether compositor shortcuts are turned off while this window has the keyboard focus.
+    is_inhibiting_shortcuts: bool,
+
+    /// Whether this window is a dialog, as of its last commit.
+    is_dialog: bool,
+
+    /// Whether this window requested attention without getting focused.
+    is_urgent: bool,
+
+    /// Name of the window group that this window belongs to, if any.
+    group: Option<String>,
+
     /// Buffer to draw instead of the window when it should be blocked out.
     block_out_buffer: RefCell<SolidColorBuffer>,
 
@@ -66,6 +79,12 @@ pub struct Mapped {
     /// Serials of commits that should be animated.
     animate_serials: Vec<Serial>,
 
+    /// Transaction to attach to the next configure.
+    pending_transaction: Option<Transaction>,
+
+    /// Transactions waiting for the commits of the corresponding configures.
+    transaction_serials: Vec<(Serial, Transaction)>,
+
     /// Snapshot right before an animated commit.
     animation_snapshot: Option<LayoutElementRenderSnapshot>,
 
@@ -76,8 +95,33 @@ pub struct Mapped {
     ///
     /// Used for double-resize-click tracking.
     last_interactive_resize_start: Cell<Option<(Duration, ResizeEdge)>>,
+
+    /// Interactive or animated resize configure that the window hasn't committed yet, and when it
+    /// was sent.
+    ///
+    /// Further resize configures are held back until this one is committed, coalescing to the
+    /// latest requested size.
+    throttled_resize_configure: Option<(Serial, Duration)>,
+
+    /// Whether a configure is being held back by the resize throttling.
+    is_configure_held_back: bool,
+
+    /// Serial and size of the latest configure sent to the window.
+    last_configure: Option<(Serial, Option<Size<i32, Logical>>)>,
+
+    /// Whether the window gets the tiled state on all edges while it's tiled.
+    ///
+    /// This is decided by the initial configure, depending on prefer-no-csd.
+    wants_tiled_state: bool,
+
+    /// Whether the window entered an output since it last got the dma-buf feedback.
+    entered_output: Cell<bool>,
 }
 
+/// How long to wait for the window to commit a throttled resize configure before sending the
+/// next one anyway.
+const RESIZE_THROTTLE_TIMEOUT: Duration = Duration::from_millis(100);
+
 niri_render_elements! {
     WindowCastRenderElements<R> => {
         Layout = LayoutElementRenderElement<R>,
@@ -127,6 +171,12 @@ impl InteractiveResize {
 
 impl Mapped {
     pub fn new(window: Window, rules: ResolvedWindowRules, hook: HookId) -> Self {
+        let wants_tiled_state = window
+            .toplevel()
+            .expect("no X11 support")
+            .with_pending_state(|state| state.states.contains(xdg_toplevel::State::TiledLeft));
+        let is_dialog = is_dialog(window.toplevel().expect("no X11 support"));
+
         Self {
             window,
             id: MappedId::next(),
@@ -134,13 +184,24 @@ impl Mapped {
             rules,
             need_to_recompute_rules: false,
             is_focused: false,
+            is_inhibiting_shortcuts: false,
+            is_dialog,
+            is_urgent: false,
+            group: None,
             is_active_in_column: false,
             block_out_buffer: RefCell::new(SolidColorBuffer::new((0., 0.), [0., 0., 0., 1.])),
             animate_next_configure: false,
             animate_serials: Vec::new(),
+            pending_transaction: None,
+            transaction_serials: Vec::new(),
             animation_snapshot: None,
             interactive_resize: None,
             last_interactive_resize_start: Cell::new(None),
+            throttled_resize_configure: None,
+            is_configure_held_back: false,
+            last_configure: None,
+            wants_tiled_state,
+            entered_output: Cell::new(false),
         }
     }
 
@@ -148,6 +209,75 @@ impl Mapped {
         self.window.toplevel().expect("no X11 support")
     }
 
+    /// Sends a configure right away, even if nothing changed.
+    ///
+    /// For requests that need a configure in response. Every configure to a mapped window must go
+    /// through here or through [`LayoutElement::send_pending_configure()`], so that the expected
+    /// size and serial stay up to date.
+    pub fn send_configure(&mut self) {
+        self.send_configure_inner(true);
+    }
+
+    fn send_configure_inner(&mut self, force: bool) {
+        let is_resizing = matches!(self.interactive_resize, Some(InteractiveResize::Ongoing(_)));
+        let is_throttled = is_resizing || self.animate_next_configure;
+        if !is_throttled {
+            self.throttled_resize_configure = None;
+        }
+
+        // During interactive resize and animated resizes, wait for the window to catch up with
+        // the previous configure instead of sending one for every pointer motion or frame.
+        let now = get_monotonic_time();
+        if let Some((_, sent_at)) = self.throttled_resize_configure {
+            if !force && now.saturating_sub(sent_at) < RESIZE_THROTTLE_TIMEOUT {
+                self.is_configure_held_back = true;
+                return;
+            }
+        }
+        self.is_configure_held_back = false;
+
+        let transaction = self.pending_transaction.take();
+
+        let serial = if force {
+            Some(self.toplevel().send_configure())
+        } else {
+            self.toplevel().send_pending_configure()
+        };
+
+        if let Some(serial) = serial {
+            let size = self.toplevel().with_pending_state(|state| state.size);
+            self.last_configure = Some((serial, size));
+
+            if self.animate_next_configure {
+                self.animate_serials.push(serial);
+            }
+
+            if let Some(transaction) = transaction {
+                self.transaction_serials.push((serial, transaction));
+            }
+
+            if is_throttled {
+                self.throttled_resize_configure = Some((serial, now));
+            }
+
+            self.interactive_resize = match self.interactive_resize.take() {
+                Some(InteractiveResize::WaitingForLastConfigure(data)) => {
+                    Some(InteractiveResize::WaitingForLastCommit { data, serial })
+                }
+                x => x,
+            }
+        } else {
+            self.interactive_resize = match self.interactive_resize.take() {
+                // We probably started and stopped resizing in the same loop cycle without anything
+                // changing.
+                Some(InteractiveResize::WaitingForLastConfigure { .. }) => None,
+                x => x,
+            }
+        }
+
+        self.animate_next_configure = false;
+    }
+
     /// Recomputes the resolved window rules and returns whether they changed.
     pub fn recompute_window_rules(&mut self, rules: &[WindowRule], is_at_startup: bool) -> bool {
         self.need_to_recompute_rules = false;
@@ -173,6 +303,16 @@ impl Mapped {
         self.recompute_window_rules(rules, is_at_startup)
     }
 
+    /// Returns when the configure held back by the resize throttling is due to be sent anyway.
+    pub fn throttled_configure_deadline(&self) -> Option<Duration> {
+        if !self.is_configure_held_back {
+            return None;
+        }
+
+        let (_, sent_at) = self.throttled_resize_configure?;
+        Some(sent_at + RESIZE_THROTTLE_TIMEOUT)
+    }
+
     pub fn id(&self) -> MappedId {
         self.id
     }
@@ -185,11 +325,53 @@ impl Mapped {
         self.is_active_in_column
     }
 
+    pub fn is_inhibiting_shortcuts(&self) -> bool {
+        self.is_inhibiting_shortcuts
+    }
+
+    pub fn set_is_inhibiting_shortcuts(&mut self, value: bool) {
+        self.is_inhibiting_shortcuts = value;
+    }
+
+    /// Re-checks whether the window is a dialog.
+    ///
+    /// The parent and the size limits only change with a commit, so this is called on commits to
+    /// avoid taking the surface lock every frame.
+    pub fn update_is_dialog(&mut self) {
+        self.is_dialog = is_dialog(self.toplevel());
+    }
+
+    pub fn is_urgent(&self) -> bool {
+        self.is_urgent
+    }
+
+    /// Returns whether the window entered an output since the last call, and so needs the
+    /// dma-buf feedback of its new output.
+    pub fn take_entered_output(&self) -> bool {
+        self.entered_output.replace(false)
+    }
+
+    pub fn set_urgent(&mut self, value: bool) {
+        self.is_urgent = value;
+    }
+
+    pub fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    pub fn set_group(&mut self, group: Option<String>) {
+        self.group = group;
+    }
+
     pub fn set_is_focused(&mut self, is_focused: bool) {
         if self.is_focused == is_focused {
             return;
         }
 
+        if is_focused {
+            self.is_urgent = false;
+        }
+
         self.is_focused = is_focused;
         self.need_to_recompute_rules = true;
     }
@@ -249,6 +431,22 @@ impl Mapped {
         should_animate
     }
 
+    /// Takes the transaction that the commit with this serial completes, if any.
+    pub fn take_transaction_for_commit(&mut self, commit_serial: Serial) -> Option<Transaction> {
+        let mut rv = None;
+        self.transaction_serials
+            .retain_mut(|(serial, transaction)| {
+                if commit_serial.is_no_older_than(serial) {
+                    // Older transactions that were skipped over are dropped here.
+                    rv = Some(transaction.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+        rv
+    }
+
     pub fn store_animation_snapshot(&mut self, renderer: &mut GlesRenderer) {
         self.animation_snapshot = Some(self.render_snapshot(renderer));
     }
@@ -439,7 +637,12 @@ impl LayoutElement for Mapped {
         }
     }
 
-    fn request_size(&mut self, size: Size<i32, Logical>, animate: bool) {
+    fn request_size(
+        &mut self,
+        size: Size<i32, Logical>,
+        animate: bool,
+        transaction: Option<Transaction>,
+    ) {
         let changed = self.toplevel().with_pending_state(|state| {
             let changed = state.size != Some(size);
             state.size = Some(size);
@@ -447,8 +650,14 @@ impl LayoutElement for Mapped {
             changed
         });
 
-        if changed && animate {
-            self.animate_next_configure = true;
+        if changed {
+            if animate {
+                self.animate_next_configure = true;
+            }
+
+            if transaction.is_some() {
+                self.pending_transaction = transaction;
+            }
         }
     }
 
@@ -459,6 +668,10 @@ impl LayoutElement for Mapped {
         });
     }
 
+    fn requested_size(&self) -> Option<Size<i32, Logical>> {
+        self.toplevel().with_pending_state(|state| state.size)
+    }
+
     fn min_size(&self) -> Size<i32, Logical> {
         let mut size = with_states(self.toplevel().wl_surface(), |state| {
             let curr = state.cached_state.current::<SurfaceCachedState>();
@@ -509,6 +722,10 @@ impl LayoutElement for Mapped {
         });
     }
 
+    fn is_dialog(&self) -> bool {
+        self.is_dialog
+    }
+
     fn has_ssd(&self) -> bool {
         let toplevel = self.toplevel();
         let mode = toplevel.current_state().decoration_mode;
@@ -529,7 +746,8 @@ impl LayoutElement for Mapped {
 
     fn output_enter(&self, output: &Output) {
         let overlap = Rectangle::from_loc_and_size((0, 0), (i32::MAX, i32::MAX));
-        self.window.output_enter(output, overlap)
+        self.window.output_enter(output, overlap);
+        self.entered_output.set(true);
     }
 
     fn output_leave(&self, output: &Output) {
@@ -567,28 +785,73 @@ impl LayoutElement for Mapped {
         });
     }
 
-    fn send_pending_configure(&mut self) {
-        if let Some(serial) = self.toplevel().send_pending_configure() {
-            if self.animate_next_configure {
-                self.animate_serials.push(serial);
-            }
+    fn set_tiled(&mut self, edges: Option<ResizeEdge>) {
+        // Windows that got the tiled state at initial configure because of prefer-no-csd keep it
+        // on all edges while tiled, so that they don't bring back their rounded corners.
+        let edges = match edges {
+            Some(_) if self.wants_tiled_state => ResizeEdge::all(),
+            Some(edges) => edges,
+            None => ResizeEdge::empty(),
+        };
 
-            self.interactive_resize = match self.interactive_resize.take() {
-                Some(InteractiveResize::WaitingForLastConfigure(data)) => {
-                    Some(InteractiveResize::WaitingForLastCommit { data, serial })
+        self.toplevel().with_pending_state(|state| {
+            for (edge, state_edge) in [
+                (ResizeEdge::LEFT, xdg_toplevel::State::TiledLeft),
+                (ResizeEdge::RIGHT, xdg_toplevel::State::TiledRight),
+                (ResizeEdge::TOP, xdg_toplevel::State::TiledTop),
+                (ResizeEdge::BOTTOM, xdg_toplevel::State::TiledBottom),
+            ] {
+                if edges.contains(edge) {
+                    state.states.set(state_edge);
+                } else {
+                    state.states.unset(state_edge);
                 }
-                x => x,
             }
-        } else {
-            self.interactive_resize = match self.interactive_resize.take() {
-                // We probably started and stopped resizing in the same loop cycle without anything
-                // changing.
-                Some(InteractiveResize::WaitingForLastConfigure { .. }) => None,
-                x => x,
+        });
+    }
+
+    fn set_maximized(&mut self, maximized: bool) {
+        self.toplevel().with_pending_state(|state| {
+            if maximized {
+                state.states.set(xdg_toplevel::State::Maximized);
+            } else {
+                state.states.unset(xdg_toplevel::State::Maximized);
             }
-        }
+        });
+    }
 
-        self.animate_next_configure = false;
+    fn set_suspended(&mut self, suspended: bool) {
+        self.toplevel().with_pending_state(|state| {
+            if suspended {
+                state.states.set(xdg_toplevel::State::Suspended);
+            } else {
+                state.states.unset(xdg_toplevel::State::Suspended);
+            }
+        });
+    }
+
+    fn send_pending_configure(&mut self) {
+        self.send_configure_inner(false);
+    }
+
+    fn configure_serial(&self) -> Option<Serial> {
+        self.last_configure.map(|(serial, _)| serial)
+    }
+
+    fn expected_size(&self) -> Option<Size<i32, Logical>> {
+        self.last_configure.and_then(|(_, size)| size)
+    }
+
+    fn accepted_serial(&self) -> Option<Serial> {
+        with_states(self.toplevel().wl_surface(), |states| {
+            let role = states
+                .data_map
+                .get::<XdgToplevelSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap();
+            role.current_serial
+        })
     }
 
     fn is_fullscreen(&self) -> bool {
@@ -646,6 +909,12 @@ impl LayoutElement for Mapped {
     }
 
     fn update_interactive_resize(&mut self, commit_serial: Serial) {
+        if let Some((serial, _)) = &self.throttled_resize_configure {
+            if commit_serial.is_no_older_than(serial) {
+                self.throttled_resize_configure = None;
+            }
+        }
+
         if let Some(InteractiveResize::WaitingForLastCommit { serial, .. }) =
             &self.interactive_resize
         {
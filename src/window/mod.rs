@@ -1,8 +1,11 @@
-use niri_config::{BlockOutFrom, BorderRule, CornerRadius, Match, WindowRule};
+use niri_config::{
+    BlockOutFrom, BorderRule, CornerRadius, Match, OnClose, OpenOnWorkspace, OversizedWindow,
+    WindowRule,
+};
 use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel;
 use smithay::wayland::compositor::with_states;
 use smithay::wayland::shell::xdg::{
-    ToplevelSurface, XdgToplevelSurfaceData, XdgToplevelSurfaceRoleAttributes,
+    SurfaceCachedState, ToplevelSurface, XdgToplevelSurfaceData, XdgToplevelSurfaceRoleAttributes,
 };
 
 use crate::layout::workspace::ColumnWidth;
@@ -33,8 +36,8 @@ pub struct ResolvedWindowRules {
     /// Output to open this window on.
     pub open_on_output: Option<String>,
 
-    /// Workspace to open this window on.
-    pub open_on_workspace: Option<String>,
+    /// Workspace to open this window on, and whether to focus the window there.
+    pub open_on_workspace: Option<OpenOnWorkspace>,
 
     /// Whether the window should open full-width.
     pub open_maximized: Option<bool>,
@@ -42,6 +45,11 @@ pub struct ResolvedWindowRules {
     /// Whether the window should open fullscreen.
     pub open_fullscreen: Option<bool>,
 
+    /// Whether to ask the window to draw no client-side decorations.
+    ///
+    /// `None` means using the global setting.
+    pub prefer_no_csd: Option<bool>,
+
     /// Extra bound on the minimum window width.
     pub min_width: Option<u16>,
     /// Extra bound on the minimum window height.
@@ -70,8 +78,25 @@ pub struct ResolvedWindowRules {
     /// Whether to clip this window to its geometry, including the corner radius.
     pub clip_to_geometry: Option<bool>,
 
+    /// What to do when this window is bigger than the size it was asked to take.
+    pub oversized_window: Option<OversizedWindow>,
+
     /// Whether to block out this window from certain render targets.
     pub block_out_from: Option<BlockOutFrom>,
+
+    /// Whether closing this window needs confirmation.
+    pub confirm_close: Option<bool>,
+
+    /// Where to move focus when this window closes.
+    ///
+    /// `None` means the default of focusing the column to the right.
+    pub on_close: Option<OnClose>,
+
+    /// Whether the column of this window stays centered whenever it is focused.
+    pub always_center: Option<bool>,
+
+    /// Whether resizing this window while it's floating keeps its aspect ratio.
+    pub keep_aspect_ratio: Option<bool>,
 }
 
 impl<'a> WindowRef<'a> {
@@ -97,6 +122,34 @@ impl<'a> WindowRef<'a> {
     }
 }
 
+/// Returns whether the toplevel looks like a dialog.
+///
+/// Dialogs are toplevels with a parent, or toplevels with a fixed size (min size equal to max
+/// size).
+pub fn is_dialog(toplevel: &ToplevelSurface) -> bool {
+    if toplevel.parent().is_some() {
+        return true;
+    }
+
+    with_states(toplevel.wl_surface(), |states| {
+        let curr = states.cached_state.current::<SurfaceCachedState>();
+        curr.min_size.w > 0 && curr.min_size.h > 0 && curr.min_size == curr.max_size
+    })
+}
+
+/// Returns the current app id of the toplevel.
+pub fn app_id(toplevel: &ToplevelSurface) -> Option<String> {
+    with_states(toplevel.wl_surface(), |states| {
+        let role = states
+            .data_map
+            .get::<XdgToplevelSurfaceData>()
+            .unwrap()
+            .lock()
+            .unwrap();
+        role.app_id.clone()
+    })
+}
+
 impl ResolvedWindowRules {
     pub const fn empty() -> Self {
         Self {
@@ -105,6 +158,7 @@ impl ResolvedWindowRules {
             open_on_workspace: None,
             open_maximized: None,
             open_fullscreen: None,
+            prefer_no_csd: None,
             min_width: None,
             min_height: None,
             max_width: None,
@@ -131,7 +185,12 @@ impl ResolvedWindowRules {
             opacity: None,
             geometry_corner_radius: None,
             clip_to_geometry: None,
+            oversized_window: None,
             block_out_from: None,
+            confirm_close: None,
+            on_close: None,
+            always_center: None,
+            keep_aspect_ratio: None,
         }
     }
 
@@ -188,7 +247,7 @@ impl ResolvedWindowRules {
                     open_on_output = Some(x);
                 }
 
-                if let Some(x) = rule.open_on_workspace.as_deref() {
+                if let Some(x) = rule.open_on_workspace.as_ref() {
                     open_on_workspace = Some(x);
                 }
 
@@ -200,6 +259,10 @@ impl ResolvedWindowRules {
                     resolved.open_fullscreen = Some(x);
                 }
 
+                if let Some(x) = rule.prefer_no_csd {
+                    resolved.prefer_no_csd = Some(x);
+                }
+
                 if let Some(x) = rule.min_width {
                     resolved.min_width = Some(x);
                 }
@@ -228,13 +291,28 @@ impl ResolvedWindowRules {
                 if let Some(x) = rule.clip_to_geometry {
                     resolved.clip_to_geometry = Some(x);
                 }
+                if let Some(x) = rule.oversized_window {
+                    resolved.oversized_window = Some(x);
+                }
                 if let Some(x) = rule.block_out_from {
                     resolved.block_out_from = Some(x);
                 }
+                if let Some(x) = rule.confirm_close {
+                    resolved.confirm_close = Some(x);
+                }
+                if let Some(x) = rule.on_close {
+                    resolved.on_close = Some(x);
+                }
+                if let Some(x) = rule.always_center {
+                    resolved.always_center = Some(x);
+                }
+                if let Some(x) = rule.keep_aspect_ratio {
+                    resolved.keep_aspect_ratio = Some(x);
+                }
             }
 
             resolved.open_on_output = open_on_output.map(|x| x.to_owned());
-            resolved.open_on_workspace = open_on_workspace.map(|x| x.to_owned());
+            resolved.open_on_workspace = open_on_workspace.cloned();
         });
 
         resolved
@@ -1,5 +1,8 @@
+use std::time::Duration;
+
 use smithay::desktop::Window;
 use smithay::output::Output;
+use smithay::utils::{Logical, Point};
 use smithay::wayland::shell::xdg::ToplevelSurface;
 
 use super::ResolvedWindowRules;
@@ -9,6 +12,14 @@ use crate::layout::workspace::ColumnWidth;
 pub struct Unmapped {
     pub window: Window,
     pub state: InitialConfigureState,
+    /// Where the window was launched from, in global coordinates.
+    ///
+    /// Comes from the activation token the window used before mapping.
+    pub activation_origin: Option<Point<f64, Logical>>,
+    /// Time until which mapping waits for a buffer of the configured size.
+    ///
+    /// Set once the window commits a buffer of a different size.
+    pub map_deadline: Option<Duration>,
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -57,6 +68,8 @@ impl Unmapped {
             state: InitialConfigureState::NotConfigured {
                 wants_fullscreen: None,
             },
+            activation_origin: None,
+            map_deadline: None,
         }
     }
 
@@ -64,6 +77,22 @@ impl Unmapped {
         matches!(self.state, InitialConfigureState::NotConfigured { .. })
     }
 
+    /// Returns whether the committed window size doesn't match the configured size yet.
+    ///
+    /// Sizes that we left up to the window always match.
+    pub fn is_waiting_for_configured_size(&self) -> bool {
+        if !matches!(self.state, InitialConfigureState::Configured { .. }) {
+            return false;
+        }
+
+        let Some(size) = self.toplevel().current_state().size else {
+            return false;
+        };
+
+        let geo = self.window.geometry().size;
+        (size.w != 0 && size.w != geo.w) || (size.h != 0 && size.h != geo.h)
+    }
+
     pub fn toplevel(&self) -> &ToplevelSurface {
         self.window.toplevel().expect("no X11 support")
     }